@@ -1,4 +1,135 @@
-use audio_engine_service::service::AudioEngineService;
+use std::ffi::{CStr, CString, c_char, c_void};
+
+use audio_engine_core::audio_graph::NodeId;
+use audio_engine_core::parameter::ParameterInfo;
+use audio_engine_service::command::{CommandResponse, GraphCommand, METER_CHANNELS};
+use audio_engine_service::graph_file::GraphDescription;
+use audio_engine_service::notifier::EventNotifier;
+use audio_engine_service::service::{AudioEngineService, EngineEvent};
+
+/// ParameterInfoRecord の文字列のバイト数（終端の NUL を含む）
+pub const PARAMETER_TEXT_LEN: usize = 64;
+
+/// 他言語に返すパラメーターの情報
+///
+/// 文字列は NUL 終端の UTF-8 で、PARAMETER_TEXT_LEN に収まらない場合は文字の境界で切り捨てます。
+#[repr(C)]
+pub struct ParameterInfoRecord {
+    /// パラメーターの ID（engine_set_param_by_name などに渡す名前）
+    pub id: [c_char; PARAMETER_TEXT_LEN],
+    /// 表示用の名前
+    pub name: [c_char; PARAMETER_TEXT_LEN],
+    /// 単位（単位がない場合は空文字列）
+    pub unit: [c_char; PARAMETER_TEXT_LEN],
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+}
+
+impl From<&ParameterInfo> for ParameterInfoRecord {
+    fn from(info: &ParameterInfo) -> Self {
+        Self {
+            id: to_c_text(info.id),
+            name: to_c_text(info.name),
+            unit: to_c_text(info.unit),
+            min: info.min,
+            max: info.max,
+            default: info.default,
+        }
+    }
+}
+
+/// 文字列を NUL 終端の固定長の配列に変換する
+fn to_c_text(text: &str) -> [c_char; PARAMETER_TEXT_LEN] {
+    let mut end = text.len().min(PARAMETER_TEXT_LEN - 1);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut c_text = [0; PARAMETER_TEXT_LEN];
+    for (dst, src) in c_text.iter_mut().zip(&text.as_bytes()[..end]) {
+        *dst = *src as c_char;
+    }
+    c_text
+}
+
+/// 他言語に返すイベントの種類
+#[repr(u32)]
+#[derive(Clone, Copy)]
+pub enum EngineEventKind {
+    /// 出力がクリップした（`count` がクリップの回数）
+    ClipDetected = 0,
+    /// xrun が発生した
+    XRun = 1,
+    /// 出力のピークレベル（`value0` が左、`value1` が右）
+    PeakLevels = 2,
+    /// MIDI 入力のノートオン（`count` がチャンネル、`code` がノート番号、`value0` がベロシティ）
+    NoteOn = 3,
+    /// ノード独自のイベント（`count` がノード ID の整数の表現、`code` と `value0` はノードごとの意味）
+    Node = 4,
+    /// 再生中にストリームが止まった
+    StreamStopped = 5,
+    /// ウォッチドッグがオーディオコールバックの停止を検出した（`count` が止まっていた時間（ミリ秒））
+    CallbackStalled = 6,
+}
+
+/// 他言語に返すイベント
+///
+/// 使わないフィールドは 0 です。フィールドの意味は `kind` ごとに EngineEventKind に記載しています。
+#[repr(C)]
+pub struct EngineEventRecord {
+    pub kind: EngineEventKind,
+    pub code: u32,
+    pub count: u64,
+    pub value0: f32,
+    pub value1: f32,
+}
+
+impl From<EngineEvent> for EngineEventRecord {
+    fn from(event: EngineEvent) -> Self {
+        let record = |kind, code, count, value0, value1| Self {
+            kind,
+            code,
+            count,
+            value0,
+            value1,
+        };
+        match event {
+            EngineEvent::ClipDetected { clips } => {
+                record(EngineEventKind::ClipDetected, 0, clips, 0.0, 0.0)
+            }
+            EngineEvent::XRun => record(EngineEventKind::XRun, 0, 0, 0.0, 0.0),
+            EngineEvent::StreamStopped => record(EngineEventKind::StreamStopped, 0, 0, 0.0, 0.0),
+            EngineEvent::CallbackStalled { stalled_ms } => {
+                record(EngineEventKind::CallbackStalled, 0, stalled_ms, 0.0, 0.0)
+            }
+            EngineEvent::PeakLevels { left, right } => {
+                record(EngineEventKind::PeakLevels, 0, 0, left, right)
+            }
+            EngineEvent::NoteOn {
+                channel,
+                note,
+                velocity,
+            } => record(
+                EngineEventKind::NoteOn,
+                note as u32,
+                channel as u64,
+                velocity,
+                0.0,
+            ),
+            EngineEvent::Node {
+                node_id,
+                code,
+                value,
+            } => record(
+                EngineEventKind::Node,
+                code,
+                node_id.as_raw() as u64,
+                value,
+                0.0,
+            ),
+        }
+    }
+}
 
 /// 他言語から呼び出すための初期化関数です。
 /// 共有ライブラリ内の必要なセットアップ処理を実行します。
@@ -9,4 +140,344 @@ pub extern "C" fn init() {
     }
 }
 
+/// マクロの値を設定します。
+///
+/// # 引数
+/// * `index` - マクロの番号（audio_graph::MAX_MACROS 未満）
+/// * `value` - マクロの値（0〜1）
+///
+/// # 戻り値
+/// * 成功した場合は `true`。init の前に呼び出した場合や、番号が不正な場合は `false`
+#[unsafe(no_mangle)]
+pub extern "C" fn engine_set_macro(index: u32, value: f32) -> bool {
+    let command = GraphCommand::SetMacro {
+        index: index as usize,
+        value,
+    };
+    request(command).is_some()
+}
+
+/// ノードの出力のピークレベルを、チャンネルごとに取得します。
+///
+/// 直前のブロックでノードが出力したサンプルの絶対値の最大値です。ホストの UI でレベルメーターを
+/// 描画するために、画面の更新ごとに呼び出せます。
+///
+/// # 引数
+/// * `node_id` - ノード ID の整数の表現
+/// * `out_peaks` - 左右のピークを書き込む、長さ 2 の f32 の配列
+///
+/// # 戻り値
+/// * 成功した場合は `true`。init の前に呼び出した場合や、ノードが存在しない場合は `false`
+///
+/// # Safety
+/// `out_peaks` は書き込み可能な長さ 2 の f32 の配列を指している必要があります。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn engine_get_meter(node_id: u64, out_peaks: *mut f32) -> bool {
+    if out_peaks.is_null() {
+        return false;
+    }
+    let command = GraphCommand::GetMeter {
+        node_id: NodeId::from_raw(node_id as usize),
+    };
+    let Some(CommandResponse::ChannelPeaks(peaks)) = request(command) else {
+        return false;
+    };
+    let out_peaks = unsafe { std::slice::from_raw_parts_mut(out_peaks, METER_CHANNELS) };
+    out_peaks.copy_from_slice(&peaks);
+    true
+}
+
+/// 名前を指定して、ノードのパラメーターの値を設定します。
+///
+/// # 引数
+/// * `node_id` - ノード ID の整数の表現
+/// * `name` - パラメーターの ID（NUL 終端の UTF-8）
+/// * `value` - 設定する値（パラメーターの範囲に制限される）
+///
+/// # 戻り値
+/// * 成功した場合は `true`。init の前に呼び出した場合や、ノードやパラメーターが存在しない場合は `false`
+///
+/// # Safety
+/// `name` は NUL 終端の文字列を指している必要があります。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn engine_set_param_by_name(
+    node_id: u64,
+    name: *const c_char,
+    value: f32,
+) -> bool {
+    let Some(parameter_id) = (unsafe { parameter_name(name) }) else {
+        return false;
+    };
+    let command = GraphCommand::SetParameter {
+        node_id: NodeId::from_raw(node_id as usize),
+        parameter_id,
+        value,
+    };
+    request(command).is_some()
+}
+
+/// 名前を指定して、ノードのパラメーターの現在の値を取得します。
+///
+/// # 引数
+/// * `node_id` - ノード ID の整数の表現
+/// * `name` - パラメーターの ID（NUL 終端の UTF-8）
+/// * `out_value` - 値を書き込む先
+///
+/// # 戻り値
+/// * 成功した場合は `true`。init の前に呼び出した場合や、ノードやパラメーターが存在しない場合は `false`
+///
+/// # Safety
+/// `name` は NUL 終端の文字列を、`out_value` は書き込み可能な f32 を指している必要があります。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn engine_get_param_by_name(
+    node_id: u64,
+    name: *const c_char,
+    out_value: *mut f32,
+) -> bool {
+    if out_value.is_null() {
+        return false;
+    }
+    let Some(parameter_id) = (unsafe { parameter_name(name) }) else {
+        return false;
+    };
+    let command = GraphCommand::GetParameter {
+        node_id: NodeId::from_raw(node_id as usize),
+        parameter_id,
+    };
+    let Some(CommandResponse::Value(value)) = request(command) else {
+        return false;
+    };
+    unsafe { out_value.write(value) };
+    true
+}
+
+/// ノードが公開するパラメーターの数を取得します。
+///
+/// # 戻り値
+/// * パラメーターの数。init の前に呼び出した場合や、ノードが存在しない場合は 0
+#[unsafe(no_mangle)]
+pub extern "C" fn engine_get_param_count(node_id: u64) -> u32 {
+    match parameter_infos(node_id) {
+        Some(infos) => infos.len() as u32,
+        None => 0,
+    }
+}
+
+/// ノードが公開するパラメーターの情報（ID、名前、範囲、デフォルト値、単位）を取得します。
+///
+/// engine_get_param_count で得た数までの番号を順に指定して、汎用のパラメーター画面を組み立てられます。
+///
+/// # 引数
+/// * `node_id` - ノード ID の整数の表現
+/// * `index` - パラメーターの番号（engine_get_param_count 未満）
+/// * `out_info` - 情報を書き込む先
+///
+/// # 戻り値
+/// * 成功した場合は `true`。init の前に呼び出した場合や、ノードが存在しない場合、番号が不正な場合は `false`
+///
+/// # Safety
+/// `out_info` は書き込み可能な ParameterInfoRecord を指している必要があります。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn engine_get_param_info(
+    node_id: u64,
+    index: u32,
+    out_info: *mut ParameterInfoRecord,
+) -> bool {
+    if out_info.is_null() {
+        return false;
+    }
+    let Some(info) = parameter_infos(node_id).and_then(|infos| infos.get(index as usize).copied())
+    else {
+        return false;
+    };
+    unsafe { out_info.write((&info).into()) };
+    true
+}
+
+/// 現在の音声グラフを JSON の記述として書き出します。
+///
+/// 記述の形式は graph_file モジュールと同じで、engine_load_graph_json でそのまま読み込めます。
+///
+/// # 戻り値
+/// * NUL 終端の UTF-8 の JSON。使い終わったら engine_free_string で解放してください。
+///   init の前に呼び出した場合や、書き出せないノードがある場合は NULL
+#[unsafe(no_mangle)]
+pub extern "C" fn engine_export_graph_json() -> *mut c_char {
+    let service = &raw const SERVICE;
+    let Some(service) = (unsafe { (*service).as_ref() }) else {
+        return std::ptr::null_mut();
+    };
+    let Ok(description) = service.export_graph() else {
+        return std::ptr::null_mut();
+    };
+    // JSON の文字列には NUL が含まれない
+    CString::new(description.to_json()).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// JSON の記述を読み込み、音声グラフを置き換えます。
+///
+/// 再生中の場合は、新しい入力ノードと出力ノードで再生を再開します。
+/// ノード ID は変わるため、読み込んだ後は engine_get_param_info などで改めて取得してください。
+///
+/// # 引数
+/// * `json` - engine_export_graph_json で書き出した記述など（NUL 終端の UTF-8）
+///
+/// # 戻り値
+/// * 成功した場合は `true`。init の前に呼び出した場合や、記述が不正な場合は `false`（音声グラフは変更しない）
+///
+/// # Safety
+/// `json` は NUL 終端の文字列を指している必要があります。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn engine_load_graph_json(json: *const c_char) -> bool {
+    let service = &raw mut SERVICE;
+    let Some(service) = (unsafe { (*service).as_mut() }) else {
+        return false;
+    };
+    if json.is_null() {
+        return false;
+    }
+    let Ok(json) = (unsafe { CStr::from_ptr(json) }).to_str() else {
+        return false;
+    };
+    GraphDescription::from_json(json)
+        .and_then(|description| service.load_graph(&description))
+        .is_ok()
+}
+
+/// このライブラリが返した文字列を解放します。
+///
+/// # Safety
+/// `text` は engine_export_graph_json が返した文字列か NULL である必要があり、解放後は使えません。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn engine_free_string(text: *mut c_char) {
+    if !text.is_null() {
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
+
+/// 音声エンジンから通知されたイベントを 1 件取り出します。
+///
+/// イベントがなくなるまで、定期的に繰り返し呼び出してください。
+///
+/// # 引数
+/// * `out` - 取り出したイベントを書き込む先
+///
+/// # 戻り値
+/// * イベントを書き込んだ場合は `true`。イベントがない場合や、init の前に呼び出した場合は `false`
+///
+/// # Safety
+/// `out` は書き込み可能な EngineEventRecord を指している必要があります。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn engine_poll_event(out: *mut EngineEventRecord) -> bool {
+    let service = &raw const SERVICE;
+    let Some(service) = (unsafe { (*service).as_ref() }) else {
+        return false;
+    };
+    if out.is_null() {
+        return false;
+    }
+    let Some(event) = service.next_event() else {
+        return false;
+    };
+    unsafe { out.write(event.into()) };
+    true
+}
+
+/// イベントを受け取る C の関数
+///
+/// 1 つ目の引数に通知されたイベント（呼び出しの間だけ有効）、2 つ目の引数に登録時の `user_data` が渡されます。
+pub type EngineEventCallback = extern "C" fn(*const EngineEventRecord, *mut c_void);
+
+/// C から渡された `user_data` を通知用のスレッドに渡すためのラッパー
+struct UserData(*mut c_void);
+
+// ポインタの指す先をスレッド間で扱えることは、登録した側が保証する
+unsafe impl Send for UserData {}
+
+impl UserData {
+    /// 包んでいるポインタ
+    ///
+    /// クロージャーがフィールドではなく UserData ごとキャプチャするよう、メソッドで取り出します。
+    fn get(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// イベントを受け取る関数を登録します。
+///
+/// 関数はオーディオスレッドではなく、専用の通知用のスレッドから呼び出されます。
+/// xrun やクリップのほか、再生中にストリームが止まった場合（EngineEventKind::StreamStopped）も通知します。
+/// 既に登録されている関数は、残っているイベントを通知してから解除されます。
+///
+/// # 引数
+/// * `callback` - イベントを受け取る関数。NULL の場合は登録を解除するだけ
+/// * `user_data` - `callback` にそのまま渡すポインタ
+///
+/// # 戻り値
+/// * 登録した場合は `true`。init の前に呼び出した場合は `false`。登録を解除した場合も `false`
+///
+/// # Safety
+/// `user_data` は登録を解除するまで、通知用のスレッドから使える必要があります。
+/// 登録している間は、engine_poll_event と同じイベントを取り合うため engine_poll_event を併用しないでください。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn engine_set_event_callback(
+    callback: Option<EngineEventCallback>,
+    user_data: *mut c_void,
+) -> bool {
+    // 前の通知用のスレッドを止めてから登録し直す
+    let notifier = &raw mut NOTIFIER;
+    unsafe { *notifier = None };
+    let service = &raw const SERVICE;
+    let Some(service) = (unsafe { (*service).as_ref() }) else {
+        return false;
+    };
+    let Some(callback) = callback else {
+        return false;
+    };
+    let user_data = UserData(user_data);
+    let started = service.spawn_event_notifier(move |event| {
+        let record = EngineEventRecord::from(event);
+        callback(&record, user_data.get());
+    });
+    unsafe { *notifier = Some(started) };
+    true
+}
+
+/// コマンドを音声グラフに適用し、結果を返す
+///
+/// init の前に呼び出した場合や、コマンドが失敗した場合は None を返します。
+fn request(command: GraphCommand) -> Option<CommandResponse> {
+    let service = &raw mut SERVICE;
+    let service = unsafe { (*service).as_mut() }?;
+    let reply = service.command_sender().request(command).ok()?;
+    service.process_commands();
+    reply.try_recv().ok()?.ok()
+}
+
+/// ノードが公開するパラメーターの情報の一覧を取得する
+fn parameter_infos(node_id: u64) -> Option<Vec<ParameterInfo>> {
+    let command = GraphCommand::GetParameterInfos {
+        node_id: NodeId::from_raw(node_id as usize),
+    };
+    match request(command)? {
+        CommandResponse::ParameterInfos(infos) => Some(infos),
+        _ => None,
+    }
+}
+
+/// C から渡されたパラメーターの ID を文字列に変換する
+///
+/// # Safety
+/// `name` は NULL か、NUL 終端の文字列を指している必要があります。
+unsafe fn parameter_name(name: *const c_char) -> Option<String> {
+    if name.is_null() {
+        return None;
+    }
+    let name = unsafe { CStr::from_ptr(name) };
+    name.to_str().ok().map(str::to_string)
+}
+
 static mut SERVICE: Option<AudioEngineService> = None;
+
+/// engine_set_event_callback で開始した通知用のスレッド
+static mut NOTIFIER: Option<EventNotifier> = None;
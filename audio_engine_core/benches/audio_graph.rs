@@ -0,0 +1,135 @@
+//! AudioGraph::process のベンチマーク
+//!
+//! 代表的なトポロジー（直列チェーン、多数の並列加算、フィードバックディレイ）を
+//! よく使うバッファサイズで処理し、ブロックごとのコピー処理の性能劣化を検出します。
+
+use std::hint::black_box;
+
+use audio_engine_core::audio_buffer::AudioBuffer;
+use audio_engine_core::audio_graph::{AudioGraph, NodeId};
+use audio_engine_core::nodes::{
+    GainProcessor, InputNode, OutputNode, SineGenerator, TapIn, TapOut,
+};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const NUM_CHANNELS: usize = 2;
+const BUFFER_SIZES: [usize; 3] = [64, 256, 1024];
+
+/// トポロジーを作成する関数（引数は最大バッファサイズ）
+type TopologyBuilder = fn(usize) -> Topology;
+
+/// ベンチマーク対象のグラフと入出力ノードのID
+struct Topology {
+    graph: AudioGraph,
+    input_id: NodeId,
+    output_id: NodeId,
+}
+
+/// 入力 → ゲイン × 16 → 出力 の直列チェーン
+fn serial_chain(buffer_size: usize) -> Topology {
+    let mut graph = AudioGraph::new();
+    let input_id = graph.add_node(Box::new(InputNode::new()));
+    let output_id = graph.add_node(Box::new(OutputNode::new()));
+    let mut prev_id = input_id;
+    for _ in 0..16 {
+        let gain_id = graph.add_node(Box::new(GainProcessor::new()));
+        graph.add_edge(prev_id, gain_id).unwrap();
+        prev_id = gain_id;
+    }
+    graph.add_edge(prev_id, output_id).unwrap();
+    graph.prepare(SAMPLE_RATE, buffer_size);
+    Topology {
+        graph,
+        input_id,
+        output_id,
+    }
+}
+
+/// サイン波 × 32 を出力ノードで合計する並列グラフ
+fn wide_parallel(buffer_size: usize) -> Topology {
+    let mut graph = AudioGraph::new();
+    let input_id = graph.add_node(Box::new(InputNode::new()));
+    let output_id = graph.add_node(Box::new(OutputNode::new()));
+    for i in 0..32 {
+        let mut sine = SineGenerator::new();
+        sine.set_frequency(110.0 * (i + 1) as f32);
+        let sine_id = graph.add_node(Box::new(sine));
+        graph.add_edge(input_id, sine_id).unwrap();
+        graph.add_edge(sine_id, output_id).unwrap();
+    }
+    graph.prepare(SAMPLE_RATE, buffer_size);
+    Topology {
+        graph,
+        input_id,
+        output_id,
+    }
+}
+
+/// TapIn/TapOut によるフィードバックディレイ
+///
+/// サイン波 → ミックス → TapIn、TapOut → フィードバックゲイン → ミックス → 出力
+fn feedback_delay(buffer_size: usize) -> Topology {
+    let mut graph = AudioGraph::new();
+    let input_id = graph.add_node(Box::new(InputNode::new()));
+    let output_id = graph.add_node(Box::new(OutputNode::new()));
+
+    let tap_in = TapIn::new();
+    let mut tap_out = TapOut::new(tap_in.shared_buffer());
+    tap_out.set_delay_time_ms(250.0);
+    let mut feedback = GainProcessor::new();
+    feedback.set_gain(0.5);
+
+    let sine_id = graph.add_node(Box::new(SineGenerator::new()));
+    let mix_id = graph.add_node(Box::new(GainProcessor::new()));
+    let tap_in_id = graph.add_node(Box::new(tap_in));
+    let tap_out_id = graph.add_node(Box::new(tap_out));
+    let feedback_id = graph.add_node(Box::new(feedback));
+
+    graph.add_edge(input_id, sine_id).unwrap();
+    graph.add_edge(sine_id, mix_id).unwrap();
+    graph.add_edge(tap_out_id, feedback_id).unwrap();
+    graph.add_edge(feedback_id, mix_id).unwrap();
+    graph.add_edge(mix_id, tap_in_id).unwrap();
+    graph.add_edge(mix_id, output_id).unwrap();
+    graph.prepare(SAMPLE_RATE, buffer_size);
+    Topology {
+        graph,
+        input_id,
+        output_id,
+    }
+}
+
+fn bench_topologies(c: &mut Criterion) {
+    let topologies: [(&str, TopologyBuilder); 3] = [
+        ("serial_chain", serial_chain),
+        ("wide_parallel", wide_parallel),
+        ("feedback_delay", feedback_delay),
+    ];
+
+    for (name, build) in topologies {
+        let mut group = c.benchmark_group(format!("audio_graph/{}", name));
+        for buffer_size in BUFFER_SIZES {
+            let mut topology = build(buffer_size);
+            let mut data = vec![0.0; NUM_CHANNELS * buffer_size];
+            group.bench_with_input(
+                BenchmarkId::from_parameter(buffer_size),
+                &buffer_size,
+                |b, &buffer_size| {
+                    b.iter(|| {
+                        let mut buffer = AudioBuffer::new(NUM_CHANNELS, buffer_size, &mut data);
+                        topology.graph.process(
+                            black_box(&mut buffer),
+                            topology.input_id,
+                            topology.output_id,
+                        );
+                    })
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_topologies);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+//! 個々のノードの process のベンチマーク
+//!
+//! よく使うバッファサイズで各ノードを単体で処理し、ノード実装の性能劣化を検出します。
+
+use std::hint::black_box;
+
+use audio_engine_core::audio_buffer::AudioBuffer;
+use audio_engine_core::audio_graph::AudioGraphNode;
+use audio_engine_core::midi::NoteEvent;
+use audio_engine_core::nodes::{Chorus, Eq3, FmSynth, GainProcessor, Phaser, SineGenerator};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const NUM_CHANNELS: usize = 2;
+const BUFFER_SIZES: [usize; 3] = [64, 256, 1024];
+
+/// ベンチマーク対象のノードを作成する
+fn build_node(name: &str) -> Box<dyn AudioGraphNode> {
+    match name {
+        "gain" => Box::new(GainProcessor::new()),
+        "sine" => Box::new(SineGenerator::new()),
+        "eq3" => {
+            let mut eq = Eq3::new();
+            eq.set_parameter("mid_gain", 6.0);
+            Box::new(eq)
+        }
+        "chorus" => Box::new(Chorus::new()),
+        "phaser" => Box::new(Phaser::new()),
+        "fm_synth" => {
+            let mut synth = FmSynth::new();
+            synth.handle_note_event(&NoteEvent::NoteOn {
+                timing: 0,
+                channel: 0,
+                note: 60,
+                velocity: 1.0,
+            });
+            Box::new(synth)
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn bench_nodes(c: &mut Criterion) {
+    for name in ["gain", "sine", "eq3", "chorus", "phaser", "fm_synth"] {
+        let mut group = c.benchmark_group(format!("nodes/{}", name));
+        for buffer_size in BUFFER_SIZES {
+            let mut node = build_node(name);
+            node.prepare(SAMPLE_RATE, buffer_size);
+            // 入力として小さなノイズ状の信号を入れておく
+            let mut data: Vec<f32> = (0..NUM_CHANNELS * buffer_size)
+                .map(|i| ((i * 7919) % 200) as f32 / 100.0 - 1.0)
+                .collect();
+            group.bench_with_input(
+                BenchmarkId::from_parameter(buffer_size),
+                &buffer_size,
+                |b, &buffer_size| {
+                    b.iter(|| {
+                        let mut buffer = AudioBuffer::new(NUM_CHANNELS, buffer_size, &mut data);
+                        node.process(black_box(&mut buffer));
+                    })
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_nodes);
+criterion_main!(benches);
@@ -1,20 +1,27 @@
+use core::iter::StepBy;
+use core::slice::{ChunksExact, ChunksExactMut, Iter, IterMut};
+
+use crate::prelude::*;
+use crate::sample::Sample;
+
 /// AudioBuffer の実装（各チャンネルのサンプルを連続領域に格納）
 /// 内部はインターリーブ方式となっています。
-pub struct AudioBuffer<'a> {
+/// サンプル型は既定で f32 です。倍精度で処理する場合は `AudioBuffer<f64>` を使います。
+pub struct AudioBuffer<'a, S: Sample = f32> {
     /// すべてのチャンネルのサンプルが連続して格納されたバッファ。
     /// 配置は interleaved。
     /// [L0, R0, L1, R1, L2, R2, ...]
-    buffer: &'a mut [f32],
+    buffer: &'a mut [S],
     /// チャンネル数（例：ステレオなら 2）
     channels: usize,
     /// 各チャンネルあたりのサンプル数（フレーム数）
     frames: usize,
 }
 
-impl<'a> AudioBuffer<'a> {
+impl<'a, S: Sample> AudioBuffer<'a, S> {
     /// 新しい AudioBuffer を作成する
     /// これはヒープアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
-    pub fn new(channels: usize, frames: usize, buffer: &'a mut [f32]) -> Self {
+    pub fn new(channels: usize, frames: usize, buffer: &'a mut [S]) -> Self {
         debug_assert_eq!(
             buffer.len(),
             channels * frames,
@@ -30,7 +37,7 @@ impl<'a> AudioBuffer<'a> {
     /// 指定されたフレームのサンプルを取得する。
     /// 引数はフレームのインデックス。
     /// 返り値は [ch0, ch1, ch2, ...] のように、チャンネルごとにサンプルが並んだ配列。
-    pub fn get_frame(&self, idx: usize) -> &[f32] {
+    pub fn get_frame(&self, idx: usize) -> &[S] {
         let start = idx * self.channels;
         let end = start + self.channels;
         &self.buffer[start..end]
@@ -39,7 +46,7 @@ impl<'a> AudioBuffer<'a> {
     /// 指定されたフレームのサンプルを取得する。
     /// 引数はフレームのインデックス。
     /// 返り値は [ch0, ch1, ch2, ...] のように、チャンネルごとにサンプルが並んだ配列。
-    pub fn get_mut_frame(&mut self, idx: usize) -> &mut [f32] {
+    pub fn get_mut_frame(&mut self, idx: usize) -> &mut [S] {
         let start = idx * self.channels;
         let end = start + self.channels;
         &mut self.buffer[start..end]
@@ -53,11 +60,217 @@ impl<'a> AudioBuffer<'a> {
         self.frames
     }
 
-    pub fn as_mut_slice(&mut self) -> &mut [f32] {
+    pub fn as_mut_slice(&mut self) -> &mut [S] {
         self.buffer
     }
 
-    pub fn as_slice(&self) -> &[f32] {
+    pub fn as_slice(&self) -> &[S] {
         self.buffer
     }
+
+    /// フレームごとのイテレーターを取得する
+    ///
+    /// 各要素は [ch0, ch1, ch2, ...] のように、チャンネルごとにサンプルが並んだ配列。
+    pub fn frames(&self) -> ChunksExact<'_, S> {
+        self.buffer.chunks_exact(self.channels.max(1))
+    }
+
+    /// フレームごとの可変イテレーターを取得する
+    ///
+    /// 各要素は [ch0, ch1, ch2, ...] のように、チャンネルごとにサンプルが並んだ配列。
+    pub fn frames_mut(&mut self) -> ChunksExactMut<'_, S> {
+        self.buffer.chunks_exact_mut(self.channels.max(1))
+    }
+
+    /// 指定されたチャンネルのサンプルを順に返すイテレーターを取得する
+    pub fn channel_iter(&self, ch: usize) -> StepBy<Iter<'_, S>> {
+        debug_assert!(ch < self.channels, "チャンネル番号が範囲外です: {}", ch);
+        self.buffer[ch.min(self.buffer.len())..]
+            .iter()
+            .step_by(self.channels.max(1))
+    }
+
+    /// 指定されたチャンネルのサンプルを順に返す可変イテレーターを取得する
+    pub fn channel_iter_mut(&mut self, ch: usize) -> StepBy<IterMut<'_, S>> {
+        debug_assert!(ch < self.channels, "チャンネル番号が範囲外です: {}", ch);
+        let start = ch.min(self.buffer.len());
+        self.buffer[start..]
+            .iter_mut()
+            .step_by(self.channels.max(1))
+    }
+
+    /// 指定したフレームでバッファを2つに分割する
+    ///
+    /// ブロックの途中でイベントを処理する場合などに使います。
+    /// これはヒープアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    ///
+    /// # 引数
+    /// * `frame` - 分割位置のフレーム。前半は `0..frame`、後半は `frame..` になる。
+    pub fn split_at_frame(&mut self, frame: usize) -> (AudioBuffer<'_, S>, AudioBuffer<'_, S>) {
+        let frame = frame.min(self.frames);
+        let (head, tail) = self.buffer.split_at_mut(frame * self.channels);
+        (
+            AudioBuffer::new(self.channels, frame, head),
+            AudioBuffer::new(self.channels, self.frames - frame, tail),
+        )
+    }
+}
+
+/// ストレージを所有するオーディオバッファ（インターリーブ）
+///
+/// 作成時に最大フレーム数分のメモリを確保し、`set_num_frames` で実際に使うフレーム数を変更します。
+/// フレーム数の変更や `as_audio_buffer` はヒープアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+pub struct OwnedAudioBuffer<S: Sample = f32> {
+    /// 最大フレーム数分のサンプル
+    data: Vec<S>,
+    /// チャンネル数
+    channels: usize,
+    /// 現在のフレーム数
+    frames: usize,
+    /// 最大フレーム数
+    max_frames: usize,
+}
+
+impl<S: Sample> OwnedAudioBuffer<S> {
+    /// 新しい OwnedAudioBuffer を作成する。フレーム数は最大フレーム数になります。
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn new(channels: usize, max_frames: usize) -> Self {
+        Self {
+            data: vec![S::ZERO; channels * max_frames],
+            channels,
+            frames: max_frames,
+            max_frames,
+        }
+    }
+
+    /// 現在のフレーム数を変更する。最大フレーム数を超える値は最大フレーム数に制限されます。
+    pub fn set_num_frames(&mut self, frames: usize) {
+        debug_assert!(
+            frames <= self.max_frames,
+            "フレーム数が最大フレーム数を超えています: {} > {}",
+            frames,
+            self.max_frames
+        );
+        self.frames = frames.min(self.max_frames);
+    }
+
+    /// 現在のフレーム数分の AudioBuffer を取得する
+    pub fn as_audio_buffer(&mut self) -> AudioBuffer<'_, S> {
+        let len = self.channels * self.frames;
+        AudioBuffer::new(self.channels, self.frames, &mut self.data[..len])
+    }
+
+    /// 現在のフレーム数分のサンプルを取得する
+    pub fn as_slice(&self) -> &[S] {
+        &self.data[..self.channels * self.frames]
+    }
+
+    /// 現在のフレーム数分のサンプルを取得する
+    pub fn as_mut_slice(&mut self) -> &mut [S] {
+        &mut self.data[..self.channels * self.frames]
+    }
+
+    /// 現在のフレーム数分のサンプルを 0 でクリアする
+    pub fn clear(&mut self) {
+        self.as_mut_slice().fill(S::ZERO);
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn max_frames(&self) -> usize {
+        self.max_frames
+    }
+}
+
+/// ノードが処理に使うバッファの配置
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferLayout {
+    /// インターリーブ（[L0, R0, L1, R1, ...]）
+    Interleaved,
+    /// チャンネルごとに分かれた配置（[[L0, L1, ...], [R0, R1, ...]]）
+    Planar,
+}
+
+/// チャンネルごとに分かれたオーディオバッファのビュー（非インターリーブ）
+///
+/// nih_plug の `Buffer::as_slice` と同じ `&mut [&mut [S]]` の形のデータを参照します。
+/// これはヒープアロケーションを伴わないため、リアルタイムスレッドから作成できます。
+pub struct PlanarAudioBuffer<'a, 'b, S: Sample = f32> {
+    /// チャンネルごとのサンプル列
+    channels: &'a mut [&'b mut [S]],
+    /// 各チャンネルあたりのサンプル数（フレーム数）
+    frames: usize,
+}
+
+impl<'a, 'b, S: Sample> PlanarAudioBuffer<'a, 'b, S> {
+    /// 新しい PlanarAudioBuffer を作成する
+    ///
+    /// フレーム数は最初のチャンネルの長さになります。すべてのチャンネルは同じ長さである必要があります。
+    pub fn new(channels: &'a mut [&'b mut [S]]) -> Self {
+        let frames = channels.first().map_or(0, |channel| channel.len());
+        debug_assert!(
+            channels.iter().all(|channel| channel.len() == frames),
+            "チャンネルごとのサンプル数が一致していません"
+        );
+        Self { channels, frames }
+    }
+
+    /// 指定されたチャンネルのサンプル列を取得する
+    pub fn channel(&self, ch: usize) -> &[S] {
+        self.channels[ch]
+    }
+
+    /// 指定されたチャンネルのサンプル列を取得する
+    pub fn channel_mut(&mut self, ch: usize) -> &mut [S] {
+        self.channels[ch]
+    }
+
+    /// チャンネルごとのサンプル列の配列を取得する
+    pub fn as_mut_slices(&mut self) -> &mut [&'b mut [S]] {
+        self.channels
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iterators_and_split() {
+        let mut vector: Vec<f32> = vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let mut buffer = AudioBuffer::new(2, 3, vector.as_mut_slice());
+
+        assert_eq!(buffer.frames().nth(1), Some(&[2.0, 3.0][..]));
+        assert_eq!(
+            buffer.channel_iter(1).copied().collect::<Vec<_>>(),
+            vec![1.0, 3.0, 5.0]
+        );
+        for sample in buffer.channel_iter_mut(0) {
+            *sample = -1.0;
+        }
+
+        let (head, mut tail) = buffer.split_at_frame(1);
+        assert_eq!(head.num_frames(), 1);
+        assert_eq!(tail.num_frames(), 2);
+        for frame in tail.frames_mut() {
+            frame[1] = 0.0;
+        }
+        assert_eq!(vector, vec![-1.0, 1.0, -1.0, 0.0, -1.0, 0.0]);
+    }
 }
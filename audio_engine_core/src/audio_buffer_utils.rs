@@ -1,4 +1,5 @@
-use crate::audio_buffer::AudioBuffer;
+use crate::audio_buffer::{AudioBuffer, PlanarAudioBuffer};
+use crate::sample::Sample;
 
 /// ソースバッファから宛先バッファにサンプルをコピーします
 ///
@@ -8,7 +9,7 @@ use crate::audio_buffer::AudioBuffer;
 ///
 /// # リアルタイム安全性
 /// * この関数はメモリ割り当てを行わないためリアルタイム安全です。
-pub fn copy_buffer(src_buffer: &AudioBuffer, dst_buffer: &mut AudioBuffer) {
+pub fn copy_buffer<S: Sample>(src_buffer: &AudioBuffer<S>, dst_buffer: &mut AudioBuffer<S>) {
     let src_slice = src_buffer.as_slice();
     let dst_slice = dst_buffer.as_mut_slice();
     dst_slice.copy_from_slice(src_slice);
@@ -22,12 +23,49 @@ pub fn copy_buffer(src_buffer: &AudioBuffer, dst_buffer: &mut AudioBuffer) {
 ///
 /// # リアルタイム安全性
 /// * この関数はメモリ割り当てを行わないためリアルタイム安全です。
-pub fn add_buffer(src_buffer: &AudioBuffer, dst_buffer: &mut AudioBuffer) {
+pub fn add_buffer<S: Sample>(src_buffer: &AudioBuffer<S>, dst_buffer: &mut AudioBuffer<S>) {
     let src_slice = src_buffer.as_slice();
     let dst_slice = dst_buffer.as_mut_slice();
     for (i, samp) in src_slice.iter().enumerate() {
         if i < dst_slice.len() {
-            dst_slice[i] += samp;
+            dst_slice[i] += *samp;
+        }
+    }
+}
+
+/// ソースバッファのサンプルにゲインを掛けて宛先バッファに加算します
+///
+/// # 引数
+/// * `src_buffer` - ソースバッファ
+/// * `dst_buffer` - 宛先バッファ
+/// * `gain` - ソースバッファのサンプルに掛けるゲイン
+///
+/// # リアルタイム安全性
+/// * この関数はメモリ割り当てを行わないためリアルタイム安全です。
+pub fn add_buffer_with_gain(src_buffer: &AudioBuffer, dst_buffer: &mut AudioBuffer, gain: f32) {
+    let src_slice = src_buffer.as_slice();
+    let dst_slice = dst_buffer.as_mut_slice();
+    for (dst, src) in dst_slice.iter_mut().zip(src_slice) {
+        *dst += *src * gain;
+    }
+}
+
+/// ソースバッファのサンプルにゲインを掛け、絶対値が宛先バッファのサンプルより大きければ置き換えます
+///
+/// # 引数
+/// * `src_buffer` - ソースバッファ
+/// * `dst_buffer` - 宛先バッファ
+/// * `gain` - ソースバッファのサンプルに掛けるゲイン
+///
+/// # リアルタイム安全性
+/// * この関数はメモリ割り当てを行わないためリアルタイム安全です。
+pub fn max_buffer_with_gain(src_buffer: &AudioBuffer, dst_buffer: &mut AudioBuffer, gain: f32) {
+    let src_slice = src_buffer.as_slice();
+    let dst_slice = dst_buffer.as_mut_slice();
+    for (dst, src) in dst_slice.iter_mut().zip(src_slice) {
+        let sample = *src * gain;
+        if sample.abs() > dst.abs() {
+            *dst = sample;
         }
     }
 }
@@ -39,9 +77,123 @@ pub fn add_buffer(src_buffer: &AudioBuffer, dst_buffer: &mut AudioBuffer) {
 ///
 /// # リアルタイム安全性
 /// * この関数はメモリ割り当てを行わないためリアルタイム安全です。
-pub fn clear_buffer(buffer: &mut AudioBuffer) {
+pub fn clear_buffer<S: Sample>(buffer: &mut AudioBuffer<S>) {
     let slice = buffer.as_mut_slice();
-    slice.fill(0.0);
+    slice.fill(S::ZERO);
+}
+
+/// チャンネル数の異なるバッファへサンプルを変換してコピーします
+///
+/// モノラルへはチャンネルの平均に、モノラルからは全チャンネルへ同じ値に変換します。
+/// それ以外の場合は同じ番号のチャンネルをコピーし、ソースにないチャンネルは 0 にします。
+/// フレーム数が異なる場合は、少ない方に合わせてコピーします。
+///
+/// # 引数
+/// * `src_buffer` - ソースバッファ
+/// * `dst_buffer` - 宛先バッファ
+///
+/// # リアルタイム安全性
+/// * この関数はメモリ割り当てを行わないためリアルタイム安全です。
+pub fn convert_channels<S: Sample>(src_buffer: &AudioBuffer<S>, dst_buffer: &mut AudioBuffer<S>) {
+    let src_channels = src_buffer.num_channels();
+    let dst_channels = dst_buffer.num_channels();
+    let src_frames = src_buffer.as_slice().chunks_exact(src_channels);
+    let dst_frames = dst_buffer.as_mut_slice().chunks_exact_mut(dst_channels);
+    for (src, dst) in src_frames.zip(dst_frames) {
+        if dst_channels == 1 {
+            let sum = src.iter().fold(0.0, |sum, sample| sum + sample.to_f64());
+            dst[0] = S::from_f64(sum / src_channels as f64);
+        } else if src_channels == 1 {
+            dst.fill(src[0]);
+        } else {
+            for (ch, sample) in dst.iter_mut().enumerate() {
+                *sample = src.get(ch).copied().unwrap_or(S::ZERO);
+            }
+        }
+    }
+}
+
+/// チャンネルごとに分かれたバッファをインターリーブのバッファに変換します
+///
+/// チャンネル数やフレーム数が異なる場合は、少ない方に合わせてコピーします。
+///
+/// # 引数
+/// * `src_buffer` - ソースバッファ（プレーナー）
+/// * `dst_buffer` - 宛先バッファ（インターリーブ）
+///
+/// # リアルタイム安全性
+/// * この関数はメモリ割り当てを行わないためリアルタイム安全です。
+pub fn interleave<S: Sample>(src_buffer: &PlanarAudioBuffer<S>, dst_buffer: &mut AudioBuffer<S>) {
+    let num_channels = dst_buffer.num_channels();
+    let num_frames = src_buffer.num_frames().min(dst_buffer.num_frames());
+    let dst_slice = &mut dst_buffer.as_mut_slice()[..num_frames * num_channels];
+
+    // よく使うステレオの場合は、フレーム単位でまとめて書き込む
+    if num_channels == 2 && src_buffer.num_channels() >= 2 {
+        let left = &src_buffer.channel(0)[..num_frames];
+        let right = &src_buffer.channel(1)[..num_frames];
+        for ((frame, &l), &r) in dst_slice.chunks_exact_mut(2).zip(left).zip(right) {
+            frame[0] = l;
+            frame[1] = r;
+        }
+        return;
+    }
+
+    for ch in 0..num_channels.min(src_buffer.num_channels()) {
+        let src = &src_buffer.channel(ch)[..num_frames];
+        for (frame, &sample) in dst_slice.chunks_exact_mut(num_channels).zip(src) {
+            frame[ch] = sample;
+        }
+    }
+}
+
+/// インターリーブのバッファをチャンネルごとに分かれたバッファに変換します
+///
+/// チャンネル数やフレーム数が異なる場合は、少ない方に合わせてコピーします。
+///
+/// # 引数
+/// * `src_buffer` - ソースバッファ（インターリーブ）
+/// * `dst_buffer` - 宛先バッファ（プレーナー）
+///
+/// # リアルタイム安全性
+/// * この関数はメモリ割り当てを行わないためリアルタイム安全です。
+pub fn deinterleave<S: Sample>(src_buffer: &AudioBuffer<S>, dst_buffer: &mut PlanarAudioBuffer<S>) {
+    let num_channels = src_buffer.num_channels();
+    let num_frames = src_buffer.num_frames().min(dst_buffer.num_frames());
+    let src_slice = &src_buffer.as_slice()[..num_frames * num_channels];
+
+    // よく使うステレオの場合は、フレーム単位でまとめて読み出す
+    if num_channels == 2 && dst_buffer.num_channels() == 2 {
+        let (left, right) = dst_buffer.as_mut_slices().split_at_mut(1);
+        let left = &mut left[0][..num_frames];
+        let right = &mut right[0][..num_frames];
+        for ((frame, l), r) in src_slice.chunks_exact(2).zip(left).zip(right) {
+            *l = frame[0];
+            *r = frame[1];
+        }
+        return;
+    }
+
+    for ch in 0..num_channels.min(dst_buffer.num_channels()) {
+        let dst = &mut dst_buffer.channel_mut(ch)[..num_frames];
+        for (frame, sample) in src_slice.chunks_exact(num_channels).zip(dst) {
+            *sample = frame[ch];
+        }
+    }
+}
+
+/// バッファのサンプルの絶対値の最大値（ピーク）を返します
+///
+/// # 引数
+/// * `buffer` - 対象のバッファ
+///
+/// # リアルタイム安全性
+/// * この関数はメモリ割り当てを行わないためリアルタイム安全です。
+pub fn peak(buffer: &AudioBuffer) -> f32 {
+    buffer
+        .as_slice()
+        .iter()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
 }
 
 #[cfg(test)]
@@ -157,6 +309,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_interleave_and_deinterleave() {
+        for num_channels in [2, 3] {
+            let mut planar_data: Vec<Vec<f32>> = (0..num_channels)
+                .map(|ch| (0..4).map(|i| (ch * 10 + i) as f32).collect())
+                .collect();
+            let mut interleaved_data = vec![0.0; num_channels * 4];
+
+            // プレーナー → インターリーブ
+            {
+                let mut slices: Vec<&mut [f32]> =
+                    planar_data.iter_mut().map(|v| v.as_mut_slice()).collect();
+                let planar = PlanarAudioBuffer::new(&mut slices);
+                let mut interleaved = AudioBuffer::new(num_channels, 4, &mut interleaved_data);
+                interleave(&planar, &mut interleaved);
+            }
+            assert_eq!(interleaved_data[num_channels * 2 + 1], 12.0);
+
+            // インターリーブ → プレーナーで元に戻る
+            let mut restored: Vec<Vec<f32>> = vec![vec![0.0; 4]; num_channels];
+            {
+                let mut slices: Vec<&mut [f32]> =
+                    restored.iter_mut().map(|v| v.as_mut_slice()).collect();
+                let mut planar = PlanarAudioBuffer::new(&mut slices);
+                let interleaved = AudioBuffer::new(num_channels, 4, &mut interleaved_data);
+                deinterleave(&interleaved, &mut planar);
+            }
+            assert_eq!(restored, planar_data);
+        }
+    }
+
+    #[test]
+    fn test_convert_channels() {
+        // ステレオ → モノラルは平均
+        let mut stereo = vec![1.0, 0.0, 0.5, -0.5];
+        let mut mono = vec![0.0; 2];
+        convert_channels(
+            &AudioBuffer::new(2, 2, &mut stereo),
+            &mut AudioBuffer::new(1, 2, &mut mono),
+        );
+        assert_eq!(mono, vec![0.5, 0.0]);
+
+        // モノラル → ステレオは複製
+        convert_channels(
+            &AudioBuffer::new(1, 2, &mut mono),
+            &mut AudioBuffer::new(2, 2, &mut stereo),
+        );
+        assert_eq!(stereo, vec![0.5, 0.5, 0.0, 0.0]);
+
+        // ステレオ → 3ch は足りないチャンネルを 0 に
+        let mut three = vec![9.0; 6];
+        convert_channels(
+            &AudioBuffer::new(2, 2, &mut [1.0, 2.0, 3.0, 4.0]),
+            &mut AudioBuffer::new(3, 2, &mut three),
+        );
+        assert_eq!(three, vec![1.0, 2.0, 0.0, 3.0, 4.0, 0.0]);
+    }
+
     /// 浮動小数点数が許容誤差の範囲内で等しいかを確認する
     fn assert_float_eq(a: f32, b: f32, epsilon: f32) {
         if (a - b).abs() > epsilon {
@@ -0,0 +1,197 @@
+//! 音声ファイルを読み込みます。
+//!
+//! WAV は wav モジュールで読み込みます。`symphonia` フィーチャーが有効な場合は、
+//! symphonia で MP3・FLAC・Ogg Vorbis もデコードできます。
+//! AudioFileLoader を使うと、デコードを共有のバックグラウンドのワーカーで行えます。
+
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use crate::background;
+use crate::wav::{self, WavData};
+
+/// 音声ファイルを読み込む
+///
+/// 拡張子が `wav` のファイルは wav モジュールで、それ以外は symphonia でデコードします。
+///
+/// # 実装時の注意
+/// この関数はファイル IO とメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+pub fn read_audio_file(path: impl AsRef<Path>) -> Result<WavData, String> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("wav") | Some("wave") => wav::read_wav(path),
+        #[cfg(feature = "symphonia")]
+        extension => {
+            let bytes = std::fs::read(path)
+                .map_err(|e| format!("音声ファイルを読み込めませんでした: {:?} ({})", path, e))?;
+            decode_audio(bytes, extension)
+        }
+        #[cfg(not(feature = "symphonia"))]
+        _ => Err(format!(
+            "WAV 以外の音声ファイルを読み込むには symphonia フィーチャーが必要です: {:?}",
+            path
+        )),
+    }
+}
+
+/// メモリ上の音声データを symphonia でデコードする
+///
+/// 最初の音声トラックを全てデコードします。壊れたパケットは読み飛ばします。
+///
+/// # 引数
+/// * `bytes` - ファイルの内容
+/// * `extension` - フォーマットを判定するための拡張子（省略した場合は内容から判定する）
+///
+/// # 実装時の注意
+/// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+#[cfg(feature = "symphonia")]
+pub fn decode_audio(bytes: Vec<u8>, extension: Option<&str>) -> Result<WavData, String> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{CODEC_TYPE_NULL, DecoderOptions};
+    use symphonia::core::errors::Error;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let source = MediaSourceStream::new(Box::new(std::io::Cursor::new(bytes)), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = extension {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("対応していない音声フォーマットです: {}", e))?
+        .format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or("音声トラックが見つかりません")?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("デコーダーを作成できませんでした: {}", e))?;
+
+    let mut wav = WavData {
+        samples: Vec::new(),
+        channels: track
+            .codec_params
+            .channels
+            .map_or(0, |channels| channels.count()),
+        sample_rate: track.codec_params.sample_rate.unwrap_or(0),
+    };
+    let mut sample_buffer: Option<SampleBuffer<f32>> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // ファイルの終わり
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(format!("音声ファイルを読み込めませんでした: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(Error::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("デコードに失敗しました: {}", e)),
+        };
+        let spec = *decoded.spec();
+        let buffer = match sample_buffer.as_mut() {
+            Some(buffer) if buffer.capacity() >= decoded.capacity() * spec.channels.count() => {
+                buffer
+            }
+            _ => sample_buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buffer.copy_interleaved_ref(decoded);
+        wav.samples.extend_from_slice(buffer.samples());
+        wav.channels = spec.channels.count();
+        wav.sample_rate = spec.rate;
+    }
+    Ok(wav)
+}
+
+/// 音声ファイルを共有のバックグラウンドのワーカー（background::shared）で読み込む
+///
+/// 読み込みの完了は所有者のスレッドから try_take で確認します。
+pub struct AudioFileLoader {
+    receiver: Receiver<Result<WavData, String>>,
+}
+
+impl AudioFileLoader {
+    /// 読み込みを開始する
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn spawn(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let (sender, receiver) = mpsc::sync_channel(1);
+        // ワーカーが停止している場合は sender が破棄され、wait がエラーを返す
+        let _ = background::shared().spawn(move || {
+            // 結果を受け取る前に破棄された場合は送れないが、問題はない
+            let _ = sender.send(read_audio_file(&path));
+        });
+        Self { receiver }
+    }
+
+    /// 読み込みが完了していれば結果を取り出す
+    ///
+    /// # 戻り値
+    /// * 読み込み中の場合、または結果を取り出し済みの場合は None
+    pub fn try_take(&self) -> Option<Result<WavData, String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => None,
+        }
+    }
+
+    /// 読み込みの完了を待って結果を取り出す
+    pub fn wait(self) -> Result<WavData, String> {
+        self.receiver
+            .recv()
+            .map_err(|_| "読み込みのジョブが異常終了しました".to_string())?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_wav_in_background() {
+        let path = std::env::temp_dir().join("audio_engine_core_audio_file_test.wav");
+        let wav = WavData {
+            samples: vec![0.0, 0.5, -0.5, 1.0],
+            channels: 2,
+            sample_rate: 48000,
+        };
+        wav::write_wav(&path, &wav).unwrap();
+
+        let loaded = AudioFileLoader::spawn(&path).wait().unwrap();
+        assert_eq!(loaded.samples, wav.samples);
+        assert_eq!(loaded.channels, 2);
+        std::fs::remove_file(&path).unwrap();
+
+        // 存在しないファイルはエラー
+        assert!(read_audio_file(std::env::temp_dir().join("missing.flac")).is_err());
+
+        // symphonia でも同じ内容にデコードされる
+        #[cfg(feature = "symphonia")]
+        {
+            let decoded = decode_audio(wav::encode_wav(&wav), None).unwrap();
+            assert_eq!(decoded.samples, wav.samples);
+            assert_eq!((decoded.channels, decoded.sample_rate), (2, 48000));
+        }
+    }
+}
@@ -1,7 +1,112 @@
-use crate::audio_buffer::AudioBuffer;
+use crate::audio_buffer::{AudioBuffer, BufferLayout, OwnedAudioBuffer, PlanarAudioBuffer};
 use crate::audio_buffer_utils;
+use crate::automation::AutomationLane;
+#[cfg(feature = "std")]
+use crate::background::{self, WorkerHandle};
+use crate::diagnostics::{DiagnosticCode, Diagnostics};
 use crate::directed_graph::DirectedGraph;
-use std::collections::HashMap;
+use crate::dsp::{RateConverter, SmoothedValue};
+use crate::event::EventQueue;
+use crate::garbage::Garbage;
+#[cfg(feature = "std")]
+use crate::garbage::GarbageSender;
+use crate::midi::NoteEvent;
+use crate::nodes::{FeedbackRegion, InputNode, SamplePlayer};
+use crate::offline::OfflineRenderer;
+use crate::parameter::{MacroCurve, ParameterInfo};
+use crate::prelude::*;
+use crate::slot_map::SlotMap;
+use crate::transport::Transport;
+use crate::tuning::Tuning;
+use alloc::sync::Arc;
+use hashbrown::{HashMap, HashSet};
+
+/// `process_f64` などのデフォルト実装で形式を変換する際の、スタック上の一時バッファのサンプル数
+const BRIDGE_SAMPLES: usize = 512;
+
+/// グラフが準備されていない場合に、フリーズのレンダリングで使うブロックサイズ
+const FREEZE_BLOCK_SIZE: usize = 512;
+
+/// プレーナー形式で処理できる最大チャンネル数
+const MAX_PLANAR_CHANNELS: usize = 8;
+
+/// グラフが処理できる最大チャンネル数（ノードは現在 2ch までのサポート）
+const MAX_CHANNELS: usize = 2;
+
+/// マクロの数
+pub const MAX_MACROS: usize = 4;
+
+/// 出力ノードを割り当てられる追加の出力バスの数
+pub const MAX_OUTPUT_BUSES: usize = 8;
+
+/// send_note_event で送り、次の process で処理するまでためておけるノートイベントの最大数
+const MAX_PENDING_NOTE_EVENTS: usize = 1024;
+
+/// send_note_event 1 回あたりに MIDI の接続を通して送れるノートイベントの最大数
+const MAX_ROUTED_NOTE_EVENTS: usize = 256;
+
+/// パラメーターポートのないパラメーターのオートメーションで、値が連続的に変化している間に
+/// パラメーターを更新する間隔（フレーム数）
+const AUTOMATION_RAMP_FRAMES: usize = 32;
+
+/// A/B モーフの位置を変更したときに、新しい位置に達するまでの時間（ミリ秒）
+const MORPH_RAMP_MS: f32 = 20.0;
+
+/// オーディオグラフのノードの ID
+///
+/// add_node が返す値で、ノードを指定する AudioGraph の各メソッドに渡します。
+/// ファイルやネットワーク越しに ID をやり取りする場合は、as_raw と from_raw で整数に変換します。
+///
+/// 削除されたノードのインデックスは次に追加するノードで再利用されますが、そのたびに世代が進むため、
+/// 削除済みのノードの ID を使った操作は新しいノードに作用せず、ノードが存在しない場合と同じく失敗します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId {
+    /// ノードのインデックス
+    index: usize,
+    /// インデックスを再利用した回数
+    generation: usize,
+}
+
+/// 整数の表現で、インデックスに使う下位のビット数（残りの上位のビットを世代に使う）
+const NODE_INDEX_BITS: u32 = usize::BITS / 2;
+
+/// インデックスと世代を取り出すためのマスク
+const NODE_INDEX_MASK: usize = (1 << NODE_INDEX_BITS) - 1;
+
+impl NodeId {
+    /// 整数の表現から ID を作る
+    ///
+    /// as_raw で得た値を渡してください。グラフに存在しない値の場合、ID を使う操作が失敗します。
+    pub fn from_raw(raw: usize) -> Self {
+        Self {
+            index: raw & NODE_INDEX_MASK,
+            generation: raw >> NODE_INDEX_BITS,
+        }
+    }
+
+    /// ID の整数の表現
+    ///
+    /// 世代が 0 の ID（インデックスを再利用していないノード）では、インデックスと同じ値になります。
+    pub fn as_raw(&self) -> usize {
+        (self.generation << NODE_INDEX_BITS) | self.index
+    }
+
+    /// ノードのインデックス（SlotMap のスロットの番号に使う）
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl core::fmt::Display for NodeId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.generation == 0 {
+            write!(f, "{}", self.index)
+        } else {
+            write!(f, "{}（世代 {}）", self.index, self.generation)
+        }
+    }
+}
+
 /// オーディオグラフのノードのインターフェース
 pub trait AudioGraphNode: Send {
     /// ノードを初期化する
@@ -19,491 +124,5744 @@ pub trait AudioGraphNode: Send {
 
     /// ノードの状態をリセットする
     fn reset(&mut self);
-}
-
-/// オーディオグラフの実装
-///
-/// 隣接リストを使用してオーディオノード間の接続を管理します。
-///
-/// このオーディオグラフはリアルタイムのオーディオ処理のためのグラフです。
-/// リアルタイムスレッドのループで process 関数呼び出されます。
-/// ノードやエッジの挿入などの操作を行った場合、リアルタイムに process 関数のバッファー書き込み処理に反映されます。
-pub struct AudioGraph {
-    /// ノードのマップ（IDとノードのペア）
-    nodes: HashMap<usize, Box<dyn AudioGraphNode>>,
-    /// グラフ構造
-    graph: DirectedGraph<usize>,
-    /// 次に割り当てられるノードID
-    next_node_id: usize,
-    /// サンプリングレート
-    sample_rate: f32,
-    /// 最大バッファサイズ
-    max_buffer_size: usize,
-    /// 各ノードの出力バッファのキャッシュ（リアルタイムセーフな処理のため）
-    node_outputs: HashMap<usize, Vec<f32>>,
-    /// 一時的な入力バッファ（リアルタイムセーフな処理のため）
-    tmp_input_buffer: Vec<f32>,
-    /// 処理中のチャンネル数
-    num_channels: usize,
-}
 
-impl AudioGraph {
-    /// 新しいオーディオグラフを作成する
-    pub fn new() -> Self {
-        Self {
-            nodes: HashMap::new(),
-            graph: DirectedGraph::<usize>::new(),
-            next_node_id: 0,
-            sample_rate: 44100.0,
-            max_buffer_size: 0,
-            node_outputs: HashMap::new(),
-            tmp_input_buffer: Vec::new(),
-            num_channels: 2, // 現在、2ch のみのサポート。
+    /// 倍精度でオーディオデータを処理する
+    ///
+    /// デフォルトでは f32 に変換して `process` を呼び出し、結果を f64 に戻します。
+    /// 倍精度で処理できるノードはこの関数をオーバーライドしてください。
+    ///
+    /// # 引数
+    /// * `buffer` - 処理するオーディオバッファ（f64）
+    ///
+    /// # 実装時の注意
+    /// デフォルトの実装はスタック上の固定長バッファを使って小さなブロックに分けて `process` を呼び出すため、
+    /// メモリアロケーションは行いません。
+    fn process_f64(&mut self, buffer: &mut AudioBuffer<f64>) {
+        let num_channels = buffer.num_channels();
+        let num_frames = buffer.num_frames();
+        debug_assert!(
+            num_channels <= BRIDGE_SAMPLES,
+            "チャンネル数が多すぎます。num_channels: {}",
+            num_channels
+        );
+        let frames_per_chunk = (BRIDGE_SAMPLES / num_channels.max(1)).max(1);
+        let mut tmp = [0.0f32; BRIDGE_SAMPLES];
+        let mut start = 0;
+        while start < num_frames {
+            let chunk_frames = frames_per_chunk.min(num_frames - start);
+            let len = chunk_frames * num_channels;
+            let samples = &mut buffer.as_mut_slice()[start * num_channels..][..len];
+            for (dst, src) in tmp.iter_mut().zip(samples.iter()) {
+                *dst = *src as f32;
+            }
+            self.process(&mut AudioBuffer::new(
+                num_channels,
+                chunk_frames,
+                &mut tmp[..len],
+            ));
+            for (dst, src) in samples.iter_mut().zip(tmp.iter()) {
+                *dst = *src as f64;
+            }
+            start += chunk_frames;
         }
     }
 
-    /// オーディオグラフのパラメータを更新する
+    /// ノードが処理に使いたいバッファの配置
+    ///
+    /// `BufferLayout::Planar` を返すノードには、グラフが `process_planar` でチャンネルごとに分かれたバッファを渡します。
+    /// デフォルトは `BufferLayout::Interleaved` です。
+    fn preferred_layout(&self) -> BufferLayout {
+        BufferLayout::Interleaved
+    }
+
+    /// チャンネルごとに分かれたバッファでオーディオデータを処理する
+    ///
+    /// デフォルトではインターリーブに変換して `process` を呼び出します。
+    /// `preferred_layout` で `BufferLayout::Planar` を返すノードはこの関数をオーバーライドしてください。
+    /// 追加の入力ポートの信号は受け取れません。
     ///
     /// # 引数
-    /// * `sample_rate` - サンプリングレート（Hz）
-    /// * `max_buffer_size` - 最大バッファサイズ
+    /// * `buffer` - 処理するオーディオバッファ（プレーナー）
     ///
     /// # 実装時の注意
-    /// この関数はサンプルレートやバッファーサイズ変更時に一度だけ、メインスレッドなどの非リアルタイムスレッドから呼び出されます。
-    pub fn prepare(&mut self, sample_rate: f32, max_buffer_size: usize) {
-        self.sample_rate = sample_rate;
-        self.max_buffer_size = max_buffer_size;
-
-        // ノード出力バッファを事前に確保
-        self.node_outputs.clear();
-        // グラフ内の全ノードIDを取得
-        for &node_id in self
-            .graph
-            .node_ids()
-            .copied()
-            .collect::<Vec<_>>()
-            .as_slice()
-        {
-            self.node_outputs
-                .insert(node_id, vec![0.0; self.num_channels * max_buffer_size]);
+    /// デフォルトの実装はスタック上の固定長バッファを使って小さなブロックに分けて `process` を呼び出すため、
+    /// メモリアロケーションは行いません。
+    fn process_planar(&mut self, buffer: &mut PlanarAudioBuffer) {
+        let num_channels = buffer.num_channels();
+        let num_frames = buffer.num_frames();
+        debug_assert!(
+            num_channels <= BRIDGE_SAMPLES,
+            "チャンネル数が多すぎます。num_channels: {}",
+            num_channels
+        );
+        let frames_per_chunk = (BRIDGE_SAMPLES / num_channels.max(1)).max(1);
+        let mut tmp = [0.0f32; BRIDGE_SAMPLES];
+        let mut start = 0;
+        while start < num_frames {
+            let chunk_frames = frames_per_chunk.min(num_frames - start);
+            let len = chunk_frames * num_channels;
+            for ch in 0..num_channels {
+                let src = &buffer.channel(ch)[start..start + chunk_frames];
+                for (frame, &sample) in tmp[..len].chunks_exact_mut(num_channels).zip(src) {
+                    frame[ch] = sample;
+                }
+            }
+            self.process(&mut AudioBuffer::new(
+                num_channels,
+                chunk_frames,
+                &mut tmp[..len],
+            ));
+            for ch in 0..num_channels {
+                let dst = &mut buffer.channel_mut(ch)[start..start + chunk_frames];
+                for (frame, sample) in tmp[..len].chunks_exact(num_channels).zip(dst) {
+                    *sample = frame[ch];
+                }
+            }
+            start += chunk_frames;
         }
+    }
 
-        // 一時入力バッファを事前に確保
-        self.tmp_input_buffer = vec![0.0; self.num_channels * max_buffer_size];
+    /// ノートイベントを受け取る
+    ///
+    /// デフォルトではイベントを無視します。シンセサイザーなど、ノートイベントで駆動されるノードが実装します。
+    ///
+    /// # 引数
+    /// * `event` - ノートイベント。`timing` は次の process 呼び出しのブロック先頭からのオフセット。
+    ///
+    /// # 実装時の注意
+    /// この関数はリアルタイムスレッドから呼び出される可能性があるため、メモリアロケーションを行わないでください。
+    fn handle_note_event(&mut self, _event: &NoteEvent) {}
 
-        // 各ノードを準備
-        for node in self.nodes.values_mut() {
-            node.prepare(sample_rate, max_buffer_size);
-        }
+    /// ノートイベントを受け取り、MIDI の送り先に送るノートイベントを出力する
+    ///
+    /// AudioGraph::add_midi_route で MIDI の送り先を接続したノードには、handle_note_event の代わりに
+    /// この関数でノートイベントが渡され、`output` に渡したイベントが送り先のノードに送られます。
+    /// デフォルトでは handle_note_event を呼び出し、受け取ったイベントをそのまま出力します（MIDI スルー）。
+    /// 和音の生成など、ノートイベントを変換する MIDI エフェクトのノードが実装します。
+    ///
+    /// # 実装時の注意
+    /// この関数はリアルタイムスレッドから呼び出される可能性があるため、メモリアロケーションを行わないでください。
+    fn process_note_event(&mut self, event: &NoteEvent, output: &mut dyn FnMut(NoteEvent)) {
+        self.handle_note_event(event);
+        output(*event);
     }
 
-    /// ノードをグラフに追加する
+    /// テンポや再生位置を受け取る
+    ///
+    /// ブロックごとに、process の前に呼び出されます。`transport.position_beats` はブロックの先頭の位置です。
+    /// デフォルトでは何もしません。テンポに同期する LFO など、トランスポートに従うノードが実装します。
+    ///
+    /// # 実装時の注意
+    /// この関数はリアルタイムスレッドから呼び出されるため、メモリアロケーションを行わないでください。
+    fn update_transport(&mut self, _transport: &Transport) {}
+
+    /// ブロックごとに、このノードが送り出すノートイベントを出力する
+    ///
+    /// update_transport の後、process の前に呼び出され、`output` に渡したイベントが
+    /// AudioGraph::add_midi_route で接続した送り先のノードに送られます。MIDI の送り先がないノードでは呼び出されません。
+    /// デフォルトでは何も出力しません。MIDI ファイルの再生など、自らノートイベントを生成するノードが実装します。
     ///
     /// # 引数
-    /// * `node` - 追加するノード
+    /// * `num_frames` - 処理するブロックのフレーム数
+    /// * `output` - 送り出すノートイベントを受け取る関数。`timing` はブロックの先頭からのオフセット。
     ///
-    /// # 戻り値
-    /// * 追加されたノードのID
+    /// # 実装時の注意
+    /// この関数はリアルタイムスレッドから呼び出されるため、メモリアロケーションを行わないでください。
+    fn generate_note_events(&mut self, _num_frames: usize, _output: &mut dyn FnMut(NoteEvent)) {}
+
+    /// ノート番号から周波数への変換に使う調律を受け取る
+    ///
+    /// グラフに追加されたときと、AudioGraph::set_tuning で調律が変更されたときに呼び出されます。
+    /// デフォルトでは何もしません。シンセサイザーなど、ノート番号から音の高さを決めるノードが実装します。
     ///
     /// # 実装時の注意
-    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
-    pub fn add_node(&mut self, mut node: Box<dyn AudioGraphNode>) -> usize {
-        let node_id = self.next_node_id;
-        self.next_node_id += 1;
+    /// この関数はリアルタイムスレッドから呼び出されるため、メモリアロケーションを行わないでください。
+    fn set_tuning(&mut self, _tuning: &Tuning) {}
 
-        // ノードにグラフIDを割り当て
-        self.graph.add_node(node_id);
+    /// 入力ポートの数（メイン入力を含む）
+    ///
+    /// デフォルトはメイン入力のみの 1 です。2 以上を返すノードは、`add_edge_to_port` で
+    /// 追加の入力ポートに接続された信号を `process_with_inputs` で受け取れます。
+    fn num_input_ports(&self) -> usize {
+        1
+    }
 
-        // ノードを初期化
-        node.prepare(self.sample_rate, self.max_buffer_size);
+    /// 追加の入力ポート付きでオーディオデータを処理する
+    ///
+    /// デフォルトでは追加の入力ポートを無視して `process` を呼び出します。
+    ///
+    /// # 引数
+    /// * `buffer` - 処理するオーディオバッファ（メイン入力ポートの信号が入っている）
+    /// * `inputs` - 追加の入力ポートの信号
+    fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, _inputs: &InputPorts) {
+        self.process(buffer);
+    }
 
-        // ノードをノードマップに追加
-        self.nodes.insert(node_id, node);
+    /// 出力ポートの数（メイン出力を含む）
+    ///
+    /// デフォルトはメイン出力のみの 1 です。2 以上を返すノードは、`process_with_outputs` で
+    /// 追加の出力ポートに書き込んだ信号を、`add_edge_from_port` で他のノードに接続できます。
+    fn num_output_ports(&self) -> usize {
+        1
+    }
 
-        // ノード出力バッファをあらかじめ確保
-        if !self.node_outputs.is_empty() {
-            self.node_outputs
-                .insert(node_id, vec![0.0; self.num_channels * self.max_buffer_size]);
-        }
+    /// 追加の入力ポートと出力ポート付きでオーディオデータを処理する
+    ///
+    /// デフォルトでは追加の出力ポートに書き込まずに `process_with_inputs` を呼び出します。
+    /// 追加の出力ポートのバッファは、呼び出し前に 0 でクリアされます。
+    ///
+    /// # 引数
+    /// * `buffer` - 処理するオーディオバッファ（メイン入力ポートの信号が入っており、メイン出力を書き込む）
+    /// * `inputs` - 追加の入力ポートの信号
+    /// * `outputs` - 追加の出力ポートのバッファ
+    fn process_with_outputs(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        inputs: &InputPorts,
+        _outputs: &mut OutputPorts,
+    ) {
+        self.process_with_inputs(buffer, inputs);
+    }
 
-        node_id
+    /// パラメーターをオーディオレートで変化させる入力ポート（パラメーターポート）の番号
+    ///
+    /// AudioGraph::add_parameter_edge でパラメーターポートに接続された信号は、`process_with_inputs` で
+    /// 受け取れます。ノードは接続がある間、パラメーターの値に信号の値をサンプルごとに加算して使います。
+    /// デフォルトは `None` で、パラメーターポートを持ちません。
+    ///
+    /// # 引数
+    /// * `parameter_id` - パラメーター ID
+    ///
+    /// # 戻り値
+    /// * 入力ポート番号（1 以上、`num_input_ports` 未満）。パラメーターポートがない場合は `None`
+    fn parameter_port(&self, _parameter_id: &str) -> Option<usize> {
+        None
     }
 
-    /// エッジ（接続）をグラフに追加する
+    /// ノードが公開するパラメーターの一覧
+    ///
+    /// デフォルトではパラメーターを公開しません。
+    fn parameters(&self) -> &[ParameterInfo] {
+        &[]
+    }
+
+    /// ID を指定してパラメーターの値を設定する
     ///
     /// # 引数
-    /// * `from_id` - 接続元ノードのID
-    /// * `to_id` - 接続先ノードのID
+    /// * `id` - `parameters` で公開しているパラメーターの ID
+    /// * `value` - 設定する値
     ///
     /// # 戻り値
-    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    /// * パラメーターが存在する場合は `true`、存在しない場合は `false`
     ///
     /// # 実装時の注意
-    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
-    pub fn add_edge(&mut self, from_id: usize, to_id: usize) -> Result<(), String> {
-        // DirectedGraphにエッジを追加（サイクルチェックなどもここで行われる）
-        self.graph.add_edge(from_id, to_id)
+    /// この関数はリアルタイムスレッドから呼び出される可能性があるため、メモリアロケーションを行わないでください。
+    fn set_parameter(&mut self, _id: &str, _value: f32) -> bool {
+        false
     }
 
-    /// ノードを取得する
+    /// ID を指定してパラメーターの値を取得する
+    ///
+    /// # 戻り値
+    /// * パラメーターが存在する場合は `Some` で値を返し、存在しない場合は `None` を返す
+    fn get_parameter(&self, _id: &str) -> Option<f32> {
+        None
+    }
+
+    /// モジュレーションソースとしての現在の出力値
+    ///
+    /// エンベロープフォロワーなど、他のノードのパラメーターを変調するノードが `Some` を返します。
+    /// デフォルトは `None` で、モジュレーションソースとして使えません。
+    fn modulation_output(&self) -> Option<f32> {
+        None
+    }
+
+    /// ノードを処理する頻度
+    ///
+    /// `ProcessRate::Block` や `ProcessRate::Every` を返すノードは、グラフが process の代わりに
+    /// `process_control` を呼び出します。デフォルトは `ProcessRate::Audio` です。
+    fn process_rate(&self) -> ProcessRate {
+        ProcessRate::Audio
+    }
+
+    /// コントロールレートでデータを処理する
+    ///
+    /// グラフは前回の戻り値から今回の戻り値まで直線で補間した信号を、ノードの出力として全チャンネルに書き込みます。
+    /// そのため、コントロール信号をオーディオレートの入力ポートに接続しても段差が生じません。
     ///
     /// # 引数
-    /// * `node_id` - 取得するノードのID
+    /// * `input` - 前回の呼び出しから今回までの入力（`process_rate` で指定した間隔のサンプル数）
     ///
     /// # 戻り値
-    /// * ノードが存在する場合は `Some` でBoxに包まれた参照を返し、存在しない場合は `None` を返す
+    /// * 入力の区間の終わりでの出力値
     ///
     /// # 実装時の注意
-    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
-    pub fn get_node(&self, node_id: usize) -> Option<&Box<dyn AudioGraphNode>> {
-        self.nodes.get(&node_id)
+    /// この関数はリアルタイムスレッドから呼び出されるため、メモリアロケーションを行わないでください。
+    fn process_control(&mut self, _input: &AudioBuffer) -> f32 {
+        0.0
     }
 
-    /// グラフを処理する（トポロジカルソートに基づいて各ノードを処理）
+    /// ノードの処理による遅延（サンプル数）
     ///
-    /// # 引数
-    /// * `buffer` - 処理するオーディオバッファ
+    /// ルックアヘッドを持つノードなど、出力が入力より遅れるノードが 0 以外を返します。
+    /// `WetDry` などはこの値を使ってドライ信号の遅延をそろえます。
+    fn latency_samples(&self) -> usize {
+        0
+    }
+
+    /// ノードを内部で処理するサンプリングレートの、グラフのサンプリングレートに対する倍率
+    ///
+    /// 2.0 を返すノードは 2 倍のオーバーサンプリングで、0.25 を返すノードは 1/4 のレートで処理されます。
+    /// 1.0 以外の場合、グラフが入出力にレート変換器を挿入し、ノードを内部のレートで prepare します。
+    /// 追加の入力ポートの信号は受け取れません。デフォルトは 1.0 です。
     ///
     /// # 実装時の注意
-    /// この関数はリアルタイムスレッドから呼び出されることを想定しています。
-    /// 実装者はメモリアロケーションなどの遅延を生む処理を行わないように注意してください。
-    pub fn process(
-        &mut self,
-        buffer: &mut AudioBuffer,
-        input_node_id: usize,
-        output_node_id: usize,
-    ) {
-        let num_channels = buffer.num_channels();
-        debug_assert!(
-            num_channels > 0,
-            "チャンネル数が不正です。チャンネル数は1以上である必要があります。"
-        );
+    /// 値は prepare の前に読み出されるため、グラフに追加した後に変えた場合は AudioGraph::prepare を呼び出してください。
+    fn rate_factor(&self) -> f32 {
+        1.0
+    }
 
-        // 処理中のチャンネル数が変わった場合のハンドリングは未実装。
-        debug_assert!(
-            num_channels == self.num_channels,
-            "チャンネル数が変わっています。現在 2ch のみのサポート。"
-        );
+    /// 入力が無音になってから出力が収まるまでのサンプル数（テール）
+    ///
+    /// ディレイのフィードバックなど、入力が止まった後も音を出し続けるエフェクトが 0 以外を返します。
+    /// AudioGraph::tail_samples で集計し、プラグインやオフラインレンダリングで処理を続ける長さに使います。
+    fn tail_samples(&self) -> usize {
+        0
+    }
 
-        let buffer_size = buffer.num_frames();
-        debug_assert!(
-            buffer_size <= self.max_buffer_size,
-            "process 関数に渡されたバッファーが prepare 関数で指定された最大バッファーサイズを超えています。"
-        );
+    /// ノードがグラフに追加されたときに呼び出される
+    ///
+    /// 共有バッファの登録や、バックグラウンドのジョブの開始など、prepare とは別に一度だけ行う準備に使います。
+    /// デフォルトでは何もしません。
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドから呼び出されるため、メモリアロケーションを行っても構いません。
+    fn on_added(&mut self, _context: &NodeContext) {}
 
-        debug_assert!(
-            self.nodes.contains_key(&input_node_id),
-            "input_node_id が見つかりません。input_node_id: {}",
-            input_node_id
-        );
+    /// ノードがグラフから取り除かれたときに呼び出される
+    ///
+    /// remove_node や freeze でグラフから取り除かれるとき、グラフが破棄されるときに呼び出されます。
+    /// on_added で行った準備の後片付けに使います。デフォルトでは何もしません。
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドから呼び出されるため、メモリアロケーションを行っても構いません。
+    fn on_removed(&mut self, _context: &NodeContext) {}
+}
 
-        debug_assert!(
-            self.nodes.contains_key(&output_node_id),
-            "output_node_id が見つかりません。output_node_id: {}",
-            output_node_id
-        );
+/// ライフサイクルのフック（on_added / on_removed）に渡す、グラフの情報
+pub struct NodeContext {
+    /// ノードのID
+    pub node_id: NodeId,
+    /// グラフのサンプリングレート
+    pub sample_rate: f32,
+    /// グラフの最大バッファサイズ（prepare の前は 0）
+    pub max_buffer_size: usize,
+    /// アプリケーションにイベントを通知するキュー（設定されていない場合は None）
+    pub events: Option<Arc<EventQueue>>,
+}
 
-        let graph = self.graph.get_real_time_safe_interface();
+impl NodeContext {
+    /// 重い処理を送るための、共有のバックグラウンドのワーカー
+    #[cfg(feature = "std")]
+    pub fn worker(&self) -> WorkerHandle {
+        background::shared()
+    }
+}
 
-        // 各ノードのバッファをクリア
-        audio_buffer_utils::clear_buffer(buffer);
+/// モジュレーションの接続（ソースノードの出力値でターゲットノードのパラメーターを変調する）
+pub(crate) struct ModulationRoute {
+    /// モジュレーションソースのノードID
+    source_id: NodeId,
+    /// ターゲットのノードID
+    target_id: NodeId,
+    /// ターゲットのパラメーター ID
+    parameter_id: String,
+    /// 変調前のパラメーターの値
+    base_value: f32,
+    /// 変調量（ソースの出力値に掛ける係数）
+    amount: f32,
+}
 
-        // オーディオ処理では入力から出力への順序で処理するため、トポロジカル順序を反転
-        let processing_order = graph.get_reverse_topological_order();
+/// A/B モーフで補間するパラメーター
+pub(crate) struct MorphRoute {
+    /// 対象のノードID
+    node_id: NodeId,
+    /// パラメーター ID
+    parameter_id: String,
+    /// 位置 0 での値
+    a: f32,
+    /// 位置 1 での値
+    b: f32,
+}
 
-        // 入力ノードから出力ノードへの順序でノードを処理
-        for &node_id in processing_order {
-            // このノードへの入力エッジを持つノードを検索
-            let input_node_ids = graph.get_input_node_ids(node_id);
+/// パラメーターに割り当てたオートメーション
+pub(crate) struct AutomationRoute {
+    /// 対象のノードID
+    node_id: NodeId,
+    /// パラメーター ID
+    parameter_id: String,
+    /// サンプルごとの値を入れるパラメーターポート。ない場合は `None`
+    port: Option<usize>,
+    /// ブレークポイントの列
+    lane: AutomationLane,
+}
 
-            // 一時入力バッファをクリア
-            let mut tmp_input_buffer =
-                AudioBuffer::new(num_channels, buffer_size, &mut self.tmp_input_buffer);
-            audio_buffer_utils::clear_buffer(&mut tmp_input_buffer);
+/// ゲインステージングの分析で記録した、ノードごとのレベル
+///
+/// レベルは分析を有効にしてから（または reset_gain_staging から）の最大値です。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GainStage {
+    /// ノードのID
+    pub node_id: NodeId,
+    /// メイン入力（ポート 0）のピーク（リニア）
+    pub input_peak: f32,
+    /// 出力のピーク（リニア）
+    pub output_peak: f32,
+    /// 出力のピークが 1.0（0 dBFS）を超えたブロック数
+    pub clipped_blocks: usize,
+    /// 記録したブロック数
+    pub blocks: usize,
+}
 
-            // 入力ノードからの出力を合計して一時入力バッファに格納
-            for &input_id in input_node_ids {
-                if let Some(mut input_buffer) = self.node_outputs.get_mut(&input_id) {
-                    let input_buffer =
-                        AudioBuffer::new(num_channels, buffer_size, &mut input_buffer);
-                    // 各チャンネル、各サンプルを加算
-                    audio_buffer_utils::add_buffer(&input_buffer, &mut tmp_input_buffer);
-                } else {
-                    debug_assert!(
-                        false,
-                        "ノードの出力バッファが見つかりません。input_id: {}",
-                        input_id
-                    );
-                }
-            }
+impl GainStage {
+    fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+            input_peak: 0.0,
+            output_peak: 0.0,
+            clipped_blocks: 0,
+            blocks: 0,
+        }
+    }
 
-            // 入力ノードの場合、外部入力バッファからデータをコピー
-            if node_id == input_node_id {
-                audio_buffer_utils::copy_buffer(buffer, &mut tmp_input_buffer);
-            }
+    /// 出力が 0 dBFS を超えたかどうか
+    pub fn clips(&self) -> bool {
+        self.clipped_blocks > 0
+    }
 
-            // 現在のノードの出力バッファへの参照を取得
-            let mut node_output = match self.node_outputs.get_mut(&node_id) {
-                Some(output) => output,
-                None => {
-                    debug_assert!(
-                        false,
-                        "ノードの出力バッファが見つかりません。node_id: {}",
-                        node_id
-                    );
-                    continue;
-                }
-            };
+    /// 入力に対する出力のゲイン（dB）
+    ///
+    /// # 戻り値
+    /// * 入力と出力がどちらも無音でない場合は `Some` で値を返し、そうでない場合は `None` を返す
+    pub fn gain_db(&self) -> Option<f32> {
+        (self.input_peak > 0.0 && self.output_peak > 0.0)
+            .then(|| 20.0 * (self.output_peak / self.input_peak).log10())
+    }
+}
 
-            // 現在のノードの処理を呼び出し
-            if let Some(node) = self.nodes.get_mut(&node_id) {
-                node.process(&mut tmp_input_buffer);
-            } else {
-                debug_assert!(false, "ノードが見つかりません。node_id: {}", node_id);
-            }
+/// ノードを処理する頻度
+///
+/// LFO やエンベロープフォロワーなど、サンプルごとに計算する必要のないノードは、
+/// コントロールレートで処理することで CPU の負荷を減らせます。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessRate {
+    /// サンプルごとに process で処理する（デフォルト）
+    Audio,
+    /// ブロックごとに 1 回だけ process_control で処理する
+    Block,
+    /// 指定したサンプル数ごとに process_control で処理する
+    Every(usize),
+}
 
-            // 処理結果をノードの出力バッファにコピー
-            audio_buffer_utils::copy_buffer(
-                &tmp_input_buffer,
-                &mut AudioBuffer::new(num_channels, buffer_size, &mut node_output),
-            );
-        }
+/// ノードに複数のエッジが接続されている場合の、入力ポートごとの入力の合成方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummingMode {
+    /// 全ての入力を加算する（デフォルト）
+    Sum,
+    /// 全ての入力を加算し、入力のエッジの数 N で割る（1/N で正規化する）
+    Average,
+    /// サンプルごとに、絶対値が最大の入力を選ぶ
+    Max,
+}
+
+impl MixPolicy for SummingMode {
+    fn mix(
+        &mut self,
+        _port: usize,
+        _source_id: NodeId,
+        input: &AudioBuffer,
+        gain: f32,
+        output: &mut AudioBuffer,
+    ) {
+        match self {
+            SummingMode::Sum | SummingMode::Average => {
+                audio_buffer_utils::add_buffer_with_gain(input, output, gain)
+            }
+            SummingMode::Max => audio_buffer_utils::max_buffer_with_gain(input, output, gain),
+        }
+    }
+
+    fn finish(&mut self, _port: usize, num_inputs: usize, output: &mut AudioBuffer) {
+        if *self == SummingMode::Average && num_inputs > 1 {
+            let scale = 1.0 / num_inputs as f32;
+            for sample in output.as_mut_slice() {
+                *sample *= scale;
+            }
+        }
+    }
+}
+
+/// ノードの入力を合成する方法
+///
+/// AudioGraph::set_mix_policy でノードに設定すると、そのノードの入力ポートごとに、接続したエッジの入力を
+/// この方法で合成します。優先度による選択や、制御信号によるクロスフェードなどを独自に実装できます。
+/// 設定のないノードは SummingMode で合成します。
+///
+/// # 実装時の注意
+/// どちらの関数もリアルタイムスレッドから呼び出されるため、メモリアロケーションを行わないでください。
+pub trait MixPolicy: Send {
+    /// 1 本のエッジの入力を、入力ポートの入力に合成する
+    ///
+    /// 入力ポートの入力は、ブロックごとに 0 でクリアしてから、接続したエッジごとに呼び出されます。
+    /// エッジの順序は決まっていないため、入力を区別する場合は `source_id` を使ってください。
+    ///
+    /// # 引数
+    /// * `port` - 入力ポート番号（0 はメイン入力）
+    /// * `source_id` - エッジの接続元ノードのID
+    /// * `input` - 接続元ノードの出力
+    /// * `gain` - エッジのゲイン
+    /// * `output` - 入力ポートの入力
+    fn mix(
+        &mut self,
+        port: usize,
+        source_id: NodeId,
+        input: &AudioBuffer,
+        gain: f32,
+        output: &mut AudioBuffer,
+    );
+
+    /// 全てのエッジの入力を合成した後に、入力ポートごとに呼び出される
+    ///
+    /// デフォルトでは何もしません。
+    ///
+    /// # 引数
+    /// * `port` - 入力ポート番号（0 はメイン入力）
+    /// * `num_inputs` - この入力ポートに合成したエッジの数
+    /// * `output` - 入力ポートの入力
+    fn finish(&mut self, _port: usize, _num_inputs: usize, _output: &mut AudioBuffer) {}
+}
+
+/// エッジ 1 本分の接続先ポートとゲイン
+///
+/// AudioGraph::edge_taps で取得し、AudioGraph::restore_edge で同じ接続を作り直せます。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeTap {
+    /// 接続元ノードの出力ポート番号（0 はメイン出力）
+    pub from_port: usize,
+    /// 接続先ノードの入力ポート番号（0 はメイン入力）
+    pub port: usize,
+    /// 接続元ノードの出力に掛けるゲイン
+    pub gain: f32,
+    /// ゲインを掛けた出力に加算するオフセット
+    pub offset: f32,
+}
+
+impl EdgeTap {
+    /// ポート 0 にゲイン 1 で接続したエッジの接続
+    pub const DEFAULT: EdgeTap = EdgeTap {
+        from_port: 0,
+        port: 0,
+        gain: 1.0,
+        offset: 0.0,
+    };
+}
+
+/// 接続先ノードから見た、入力のエッジ 1 本分の接続
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct InputTap {
+    /// 接続元ノードのID
+    from_id: NodeId,
+    /// 接続先ポートとゲイン
+    tap: EdgeTap,
+}
+
+/// フリーズしたサブグラフ（unfreeze で元に戻すために保存する）
+pub(crate) struct FrozenSubgraph {
+    /// ノード（元のID, ノード）
+    nodes: Vec<(NodeId, Box<dyn AudioGraphNode>)>,
+    /// サブグラフ内の接続と、サブグラフから外への接続（接続元ID, 接続先ID, 並列のエッジごとの接続）
+    edges: Vec<(NodeId, NodeId, Vec<EdgeTap>)>,
+    /// サブグラフ内のフィードバックのエッジ（接続元ID, 接続先ID, 接続）
+    feedback_edges: Vec<(NodeId, NodeId, EdgeTap)>,
+}
+
+/// マクロからノードのパラメーターへの割り当て
+pub(crate) struct MacroMapping {
+    /// マクロの番号
+    macro_index: usize,
+    /// 対象のノードID
+    node_id: NodeId,
+    /// パラメーター ID
+    parameter_id: String,
+    /// マクロの値が 0 のときの値
+    min: f32,
+    /// マクロの値が 1 のときの値
+    max: f32,
+    /// マクロの値からパラメーターの値への変換
+    curve: MacroCurve,
+}
+
+/// 接続元ノードの出力を 1 ブロック遅れて接続先ノードに入力するフィードバックのエッジ
+struct FeedbackEdge {
+    /// 接続元ノードID
+    from_id: NodeId,
+    /// 接続先ノードID
+    to_id: NodeId,
+    /// 接続先ポートとゲイン
+    tap: EdgeTap,
+    /// 接続元ノードの前のブロックの出力
+    buffer: OwnedAudioBuffer,
+}
+
+/// 出力ノードを割り当てた追加の出力バス
+struct OutputBus {
+    /// 割り当てたノードID
+    node_id: Option<NodeId>,
+    /// 最後の process でノードから書き込まれた出力
+    buffer: OwnedAudioBuffer,
+}
+
+/// ノードの追加の入力ポート（ポート番号 1 以降）の信号
+///
+/// ポート 0 はメイン入力で、`process_with_inputs` の `buffer` として渡されます。
+pub struct InputPorts<'a> {
+    /// ポートごとのバッファ（インターリーブ）。インデックス 0 がポート 1 に対応する。
+    buffers: &'a [Vec<f32>],
+    /// ポートごとの接続の有無。インデックス 0 がポート 1 に対応する。
+    connected: &'a [bool],
+    /// チャンネル数
+    num_channels: usize,
+    /// フレーム数
+    num_frames: usize,
+}
+
+impl<'a> InputPorts<'a> {
+    /// 新しい InputPorts を作成する
+    ///
+    /// `buffers` と `connected` のインデックス 0 がポート 1 に対応します。
+    /// これはヒープアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn new(
+        buffers: &'a [Vec<f32>],
+        connected: &'a [bool],
+        num_channels: usize,
+        num_frames: usize,
+    ) -> Self {
+        Self {
+            buffers,
+            connected,
+            num_channels,
+            num_frames,
+        }
+    }
+
+    /// 追加の入力ポートがない InputPorts を作成する
+    pub fn empty() -> Self {
+        Self::new(&[], &[], 0, 0)
+    }
+
+    /// 指定されたポートの信号を取得する
+    ///
+    /// # 引数
+    /// * `port` - ポート番号（1 以上）
+    ///
+    /// # 戻り値
+    /// * インターリーブされたサンプル列。ポートが存在しない場合は `None`。
+    pub fn get(&self, port: usize) -> Option<&[f32]> {
+        if port == 0 {
+            return None;
+        }
+        let len = self.num_channels * self.num_frames;
+        self.buffers
+            .get(port - 1)
+            .and_then(|buffer| buffer.get(..len))
+    }
+
+    /// 指定されたポートに接続があるかどうか
+    pub fn is_connected(&self, port: usize) -> bool {
+        port > 0 && self.connected.get(port - 1).copied().unwrap_or(false)
+    }
+
+    /// パラメーターポートの信号を、フレームごとに最初のチャンネルの値で取得する
+    ///
+    /// # 戻り値
+    /// * ポートに接続がない場合は `None`
+    pub fn parameter_signal(&self, port: usize) -> Option<impl Iterator<Item = f32> + '_> {
+        if !self.is_connected(port) {
+            return None;
+        }
+        let signal = self.get(port)?;
+        Some(
+            signal
+                .chunks_exact(self.num_channels.max(1))
+                .map(|frame| frame[0]),
+        )
+    }
+
+    /// チャンネル数
+    pub fn num_channels(&self) -> usize {
+        self.num_channels
+    }
+
+    /// フレーム数
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+}
+
+/// ノードの追加の出力ポート（ポート番号 1 以降）のバッファ
+///
+/// ポート 0 はメイン出力で、`process_with_outputs` の `buffer` に書き込みます。
+pub struct OutputPorts<'a> {
+    /// ポートごとのバッファ（インターリーブ）。インデックス 0 がポート 1 に対応する。
+    buffers: &'a mut [OwnedAudioBuffer],
+}
+
+impl<'a> OutputPorts<'a> {
+    /// 新しい OutputPorts を作成する
+    ///
+    /// `buffers` のインデックス 0 がポート 1 に対応します。
+    /// これはヒープアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn new(buffers: &'a mut [OwnedAudioBuffer]) -> Self {
+        Self { buffers }
+    }
+
+    /// 追加の出力ポートがない OutputPorts を作成する
+    pub fn empty() -> Self {
+        Self::new(&mut [])
+    }
+
+    /// 指定されたポートのバッファを取得する
+    ///
+    /// # 引数
+    /// * `port` - ポート番号（1 以上）
+    ///
+    /// # 戻り値
+    /// * インターリーブされたサンプル列。ポートが存在しない場合は `None`。
+    pub fn get_mut(&mut self, port: usize) -> Option<&mut [f32]> {
+        if port == 0 {
+            return None;
+        }
+        self.buffers
+            .get_mut(port - 1)
+            .map(|buffer| buffer.as_mut_slice())
+    }
+
+    /// 全ての追加の出力ポートのバッファを、ポート番号の順に取得する
+    ///
+    /// 複数のポートにサンプルごとに書き込むノードは、これで同時に借用します。
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+        self.buffers.iter_mut().map(|buffer| buffer.as_mut_slice())
+    }
+}
+
+/// 固定容量モードのグラフの大きさの上限
+///
+/// AudioGraph::with_capacity に渡すと、この上限までのメモリを作成時と prepare であらかじめ確保します。
+/// 上限を超えない限り、prepare の後のノードの追加（try_add_node）・削除とエッジの追加・削除は
+/// メモリアロケーションを行わないため、組み込み機器などでオーディオスレッドからグラフを変更できます。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphCapacity {
+    /// ノード数の上限（入力ノードと出力ノードを含む）
+    pub max_nodes: usize,
+    /// 1 つのノードから出る、または 1 つのノードに入るエッジの数の上限
+    pub max_edges_per_node: usize,
+    /// 1 つのノードの入力ポート数の上限（メイン入力を含む）
+    pub max_input_ports: usize,
+}
+
+/// グラフの大きさと処理量の統計（AudioGraph::stats）
+///
+/// 自動生成した大きなパッチを最適化する際に、ノードやエッジの数と、バッファのメモリや 1 ブロックあたりの
+/// コピーの量を比べるために使います。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GraphStats {
+    /// ノード数（入力ノードと出力ノードを含む）
+    pub num_nodes: usize,
+    /// エッジ数（並列のエッジは 1 本ずつ数え、フィードバックのエッジは含まない）
+    pub num_edges: usize,
+    /// フィードバックのエッジ数
+    pub num_feedback_edges: usize,
+    /// 最も長い経路のエッジ数（フィードバックのエッジは含まない）
+    pub longest_path: usize,
+    /// グラフが確保したオーディオバッファのバイト数の見積もり（ノードの内部の状態は含まない）
+    pub buffer_bytes: usize,
+    /// 最大バッファサイズの 1 ブロックの処理で、バッファ間でコピー・合成するサンプル数の理論値
+    pub samples_copied_per_block: usize,
+}
+
+/// オーディオスレッドが参照しているグラフの状態のスナップショット（AudioGraph::debug_snapshot）
+///
+/// 処理の順序、各ノードの入力のエッジ、バッファの大きさ、パラメーターの値を、process が実際に使う
+/// キャッシュから写したものです。編集用の状態とオーディオスレッドの状態の食い違いを調べるために使います。
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphSnapshot {
+    /// サンプリングレート
+    pub sample_rate: f32,
+    /// 最大バッファサイズ
+    pub max_buffer_size: usize,
+    /// 内部で処理するブロックのサイズ
+    pub block_size: usize,
+    /// 処理中のチャンネル数
+    pub num_channels: usize,
+    /// ノードを処理する順序
+    pub processing_order: Vec<NodeId>,
+    /// ノードごとの状態（処理の順）
+    pub nodes: Vec<NodeSnapshot>,
+}
+
+/// スナップショットの、1 つのノードの状態
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeSnapshot {
+    /// ノードID
+    pub node_id: NodeId,
+    /// set_node_kind で記録したノードの種類の名前
+    pub kind: Option<&'static str>,
+    /// 入力のエッジ（process が合成する順、並列のエッジごとに 1 つ）
+    pub inputs: Vec<InputSnapshot>,
+    /// 出力バッファのチャンネル数
+    pub output_channels: usize,
+    /// 出力バッファのフレーム数（最後に処理したブロックの長さ）
+    pub output_frames: usize,
+    /// 出力バッファの容量（フレーム数）。出力バッファがない場合は 0
+    pub output_capacity: usize,
+    /// 追加の出力ポートのバッファの数
+    pub num_port_outputs: usize,
+    /// パラメーターの ID と現在の値
+    pub parameters: Vec<(&'static str, f32)>,
+}
+
+/// スナップショットの、1 本の入力のエッジ
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputSnapshot {
+    /// 接続元ノードのID
+    pub from_id: NodeId,
+    /// 接続元ノードの出力ポート番号
+    pub from_port: usize,
+    /// 接続先ノードの入力ポート番号
+    pub port: usize,
+    /// 接続元ノードの出力に掛けるゲイン
+    pub gain: f32,
+    /// ゲインを掛けた出力に加算するオフセット
+    pub offset: f32,
+}
+
+/// オーディオグラフの実装
+///
+/// 隣接リストを使用してオーディオノード間の接続を管理します。
+///
+/// このオーディオグラフはリアルタイムのオーディオ処理のためのグラフです。
+/// リアルタイムスレッドのループで process 関数呼び出されます。
+/// ノードやエッジの挿入などの操作を行った場合、リアルタイムに process 関数のバッファー書き込み処理に反映されます。
+pub struct AudioGraph {
+    /// ノードのマップ（IDとノードのペア）
+    nodes: SlotMap<NodeId, Box<dyn AudioGraphNode>>,
+    /// set_node_kind で記録した、ノードの種類の名前（フリーズ中のノードの分も保持する）
+    node_kinds: SlotMap<NodeId, &'static str>,
+    /// グラフ構造
+    graph: DirectedGraph<NodeId>,
+    /// インデックスごとの現在の世代（ノードを削除するたびに進める）
+    generations: Vec<usize>,
+    /// 削除されたノードの、再利用できるインデックス
+    free_indices: Vec<usize>,
+    /// サンプリングレート
+    sample_rate: f32,
+    /// 最大バッファサイズ
+    max_buffer_size: usize,
+    /// 内部で処理するブロックのサイズ（`None` の場合は最大バッファサイズまでまとめて処理する）
+    internal_block_size: Option<usize>,
+    /// 各ノードの出力バッファのキャッシュ（リアルタイムセーフな処理のため）
+    node_outputs: SlotMap<NodeId, OwnedAudioBuffer>,
+    /// 一時的な入力バッファ（リアルタイムセーフな処理のため）
+    tmp_input_buffer: OwnedAudioBuffer,
+    /// プレーナー形式を好むノードに渡すための一時バッファ（チャンネルごとに max_buffer_size ずつ並ぶ）
+    tmp_planar_buffer: Vec<f32>,
+    /// 各ノードへの入力のエッジの接続（キー: 接続先ID、エッジを追加した順、並列のエッジごとに 1 つ）。
+    /// process はこのリストをたどって入力を合成するため、エッジのたびにマップを引かずに済む。
+    input_taps: SlotMap<NodeId, Vec<InputTap>>,
+    /// 同じノード間に複数のエッジを追加できるかどうか
+    allow_parallel_edges: bool,
+    /// ノードの入力の合成方法（登録のないノードは SummingMode::Sum）
+    summing_modes: SlotMap<NodeId, SummingMode>,
+    /// ノードの入力の独自の合成方法（summing_modes より優先する）
+    mix_policies: SlotMap<NodeId, Box<dyn MixPolicy>>,
+    /// rate_factor が 1 以外のノードの入出力のレート変換器
+    rate_converters: SlotMap<NodeId, RateConverter>,
+    /// モジュレーションの接続
+    modulations: Vec<ModulationRoute>,
+    /// MIDI の接続（送り元ID, 送り先ID）
+    midi_routes: Vec<(NodeId, NodeId)>,
+    /// フィードバックのエッジ
+    feedback_edges: Vec<FeedbackEdge>,
+    /// send_note_event で送られ、まだノードに渡していないノートイベント（送り先ID, イベント）
+    pending_note_events: Vec<(NodeId, NoteEvent)>,
+    /// MIDI の接続を通して送るノートイベントの一時バッファ（送り先ID, イベント）
+    routed_note_events: Vec<(NodeId, NoteEvent)>,
+    /// A/B モーフで補間するパラメーター
+    morph_routes: Vec<MorphRoute>,
+    /// A/B モーフの位置（0 で A、1 で B）
+    morph_position: SmoothedValue,
+    /// マクロの割り当て
+    macro_mappings: Vec<MacroMapping>,
+    /// マクロの値（0〜1）
+    macro_values: [f32; MAX_MACROS],
+    /// パラメーターに割り当てたオートメーション
+    automation: Vec<AutomationRoute>,
+    /// 次のブロックの先頭のオートメーションの位置（サンプル数）
+    automation_position: u64,
+    /// ゲインステージングの分析で記録したレベル（分析が無効の場合は `None`）
+    gain_stages: Option<SlotMap<NodeId, GainStage>>,
+    /// フリーズしたサブグラフ（キー: 代わりに再生する SamplePlayer のノードID）
+    frozen: HashMap<NodeId, FrozenSubgraph>,
+    /// 削除したノードやバッファの送り先（`None` の場合はその場で解放する）
+    #[cfg(feature = "std")]
+    garbage: Option<GarbageSender>,
+    /// ノードがアプリケーションにイベントを通知するキュー
+    events: Option<Arc<EventQueue>>,
+    /// process で検出した問題の記録先
+    diagnostics: Arc<Diagnostics>,
+    /// 追加の出力ポートを持つノードの、追加の出力ポートのバッファ（インデックス 0 がポート 1 に対応する）
+    port_outputs: SlotMap<NodeId, Vec<OwnedAudioBuffer>>,
+    /// 追加の入力ポート用の一時バッファ（インデックス 0 がポート 1 に対応する）
+    tmp_port_buffers: Vec<Vec<f32>>,
+    /// 追加の入力ポートの接続の有無（インデックス 0 がポート 1 に対応する）
+    tmp_port_connected: Vec<bool>,
+    /// 追加の入力ポートに接続したエッジの数（インデックス 0 がポート 1 に対応する）
+    tmp_port_counts: Vec<usize>,
+    /// 処理中のチャンネル数
+    num_channels: usize,
+    /// チャンネル数の異なるバッファを変換して処理するための一時バッファ（グラフのチャンネル数）
+    tmp_channel_buffer: OwnedAudioBuffer,
+    /// 最後に process に渡されたバッファのチャンネル数
+    host_num_channels: usize,
+    /// 処理中の process に渡されたバッファのフレーム数
+    host_num_frames: usize,
+    /// 次のブロックの先頭のトランスポート
+    transport: Transport,
+    /// ノート番号から周波数への変換に使う調律
+    tuning: Tuning,
+    /// サイドチェーン入力を受け取るノード
+    sidechain_node_id: Option<NodeId>,
+    /// ホストから受け取ったサイドチェーン入力（グラフのチャンネル数）
+    sidechain_buffer: OwnedAudioBuffer,
+    /// サイドチェーン入力のうち、次のブロックで読み出す位置（フレーム数）
+    sidechain_position: usize,
+    /// 追加の出力バス（インデックスがバスの番号に対応する）
+    output_buses: Vec<OutputBus>,
+    /// process に渡されたバッファのうち、処理中のブロックの先頭の位置（フレーム数）
+    block_position: usize,
+    /// 固定容量モードの上限（`None` の場合は必要に応じてメモリを確保する）
+    capacity: Option<GraphCapacity>,
+    /// 固定容量モードで、追加するノードに割り当てる出力バッファ
+    spare_outputs: Vec<OwnedAudioBuffer>,
+    /// 固定容量モードで、追加するノードに割り当てる入力のエッジのリスト
+    spare_input_taps: Vec<Vec<InputTap>>,
+}
+
+impl AudioGraph {
+    /// 新しいオーディオグラフを作成する
+    pub fn new() -> Self {
+        Self {
+            nodes: SlotMap::new(),
+            node_kinds: SlotMap::new(),
+            graph: DirectedGraph::<NodeId>::new(),
+            generations: Vec::new(),
+            free_indices: Vec::new(),
+            sample_rate: 44100.0,
+            max_buffer_size: 0,
+            internal_block_size: None,
+            node_outputs: SlotMap::new(),
+            tmp_input_buffer: OwnedAudioBuffer::new(2, 0),
+            tmp_planar_buffer: Vec::new(),
+            input_taps: SlotMap::new(),
+            allow_parallel_edges: false,
+            summing_modes: SlotMap::new(),
+            mix_policies: SlotMap::new(),
+            rate_converters: SlotMap::new(),
+            modulations: Vec::new(),
+            midi_routes: Vec::new(),
+            feedback_edges: Vec::new(),
+            pending_note_events: Vec::with_capacity(MAX_PENDING_NOTE_EVENTS),
+            routed_note_events: Vec::with_capacity(MAX_ROUTED_NOTE_EVENTS),
+            morph_routes: Vec::new(),
+            morph_position: SmoothedValue::new(0.0),
+            macro_mappings: Vec::new(),
+            macro_values: [0.0; MAX_MACROS],
+            automation: Vec::new(),
+            automation_position: 0,
+            gain_stages: None,
+            frozen: HashMap::new(),
+            #[cfg(feature = "std")]
+            garbage: None,
+            events: None,
+            diagnostics: Arc::new(Diagnostics::new()),
+            port_outputs: SlotMap::new(),
+            tmp_port_buffers: Vec::new(),
+            tmp_port_connected: Vec::new(),
+            tmp_port_counts: Vec::new(),
+            num_channels: 2,
+            tmp_channel_buffer: OwnedAudioBuffer::new(2, 0),
+            host_num_channels: 2,
+            host_num_frames: 0,
+            transport: Transport::new(),
+            tuning: Tuning::equal_temperament(),
+            sidechain_node_id: None,
+            sidechain_buffer: OwnedAudioBuffer::new(2, 0),
+            sidechain_position: 0,
+            output_buses: Vec::new(),
+            block_position: 0,
+            capacity: None,
+            spare_outputs: Vec::new(),
+            spare_input_taps: Vec::new(),
+        }
+    }
+
+    /// 固定容量モードのオーディオグラフを作成する
+    ///
+    /// ノードやエッジの管理に使うメモリを `capacity` の上限まであらかじめ確保し、ノードの出力バッファは prepare で確保します。
+    /// 上限を超えない限り、prepare の後の次の操作はメモリアロケーションを行いません
+    /// （失敗した場合のエラーメッセージの作成を除く）。
+    ///
+    /// * try_add_node でのノードの追加
+    /// * remove_node でのノードの削除（返されたノードの解放は呼び出し元が行う）
+    /// * add_edge・add_edge_to_port・add_edge_with_gain・add_edge_from_port でのエッジの追加（並列のエッジを除く）
+    /// * remove_edge でのエッジの削除
+    ///
+    /// # 引数
+    /// * `capacity` - グラフの大きさの上限
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn with_capacity(capacity: GraphCapacity) -> Self {
+        let GraphCapacity {
+            max_nodes,
+            max_edges_per_node,
+            ..
+        } = capacity;
+        let mut audio_graph = Self::new();
+        audio_graph.nodes.reserve(max_nodes);
+        audio_graph.node_kinds.reserve(max_nodes);
+        audio_graph.node_outputs.reserve(max_nodes);
+        audio_graph.input_taps.reserve(max_nodes);
+        audio_graph.graph = DirectedGraph::with_capacity(max_nodes, max_edges_per_node);
+        audio_graph.generations.reserve(max_nodes);
+        audio_graph.free_indices.reserve(max_nodes);
+        audio_graph.spare_outputs.reserve(max_nodes);
+        audio_graph.spare_input_taps = (0..max_nodes)
+            .map(|_| Vec::with_capacity(max_edges_per_node))
+            .collect();
+        audio_graph.capacity = Some(capacity);
+        audio_graph
+    }
+
+    /// 固定容量モードの上限（with_capacity で作成していない場合は `None`）
+    pub fn capacity(&self) -> Option<GraphCapacity> {
+        self.capacity
+    }
+
+    /// オーディオグラフのパラメータを更新する
+    ///
+    /// # 引数
+    /// * `sample_rate` - サンプリングレート（Hz）
+    /// * `max_buffer_size` - 最大バッファサイズ
+    ///
+    /// # 実装時の注意
+    /// この関数はサンプルレートやバッファーサイズ変更時に一度だけ、メインスレッドなどの非リアルタイムスレッドから呼び出されます。
+    /// prepare_with_block_size で内部のブロックサイズを指定している場合は、その設定を保ちます。
+    pub fn prepare(&mut self, sample_rate: f32, max_buffer_size: usize) {
+        self.prepare_with_block_size(sample_rate, max_buffer_size, self.internal_block_size);
+    }
+
+    /// 内部のブロックサイズを指定して、オーディオグラフのパラメータを更新する
+    ///
+    /// ブロックサイズを指定すると、process はホストのバッファサイズによらず、バッファをブロックサイズずつに分けて処理します。
+    /// ブロックごとにパラメーターのモジュレーションやコントロールレートのノードが更新されるため、オートメーションの時間の
+    /// 細かさがホストのバッファサイズに左右されず、ブロックごとの処理の負荷も均一になります。
+    /// バッファの長さがブロックサイズで割り切れない場合、残りのサンプルは短いブロックとしてそのまま処理するため、
+    /// 遅延は生じません。
+    ///
+    /// # 引数
+    /// * `sample_rate` - サンプリングレート（Hz）
+    /// * `max_buffer_size` - 最大バッファサイズ
+    /// * `block_size` - 内部で処理するブロックのサイズ。`None` または 0 の場合は最大バッファサイズまでまとめて処理し、
+    ///   最大バッファサイズより大きい場合は最大バッファサイズで処理する
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn prepare_with_block_size(
+        &mut self,
+        sample_rate: f32,
+        max_buffer_size: usize,
+        block_size: Option<usize>,
+    ) {
+        self.sample_rate = sample_rate;
+        self.max_buffer_size = max_buffer_size;
+        self.internal_block_size = block_size.filter(|&size| size > 0);
+
+        // ノード出力バッファを事前に確保
+        self.node_outputs.clear();
+        // グラフ内の全ノードIDを取得
+        for &node_id in self
+            .graph
+            .node_ids()
+            .copied()
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            self.node_outputs.insert(
+                node_id,
+                OwnedAudioBuffer::new(self.num_channels, max_buffer_size),
+            );
+        }
+
+        // 固定容量モードでは、上限までのノードの出力バッファを事前に確保
+        self.spare_outputs.clear();
+        if let Some(capacity) = self.capacity {
+            let num_spares = capacity.max_nodes.saturating_sub(self.node_outputs.len());
+            self.spare_outputs.extend(
+                (0..num_spares).map(|_| OwnedAudioBuffer::new(self.num_channels, max_buffer_size)),
+            );
+        }
+
+        // 追加の出力ポートのバッファを事前に確保
+        self.port_outputs.clear();
+        for (&node_id, node) in self.nodes.iter() {
+            let num_ports = node.num_output_ports();
+            if num_ports > 1 {
+                self.port_outputs.insert(
+                    node_id,
+                    (1..num_ports)
+                        .map(|_| OwnedAudioBuffer::new(self.num_channels, max_buffer_size))
+                        .collect(),
+                );
+            }
+        }
+
+        // 一時入力バッファを事前に確保
+        self.tmp_input_buffer = OwnedAudioBuffer::new(self.num_channels, max_buffer_size);
+        self.tmp_planar_buffer = vec![0.0; self.num_channels * max_buffer_size];
+        self.tmp_channel_buffer = OwnedAudioBuffer::new(self.num_channels, max_buffer_size);
+        self.sidechain_buffer = OwnedAudioBuffer::new(self.num_channels, max_buffer_size);
+        self.sidechain_buffer.set_num_frames(0);
+        self.sidechain_position = 0;
+        for bus in self.output_buses.iter_mut() {
+            bus.buffer = OwnedAudioBuffer::new(self.num_channels, max_buffer_size);
+        }
+        for edge in self.feedback_edges.iter_mut() {
+            edge.buffer = OwnedAudioBuffer::new(self.num_channels, max_buffer_size);
+            edge.buffer.set_num_frames(0);
+        }
+        self.tmp_port_buffers.clear();
+        self.tmp_port_connected.clear();
+        self.tmp_port_counts.clear();
+        let max_ports = self
+            .nodes
+            .values()
+            .map(|node| node.num_input_ports())
+            .chain(self.capacity.map(|capacity| capacity.max_input_ports))
+            .max()
+            .unwrap_or(1);
+        self.reserve_port_buffers(max_ports);
+
+        // 各ノードを準備
+        for (&node_id, node) in self.nodes.iter_mut() {
+            prepare_node(
+                node.as_mut(),
+                node_id,
+                &mut self.rate_converters,
+                self.num_channels,
+                sample_rate,
+                max_buffer_size,
+            );
+        }
+        self.morph_position.prepare(sample_rate, MORPH_RAMP_MS);
+    }
+
+    /// prepare_with_block_size で指定した、内部で処理するブロックのサイズ
+    pub fn internal_block_size(&self) -> Option<usize> {
+        self.internal_block_size
+    }
+
+    /// process が 1 回の process_block で処理する最大のフレーム数
+    fn block_size(&self) -> usize {
+        match self.internal_block_size {
+            Some(size) => size.min(self.max_buffer_size),
+            None => self.max_buffer_size,
+        }
+    }
+
+    /// グラフが処理するチャンネル数
+    pub fn num_channels(&self) -> usize {
+        self.num_channels
+    }
+
+    /// ホストのチャンネル数が変わった場合に、そのチャンネル数を返す
+    ///
+    /// 最後に process に渡されたバッファのチャンネル数がグラフのチャンネル数と異なり、
+    /// グラフがそのチャンネル数に対応している場合に `Some` を返します。
+    /// ホストは非リアルタイムスレッドでこれを確認し、set_num_channels でグラフを再構成します。
+    /// 再構成されるまでの間、process はバッファをグラフのチャンネル数に変換して処理します。
+    pub fn requested_num_channels(&self) -> Option<usize> {
+        (self.host_num_channels != self.num_channels && self.host_num_channels <= MAX_CHANNELS)
+            .then_some(self.host_num_channels)
+    }
+
+    /// グラフが処理するチャンネル数を変更する
+    ///
+    /// prepare 済みの場合は、チャンネル数に依存するバッファやノードの状態を作り直すため、
+    /// 同じサンプリングレートと最大バッファサイズで prepare し直します。
+    ///
+    /// # 引数
+    /// * `num_channels` - チャンネル数（1 以上 MAX_CHANNELS 以下）
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、対応していないチャンネル数の場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn set_num_channels(&mut self, num_channels: usize) -> Result<(), String> {
+        if num_channels == 0 || num_channels > MAX_CHANNELS {
+            return Err(format!(
+                "対応していないチャンネル数です: {}（1～{}）",
+                num_channels, MAX_CHANNELS
+            ));
+        }
+        if num_channels == self.num_channels {
+            return Ok(());
+        }
+        self.num_channels = num_channels;
+        if self.max_buffer_size > 0 {
+            self.prepare(self.sample_rate, self.max_buffer_size);
+        }
+        Ok(())
+    }
+
+    /// ノードをグラフに追加する
+    ///
+    /// # 引数
+    /// * `node` - 追加するノード
+    ///
+    /// # 戻り値
+    /// * 追加されたノードのID
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn add_node(&mut self, node: Box<dyn AudioGraphNode>) -> NodeId {
+        let node_id = self.allocate_node_id();
+        self.insert_node(node_id, node);
+        node_id
+    }
+
+    /// 固定容量モードで、メモリアロケーションを行わずにノードをグラフに追加する
+    ///
+    /// グラフはノードの prepare を呼び出さないため、ノードはグラフと同じサンプリングレートと最大バッファサイズで
+    /// 非リアルタイムスレッドで prepare してから渡してください（グラフの prepare の前に追加した場合は、
+    /// グラフの prepare で準備されます）。
+    ///
+    /// # 引数
+    /// * `node` - 追加するノード
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok` で追加されたノードのID。固定容量モードでない場合や、ノード数が上限に達している場合、
+    ///   固定容量モードで扱えないノード（出力ポートが複数ある、入力ポート数が上限を超える、rate_factor が 1 以外）の場合は
+    ///   `Err` で渡したノードをそのまま返す（解放は呼び出し元が非リアルタイムスレッドで行う）
+    ///
+    /// # 実装時の注意
+    /// ノードの on_added がメモリアロケーションを行わない限り、リアルタイムスレッドから呼び出せます。
+    pub fn try_add_node(
+        &mut self,
+        node: Box<dyn AudioGraphNode>,
+    ) -> Result<NodeId, Box<dyn AudioGraphNode>> {
+        let Some(capacity) = self.capacity else {
+            return Err(node);
+        };
+        if self.nodes.len() >= capacity.max_nodes
+            || node.num_output_ports() > 1
+            || node.num_input_ports() > capacity.max_input_ports
+            || node.rate_factor() != 1.0
+        {
+            return Err(node);
+        }
+        let node_id = self.allocate_node_id();
+        self.attach_node(node_id, node);
+        Ok(node_id)
+    }
+
+    /// 新しいノードの ID を割り当てる
+    fn allocate_node_id(&mut self) -> NodeId {
+        // 削除されたノードのインデックスがあれば、世代を進めた ID で再利用する
+        match self.free_indices.pop() {
+            Some(index) => NodeId {
+                index,
+                generation: self.generations[index],
+            },
+            None => {
+                self.generations.push(0);
+                NodeId {
+                    index: self.generations.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// 割り当て済みの ID でノードをグラフに追加する
+    fn insert_node(&mut self, node_id: NodeId, mut node: Box<dyn AudioGraphNode>) {
+        // ノードを初期化
+        prepare_node(
+            node.as_mut(),
+            node_id,
+            &mut self.rate_converters,
+            self.num_channels,
+            self.sample_rate,
+            self.max_buffer_size,
+        );
+        self.attach_node(node_id, node);
+    }
+
+    /// 準備済みのノードを、割り当て済みの ID でグラフに追加する
+    fn attach_node(&mut self, node_id: NodeId, mut node: Box<dyn AudioGraphNode>) {
+        // ノードにグラフIDを割り当て
+        self.graph.add_node(node_id);
+
+        node.on_added(&self.node_context(node_id));
+        node.set_tuning(&self.tuning);
+
+        // 追加の入力ポート用のバッファと、追加の出力ポートのバッファをあらかじめ確保
+        self.reserve_port_buffers(node.num_input_ports());
+        if node.num_output_ports() > 1 {
+            self.port_outputs.insert(
+                node_id,
+                (1..node.num_output_ports())
+                    .map(|_| OwnedAudioBuffer::new(self.num_channels, self.max_buffer_size))
+                    .collect(),
+            );
+        }
+
+        // ノードをノードマップに追加し、入力のエッジのリストを用意（固定容量モードでは確保済みのものを使う）
+        self.nodes.insert(node_id, node);
+        let inputs = self.spare_input_taps.pop().unwrap_or_default();
+        self.input_taps.insert(node_id, inputs);
+
+        // ゲインステージングの分析の記録先をあらかじめ確保
+        if let Some(gain_stages) = self.gain_stages.as_mut() {
+            gain_stages.insert(node_id, GainStage::new(node_id));
+        }
+
+        // ノード出力バッファをあらかじめ確保（固定容量モードでは確保済みのものを使う）
+        if !self.node_outputs.is_empty() || !self.spare_outputs.is_empty() {
+            let buffer = match self.spare_outputs.pop() {
+                Some(mut buffer) => {
+                    buffer.clear();
+                    buffer
+                }
+                None => OwnedAudioBuffer::new(self.num_channels, self.max_buffer_size),
+            };
+            self.node_outputs.insert(node_id, buffer);
+        }
+    }
+
+    /// エッジ（接続）をグラフに追加する
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `to_id` - 接続先ノードのID
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn add_edge(&mut self, from_id: NodeId, to_id: NodeId) -> Result<(), String> {
+        self.add_edge_to_port(from_id, to_id, 0)
+    }
+
+    /// エッジ（接続）を接続先ノードの指定した入力ポートに追加する
+    ///
+    /// 同じノード間のエッジが既に存在する場合は、接続先ポートを変更します（ゲインは 1 に戻ります）。
+    /// set_allow_parallel_edges で並列のエッジを許可している場合は、もう 1 本のエッジを追加します。
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `to_id` - 接続先ノードのID
+    /// * `port` - 接続先ノードの入力ポート番号（0 はメイン入力）
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn add_edge_to_port(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        port: usize,
+    ) -> Result<(), String> {
+        self.add_edge_with_gain(from_id, to_id, port, 1.0)
+    }
+
+    /// ゲイン付きのエッジ（接続）を接続先ノードの指定した入力ポートに追加する
+    ///
+    /// 接続元ノードの出力にゲインを掛けて、接続先ノードの入力に加算します。
+    /// 同じノード間のエッジが既に存在する場合は、接続先ポートとゲインを置き換えます。
+    /// set_allow_parallel_edges で並列のエッジを許可している場合は、もう 1 本のエッジを追加します。
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `to_id` - 接続先ノードのID
+    /// * `port` - 接続先ノードの入力ポート番号（0 はメイン入力）
+    /// * `gain` - 接続元ノードの出力に掛けるゲイン
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn add_edge_with_gain(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        port: usize,
+        gain: f32,
+    ) -> Result<(), String> {
+        self.add_edge_tap(
+            from_id,
+            to_id,
+            EdgeTap {
+                port,
+                gain,
+                ..EdgeTap::DEFAULT
+            },
+        )
+    }
+
+    /// 接続元ノードの出力ポートから、接続先ノードの入力ポートにエッジを追加する
+    ///
+    /// 複数の出力ポートを持つノード（AudioGraphNode::num_output_ports）の、メイン出力以外の出力を接続するために使います。
+    /// 同じノード間のエッジが既に存在する場合は置き換えるため、同じノードの複数の出力ポートを同じノードに接続する場合は
+    /// set_allow_parallel_edges で並列のエッジを許可してください。
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `from_port` - 接続元ノードの出力ポート番号（0 はメイン出力）
+    /// * `to_id` - 接続先ノードのID
+    /// * `to_port` - 接続先ノードの入力ポート番号（0 はメイン入力）
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn add_edge_from_port(
+        &mut self,
+        from_id: NodeId,
+        from_port: usize,
+        to_id: NodeId,
+        to_port: usize,
+    ) -> Result<(), String> {
+        self.add_edge_tap(
+            from_id,
+            to_id,
+            EdgeTap {
+                from_port,
+                port: to_port,
+                ..EdgeTap::DEFAULT
+            },
+        )
+    }
+
+    /// 接続元ノードの出力で、接続先ノードのパラメーターをオーディオレートで変化させるエッジを追加する
+    ///
+    /// 接続元ノードの出力に `scale` を掛けて `offset` を加算した信号を、接続先ノードのパラメーターポート
+    /// （AudioGraphNode::parameter_port）に入力します。接続先ノードはパラメーターの値に信号の値を加算して使うため、
+    /// サイン波の周波数に接続すると FM、位相に接続すると PM になります。
+    /// エッジは通常のエッジと同じく remove_edge で削除できます。
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `to_id` - 接続先ノードのID
+    /// * `parameter_id` - 接続先ノードのパラメーター ID
+    /// * `scale` - 接続元ノードの出力に掛ける値
+    /// * `offset` - `scale` を掛けた出力に加算する値
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn add_parameter_edge(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        parameter_id: &str,
+        scale: f32,
+        offset: f32,
+    ) -> Result<(), String> {
+        let port = self
+            .nodes
+            .get(&to_id)
+            .ok_or_else(|| self.node_not_found(to_id))?
+            .parameter_port(parameter_id)
+            .ok_or_else(|| {
+                format!(
+                    "ノードID {} のパラメーター {} にはパラメーターポートがありません",
+                    to_id, parameter_id
+                )
+            })?;
+        self.add_edge_tap(
+            from_id,
+            to_id,
+            EdgeTap {
+                port,
+                gain: scale,
+                offset,
+                ..EdgeTap::DEFAULT
+            },
+        )
+    }
+
+    /// 接続先ポートとゲインを指定してエッジを追加する
+    fn add_edge_tap(&mut self, from_id: NodeId, to_id: NodeId, tap: EdgeTap) -> Result<(), String> {
+        self.check_edge_ports(from_id, to_id, &tap)?;
+
+        // 固定容量モードでは、確保したリストの容量を超えるエッジを追加しない
+        let existed = self.has_edge(from_id, to_id);
+        if let Some(capacity) = self.capacity
+            && !existed
+            && (self.graph.get_output_node_ids(from_id).len() >= capacity.max_edges_per_node
+                || self.graph.get_input_node_ids(to_id).len() >= capacity.max_edges_per_node)
+        {
+            return Err(format!(
+                "ノードID {} から {} への接続は、ノードあたりのエッジ数の上限（{}）を超えます",
+                from_id, to_id, capacity.max_edges_per_node
+            ));
+        }
+
+        // 接続先から接続元への経路があれば、この接続で循環する
+        if self.graph.has_path(to_id, from_id) {
+            let path = self.graph.find_path(to_id, from_id).unwrap_or_default();
+            let cycle: Vec<String> = path.iter().map(|id| id.to_string()).collect();
+            return Err(format!(
+                "ノードID {} から {} への接続は循環参照を作成します（循環: {} -> {}）",
+                from_id,
+                to_id,
+                from_id,
+                cycle.join(" -> ")
+            ));
+        }
+
+        // DirectedGraphにエッジを追加（並列のエッジは input_taps にだけ記録する）
+        self.graph.add_edge(from_id, to_id)?;
+
+        if existed && self.allow_parallel_edges {
+            let mut taps = self.edge_taps(from_id, to_id);
+            taps.push(tap);
+            self.set_edge_taps(from_id, to_id, &taps);
+        } else {
+            self.set_edge_taps(from_id, to_id, &[tap]);
+        }
+        Ok(())
+    }
+
+    /// エッジの接続元の出力ポートと接続先の入力ポートが存在するかどうかを確認する
+    fn check_edge_ports(
+        &self,
+        from_id: NodeId,
+        to_id: NodeId,
+        tap: &EdgeTap,
+    ) -> Result<(), String> {
+        let num_output_ports = self
+            .nodes
+            .get(&from_id)
+            .ok_or_else(|| self.node_not_found(from_id))?
+            .num_output_ports();
+        if tap.from_port >= num_output_ports {
+            return Err(format!(
+                "ノードID {} には出力ポート {} がありません（ポート数: {}）",
+                from_id, tap.from_port, num_output_ports
+            ));
+        }
+        let num_ports = self
+            .nodes
+            .get(&to_id)
+            .ok_or_else(|| self.node_not_found(to_id))?
+            .num_input_ports();
+        if tap.port >= num_ports {
+            return Err(format!(
+                "ノードID {} には入力ポート {} がありません（ポート数: {}）",
+                to_id, tap.port, num_ports
+            ));
+        }
+        Ok(())
+    }
+
+    /// 同じノード間に複数のエッジを追加できるようにする
+    ///
+    /// 許可すると、add_edge などで既に接続のあるノード間にもう 1 本のエッジを追加し、それぞれのゲインで加算します。
+    /// センドを重ねる場合などに使います。デフォルトは `false` で、既存のエッジの接続を置き換えます。
+    pub fn set_allow_parallel_edges(&mut self, allow: bool) {
+        self.allow_parallel_edges = allow;
+    }
+
+    /// ノードの入力の合成方法を設定する
+    ///
+    /// 多くの並列のブランチをまとめるノードで SummingMode::Average などを使うと、加算によるクリップを防げます。
+    /// set_mix_policy で独自の合成方法を設定している場合は、そちらを優先します。
+    ///
+    /// # 引数
+    /// * `node_id` - 対象のノードID
+    /// * `mode` - 入力の合成方法
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、ノードが存在しない場合は `Err` でエラーメッセージを返す
+    pub fn set_summing_mode(&mut self, node_id: NodeId, mode: SummingMode) -> Result<(), String> {
+        if !self.nodes.contains_key(&node_id) {
+            return Err(self.node_not_found(node_id));
+        }
+        if mode == SummingMode::Sum {
+            self.summing_modes.remove(&node_id);
+        } else {
+            self.summing_modes.insert(node_id, mode);
+        }
+        Ok(())
+    }
+
+    /// ノードの入力を独自の方法で合成するように設定する
+    ///
+    /// 設定すると、set_summing_mode の設定より優先します。
+    ///
+    /// # 引数
+    /// * `node_id` - 対象のノードID
+    /// * `policy` - 入力の合成方法
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、ノードが存在しない場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn set_mix_policy(
+        &mut self,
+        node_id: NodeId,
+        policy: Box<dyn MixPolicy>,
+    ) -> Result<(), String> {
+        if !self.nodes.contains_key(&node_id) {
+            return Err(self.node_not_found(node_id));
+        }
+        self.mix_policies.insert(node_id, policy);
+        Ok(())
+    }
+
+    /// ノードの独自の入力の合成方法を解除し、SummingMode による合成に戻す
+    ///
+    /// # 戻り値
+    /// * 設定されていた合成方法
+    pub fn clear_mix_policy(&mut self, node_id: NodeId) -> Option<Box<dyn MixPolicy>> {
+        self.mix_policies.remove(&node_id)
+    }
+
+    /// ノードの入力の合成方法を取得する
+    ///
+    /// # 戻り値
+    /// * ノードが存在しない場合は `None`
+    pub fn summing_mode(&self, node_id: NodeId) -> Option<SummingMode> {
+        self.nodes.contains_key(&node_id).then(|| {
+            self.summing_modes
+                .get(&node_id)
+                .copied()
+                .unwrap_or(SummingMode::Sum)
+        })
+    }
+
+    /// エッジが存在するかどうか
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `to_id` - 接続先ノードのID
+    pub fn has_edge(&self, from_id: NodeId, to_id: NodeId) -> bool {
+        self.graph.get_output_node_ids(from_id).contains(&to_id)
+    }
+
+    /// ノードからのエッジの接続先を昇順で取得する（並列のエッジがあっても 1 回だけ含む）
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn edges_from(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut to_ids = self.graph.get_output_node_ids(node_id).to_vec();
+        to_ids.sort_unstable();
+        to_ids
+    }
+
+    /// ノード間のエッジのゲインを、並列のエッジを追加した順に取得する
+    ///
+    /// # 戻り値
+    /// * エッジが存在しない場合は空
+    pub fn edge_gains(&self, from_id: NodeId, to_id: NodeId) -> Vec<f32> {
+        if !self.has_edge(from_id, to_id) {
+            return Vec::new();
+        }
+        self.edge_taps(from_id, to_id)
+            .iter()
+            .map(|tap| tap.gain)
+            .collect()
+    }
+
+    /// エッジのゲインを設定する
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `to_id` - 接続先ノードのID
+    /// * `index` - 並列のエッジの番号（追加した順、並列でない場合は 0）
+    /// * `gain` - 接続元ノードの出力に掛けるゲイン
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、エッジが存在しない場合は `Err` でエラーメッセージを返す
+    pub fn set_edge_gain(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        index: usize,
+        gain: f32,
+    ) -> Result<(), String> {
+        if !self.has_edge(from_id, to_id) {
+            return Err(format!("エッジ {} -> {} が存在しません", from_id, to_id));
+        }
+        let mut taps = self.edge_taps(from_id, to_id);
+        let Some(tap) = taps.get_mut(index) else {
+            return Err(format!(
+                "エッジ {} -> {} に {} 番目の並列のエッジがありません",
+                from_id, to_id, index
+            ));
+        };
+        tap.gain = gain;
+        self.set_edge_taps(from_id, to_id, &taps);
+        Ok(())
+    }
+
+    /// エッジの接続を、並列のエッジを追加した順に取得する（記録のないエッジはポート 0、ゲイン 1 の 1 本）
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn edge_taps(&self, from_id: NodeId, to_id: NodeId) -> Vec<EdgeTap> {
+        let taps: Vec<EdgeTap> = self
+            .input_taps
+            .get(&to_id)
+            .into_iter()
+            .flatten()
+            .filter(|input| input.from_id == from_id)
+            .map(|input| input.tap)
+            .collect();
+        if taps.is_empty() {
+            vec![EdgeTap::DEFAULT]
+        } else {
+            taps
+        }
+    }
+
+    /// エッジの接続を設定する（接続先ノードの入力のリストの、元の接続と同じ位置に置き換える）
+    ///
+    /// # 実装時の注意
+    /// 接続の数が接続先ノードの入力のリストの容量を超えない限り、メモリアロケーションを行いません。
+    fn set_edge_taps(&mut self, from_id: NodeId, to_id: NodeId, taps: &[EdgeTap]) {
+        let Some(inputs) = self.input_taps.get_mut(&to_id) else {
+            return;
+        };
+        let position = inputs
+            .iter()
+            .position(|input| input.from_id == from_id)
+            .unwrap_or(inputs.len());
+        inputs.retain(|input| input.from_id != from_id);
+        for (i, &tap) in taps.iter().enumerate() {
+            inputs.insert(position + i, InputTap { from_id, tap });
+        }
+    }
+
+    /// edge_taps で取得したエッジの接続を、並列のエッジも含めてまとめて追加する
+    ///
+    /// 同じノード間のエッジが既に存在する場合は置き換えます。set_allow_parallel_edges の設定によらず、
+    /// `taps` の全ての接続を追加します。フリーズの前後で接続を移す場合や、書き出したグラフを読み込み直す場合に使います。
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `to_id` - 接続先ノードのID
+    /// * `taps` - 並列のエッジごとの接続（1 つ以上）
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す（グラフは変更しない）
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn restore_edge(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        taps: &[EdgeTap],
+    ) -> Result<(), String> {
+        let Some((first, rest)) = taps.split_first() else {
+            return Err(format!(
+                "エッジ {} -> {} の接続がありません",
+                from_id, to_id
+            ));
+        };
+        // 2 本目以降のポートも、エッジを追加する前に確認する
+        for tap in rest {
+            self.check_edge_ports(from_id, to_id, tap)?;
+        }
+        self.add_edge_tap(from_id, to_id, *first)?;
+        self.set_edge_taps(from_id, to_id, taps);
+        Ok(())
+    }
+
+    /// 追加の入力ポート用のバッファを、指定したポート数まで確保する
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    fn reserve_port_buffers(&mut self, num_ports: usize) {
+        while self.tmp_port_buffers.len() + 1 < num_ports {
+            self.tmp_port_buffers
+                .push(vec![0.0; self.num_channels * self.max_buffer_size]);
+            self.tmp_port_connected.push(false);
+            self.tmp_port_counts.push(0);
+        }
+    }
+
+    /// ノードを取得する
+    ///
+    /// # 引数
+    /// * `node_id` - 取得するノードのID
+    ///
+    /// # 戻り値
+    /// * ノードが存在する場合は `Some` でBoxに包まれた参照を返し、存在しない場合は `None` を返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn get_node(&self, node_id: NodeId) -> Option<&Box<dyn AudioGraphNode>> {
+        self.nodes.get(&node_id)
+    }
+
+    /// グラフ内の全ノードのIDを昇順で取得する
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn node_ids(&self) -> Vec<NodeId> {
+        let mut node_ids: Vec<NodeId> = self.nodes.keys().copied().collect();
+        node_ids.sort_unstable();
+        node_ids
+    }
+
+    /// グラフ内の全エッジを (接続元ID, 接続先ID) の昇順で取得する
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn edges(&self) -> Vec<(NodeId, NodeId)> {
+        let mut edges: Vec<(NodeId, NodeId)> = self
+            .graph
+            .node_ids()
+            .flat_map(|&to_id| {
+                self.graph
+                    .get_input_node_ids(to_id)
+                    .iter()
+                    .map(move |&from_id| (from_id, to_id))
+            })
+            .collect();
+        edges.sort_unstable();
+        edges
+    }
+
+    /// 直前の process でノードが出力したサンプルの絶対値の最大値を取得する（メーター表示用）
+    ///
+    /// # 戻り値
+    /// * ノードが存在し、prepare 済みの場合は `Some` で値を返し、そうでない場合は `None` を返す
+    pub fn output_peak(&self, node_id: NodeId) -> Option<f32> {
+        self.node_outputs.get(&node_id).map(|output| {
+            output
+                .as_slice()
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+        })
+    }
+
+    /// 直前の process でノードが出力したサンプルの絶対値の最大値を、チャンネルごとに取得する（メーター表示用）
+    ///
+    /// # 引数
+    /// * `node_id` - 対象ノードのID
+    /// * `peaks` - チャンネルごとの値を書き込む先（ノードの出力にないチャンネルには 0 を書き込む）
+    ///
+    /// # 戻り値
+    /// * ノードが存在し、prepare 済みの場合は `true`
+    pub fn output_channel_peaks(&self, node_id: NodeId, peaks: &mut [f32]) -> bool {
+        let Some(output) = self.node_outputs.get(&node_id) else {
+            return false;
+        };
+        peaks.fill(0.0);
+        let num_channels = output.num_channels().max(1);
+        for frame in output.as_slice().chunks_exact(num_channels) {
+            for (peak, sample) in peaks.iter_mut().zip(frame) {
+                *peak = peak.max(sample.abs());
+            }
+        }
+        true
+    }
+
+    /// ノードの種類の名前を記録する
+    ///
+    /// 種類の名前からノードを作成する仕組み（サービスの node_factory など）が、作成したノードに記録します。
+    /// 記録した名前は node_kind で取得でき、音声グラフを記述ファイルに書き出すときに使います。
+    pub fn set_node_kind(&mut self, node_id: NodeId, kind: &'static str) -> Result<(), String> {
+        if !self.nodes.contains_key(&node_id) {
+            return Err(self.node_not_found(node_id));
+        }
+        self.node_kinds.insert(node_id, kind);
+        Ok(())
+    }
+
+    /// set_node_kind で記録したノードの種類の名前を取得する
+    ///
+    /// # 戻り値
+    /// * 記録されていない場合やノードが存在しない場合は `None`
+    pub fn node_kind(&self, node_id: NodeId) -> Option<&'static str> {
+        if !self.nodes.contains_key(&node_id) {
+            return None;
+        }
+        self.node_kinds.get(&node_id).copied()
+    }
+
+    /// ノードのパラメーターを設定する
+    ///
+    /// # 引数
+    /// * `node_id` - 対象ノードのID
+    /// * `parameter_id` - パラメーターの ID
+    /// * `value` - 設定する値
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn set_parameter(
+        &mut self,
+        node_id: NodeId,
+        parameter_id: &str,
+        value: f32,
+    ) -> Result<(), String> {
+        if !self.nodes.contains_key(&node_id) {
+            return Err(self.node_not_found(node_id));
+        }
+        if set_node_parameter(
+            &mut self.nodes,
+            &mut self.modulations,
+            node_id,
+            parameter_id,
+            value,
+        ) {
+            Ok(())
+        } else {
+            Err(format!(
+                "ノードID {} にパラメーター {} がありません",
+                node_id, parameter_id
+            ))
+        }
+    }
+
+    /// ノードのパラメーターを取得する
+    ///
+    /// # 戻り値
+    /// * ノードとパラメーターが存在する場合は `Some` で値を返し、存在しない場合は `None` を返す
+    pub fn get_parameter(&self, node_id: NodeId, parameter_id: &str) -> Option<f32> {
+        self.nodes
+            .get(&node_id)
+            .and_then(|node| node.get_parameter(parameter_id))
+    }
+
+    /// モジュレーションの接続を追加する
+    ///
+    /// ソースノードの `modulation_output` の値に `amount` を掛けたものを、変調前のパラメーターの値に加算して
+    /// ターゲットノードのパラメーターに設定します。値はターゲットノードを処理する直前に、ブロックごとに更新されます。
+    /// 変調前の値は接続時点の値で、その後に set_parameter・マクロ・オートメーション・A/B モーフで設定した値に置き換わります。
+    ///
+    /// # 引数
+    /// * `source_id` - モジュレーションソースのノードID
+    /// * `target_id` - ターゲットのノードID
+    /// * `parameter_id` - ターゲットのパラメーター ID
+    /// * `amount` - 変調量
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn add_modulation(
+        &mut self,
+        source_id: NodeId,
+        target_id: NodeId,
+        parameter_id: &str,
+        amount: f32,
+    ) -> Result<(), String> {
+        let source = self
+            .nodes
+            .get(&source_id)
+            .ok_or_else(|| self.node_not_found(source_id))?;
+        if source.modulation_output().is_none() {
+            return Err(format!(
+                "ノードID {} はモジュレーションソースではありません",
+                source_id
+            ));
+        }
+        let target = self
+            .nodes
+            .get(&target_id)
+            .ok_or_else(|| self.node_not_found(target_id))?;
+        let base_value = target.get_parameter(parameter_id).ok_or_else(|| {
+            format!(
+                "ノードID {} にパラメーター {} がありません",
+                target_id, parameter_id
+            )
+        })?;
+        // 同じパラメーターを既に変調している場合、現在の値は変調後の値のため、変調前の値を引き継ぐ
+        let base_value = self
+            .modulations
+            .iter()
+            .find(|route| route.target_id == target_id && route.parameter_id == parameter_id)
+            .map_or(base_value, |route| route.base_value);
+
+        // 同じ接続が既にある場合は変調量だけを更新する
+        if let Some(route) = self.modulations.iter_mut().find(|route| {
+            route.source_id == source_id
+                && route.target_id == target_id
+                && route.parameter_id == parameter_id
+        }) {
+            route.amount = amount;
+            return Ok(());
+        }
+
+        self.modulations.push(ModulationRoute {
+            source_id,
+            target_id,
+            parameter_id: parameter_id.to_string(),
+            base_value,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// モジュレーションの接続を削除し、パラメーターを変調前の値に戻す
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `true`、存在しない場合は `false`
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn remove_modulation(
+        &mut self,
+        source_id: NodeId,
+        target_id: NodeId,
+        parameter_id: &str,
+    ) -> bool {
+        let Some(idx) = self.modulations.iter().position(|route| {
+            route.source_id == source_id
+                && route.target_id == target_id
+                && route.parameter_id == parameter_id
+        }) else {
+            return false;
+        };
+        let route = self.modulations.remove(idx);
+        if let Some(target) = self.nodes.get_mut(&target_id) {
+            target.set_parameter(&route.parameter_id, route.base_value);
+        }
+        true
+    }
+
+    /// A/B モーフの 2 つのスナップショットを設定する
+    ///
+    /// どちらかのスナップショットに含まれるパラメーターを、set_morph_position で指定した位置に応じて
+    /// A の値と B の値の間で直線的に補間します。一方にしか含まれないパラメーターは、
+    /// もう一方の値として設定時点の値を使います。値は現在の位置ですぐに適用されます。
+    ///
+    /// # 引数
+    /// * `a` - 位置 0 でのパラメーターの値（ノードID, パラメーター ID, 値）
+    /// * `b` - 位置 1 でのパラメーターの値（ノードID, パラメーター ID, 値）
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、存在しないノードやパラメーターを含む場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn set_morph_snapshots(
+        &mut self,
+        a: &[(NodeId, String, f32)],
+        b: &[(NodeId, String, f32)],
+    ) -> Result<(), String> {
+        let mut routes: Vec<MorphRoute> = Vec::new();
+        for (is_a, &(node_id, ref parameter_id, value)) in a
+            .iter()
+            .map(|entry| (true, entry))
+            .chain(b.iter().map(|entry| (false, entry)))
+        {
+            let current = self
+                .nodes
+                .get(&node_id)
+                .ok_or_else(|| self.node_not_found(node_id))?
+                .get_parameter(parameter_id)
+                .ok_or_else(|| {
+                    format!(
+                        "ノードID {} にパラメーター {} がありません",
+                        node_id, parameter_id
+                    )
+                })?;
+            let route = match routes
+                .iter_mut()
+                .position(|route| route.node_id == node_id && route.parameter_id == *parameter_id)
+            {
+                Some(idx) => &mut routes[idx],
+                None => {
+                    routes.push(MorphRoute {
+                        node_id,
+                        parameter_id: parameter_id.clone(),
+                        a: current,
+                        b: current,
+                    });
+                    routes.last_mut().unwrap()
+                }
+            };
+            if is_a {
+                route.a = value;
+            } else {
+                route.b = value;
+            }
+        }
+        self.morph_routes = routes;
+        self.apply_morph();
+        Ok(())
+    }
+
+    /// A/B モーフの位置を設定する
+    ///
+    /// パラメーターは process のブロックごとに、新しい位置まで滑らかに変化します。
+    ///
+    /// # 引数
+    /// * `position` - モーフの位置（0 で A、1 で B。範囲外の値は制限される）
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行わないため、process 呼び出しの直前にリアルタイムスレッドから呼び出すことができます。
+    pub fn set_morph_position(&mut self, position: f32) {
+        self.morph_position.set_target(position.clamp(0.0, 1.0));
+    }
+
+    /// A/B モーフの位置の目標値
+    pub fn morph_position(&self) -> f32 {
+        self.morph_position.target()
+    }
+
+    /// A/B モーフのスナップショットを解除する（パラメーターは現在の値のまま残る）
+    pub fn clear_morph(&mut self) {
+        self.morph_routes.clear();
+    }
+
+    /// A/B モーフの現在の位置で、補間したパラメーターの値を設定する
+    fn apply_morph(&mut self) {
+        let position = self.morph_position.current();
+        for route in self.morph_routes.iter() {
+            set_node_parameter(
+                &mut self.nodes,
+                &mut self.modulations,
+                route.node_id,
+                &route.parameter_id,
+                route.a + (route.b - route.a) * position,
+            );
+        }
+    }
+
+    /// マクロにノードのパラメーターを割り当てる
+    ///
+    /// 1 つのマクロに複数のパラメーターを割り当てられます。同じパラメーターが既に割り当てられている場合は、
+    /// 範囲とカーブを更新します。割り当てたパラメーターには、マクロの現在の値をすぐに適用します。
+    ///
+    /// # 引数
+    /// * `macro_index` - マクロの番号（MAX_MACROS 未満）
+    /// * `node_id` - 対象ノードのID
+    /// * `parameter_id` - パラメーター ID
+    /// * `min` - マクロの値が 0 のときのパラメーターの値
+    /// * `max` - マクロの値が 1 のときのパラメーターの値
+    /// * `curve` - マクロの値からパラメーターの値への変換
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn add_macro_mapping(
+        &mut self,
+        macro_index: usize,
+        node_id: NodeId,
+        parameter_id: &str,
+        min: f32,
+        max: f32,
+        curve: MacroCurve,
+    ) -> Result<(), String> {
+        if macro_index >= MAX_MACROS {
+            return Err(format!(
+                "マクロの番号 {} が不正です（マクロの数: {}）",
+                macro_index, MAX_MACROS
+            ));
+        }
+        let node = self
+            .nodes
+            .get(&node_id)
+            .ok_or_else(|| self.node_not_found(node_id))?;
+        if node.get_parameter(parameter_id).is_none() {
+            return Err(format!(
+                "ノードID {} にパラメーター {} がありません",
+                node_id, parameter_id
+            ));
+        }
+
+        self.macro_mappings.retain(|mapping| {
+            !(mapping.macro_index == macro_index
+                && mapping.node_id == node_id
+                && mapping.parameter_id == parameter_id)
+        });
+        self.macro_mappings.push(MacroMapping {
+            macro_index,
+            node_id,
+            parameter_id: parameter_id.to_string(),
+            min,
+            max,
+            curve,
+        });
+        self.set_macro(macro_index, self.macro_values[macro_index]);
+        Ok(())
+    }
+
+    /// マクロへのパラメーターの割り当てを削除する（パラメーターは現在の値のまま残る）
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `true`、存在しない場合は `false`
+    pub fn remove_macro_mapping(
+        &mut self,
+        macro_index: usize,
+        node_id: NodeId,
+        parameter_id: &str,
+    ) -> bool {
+        let len = self.macro_mappings.len();
+        self.macro_mappings.retain(|mapping| {
+            !(mapping.macro_index == macro_index
+                && mapping.node_id == node_id
+                && mapping.parameter_id == parameter_id)
+        });
+        self.macro_mappings.len() != len
+    }
+
+    /// マクロの値を設定し、割り当てたパラメーターに適用する
+    ///
+    /// # 引数
+    /// * `macro_index` - マクロの番号（MAX_MACROS 未満）
+    /// * `value` - マクロの値（0〜1 に制限される）
+    ///
+    /// # 戻り値
+    /// * マクロの番号が正しい場合は `true`、そうでない場合は `false`
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行わないため、process 呼び出しの直前にリアルタイムスレッドから呼び出すことができます。
+    pub fn set_macro(&mut self, macro_index: usize, value: f32) -> bool {
+        if macro_index >= MAX_MACROS {
+            return false;
+        }
+        let value = value.clamp(0.0, 1.0);
+        self.macro_values[macro_index] = value;
+        for mapping in self.macro_mappings.iter() {
+            if mapping.macro_index != macro_index {
+                continue;
+            }
+            set_node_parameter(
+                &mut self.nodes,
+                &mut self.modulations,
+                mapping.node_id,
+                &mapping.parameter_id,
+                mapping.curve.map(value, mapping.min, mapping.max),
+            );
+        }
+        true
+    }
+
+    /// マクロの現在の値
+    ///
+    /// # 戻り値
+    /// * マクロの番号が正しい場合は `Some` で値を返し、そうでない場合は `None` を返す
+    pub fn macro_value(&self, macro_index: usize) -> Option<f32> {
+        self.macro_values.get(macro_index).copied()
+    }
+
+    /// ノードのパラメーターにオートメーションを割り当てる
+    ///
+    /// process のたびに、ブロックの先頭のオートメーションの位置での値をパラメーターに設定します。
+    /// パラメーターにパラメーターポート（AudioGraphNode::parameter_port）がある場合は、ブロックの先頭の値からの差を
+    /// サンプルごとにパラメーターポートへ入れるため、ブレークポイントでの切り替えも連続的な変化もサンプル単位で正確になります。
+    /// パラメーターポートがない場合は、ブレークポイントの時刻でブロックを分けて値を切り替え、値が連続的に変化している区間では
+    /// AUTOMATION_RAMP_FRAMES フレームごとに値を更新します。同じパラメーターに割り当て済みの場合は置き換えます。
+    ///
+    /// # 引数
+    /// * `node_id` - 対象ノードのID
+    /// * `parameter_id` - パラメーター ID
+    /// * `lane` - ブレークポイントの列
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn set_automation(
+        &mut self,
+        node_id: NodeId,
+        parameter_id: &str,
+        lane: AutomationLane,
+    ) -> Result<(), String> {
+        let node = self
+            .nodes
+            .get(&node_id)
+            .ok_or_else(|| self.node_not_found(node_id))?;
+        if node.get_parameter(parameter_id).is_none() {
+            return Err(format!(
+                "ノードID {} にパラメーター {} がありません",
+                node_id, parameter_id
+            ));
+        }
+        let port = node
+            .parameter_port(parameter_id)
+            .filter(|&port| port > 0 && port < node.num_input_ports());
+
+        match self
+            .automation
+            .iter_mut()
+            .find(|route| route.node_id == node_id && route.parameter_id == parameter_id)
+        {
+            Some(route) => route.lane = lane,
+            None => self.automation.push(AutomationRoute {
+                node_id,
+                parameter_id: parameter_id.to_string(),
+                port,
+                lane,
+            }),
+        }
+        Ok(())
+    }
+
+    /// パラメーターに割り当てたオートメーションを取得する
+    pub fn automation(&self, node_id: NodeId, parameter_id: &str) -> Option<&AutomationLane> {
+        self.automation
+            .iter()
+            .find(|route| route.node_id == node_id && route.parameter_id == parameter_id)
+            .map(|route| &route.lane)
+    }
+
+    /// パラメーターに割り当てたオートメーションを削除する（パラメーターは現在の値のまま残る）
+    ///
+    /// # 戻り値
+    /// * 削除した場合は `Some` でオートメーションを返し、存在しない場合は `None` を返す
+    pub fn remove_automation(
+        &mut self,
+        node_id: NodeId,
+        parameter_id: &str,
+    ) -> Option<AutomationLane> {
+        let idx = self
+            .automation
+            .iter()
+            .position(|route| route.node_id == node_id && route.parameter_id == parameter_id)?;
+        Some(self.automation.remove(idx).lane)
+    }
+
+    /// オートメーションの位置を設定する
+    ///
+    /// 位置は process のたびに処理したフレーム数だけ進みます。再生位置を移動する場合や、
+    /// オフラインレンダリングを最初からやり直す場合に設定します。
+    ///
+    /// # 引数
+    /// * `position` - 次のブロックの先頭の位置（サンプル数）
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行わないため、process 呼び出しの直前にリアルタイムスレッドから呼び出すことができます。
+    pub fn set_automation_position(&mut self, position: u64) {
+        self.automation_position = position;
+    }
+
+    /// 次のブロックの先頭のオートメーションの位置（サンプル数）
+    pub fn automation_position(&self) -> u64 {
+        self.automation_position
+    }
+
+    /// ゲインステージングの分析を有効または無効にする
+    ///
+    /// 有効な間は process のブロックごとに、各ノードのメイン入力と出力のピークを記録します。
+    /// 有効にすると、それまでの記録は消去されます。
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn set_gain_staging_enabled(&mut self, enabled: bool) {
+        self.gain_stages = enabled.then(|| {
+            let mut gain_stages: SlotMap<NodeId, _> = self
+                .nodes
+                .keys()
+                .map(|&node_id| (node_id, GainStage::new(node_id)))
+                .collect();
+            // 固定容量モードでは、上限までのノードの記録先を確保しておく
+            if let Some(capacity) = self.capacity {
+                gain_stages.reserve(capacity.max_nodes);
+            }
+            gain_stages
+        });
+    }
+
+    /// ゲインステージングの分析の記録を消去する
+    pub fn reset_gain_staging(&mut self) {
+        if let Some(gain_stages) = self.gain_stages.as_mut() {
+            for stage in gain_stages.values_mut() {
+                *stage = GainStage::new(stage.node_id);
+            }
+        }
+    }
+
+    /// ゲインステージングのレポートを取得する
+    ///
+    /// # 戻り値
+    /// * ノードごとの記録（ノードIDの昇順）。分析が無効の場合は空
+    ///
+    /// # 実装時の注意
+    /// 内部でクリップしているノードは、GainStage::clips で見つけられます。
+    /// 出力ノードより前でクリップしている場合、最終出力が 0 dBFS 以下でも歪みの原因になります。
+    pub fn gain_staging_report(&self) -> Vec<GainStage> {
+        let mut report: Vec<GainStage> = self
+            .gain_stages
+            .iter()
+            .flat_map(|gain_stages| gain_stages.values().copied())
+            .collect();
+        report.sort_unstable_by_key(|stage| stage.node_id);
+        report
+    }
+
+    /// ノードにノートイベントを送る
+    ///
+    /// イベントは次の process まで保持し、`timing`（次の process に渡すバッファの先頭からのオフセット）を含むブロックの
+    /// 処理の直前に、そのブロックの先頭からのオフセットに直してノードに渡します。バッファを内部のブロックに分けて
+    /// 処理する場合も、イベントは指定したサンプルで処理されます。バッファの範囲外のイベントは最後のブロックで渡します。
+    /// add_midi_route で MIDI の送り先を接続したノードの場合は、ノードが出力したイベントを送り先にも送ります。
+    ///
+    /// # 引数
+    /// * `node_id` - 送り先ノードのID
+    /// * `event` - ノートイベント
+    ///
+    /// # 戻り値
+    /// * ノードが存在する場合は `true`、存在しない場合や、ためておけるイベント数
+    ///   （MAX_PENDING_NOTE_EVENTS）を超えた場合は `false`
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行わないため、process 呼び出しの直前にリアルタイムスレッドから呼び出すことができます。
+    pub fn send_note_event(&mut self, node_id: NodeId, event: NoteEvent) -> bool {
+        if !self.nodes.contains_key(&node_id) {
+            return false;
+        }
+        // 容量を超えたイベントは、アロケーションを避けるため破棄する
+        if self.pending_note_events.len() >= MAX_PENDING_NOTE_EVENTS {
+            return false;
+        }
+        self.pending_note_events.push((node_id, event));
+        true
+    }
+
+    /// 処理するブロックに含まれる、保持しているノートイベントをノードに渡す
+    ///
+    /// # 引数
+    /// * `buffer_size` - 処理するブロックのフレーム数
+    fn dispatch_note_events(&mut self, buffer_size: usize) {
+        let block_start = self.block_position;
+        let block_end = block_start + buffer_size;
+        let is_last_block = block_end >= self.host_num_frames;
+        // 1 回の走査で、このブロックのイベントを渡しながら残りのイベントを追加した順のまま前に詰める
+        let mut num_kept = 0;
+        for idx in 0..self.pending_note_events.len() {
+            let (node_id, event) = self.pending_note_events[idx];
+            let timing = event.timing() as usize;
+            if !is_last_block && timing >= block_end {
+                self.pending_note_events[num_kept] = (node_id, event);
+                num_kept += 1;
+                continue;
+            }
+            let timing = timing.saturating_sub(block_start) as u32;
+            self.deliver_note_event(node_id, event.with_timing(timing));
+        }
+        self.pending_note_events.truncate(num_kept);
+    }
+
+    /// MIDI の送り先を持つノードに、このブロックで送り出すノートイベントを生成させて送り先に渡す
+    ///
+    /// # 引数
+    /// * `buffer_size` - 処理するブロックのフレーム数
+    fn generate_note_events(&mut self, buffer_size: usize) {
+        for route_idx in 0..self.midi_routes.len() {
+            let from_id = self.midi_routes[route_idx].0;
+            // 同じ送り元のノードには 1 度だけ生成させる
+            if self.midi_routes[..route_idx]
+                .iter()
+                .any(|&(route_from, _)| route_from == from_id)
+            {
+                continue;
+            }
+            let Self {
+                nodes,
+                midi_routes,
+                routed_note_events,
+                ..
+            } = self;
+            let Some(node) = nodes.get_mut(&from_id) else {
+                continue;
+            };
+            routed_note_events.clear();
+            node.generate_note_events(buffer_size, &mut |event| {
+                let event =
+                    event.with_timing(event.timing().min(buffer_size.saturating_sub(1) as u32));
+                for &(_, to_id) in midi_routes
+                    .iter()
+                    .filter(|&&(route_from, _)| route_from == from_id)
+                {
+                    // 容量を超えたイベントは、アロケーションを避けるため破棄する
+                    if routed_note_events.len() < MAX_ROUTED_NOTE_EVENTS {
+                        routed_note_events.push((to_id, event));
+                    }
+                }
+            });
+            self.route_note_events();
+        }
+    }
+
+    /// ノードにノートイベントを渡し、MIDI の接続に従って送り先にも渡す
+    fn deliver_note_event(&mut self, node_id: NodeId, event: NoteEvent) {
+        self.routed_note_events.clear();
+        self.routed_note_events.push((node_id, event));
+        self.route_note_events();
+    }
+
+    /// routed_note_events にためたノートイベントを順にノードに渡し、MIDI の接続に従って送り先にも渡す
+    fn route_note_events(&mut self) {
+        let Self {
+            nodes,
+            rate_converters,
+            midi_routes,
+            routed_note_events,
+            ..
+        } = self;
+        let mut idx = 0;
+        while let Some(&(node_id, event)) = routed_note_events.get(idx) {
+            idx += 1;
+            let Some(node) = nodes.get_mut(&node_id) else {
+                continue;
+            };
+            // 内部のレートで処理するノードには、タイミングを内部のレートのサンプル数に換算して送る
+            let rate_factor = if rate_converters.contains_key(&node_id) {
+                node.rate_factor()
+            } else {
+                1.0
+            };
+            let event = event.with_timing((event.timing() as f32 * rate_factor) as u32);
+            if !midi_routes.iter().any(|&(from_id, _)| from_id == node_id) {
+                node.handle_note_event(&event);
+                continue;
+            }
+            node.process_note_event(&event, &mut |output| {
+                let output = output.with_timing((output.timing() as f32 / rate_factor) as u32);
+                for &(_, to_id) in midi_routes
+                    .iter()
+                    .filter(|&&(from_id, _)| from_id == node_id)
+                {
+                    // 容量を超えたイベントは、アロケーションを避けるため破棄する
+                    if routed_note_events.len() < MAX_ROUTED_NOTE_EVENTS {
+                        routed_note_events.push((to_id, output));
+                    }
+                }
+            });
+        }
+    }
+
+    /// MIDI の接続を追加する
+    ///
+    /// 送り元のノードに send_note_event で送ったノートイベントを、送り元のノードの process_note_event で
+    /// 変換してから送り先のノードに送ります。MIDI の接続はオーディオのエッジとは独立しています。
+    ///
+    /// # 引数
+    /// * `from_id` - 送り元のノードID
+    /// * `to_id` - 送り先のノードID
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn add_midi_route(&mut self, from_id: NodeId, to_id: NodeId) -> Result<(), String> {
+        for node_id in [from_id, to_id] {
+            if !self.nodes.contains_key(&node_id) {
+                return Err(self.node_not_found(node_id));
+            }
+        }
+        if self.midi_routes.contains(&(from_id, to_id)) {
+            return Err(format!(
+                "MIDI の接続 {} -> {} は既に存在します",
+                from_id, to_id
+            ));
+        }
+        // 送り先から送り元に MIDI の接続をたどれる場合は循環する
+        let mut stack = vec![to_id];
+        let mut visited = HashSet::new();
+        while let Some(node_id) = stack.pop() {
+            if node_id == from_id {
+                return Err(format!(
+                    "MIDI の接続 {} -> {} は循環を作ります",
+                    from_id, to_id
+                ));
+            }
+            if visited.insert(node_id) {
+                stack.extend(
+                    self.midi_routes
+                        .iter()
+                        .filter(|&&(route_from, _)| route_from == node_id)
+                        .map(|&(_, route_to)| route_to),
+                );
+            }
+        }
+        self.midi_routes.push((from_id, to_id));
+        Ok(())
+    }
+
+    /// MIDI の接続を削除する
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `true`、存在しない場合は `false`
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn remove_midi_route(&mut self, from_id: NodeId, to_id: NodeId) -> bool {
+        let len = self.midi_routes.len();
+        self.midi_routes.retain(|&route| route != (from_id, to_id));
+        self.midi_routes.len() != len
+    }
+
+    /// MIDI の接続の一覧（送り元ID, 送り先ID）
+    pub fn midi_routes(&self) -> &[(NodeId, NodeId)] {
+        &self.midi_routes
+    }
+
+    /// フィードバックのエッジを追加する
+    ///
+    /// 接続元ノードの出力を 1 ブロック遅らせて、接続先ノードの入力に加算します。通常のエッジと異なり循環を作れるため、
+    /// 接続元ノードの出力を自身に戻すこともできます。遅延はブロックの長さに依存するため、1 サンプルの遅延で
+    /// フィードバックさせたい場合は、create_feedback_region でノードをフィードバックリージョンにまとめてください。
+    /// 同じノード間のフィードバックのエッジが既に存在する場合は、接続先ポートとゲインを置き換えます。
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `to_id` - 接続先ノードのID
+    /// * `port` - 接続先ノードの入力ポート番号（0 はメイン入力）
+    /// * `gain` - 接続元ノードの出力に掛けるゲイン
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn add_feedback_edge(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        port: usize,
+        gain: f32,
+    ) -> Result<(), String> {
+        self.add_feedback_edge_tap(
+            from_id,
+            to_id,
+            EdgeTap {
+                port,
+                gain,
+                ..EdgeTap::DEFAULT
+            },
+        )
+    }
+
+    /// 接続先ポートとゲインを指定してフィードバックのエッジを追加する
+    fn add_feedback_edge_tap(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        tap: EdgeTap,
+    ) -> Result<(), String> {
+        if !self.nodes.contains_key(&from_id) {
+            return Err(self.node_not_found(from_id));
+        }
+        let num_ports = self
+            .nodes
+            .get(&to_id)
+            .ok_or_else(|| self.node_not_found(to_id))?
+            .num_input_ports();
+        if tap.port >= num_ports {
+            return Err(format!(
+                "ノードID {} には入力ポート {} がありません（ポート数: {}）",
+                to_id, tap.port, num_ports
+            ));
+        }
+
+        if let Some(edge) = self
+            .feedback_edges
+            .iter_mut()
+            .find(|edge| edge.from_id == from_id && edge.to_id == to_id)
+        {
+            edge.tap = tap;
+            return Ok(());
+        }
+        let mut buffer = OwnedAudioBuffer::new(self.num_channels, self.max_buffer_size);
+        buffer.set_num_frames(0);
+        self.feedback_edges.push(FeedbackEdge {
+            from_id,
+            to_id,
+            tap,
+            buffer,
+        });
+        Ok(())
+    }
+
+    /// フィードバックのエッジを削除する
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `true`、存在しない場合は `false`
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn remove_feedback_edge(&mut self, from_id: NodeId, to_id: NodeId) -> bool {
+        let len = self.feedback_edges.len();
+        self.feedback_edges
+            .retain(|edge| edge.from_id != from_id || edge.to_id != to_id);
+        self.feedback_edges.len() != len
+    }
+
+    /// フィードバックのエッジの一覧（接続元ID, 接続先ID）
+    pub fn feedback_edges(&self) -> Vec<(NodeId, NodeId)> {
+        self.feedback_edges
+            .iter()
+            .map(|edge| (edge.from_id, edge.to_id))
+            .collect()
+    }
+
+    /// トランスポートを設定する
+    ///
+    /// プラグインでは、ホストから受け取ったテンポや再生位置をブロックごとに設定します。
+    /// 設定しない場合も、再生中であれば process のたびにブロックの長さだけ位置が進みます。
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行わないため、process 呼び出しの直前にリアルタイムスレッドから呼び出すことができます。
+    pub fn set_transport(&mut self, transport: Transport) {
+        self.transport = transport;
+    }
+
+    /// 次のブロックの先頭のトランスポートを取得する
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    /// ノート番号から周波数への変換に使う調律を設定し、全てのノードに渡す
+    ///
+    /// デフォルトは 12 平均律です。後から追加したノードにも、追加時に同じ調律が渡されます。
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行わないため、リアルタイムスレッドから呼び出すことができます。
+    pub fn set_tuning(&mut self, tuning: Tuning) {
+        self.tuning = tuning;
+        for node in self.nodes.values_mut() {
+            node.set_tuning(&self.tuning);
+        }
+    }
+
+    /// ノート番号から周波数への変換に使う調律を取得する
+    pub fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
+
+    /// サイドチェーン入力を受け取るノードを設定する
+    ///
+    /// 設定したノードには、エッジからの入力の代わりに set_sidechain_input で渡したバッファが入力されます。
+    /// 通常は InputNode を追加して設定し、その出力をサイドチェーンで制御したいノードに接続します。
+    ///
+    /// # 引数
+    /// * `node_id` - サイドチェーン入力を受け取るノード。`None` の場合はサイドチェーン入力を使わない
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、ノードが存在しない場合は `Err` でエラーメッセージを返す
+    pub fn set_sidechain_node(&mut self, node_id: Option<NodeId>) -> Result<(), String> {
+        if let Some(node_id) = node_id
+            && !self.nodes.contains_key(&node_id)
+        {
+            return Err(self.node_not_found(node_id));
+        }
+        self.sidechain_node_id = node_id;
+        Ok(())
+    }
+
+    /// サイドチェーン入力を受け取るノード
+    pub fn sidechain_node(&self) -> Option<NodeId> {
+        self.sidechain_node_id
+    }
+
+    /// 次の process で使うサイドチェーン入力を設定する
+    ///
+    /// チャンネル数がグラフと異なる場合はグラフのチャンネル数に変換し、prepare で指定した最大バッファサイズを
+    /// 超える部分は捨てます。次の process で読み切った後のブロックや、設定しなかった場合は無音になります。
+    ///
+    /// # 引数
+    /// * `input` - サイドチェーン入力（インターリーブ）
+    ///
+    /// # 実装時の注意
+    /// この関数はリアルタイムスレッドから、process の直前に呼び出されることを想定しています。
+    pub fn set_sidechain_input(&mut self, input: &AudioBuffer) {
+        let frames = if input.num_channels() == 0 {
+            0
+        } else {
+            input.num_frames().min(self.sidechain_buffer.max_frames())
+        };
+        self.sidechain_buffer.set_num_frames(frames);
+        if frames > 0 {
+            audio_buffer_utils::convert_channels(
+                input,
+                &mut self.sidechain_buffer.as_audio_buffer(),
+            );
+        }
+        self.sidechain_position = 0;
+    }
+
+    /// 出力ノードを追加の出力バスに割り当てる
+    ///
+    /// 割り当てたノードの出力は、process のたびに出力バスのバッファにコピーされ、output_bus で読み出せます。
+    /// プラグインのホストでは、ドライやウェット、クリックなどのグラフの枝を別々のチャンネルに出力するために使います。
+    ///
+    /// # 引数
+    /// * `bus` - 出力バスの番号（MAX_OUTPUT_BUSES 未満）
+    /// * `node_id` - 割り当てるノード。`None` の場合は割り当てを解除する
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、バスの番号が範囲外かノードが存在しない場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn set_output_bus(&mut self, bus: usize, node_id: Option<NodeId>) -> Result<(), String> {
+        if bus >= MAX_OUTPUT_BUSES {
+            return Err(format!(
+                "出力バスの番号が範囲外です: {}（0～{}）",
+                bus,
+                MAX_OUTPUT_BUSES - 1
+            ));
+        }
+        if let Some(node_id) = node_id
+            && !self.nodes.contains_key(&node_id)
+        {
+            return Err(self.node_not_found(node_id));
+        }
+        while self.output_buses.len() <= bus {
+            self.output_buses.push(OutputBus {
+                node_id: None,
+                buffer: OwnedAudioBuffer::new(self.num_channels, self.max_buffer_size),
+            });
+        }
+        let output_bus = &mut self.output_buses[bus];
+        output_bus.node_id = node_id;
+        output_bus.buffer.clear();
+        Ok(())
+    }
+
+    /// 出力バスに割り当てたノード
+    pub fn output_bus_node(&self, bus: usize) -> Option<NodeId> {
+        self.output_buses
+            .get(bus)
+            .and_then(|output_bus| output_bus.node_id)
+    }
+
+    /// 最後の process で出力バスに書き込まれた出力（インターリーブ、グラフのチャンネル数）
+    ///
+    /// prepare で指定した最大バッファサイズを超える部分は含みません。
+    ///
+    /// # 戻り値
+    /// * ノードを割り当てた出力バスの場合は `Some` で返す
+    pub fn output_bus(&self, bus: usize) -> Option<&[f32]> {
+        let output_bus = self.output_buses.get(bus)?;
+        output_bus.node_id?;
+        Some(output_bus.buffer.as_slice())
+    }
+
+    /// グラフを処理する（トポロジカルソートに基づいて各ノードを処理）
+    ///
+    /// prepare で指定した最大バッファサイズ（prepare_with_block_size でブロックサイズを指定した場合はブロックサイズ）を
+    /// 超えるバッファは、そのサイズずつに分けて順に処理します。
+    /// バッファのチャンネル数がグラフのチャンネル数と異なる場合は、グラフのチャンネル数に変換して処理します
+    /// （requested_num_channels を参照）。
+    ///
+    /// # 引数
+    /// * `buffer` - 処理するオーディオバッファ
+    ///
+    /// # 実装時の注意
+    /// この関数はリアルタイムスレッドから呼び出されることを想定しています。
+    /// 実装者はメモリアロケーションなどの遅延を生む処理を行わないように注意してください。
+    pub fn process(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        input_node_id: NodeId,
+        output_node_id: NodeId,
+    ) {
+        let num_channels = buffer.num_channels();
+        if num_channels == 0 {
+            self.diagnostics
+                .report(DiagnosticCode::InvalidChannelCount, None);
+            return;
+        }
+
+        // 出力バスとノートイベントのタイミングは、バッファの先頭を基準にする
+        self.block_position = 0;
+        self.host_num_frames = buffer.num_frames();
+        for output_bus in self.output_buses.iter_mut() {
+            let frames = buffer.num_frames().min(output_bus.buffer.max_frames());
+            output_bus.buffer.set_num_frames(frames);
+        }
+
+        // チャンネル数が変わった場合は、set_num_channels で再構成されるまで変換して処理する
+        self.host_num_channels = num_channels;
+        if num_channels != self.num_channels {
+            self.process_converted(buffer, input_node_id, output_node_id);
+            return;
+        }
+
+        // ブロックサイズを超えるバッファは、ブロックサイズずつに分けて順に処理する（残りは短いブロックで処理する）
+        let buffer_size = buffer.num_frames();
+        let block_size = self.block_size();
+        if buffer_size > block_size {
+            if block_size == 0 {
+                // prepare されていないため処理できない。無音を出力する
+                audio_buffer_utils::clear_buffer(buffer);
+                return;
+            }
+            let chunk_len = block_size * num_channels;
+            for chunk in buffer.as_mut_slice().chunks_mut(chunk_len) {
+                let frames = chunk.len() / num_channels;
+                self.process_automated(
+                    &mut AudioBuffer::new(num_channels, frames, chunk),
+                    input_node_id,
+                    output_node_id,
+                );
+            }
+            return;
+        }
+        self.process_automated(buffer, input_node_id, output_node_id);
+    }
+
+    /// グラフと異なるチャンネル数のバッファを、グラフのチャンネル数に変換して処理する
+    fn process_converted(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        input_node_id: NodeId,
+        output_node_id: NodeId,
+    ) {
+        let host_channels = buffer.num_channels();
+        let block_size = self.block_size().min(self.tmp_channel_buffer.max_frames());
+        if block_size == 0 {
+            // prepare されていないため処理できない。無音を出力する
+            audio_buffer_utils::clear_buffer(buffer);
+            return;
+        }
+
+        // process_block が self を借用するため、一時バッファを取り出しておく（容量 0 の置き換えは確保を伴わない）
+        let mut converted =
+            core::mem::replace(&mut self.tmp_channel_buffer, OwnedAudioBuffer::new(0, 0));
+        for chunk in buffer.as_mut_slice().chunks_mut(block_size * host_channels) {
+            let frames = chunk.len() / host_channels;
+            let mut host_buffer = AudioBuffer::new(host_channels, frames, chunk);
+            converted.set_num_frames(frames);
+            let mut graph_buffer = converted.as_audio_buffer();
+            audio_buffer_utils::convert_channels(&host_buffer, &mut graph_buffer);
+            self.process_automated(&mut graph_buffer, input_node_id, output_node_id);
+            audio_buffer_utils::convert_channels(&graph_buffer, &mut host_buffer);
+        }
+        self.tmp_channel_buffer = converted;
+    }
+
+    /// オートメーションの値を適用しながら、最大バッファサイズ以下のバッファを処理する
+    ///
+    /// パラメーターポートのないパラメーターのオートメーションについて、ブレークポイントの時刻でブロックを分け、
+    /// 値が連続的に変化している間は AUTOMATION_RAMP_FRAMES フレームごとに分けて処理します。
+    /// 分けたブロックの先頭での値は、process_block でノードを処理する直前にパラメーターに設定します。
+    fn process_automated(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        input_node_id: NodeId,
+        output_node_id: NodeId,
+    ) {
+        if self.automation.is_empty() {
+            self.process_block(buffer, input_node_id, output_node_id);
+            return;
+        }
+
+        let num_channels = buffer.num_channels();
+        let num_frames = buffer.num_frames();
+        let mut start = 0;
+        while start < num_frames {
+            let position = self.automation_position;
+            let mut end = num_frames;
+            // パラメーターポートのあるパラメーターは、サンプルごとの値を process_block で渡すため分けない
+            for route in self.automation.iter().filter(|route| route.port.is_none()) {
+                if let Some(next) = route.lane.next_breakpoint_time(position) {
+                    let frames = (next - position).min((end - start) as u64) as usize;
+                    end = start + frames;
+                }
+                if route.lane.is_ramping(position) {
+                    end = end.min(start + AUTOMATION_RAMP_FRAMES);
+                }
+            }
+            let chunk = &mut buffer.as_mut_slice()[start * num_channels..end * num_channels];
+            self.process_block(
+                &mut AudioBuffer::new(num_channels, end - start, chunk),
+                input_node_id,
+                output_node_id,
+            );
+            start = end;
+        }
+    }
+
+    /// 最大バッファサイズ以下のバッファを処理する
+    fn process_block(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        input_node_id: NodeId,
+        output_node_id: NodeId,
+    ) {
+        let num_channels = buffer.num_channels();
+        let buffer_size = buffer.num_frames();
+
+        // 問題は文字列を作らずに記録し、処理は続ける
+        if !self.nodes.contains_key(&input_node_id) {
+            self.diagnostics
+                .report(DiagnosticCode::MissingInputNode, Some(input_node_id));
+        }
+        if !self.nodes.contains_key(&output_node_id) {
+            self.diagnostics
+                .report(DiagnosticCode::MissingOutputNode, Some(output_node_id));
+        }
+
+        // A/B モーフの位置をブロックの長さだけ進め、変化中であればパラメーターを更新
+        if !self.morph_routes.is_empty() && self.morph_position.is_smoothing() {
+            for _ in 0..buffer_size {
+                self.morph_position.next_value();
+            }
+            self.apply_morph();
+        }
+
+        // このブロックに含まれるノートイベントを、ブロックの先頭からのタイミングでノードに渡す
+        if !self.pending_note_events.is_empty() {
+            self.dispatch_note_events(buffer_size);
+        }
+
+        // ブロックの先頭のトランスポートを全てのノードに渡す
+        for node in self.nodes.values_mut() {
+            node.update_transport(&self.transport);
+        }
+
+        // ノードが生成したノートイベントを、MIDI の接続に従って送り先に渡す
+        if !self.midi_routes.is_empty() {
+            self.generate_note_events(buffer_size);
+        }
+
+        let graph = self.graph.get_real_time_safe_interface();
+
+        // 外部バッファは入力ノードへの入力として読み出し、最後に出力ノードの出力で上書きする
+
+        // オーディオ処理では入力から出力への順序で処理するため、トポロジカル順序を反転
+        let processing_order = graph.get_reverse_topological_order();
+
+        // 入力ノードから出力ノードへの順序でノードを処理
+        // （ノードごとの情報は SlotMap からインデックスで引き、ハッシュの計算を行わない）
+        for &node_id in processing_order {
+            // このノードへの入力のエッジ
+            let inputs = self
+                .input_taps
+                .get(&node_id)
+                .map_or(&[][..], |inputs| inputs.as_slice());
+
+            // 一時入力バッファをクリア
+            self.tmp_input_buffer.set_num_frames(buffer_size);
+            let mut tmp_input_buffer = self.tmp_input_buffer.as_audio_buffer();
+            audio_buffer_utils::clear_buffer(&mut tmp_input_buffer);
+
+            // 追加の入力ポート用の一時バッファをクリア
+            let num_ports = match self.nodes.get(&node_id) {
+                Some(node) => node.num_input_ports().min(self.tmp_port_buffers.len() + 1),
+                None => 1,
+            };
+            for port_idx in 0..num_ports - 1 {
+                self.tmp_port_buffers[port_idx][..num_channels * buffer_size].fill(0.0);
+                self.tmp_port_connected[port_idx] = false;
+                self.tmp_port_counts[port_idx] = 0;
+            }
+
+            // 入力ノードからの出力を合成方法に従ってまとめ、接続先ポートの一時入力バッファに格納
+            let mut summing_mode = self
+                .summing_modes
+                .get(&node_id)
+                .copied()
+                .unwrap_or(SummingMode::Sum);
+            let policy: &mut dyn MixPolicy = match self.mix_policies.get_mut(&node_id) {
+                Some(policy) => policy.as_mut(),
+                None => &mut summing_mode,
+            };
+            let mut main_count = 0;
+            // 並列のエッジごとに、接続元ノードの出力ポートの出力を接続先ポートの入力に合成
+            for &InputTap {
+                from_id: input_id,
+                tap:
+                    EdgeTap {
+                        from_port,
+                        port,
+                        gain,
+                        offset,
+                    },
+            } in inputs
+            {
+                let input_buffer = if from_port == 0 {
+                    self.node_outputs.get_mut(&input_id)
+                } else {
+                    self.port_outputs
+                        .get_mut(&input_id)
+                        .and_then(|outputs| outputs.get_mut(from_port - 1))
+                };
+                let Some(input_buffer) = input_buffer else {
+                    self.diagnostics
+                        .report(DiagnosticCode::MissingOutputBuffer, Some(input_id));
+                    continue;
+                };
+                input_buffer.set_num_frames(buffer_size);
+                let input_buffer = input_buffer.as_audio_buffer();
+                if port == 0 {
+                    policy.mix(0, input_id, &input_buffer, gain, &mut tmp_input_buffer);
+                    add_offset(tmp_input_buffer.as_mut_slice(), offset);
+                    main_count += 1;
+                } else if port < num_ports {
+                    let port_buffer =
+                        &mut self.tmp_port_buffers[port - 1][..num_channels * buffer_size];
+                    policy.mix(
+                        port,
+                        input_id,
+                        &input_buffer,
+                        gain,
+                        &mut AudioBuffer::new(num_channels, buffer_size, port_buffer),
+                    );
+                    add_offset(port_buffer, offset);
+                    self.tmp_port_connected[port - 1] = true;
+                    self.tmp_port_counts[port - 1] += 1;
+                }
+            }
+
+            // 全ての入力を合成した後の処理を、ポートごとに行う
+            policy.finish(0, main_count, &mut tmp_input_buffer);
+            for port in 1..num_ports {
+                policy.finish(
+                    port,
+                    self.tmp_port_counts[port - 1],
+                    &mut AudioBuffer::new(
+                        num_channels,
+                        buffer_size,
+                        &mut self.tmp_port_buffers[port - 1][..num_channels * buffer_size],
+                    ),
+                );
+            }
+
+            // フィードバックのエッジは、接続元ノードの前のブロックの出力を合成方法によらず加算する
+            for edge in self.feedback_edges.iter() {
+                if edge.to_id != node_id {
+                    continue;
+                }
+                let EdgeTap {
+                    port, gain, offset, ..
+                } = edge.tap;
+                let dst = if port == 0 {
+                    tmp_input_buffer.as_mut_slice()
+                } else if port < num_ports {
+                    self.tmp_port_connected[port - 1] = true;
+                    &mut self.tmp_port_buffers[port - 1][..num_channels * buffer_size]
+                } else {
+                    continue;
+                };
+                for (dst, src) in dst.iter_mut().zip(edge.buffer.as_slice()) {
+                    *dst += src * gain + offset;
+                }
+            }
+
+            // オートメーションの値を設定し、パラメーターポートがあればブロックの先頭の値からの差をサンプルごとに加算する
+            for route in self.automation.iter() {
+                if route.node_id != node_id {
+                    continue;
+                }
+                let position = self.automation_position;
+                let Some(value) = route.lane.value_at(position) else {
+                    continue;
+                };
+                set_node_parameter(
+                    &mut self.nodes,
+                    &mut self.modulations,
+                    node_id,
+                    &route.parameter_id,
+                    value,
+                );
+                let Some(port) = route.port.filter(|&port| port < num_ports) else {
+                    continue;
+                };
+                // 範囲に収めた後の値を基準にして、ポートの信号と合わせた値がオートメーションの値になるようにする
+                let base = self
+                    .nodes
+                    .get(&node_id)
+                    .and_then(|node| node.get_parameter(&route.parameter_id))
+                    .unwrap_or(value);
+                self.tmp_port_connected[port - 1] = true;
+                let dst = &mut self.tmp_port_buffers[port - 1][..num_channels * buffer_size];
+                for (i, frame) in dst.chunks_exact_mut(num_channels.max(1)).enumerate() {
+                    let delta = route.lane.value_at(position + i as u64).unwrap_or(value) - base;
+                    for sample in frame {
+                        *sample += delta;
+                    }
+                }
+            }
+
+            // 入力ノードの場合、外部入力バッファからデータをコピー
+            if node_id == input_node_id {
+                audio_buffer_utils::copy_buffer(buffer, &mut tmp_input_buffer);
+            }
+
+            // サイドチェーン入力を受け取るノードの場合、このブロックの範囲のサイドチェーン入力をコピー
+            if Some(node_id) == self.sidechain_node_id {
+                let sidechain = self.sidechain_buffer.as_slice();
+                let start = (self.sidechain_position * num_channels).min(sidechain.len());
+                let end =
+                    ((self.sidechain_position + buffer_size) * num_channels).min(sidechain.len());
+                let dst = tmp_input_buffer.as_mut_slice();
+                dst[..end - start].copy_from_slice(&sidechain[start..end]);
+                dst[end - start..].fill(0.0);
+            }
+
+            // 現在のノードの出力バッファへの参照を取得
+            let node_output = match self.node_outputs.get_mut(&node_id) {
+                Some(output) => output,
+                None => {
+                    self.diagnostics
+                        .report(DiagnosticCode::MissingOutputBuffer, Some(node_id));
+                    continue;
+                }
+            };
+
+            // ゲインステージングの分析が有効な場合、処理前のメイン入力のピークを記録
+            let input_peak = match self.gain_stages {
+                Some(_) => audio_buffer_utils::peak(&tmp_input_buffer),
+                None => 0.0,
+            };
+
+            // モジュレーションの接続に従って、このノードのパラメーターを更新
+            for route in self.modulations.iter() {
+                if route.target_id != node_id {
+                    continue;
+                }
+                let Some(value) = self
+                    .nodes
+                    .get(&route.source_id)
+                    .and_then(|source| source.modulation_output())
+                else {
+                    continue;
+                };
+                if let Some(target) = self.nodes.get_mut(&node_id) {
+                    target.set_parameter(
+                        &route.parameter_id,
+                        route.base_value + value * route.amount,
+                    );
+                }
+            }
+
+            // 追加の出力ポートのバッファを、このブロックの長さの無音にする
+            let port_outputs = match self.port_outputs.get_mut(&node_id) {
+                Some(outputs) => {
+                    for output in outputs.iter_mut() {
+                        output.set_num_frames(buffer_size);
+                        output.clear();
+                    }
+                    outputs.as_mut_slice()
+                }
+                None => &mut [],
+            };
+
+            // 現在のノードの処理を呼び出し
+            if let Some(node) = self.nodes.get_mut(&node_id) {
+                let control_interval = match node.process_rate() {
+                    ProcessRate::Audio => None,
+                    ProcessRate::Block => Some(buffer_size),
+                    ProcessRate::Every(interval) => Some(interval),
+                };
+                if let Some(interval) = control_interval {
+                    // 前回のブロックの最後の出力値から補間する
+                    let previous = node_output.as_slice().last().copied().unwrap_or(0.0);
+                    process_control_rate(node.as_mut(), interval, previous, &mut tmp_input_buffer);
+                } else if let Some(converter) = self.rate_converters.get_mut(&node_id) {
+                    // 内部のレートに変換して処理し、グラフのレートに戻す
+                    converter.process(&mut tmp_input_buffer, |b| node.process(b));
+                } else if node.preferred_layout() == BufferLayout::Planar
+                    && num_channels <= MAX_PLANAR_CHANNELS
+                {
+                    // プレーナー形式に変換して処理し、インターリーブに戻す
+                    let mut channels = self
+                        .tmp_planar_buffer
+                        .chunks_mut(self.max_buffer_size.max(1));
+                    let mut slices: [&mut [f32]; MAX_PLANAR_CHANNELS] =
+                        core::array::from_fn(|_| {
+                            channels
+                                .next()
+                                .map(|channel| &mut channel[..buffer_size])
+                                .unwrap_or_default()
+                        });
+                    let mut planar = PlanarAudioBuffer::new(&mut slices[..num_channels]);
+                    audio_buffer_utils::deinterleave(&tmp_input_buffer, &mut planar);
+                    node.process_planar(&mut planar);
+                    audio_buffer_utils::interleave(&planar, &mut tmp_input_buffer);
+                } else {
+                    let inputs = InputPorts::new(
+                        &self.tmp_port_buffers[..num_ports - 1],
+                        &self.tmp_port_connected[..num_ports - 1],
+                        num_channels,
+                        buffer_size,
+                    );
+                    node.process_with_outputs(
+                        &mut tmp_input_buffer,
+                        &inputs,
+                        &mut OutputPorts::new(port_outputs),
+                    );
+                }
+            } else {
+                self.diagnostics
+                    .report(DiagnosticCode::MissingNode, Some(node_id));
+            }
+
+            // 処理結果をノードの出力バッファにコピー
+            node_output.set_num_frames(buffer_size);
+            audio_buffer_utils::copy_buffer(&tmp_input_buffer, &mut node_output.as_audio_buffer());
+
+            if let Some(stage) = self
+                .gain_stages
+                .as_mut()
+                .and_then(|gain_stages| gain_stages.get_mut(&node_id))
+            {
+                let output_peak = audio_buffer_utils::peak(&tmp_input_buffer);
+                stage.input_peak = stage.input_peak.max(input_peak);
+                stage.output_peak = stage.output_peak.max(output_peak);
+                if output_peak > 1.0 {
+                    stage.clipped_blocks += 1;
+                }
+                stage.blocks += 1;
+            }
+        }
+
+        // フィードバックのエッジの接続元ノードの出力を、次のブロックのために保存
+        for edge in self.feedback_edges.iter_mut() {
+            let Some(node_output) = self.node_outputs.get(&edge.from_id) else {
+                continue;
+            };
+            let frames = node_output.num_frames().min(edge.buffer.max_frames());
+            edge.buffer.set_num_frames(frames);
+            edge.buffer
+                .as_mut_slice()
+                .copy_from_slice(&node_output.as_slice()[..frames * num_channels]);
+        }
+
+        // 次のブロックのために再生位置とオートメーションの位置、サイドチェーン入力の読み出し位置を進める
+        self.transport.advance(buffer_size, self.sample_rate);
+        self.automation_position += buffer_size as u64;
+        self.sidechain_position += buffer_size;
+
+        // 出力バスに割り当てたノードの出力を、このブロックの範囲にコピー
+        for output_bus in self.output_buses.iter_mut() {
+            let Some(node_output) = output_bus
+                .node_id
+                .and_then(|node_id| self.node_outputs.get(&node_id))
+            else {
+                continue;
+            };
+            let src = node_output.as_slice();
+            let dst = output_bus.buffer.as_mut_slice();
+            let start = (self.block_position * num_channels).min(dst.len());
+            let len = (dst.len() - start).min(src.len());
+            dst[start..start + len].copy_from_slice(&src[..len]);
+        }
+        self.block_position += buffer_size;
 
         // 出力ノードの出力バッファへの参照を取得
         let out_node_output = match self.node_outputs.get_mut(&output_node_id) {
             Some(output) => output,
             None => {
-                debug_assert!(
-                    false,
-                    "出力ノードが見つかりません。output_node_id: {}",
-                    output_node_id
+                self.diagnostics
+                    .report(DiagnosticCode::MissingOutputBuffer, Some(output_node_id));
+                return;
+            }
+        };
+
+        // 出力ノードの出力を外部バッファにコピー
+        out_node_output.set_num_frames(buffer_size);
+        audio_buffer_utils::copy_buffer(&out_node_output.as_audio_buffer(), buffer);
+    }
+
+    /// 入力が無音になってから、出力ノードの出力が収まるまでのサンプル数
+    ///
+    /// 入力ノードから出力ノードまでの経路ごとに、各ノードのテールと遅延を合計した最大値です。
+    ///
+    /// # 引数
+    /// * `output_node_id` - 出力ノードのID
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn tail_samples(&self, output_node_id: NodeId) -> usize {
+        let mut tails: HashMap<NodeId, usize> = HashMap::new();
+        for &node_id in self.graph.get_reverse_topological_order() {
+            let own = self
+                .nodes
+                .get(&node_id)
+                .map_or(0, |node| node.tail_samples() + node.latency_samples())
+                + self
+                    .rate_converters
+                    .get(&node_id)
+                    .map_or(0, |converter| converter.latency_frames());
+            let upstream = self
+                .graph
+                .get_input_node_ids(node_id)
+                .iter()
+                .filter_map(|input_id| tails.get(input_id))
+                .max()
+                .copied()
+                .unwrap_or(0);
+            tails.insert(node_id, upstream + own);
+        }
+        tails.get(&output_node_id).copied().unwrap_or(0)
+    }
+
+    /// グラフの大きさと処理量の統計を取得する
+    ///
+    /// `samples_copied_per_block` は、エッジごとの入力の合成、ノードごとの出力バッファへのコピー、
+    /// 入力ノードへの外部入力のコピーと出力ノードからの出力のコピー、フィードバックのエッジと出力バスへのコピーを、
+    /// 最大バッファサイズとチャンネル数で数えた値です。prepare の前は 0 になります。
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn stats(&self) -> GraphStats {
+        let num_edges = self.input_taps.values().map(|inputs| inputs.len()).sum();
+
+        // 処理の順に、各ノードまでの最も長い経路のエッジ数を求める
+        let mut depths: SlotMap<NodeId, usize> = SlotMap::with_capacity(self.generations.len());
+        for &node_id in self.graph.get_reverse_topological_order() {
+            let depth = self
+                .graph
+                .get_input_node_ids(node_id)
+                .iter()
+                .filter_map(|input_id| depths.get(input_id))
+                .map(|depth| depth + 1)
+                .max()
+                .unwrap_or(0);
+            depths.insert(node_id, depth);
+        }
+        let longest_path = depths.values().copied().max().unwrap_or(0);
+
+        let buffer_samples =
+            |buffer: &OwnedAudioBuffer| buffer.max_frames() * buffer.num_channels();
+        let buffer_samples = self
+            .node_outputs
+            .values()
+            .map(buffer_samples)
+            .sum::<usize>()
+            + self
+                .port_outputs
+                .values()
+                .flatten()
+                .map(buffer_samples)
+                .sum::<usize>()
+            + self.spare_outputs.iter().map(buffer_samples).sum::<usize>()
+            + self
+                .feedback_edges
+                .iter()
+                .map(|edge| buffer_samples(&edge.buffer))
+                .sum::<usize>()
+            + self
+                .output_buses
+                .iter()
+                .map(|bus| buffer_samples(&bus.buffer))
+                .sum::<usize>()
+            + self.tmp_port_buffers.iter().map(Vec::len).sum::<usize>()
+            + buffer_samples(&self.tmp_input_buffer)
+            + buffer_samples(&self.tmp_channel_buffer)
+            + buffer_samples(&self.sidechain_buffer)
+            + self.tmp_planar_buffer.len();
+
+        let num_copies = num_edges
+            + self.nodes.len()
+            + 2
+            + self.feedback_edges.len() * 2
+            + self
+                .output_buses
+                .iter()
+                .filter(|bus| bus.node_id.is_some())
+                .count();
+
+        GraphStats {
+            num_nodes: self.nodes.len(),
+            num_edges,
+            num_feedback_edges: self.feedback_edges.len(),
+            longest_path,
+            buffer_bytes: buffer_samples * core::mem::size_of::<f32>(),
+            samples_copied_per_block: num_copies * self.max_buffer_size * self.num_channels,
+        }
+    }
+
+    /// オーディオスレッドが参照しているグラフの状態のスナップショットを取得する
+    ///
+    /// process が使う処理の順序のキャッシュと入力のエッジのリストをそのまま写すため、
+    /// グラフの変更がオーディオスレッドの状態に反映されているかを確認できます。
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn debug_snapshot(&self) -> GraphSnapshot {
+        let processing_order = self.graph.get_reverse_topological_order().to_vec();
+        let nodes = processing_order
+            .iter()
+            .map(|&node_id| {
+                let inputs = self
+                    .input_taps
+                    .get(&node_id)
+                    .map(|inputs| {
+                        inputs
+                            .iter()
+                            .map(|input| InputSnapshot {
+                                from_id: input.from_id,
+                                from_port: input.tap.from_port,
+                                port: input.tap.port,
+                                gain: input.tap.gain,
+                                offset: input.tap.offset,
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let output = self.node_outputs.get(&node_id);
+                let parameters = self
+                    .nodes
+                    .get(&node_id)
+                    .map(|node| {
+                        node.parameters()
+                            .iter()
+                            .filter_map(|info| {
+                                node.get_parameter(info.id).map(|value| (info.id, value))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                NodeSnapshot {
+                    node_id,
+                    kind: self.node_kinds.get(&node_id).copied(),
+                    inputs,
+                    output_channels: output.map_or(0, |buffer| buffer.num_channels()),
+                    output_frames: output.map_or(0, |buffer| buffer.num_frames()),
+                    output_capacity: output.map_or(0, |buffer| buffer.max_frames()),
+                    num_port_outputs: self.port_outputs.get(&node_id).map_or(0, Vec::len),
+                    parameters,
+                }
+            })
+            .collect();
+
+        GraphSnapshot {
+            sample_rate: self.sample_rate,
+            max_buffer_size: self.max_buffer_size,
+            block_size: self.block_size(),
+            num_channels: self.num_channels,
+            processing_order,
+            nodes,
+        }
+    }
+
+    /// グラフのすべてのノードをリセットする
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn reset(&mut self) {
+        for node in self.nodes.values_mut() {
+            node.reset();
+        }
+        for edge in self.feedback_edges.iter_mut() {
+            edge.buffer.set_num_frames(0);
+        }
+    }
+
+    /// ノードを削除する
+    ///
+    /// # 引数
+    /// * `node_id` - 削除するノードのID
+    ///
+    /// # 戻り値
+    /// * 成功した場合はノードが含まれる `Some`、存在しない場合は `None`
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn remove_node(&mut self, node_id: NodeId) -> Option<Box<dyn AudioGraphNode>> {
+        // グラフからノードを削除
+        if !self.graph.remove_node(node_id) {
+            return None;
+        }
+        self.detach_node(node_id);
+        self.node_kinds.remove(&node_id);
+        if let Some(frozen) = self.frozen.remove(&node_id) {
+            self.dispose(Garbage::Frozen(frozen));
+        }
+
+        // 世代を進めて、古い ID が再利用後のノードを指さないようにする
+        self.generations[node_id.index] =
+            (node_id.generation + 1) & (usize::MAX >> NODE_INDEX_BITS);
+        self.free_indices.push(node_id.index);
+
+        // ノードマップからノードを削除して返す
+        let mut node = self.nodes.remove(&node_id)?;
+        node.on_removed(&self.node_context(node_id));
+        Some(node)
+    }
+
+    /// ノードを削除し、ノードを解放する
+    ///
+    /// remove_node と異なりノードを返さず、set_garbage_sender で送信側を設定している場合は
+    /// ノードを GarbageCollector に送ります。コマンドキューなどでオーディオスレッドから削除する場合に使います。
+    ///
+    /// # 引数
+    /// * `node_id` - 削除するノードのID
+    ///
+    /// # 戻り値
+    /// * ノードが存在した場合は `true`
+    pub fn discard_node(&mut self, node_id: NodeId) -> bool {
+        match self.remove_node(node_id) {
+            Some(node) => {
+                self.dispose(Garbage::Node(node));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 削除したノードやバッファの送り先を設定する
+    ///
+    /// 設定すると、remove_node で削除したノードの出力バッファや、discard_node で削除したノードを
+    /// GarbageCollector に送り、その所有者のスレッドで解放します。`None` の場合はその場で解放します。
+    #[cfg(feature = "std")]
+    pub fn set_garbage_sender(&mut self, sender: Option<GarbageSender>) {
+        self.garbage = sender;
+    }
+
+    /// ノードがアプリケーションにイベントを通知するキューを設定する
+    ///
+    /// 設定した後に追加したノードは、on_added で受け取る NodeContext の `events` からキューに書き込めます。
+    pub fn set_event_queue(&mut self, events: Option<Arc<EventQueue>>) {
+        self.events = events;
+    }
+
+    /// process で検出した問題の記録先を取得する
+    ///
+    /// メインスレッドで保持し、定期的に確認することで、リリースビルドでもノードの出力バッファが見つからない
+    /// などの問題に気づけます。
+    pub fn diagnostics(&self) -> Arc<Diagnostics> {
+        self.diagnostics.clone()
+    }
+
+    /// process で検出した問題の記録先を置き換える
+    ///
+    /// 複数のグラフで同じ記録先を共有する場合に使います。
+    pub fn set_diagnostics(&mut self, diagnostics: Arc<Diagnostics>) {
+        self.diagnostics = diagnostics;
+    }
+
+    /// 解放待ちのものを送り先に送る（送り先がない場合はその場で解放する）
+    fn dispose(&self, garbage: Garbage) {
+        #[cfg(feature = "std")]
+        if let Some(sender) = self.garbage.as_ref() {
+            sender.dispose(garbage);
+        }
+        #[cfg(not(feature = "std"))]
+        drop(garbage);
+    }
+
+    /// ライフサイクルのフックに渡す情報を作成する
+    fn node_context(&self, node_id: NodeId) -> NodeContext {
+        NodeContext {
+            node_id,
+            sample_rate: self.sample_rate,
+            max_buffer_size: self.max_buffer_size,
+            events: self.events.clone(),
+        }
+    }
+
+    /// グラフから削除したノードの、出力バッファや接続の情報を削除する
+    fn detach_node(&mut self, node_id: NodeId) {
+        // ノード出力バッファと接続先ポートの情報を削除（固定容量モードでは出力バッファを再利用する）
+        if let Some(buffer) = self.node_outputs.remove(&node_id) {
+            if self.capacity.is_some() && self.spare_outputs.len() < self.spare_outputs.capacity() {
+                self.spare_outputs.push(buffer);
+            } else {
+                self.dispose(Garbage::Buffer(buffer));
+            }
+        }
+        for buffer in self.port_outputs.remove(&node_id).into_iter().flatten() {
+            self.dispose(Garbage::Buffer(buffer));
+        }
+        if let Some(mut inputs) = self.input_taps.remove(&node_id) {
+            if self.spare_input_taps.len() < self.spare_input_taps.capacity() {
+                inputs.clear();
+                self.spare_input_taps.push(inputs);
+            } else {
+                self.dispose(Garbage::InputTaps(inputs));
+            }
+        }
+        for inputs in self.input_taps.values_mut() {
+            inputs.retain(|input| input.from_id != node_id);
+        }
+
+        // パラメーター ID の文字列などを持つ割り当ても、オーディオスレッドで解放しないよう解放待ちとして送る
+        let mut modulations = core::mem::take(&mut self.modulations);
+        for route in modulations.extract_if(.., |route| {
+            route.source_id == node_id || route.target_id == node_id
+        }) {
+            self.dispose(Garbage::Modulation(route));
+        }
+        self.modulations = modulations;
+        self.midi_routes
+            .retain(|&(from_id, to_id)| from_id != node_id && to_id != node_id);
+        let mut i = 0;
+        while i < self.feedback_edges.len() {
+            let edge = &self.feedback_edges[i];
+            if edge.from_id == node_id || edge.to_id == node_id {
+                let edge = self.feedback_edges.remove(i);
+                self.dispose(Garbage::Buffer(edge.buffer));
+            } else {
+                i += 1;
+            }
+        }
+        let mut morph_routes = core::mem::take(&mut self.morph_routes);
+        for route in morph_routes.extract_if(.., |route| route.node_id == node_id) {
+            self.dispose(Garbage::Morph(route));
+        }
+        self.morph_routes = morph_routes;
+        if let Some(gain_stages) = self.gain_stages.as_mut() {
+            gain_stages.remove(&node_id);
+        }
+        let mut macro_mappings = core::mem::take(&mut self.macro_mappings);
+        for mapping in macro_mappings.extract_if(.., |mapping| mapping.node_id == node_id) {
+            self.dispose(Garbage::Macro(mapping));
+        }
+        self.macro_mappings = macro_mappings;
+        let mut automation = core::mem::take(&mut self.automation);
+        for route in automation.extract_if(.., |route| route.node_id == node_id) {
+            self.dispose(Garbage::Automation(route));
+        }
+        self.automation = automation;
+        self.summing_modes.remove(&node_id);
+        self.mix_policies.remove(&node_id);
+        self.rate_converters.remove(&node_id);
+        if self.sidechain_node_id == Some(node_id) {
+            self.sidechain_node_id = None;
+        }
+        for output_bus in self.output_buses.iter_mut() {
+            if output_bus.node_id == Some(node_id) {
+                output_bus.node_id = None;
+            }
+        }
+    }
+
+    /// サブグラフをサンプル列にレンダリングし、そのサンプル列を再生する SamplePlayer に置き換える（フリーズ）
+    ///
+    /// 重いエフェクトの連鎖などを一度だけレンダリングして、処理の負荷を減らすために使います。
+    /// 元のノードは ID を保ったまま保存し、unfreeze で元に戻せます。フリーズ中は元のノードの ID を使う操作は失敗し、
+    /// 元のノードに対するモジュレーションやマクロなどの割り当ては解除されます。
+    /// サブグラフ内のフィードバックのエッジは、レンダリングに含め、unfreeze で元に戻します。
+    ///
+    /// # 引数
+    /// * `node_ids` - フリーズするノード。サブグラフの外からの入力はなく、外へ出力するノードは 1 つである必要がある。
+    ///   サブグラフの境界をまたぐフィードバックのエッジがある場合はフリーズできない
+    /// * `num_frames` - レンダリングするフレーム数
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok` で代わりに追加した SamplePlayer のノードIDを返し、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn freeze(&mut self, node_ids: &[NodeId], num_frames: usize) -> Result<NodeId, String> {
+        if node_ids.is_empty() {
+            return Err("フリーズするノードがありません".to_string());
+        }
+        for (i, &node_id) in node_ids.iter().enumerate() {
+            if !self.nodes.contains_key(&node_id) {
+                return Err(self.node_not_found(node_id));
+            }
+            if node_ids[..i].contains(&node_id) {
+                return Err(format!("ノードID {} が重複しています", node_id));
+            }
+        }
+
+        // サブグラフの内部の接続と、外への接続を調べる
+        let mut edges = Vec::new();
+        let mut tail_id = None;
+        for (from_id, to_id) in self.edges() {
+            let taps = self.edge_taps(from_id, to_id);
+            match (node_ids.contains(&from_id), node_ids.contains(&to_id)) {
+                (false, true) => {
+                    return Err(format!(
+                        "サブグラフの外のノードID {} から入力があるため、フリーズできません",
+                        from_id
+                    ));
+                }
+                (true, false) if tail_id.is_some_and(|id| id != from_id) => {
+                    return Err(
+                        "サブグラフの外へ出力するノードは 1 つである必要があります".to_string()
+                    );
+                }
+                (true, false) if taps.iter().any(|tap| tap.from_port != 0) => {
+                    return Err(format!(
+                        "ノードID {} のメイン出力以外の出力ポートがサブグラフの外に接続されているため、フリーズできません",
+                        from_id
+                    ));
+                }
+                (true, false) => tail_id = Some(from_id),
+                (false, false) => continue,
+                (true, true) => {}
+            }
+            edges.push((from_id, to_id, taps));
+        }
+        let tail_id = tail_id.ok_or("サブグラフの外へ出力するノードがありません")?;
+
+        let mut feedback_edges = Vec::new();
+        for edge in self.feedback_edges.iter() {
+            match (
+                node_ids.contains(&edge.from_id),
+                node_ids.contains(&edge.to_id),
+            ) {
+                (true, true) => feedback_edges.push((edge.from_id, edge.to_id, edge.tap)),
+                (false, false) => {}
+                _ => {
+                    return Err(format!(
+                        "フィードバックのエッジ {} -> {} がサブグラフの境界をまたいでいるため、フリーズできません",
+                        edge.from_id, edge.to_id
+                    ));
+                }
+            }
+        }
+
+        // ノードを一時的なグラフに移してレンダリングする
+        let mut subgraph = AudioGraph::new();
+        let input_id = subgraph.add_node(Box::new(InputNode::new()));
+        let mut subgraph_ids = HashMap::new();
+        for &node_id in node_ids {
+            let mut node = self.nodes.remove(&node_id).unwrap();
+            node.on_removed(&self.node_context(node_id));
+            subgraph_ids.insert(node_id, subgraph.add_node(node));
+        }
+        if let Err(error) =
+            Self::connect_subgraph(&mut subgraph, &subgraph_ids, &edges, &feedback_edges)
+        {
+            // 接続できない場合は、移したノードを元のグラフに戻す（元のグラフの接続は変更していない）
+            for &node_id in node_ids {
+                let mut node = subgraph.remove_node(subgraph_ids[&node_id]).unwrap();
+                prepare_node(
+                    node.as_mut(),
+                    node_id,
+                    &mut self.rate_converters,
+                    self.num_channels,
+                    self.sample_rate,
+                    self.max_buffer_size,
                 );
+                node.on_added(&self.node_context(node_id));
+                self.nodes.insert(node_id, node);
+            }
+            return Err(error);
+        }
+        let block_size = if self.max_buffer_size > 0 {
+            self.max_buffer_size
+        } else {
+            FREEZE_BLOCK_SIZE
+        };
+        let rendered = OfflineRenderer::new(self.sample_rate, block_size).render(
+            &mut subgraph,
+            input_id,
+            subgraph_ids[&tail_id],
+            num_frames,
+        );
+
+        // 元のノードを ID を保ったまま保存し、SamplePlayer に置き換える
+        let mut nodes = Vec::new();
+        for &node_id in node_ids {
+            let node = subgraph.remove_node(subgraph_ids[&node_id]).unwrap();
+            self.graph.remove_node(node_id);
+            self.detach_node(node_id);
+            nodes.push((node_id, node));
+        }
+        let player = SamplePlayer::new(rendered.samples, rendered.channels);
+        let player_id = self.add_node(Box::new(player));
+        for (from_id, to_id, taps) in edges.iter() {
+            if *from_id == tail_id && !node_ids.contains(to_id) {
+                self.restore_edge(player_id, *to_id, taps)?;
+            }
+        }
+        self.frozen.insert(
+            player_id,
+            FrozenSubgraph {
+                nodes,
+                edges,
+                feedback_edges,
+            },
+        );
+        Ok(player_id)
+    }
+
+    /// フリーズするノードを移した一時的なグラフに、サブグラフ内の接続とフィードバックのエッジを追加する
+    fn connect_subgraph(
+        subgraph: &mut AudioGraph,
+        subgraph_ids: &HashMap<NodeId, NodeId>,
+        edges: &[(NodeId, NodeId, Vec<EdgeTap>)],
+        feedback_edges: &[(NodeId, NodeId, EdgeTap)],
+    ) -> Result<(), String> {
+        for (from_id, to_id, taps) in edges.iter() {
+            if let (Some(&from), Some(&to)) = (subgraph_ids.get(from_id), subgraph_ids.get(to_id)) {
+                subgraph.restore_edge(from, to, taps)?;
+            }
+        }
+        for &(from_id, to_id, tap) in feedback_edges.iter() {
+            subgraph.add_feedback_edge_tap(subgraph_ids[&from_id], subgraph_ids[&to_id], tap)?;
+        }
+        Ok(())
+    }
+
+    /// フリーズしたサブグラフを元のノードに戻す
+    ///
+    /// SamplePlayer を削除し、元のノードを同じ ID で接続し直します。元のノードは reset と prepare をした状態で戻ります。
+    /// フリーズ中に削除されたノードへの接続は戻しません。
+    ///
+    /// # 引数
+    /// * `player_id` - freeze が返した SamplePlayer のノードID
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok(())`、フリーズしたサブグラフでない場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn unfreeze(&mut self, player_id: NodeId) -> Result<(), String> {
+        let frozen = self.frozen.remove(&player_id).ok_or_else(|| {
+            format!(
+                "ノードID {} はフリーズしたサブグラフではありません",
+                player_id
+            )
+        })?;
+        self.discard_node(player_id);
+        for (node_id, mut node) in frozen.nodes {
+            node.reset();
+            self.insert_node(node_id, node);
+        }
+        for (from_id, to_id, taps) in frozen.edges {
+            if self.nodes.contains_key(&to_id) {
+                self.restore_edge(from_id, to_id, &taps)?;
+            }
+        }
+        for (from_id, to_id, tap) in frozen.feedback_edges {
+            self.add_feedback_edge_tap(from_id, to_id, tap)?;
+        }
+        Ok(())
+    }
+
+    /// ノードの組を、1 サンプルずつ処理するフィードバックリージョンにまとめる
+    ///
+    /// まとめたノードは FeedbackRegion の内部のグラフに移り、グラフの他の部分はブロックごとに処理したまま、
+    /// リージョンの中だけをバッファサイズ 1 で処理します。ノード間のフィードバックのエッジ（add_feedback_edge）は
+    /// リージョンの中では 1 サンプルの遅延になります。リージョンの外との接続は FeedbackRegion のノードに付け替え、
+    /// まとめたノードに対するモジュレーションやマクロなどの割り当ては解除されます。内部のノードのパラメーターは
+    /// FeedbackRegion のパラメーター `"{元のノードIDの整数の表現}/{パラメーターID}"` で設定できます。
+    ///
+    /// # 引数
+    /// * `node_ids` - まとめるノード。外から入力を受け取るノードと外へ出力するノードは、それぞれ 1 つである必要がある
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok` で代わりに追加した FeedbackRegion のノードIDを返し、失敗した場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn create_feedback_region(&mut self, node_ids: &[NodeId]) -> Result<NodeId, String> {
+        if node_ids.is_empty() {
+            return Err("フィードバックリージョンにまとめるノードがありません".to_string());
+        }
+        for (i, &node_id) in node_ids.iter().enumerate() {
+            if !self.nodes.contains_key(&node_id) {
+                return Err(self.node_not_found(node_id));
+            }
+            if node_ids[..i].contains(&node_id) {
+                return Err(format!("ノードID {} が重複しています", node_id));
+            }
+        }
+
+        // リージョンの内部の接続と、外との接続を調べる
+        let mut internal_edges = Vec::new();
+        let mut incoming_edges = Vec::new();
+        let mut outgoing_edges = Vec::new();
+        let mut head_id = None;
+        let mut tail_id = None;
+        for (from_id, to_id) in self.edges() {
+            let taps = self.edge_taps(from_id, to_id);
+            match (node_ids.contains(&from_id), node_ids.contains(&to_id)) {
+                (false, true) => {
+                    if head_id.is_some_and(|id| id != to_id) {
+                        return Err(
+                            "リージョンの外から入力を受け取るノードは 1 つである必要があります"
+                                .to_string(),
+                        );
+                    }
+                    if taps.iter().any(|tap| tap.port != 0) {
+                        return Err(format!(
+                            "ノードID {} からの入力がメイン入力以外のポートに接続されているため、リージョンにまとめられません",
+                            from_id
+                        ));
+                    }
+                    head_id = Some(to_id);
+                    incoming_edges.push((from_id, taps));
+                }
+                (true, false) => {
+                    if tail_id.is_some_and(|id| id != from_id) {
+                        return Err(
+                            "リージョンの外へ出力するノードは 1 つである必要があります".to_string()
+                        );
+                    }
+                    if taps.iter().any(|tap| tap.from_port != 0) {
+                        return Err(format!(
+                            "ノードID {} のメイン出力以外の出力ポートがリージョンの外に接続されているため、リージョンにまとめられません",
+                            from_id
+                        ));
+                    }
+                    tail_id = Some(from_id);
+                    outgoing_edges.push((to_id, taps));
+                }
+                (true, true) => internal_edges.push((from_id, to_id, taps)),
+                (false, false) => {}
+            }
+        }
+        let tail_id = tail_id.ok_or("リージョンの外へ出力するノードがありません")?;
+
+        // リージョンから出て再び入る経路があると、付け替えたエッジが循環する
+        for &(to_id, _) in outgoing_edges.iter() {
+            for &(from_id, _) in incoming_edges.iter() {
+                if to_id == from_id || self.graph.find_path(to_id, from_id).is_some() {
+                    return Err(format!(
+                        "ノードID {} を経由してリージョンに戻る経路があるため、リージョンにまとめられません",
+                        to_id
+                    ));
+                }
+            }
+        }
+
+        let mut feedback_edges = Vec::new();
+        for edge in self.feedback_edges.iter() {
+            match (
+                node_ids.contains(&edge.from_id),
+                node_ids.contains(&edge.to_id),
+            ) {
+                (true, true) => feedback_edges.push((edge.from_id, edge.to_id, edge.tap)),
+                (false, false) => {}
+                _ => {
+                    return Err(format!(
+                        "フィードバックのエッジ {} -> {} がリージョンの境界をまたいでいます",
+                        edge.from_id, edge.to_id
+                    ));
+                }
+            }
+        }
+
+        // ノードをリージョンの内部のグラフに移し、内部の接続を戻す
+        let mut region = FeedbackRegion::new(self.num_channels);
+        for &node_id in node_ids {
+            let mut node = self.nodes.remove(&node_id).unwrap();
+            node.on_removed(&self.node_context(node_id));
+            let inner_id = region.graph_mut().add_node(node);
+            region.map_node_id(node_id, inner_id);
+        }
+        let inner_id = |region: &FeedbackRegion, node_id| region.inner_node_id(node_id).unwrap();
+        for (from_id, to_id, taps) in internal_edges.iter() {
+            let (from, to) = (inner_id(&region, *from_id), inner_id(&region, *to_id));
+            region.graph_mut().restore_edge(from, to, taps)?;
+        }
+        for &(from_id, to_id, tap) in feedback_edges.iter() {
+            let (from, to) = (inner_id(&region, from_id), inner_id(&region, to_id));
+            region.graph_mut().add_feedback_edge_tap(from, to, tap)?;
+        }
+        if let Some(head_id) = head_id {
+            let (input_id, head) = (region.input_node_id(), inner_id(&region, head_id));
+            region.graph_mut().add_edge(input_id, head)?;
+        }
+        let (tail, output_id) = (inner_id(&region, tail_id), region.output_node_id());
+        region.graph_mut().add_edge(tail, output_id)?;
+
+        // 元のノードを取り除き、外との接続を FeedbackRegion に付け替える
+        for &node_id in node_ids {
+            self.graph.remove_node(node_id);
+            self.detach_node(node_id);
+        }
+        let region_id = self.add_node(Box::new(region));
+        for (from_id, taps) in incoming_edges.iter() {
+            self.restore_edge(*from_id, region_id, taps)?;
+        }
+        for (to_id, taps) in outgoing_edges.iter() {
+            self.restore_edge(region_id, *to_id, taps)?;
+        }
+        Ok(region_id)
+    }
+
+    /// エッジを削除する（並列のエッジがある場合は全て削除する）
+    ///
+    /// # 引数
+    /// * `from_id` - 接続元ノードのID
+    /// * `to_id` - 接続先ノードのID
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `true`、存在しない場合は `false`
+    ///
+    /// # 実装時の注意
+    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
+    pub fn remove_edge(&mut self, from_id: NodeId, to_id: NodeId) -> bool {
+        if let Some(inputs) = self.input_taps.get_mut(&to_id) {
+            inputs.retain(|input| input.from_id != from_id);
+        }
+        self.graph.remove_edge(from_id, to_id)
+    }
+
+    /// 存在しないノードの ID を使った場合のエラーメッセージ
+    ///
+    /// インデックスが別の世代のノードに再利用されている場合は、削除済みのノードの ID であることを示す。
+    fn node_not_found(&self, node_id: NodeId) -> String {
+        match self.generations.get(node_id.index) {
+            Some(&generation) if generation != node_id.generation => {
+                format!("ノードID {}は削除済みのノードです", node_id)
+            }
+            _ => format!("ノードID {}が存在しません", node_id),
+        }
+    }
+}
+
+/// rate_factor に従ってノードを準備する
+///
+/// 倍率が 1 以外のノードは、レート変換器を `rate_converters` に登録し、内部のレートと最大ブロックサイズで準備します。
+///
+/// # 引数
+/// * `node` - 準備するノード
+/// * `node_id` - ノードのID
+/// * `rate_converters` - ノードごとのレート変換器
+/// * `num_channels` - グラフが処理するチャンネル数
+/// * `sample_rate` - グラフのサンプリングレート（Hz）
+/// * `max_buffer_size` - グラフの最大バッファサイズ
+fn prepare_node(
+    node: &mut dyn AudioGraphNode,
+    node_id: NodeId,
+    rate_converters: &mut SlotMap<NodeId, RateConverter>,
+    num_channels: usize,
+    sample_rate: f32,
+    max_buffer_size: usize,
+) {
+    let rate_factor = node.rate_factor();
+    if rate_factor == 1.0 || rate_factor <= 0.0 || !rate_factor.is_finite() {
+        rate_converters.remove(&node_id);
+        node.prepare(sample_rate, max_buffer_size);
+        return;
+    }
+    let inner_rate = sample_rate * rate_factor;
+    let converter = rate_converters.get_or_insert_with(node_id, RateConverter::new);
+    converter.prepare(num_channels, sample_rate, inner_rate, max_buffer_size);
+    node.prepare(inner_rate, converter.max_inner_frames());
+}
+
+/// ノードのパラメーターを設定し、そのパラメーターへのモジュレーションの変調前の値も更新する
+///
+/// モジュレーションはブロックごとに変調前の値からパラメーターを設定し直すため、グラフを通して設定した値は
+/// 変調前の値として記録しないと、次のブロックで上書きされてしまいます。
+///
+/// # 戻り値
+/// * ノードとパラメーターが存在する場合は `true`
+fn set_node_parameter(
+    nodes: &mut SlotMap<NodeId, Box<dyn AudioGraphNode>>,
+    modulations: &mut [ModulationRoute],
+    node_id: NodeId,
+    parameter_id: &str,
+    value: f32,
+) -> bool {
+    let Some(node) = nodes.get_mut(&node_id) else {
+        return false;
+    };
+    if !node.set_parameter(parameter_id, value) {
+        return false;
+    }
+    let value = node.get_parameter(parameter_id).unwrap_or(value);
+    for route in modulations.iter_mut() {
+        if route.target_id == node_id && route.parameter_id == parameter_id {
+            route.base_value = value;
+        }
+    }
+    true
+}
+
+/// エッジのオフセットを入力に加算する
+fn add_offset(samples: &mut [f32], offset: f32) {
+    if offset != 0.0 {
+        for sample in samples.iter_mut() {
+            *sample += offset;
+        }
+    }
+}
+
+/// コントロールレートのノードを処理し、出力をオーディオレートに補間する
+///
+/// # 引数
+/// * `node` - 処理するノード
+/// * `interval` - `process_control` を呼び出す間隔（サンプル数）
+/// * `previous` - 補間の始点にする前回の出力値
+/// * `buffer` - ノードへの入力。処理後はノードの出力になる
+fn process_control_rate(
+    node: &mut dyn AudioGraphNode,
+    interval: usize,
+    mut previous: f32,
+    buffer: &mut AudioBuffer,
+) {
+    let num_channels = buffer.num_channels().max(1);
+    for chunk in buffer
+        .as_mut_slice()
+        .chunks_mut(interval.max(1) * num_channels)
+    {
+        let num_frames = chunk.len() / num_channels;
+        let value = node.process_control(&AudioBuffer::new(num_channels, num_frames, chunk));
+        for (i, frame) in chunk.chunks_exact_mut(num_channels).enumerate() {
+            frame.fill(previous + (value - previous) * (i + 1) as f32 / num_frames as f32);
+        }
+        previous = value;
+    }
+}
+
+impl Drop for AudioGraph {
+    fn drop(&mut self) {
+        // グラフに残っているノードも、グラフから取り除かれたものとして後片付けさせる
+        let node_ids = self.node_ids();
+        for node_id in node_ids {
+            let context = self.node_context(node_id);
+            if let Some(node) = self.nodes.get_mut(&node_id) {
+                node.on_removed(&context);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(debug_assertions)]
+    use assert_no_alloc::AllocDisabler;
+    use assert_no_alloc::assert_no_alloc;
+    use proptest::prelude::*;
+
+    use crate::automation::AutomationCurve;
+    use crate::nodes::{
+        ChordGenerator, Crossfader, Eq3, FmSynth, GainProcessor, InputNode, OutputNode,
+        SineGenerator,
+    };
+
+    use super::*;
+
+    #[cfg(debug_assertions)] // required when disable_release is set (default)
+    #[global_allocator]
+    static A: AllocDisabler = AllocDisabler;
+
+    // テスト用のダミーノード
+    struct TestNode {
+        value: f32,
+    }
+
+    impl TestNode {
+        fn new(value: f32) -> Self {
+            Self { value }
+        }
+    }
+
+    impl AudioGraphNode for TestNode {
+        fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {
+            // 何もしない
+        }
+
+        fn process(&mut self, buffer: &mut AudioBuffer) {
+            // すべてのサンプルの値を value にします。
+            for sample in buffer.as_mut_slice() {
+                *sample = self.value;
+            }
+        }
+
+        fn reset(&mut self) {
+            // 何もしない
+        }
+    }
+
+    #[test]
+    fn test_add_node() {
+        let mut graph = AudioGraph::new();
+
+        let node_id = graph.add_node(Box::new(TestNode::new(0.5)));
+
+        assert_eq!(graph.nodes.len(), 1);
+        assert!(graph.nodes.contains_key(&node_id));
+    }
+
+    #[test]
+    fn test_add_edge() {
+        let mut graph = AudioGraph::new();
+        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let node2_id = graph.add_node(Box::new(TestNode::new(0.3)));
+
+        let result = graph.add_edge(node1_id, node2_id);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let mut graph = AudioGraph::new();
+        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let node2_id = graph.add_node(Box::new(TestNode::new(0.3)));
+        let node3_id = graph.add_node(Box::new(TestNode::new(0.2)));
+
+        // node1 -> node2 -> node3
+        assert!(graph.add_edge(node1_id, node2_id).is_ok());
+        assert!(graph.add_edge(node2_id, node3_id).is_ok());
+
+        // node3 -> node1 would create a cycle
+        let result = graph.add_edge(node3_id, node1_id);
+        assert_eq!(
+            result,
+            Err(
+                "ノードID 2 から 0 への接続は循環参照を作成します（循環: 2 -> 0 -> 1 -> 2）"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parallel_edges_and_summing_modes() {
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let impulse_id = graph.add_node(Box::new(crate::nodes::ImpulseGenerator::new()));
+        graph.prepare(48000.0, 4);
+
+        // 並列のエッジを許可しない場合は、既存のエッジの接続を置き換える
+        graph
+            .add_edge_with_gain(impulse_id, output_id, 0, 0.5)
+            .unwrap();
+        graph.add_edge(impulse_id, output_id).unwrap();
+        assert!(graph.has_edge(impulse_id, output_id));
+        assert!(!graph.has_edge(output_id, impulse_id));
+        assert_eq!(graph.edges_from(impulse_id), vec![output_id]);
+        assert_eq!(graph.edge_gains(impulse_id, output_id), vec![1.0]);
+
+        // 許可すると、それぞれのゲインで加算される
+        graph.set_allow_parallel_edges(true);
+        graph
+            .add_edge_with_gain(impulse_id, output_id, 0, 0.25)
+            .unwrap();
+        graph.set_edge_gain(impulse_id, output_id, 0, 0.5).unwrap();
+        assert!(graph.set_edge_gain(impulse_id, output_id, 2, 0.5).is_err());
+        assert_eq!(graph.edges(), vec![(impulse_id, output_id)]);
+        assert_eq!(graph.edge_gains(impulse_id, output_id), vec![0.5, 0.25]);
+
+        let mut data = vec![0.0; 2 * 4];
+        graph.process(&mut AudioBuffer::new(2, 4, &mut data), input_id, output_id);
+        assert_eq!(&data[0..2], &[0.75, 0.75]);
+
+        // 平均では入力のエッジの数で割り、最大では絶対値が最大の入力を選ぶ
+        graph
+            .set_summing_mode(output_id, SummingMode::Average)
+            .unwrap();
+        assert_eq!(graph.summing_mode(output_id), Some(SummingMode::Average));
+        graph.reset();
+        graph.process(&mut AudioBuffer::new(2, 4, &mut data), input_id, output_id);
+        assert_eq!(&data[0..2], &[0.375, 0.375]);
+        graph.set_summing_mode(output_id, SummingMode::Max).unwrap();
+        graph
+            .set_edge_gain(impulse_id, output_id, 1, -0.75)
+            .unwrap();
+        graph.reset();
+        graph.process(&mut AudioBuffer::new(2, 4, &mut data), input_id, output_id);
+        assert_eq!(&data[0..2], &[-0.75, -0.75]);
+
+        // 独自の合成方法は SummingMode より優先される
+        struct PrioritySelect(NodeId);
+        impl MixPolicy for PrioritySelect {
+            fn mix(
+                &mut self,
+                _port: usize,
+                source_id: NodeId,
+                input: &AudioBuffer,
+                gain: f32,
+                output: &mut AudioBuffer,
+            ) {
+                if source_id == self.0 {
+                    audio_buffer_utils::add_buffer_with_gain(input, output, gain);
+                }
+            }
+        }
+        let other_id = graph.add_node(Box::new(crate::nodes::ImpulseGenerator::new()));
+        graph.add_edge(other_id, output_id).unwrap();
+        graph
+            .set_mix_policy(output_id, Box::new(PrioritySelect(other_id)))
+            .unwrap();
+        graph.reset();
+        graph.process(&mut AudioBuffer::new(2, 4, &mut data), input_id, output_id);
+        assert_eq!(&data[0..2], &[1.0, 1.0]);
+        assert!(graph.clear_mix_policy(output_id).is_some());
+        graph.remove_node(other_id);
+
+        // 削除すると並列のエッジも全て削除される
+        assert!(graph.remove_edge(impulse_id, output_id));
+        assert!(graph.edge_gains(impulse_id, output_id).is_empty());
+    }
+
+    #[test]
+    fn test_serial_process() {
+        let mut graph = AudioGraph::new();
+
+        let input_node = InputNode::new();
+        let output_node = OutputNode::new();
+
+        let input_node_id = graph.add_node(Box::new(input_node));
+        let output_node_id = graph.add_node(Box::new(output_node));
+        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let node2_id = graph.add_node(Box::new(TestNode::new(0.3)));
+
+        // 直列に接続。
+        // 入力ノード -> node1 -> node2 -> 出力ノード
+        assert!(graph.add_edge(input_node_id, node1_id).is_ok());
+        assert!(graph.add_edge(node1_id, node2_id).is_ok());
+        assert!(graph.add_edge(node2_id, output_node_id).is_ok());
+
+        // オーディオ処理の準備
+        graph.prepare(44100.0, 4);
+
+        // 2チャンネル、4サンプルのバッファを作成
+        let mut buffer: Vec<f32> = vec![0.0; 8];
+        let mut audio_buffer = AudioBuffer::new(2, 4, &mut buffer);
+
+        assert_no_alloc(|| {
+            // グラフを処理
+            graph.process(&mut audio_buffer, input_node_id, output_node_id);
+        });
+
+        // トポロジカル順序で処理されるため、node1とnode2の両方が適用されるはず
+        for sample in audio_buffer.as_slice() {
+            // 最後のノードの値になるはず。
+            assert_eq!(*sample, 0.3);
+        }
+    }
+
+    #[test]
+    fn test_input_reaches_graph() {
+        let mut graph = AudioGraph::new();
+        let mut input_node = InputNode::new();
+        input_node.set_gain_db(-6.0);
+        let input_node_id = graph.add_node(Box::new(input_node));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        assert!(graph.add_edge(input_node_id, output_node_id).is_ok());
+        graph.prepare(44100.0, 4);
+
+        // 外部バッファの入力が入力ノードのゲインを通って出力される
+        let mut buffer: Vec<f32> = vec![1.0, -1.0, 0.5, -0.5, 0.25, -0.25, 0.0, 0.0];
+        let mut audio_buffer = AudioBuffer::new(2, 4, &mut buffer);
+        graph.process(&mut audio_buffer, input_node_id, output_node_id);
+        let gain = 10.0f32.powf(-6.0 / 20.0);
+        for (sample, expected) in buffer
+            .iter()
+            .zip([1.0, -1.0, 0.5, -0.5, 0.25, -0.25, 0.0, 0.0])
+        {
+            assert!((sample - expected * gain).abs() < 1e-6);
+        }
+
+        // ミュートすると、ランプの後は無音になる
+        assert!(graph.set_parameter(input_node_id, "mute", 1.0).is_ok());
+        for _ in 0..200 {
+            let mut buffer: Vec<f32> = vec![1.0; 8];
+            graph.process(
+                &mut AudioBuffer::new(2, 4, &mut buffer),
+                input_node_id,
+                output_node_id,
+            );
+        }
+        let mut buffer: Vec<f32> = vec![1.0; 8];
+        graph.process(
+            &mut AudioBuffer::new(2, 4, &mut buffer),
+            input_node_id,
+            output_node_id,
+        );
+        assert_eq!(buffer, vec![0.0; 8]);
+    }
+
+    #[test]
+    fn test_sidechain_input() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let sidechain_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        assert!(graph.add_edge(sidechain_node_id, output_node_id).is_ok());
+        assert!(graph.set_sidechain_node(Some(sidechain_node_id)).is_ok());
+        graph.prepare(44100.0, 2);
+
+        // モノラルのサイドチェーン入力がグラフのチャンネル数に変換され、最大バッファサイズずつ読み出される
+        graph.set_sidechain_input(&AudioBuffer::new(1, 2, &mut [0.5, -0.5]));
+        let mut buffer: Vec<f32> = vec![1.0; 8];
+        graph.process(
+            &mut AudioBuffer::new(2, 4, &mut buffer),
+            input_node_id,
+            output_node_id,
+        );
+        assert_eq!(buffer, vec![0.5, 0.5, -0.5, -0.5, 0.0, 0.0, 0.0, 0.0]);
+
+        // ノードを削除するとサイドチェーン入力も解除される
+        graph.remove_node(sidechain_node_id);
+        assert_eq!(graph.sidechain_node(), None);
+        assert!(graph.set_sidechain_node(Some(sidechain_node_id)).is_err());
+    }
+
+    #[test]
+    fn test_output_bus() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let bus_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let node_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        assert!(graph.add_edge(input_node_id, output_node_id).is_ok());
+        assert!(graph.add_edge(node_id, bus_node_id).is_ok());
+        assert!(graph.set_output_bus(1, Some(bus_node_id)).is_ok());
+        assert!(
+            graph
+                .set_output_bus(MAX_OUTPUT_BUSES, Some(bus_node_id))
+                .is_err()
+        );
+        graph.prepare(44100.0, 2);
+
+        // 割り当てたノードの出力は、メインの出力とは別に出力バスから読み出せる
+        let mut buffer: Vec<f32> = vec![0.0; 4];
+        graph.process(
+            &mut AudioBuffer::new(2, 2, &mut buffer),
+            input_node_id,
+            output_node_id,
+        );
+        assert_eq!(graph.output_bus(0), None);
+        assert_eq!(graph.output_bus(1), Some(&[0.5, 0.5, 0.5, 0.5][..]));
+        assert_eq!(buffer, vec![0.0; 4]);
+
+        // ノードを削除すると割り当ても解除される
+        graph.remove_node(bus_node_id);
+        assert_eq!(graph.output_bus_node(1), None);
+        assert_eq!(graph.output_bus(1), None);
+    }
+
+    #[test]
+    fn test_feedback_region() {
+        let mut graph = AudioGraph::new();
+        assert!(graph.set_num_channels(1).is_ok());
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let mut gain = GainProcessor::new();
+        gain.set_gain(0.5);
+        let gain_id = graph.add_node(Box::new(gain));
+        assert!(graph.add_edge(input_node_id, gain_id).is_ok());
+        assert!(graph.add_edge(gain_id, output_node_id).is_ok());
+        assert!(graph.add_feedback_edge(gain_id, gain_id, 0, 1.0).is_ok());
+        graph.prepare(44100.0, 4);
+
+        // ブロックごとの処理では、フィードバックは次のブロックに遅れて入力される
+        let mut buffer = vec![1.0, 0.0, 0.0, 0.0];
+        graph.process(
+            &mut AudioBuffer::new(1, 4, &mut buffer),
+            input_node_id,
+            output_node_id,
+        );
+        assert_eq!(buffer, vec![0.5, 0.0, 0.0, 0.0]);
+
+        // フィードバックリージョンの中では 1 サンプル遅れて入力される
+        assert!(
+            graph
+                .create_feedback_region(&[gain_id, output_node_id])
+                .is_err()
+        );
+        let region_id = graph.create_feedback_region(&[gain_id]).unwrap();
+        assert!(graph.has_edge(input_node_id, region_id));
+        assert!(graph.has_edge(region_id, output_node_id));
+        assert!(graph.feedback_edges().is_empty());
+        let mut buffer = vec![1.0, 0.0, 0.0, 0.0];
+        graph.process(
+            &mut AudioBuffer::new(1, 4, &mut buffer),
+            input_node_id,
+            output_node_id,
+        );
+        assert_eq!(buffer, vec![0.5, 0.25, 0.125, 0.0625]);
+
+        // 内部のノードのパラメーターは元のノードIDで設定する
+        let parameter_id = format!("{}/gain", gain_id.as_raw());
+        assert!(graph.set_parameter(region_id, &parameter_id, 0.25).is_ok());
+        assert_eq!(graph.get_parameter(region_id, &parameter_id), Some(0.25));
+    }
+
+    #[test]
+    fn test_parameter_edge() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let frequency_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let phase_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let mut sine = SineGenerator::new();
+        sine.set_frequency(0.0);
+        let sine_id = graph.add_node(Box::new(sine));
+        assert!(graph.add_edge(sine_id, output_node_id).is_ok());
+
+        // 周波数は 0 + 0.5 * 0 + 1 = 1Hz、位相のオフセットは 0.5 * 0.5 = 0.25 周期になる
+        assert!(
+            graph
+                .add_parameter_edge(frequency_id, sine_id, "frequency", 0.0, 1.0)
+                .is_ok()
+        );
+        assert!(
+            graph
+                .add_parameter_edge(phase_id, sine_id, "phase", 0.5, 0.0)
+                .is_ok()
+        );
+        assert!(
+            graph
+                .add_parameter_edge(phase_id, output_node_id, "gain", 1.0, 0.0)
+                .is_err()
+        );
+        graph.prepare(4.0, 4);
+
+        let mut buffer: Vec<f32> = vec![0.0; 8];
+        graph.process(
+            &mut AudioBuffer::new(2, 4, &mut buffer),
+            input_node_id,
+            output_node_id,
+        );
+        for (sample, expected) in buffer.iter().step_by(2).zip([1.0, 0.0, -1.0, 0.0]) {
+            assert!((sample - expected).abs() < 1e-5, "{:?}", buffer);
+        }
+    }
+
+    #[test]
+    fn test_midi_route() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let chord_id = graph.add_node(Box::new(ChordGenerator::new()));
+        let synth_id = graph.add_node(Box::new(FmSynth::new()));
+        assert!(graph.add_edge(synth_id, output_node_id).is_ok());
+        assert!(graph.add_midi_route(chord_id, synth_id).is_ok());
+        assert!(graph.add_midi_route(chord_id, synth_id).is_err());
+        assert!(graph.add_midi_route(synth_id, chord_id).is_err());
+        graph.prepare(44100.0, 4);
+
+        // 和音に変換されたノートイベントがシンセに届き、発音する
+        assert_no_alloc(|| {
+            graph.send_note_event(
+                chord_id,
+                NoteEvent::NoteOn {
+                    timing: 0,
+                    channel: 0,
+                    note: 60,
+                    velocity: 1.0,
+                },
+            );
+        });
+        let mut buffer: Vec<f32> = vec![0.0; 8];
+        graph.process(
+            &mut AudioBuffer::new(2, 4, &mut buffer),
+            input_node_id,
+            output_node_id,
+        );
+        assert!(buffer.iter().any(|sample| sample.abs() > 0.0));
+
+        // ノードを削除すると MIDI の接続も解除される
+        graph.remove_node(synth_id);
+        assert!(graph.midi_routes().is_empty());
+    }
+
+    #[test]
+    fn test_oversized_buffer_is_split() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let sine_id = graph.add_node(Box::new(SineGenerator::new()));
+        graph.add_edge(input_node_id, output_node_id).unwrap();
+        graph.add_edge(sine_id, output_node_id).unwrap();
+        graph.prepare(44100.0, 4);
+
+        // 最大バッファサイズを超えるバッファも、4 フレームずつ処理した場合と同じ結果になる
+        let input: Vec<f32> = (0..20).map(|i| i as f32 * 0.01).collect();
+        let mut buffer = input.clone();
+        assert_no_alloc(|| {
+            graph.process(
+                &mut AudioBuffer::new(2, 10, &mut buffer),
+                input_node_id,
+                output_node_id,
+            );
+        });
+
+        graph.reset();
+        let mut expected = input.clone();
+        for chunk in expected.chunks_mut(8) {
+            let frames = chunk.len() / 2;
+            graph.process(
+                &mut AudioBuffer::new(2, frames, chunk),
+                input_node_id,
+                output_node_id,
+            );
+        }
+        assert_eq!(buffer, expected);
+        assert_ne!(buffer, input);
+    }
+
+    #[test]
+    fn test_oversized_buffer_note_timing() {
+        // 最大バッファサイズを超えるバッファの 2 つ目の範囲のノートイベントも、指定したフレームで処理される
+        assert_eq!(note_onset_frame(None, 64, 160, 100), Some(101));
+        assert_eq!(note_onset_frame(None, 64, 160, 140), Some(141));
+    }
+
+    #[test]
+    fn test_internal_block_size() {
+        use std::sync::{Arc, Mutex};
+
+        /// 処理したブロックのフレーム数を記録するノード
+        struct BlockRecorder(Arc<Mutex<Vec<usize>>>);
+
+        impl AudioGraphNode for BlockRecorder {
+            fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+            fn process(&mut self, buffer: &mut AudioBuffer) {
+                self.0.lock().unwrap().push(buffer.num_frames());
+            }
+            fn reset(&mut self) {}
+        }
+
+        let blocks = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let recorder_id = graph.add_node(Box::new(BlockRecorder(blocks.clone())));
+        graph.add_edge(input_node_id, recorder_id).unwrap();
+        graph.add_edge(recorder_id, output_node_id).unwrap();
+        graph.prepare_with_block_size(44100.0, 8, Some(3));
+        let process = |graph: &mut AudioGraph, channels: usize, frames: usize| {
+            let mut buffer = vec![0.0; channels * frames];
+            graph.process(
+                &mut AudioBuffer::new(channels, frames, &mut buffer),
+                input_node_id,
+                output_node_id,
+            );
+            core::mem::take(&mut *blocks.lock().unwrap())
+        };
+
+        // ホストのバッファサイズによらず 3 フレームずつ処理し、残りは短いブロックで処理する
+        assert_eq!(process(&mut graph, 2, 10), [3, 3, 3, 1]);
+        assert_eq!(process(&mut graph, 2, 2), [2]);
+        assert_eq!(process(&mut graph, 1, 7), [3, 3, 1]);
+
+        // prepare し直してもブロックサイズを保ち、None を指定すると最大バッファサイズでまとめて処理する
+        graph.prepare(44100.0, 8);
+        assert_eq!(graph.internal_block_size(), Some(3));
+        assert_eq!(process(&mut graph, 2, 8), [3, 3, 2]);
+        graph.prepare_with_block_size(44100.0, 8, None);
+        assert_eq!(process(&mut graph, 2, 10), [8, 2]);
+    }
+
+    /// FmSynth にノートオンを送って 1 回 process し、最初に無音でなくなったフレームを返す
+    fn note_onset_frame(
+        block_size: Option<usize>,
+        max_frames: usize,
+        frames: usize,
+        timing: u32,
+    ) -> Option<usize> {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let mut synth = FmSynth::new();
+        synth.set_attack_ms(0.0);
+        let synth_id = graph.add_node(Box::new(synth));
+        graph.add_edge(synth_id, output_node_id).unwrap();
+        graph.prepare_with_block_size(48000.0, max_frames, block_size);
+
+        graph.send_note_event(
+            synth_id,
+            NoteEvent::NoteOn {
+                timing,
+                channel: 0,
+                note: 69,
+                velocity: 1.0,
+            },
+        );
+        let mut buffer = vec![0.0; 2 * frames];
+        graph.process(
+            &mut AudioBuffer::new(2, frames, &mut buffer),
+            input_node_id,
+            output_node_id,
+        );
+        buffer.chunks_exact(2).position(|frame| frame[0] != 0.0)
+    }
+
+    #[test]
+    fn test_note_timing_across_internal_blocks() {
+        // 内部のブロックに分けても、ノートイベントは指定したフレームで処理される（発音の最初のサンプルは位相 0 で無音）
+        let whole = note_onset_frame(None, 64, 64, 40);
+        assert_eq!(whole, Some(41));
+        assert_eq!(note_onset_frame(Some(16), 64, 64, 40), whole);
+        // ブロックの境界ちょうどのイベントと、端数のブロックのイベント
+        assert_eq!(note_onset_frame(Some(16), 64, 64, 32), Some(33));
+        assert_eq!(note_onset_frame(Some(16), 64, 60, 50), Some(51));
+    }
+
+    #[test]
+    fn test_channel_count_change() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        graph.add_edge(input_node_id, output_node_id).unwrap();
+        graph.prepare(44100.0, 4);
+        assert_eq!(graph.requested_num_channels(), None);
+
+        // モノラルのバッファは、再構成されるまでステレオに変換して処理される
+        let input: Vec<f32> = (0..6).map(|i| i as f32 * 0.1).collect();
+        let mut buffer = input.clone();
+        assert_no_alloc(|| {
+            graph.process(
+                &mut AudioBuffer::new(1, 6, &mut buffer),
+                input_node_id,
+                output_node_id,
+            );
+        });
+        assert_eq!(buffer, input);
+        assert_eq!(graph.requested_num_channels(), Some(1));
+
+        // 再構成した後はモノラルのまま処理される
+        assert!(graph.set_num_channels(1).is_ok());
+        assert_eq!(graph.num_channels(), 1);
+        assert_eq!(graph.requested_num_channels(), None);
+        let mut buffer = input.clone();
+        assert_no_alloc(|| {
+            graph.process(
+                &mut AudioBuffer::new(1, 6, &mut buffer),
+                input_node_id,
+                output_node_id,
+            );
+        });
+        assert_eq!(buffer, input);
+
+        assert!(graph.set_num_channels(0).is_err());
+        assert!(graph.set_num_channels(3).is_err());
+    }
+
+    #[test]
+    fn test_parallel_process() {
+        let mut graph = AudioGraph::new();
+
+        let input_node = InputNode::new();
+        let output_node = OutputNode::new();
+
+        let input_node_id = graph.add_node(Box::new(input_node));
+        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let node2_id = graph.add_node(Box::new(TestNode::new(0.3)));
+        let output_node_id = graph.add_node(Box::new(output_node));
+
+        /*
+        両方のノードを出力ノードに接続する（並列処理）
+        ```mermaid
+        flowchart LR
+            入力ノード --> ノード1
+            入力ノード --> ノード2
+            ノード1 --> 出力ノード
+            ノード2 --> 出力ノード
+        ```
+        */
+        assert!(graph.add_edge(input_node_id, node1_id).is_ok());
+        assert!(graph.add_edge(input_node_id, node2_id).is_ok());
+        assert!(graph.add_edge(node1_id, output_node_id).is_ok());
+        assert!(graph.add_edge(node2_id, output_node_id).is_ok());
+
+        // オーディオ処理の準備
+        graph.prepare(44100.0, 4);
+
+        // 2チャンネル、4サンプルのバッファを作成
+        let mut buffer: Vec<f32> = vec![0.0; 2 * 4];
+        let mut audio_buffer = AudioBuffer::new(2, 4, &mut buffer);
+
+        // グラフを処理
+        assert_no_alloc(|| {
+            graph.process(&mut audio_buffer, input_node_id, output_node_id);
+        });
+
+        // node1とnode2のが合流するので両方が適用されるはず
+        for sample in audio_buffer.as_slice() {
+            // 0.5 + 0.3 = 0.8
+            assert_eq!(*sample, 0.8);
+        }
+    }
+
+    // ポート 1 の信号をメイン入力に掛け合わせるテスト用のノード
+    struct MultiplyNode {}
+
+    impl AudioGraphNode for MultiplyNode {
+        fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+
+        fn process(&mut self, _buffer: &mut AudioBuffer) {}
+
+        fn reset(&mut self) {}
+
+        fn num_input_ports(&self) -> usize {
+            2
+        }
+
+        fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, inputs: &InputPorts) {
+            let Some(port) = inputs.get(1) else {
+                return;
+            };
+            for (sample, modulator) in buffer.as_mut_slice().iter_mut().zip(port) {
+                *sample *= modulator;
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_with_input_port() {
+        let mut graph = AudioGraph::new();
+
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let node2_id = graph.add_node(Box::new(TestNode::new(0.4)));
+        let multiply_id = graph.add_node(Box::new(MultiplyNode {}));
+
+        // node1 はメイン入力、node2 はポート 1 に接続する
+        assert!(graph.add_edge(node1_id, multiply_id).is_ok());
+        assert!(graph.add_edge_to_port(node2_id, multiply_id, 1).is_ok());
+        assert!(graph.add_edge(multiply_id, output_node_id).is_ok());
+        // 存在しないポートへの接続は失敗する
+        assert!(graph.add_edge_to_port(node1_id, multiply_id, 2).is_err());
+
+        graph.prepare(44100.0, 4);
+
+        let mut buffer: Vec<f32> = vec![0.0; 8];
+        let mut audio_buffer = AudioBuffer::new(2, 4, &mut buffer);
+        assert_no_alloc(|| {
+            graph.process(&mut audio_buffer, input_node_id, output_node_id);
+        });
+
+        for sample in audio_buffer.as_slice() {
+            assert!((*sample - 0.2).abs() < 1e-6);
+        }
+    }
+
+    // メイン入力を反転した信号を出力ポート 1 に書き込むテスト用のノード
+    struct InvertPortNode;
+
+    impl AudioGraphNode for InvertPortNode {
+        fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+
+        fn process(&mut self, _buffer: &mut AudioBuffer) {}
+
+        fn reset(&mut self) {}
+
+        fn num_output_ports(&self) -> usize {
+            2
+        }
+
+        fn process_with_outputs(
+            &mut self,
+            buffer: &mut AudioBuffer,
+            _inputs: &InputPorts,
+            outputs: &mut OutputPorts,
+        ) {
+            let Some(port) = outputs.get_mut(1) else {
                 return;
+            };
+            for (out, sample) in port.iter_mut().zip(buffer.as_slice()) {
+                *out = -sample;
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_ports() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let node_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let invert_id = graph.add_node(Box::new(InvertPortNode));
+        let gain_id = graph.add_node(Box::new(GainProcessor::new()));
+        assert!(graph.add_edge(node_id, invert_id).is_ok());
+        assert!(graph.add_edge(invert_id, gain_id).is_ok());
+
+        // 出力ポート 1 を出力ノードに接続する。存在しない出力ポートからの接続は失敗する
+        assert!(
+            graph
+                .add_edge_from_port(invert_id, 1, output_node_id, 0)
+                .is_ok()
+        );
+        assert!(
+            graph
+                .add_edge_from_port(gain_id, 1, output_node_id, 0)
+                .is_err()
+        );
+        graph.prepare(44100.0, 4);
+
+        let mut buffer: Vec<f32> = vec![0.0; 8];
+        let mut audio_buffer = AudioBuffer::new(2, 4, &mut buffer);
+        assert_no_alloc(|| {
+            graph.process(&mut audio_buffer, input_node_id, output_node_id);
+        });
+        assert!(buffer.iter().all(|sample| (*sample + 0.5).abs() < 1e-6));
+    }
+
+    /// プレーナー形式で処理し、右チャンネルに左チャンネルを加算するノード
+    struct PlanarMixNode;
+
+    impl AudioGraphNode for PlanarMixNode {
+        fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+
+        fn process(&mut self, _buffer: &mut AudioBuffer) {
+            panic!("プレーナー形式で呼び出されるはずです");
+        }
+
+        fn reset(&mut self) {}
+
+        fn preferred_layout(&self) -> BufferLayout {
+            BufferLayout::Planar
+        }
+
+        fn process_planar(&mut self, buffer: &mut PlanarAudioBuffer) {
+            let slices = buffer.as_mut_slices();
+            let (left, right) = slices.split_at_mut(1);
+            for (r, l) in right[0].iter_mut().zip(left[0].iter()) {
+                *r += *l;
             }
-        };
+        }
+    }
 
-        // 出力ノードの出力を外部バッファにコピー
-        audio_buffer_utils::copy_buffer(
-            &AudioBuffer::new(num_channels, buffer_size, out_node_output),
-            buffer,
-        );
+    #[test]
+    fn test_process_planar_node() {
+        let mut graph = AudioGraph::new();
+        let source_id = graph.add_node(Box::new(TestNode::new(1.0)));
+        let planar_id = graph.add_node(Box::new(PlanarMixNode));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        graph.add_edge(source_id, planar_id).unwrap();
+        graph.add_edge(planar_id, output_id).unwrap();
+        graph.prepare(44100.0, 4);
+
+        let mut vector: Vec<f32> = vec![0.0; 8];
+        let mut buffer = AudioBuffer::new(2, 4, vector.as_mut_slice());
+        graph.process(&mut buffer, source_id, output_id);
+
+        assert_eq!(vector, vec![1.0, 2.0, 1.0, 2.0, 1.0, 2.0, 1.0, 2.0]);
     }
 
-    /// グラフのすべてのノードをリセットする
-    ///
-    /// # 実装時の注意
-    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
-    pub fn reset(&mut self) {
-        for node in self.nodes.values_mut() {
-            node.reset();
+    #[test]
+    fn test_rate_factor_node() {
+        /// 2 倍のレートで処理し、準備されたレートと処理したフレーム数を記録するノード
+        struct OversampledNode {
+            sample_rate: f32,
+            processed_frames: usize,
         }
-    }
 
-    /// ノードを削除する
-    ///
-    /// # 引数
-    /// * `node_id` - 削除するノードのID
-    ///
-    /// # 戻り値
-    /// * 成功した場合はノードが含まれる `Some`、存在しない場合は `None`
-    ///
-    /// # 実装時の注意
-    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
-    pub fn remove_node(&mut self, node_id: usize) -> Option<Box<dyn AudioGraphNode>> {
-        // グラフからノードを削除
-        if !self.graph.remove_node(node_id) {
-            return None;
+        impl AudioGraphNode for OversampledNode {
+            fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+                self.sample_rate = sample_rate;
+            }
+            fn process(&mut self, buffer: &mut AudioBuffer) {
+                self.processed_frames += buffer.num_frames();
+                buffer.as_mut_slice().fill(0.5);
+            }
+            fn reset(&mut self) {}
+            fn get_parameter(&self, id: &str) -> Option<f32> {
+                match id {
+                    "sample_rate" => Some(self.sample_rate),
+                    "processed_frames" => Some(self.processed_frames as f32),
+                    _ => None,
+                }
+            }
+            fn rate_factor(&self) -> f32 {
+                2.0
+            }
         }
 
-        // ノード出力バッファを削除
-        self.node_outputs.remove(&node_id);
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let node_id = graph.add_node(Box::new(OversampledNode {
+            sample_rate: 0.0,
+            processed_frames: 0,
+        }));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        graph.add_edge(input_id, node_id).unwrap();
+        graph.add_edge(node_id, output_id).unwrap();
+        graph.prepare(48000.0, 64);
+        assert_eq!(graph.get_parameter(node_id, "sample_rate"), Some(96000.0));
+        assert!(graph.tail_samples(output_id) > 0);
 
-        // ノードマップからノードを削除して返す
-        self.nodes.remove(&node_id)
-    }
+        let mut vector: Vec<f32> = vec![0.0; 128];
+        for _ in 0..32 {
+            let mut buffer = AudioBuffer::new(2, 64, vector.as_mut_slice());
+            graph.process(&mut buffer, input_id, output_id);
+        }
 
-    /// エッジを削除する
-    ///
-    /// # 引数
-    /// * `from_id` - 接続元ノードのID
-    /// * `to_id` - 接続先ノードのID
-    ///
-    /// # 戻り値
-    /// * 成功した場合は `true`、存在しない場合は `false`
-    ///
-    /// # 実装時の注意
-    /// この関数はメインスレッドなどの非リアルタイムスレッドから呼び出されることを想定しています。
-    pub fn remove_edge(&mut self, from_id: usize, to_id: usize) -> bool {
-        self.graph.remove_edge(from_id, to_id)
+        // 内部では 2 倍のフレーム数を処理し、出力はグラフのレートに戻る
+        let processed = graph.get_parameter(node_id, "processed_frames").unwrap();
+        assert!(
+            (processed - 2.0 * 32.0 * 64.0).abs() <= 4.0,
+            "{}",
+            processed
+        );
+        assert!(
+            vector.iter().all(|s| (s - 0.5).abs() < 1e-2),
+            "{:?}",
+            vector
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use assert_no_alloc::AllocDisabler;
-    use assert_no_alloc::assert_no_alloc;
+    #[test]
+    fn test_get_node() {
+        let mut graph = AudioGraph::new();
+        let node_id = graph.add_node(Box::new(TestNode::new(0.5)));
 
-    use crate::nodes::{InputNode, OutputNode};
+        assert!(graph.get_node(node_id).is_some());
+        assert!(graph.get_node(NodeId::from_raw(999)).is_none()); // 存在しないID
+    }
 
-    use super::*;
+    #[test]
+    fn test_morph_between_snapshots() {
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let gain_id = graph.add_node(Box::new(GainProcessor::new()));
+        let sine_id = graph.add_node(Box::new(SineGenerator::new()));
+        graph.add_edge(sine_id, gain_id).unwrap();
+        graph.add_edge(gain_id, output_id).unwrap();
+        graph.prepare(1000.0, 10);
 
-    #[cfg(debug_assertions)] // required when disable_release is set (default)
-    #[global_allocator]
-    static A: AllocDisabler = AllocDisabler;
+        // 周波数は A にしか含まれないため、B 側は設定時点の値になる
+        let frequency = graph.get_parameter(sine_id, "frequency").unwrap();
+        graph
+            .set_morph_snapshots(
+                &[
+                    (gain_id, "gain".to_string(), 0.0),
+                    (sine_id, "frequency".to_string(), 100.0),
+                ],
+                &[(gain_id, "gain".to_string(), 1.0)],
+            )
+            .unwrap();
+        assert_eq!(graph.get_parameter(gain_id, "gain"), Some(0.0));
+        assert_eq!(graph.get_parameter(sine_id, "frequency"), Some(100.0));
 
-    // テスト用のダミーノード
-    struct TestNode {
-        value: f32,
+        // 位置はブロックごとに 20ms かけて変化する
+        graph.set_morph_position(0.5);
+        let mut samples = vec![0.0; 2 * 10];
+        graph.process(
+            &mut AudioBuffer::new(2, 10, &mut samples),
+            input_id,
+            output_id,
+        );
+        assert!((graph.get_parameter(gain_id, "gain").unwrap() - 0.25).abs() < 1e-6);
+        graph.process(
+            &mut AudioBuffer::new(2, 10, &mut samples),
+            input_id,
+            output_id,
+        );
+        assert_eq!(graph.get_parameter(gain_id, "gain"), Some(0.5));
+        let expected = 100.0 + (frequency - 100.0) * 0.5;
+        assert_eq!(graph.get_parameter(sine_id, "frequency"), Some(expected));
+
+        assert!(
+            graph
+                .set_morph_snapshots(&[(gain_id, "missing".to_string(), 0.0)], &[])
+                .is_err()
+        );
     }
 
-    impl TestNode {
-        fn new(value: f32) -> Self {
-            Self { value }
-        }
+    #[test]
+    fn test_macro_drives_multiple_parameters() {
+        let mut graph = AudioGraph::new();
+        let gain_id = graph.add_node(Box::new(GainProcessor::new()));
+        let sine_id = graph.add_node(Box::new(SineGenerator::new()));
+        graph
+            .add_macro_mapping(1, gain_id, "gain", 1.0, 0.0, MacroCurve::Linear)
+            .unwrap();
+        graph
+            .add_macro_mapping(
+                1,
+                sine_id,
+                "frequency",
+                100.0,
+                400.0,
+                MacroCurve::Exponential,
+            )
+            .unwrap();
+        assert_eq!(graph.get_parameter(gain_id, "gain"), Some(1.0));
+
+        // 1 つのマクロで、範囲とカーブの異なる複数のパラメーターが変わる
+        assert!(graph.set_macro(1, 0.5));
+        assert_eq!(graph.get_parameter(gain_id, "gain"), Some(0.5));
+        let frequency = graph.get_parameter(sine_id, "frequency").unwrap();
+        assert!((frequency - 200.0).abs() < 1e-3, "{}", frequency);
+        assert_eq!(graph.macro_value(1), Some(0.5));
+
+        // 割り当てを削除したパラメーターは変わらない
+        assert!(graph.remove_macro_mapping(1, gain_id, "gain"));
+        graph.set_macro(1, 1.0);
+        assert_eq!(graph.get_parameter(gain_id, "gain"), Some(0.5));
+        assert!(!graph.set_macro(MAX_MACROS, 0.0));
+        assert!(
+            graph
+                .add_macro_mapping(0, gain_id, "missing", 0.0, 1.0, MacroCurve::Linear)
+                .is_err()
+        );
     }
 
-    impl AudioGraphNode for TestNode {
-        fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {
-            // 何もしない
-        }
+    #[test]
+    fn test_automation_switches_at_exact_sample() {
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let gain_id = graph.add_node(Box::new(GainProcessor::new()));
+        graph.add_edge(input_id, gain_id).unwrap();
+        graph.add_edge(gain_id, output_id).unwrap();
+        graph.prepare(48000.0, 64);
 
-        fn process(&mut self, buffer: &mut AudioBuffer) {
-            // すべてのサンプルの値を value にします。
-            for sample in buffer.as_mut_slice() {
-                *sample = self.value;
+        // 10 フレーム目で 0 に切り替え、40 フレーム目から 72 フレーム目まで 0 から 1 に直線的に変化させる
+        let mut lane = AutomationLane::new();
+        lane.add_breakpoint(0, 1.0, AutomationCurve::Hold);
+        lane.add_breakpoint(10, 0.0, AutomationCurve::Hold);
+        lane.add_breakpoint(40, 0.0, AutomationCurve::Linear);
+        lane.add_breakpoint(72, 1.0, AutomationCurve::Hold);
+        graph.set_automation(gain_id, "gain", lane).unwrap();
+        assert!(
+            graph
+                .set_automation(gain_id, "missing", AutomationLane::new())
+                .is_err()
+        );
+
+        // Hold の区間はブレークポイントのサンプルで切り替わり、Linear の区間はサンプルごとに補間した値になる
+        let expected = |frame: usize| match frame {
+            0..10 => 1.0,
+            10..40 => 0.0,
+            40..72 => (frame - 40) as f32 / 32.0,
+            _ => 1.0,
+        };
+        for block in 0..2 {
+            let mut data = vec![1.0; 2 * 64];
+            graph.process(&mut AudioBuffer::new(2, 64, &mut data), input_id, output_id);
+            for (i, frame) in data.chunks_exact(2).enumerate() {
+                let value = expected(block * 64 + i);
+                assert!(
+                    frame.iter().all(|&s| (s - value).abs() < 1e-6),
+                    "frame {}: {:?} != {}",
+                    block * 64 + i,
+                    frame,
+                    value
+                );
             }
         }
+        assert_eq!(graph.automation_position(), 128);
 
-        fn reset(&mut self) {
-            // 何もしない
-        }
+        // 位置を戻すと同じ値から再生し、削除した後はパラメーターを変更しない
+        graph.set_automation_position(0);
+        let mut data = vec![1.0; 2 * 4];
+        graph.process(&mut AudioBuffer::new(2, 4, &mut data), input_id, output_id);
+        assert_eq!(data[0], 1.0);
+        assert!(graph.remove_automation(gain_id, "gain").is_some());
+        graph.set_parameter(gain_id, "gain", 0.5).unwrap();
+        graph.process(&mut AudioBuffer::new(2, 4, &mut data), input_id, output_id);
+        assert_eq!(graph.get_parameter(gain_id, "gain"), Some(0.5));
     }
 
     #[test]
-    fn test_add_node() {
+    fn test_gain_staging_report() {
         let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let boost_id = graph.add_node(Box::new(TestNode::new(2.0)));
+        let mut gain = GainProcessor::new();
+        gain.set_gain(0.25);
+        let gain_id = graph.add_node(Box::new(gain));
+        graph.add_edge(input_id, boost_id).unwrap();
+        graph.add_edge(boost_id, gain_id).unwrap();
+        graph.add_edge(gain_id, output_id).unwrap();
+        graph.prepare(44100.0, 4);
+        graph.set_gain_staging_enabled(true);
 
-        let node_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        // TestNode は 2.0 を出力するため内部でクリップするが、後段で下げるので出力はクリップしない
+        let mut samples = vec![0.0; 8];
+        assert_no_alloc(|| {
+            graph.process(
+                &mut AudioBuffer::new(2, 4, &mut samples),
+                input_id,
+                output_id,
+            )
+        });
+        let report = graph.gain_staging_report();
+        let stage = |id: NodeId| *report.iter().find(|stage| stage.node_id == id).unwrap();
+        assert!(stage(boost_id).clips());
+        assert_eq!(stage(boost_id).output_peak, 2.0);
+        assert!(!stage(gain_id).clips());
+        assert_eq!(stage(gain_id).gain_db(), Some(20.0 * 0.25f32.log10()));
+        assert_eq!(stage(output_id).blocks, 1);
 
-        assert_eq!(graph.nodes.len(), 1);
-        assert!(graph.nodes.contains_key(&node_id));
+        graph.reset_gain_staging();
+        assert!(
+            graph
+                .gain_staging_report()
+                .iter()
+                .all(|stage| stage.blocks == 0)
+        );
+        graph.set_gain_staging_enabled(false);
+        assert!(graph.gain_staging_report().is_empty());
     }
 
     #[test]
-    fn test_add_edge() {
+    fn test_stats() {
         let mut graph = AudioGraph::new();
-        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
-        let node2_id = graph.add_node(Box::new(TestNode::new(0.3)));
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let first_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let second_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        graph.add_edge(input_id, first_id).unwrap();
+        graph.add_edge(first_id, second_id).unwrap();
+        graph.add_edge(second_id, output_id).unwrap();
+        graph.add_edge(input_id, output_id).unwrap();
+        assert_eq!(graph.stats().samples_copied_per_block, 0);
 
-        let result = graph.add_edge(node1_id, node2_id);
-        assert!(result.is_ok());
+        // 4 ノードの出力バッファと 4 つの一時バッファが、それぞれ 2ch × 4 フレーム
+        graph.prepare(44100.0, 4);
+        let stats = graph.stats();
+        assert_eq!(stats.num_nodes, 4);
+        assert_eq!(stats.num_edges, 4);
+        assert_eq!(stats.num_feedback_edges, 0);
+        assert_eq!(stats.longest_path, 3);
+        assert_eq!(stats.buffer_bytes, 8 * 8 * 4);
+        // 4 本のエッジの合成、4 ノードの出力のコピー、外部バッファとの 2 回のコピー
+        assert_eq!(stats.samples_copied_per_block, 10 * 8);
     }
 
     #[test]
-    fn test_cycle_detection() {
+    fn test_debug_snapshot() {
         let mut graph = AudioGraph::new();
-        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
-        let node2_id = graph.add_node(Box::new(TestNode::new(0.3)));
-        let node3_id = graph.add_node(Box::new(TestNode::new(0.2)));
-
-        // node1 -> node2 -> node3
-        assert!(graph.add_edge(node1_id, node2_id).is_ok());
-        assert!(graph.add_edge(node2_id, node3_id).is_ok());
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let gain_id = graph.add_node(Box::new(GainProcessor::new()));
+        graph.add_edge(input_id, gain_id).unwrap();
+        graph.add_edge(gain_id, output_id).unwrap();
+        graph.set_edge_gain(gain_id, output_id, 0, 0.5).unwrap();
+        graph.set_parameter(gain_id, "gain", 0.25).unwrap();
+        graph.prepare_with_block_size(48000.0, 64, Some(16));
 
-        // node3 -> node1 would create a cycle
-        let result = graph.add_edge(node3_id, node1_id);
-        assert!(result.is_err());
+        let snapshot = graph.debug_snapshot();
+        assert_eq!(snapshot.block_size, 16);
+        assert_eq!(
+            snapshot.processing_order,
+            vec![input_id, gain_id, output_id]
+        );
+        let gain = &snapshot.nodes[1];
+        assert_eq!(gain.node_id, gain_id);
+        assert_eq!(gain.output_capacity, 64);
+        assert_eq!(gain.parameters, vec![("gain", 0.25)]);
+        let output = &snapshot.nodes[2];
+        assert_eq!(output.inputs.len(), 1);
+        assert_eq!(output.inputs[0].from_id, gain_id);
+        assert_eq!(output.inputs[0].gain, 0.5);
     }
 
     #[test]
-    fn test_serial_process() {
+    fn test_freeze_and_unfreeze_subgraph() {
         let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let sine_id = graph.add_node(Box::new(SineGenerator::new()));
+        let gain_id = graph.add_node(Box::new(GainProcessor::new()));
+        graph.add_edge(sine_id, gain_id).unwrap();
+        graph.add_edge(gain_id, output_id).unwrap();
+        graph.set_parameter(gain_id, "gain", 0.5).unwrap();
+        graph.prepare(48000.0, 64);
 
-        let input_node = InputNode::new();
-        let output_node = OutputNode::new();
+        let mut expected = vec![0.0; 2 * 128];
+        graph.process(
+            &mut AudioBuffer::new(2, 128, &mut expected),
+            input_id,
+            output_id,
+        );
+        graph.reset();
 
-        let input_node_id = graph.add_node(Box::new(input_node));
-        let output_node_id = graph.add_node(Box::new(output_node));
-        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
-        let node2_id = graph.add_node(Box::new(TestNode::new(0.3)));
+        // フリーズ後は、レンダリングしたサンプル列が同じ出力になる
+        let player_id = graph.freeze(&[sine_id, gain_id], 128).unwrap();
+        assert_eq!(graph.edges(), vec![(player_id, output_id)]);
+        assert!(graph.set_parameter(gain_id, "gain", 1.0).is_err());
+        let mut frozen = vec![0.0; 2 * 128];
+        graph.process(
+            &mut AudioBuffer::new(2, 128, &mut frozen),
+            input_id,
+            output_id,
+        );
+        assert_eq!(frozen, expected);
 
-        // 直列に接続。
-        // 入力ノード -> node1 -> node2 -> 出力ノード
-        assert!(graph.add_edge(input_node_id, node1_id).is_ok());
-        assert!(graph.add_edge(node1_id, node2_id).is_ok());
-        assert!(graph.add_edge(node2_id, output_node_id).is_ok());
+        // 元に戻すと、同じ ID で同じ接続になる
+        graph.unfreeze(player_id).unwrap();
+        assert!(graph.get_node(player_id).is_none());
+        assert_eq!(
+            graph.edges(),
+            vec![(sine_id, gain_id), (gain_id, output_id)]
+        );
+        assert_eq!(graph.get_parameter(gain_id, "gain"), Some(0.5));
+        let mut unfrozen = vec![0.0; 2 * 128];
+        graph.process(
+            &mut AudioBuffer::new(2, 128, &mut unfrozen),
+            input_id,
+            output_id,
+        );
+        assert_eq!(unfrozen, expected);
 
-        // オーディオ処理の準備
-        graph.prepare(44100.0, 4);
+        // サブグラフの外から入力がある場合はフリーズできない
+        graph.add_edge(input_id, gain_id).unwrap();
+        assert!(graph.freeze(&[gain_id], 128).is_err());
+    }
 
-        // 2チャンネル、4サンプルのバッファを作成
-        let mut buffer: Vec<f32> = vec![0.0; 8];
-        let mut audio_buffer = AudioBuffer::new(2, 4, &mut buffer);
+    #[test]
+    fn test_freeze_keeps_feedback_edges() {
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let sine_id = graph.add_node(Box::new(SineGenerator::new()));
+        let gain_id = graph.add_node(Box::new(GainProcessor::new()));
+        graph.add_edge(sine_id, gain_id).unwrap();
+        graph.add_edge(gain_id, output_id).unwrap();
+        graph.add_feedback_edge(gain_id, gain_id, 0, 0.5).unwrap();
+        graph.prepare(48000.0, 64);
 
-        assert_no_alloc(|| {
-            // グラフを処理
-            graph.process(&mut audio_buffer, input_node_id, output_node_id);
-        });
+        let mut expected = vec![0.0; 2 * 128];
+        graph.process(
+            &mut AudioBuffer::new(2, 128, &mut expected),
+            input_id,
+            output_id,
+        );
+        graph.reset();
 
-        // トポロジカル順序で処理されるため、node1とnode2の両方が適用されるはず
-        for sample in audio_buffer.as_slice() {
-            // 最後のノードの値になるはず。
-            assert_eq!(*sample, 0.3);
-        }
+        // サブグラフ内のフィードバックもレンダリングに含め、元に戻すとフィードバックのエッジも戻る
+        let player_id = graph.freeze(&[sine_id, gain_id], 128).unwrap();
+        assert!(graph.feedback_edges().is_empty());
+        let mut frozen = vec![0.0; 2 * 128];
+        graph.process(
+            &mut AudioBuffer::new(2, 128, &mut frozen),
+            input_id,
+            output_id,
+        );
+        assert_eq!(frozen, expected);
+        graph.unfreeze(player_id).unwrap();
+        assert_eq!(graph.feedback_edges(), vec![(gain_id, gain_id)]);
+
+        // 境界をまたぐフィードバックのエッジがある場合はフリーズできず、グラフは変わらない
+        graph.add_feedback_edge(gain_id, sine_id, 0, 0.5).unwrap();
+        assert!(graph.freeze(&[gain_id], 128).is_err());
+        assert!(graph.get_node(gain_id).is_some());
+        assert_eq!(graph.feedback_edges().len(), 2);
     }
 
     #[test]
-    fn test_parallel_process() {
+    fn test_lifecycle_hooks() {
+        use std::sync::{Arc, Mutex};
+
+        /// フックの呼び出しを記録するノード
+        struct LifecycleNode(Arc<Mutex<Vec<String>>>);
+
+        impl AudioGraphNode for LifecycleNode {
+            fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+            fn process(&mut self, _buffer: &mut AudioBuffer) {}
+            fn reset(&mut self) {}
+            fn on_added(&mut self, context: &NodeContext) {
+                let event = format!("added {} ({})", context.node_id, context.max_buffer_size);
+                self.0.lock().unwrap().push(event);
+            }
+            fn on_removed(&mut self, context: &NodeContext) {
+                let event = format!("removed {}", context.node_id);
+                self.0.lock().unwrap().push(event);
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
         let mut graph = AudioGraph::new();
+        graph.prepare(48000.0, 64);
+        let first_id = graph.add_node(Box::new(LifecycleNode(events.clone())));
+        let second_id = graph.add_node(Box::new(LifecycleNode(events.clone())));
+        assert!(graph.remove_node(first_id).is_some());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["added 0 (64)", "added 1 (64)", "removed 0"]
+        );
 
-        let input_node = InputNode::new();
-        let output_node = OutputNode::new();
+        // グラフを破棄すると、残っているノードも取り除かれる
+        drop(graph);
+        assert_eq!(events.lock().unwrap().len(), 4);
+        assert_eq!(events.lock().unwrap()[3], format!("removed {}", second_id));
+    }
 
-        let input_node_id = graph.add_node(Box::new(input_node));
-        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
-        let node2_id = graph.add_node(Box::new(TestNode::new(0.3)));
-        let output_node_id = graph.add_node(Box::new(output_node));
+    #[test]
+    fn test_stale_node_id() {
+        let mut graph = AudioGraph::new();
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let old_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        assert!(graph.remove_node(old_id).is_some());
 
-        /*
-        両方のノードを出力ノードに接続する（並列処理）
-        ```mermaid
-        flowchart LR
-            入力ノード --> ノード1
-            入力ノード --> ノード2
-            ノード1 --> 出力ノード
-            ノード2 --> 出力ノード
-        ```
-        */
-        assert!(graph.add_edge(input_node_id, node1_id).is_ok());
-        assert!(graph.add_edge(input_node_id, node2_id).is_ok());
-        assert!(graph.add_edge(node1_id, output_node_id).is_ok());
-        assert!(graph.add_edge(node2_id, output_node_id).is_ok());
+        // インデックスは再利用されるが、世代が異なるため古い ID では新しいノードを操作できない
+        let new_id = graph.add_node(Box::new(GainProcessor::new()));
+        assert_ne!(new_id, old_id);
+        assert_eq!(NodeId::from_raw(new_id.as_raw()), new_id);
+        assert!(graph.get_node(old_id).is_none());
+        assert!(graph.remove_node(old_id).is_none());
+        assert!(graph.set_parameter(old_id, "gain", 0.5).is_err());
+        let error = graph.add_edge(old_id, output_id).unwrap_err();
+        assert!(error.contains("削除済み"), "{}", error);
+        graph.add_edge(new_id, output_id).unwrap();
+        assert_eq!(graph.edges(), vec![(new_id, output_id)]);
+    }
 
-        // オーディオ処理の準備
+    #[test]
+    fn test_process_reports_diagnostics() {
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        graph.add_edge(input_id, output_id).unwrap();
         graph.prepare(44100.0, 4);
+        let diagnostics = graph.diagnostics();
 
-        // 2チャンネル、4サンプルのバッファを作成
-        let mut buffer: Vec<f32> = vec![0.0; 2 * 4];
-        let mut audio_buffer = AudioBuffer::new(2, 4, &mut buffer);
+        // 削除した出力ノードを渡しても panic せず、問題の種類とノードを記録する
+        graph.remove_node(output_id);
+        let mut samples = vec![0.0; 8];
+        assert_no_alloc(|| {
+            graph.process(
+                &mut AudioBuffer::new(2, 4, &mut samples),
+                input_id,
+                output_id,
+            );
+            graph.process(&mut AudioBuffer::new(0, 0, &mut []), input_id, output_id);
+        });
+        assert_eq!(diagnostics.count(DiagnosticCode::MissingOutputNode), 1);
+        assert_eq!(
+            diagnostics.last_node(DiagnosticCode::MissingOutputBuffer),
+            Some(output_id)
+        );
+        assert_eq!(diagnostics.count(DiagnosticCode::InvalidChannelCount), 1);
+        assert_eq!(diagnostics.count(DiagnosticCode::MissingInputNode), 0);
+    }
 
-        // グラフを処理
+    #[test]
+    fn test_fixed_capacity_edits_without_allocation() {
+        let mut graph = AudioGraph::with_capacity(GraphCapacity {
+            max_nodes: 4,
+            max_edges_per_node: 2,
+            max_input_ports: 3,
+        });
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        graph.add_edge(input_node_id, output_node_id).unwrap();
+        graph.prepare(44100.0, 4);
+
+        // ノードは非リアルタイムスレッドで準備してから渡す
+        let mut sines: Vec<Option<Box<dyn AudioGraphNode>>> = (0..3)
+            .map(|_| {
+                let mut sine = SineGenerator::new();
+                sine.prepare(44100.0, 4);
+                Some(Box::new(sine) as Box<dyn AudioGraphNode>)
+            })
+            .collect();
+        let mut buffer: Vec<f32> = vec![0.0; 8];
+        let mut added = [None; 2];
         assert_no_alloc(|| {
-            graph.process(&mut audio_buffer, input_node_id, output_node_id);
+            // 追加と削除を繰り返しても、確保済みのメモリを使い回す
+            for _ in 0..16 {
+                let sine_id = graph.try_add_node(sines[0].take().unwrap()).ok().unwrap();
+                graph
+                    .add_edge_with_gain(sine_id, output_node_id, 0, 0.5)
+                    .unwrap();
+                graph.process(
+                    &mut AudioBuffer::new(2, 4, &mut buffer),
+                    input_node_id,
+                    output_node_id,
+                );
+                assert!(graph.remove_edge(sine_id, output_node_id));
+                sines[0] = graph.remove_node(sine_id);
+            }
+
+            // 上限を超えるノードは追加せずに返す
+            added[0] = graph.try_add_node(sines[0].take().unwrap()).ok();
+            added[1] = graph.try_add_node(sines[1].take().unwrap()).ok();
+            sines[2] = graph.try_add_node(sines[2].take().unwrap()).err();
         });
+        assert!(buffer.iter().any(|sample| sample.abs() > 0.0));
+        assert!(sines[2].is_some());
 
-        // node1とnode2のが合流するので両方が適用されるはず
-        for sample in audio_buffer.as_slice() {
-            // 0.5 + 0.3 = 0.8
-            assert_eq!(*sample, 0.8);
-        }
+        // ノードあたりのエッジ数の上限を超える接続は失敗する
+        let [Some(first), Some(second)] = added else {
+            panic!("ノードを追加できません");
+        };
+        graph.add_edge(first, output_node_id).unwrap();
+        assert!(graph.add_edge(second, output_node_id).is_err());
+
+        // 固定容量モードでないグラフには追加できない
+        assert!(
+            AudioGraph::new()
+                .try_add_node(sines[2].take().unwrap())
+                .is_err()
+        );
     }
 
     #[test]
-    fn test_get_node() {
+    fn test_topology_and_output_peak() {
         let mut graph = AudioGraph::new();
-        let node_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let node1_id = graph.add_node(Box::new(TestNode::new(0.5)));
+        let node2_id = graph.add_node(Box::new(TestNode::new(-0.3)));
+        graph.add_edge(input_node_id, node1_id).unwrap();
+        graph.add_edge(node1_id, node2_id).unwrap();
+        graph.add_edge(node2_id, output_node_id).unwrap();
 
-        assert!(graph.get_node(node_id).is_some());
-        assert!(graph.get_node(999).is_none()); // 存在しないID
+        assert_eq!(
+            graph.node_ids(),
+            vec![input_node_id, output_node_id, node1_id, node2_id]
+        );
+        assert_eq!(
+            graph.edges(),
+            vec![
+                (input_node_id, node1_id),
+                (node1_id, node2_id),
+                (node2_id, output_node_id)
+            ]
+        );
+
+        // prepare 前はメーターの値がない
+        assert_eq!(graph.output_peak(node1_id), None);
+        graph.prepare(44100.0, 4);
+        let mut buffer: Vec<f32> = vec![0.0; 8];
+        graph.process(
+            &mut AudioBuffer::new(2, 4, &mut buffer),
+            input_node_id,
+            output_node_id,
+        );
+        assert_eq!(graph.output_peak(node1_id), Some(0.5));
+        assert_eq!(graph.output_peak(node2_id), Some(0.3));
+
+        // チャンネルごとの値。出力にないチャンネルは 0 になる
+        let mut peaks = [1.0; 3];
+        assert!(graph.output_channel_peaks(node2_id, &mut peaks));
+        assert_eq!(peaks, [0.3, 0.3, 0.0]);
+    }
+
+    /// グラフに対する操作（プロパティテスト用）
+    ///
+    /// ノードやエッジは、その時点の一覧のインデックス（一覧の長さで剰余をとる）で指定します。
+    #[derive(Clone, Debug)]
+    enum GraphOperation {
+        AddNode(u8),
+        AddEdge(usize, usize, usize),
+        RemoveNode(usize),
+        RemoveEdge(usize),
+        Prepare(usize),
+        Process(usize),
+    }
+
+    fn graph_operation() -> impl Strategy<Value = GraphOperation> {
+        prop_oneof![
+            (0u8..5).prop_map(GraphOperation::AddNode),
+            (any::<usize>(), any::<usize>(), 0usize..3)
+                .prop_map(|(from, to, port)| GraphOperation::AddEdge(from, to, port)),
+            any::<usize>().prop_map(GraphOperation::RemoveNode),
+            any::<usize>().prop_map(GraphOperation::RemoveEdge),
+            (1usize..=128).prop_map(GraphOperation::Prepare),
+            any::<usize>().prop_map(GraphOperation::Process),
+        ]
+    }
+
+    fn create_node(kind: u8) -> Box<dyn AudioGraphNode> {
+        match kind {
+            0 => Box::new(TestNode::new(0.25)),
+            1 => Box::new(SineGenerator::new()),
+            2 => Box::new(GainProcessor::new()),
+            3 => Box::new(Eq3::new()),
+            _ => Box::new(Crossfader::new()),
+        }
+    }
+
+    proptest! {
+        /// 再生中の編集を想定して、ランダムな操作の列を適用しても不変条件が保たれることを確認する
+        #[test]
+        fn test_random_graph_edits(
+            operations in prop::collection::vec(graph_operation(), 1..64)
+        ) {
+            let mut graph = AudioGraph::new();
+            let input_id = graph.add_node(Box::new(InputNode::new()));
+            let output_id = graph.add_node(Box::new(OutputNode::new()));
+            graph.prepare(48000.0, 64);
+            // 最大バッファサイズを超えるバッファも渡す
+            let mut buffer = vec![0.0; 2 * 256];
+
+            for operation in operations {
+                let node_ids = graph.node_ids();
+                match operation {
+                    GraphOperation::AddNode(kind) => {
+                        graph.add_node(create_node(kind));
+                    }
+                    GraphOperation::AddEdge(from, to, port) => {
+                        // 循環や存在しないポートへの接続はエラーになるだけ
+                        let from = node_ids[from % node_ids.len()];
+                        let to = node_ids[to % node_ids.len()];
+                        let _ = graph.add_edge_to_port(from, to, port);
+                    }
+                    GraphOperation::RemoveNode(index) => {
+                        // 入力ノードと出力ノードは process に必要なため削除しない
+                        let removable: Vec<NodeId> = node_ids
+                            .into_iter()
+                            .filter(|&id| id != input_id && id != output_id)
+                            .collect();
+                        if !removable.is_empty() {
+                            let node_id = removable[index % removable.len()];
+                            prop_assert!(graph.remove_node(node_id).is_some());
+                        }
+                    }
+                    GraphOperation::RemoveEdge(index) => {
+                        let edges = graph.edges();
+                        if !edges.is_empty() {
+                            let (from, to) = edges[index % edges.len()];
+                            prop_assert!(graph.remove_edge(from, to));
+                        }
+                    }
+                    GraphOperation::Prepare(size) => {
+                        graph.prepare(48000.0, size);
+                    }
+                    GraphOperation::Process(frames) => {
+                        let frames = frames % 257;
+                        let samples = &mut buffer[..2 * frames];
+                        for (i, sample) in samples.iter_mut().enumerate() {
+                            *sample = (i as f32 * 0.1).sin();
+                        }
+                        assert_no_alloc(|| {
+                            graph.process(
+                                &mut AudioBuffer::new(2, frames, samples),
+                                input_id,
+                                output_id,
+                            )
+                        });
+                        prop_assert!(samples.iter().all(|sample| sample.is_finite()));
+                    }
+                }
+
+                // 処理順には全ノードが 1 回ずつ現れ、エッジの接続元は接続先より先に処理される
+                let order = graph.graph.get_reverse_topological_order();
+                prop_assert_eq!(order.len(), graph.node_ids().len());
+                let position = |id: NodeId| order.iter().position(|&node_id| node_id == id);
+                for (from, to) in graph.edges() {
+                    prop_assert!(position(from).unwrap() < position(to).unwrap());
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,182 @@
+//! パラメーターのオートメーション（時刻と値のブレークポイントの列）です。
+//!
+//! AutomationLane に（サンプル単位の時刻, 値, カーブ）のブレークポイントを並べ、
+//! AudioGraph::set_automation でノードのパラメーターに割り当てると、process のたびにグラフの
+//! オートメーションの位置（AudioGraph::automation_position）での値がパラメーターに設定されます。
+//! パラメーターポートのあるパラメーターには、値がサンプルごとに渡されます。
+//! ホストのない環境（オフラインレンダリングやサービス）でも、DAW と同じようにパラメーターを時間に沿って変化させられます。
+//!
+//! ```ignore
+//! let mut lane = AutomationLane::new();
+//! lane.add_breakpoint(0, 200.0, AutomationCurve::Exponential);
+//! lane.add_breakpoint(48000, 2000.0, AutomationCurve::Hold);
+//! audio_graph.set_automation(filter_id, "cutoff", lane)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+use crate::parameter::MacroCurve;
+
+/// ブレークポイントから次のブレークポイントまでの値の変化のしかた
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AutomationCurve {
+    /// 次のブレークポイントまで値を保ち、次のブレークポイントの時刻で切り替える
+    Hold,
+    /// 直線的に変化する
+    Linear,
+    /// 比が一定になるように変化する（周波数など）。2 つの値が同じ符号でない場合は直線になる。
+    Exponential,
+    /// 経過の割合を指定した指数で累乗してから直線的に変化する
+    Power(f32),
+}
+
+/// オートメーションのブレークポイント
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Breakpoint {
+    /// 時刻（オートメーションの位置のサンプル数）
+    pub time: u64,
+    /// 値
+    pub value: f32,
+    /// 次のブレークポイントまでの値の変化のしかた
+    pub curve: AutomationCurve,
+}
+
+/// 1 つのパラメーターのオートメーション
+///
+/// ブレークポイントは時刻の順に保持します。最初のブレークポイントより前は最初の値、
+/// 最後のブレークポイントより後は最後の値になります。
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AutomationLane {
+    /// ブレークポイント（時刻の順）
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl AutomationLane {
+    /// 新しいAutomationLaneを作成
+    pub fn new() -> Self {
+        Self {
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// ブレークポイントを追加する
+    ///
+    /// 同じ時刻のブレークポイントが既にある場合は置き換えます。
+    ///
+    /// # 引数
+    /// * `time` - 時刻（オートメーションの位置のサンプル数）
+    /// * `value` - 値
+    /// * `curve` - 次のブレークポイントまでの値の変化のしかた
+    pub fn add_breakpoint(&mut self, time: u64, value: f32, curve: AutomationCurve) {
+        let breakpoint = Breakpoint { time, value, curve };
+        let idx = self.breakpoints.partition_point(|bp| bp.time < time);
+        match self.breakpoints.get_mut(idx) {
+            Some(existing) if existing.time == time => *existing = breakpoint,
+            _ => self.breakpoints.insert(idx, breakpoint),
+        }
+    }
+
+    /// 指定した時刻のブレークポイントを削除する
+    ///
+    /// # 戻り値
+    /// * 削除した場合は `true`、存在しない場合は `false`
+    pub fn remove_breakpoint(&mut self, time: u64) -> bool {
+        match self.breakpoints.binary_search_by_key(&time, |bp| bp.time) {
+            Ok(idx) => {
+                self.breakpoints.remove(idx);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// ブレークポイントの一覧（時刻の順）
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// ブレークポイントがないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty()
+    }
+
+    /// 指定した時刻の値を求める
+    ///
+    /// # 戻り値
+    /// * ブレークポイントがない場合は `None`
+    pub fn value_at(&self, time: u64) -> Option<f32> {
+        // time 以前の最後のブレークポイント
+        let next_idx = self.breakpoints.partition_point(|bp| bp.time <= time);
+        let Some(prev) = next_idx.checked_sub(1).map(|idx| &self.breakpoints[idx]) else {
+            return self.breakpoints.first().map(|bp| bp.value);
+        };
+        let Some(next) = self.breakpoints.get(next_idx) else {
+            return Some(prev.value);
+        };
+        let ratio = (time - prev.time) as f64 / (next.time - prev.time) as f64;
+        let ratio = ratio as f32;
+        Some(match prev.curve {
+            AutomationCurve::Hold => prev.value,
+            AutomationCurve::Linear => MacroCurve::Linear.map(ratio, prev.value, next.value),
+            AutomationCurve::Exponential => {
+                MacroCurve::Exponential.map(ratio, prev.value, next.value)
+            }
+            AutomationCurve::Power(exponent) => {
+                MacroCurve::Power(exponent).map(ratio, prev.value, next.value)
+            }
+        })
+    }
+
+    /// 指定した時刻より後の、最初のブレークポイントの時刻
+    pub fn next_breakpoint_time(&self, time: u64) -> Option<u64> {
+        let idx = self.breakpoints.partition_point(|bp| bp.time <= time);
+        self.breakpoints.get(idx).map(|bp| bp.time)
+    }
+
+    /// 指定した時刻で値が連続的に変化している（Hold 以外の区間の途中にある）かどうか
+    pub fn is_ramping(&self, time: u64) -> bool {
+        let next_idx = self.breakpoints.partition_point(|bp| bp.time <= time);
+        match next_idx.checked_sub(1) {
+            Some(idx) => {
+                next_idx < self.breakpoints.len()
+                    && self.breakpoints[idx].curve != AutomationCurve::Hold
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lane_values() {
+        let mut lane = AutomationLane::new();
+        assert_eq!(lane.value_at(0), None);
+
+        lane.add_breakpoint(200, 0.0, AutomationCurve::Hold);
+        lane.add_breakpoint(100, 1.0, AutomationCurve::Linear);
+        lane.add_breakpoint(300, 0.5, AutomationCurve::Hold);
+        // 同じ時刻のブレークポイントは置き換える
+        lane.add_breakpoint(300, 1.0, AutomationCurve::Hold);
+        assert_eq!(lane.breakpoints().len(), 3);
+
+        // 最初より前は最初の値、最後より後は最後の値
+        assert_eq!(lane.value_at(0), Some(1.0));
+        assert_eq!(lane.value_at(1000), Some(1.0));
+        // Linear の区間は補間し、Hold の区間は次のブレークポイントまで値を保つ
+        assert_eq!(lane.value_at(150), Some(0.5));
+        assert_eq!(lane.value_at(299), Some(0.0));
+        assert!(lane.is_ramping(150));
+        assert!(!lane.is_ramping(250));
+        assert!(!lane.is_ramping(50));
+        assert_eq!(lane.next_breakpoint_time(150), Some(200));
+        assert_eq!(lane.next_breakpoint_time(300), None);
+
+        assert!(lane.remove_breakpoint(200));
+        assert!(!lane.remove_breakpoint(200));
+        assert_eq!(lane.value_at(200), Some(1.0));
+    }
+}
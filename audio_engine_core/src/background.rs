@@ -0,0 +1,378 @@
+//! ノードの重い処理（リアルタイムでない処理）をバックグラウンドのスレッドで実行する仕組みです。
+//!
+//! ウェーブテーブルの読み込みや FIR の再計算など、`process` の中で行えない処理は、
+//! ノードごとにスレッドを作成せずに、共有のワーカー（shared）へジョブとして送ります。
+//! ジョブの結果は Mailbox に届き、ノードはオーディオスレッドからロックやメモリアロケーションなしで受け取れます。
+//!
+//! スレッドを作成できない wasm32 では、ジョブはワーカーに送らず、送った側のスレッドでその場で実行します。
+//!
+//! ```ignore
+//! use audio_engine_core::background::{self, Mailbox};
+//!
+//! // メインスレッド（set_parameter など）でジョブを送る
+//! background::shared().submit(&self.table_mailbox, move || build_table(size))?;
+//!
+//! // オーディオスレッド（process）で結果を受け取り、古い値をワーカーで解放させる
+//! if let Some(table) = self.table_mailbox.take() {
+//!     if let Some(old) = self.table.replace(table) {
+//!         self.table_mailbox.retire(old);
+//!     }
+//! }
+//! ```
+
+use std::ops::{Deref, DerefMut};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle};
+
+/// Mailbox で受け渡す値と、解放待ちのリストの次の要素
+struct Envelope<T> {
+    value: T,
+    next: *mut Envelope<T>,
+}
+
+/// Mailbox の中身（ワーカーとノードで共有する）
+struct MailboxSlots<T> {
+    /// まだ受け取られていない最新の結果
+    latest: AtomicPtr<Envelope<T>>,
+    /// ノードが使い終わり、ワーカーでの解放を待つ値のリスト
+    retired: AtomicPtr<Envelope<T>>,
+}
+
+// 値へのアクセスはポインターの swap で排他されているため、スレッド間で共有できる
+unsafe impl<T: Send> Send for MailboxSlots<T> {}
+unsafe impl<T: Send> Sync for MailboxSlots<T> {}
+
+impl<T> MailboxSlots<T> {
+    /// 解放待ちのリストを全て解放する
+    fn free_retired(&self) {
+        let mut envelope = self.retired.swap(ptr::null_mut(), Ordering::Acquire);
+        while !envelope.is_null() {
+            let boxed = unsafe { Box::from_raw(envelope) };
+            envelope = boxed.next;
+        }
+    }
+}
+
+impl<T> Drop for MailboxSlots<T> {
+    fn drop(&mut self) {
+        let latest = self.latest.swap(ptr::null_mut(), Ordering::Acquire);
+        if !latest.is_null() {
+            drop(unsafe { Box::from_raw(latest) });
+        }
+        self.free_retired();
+    }
+}
+
+/// バックグラウンドのジョブの結果を、オーディオスレッドへ受け渡すための受け取り口
+///
+/// 結果は最新の 1 つだけを保持します。受け取る前に次の結果が届いた場合、古い結果はワーカーで破棄されます。
+/// クローンすると同じ受け取り口を共有します。
+pub struct Mailbox<T> {
+    slots: Arc<MailboxSlots<T>>,
+}
+
+impl<T: Send + 'static> Mailbox<T> {
+    /// 新しいMailboxを作成
+    pub fn new() -> Self {
+        Self {
+            slots: Arc::new(MailboxSlots {
+                latest: AtomicPtr::new(ptr::null_mut()),
+                retired: AtomicPtr::new(ptr::null_mut()),
+            }),
+        }
+    }
+
+    /// 結果を置く（以前の結果が受け取られていなければ破棄し、解放待ちの値も解放する）
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    /// 通常は WorkerHandle::submit で送ったジョブの完了時に、ワーカーから呼び出されます。
+    pub fn post(&self, value: T) {
+        let envelope = Box::into_raw(Box::new(Envelope {
+            value,
+            next: ptr::null_mut(),
+        }));
+        let previous = self.slots.latest.swap(envelope, Ordering::AcqRel);
+        if !previous.is_null() {
+            drop(unsafe { Box::from_raw(previous) });
+        }
+        self.slots.free_retired();
+    }
+
+    /// 届いている結果を受け取る
+    ///
+    /// # 戻り値
+    /// * 前回受け取った後に結果が届いていなければ None
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn take(&self) -> Option<Letter<T>> {
+        let envelope = self.slots.latest.swap(ptr::null_mut(), Ordering::AcqRel);
+        (!envelope.is_null()).then(|| Letter {
+            envelope: unsafe { Box::from_raw(envelope) },
+        })
+    }
+
+    /// 使い終わった値を返し、次の結果が届いたときにワーカーで解放させる
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn retire(&self, letter: Letter<T>) {
+        let envelope = Box::into_raw(letter.envelope);
+        let mut head = self.slots.retired.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*envelope).next = head };
+            match self.slots.retired.compare_exchange_weak(
+                head,
+                envelope,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+}
+
+impl<T: Send + 'static> Default for Mailbox<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for Mailbox<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slots: self.slots.clone(),
+        }
+    }
+}
+
+/// Mailbox から受け取った値
+///
+/// オーディオスレッドで drop するとメモリを解放してしまうため、使い終わったら Mailbox::retire で返してください。
+pub struct Letter<T> {
+    envelope: Box<Envelope<T>>,
+}
+
+// 中身の値を所有しているだけなので、値が Send ならスレッド間で移動できる
+unsafe impl<T: Send> Send for Letter<T> {}
+
+impl<T> Letter<T> {
+    /// 中身の値を取り出す
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリの解放を行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn into_inner(self) -> T {
+        self.envelope.value
+    }
+}
+
+impl<T> Deref for Letter<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.envelope.value
+    }
+}
+
+impl<T> DerefMut for Letter<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.envelope.value
+    }
+}
+
+/// ワーカーで実行するジョブ
+type Job = Box<dyn FnOnce() + Send>;
+
+/// ワーカーのスレッドへのメッセージ
+enum Message {
+    Run(Job),
+    Stop,
+}
+
+/// ジョブを順番に実行するバックグラウンドのスレッド
+///
+/// drop するとスレッドを停止します。停止する前に、送られたジョブを全て実行します。
+pub struct BackgroundWorker {
+    /// ジョブの送信側
+    sender: Sender<Message>,
+    /// ジョブを実行するスレッド
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BackgroundWorker {
+    /// ワーカーのスレッドを開始
+    ///
+    /// # 引数
+    /// * `name` - スレッドの名前
+    pub fn start(name: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        // wasm32 ではスレッドを作成できないため、ジョブは WorkerHandle::spawn の中で実行する
+        let thread = if cfg!(target_arch = "wasm32") {
+            None
+        } else {
+            let thread = thread::Builder::new()
+                .name(name.to_string())
+                .spawn(move || {
+                    while let Ok(Message::Run(job)) = receiver.recv() {
+                        run_job(job);
+                    }
+                })
+                .expect("バックグラウンドのスレッドを開始できませんでした");
+            Some(thread)
+        };
+        Self { sender, thread }
+    }
+
+    /// ジョブを送るためのハンドルを取得
+    pub fn handle(&self) -> WorkerHandle {
+        WorkerHandle {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl Drop for BackgroundWorker {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Message::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// BackgroundWorker へジョブを送るハンドル。クローンして複数のノードで共有できます。
+#[derive(Clone)]
+pub struct WorkerHandle {
+    sender: Sender<Message>,
+}
+
+impl WorkerHandle {
+    /// ジョブを送る
+    ///
+    /// # 戻り値
+    /// * 送れた場合は `Ok(())`、ワーカーが停止している場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn spawn(&self, job: impl FnOnce() + Send + 'static) -> Result<(), String> {
+        if cfg!(target_arch = "wasm32") {
+            run_job(Box::new(job));
+            return Ok(());
+        }
+        self.sender
+            .send(Message::Run(Box::new(job)))
+            .map_err(|_| "バックグラウンドのワーカーが停止しています".to_string())
+    }
+
+    /// ジョブを送り、結果を Mailbox に届ける
+    ///
+    /// # 引数
+    /// * `mailbox` - 結果の届け先
+    /// * `job` - ワーカーで実行する処理
+    ///
+    /// # 戻り値
+    /// * 送れた場合は `Ok(())`、ワーカーが停止している場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn submit<T: Send + 'static>(
+        &self,
+        mailbox: &Mailbox<T>,
+        job: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<(), String> {
+        let mailbox = mailbox.clone();
+        self.spawn(move || mailbox.post(job()))
+    }
+}
+
+/// ジョブを実行する
+fn run_job(job: Job) {
+    // ジョブがパニックしても、他のノードのジョブは実行を続ける
+    let _ = panic::catch_unwind(AssertUnwindSafe(job));
+}
+
+/// 共有のワーカー（shared で最初に使うときに開始する）
+static SHARED_WORKER: OnceLock<BackgroundWorker> = OnceLock::new();
+
+/// ノードで共有するワーカーへのハンドルを取得
+///
+/// ワーカーのスレッドはプロセスの終了まで動作します。
+pub fn shared() -> WorkerHandle {
+    SHARED_WORKER
+        .get_or_init(|| BackgroundWorker::start("background-worker"))
+        .handle()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    use assert_no_alloc::assert_no_alloc;
+
+    use super::*;
+
+    /// drop された回数を数える値
+    struct Counted(usize, Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.1.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_submit_and_retire_through_mailbox() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mailbox = Mailbox::new();
+        let worker = BackgroundWorker::start("test-worker");
+        let handle = worker.handle();
+
+        let wait = |mailbox: &Mailbox<Counted>| loop {
+            if let Some(letter) = mailbox.take() {
+                return letter;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+
+        let counter = dropped.clone();
+        handle
+            .submit(&mailbox, move || Counted(1, counter))
+            .unwrap();
+        let first = wait(&mailbox);
+        assert_eq!(first.0, 1);
+
+        // 受け取りと返却はオーディオスレッドで行える
+        let counter = dropped.clone();
+        handle
+            .submit(&mailbox, move || Counted(2, counter))
+            .unwrap();
+        let second = wait(&mailbox);
+        assert_no_alloc(|| {
+            assert!(mailbox.take().is_none());
+            mailbox.retire(first);
+        });
+        assert_eq!(second.0, 2);
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+        // 返した値は次の結果が届いたときにワーカーで解放される
+        let counter = dropped.clone();
+        handle
+            .submit(&mailbox, move || Counted(3, counter))
+            .unwrap();
+        drop(worker);
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        assert_eq!(mailbox.take().unwrap().0, 3);
+
+        // 停止したワーカーにはジョブを送れない
+        assert!(handle.spawn(|| {}).is_err());
+        drop(second);
+    }
+}
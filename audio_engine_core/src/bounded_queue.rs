@@ -0,0 +1,190 @@
+//! 複数のスレッドから同時に書き込み・取り出しができる、固定長のロックフリーのキューです。
+//!
+//! EventQueue（イベント）と RtLogQueue（ログレコード）が共通して使います。各要素はスタンプで
+//! 書き込み済みかどうかを表し、書き込み位置と取り出し位置を compare_exchange で進めます。
+//! 要素の型は Copy に限るため、取り出されずに残った要素を破棄する処理は必要ありません。
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+/// キューの 1 要素
+pub(crate) struct Slot<T> {
+    /// 周回数を表すスタンプ。2 * 周回数 なら空き、2 * 周回数 + 1 なら書き込み済み。
+    stamp: AtomicUsize,
+    /// 要素
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    /// 空の要素を作成
+    pub(crate) const fn new() -> Self {
+        Self {
+            stamp: AtomicUsize::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+/// 固定長のロックフリーのキュー
+///
+/// `S` は要素の列で、static に置く場合は配列、実行時に長さを決める場合は Box のスライスを使います。
+/// いっぱいの場合、書き込んだ要素は破棄され、破棄した件数が記録されます。
+pub(crate) struct BoundedQueue<T, S = Box<[Slot<T>]>> {
+    slots: S,
+    /// 次に書き込む位置
+    tail: AtomicUsize,
+    /// 次に取り出す位置
+    head: AtomicUsize,
+    /// いっぱいで破棄した要素数
+    dropped: AtomicUsize,
+    _marker: core::marker::PhantomData<T>,
+}
+
+// Slot の value へのアクセスはスタンプで排他されているため、スレッド間で共有できる
+unsafe impl<T: Send, S: Send> Sync for BoundedQueue<T, S> {}
+
+impl<T> BoundedQueue<T> {
+    /// 実行時に長さを決めたキューを作成
+    ///
+    /// # 引数
+    /// * `capacity` - 取り出すまでにためておける要素数（0 の場合は 1）
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self::from_slots((0..capacity.max(1)).map(|_| Slot::new()).collect())
+    }
+}
+
+impl<T, S> BoundedQueue<T, S> {
+    /// 要素の列からキューを作成
+    pub(crate) const fn from_slots(slots: S) -> Self {
+        Self {
+            slots,
+            tail: AtomicUsize::new(0),
+            head: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// いっぱいで破棄した要素数を取得し、0 に戻す
+    pub(crate) fn take_dropped(&self) -> usize {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+impl<T: Copy, S: AsRef<[Slot<T>]>> BoundedQueue<T, S> {
+    /// 要素を書き込む
+    ///
+    /// # 戻り値
+    /// * 書き込めた場合は true。いっぱいの場合は false（要素は破棄される）
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub(crate) fn push(&self, value: T) -> bool {
+        let slots = self.slots.as_ref();
+        let capacity = slots.len();
+        let mut pos = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &slots[pos % capacity];
+            let lap = pos / capacity;
+            let stamp = slot.stamp.load(Ordering::Acquire);
+            if stamp == 2 * lap {
+                match self.tail.compare_exchange_weak(
+                    pos,
+                    pos + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // 位置を確保したスレッドだけが、スタンプを進めるまでこの要素に書き込む
+                        unsafe { (*slot.value.get()).write(value) };
+                        slot.stamp.store(2 * lap + 1, Ordering::Release);
+                        return true;
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if stamp < 2 * lap {
+                // 前の周回の要素がまだ取り出されていない
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            } else {
+                pos = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// 最も古い要素を取り出す
+    pub(crate) fn pop(&self) -> Option<T> {
+        let slots = self.slots.as_ref();
+        let capacity = slots.len();
+        let mut pos = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &slots[pos % capacity];
+            let lap = pos / capacity;
+            let stamp = slot.stamp.load(Ordering::Acquire);
+            if stamp == 2 * lap + 1 {
+                match self.head.compare_exchange_weak(
+                    pos,
+                    pos + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // スタンプが書き込み済みを表しているため、要素は初期化されている
+                        let value = unsafe { (*slot.value.get()).assume_init_read() };
+                        slot.stamp.store(2 * (lap + 1), Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if stamp < 2 * lap + 1 {
+                // まだ書き込まれていない
+                return None;
+            } else {
+                pos = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_and_boxed_queues() {
+        static QUEUE: BoundedQueue<u32, [Slot<u32>; 2]> =
+            BoundedQueue::from_slots([const { Slot::new() }; 2]);
+        assert!(QUEUE.push(1));
+        assert!(QUEUE.push(2));
+        assert!(!QUEUE.push(3));
+        assert_eq!(QUEUE.take_dropped(), 1);
+        assert_eq!(QUEUE.pop(), Some(1));
+        assert!(QUEUE.push(3));
+        assert_eq!(QUEUE.pop(), Some(2));
+        assert_eq!(QUEUE.pop(), Some(3));
+        assert_eq!(QUEUE.pop(), None);
+
+        // 複数のスレッドから書き込んでも、全ての要素を 1 回ずつ取り出せる
+        let queue = std::sync::Arc::new(BoundedQueue::<usize>::with_capacity(4000));
+        let writers: Vec<_> = (0..4)
+            .map(|t| {
+                let queue = queue.clone();
+                std::thread::spawn(move || {
+                    for i in 0..1000 {
+                        assert!(queue.push(t * 1000 + i));
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let mut values: Vec<_> = core::iter::from_fn(|| queue.pop()).collect();
+        values.sort_unstable();
+        assert_eq!(values, (0..4000).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,97 @@
+//! process 中に一時バッファを取得するためのバッファプールを定義します。
+
+use core::cell::{RefCell, RefMut};
+
+use crate::audio_buffer::OwnedAudioBuffer;
+use crate::prelude::*;
+use crate::sample::Sample;
+
+/// 事前に確保した一時バッファを貸し出すプール
+///
+/// prepare で必要な数のバッファを確保しておき、process 中は `acquire` で空いているバッファを借ります。
+/// 借りたバッファはガード（`RefMut`）が破棄されると自動的にプールに戻ります。
+/// `acquire` はメモリアロケーションを行わないため、リアルタイムスレッドから呼び出せます。
+pub struct BufferPool<S: Sample = f32> {
+    /// プールされたバッファ
+    buffers: Vec<RefCell<OwnedAudioBuffer<S>>>,
+}
+
+impl<S: Sample> BufferPool<S> {
+    /// バッファの数と大きさを指定して新しい BufferPool を作成する
+    ///
+    /// # 引数
+    /// * `num_buffers` - 同時に貸し出せるバッファの数
+    /// * `channels` - 各バッファのチャンネル数
+    /// * `max_frames` - 各バッファの最大フレーム数
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn new(num_buffers: usize, channels: usize, max_frames: usize) -> Self {
+        Self {
+            buffers: (0..num_buffers)
+                .map(|_| RefCell::new(OwnedAudioBuffer::new(channels, max_frames)))
+                .collect(),
+        }
+    }
+
+    /// 空いているバッファを借りる
+    ///
+    /// 借りたバッファは指定したフレーム数に設定され、0 でクリアされています。
+    ///
+    /// # 引数
+    /// * `frames` - 使用するフレーム数（最大フレーム数を超える値は最大フレーム数に制限されます）
+    ///
+    /// # 戻り値
+    /// * 空いているバッファがある場合は `Some`、すべて貸し出し中の場合は `None`
+    pub fn acquire(&self, frames: usize) -> Option<RefMut<'_, OwnedAudioBuffer<S>>> {
+        let mut buffer = self
+            .buffers
+            .iter()
+            .find_map(|buffer| buffer.try_borrow_mut().ok())?;
+        let max_frames = buffer.max_frames();
+        buffer.set_num_frames(frames.min(max_frames));
+        buffer.clear();
+        Some(buffer)
+    }
+
+    /// 現在貸し出せるバッファの数
+    pub fn num_available(&self) -> usize {
+        self.buffers
+            .iter()
+            .filter(|buffer| buffer.try_borrow_mut().is_ok())
+            .count()
+    }
+
+    /// プールされたバッファの総数
+    pub fn capacity(&self) -> usize {
+        self.buffers.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_no_alloc::assert_no_alloc;
+
+    use super::*;
+
+    #[test]
+    fn test_buffer_pool_acquire_and_release() {
+        let pool: BufferPool = BufferPool::new(2, 2, 16);
+
+        assert_no_alloc(|| {
+            let mut first = pool.acquire(8).unwrap();
+            first.as_mut_slice().fill(1.0);
+            {
+                let second = pool.acquire(4).unwrap();
+                assert_eq!(second.num_frames(), 4);
+                // すべて貸し出し中
+                assert!(pool.acquire(4).is_none());
+            }
+            // 返却されたバッファは 0 でクリアされて再び借りられる
+            let third = pool.acquire(16).unwrap();
+            assert!(third.as_slice().iter().all(|&s| s == 0.0));
+            assert_eq!(first.as_audio_buffer().num_frames(), 8);
+        });
+        assert_eq!(pool.num_available(), 2);
+    }
+}
@@ -0,0 +1,167 @@
+//! オーディオスレッドで検出した問題を、文字列を作らずに記録する仕組みです。
+//!
+//! AudioGraph の process は、ノードの出力バッファが見つからないなどの問題を検出すると、
+//! 問題の種類（DiagnosticCode）ごとのアトミックなカウンターを増やし、問題が起きたノードを記録します。
+//! フォーマットやメモリアロケーションを伴わないため、リリースビルドでも記録され、メインスレッドから確認できます。
+//!
+//! ```ignore
+//! let diagnostics = audio_graph.diagnostics();
+//! // メインスレッドで定期的に確認する
+//! for code in DiagnosticCode::ALL {
+//!     if diagnostics.count(code) > 0 {
+//!         eprintln!("{}: {} 回", code.description(), diagnostics.count(code));
+//!     }
+//! }
+//! ```
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::audio_graph::NodeId;
+
+/// 問題が起きたノードを記録していないことを表す値
+const NO_NODE: usize = usize::MAX;
+
+/// process で検出する問題の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    /// チャンネル数が 0 のバッファが渡された
+    InvalidChannelCount = 0,
+    /// process に渡された入力ノードがグラフに存在しない
+    MissingInputNode = 1,
+    /// process に渡された出力ノードがグラフに存在しない
+    MissingOutputNode = 2,
+    /// 処理の順序に含まれるノードが存在しない
+    MissingNode = 3,
+    /// ノードの出力バッファが見つからない（prepare の前に処理した場合など）
+    MissingOutputBuffer = 4,
+}
+
+impl DiagnosticCode {
+    /// 全ての問題の種類（番号の順）
+    pub const ALL: [DiagnosticCode; 5] = [
+        DiagnosticCode::InvalidChannelCount,
+        DiagnosticCode::MissingInputNode,
+        DiagnosticCode::MissingOutputNode,
+        DiagnosticCode::MissingNode,
+        DiagnosticCode::MissingOutputBuffer,
+    ];
+
+    /// 問題の種類の番号（ログやネットワーク越しに送るための数値）
+    pub fn code(&self) -> u32 {
+        *self as u32
+    }
+
+    /// 問題の説明
+    pub fn description(&self) -> &'static str {
+        match self {
+            DiagnosticCode::InvalidChannelCount => "チャンネル数が不正です",
+            DiagnosticCode::MissingInputNode => "入力ノードが見つかりません",
+            DiagnosticCode::MissingOutputNode => "出力ノードが見つかりません",
+            DiagnosticCode::MissingNode => "ノードが見つかりません",
+            DiagnosticCode::MissingOutputBuffer => "ノードの出力バッファが見つかりません",
+        }
+    }
+}
+
+/// 問題の種類ごとの発生回数と、最後に問題が起きたノード
+///
+/// AudioGraph が Arc で保持し、オーディオスレッドから書き込みます。AudioGraph::diagnostics で取得した
+/// Arc をメインスレッドで保持すれば、ロックなしで読み出せます。set_diagnostics で複数のグラフの記録先を
+/// 1 つにまとめることもできます。
+pub struct Diagnostics {
+    /// 問題の種類ごとの発生回数
+    counts: [AtomicUsize; DiagnosticCode::ALL.len()],
+    /// 問題の種類ごとの、最後に問題が起きたノードIDの整数の表現（NO_NODE の場合は記録なし）
+    last_nodes: [AtomicUsize; DiagnosticCode::ALL.len()],
+}
+
+impl Diagnostics {
+    /// 新しいDiagnosticsを作成
+    pub const fn new() -> Self {
+        Self {
+            counts: [const { AtomicUsize::new(0) }; DiagnosticCode::ALL.len()],
+            last_nodes: [const { AtomicUsize::new(NO_NODE) }; DiagnosticCode::ALL.len()],
+        }
+    }
+
+    /// 問題を記録する
+    ///
+    /// # 引数
+    /// * `code` - 問題の種類
+    /// * `node_id` - 問題が起きたノード（ノードによらない問題の場合は `None`）
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn report(&self, code: DiagnosticCode, node_id: Option<NodeId>) {
+        let index = code as usize;
+        self.counts[index].fetch_add(1, Ordering::Relaxed);
+        if let Some(node_id) = node_id {
+            self.last_nodes[index].store(node_id.as_raw(), Ordering::Relaxed);
+        }
+    }
+
+    /// 問題の発生回数（clear からの累計）
+    pub fn count(&self, code: DiagnosticCode) -> usize {
+        self.counts[code as usize].load(Ordering::Relaxed)
+    }
+
+    /// 最後に問題が起きたノード
+    pub fn last_node(&self, code: DiagnosticCode) -> Option<NodeId> {
+        match self.last_nodes[code as usize].load(Ordering::Relaxed) {
+            NO_NODE => None,
+            raw => Some(NodeId::from_raw(raw)),
+        }
+    }
+
+    /// 問題が 1 回でも記録されているかどうか
+    pub fn has_problems(&self) -> bool {
+        DiagnosticCode::ALL.iter().any(|&code| self.count(code) > 0)
+    }
+
+    /// 記録を消去する
+    pub fn clear(&self) {
+        for (count, last_node) in self.counts.iter().zip(self.last_nodes.iter()) {
+            count.store(0, Ordering::Relaxed);
+            last_node.store(NO_NODE, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_and_clear() {
+        let diagnostics = Diagnostics::new();
+        assert!(!diagnostics.has_problems());
+
+        let node_id = NodeId::from_raw(7);
+        diagnostics.report(DiagnosticCode::MissingOutputBuffer, Some(node_id));
+        diagnostics.report(DiagnosticCode::MissingOutputBuffer, None);
+        diagnostics.report(DiagnosticCode::InvalidChannelCount, None);
+        assert_eq!(diagnostics.count(DiagnosticCode::MissingOutputBuffer), 2);
+        assert_eq!(
+            diagnostics.last_node(DiagnosticCode::MissingOutputBuffer),
+            Some(node_id)
+        );
+        assert_eq!(
+            diagnostics.last_node(DiagnosticCode::InvalidChannelCount),
+            None
+        );
+        assert_eq!(diagnostics.count(DiagnosticCode::MissingNode), 0);
+
+        diagnostics.clear();
+        assert!(!diagnostics.has_problems());
+        assert_eq!(
+            diagnostics.last_node(DiagnosticCode::MissingOutputBuffer),
+            None
+        );
+    }
+}
@@ -1,29 +1,53 @@
-use std::collections::{HashMap, HashSet};
-use std::fmt::Debug;
-use std::hash::Hash;
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+use core::fmt::Debug;
+use core::mem;
+
+use crate::prelude::*;
+use crate::slot_map::{SlotKey, SlotMap};
 
 /// DirectedGraph - 有向グラフの汎用的な実装
 ///
 /// ジェネリック型 T を使用してノードの識別子を表します。
 /// 隣接リストを使用してノード間の接続を管理します。
+/// ノードごとの情報は SlotMap に保持するため、ハッシュの計算や再ハッシュによるメモリアロケーションを行いません。
+///
+/// トポロジカルソートの結果と入力ノードのマップは、変更のたびに差分で更新します。ノードの追加と、
+/// 今の順序に沿ったエッジの追加では並べ直さないため、多数のノードを順に追加して接続する場合も速く構築できます。
 pub struct DirectedGraph<T>
 where
-    T: Eq + Hash + Copy + Debug,
+    T: SlotKey + Debug,
 {
     /// 隣接リスト（各ノードIDから接続先ノードIDのリスト）
-    adjacency_list: HashMap<T, Vec<T>>,
+    adjacency_list: SlotMap<T, Vec<T>>,
     /// キャッシュされたトポロジカルソート結果
     cached_topo_sort: Vec<T>,
     /// キャッシュされた逆トポロジカルソート結果
     cached_reverse_topo_sort: Vec<T>,
+    /// 各ノードの、逆トポロジカルソート結果の中の位置
+    positions: SlotMap<T, usize>,
     /// キャッシュされた入力ノードマップ（キー: ノードID、値: そのノードに入力するノードのIDのリスト）
     /// 要するに adjacency_list の逆引き。
-    cached_input_nodes: HashMap<T, Vec<T>>,
+    cached_input_nodes: SlotMap<T, Vec<T>>,
+    /// ノードを追加した順のノードIDのリスト（トポロジカルソートで順序が決まらないノードの並びに使う）
+    insertion_order: Vec<T>,
+    /// 削除したノードのリストの再利用のための置き場（with_capacity で確保した容量を超えては保持しない）
+    spare_lists: Vec<Vec<T>>,
+    /// 経路の探索で使う、訪問済みのノード
+    visited: SlotMap<T, ()>,
+    /// 経路の探索で使うスタック
+    stack: Vec<T>,
+    /// トポロジカルソートで使う、ノードIDから追加した順の番号へのマップ
+    ranks: SlotMap<T, usize>,
+    /// トポロジカルソートで使う、追加した順の番号ごとの未処理の入力エッジの数
+    in_degrees: Vec<usize>,
+    /// トポロジカルソートで使う、入力エッジがすべて処理されたノードの追加した順の番号
+    ready: BinaryHeap<Reverse<usize>>,
 }
 
 impl<T> DirectedGraph<T>
 where
-    T: Eq + Hash + Copy + Debug,
+    T: SlotKey + Debug,
 {
     /// 新しい有向グラフを作成します
     ///
@@ -31,10 +55,49 @@ where
     /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
     pub fn new() -> Self {
         Self {
-            adjacency_list: HashMap::new(),
+            adjacency_list: SlotMap::new(),
             cached_topo_sort: Vec::new(),
             cached_reverse_topo_sort: Vec::new(),
-            cached_input_nodes: HashMap::new(),
+            positions: SlotMap::new(),
+            cached_input_nodes: SlotMap::new(),
+            insertion_order: Vec::new(),
+            spare_lists: Vec::new(),
+            visited: SlotMap::new(),
+            stack: Vec::new(),
+            ranks: SlotMap::new(),
+            in_degrees: Vec::new(),
+            ready: BinaryHeap::new(),
+        }
+    }
+
+    /// ノード数とエッジ数の上限まで、あらかじめメモリを確保した有向グラフを作成します
+    ///
+    /// 上限を超えない限り、add_node・add_edge・remove_node・remove_edge はメモリアロケーションを行いません
+    /// （エッジが循環参照を作成する場合のエラーメッセージの作成を除く）。
+    ///
+    /// # 引数
+    /// * `max_nodes` - ノード数の上限（ノードIDのスロットの番号も、この値未満である必要がある）
+    /// * `max_edges_per_node` - 1 つのノードから出る、または 1 つのノードに入るエッジの数の上限
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn with_capacity(max_nodes: usize, max_edges_per_node: usize) -> Self {
+        Self {
+            adjacency_list: SlotMap::with_capacity(max_nodes),
+            cached_topo_sort: Vec::with_capacity(max_nodes),
+            cached_reverse_topo_sort: Vec::with_capacity(max_nodes),
+            positions: SlotMap::with_capacity(max_nodes),
+            cached_input_nodes: SlotMap::with_capacity(max_nodes),
+            insertion_order: Vec::with_capacity(max_nodes),
+            // 隣接リストと入力ノードのリストを、ノードごとに 1 つずつ
+            spare_lists: (0..max_nodes * 2)
+                .map(|_| Vec::with_capacity(max_edges_per_node))
+                .collect(),
+            visited: SlotMap::with_capacity(max_nodes),
+            stack: Vec::with_capacity(max_nodes * max_edges_per_node + 1),
+            ranks: SlotMap::with_capacity(max_nodes),
+            in_degrees: Vec::with_capacity(max_nodes),
+            ready: BinaryHeap::with_capacity(max_nodes),
         }
     }
 
@@ -53,8 +116,17 @@ where
             return false;
         }
 
-        self.adjacency_list.insert(node_id, Vec::new());
-        self.update_cache();
+        let neighbors = self.take_list();
+        self.adjacency_list.insert(node_id, neighbors);
+        let inputs = self.take_list();
+        self.cached_input_nodes.insert(node_id, inputs);
+        self.insertion_order.push(node_id);
+
+        // 接続のないノードは、追加した順で最後になるため、並べ直さずに末尾に加える
+        self.positions
+            .insert(node_id, self.cached_reverse_topo_sort.len());
+        self.cached_reverse_topo_sort.push(node_id);
+        self.cached_topo_sort.insert(0, node_id);
 
         true
     }
@@ -80,9 +152,15 @@ where
             return Err(format!("ノードID {:?}が存在しません", to_id));
         }
 
-        // 循環参照をチェック
-        if self.would_create_cycle(from_id, to_id) {
-            return Err("この接続は循環参照を作成します".to_string());
+        // 循環参照をチェック（接続先から接続元への経路があれば、この接続で循環する）
+        if self.has_path(to_id, from_id) {
+            let path = self.find_path(to_id, from_id).unwrap_or_default();
+            let cycle: Vec<String> = path.iter().map(|id| format!("{:?}", id)).collect();
+            return Err(format!(
+                "この接続は循環参照を作成します（循環: {:?} -> {}）",
+                from_id,
+                cycle.join(" -> ")
+            ));
         }
 
         // 既に接続が存在するかチェック
@@ -92,11 +170,17 @@ where
             }
         }
 
-        // エッジを追加
+        // エッジを追加し、接続先の入力ノードのキャッシュにも加える
         self.adjacency_list.get_mut(&from_id).unwrap().push(to_id);
+        if let Some(inputs) = self.cached_input_nodes.get_mut(&to_id) {
+            inputs.push(from_id);
+        }
 
-        // グラフが変更されたのでキャッシュを更新
-        self.update_cache();
+        // 接続元が接続先より前に並んでいれば、今の順序のままで良い（制約が増えただけなので、
+        // 追加した順で最小の順序は変わらない）。そうでなければ並べ直す
+        if self.positions[&from_id] > self.positions[&to_id] {
+            self.update_topological_sort_cache();
+        }
 
         Ok(())
     }
@@ -112,20 +196,29 @@ where
     /// # 実装時の注意
     /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
     pub fn remove_node(&mut self, node_id: T) -> bool {
-        if !self.adjacency_list.contains_key(&node_id) {
-            return false;
-        }
-
         // 隣接リストから削除
-        self.adjacency_list.remove(&node_id);
+        let Some(neighbors) = self.adjacency_list.remove(&node_id) else {
+            return false;
+        };
+        let inputs = self.cached_input_nodes.remove(&node_id).unwrap_or_default();
 
-        // 他のノードの隣接リストからも削除
-        for neighbors in self.adjacency_list.values_mut() {
-            neighbors.retain(|&n| n != node_id);
+        // 接続していたノードの隣接リストと入力ノードのキャッシュからも削除
+        for neighbor in &neighbors {
+            if let Some(list) = self.cached_input_nodes.get_mut(neighbor) {
+                list.retain(|&n| n != node_id);
+            }
         }
+        for input in &inputs {
+            if let Some(list) = self.adjacency_list.get_mut(input) {
+                list.retain(|&n| n != node_id);
+            }
+        }
+        self.recycle_list(neighbors);
+        self.recycle_list(inputs);
+        self.insertion_order.retain(|&id| id != node_id);
 
-        // グラフが変更されたのでキャッシュを更新
-        self.update_cache();
+        // 制約が減ると順序が変わる場合があるため、並べ直す
+        self.update_topological_sort_cache();
 
         true
     }
@@ -148,8 +241,11 @@ where
             let removed = neighbors.len() < len_before;
 
             if removed {
-                // グラフが変更されたのでキャッシュを更新
-                self.update_cache();
+                if let Some(inputs) = self.cached_input_nodes.get_mut(&to_id) {
+                    inputs.retain(|&n| n != from_id);
+                }
+                // 制約が減ると順序が変わる場合があるため、並べ直す
+                self.update_topological_sort_cache();
             }
 
             return removed;
@@ -158,118 +254,158 @@ where
         false
     }
 
-    /// 指定された接続が循環参照を作成するかチェックします
+    /// あるノードから別のノードへ、エッジをたどって到達できるかを調べます
     ///
     /// # 引数
-    /// * `from_id` - 接続元ノードのID
-    /// * `to_id` - 接続先ノードのID
+    /// * `from_id` - 経路の始点のノードID
+    /// * `to_id` - 経路の終点のノードID
     ///
     /// # 戻り値
-    /// * 循環参照が発生する場合は `true`、発生しない場合は `false`
+    /// * 到達できる場合は `true`
     ///
     /// # 実装時の注意
-    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
-    fn would_create_cycle(&self, from_id: T, to_id: T) -> bool {
-        // to_id から始まる経路がfrom_idに戻るかをチェック
-        let mut visited = HashSet::new();
-        let mut stack = vec![to_id];
-
-        while let Some(current) = stack.pop() {
-            if current == from_id {
-                return true; // 循環参照を発見
-            }
+    /// with_capacity で確保した容量を超えない限り、メモリアロケーションを行いません。
+    pub fn has_path(&mut self, from_id: T, to_id: T) -> bool {
+        // 経路があれば始点は終点より前に並ぶため、後ろに並ぶ場合は探索するまでもない
+        if let (Some(from), Some(to)) = (self.positions.get(&from_id), self.positions.get(&to_id))
+            && from > to
+        {
+            return false;
+        }
+
+        self.visited.clear();
+        self.stack.clear();
+        self.stack.push(from_id);
 
-            if !visited.insert(current) {
+        while let Some(current) = self.stack.pop() {
+            if current == to_id {
+                return true;
+            }
+            if self.visited.insert(current, ()).is_some() {
                 continue; // 既に訪問済み
             }
-
             if let Some(neighbors) = self.adjacency_list.get(&current) {
-                for &neighbor in neighbors {
-                    stack.push(neighbor);
-                }
+                self.stack.extend_from_slice(neighbors);
             }
         }
 
         false
     }
 
-    /// グラフのトポロジカルソートを実行します
+    /// あるノードから別のノードへ、エッジをたどって到達する経路を探します
+    ///
+    /// `to_id` から `from_id` への経路がある場合、`from_id -> to_id` の接続は循環参照を作成します。
+    ///
+    /// # 引数
+    /// * `from_id` - 経路の始点のノードID
+    /// * `to_id` - 経路の終点のノードID
     ///
     /// # 戻り値
-    /// * ノードIDのトポロジカル順序の配列
+    /// * 経路がある場合は始点と終点を含むノードIDの列、ない場合は `None`
     ///
     /// # 実装時の注意
     /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
-    fn topological_sort(&self) -> Vec<T> {
-        let mut result = Vec::new();
-        let mut visited = HashSet::new();
-        let mut temp_mark = HashSet::new();
-
-        // すべてのノードを訪問
-        for &node_id in self.adjacency_list.keys() {
-            if !visited.contains(&node_id) {
-                self.visit(node_id, &mut visited, &mut temp_mark, &mut result);
+    pub fn find_path(&self, from_id: T, to_id: T) -> Option<Vec<T>> {
+        // 訪問したノードごとに、どのノードから来たかを記録する
+        let mut came_from: SlotMap<T, Option<T>> = SlotMap::new();
+        let mut stack = vec![(from_id, None)];
+
+        while let Some((current, previous)) = stack.pop() {
+            if came_from.contains_key(&current) {
+                continue; // 既に訪問済み
+            }
+            came_from.insert(current, previous);
+
+            if current == to_id {
+                // 終点から始点へ記録をたどって経路を復元する
+                let mut path = vec![current];
+                while let Some(&Some(previous)) = came_from.get(path.last().unwrap()) {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            if let Some(neighbors) = self.adjacency_list.get(&current) {
+                for &neighbor in neighbors {
+                    stack.push((neighbor, Some(current)));
+                }
             }
         }
 
-        result
+        None
     }
 
-    /// トポロジカルソートのためのDFS訪問
+    /// グラフのトポロジカルソート（Kahn のアルゴリズム）を実行します
+    ///
+    /// 再帰を使わないため、数千ノードの直列の連鎖でもスタックを使い切りません。
+    /// 順序が決まらないノードの間では、先に追加したノードを先に並べるため、結果は常に同じになります。
+    ///
+    /// # 引数
+    /// * `result` - 接続元が接続先より前に並ぶ順序を書き込む配列（既存の要素は消去される）
     ///
     /// # 実装時の注意
-    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
-    fn visit(
-        &self,
-        node_id: T,
-        visited: &mut HashSet<T>,
-        temp_mark: &mut HashSet<T>,
-        result: &mut Vec<T>,
-    ) {
-        // 一時マークがあれば循環があるので何もしない
-        if temp_mark.contains(&node_id) {
-            return;
+    /// with_capacity で確保した容量を超えない限り、メモリアロケーションを行いません。
+    fn topological_sort(&mut self, result: &mut Vec<T>) {
+        result.clear();
+
+        // ノードごとに、追加した順の番号と入力エッジの数を数える
+        self.ranks.clear();
+        for (rank, &node_id) in self.insertion_order.iter().enumerate() {
+            self.ranks.insert(node_id, rank);
         }
-
-        // 既に訪問済みならスキップ
-        if visited.contains(&node_id) {
-            return;
+        self.in_degrees.clear();
+        self.in_degrees.resize(self.insertion_order.len(), 0);
+        for neighbors in self.adjacency_list.values() {
+            for neighbor in neighbors {
+                self.in_degrees[self.ranks[neighbor]] += 1;
+            }
         }
 
-        // 一時マークを付ける
-        temp_mark.insert(node_id);
-
-        // 隣接ノードを訪問
-        if let Some(neighbors) = self.adjacency_list.get(&node_id) {
-            for &neighbor in neighbors {
-                self.visit(neighbor, visited, temp_mark, result);
+        // 入力エッジのないノードから、追加した順の番号が小さいものを順に取り出す
+        self.ready.clear();
+        for (rank, &in_degree) in self.in_degrees.iter().enumerate() {
+            if in_degree == 0 {
+                self.ready.push(Reverse(rank));
+            }
+        }
+        while let Some(Reverse(rank)) = self.ready.pop() {
+            let node_id = self.insertion_order[rank];
+            result.push(node_id);
+            for neighbor in &self.adjacency_list[&node_id] {
+                let neighbor_rank = self.ranks[neighbor];
+                self.in_degrees[neighbor_rank] -= 1;
+                if self.in_degrees[neighbor_rank] == 0 {
+                    self.ready.push(Reverse(neighbor_rank));
+                }
             }
         }
 
-        // 一時マークを外す
-        temp_mark.remove(&node_id);
-
-        // 訪問済みマークを付ける
-        visited.insert(node_id);
-
-        // 結果リストに追加
-        result.push(node_id);
-    }
-
-    fn update_cache(&mut self) {
-        self.update_topological_sort_cache();
-        self.update_input_nodes_cache();
+        // 循環がある場合に取り出せなかったノードは、追加した順に最後に並べる
+        if result.len() < self.insertion_order.len() {
+            for (rank, &node_id) in self.insertion_order.iter().enumerate() {
+                if self.in_degrees[rank] > 0 {
+                    result.push(node_id);
+                }
+            }
+        }
     }
 
     /// トポロジカルソートを更新し、キャッシュに保存します
     ///
     /// # 実装時の注意
-    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    /// with_capacity で確保した容量を超えない限り、メモリアロケーションを行いません。
     fn update_topological_sort_cache(&mut self) {
-        let order = self.topological_sort();
-        let mut reverse_order = order.clone();
-        reverse_order.reverse();
-        self.cached_topo_sort = order;
+        // ソートの結果は接続元が先に並ぶため、逆トポロジカルソートの結果になる
+        let mut reverse_order = mem::take(&mut self.cached_reverse_topo_sort);
+        self.topological_sort(&mut reverse_order);
+        self.cached_topo_sort.clear();
+        self.cached_topo_sort
+            .extend(reverse_order.iter().rev().copied());
+        self.positions.clear();
+        for (position, &node_id) in reverse_order.iter().enumerate() {
+            self.positions.insert(node_id, position);
+        }
         self.cached_reverse_topo_sort = reverse_order;
     }
 
@@ -296,28 +432,17 @@ where
         &self.cached_reverse_topo_sort
     }
 
-    /// 入力ノードのキャッシュを更新します
-    ///
-    /// # 実装時の注意
-    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
-    fn update_input_nodes_cache(&mut self) {
-        let mut input_nodes = HashMap::new();
-
-        // グラフ内の全ノードに対して入力ノードのキャッシュを初期化
-        for &node_id in self.adjacency_list.keys() {
-            input_nodes.insert(node_id, Vec::new());
-        }
+    /// 再利用できるリストを取り出す（ない場合は新しく作成する）
+    fn take_list(&mut self) -> Vec<T> {
+        self.spare_lists.pop().unwrap_or_default()
+    }
 
-        // 各エッジに基づいて入力ノードキャッシュを構築
-        for (&src_id, dst_ids) in &self.adjacency_list {
-            for &dst_id in dst_ids {
-                if let Some(inputs) = input_nodes.get_mut(&dst_id) {
-                    inputs.push(src_id);
-                }
-            }
+    /// 使わなくなったリストを、確保した容量の範囲で再利用のために保持する
+    fn recycle_list(&mut self, mut list: Vec<T>) {
+        if self.spare_lists.len() < self.spare_lists.capacity() {
+            list.clear();
+            self.spare_lists.push(list);
         }
-
-        self.cached_input_nodes = input_nodes;
     }
 
     /// 特定のノードに入力エッジを持つノードのIDを取得します（リアルタイムスレッドセーフ版）
@@ -339,6 +464,24 @@ where
         }
     }
 
+    /// 特定のノードから出力エッジが向かうノードのIDを取得します
+    ///
+    /// # 引数
+    /// * `node_id` - 対象ノードのID
+    ///
+    /// # 戻り値
+    /// * 出力エッジの接続先のノードIDのスライス（エッジを追加した順）
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行わないため、リアルタイムスレッドから安全に呼び出すことができます。
+    pub fn get_output_node_ids(&self, node_id: T) -> &[T] {
+        if let Some(output_nodes) = self.adjacency_list.get(&node_id) {
+            output_nodes
+        } else {
+            &[]
+        }
+    }
+
     /// グラフのノード数を取得します
     ///
     /// # 戻り値
@@ -385,14 +528,14 @@ where
 /// リアルタイムスレッドから安全に呼び出せるメソッドだけを公開するためのラッパー
 pub struct RealTimeSafeDirectedGraph<'a, T>
 where
-    T: Eq + Hash + Copy + Debug,
+    T: SlotKey + Debug,
 {
     graph: &'a DirectedGraph<T>,
 }
 
 impl<'a, T> RealTimeSafeDirectedGraph<'a, T>
 where
-    T: Eq + Hash + Copy + Debug,
+    T: SlotKey + Debug,
 {
     pub fn new(graph: &'a DirectedGraph<T>) -> Self {
         Self { graph }
@@ -422,6 +565,7 @@ where
         self.graph.node_ids()
     }
 
+    #[allow(dead_code)]
     pub fn get_input_node_ids(&self, node_id: T) -> &[T] {
         self.graph.get_input_node_ids(node_id)
     }
@@ -466,7 +610,12 @@ mod tests {
         assert!(graph.add_edge(2, 3).is_ok());
 
         // 3 -> 1 はサイクルを作るため失敗するはず
-        assert!(graph.add_edge(3, 1).is_err());
+        assert_eq!(
+            graph.add_edge(3, 1),
+            Err("この接続は循環参照を作成します（循環: 3 -> 1 -> 2 -> 3）".to_string())
+        );
+        assert_eq!(graph.find_path(1, 3), Some(vec![1, 2, 3]));
+        assert_eq!(graph.find_path(3, 1), None);
     }
 
     #[test]
@@ -502,4 +651,61 @@ mod tests {
         let reverse_order = graph.get_reverse_topological_order();
         assert_eq!(reverse_order, &[1, 2, 3]);
     }
+
+    #[test]
+    fn test_topological_sort_is_stable_and_iterative() {
+        let mut graph = DirectedGraph::<usize>::new();
+        for node_id in [5, 3, 9, 1] {
+            graph.add_node(node_id);
+        }
+
+        // 順序の決まらないノードは追加した順に並ぶ
+        assert_eq!(graph.get_reverse_topological_order(), &[5, 3, 9, 1]);
+        graph.add_edge(9, 5).unwrap();
+        assert_eq!(graph.get_reverse_topological_order(), &[3, 9, 5, 1]);
+
+        // 深い直列の連鎖でも、小さなスタックを使い切らない
+        let depth = 500;
+        let handle = std::thread::Builder::new()
+            .stack_size(32 * 1024)
+            .spawn(move || {
+                // 逆順に追加し、エッジを追加するたびに並べ直させる
+                let mut graph = DirectedGraph::<usize>::new();
+                for node_id in (0..depth).rev() {
+                    graph.add_node(node_id);
+                }
+                for node_id in 1..depth {
+                    graph.add_edge(node_id - 1, node_id).unwrap();
+                }
+                graph.get_reverse_topological_order().to_vec()
+            })
+            .unwrap();
+        assert!(handle.join().unwrap().into_iter().eq(0..depth));
+    }
+
+    #[test]
+    fn test_incremental_cache_matches_full_sort() {
+        // 出力ノード 0 と、1 から始まる直列の連鎖を順に組み立てる
+        let num_nodes = 1_000;
+        let mut graph = DirectedGraph::<usize>::new();
+        for node_id in 0..num_nodes {
+            graph.add_node(node_id);
+        }
+        for node_id in 2..num_nodes {
+            graph.add_edge(node_id - 1, node_id).unwrap();
+        }
+        // 出力ノードへのエッジは今の順序に逆らうため、並べ直される
+        graph.add_edge(num_nodes - 1, 0).unwrap();
+        assert_eq!(graph.get_reverse_topological_order().last(), Some(&0));
+        assert_eq!(graph.get_input_node_ids(0), &[num_nodes - 1]);
+        assert!(!graph.has_path(0, 1));
+
+        graph.remove_node(num_nodes / 2);
+        assert_eq!(graph.get_input_node_ids(num_nodes / 2 + 1), &[]);
+
+        // 差分で更新した結果は、全体を並べ直した結果と一致する
+        let incremental = graph.get_reverse_topological_order().to_vec();
+        graph.update_topological_sort_cache();
+        assert_eq!(graph.get_reverse_topological_order(), incremental);
+    }
 }
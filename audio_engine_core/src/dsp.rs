@@ -0,0 +1,28 @@
+mod biquad;
+mod delay_line;
+mod fft;
+mod first_order_allpass;
+mod glide;
+mod lfo;
+mod limiter;
+mod rate_converter;
+mod sinc_resampler;
+mod smoothed_value;
+mod stft;
+
+pub use biquad::Biquad;
+pub use biquad::BiquadCoefficients;
+pub use delay_line::DelayLine;
+pub use fft::Fft;
+pub use fft::fft;
+pub use first_order_allpass::FirstOrderAllpass;
+pub use glide::Glide;
+pub use glide::GlideMode;
+pub use lfo::Lfo;
+pub use lfo::LfoShape;
+pub use limiter::Limiter;
+pub use rate_converter::RateConverter;
+pub use sinc_resampler::SincResampler;
+pub use sinc_resampler::resample;
+pub use smoothed_value::SmoothedValue;
+pub use stft::Stft;
@@ -0,0 +1,216 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+/// 双2次フィルターの係数（a0 で正規化済み）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BiquadCoefficients {
+    pub b0: f32,
+    pub b1: f32,
+    pub b2: f32,
+    pub a1: f32,
+    pub a2: f32,
+}
+
+impl BiquadCoefficients {
+    /// 入力をそのまま出力する係数
+    pub fn identity() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+        }
+    }
+
+    /// ローパスフィルター
+    pub fn low_pass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_w0, alpha) = Self::intermediates(sample_rate, frequency, q);
+        Self::normalize(
+            (1.0 - cos_w0) / 2.0,
+            1.0 - cos_w0,
+            (1.0 - cos_w0) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_w0,
+            1.0 - alpha,
+        )
+    }
+
+    /// ハイパスフィルター
+    pub fn high_pass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_w0, alpha) = Self::intermediates(sample_rate, frequency, q);
+        Self::normalize(
+            (1.0 + cos_w0) / 2.0,
+            -(1.0 + cos_w0),
+            (1.0 + cos_w0) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_w0,
+            1.0 - alpha,
+        )
+    }
+
+    /// バンドパスフィルター（ピークゲイン 0dB）
+    pub fn band_pass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_w0, alpha) = Self::intermediates(sample_rate, frequency, q);
+        Self::normalize(alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha)
+    }
+
+    /// ノッチフィルター
+    pub fn notch(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_w0, alpha) = Self::intermediates(sample_rate, frequency, q);
+        Self::normalize(
+            1.0,
+            -2.0 * cos_w0,
+            1.0,
+            1.0 + alpha,
+            -2.0 * cos_w0,
+            1.0 - alpha,
+        )
+    }
+
+    /// ピーキングイコライザー
+    pub fn peaking(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let (cos_w0, alpha) = Self::intermediates(sample_rate, frequency, q);
+        Self::normalize(
+            1.0 + alpha * a,
+            -2.0 * cos_w0,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos_w0,
+            1.0 - alpha / a,
+        )
+    }
+
+    /// ローシェルフ
+    pub fn low_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let (cos_w0, alpha) = Self::intermediates(sample_rate, frequency, q);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+        Self::normalize(
+            a * ((a + 1.0) - (a - 1.0) * cos_w0 + sqrt_a_alpha),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w0),
+            a * ((a + 1.0) - (a - 1.0) * cos_w0 - sqrt_a_alpha),
+            (a + 1.0) + (a - 1.0) * cos_w0 + sqrt_a_alpha,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos_w0),
+            (a + 1.0) + (a - 1.0) * cos_w0 - sqrt_a_alpha,
+        )
+    }
+
+    /// ハイシェルフ
+    pub fn high_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let (cos_w0, alpha) = Self::intermediates(sample_rate, frequency, q);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+        Self::normalize(
+            a * ((a + 1.0) + (a - 1.0) * cos_w0 + sqrt_a_alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w0),
+            a * ((a + 1.0) + (a - 1.0) * cos_w0 - sqrt_a_alpha),
+            (a + 1.0) - (a - 1.0) * cos_w0 + sqrt_a_alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos_w0),
+            (a + 1.0) - (a - 1.0) * cos_w0 - sqrt_a_alpha,
+        )
+    }
+
+    /// 指定した周波数での振幅特性（倍率）を計算する
+    pub fn magnitude_at(&self, sample_rate: f32, frequency: f32) -> f32 {
+        let w = core::f32::consts::TAU * frequency / sample_rate;
+        let (cos1, sin1) = (w.cos(), w.sin());
+        let (cos2, sin2) = ((2.0 * w).cos(), (2.0 * w).sin());
+        let num_re = self.b0 + self.b1 * cos1 + self.b2 * cos2;
+        let num_im = -(self.b1 * sin1 + self.b2 * sin2);
+        let den_re = 1.0 + self.a1 * cos1 + self.a2 * cos2;
+        let den_im = -(self.a1 * sin1 + self.a2 * sin2);
+        ((num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im)).sqrt()
+    }
+
+    /// RBJ Audio EQ Cookbook の中間値（cos(w0), alpha）を計算する
+    fn intermediates(sample_rate: f32, frequency: f32, q: f32) -> (f32, f32) {
+        let frequency = frequency.clamp(1.0, sample_rate * 0.49);
+        let w0 = core::f32::consts::TAU * frequency / sample_rate;
+        (w0.cos(), w0.sin() / (2.0 * q.max(0.01)))
+    }
+
+    fn normalize(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+/// 双2次フィルター（転置直接形 II、モノラル）
+///
+/// 係数は RBJ Audio EQ Cookbook に基づいて `BiquadCoefficients` で計算します。
+pub struct Biquad {
+    /// 係数
+    coefficients: BiquadCoefficients,
+    /// 状態変数
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    /// 新しいBiquadを作成（入力をそのまま出力する係数で初期化）
+    pub fn new() -> Self {
+        Self {
+            coefficients: BiquadCoefficients::identity(),
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    /// 係数を設定する。状態変数は保持されます。
+    pub fn set_coefficients(&mut self, coefficients: BiquadCoefficients) {
+        self.coefficients = coefficients;
+    }
+
+    /// 現在の係数を取得する
+    pub fn coefficients(&self) -> &BiquadCoefficients {
+        &self.coefficients
+    }
+
+    /// 1サンプル処理する
+    pub fn process(&mut self, x: f32) -> f32 {
+        let c = &self.coefficients;
+        let y = c.b0 * x + self.z1;
+        self.z1 = c.b1 * x - c.a1 * y + self.z2;
+        self.z2 = c.b2 * x - c.a2 * y;
+        y
+    }
+
+    /// 内部状態をリセットする
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_biquad_peaking_gain_at_center() {
+        let coefficients = BiquadCoefficients::peaking(48000.0, 1000.0, 1.0, 6.0);
+        let gain_db = 20.0 * coefficients.magnitude_at(48000.0, 1000.0).log10();
+        assert!((gain_db - 6.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_biquad_low_pass_attenuates_high_frequency() {
+        let mut biquad = Biquad::new();
+        biquad.set_coefficients(BiquadCoefficients::low_pass(48000.0, 100.0, 0.707));
+
+        // ナイキスト周波数の信号はほぼ完全に減衰する
+        let mut last = 0.0;
+        for i in 0..1000 {
+            let x = if i % 2 == 0 { 1.0 } else { -1.0 };
+            last = biquad.process(x);
+        }
+        assert!(last.abs() < 1e-3);
+    }
+}
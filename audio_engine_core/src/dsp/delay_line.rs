@@ -0,0 +1,88 @@
+use crate::prelude::*;
+
+/// 小数サンプルの遅延に対応したディレイライン（モノラル）
+///
+/// 読み出しは線形補間で行います。コーラスやフランジャーなど、遅延時間を変調するエフェクトで使います。
+pub struct DelayLine {
+    /// リングバッファ本体
+    data: Vec<f32>,
+    /// 次に書き込む位置
+    write_pos: usize,
+}
+
+impl DelayLine {
+    /// 新しいDelayLineを作成
+    pub fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            write_pos: 0,
+        }
+    }
+
+    /// 最大遅延サンプル数を指定してバッファを確保する
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn prepare(&mut self, max_delay_samples: usize) {
+        // 補間のために1サンプル余分に確保
+        self.data = vec![0.0; max_delay_samples + 2];
+        self.write_pos = 0;
+    }
+
+    /// 1サンプル書き込む
+    pub fn write(&mut self, sample: f32) {
+        if self.data.is_empty() {
+            return;
+        }
+        self.data[self.write_pos] = sample;
+        self.write_pos += 1;
+        if self.write_pos >= self.data.len() {
+            self.write_pos = 0;
+        }
+    }
+
+    /// 直前に書き込んだサンプルから `delay_samples` 遅れたサンプルを読み出す
+    ///
+    /// `delay_samples` が 0 の場合は直前に書き込んだサンプルを返します。
+    /// 最大遅延サンプル数を超える値は最大遅延サンプル数に制限されます。
+    pub fn read(&self, delay_samples: f32) -> f32 {
+        let len = self.data.len();
+        if len < 2 {
+            return 0.0;
+        }
+        let delay = delay_samples.clamp(0.0, (len - 2) as f32);
+        let int_part = delay as usize;
+        let frac = delay - int_part as f32;
+
+        // write_pos の1つ前が直前に書き込んだサンプル
+        let idx_a = (self.write_pos + len - 1 - int_part) % len;
+        let idx_b = (idx_a + len - 1) % len;
+        let a = self.data[idx_a];
+        let b = self.data[idx_b];
+        a + (b - a) * frac
+    }
+
+    /// バッファを 0 でクリアする
+    pub fn reset(&mut self) {
+        self.data.fill(0.0);
+        self.write_pos = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_line_interpolation() {
+        let mut delay_line = DelayLine::new();
+        delay_line.prepare(4);
+        for x in [1.0, 2.0, 3.0, 4.0] {
+            delay_line.write(x);
+        }
+
+        assert_eq!(delay_line.read(0.0), 4.0);
+        assert_eq!(delay_line.read(2.0), 2.0);
+        assert_eq!(delay_line.read(1.5), 2.5);
+    }
+}
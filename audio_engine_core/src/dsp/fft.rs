@@ -0,0 +1,217 @@
+use crate::prelude::*;
+
+/// 基数 2 の FFT をその場で計算する
+///
+/// 逆変換の場合は 1/N で正規化するため、順変換と逆変換を続けると元の信号に戻ります。
+///
+/// # 引数
+/// * `re` - 実部（変換結果で上書きされる）
+/// * `im` - 虚部（変換結果で上書きされる）
+/// * `inverse` - 逆変換する場合は true
+///
+/// # 実装時の注意
+/// 長さは 2 のべき乗で、実部と虚部で同じでなければなりません。
+/// 解析やテスト向けの実装で、回転因子を毎回計算するため、リアルタイムの処理には向きません。
+/// リアルタイムの処理には、回転因子を事前に計算する `Fft` を使ってください。
+pub fn fft(re: &mut [f64], im: &mut [f64], inverse: bool) {
+    let n = re.len();
+    assert!(
+        n.is_power_of_two() && im.len() == n,
+        "FFT の長さが不正です: {} / {}",
+        n,
+        im.len()
+    );
+
+    // ビット反転の順に並べ替える
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    // バタフライ演算
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let angle = sign * core::f64::consts::TAU / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..half {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let a = start + k;
+                let b = a + half;
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        let scale = 1.0 / n as f64;
+        re.iter_mut().for_each(|x| *x *= scale);
+        im.iter_mut().for_each(|x| *x *= scale);
+    }
+}
+
+/// 回転因子とビット反転の表を事前に計算した、リアルタイム処理向けの基数 2 の FFT
+///
+/// `process` はメモリアロケーションを行わないため、オーディオスレッドから呼び出せます。
+pub struct Fft {
+    /// 回転因子の余弦（長さ N/2）
+    cos_table: Vec<f32>,
+    /// 回転因子の正弦（長さ N/2）
+    sin_table: Vec<f32>,
+    /// ビット反転した位置
+    bit_reversed: Vec<usize>,
+}
+
+impl Fft {
+    /// 長さを指定して新しいFftを作成
+    ///
+    /// # 引数
+    /// * `size` - FFT の長さ（2 のべき乗）
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two(), "FFT の長さが不正です: {}", size);
+        let (sin_table, cos_table) = (0..size / 2)
+            .map(|k| (-core::f64::consts::TAU * k as f64 / size as f64).sin_cos())
+            .map(|(sin, cos)| (sin as f32, cos as f32))
+            .unzip();
+        let bits = size.trailing_zeros();
+        let bit_reversed = (0..size)
+            .map(|i| {
+                if bits == 0 {
+                    0
+                } else {
+                    i.reverse_bits() >> (usize::BITS - bits)
+                }
+            })
+            .collect();
+        Self {
+            cos_table,
+            sin_table,
+            bit_reversed,
+        }
+    }
+
+    /// FFT の長さ
+    pub fn size(&self) -> usize {
+        self.bit_reversed.len()
+    }
+
+    /// FFT をその場で計算する
+    ///
+    /// 逆変換の場合は `fft` と同じく 1/N で正規化します。
+    ///
+    /// # 引数
+    /// * `re` - 実部（長さは `size`、変換結果で上書きされる）
+    /// * `im` - 虚部（長さは `size`、変換結果で上書きされる）
+    /// * `inverse` - 逆変換する場合は true
+    pub fn process(&self, re: &mut [f32], im: &mut [f32], inverse: bool) {
+        let n = self.size();
+        assert!(
+            re.len() == n && im.len() == n,
+            "FFT の長さが不正です: {} / {}",
+            re.len(),
+            im.len()
+        );
+
+        for (i, &j) in self.bit_reversed.iter().enumerate() {
+            if i < j {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        // バタフライ演算（逆変換は回転因子の共役を使う）
+        let sign = if inverse { -1.0 } else { 1.0 };
+        let mut len = 2;
+        while len <= n {
+            let half = len / 2;
+            let stride = n / len;
+            for start in (0..n).step_by(len) {
+                for k in 0..half {
+                    let cos = self.cos_table[k * stride];
+                    let sin = sign * self.sin_table[k * stride];
+                    let a = start + k;
+                    let b = a + half;
+                    let tr = re[b] * cos - im[b] * sin;
+                    let ti = re[b] * sin + im[b] * cos;
+                    re[b] = re[a] - tr;
+                    im[b] = im[a] - ti;
+                    re[a] += tr;
+                    im[a] += ti;
+                }
+            }
+            len <<= 1;
+        }
+
+        if inverse {
+            let scale = 1.0 / n as f32;
+            re.iter_mut().for_each(|x| *x *= scale);
+            im.iter_mut().for_each(|x| *x *= scale);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fft_round_trip() {
+        // 周期 4 のコサインはビン 2 と 6 に現れる
+        let signal: Vec<f64> = (0..8)
+            .map(|i| (core::f64::consts::TAU * i as f64 / 4.0).cos())
+            .collect();
+        let mut re = signal.clone();
+        let mut im = vec![0.0; 8];
+        fft(&mut re, &mut im, false);
+        for (bin, (r, i)) in re.iter().zip(&im).enumerate() {
+            let expected = if bin == 2 || bin == 6 { 4.0 } else { 0.0 };
+            assert!((r - expected).abs() < 1e-9 && i.abs() < 1e-9);
+        }
+
+        fft(&mut re, &mut im, true);
+        for (r, s) in re.iter().zip(&signal) {
+            assert!((r - s).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_fft_table_matches_fft() {
+        let signal: Vec<f64> = (0..16).map(|i| ((i * 7) % 5) as f64 - 2.0).collect();
+        let mut re = signal.clone();
+        let mut im = vec![0.0; 16];
+        fft(&mut re, &mut im, false);
+
+        let table = Fft::new(16);
+        let mut re32: Vec<f32> = signal.iter().map(|&x| x as f32).collect();
+        let mut im32 = vec![0.0; 16];
+        table.process(&mut re32, &mut im32, false);
+        for k in 0..16 {
+            assert!((re32[k] as f64 - re[k]).abs() < 1e-4);
+            assert!((im32[k] as f64 - im[k]).abs() < 1e-4);
+        }
+
+        table.process(&mut re32, &mut im32, true);
+        for (r, s) in re32.iter().zip(&signal) {
+            assert!((*r as f64 - s).abs() < 1e-5);
+        }
+    }
+}
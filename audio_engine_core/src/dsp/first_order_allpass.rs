@@ -0,0 +1,45 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+/// 1次オールパスフィルター
+///
+/// 振幅特性を変えずに、指定した周波数で位相を 90 度回転させます。フェイザーの段として使います。
+pub struct FirstOrderAllpass {
+    /// フィルター係数
+    coefficient: f32,
+    /// 直前の入力
+    x1: f32,
+    /// 直前の出力
+    y1: f32,
+}
+
+impl FirstOrderAllpass {
+    /// 新しいFirstOrderAllpassを作成
+    pub fn new() -> Self {
+        Self {
+            coefficient: 0.0,
+            x1: 0.0,
+            y1: 0.0,
+        }
+    }
+
+    /// 位相が 90 度回転する周波数を設定する
+    pub fn set_frequency(&mut self, frequency: f32, sample_rate: f32) {
+        let t = (core::f32::consts::PI * frequency / sample_rate).tan();
+        self.coefficient = (t - 1.0) / (t + 1.0);
+    }
+
+    /// 1サンプル処理する
+    pub fn process(&mut self, x: f32) -> f32 {
+        let y = self.coefficient * x + self.x1 - self.coefficient * self.y1;
+        self.x1 = x;
+        self.y1 = y;
+        y
+    }
+
+    /// 内部状態をリセットする
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.y1 = 0.0;
+    }
+}
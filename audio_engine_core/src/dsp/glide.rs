@@ -0,0 +1,119 @@
+use super::SmoothedValue;
+
+/// ポルタメントの時間の扱い
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlideMode {
+    /// 音程の差にかかわらず、設定した時間で目標の音程に到達する
+    ConstantTime,
+    /// 1 オクターブあたり設定した時間の速さで変化する（音程の差が大きいほど時間がかかる）
+    ConstantRate,
+}
+
+/// ノート番号（半音単位）の音程を滑らかに変化させるポルタメント
+///
+/// ボイスごとに 1 つ持ち、ノートオンのたびに glide_to または jump_to で目標の音程を設定します。
+/// 音程は半音単位で線形に変化するため、周波数は指数的に変化します。
+pub struct Glide {
+    /// 現在の音程（ノート番号）
+    pitch: SmoothedValue,
+    /// 時間の扱い
+    mode: GlideMode,
+    /// ポルタメントの時間（ms）。ConstantRate の場合は 1 オクターブあたりの時間
+    time_ms: f32,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl Glide {
+    /// 初期の音程を指定して新しいGlideを作成
+    pub fn new(initial_pitch: f32) -> Self {
+        Self {
+            pitch: SmoothedValue::new(initial_pitch),
+            mode: GlideMode::ConstantTime,
+            time_ms: 0.0,
+            sample_rate: 44100.0,
+        }
+    }
+
+    /// サンプリングレートを設定する
+    pub fn prepare(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.pitch.set_current_and_target(self.pitch.target());
+    }
+
+    /// 時間の扱いを設定する
+    pub fn set_mode(&mut self, mode: GlideMode) {
+        self.mode = mode;
+    }
+
+    /// ポルタメントの時間を設定する（ms）。0 の場合はポルタメントしない
+    pub fn set_time_ms(&mut self, time_ms: f32) {
+        self.time_ms = time_ms.max(0.0);
+    }
+
+    /// ポルタメントの時間（ms）
+    pub fn time_ms(&self) -> f32 {
+        self.time_ms
+    }
+
+    /// 現在の音程から目標の音程まで、設定した時間をかけて変化させる
+    pub fn glide_to(&mut self, pitch: f32) {
+        let mut ms = self.time_ms;
+        if self.mode == GlideMode::ConstantRate {
+            ms *= (pitch - self.pitch.current()).abs() / 12.0;
+        }
+        let num_samples = (ms / 1000.0 * self.sample_rate) as usize;
+        self.pitch.ramp_to(pitch, num_samples);
+    }
+
+    /// 変化させずに音程を設定する
+    pub fn jump_to(&mut self, pitch: f32) {
+        self.pitch.set_current_and_target(pitch);
+    }
+
+    /// 1サンプル進めて、現在の音程を返す
+    pub fn next_value(&mut self) -> f32 {
+        self.pitch.next_value()
+    }
+
+    /// 現在の音程を取得する
+    pub fn current(&self) -> f32 {
+        self.pitch.current()
+    }
+
+    /// 変化中かどうか
+    pub fn is_gliding(&self) -> bool {
+        self.pitch.is_smoothing()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glide_time_modes() {
+        let mut glide = Glide::new(60.0);
+        glide.prepare(1000.0);
+        glide.set_time_ms(4.0);
+
+        // 一定時間: 音程の差にかかわらず 4 サンプルで到達する
+        glide.glide_to(84.0);
+        let ramp: Vec<f32> = (0..5).map(|_| glide.next_value()).collect();
+        assert_eq!(ramp, vec![66.0, 72.0, 78.0, 84.0, 84.0]);
+
+        // 一定の速さ: 2 オクターブの変化は 8 サンプルかかる
+        glide.set_mode(GlideMode::ConstantRate);
+        glide.glide_to(60.0);
+        let samples =
+            core::iter::from_fn(|| glide.is_gliding().then(|| glide.next_value())).count();
+        assert_eq!(samples, 8);
+        assert_eq!(glide.current(), 60.0);
+
+        // 時間が 0 の場合はすぐに到達する
+        glide.set_time_ms(0.0);
+        glide.glide_to(72.0);
+        assert!(!glide.is_gliding());
+        assert_eq!(glide.current(), 72.0);
+    }
+}
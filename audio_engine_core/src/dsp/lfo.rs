@@ -0,0 +1,108 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+/// LFO の波形
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LfoShape {
+    /// サイン波
+    Sine,
+    /// 三角波
+    Triangle,
+}
+
+/// 低周波オシレーター
+///
+/// -1～1 の範囲の値を出力します。モジュレーション系のエフェクトで共通して使います。
+pub struct Lfo {
+    /// 波形
+    shape: LfoShape,
+    /// 周波数（Hz）
+    rate: f32,
+    /// 現在の位相（0～1の範囲で保持）
+    phase: f32,
+    /// reset 時に戻す初期位相（0～1）
+    initial_phase: f32,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl Lfo {
+    /// 新しいLfoを作成
+    pub fn new() -> Self {
+        Self {
+            shape: LfoShape::Sine,
+            rate: 1.0,
+            phase: 0.0,
+            initial_phase: 0.0,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// サンプリングレートを設定
+    pub fn prepare(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// 波形を設定
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    /// 周波数を設定（Hz）
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate;
+    }
+
+    /// 初期位相を設定（0～1）。現在の位相も同じ値に設定されます。
+    pub fn set_phase(&mut self, phase: f32) {
+        self.initial_phase = phase.rem_euclid(1.0);
+        self.phase = self.initial_phase;
+    }
+
+    /// 1サンプル分の値を計算し、位相を進める
+    pub fn next_value(&mut self) -> f32 {
+        let value = self.value();
+        self.skip(1);
+        value
+    }
+
+    /// 現在の位相の値を計算する（位相は進めない）
+    pub fn value(&self) -> f32 {
+        match self.shape {
+            LfoShape::Sine => (self.phase * core::f32::consts::TAU).sin(),
+            // 位相 0 で 0、0.25 で 1、0.75 で -1 となる三角波
+            LfoShape::Triangle => 4.0 * ((self.phase - 0.25).rem_euclid(1.0) - 0.5).abs() - 1.0,
+        }
+    }
+
+    /// 値を計算せずに、指定したサンプル数分だけ位相を進める
+    pub fn skip(&mut self, num_samples: usize) {
+        self.phase += self.rate / self.sample_rate * num_samples as f32;
+        self.phase -= self.phase.floor();
+    }
+
+    /// 位相を初期位相に戻す
+    pub fn reset(&mut self) {
+        self.phase = self.initial_phase;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lfo_shapes() {
+        let mut lfo = Lfo::new();
+        lfo.prepare(4.0);
+        lfo.set_rate(1.0);
+        let sine: Vec<f32> = (0..4).map(|_| lfo.next_value()).collect();
+        assert!(sine[0].abs() < 1e-6);
+        assert!((sine[1] - 1.0).abs() < 1e-6);
+
+        lfo.set_shape(LfoShape::Triangle);
+        lfo.set_phase(0.0);
+        let triangle: Vec<f32> = (0..4).map(|_| lfo.next_value()).collect();
+        assert_eq!(triangle, vec![0.0, 1.0, 0.0, -1.0]);
+    }
+}
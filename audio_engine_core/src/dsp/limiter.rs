@@ -0,0 +1,243 @@
+use crate::audio_buffer::AudioBuffer;
+use crate::prelude::*;
+
+/// ルックアヘッド付きのブリックウォールリミッター
+///
+/// 信号をルックアヘッドの時間だけ遅らせ、その間に現れるピークが天井を超えないように
+/// 前もってゲインを下げます。ゲインはルックアヘッドの時間をかけて滑らかに下がり、
+/// リリースの時間をかけて戻ります。全チャンネルに同じゲインを掛けるため、定位は変わりません。
+///
+/// 出力は天井を超えないことが保証されます（浮動小数点の誤差の分は最後にクリップします）。
+pub struct Limiter {
+    /// 天井（リニア）
+    ceiling: f32,
+    /// リリースの時間（ms）
+    release_ms: f32,
+    /// リリースの 1 サンプルあたりの係数
+    release_coef: f32,
+    /// サンプリングレート
+    sample_rate: f32,
+    /// ルックアヘッドのサンプル数
+    lookahead: usize,
+    /// チャンネル数
+    num_channels: usize,
+    /// 遅延用のリングバッファ（lookahead フレーム、インターリーブ）
+    delay: Vec<f32>,
+    /// 必要なゲインの区間最小値を求めるための単調キュー（サンプル番号）
+    min_index: Vec<u64>,
+    /// 単調キュー（必要なゲイン）
+    min_value: Vec<f32>,
+    /// 単調キューの先頭の位置
+    min_head: usize,
+    /// 単調キューの要素数
+    min_len: usize,
+    /// 区間最小値の履歴（移動平均用のリングバッファ）
+    average_history: Vec<f32>,
+    /// 区間最小値の履歴の合計
+    average_sum: f64,
+    /// 現在のゲイン
+    gain: f32,
+    /// 処理したサンプル数（リングバッファの位置にも使う）
+    position: u64,
+}
+
+impl Limiter {
+    /// 新しいLimiterを作成（天井 -1dB、リリース 50ms）
+    pub fn new() -> Self {
+        Self {
+            ceiling: db_to_linear(-1.0),
+            release_ms: 50.0,
+            release_coef: 0.0,
+            sample_rate: 44100.0,
+            lookahead: 0,
+            num_channels: 0,
+            delay: Vec::new(),
+            min_index: Vec::new(),
+            min_value: Vec::new(),
+            min_head: 0,
+            min_len: 0,
+            average_history: Vec::new(),
+            average_sum: 0.0,
+            gain: 1.0,
+            position: 0,
+        }
+    }
+
+    /// バッファを確保し、状態をリセットする
+    ///
+    /// # 引数
+    /// * `sample_rate` - サンプリングレート
+    /// * `num_channels` - チャンネル数
+    /// * `lookahead_ms` - ルックアヘッドの時間（ms）
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn prepare(&mut self, sample_rate: f32, num_channels: usize, lookahead_ms: f32) {
+        self.lookahead = ((lookahead_ms / 1000.0) * sample_rate).max(1.0) as usize;
+        self.num_channels = num_channels.max(1);
+        let window = self.lookahead + 1;
+        self.delay = vec![0.0; self.lookahead * self.num_channels];
+        self.min_index = vec![0; window];
+        self.min_value = vec![1.0; window];
+        self.average_history = vec![1.0; window];
+        self.sample_rate = sample_rate;
+        self.update_release_coef();
+        self.reset();
+    }
+
+    /// 天井を設定する（dB）
+    pub fn set_ceiling_db(&mut self, ceiling_db: f32) {
+        self.ceiling = db_to_linear(ceiling_db);
+    }
+
+    /// 天井（dB）
+    pub fn ceiling_db(&self) -> f32 {
+        20.0 * self.ceiling.log10()
+    }
+
+    /// リリースの時間を設定する（ms）
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.max(0.0);
+        self.update_release_coef();
+    }
+
+    fn update_release_coef(&mut self) {
+        let release_samples = (self.release_ms / 1000.0 * self.sample_rate).max(1.0);
+        self.release_coef = 1.0 - (-1.0 / release_samples).exp();
+    }
+
+    /// 現在のゲインの減少量（dB、0 以下）
+    pub fn gain_reduction_db(&self) -> f32 {
+        20.0 * self.gain.max(1e-6).log10()
+    }
+
+    /// ルックアヘッドによる遅延（サンプル数）
+    pub fn latency_samples(&self) -> usize {
+        self.lookahead
+    }
+
+    /// 遅延中の信号とゲインをリセットする
+    pub fn reset(&mut self) {
+        self.delay.fill(0.0);
+        self.min_head = 0;
+        self.min_len = 0;
+        self.average_history.fill(1.0);
+        self.average_sum = self.average_history.len() as f64;
+        self.gain = 1.0;
+        self.position = 0;
+    }
+
+    /// バッファを処理する（インターリーブ、チャンネル数は prepare で指定したもの以下）
+    pub fn process(&mut self, buffer: &mut AudioBuffer) {
+        let num_channels = buffer.num_channels();
+        if self.delay.is_empty() || num_channels > self.num_channels {
+            return;
+        }
+        let window = self.lookahead + 1;
+        let num_frames = buffer.num_frames();
+        let samples = buffer.as_mut_slice();
+        for frame in samples.chunks_exact_mut(num_channels).take(num_frames) {
+            let n = self.position;
+            let slot = (n % window as u64) as usize;
+
+            // このフレームを天井に収めるのに必要なゲイン
+            let peak = frame.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            let required = if peak > self.ceiling {
+                self.ceiling / peak
+            } else {
+                1.0
+            };
+
+            // 直近 window サンプルの必要なゲインの最小値（単調キュー）
+            while self.min_len > 0 {
+                let back = (self.min_head + self.min_len - 1) % window;
+                if self.min_value[back] < required {
+                    break;
+                }
+                self.min_len -= 1;
+            }
+            let back = (self.min_head + self.min_len) % window;
+            self.min_index[back] = n;
+            self.min_value[back] = required;
+            self.min_len += 1;
+            while self.min_index[self.min_head] + (window as u64) <= n {
+                self.min_head = (self.min_head + 1) % window;
+                self.min_len -= 1;
+            }
+            let minimum = self.min_value[self.min_head];
+
+            // 区間最小値を window サンプルで平均し、ゲインを滑らかに下げる
+            self.average_sum += (minimum - self.average_history[slot]) as f64;
+            self.average_history[slot] = minimum;
+            let target = (self.average_sum / window as f64) as f32;
+            self.gain = if target < self.gain {
+                target
+            } else {
+                self.gain + (target - self.gain) * self.release_coef
+            };
+
+            // 遅延した信号にゲインを掛けて出力する（リングバッファの同じ位置が最も古いフレーム）
+            let delay_slot = (n % self.lookahead as u64) as usize * self.num_channels;
+            let delayed = &mut self.delay[delay_slot..delay_slot + num_channels];
+            for (sample, old) in frame.iter_mut().zip(delayed.iter_mut()) {
+                let input = *sample;
+                *sample = (*old * self.gain).clamp(-self.ceiling, self.ceiling);
+                *old = input;
+            }
+            self.position += 1;
+        }
+    }
+}
+
+/// dB をリニアの値に変換する
+fn db_to_linear(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peaks_stay_below_ceiling() {
+        let sample_rate = 48000.0;
+        let mut limiter = Limiter::new();
+        limiter.set_ceiling_db(-6.0);
+        limiter.prepare(sample_rate, 2, 5.0);
+        let latency = limiter.latency_samples();
+        assert_eq!(latency, 240);
+
+        // 小さい信号は遅延するだけで変化しない
+        let quiet: Vec<f32> = (0..4800)
+            .flat_map(|i| {
+                let s = 0.25 * (i as f32 * 0.05).sin();
+                [s, -s]
+            })
+            .collect();
+        let mut samples = quiet.clone();
+        for block in samples.chunks_mut(512) {
+            let num_frames = block.len() / 2;
+            limiter.process(&mut AudioBuffer::new(2, num_frames, block));
+        }
+        for (out, input) in samples[latency * 2..].iter().zip(&quiet) {
+            assert!((out - input).abs() < 1e-6);
+        }
+
+        // 天井を大きく超える信号と急なピークも天井に収まる
+        let ceiling = db_to_linear(-6.0);
+        let mut loud: Vec<f32> = (0..9600)
+            .flat_map(|i| {
+                let s = 4.0 * (i as f32 * 0.05).sin();
+                [s, s * 0.5]
+            })
+            .collect();
+        loud[6000] = 100.0;
+        for block in loud.chunks_mut(512) {
+            let num_frames = block.len() / 2;
+            limiter.process(&mut AudioBuffer::new(2, num_frames, block));
+        }
+        assert!(loud.iter().all(|s| s.abs() <= ceiling));
+        assert!(loud.iter().any(|s| s.abs() > ceiling * 0.9));
+        assert!(limiter.gain_reduction_db() < -6.0);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::audio_buffer::AudioBuffer;
+use crate::prelude::*;
+
+use super::SincResampler;
+
+/// 出力の FIFO にあらかじめ入れておく無音のフレーム数
+///
+/// ブロックごとに内部レートで処理されるフレーム数が ±1 揺らぐため、その分を吸収します。
+const PREFILL_FRAMES: usize = 4;
+
+/// 外部のサンプリングレートで届くブロックを、別のサンプリングレートで処理するための変換器
+///
+/// 入力を内部レートに変換してコールバックで処理し、その結果を外部レートに戻します。
+/// 内部で処理されるフレーム数はブロックごとに変わるため、結果は FIFO を通して返します。
+/// 48kHz で準備したグラフを 44.1kHz のデバイスで動かす場合などに使います。
+pub struct RateConverter {
+    /// チャンネル数
+    num_channels: usize,
+    /// 外部レートと内部レートが同じかどうか（同じ場合は変換しない）
+    bypass: bool,
+    /// 外部レートから内部レートへの変換
+    upstream: SincResampler,
+    /// 内部レートから外部レートへの変換
+    downstream: SincResampler,
+    /// 内部レートのブロック（インターリーブ）
+    inner_buffer: Vec<f32>,
+    /// 外部レートに戻したブロック（インターリーブ）
+    outer_buffer: Vec<f32>,
+    /// 外部レートの出力を貯めるリングバッファ（インターリーブ）
+    fifo: Vec<f32>,
+    /// FIFO の読み出し位置（フレーム）
+    fifo_read: usize,
+    /// FIFO に貯まっているフレーム数
+    fifo_len: usize,
+    /// 内部レートの最大ブロックサイズ
+    max_inner_frames: usize,
+}
+
+impl RateConverter {
+    /// 新しいRateConverterを作成
+    pub fn new() -> Self {
+        Self {
+            num_channels: 0,
+            bypass: true,
+            upstream: SincResampler::new(),
+            downstream: SincResampler::new(),
+            inner_buffer: Vec::new(),
+            outer_buffer: Vec::new(),
+            fifo: Vec::new(),
+            fifo_read: 0,
+            fifo_len: 0,
+            max_inner_frames: 0,
+        }
+    }
+
+    /// 変換器を準備する
+    ///
+    /// # 引数
+    /// * `num_channels` - チャンネル数
+    /// * `outer_rate` - 外部（デバイスなど）のサンプリングレート
+    /// * `inner_rate` - 内部で処理するサンプリングレート
+    /// * `max_outer_frames` - 外部の最大ブロックサイズ
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn prepare(
+        &mut self,
+        num_channels: usize,
+        outer_rate: f32,
+        inner_rate: f32,
+        max_outer_frames: usize,
+    ) {
+        self.num_channels = num_channels;
+        self.bypass = outer_rate == inner_rate;
+        if self.bypass {
+            self.max_inner_frames = max_outer_frames;
+            return;
+        }
+        self.upstream.prepare(num_channels, outer_rate, inner_rate);
+        self.downstream
+            .prepare(num_channels, inner_rate, outer_rate);
+
+        let ratio = inner_rate as f64 / outer_rate as f64;
+        self.max_inner_frames = (max_outer_frames as f64 * ratio).ceil() as usize + 2;
+        let max_returned_frames = max_outer_frames + 4;
+        self.inner_buffer = vec![0.0; self.max_inner_frames * num_channels];
+        self.outer_buffer = vec![0.0; max_returned_frames * num_channels];
+        self.fifo =
+            vec![0.0; (max_outer_frames + max_returned_frames + PREFILL_FRAMES) * num_channels];
+        self.reset();
+    }
+
+    /// 内部状態をリセットする
+    pub fn reset(&mut self) {
+        self.upstream.reset();
+        self.downstream.reset();
+        self.fifo.fill(0.0);
+        self.fifo_read = 0;
+        self.fifo_len = if self.bypass { 0 } else { PREFILL_FRAMES };
+    }
+
+    /// 内部レートで処理される最大ブロックサイズ
+    ///
+    /// 内部で処理するノードやグラフは、このサイズで prepare してください。
+    pub fn max_inner_frames(&self) -> usize {
+        self.max_inner_frames
+    }
+
+    /// 変換による遅延（外部レートのフレーム数）
+    pub fn latency_frames(&self) -> usize {
+        if self.bypass {
+            return 0;
+        }
+        let downstream = self.downstream.latency_frames() / self.downstream.ratio();
+        (self.upstream.latency_frames() + downstream).round() as usize + PREFILL_FRAMES
+    }
+
+    /// ブロックを内部レートに変換して処理し、外部レートに戻す
+    ///
+    /// # 引数
+    /// * `buffer` - 外部レートのブロック。処理結果で上書きされる。
+    /// * `process` - 内部レートのブロックを処理するコールバック
+    pub fn process(&mut self, buffer: &mut AudioBuffer, mut process: impl FnMut(&mut AudioBuffer)) {
+        let num_channels = self.num_channels;
+        if self.bypass || buffer.num_channels() != num_channels {
+            process(buffer);
+            return;
+        }
+
+        // 外部レート → 内部レート
+        let (_, inner_frames) = self
+            .upstream
+            .process(buffer.as_slice(), &mut self.inner_buffer);
+        {
+            let inner = &mut self.inner_buffer[..inner_frames * num_channels];
+            let mut inner_buffer = AudioBuffer::new(num_channels, inner_frames, inner);
+            process(&mut inner_buffer);
+        }
+
+        // 内部レート → 外部レート
+        let (_, outer_frames) = self.downstream.process(
+            &self.inner_buffer[..inner_frames * num_channels],
+            &mut self.outer_buffer,
+        );
+        let fifo_frames = self.fifo.len() / num_channels;
+        for frame in self.outer_buffer[..outer_frames * num_channels].chunks_exact(num_channels) {
+            if self.fifo_len == fifo_frames {
+                break;
+            }
+            let pos = (self.fifo_read + self.fifo_len) % fifo_frames;
+            self.fifo[pos * num_channels..(pos + 1) * num_channels].copy_from_slice(frame);
+            self.fifo_len += 1;
+        }
+
+        // FIFO からブロック分を取り出す。足りない場合は無音で埋める。
+        for i in 0..buffer.num_frames() {
+            let frame = buffer.get_mut_frame(i);
+            if self.fifo_len == 0 {
+                frame.fill(0.0);
+                continue;
+            }
+            let pos = self.fifo_read;
+            frame.copy_from_slice(&self.fifo[pos * num_channels..(pos + 1) * num_channels]);
+            self.fifo_read = (self.fifo_read + 1) % fifo_frames;
+            self.fifo_len -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_converter_passes_dc() {
+        let mut converter = RateConverter::new();
+        converter.prepare(2, 44100.0, 48000.0, 64);
+
+        let mut max_inner = 0;
+        let mut vector: Vec<f32> = vec![0.0; 128];
+        for _ in 0..20 {
+            vector.fill(0.5);
+            let mut buffer = AudioBuffer::new(2, 64, vector.as_mut_slice());
+            converter.process(&mut buffer, |inner| {
+                max_inner = max_inner.max(inner.num_frames());
+            });
+        }
+
+        // 十分な時間が経過した後は、直流成分がそのまま戻ってくる
+        assert!(max_inner <= converter.max_inner_frames());
+        for sample in vector.iter() {
+            assert!((sample - 0.5).abs() < 1e-3);
+        }
+    }
+}
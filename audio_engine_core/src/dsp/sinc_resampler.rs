@@ -0,0 +1,244 @@
+use crate::prelude::*;
+
+/// カットオフ付近の片側のタップ数（アップサンプリング時）
+const HALF_TAPS: usize = 16;
+
+/// ポリフェーズフィルターの位相数（位相の間は線形補間します）
+const NUM_PHASES: usize = 256;
+
+/// ナイキスト周波数に対するカットオフ周波数の割合（エイリアシングを抑えるため少し下げる）
+const ROLLOFF: f64 = 0.9;
+
+/// 窓付き sinc 関数によるポリフェーズのリサンプラー（インターリーブ、マルチチャンネル）
+///
+/// 任意の比率のサンプリングレート変換をストリーミングで行います。
+/// 出力は入力に対して `latency_frames` だけ遅れます。
+pub struct SincResampler {
+    /// チャンネル数
+    num_channels: usize,
+    /// 1 出力サンプルあたりに進む入力サンプル数（入力レート / 出力レート）
+    step: f64,
+    /// 片側のタップ数
+    half: usize,
+    /// 位相ごとのフィルター係数（`(NUM_PHASES + 1) * 2 * half`）
+    table: Vec<f32>,
+    /// チャンネルごとの入力履歴（連続したスライスで読めるよう 2 倍の長さで二重に書き込む）
+    history: Vec<f32>,
+    /// 次に書き込む履歴の位置
+    write_pos: usize,
+    /// 次の出力の、履歴の中心からの小数位置。1 以上になったら入力を 1 サンプル読み込む。
+    frac: f64,
+}
+
+impl SincResampler {
+    /// 新しいSincResamplerを作成
+    pub fn new() -> Self {
+        Self {
+            num_channels: 0,
+            step: 1.0,
+            half: HALF_TAPS,
+            table: Vec::new(),
+            history: Vec::new(),
+            write_pos: 0,
+            frac: 1.0,
+        }
+    }
+
+    /// 変換元と変換先のサンプリングレートを指定してフィルターを準備する
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn prepare(&mut self, num_channels: usize, input_rate: f32, output_rate: f32) {
+        self.num_channels = num_channels;
+        self.step = input_rate as f64 / output_rate as f64;
+
+        // ダウンサンプリング時は出力のナイキスト周波数までに帯域を制限するため、カットオフを下げてタップを伸ばす
+        let cutoff = self.step.recip().min(1.0) * ROLLOFF;
+        self.half = (HALF_TAPS as f64 / cutoff.min(1.0)).ceil() as usize;
+        let taps = 2 * self.half;
+
+        self.table = vec![0.0; (NUM_PHASES + 1) * taps];
+        for (phase, coefficients) in self.table.chunks_exact_mut(taps).enumerate() {
+            let frac = phase as f64 / NUM_PHASES as f64;
+            let mut sum = 0.0;
+            for (j, coefficient) in coefficients.iter_mut().enumerate() {
+                let x = j as f64 - (self.half as f64 - 1.0 + frac);
+                let value = cutoff * sinc(cutoff * x) * blackman(x / self.half as f64);
+                *coefficient = value as f32;
+                sum += value;
+            }
+            // 直流のゲインを 1 にそろえる
+            for coefficient in coefficients.iter_mut() {
+                *coefficient /= sum as f32;
+            }
+        }
+
+        self.history = vec![0.0; num_channels * 2 * taps];
+        self.reset();
+    }
+
+    /// 内部状態をリセットする
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.write_pos = 0;
+        self.frac = 1.0;
+    }
+
+    /// 入力に対する出力の遅延（入力のフレーム数）
+    pub fn latency_frames(&self) -> f64 {
+        self.half as f64
+    }
+
+    /// 入力レート / 出力レート
+    pub fn ratio(&self) -> f64 {
+        self.step
+    }
+
+    /// 入力を読み込んで変換後のサンプルを出力する
+    ///
+    /// 入力を使い切るか、出力が埋まった時点で終了します。
+    ///
+    /// # 引数
+    /// * `input` - 入力サンプル（インターリーブ）
+    /// * `output` - 出力先（インターリーブ）
+    ///
+    /// # 戻り値
+    /// * (読み込んだ入力のフレーム数, 書き込んだ出力のフレーム数)
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> (usize, usize) {
+        let num_channels = self.num_channels;
+        if num_channels == 0 {
+            return (0, 0);
+        }
+        let input_frames = input.len() / num_channels;
+        let output_frames = output.len() / num_channels;
+        let taps = 2 * self.half;
+        let mut consumed = 0;
+        let mut produced = 0;
+
+        loop {
+            while self.frac >= 1.0 {
+                if consumed == input_frames {
+                    return (consumed, produced);
+                }
+                let frame = &input[consumed * num_channels..(consumed + 1) * num_channels];
+                for (ch, &sample) in frame.iter().enumerate() {
+                    let history = &mut self.history[ch * 2 * taps..(ch + 1) * 2 * taps];
+                    history[self.write_pos] = sample;
+                    history[self.write_pos + taps] = sample;
+                }
+                self.write_pos = (self.write_pos + 1) % taps;
+                consumed += 1;
+                self.frac -= 1.0;
+            }
+            if produced == output_frames {
+                return (consumed, produced);
+            }
+
+            // 隣り合う 2 つの位相のフィルター出力を線形補間する
+            let phase_pos = self.frac * NUM_PHASES as f64;
+            let phase = (phase_pos as usize).min(NUM_PHASES - 1);
+            let alpha = (phase_pos - phase as f64) as f32;
+            let coefficients_a = &self.table[phase * taps..(phase + 1) * taps];
+            let coefficients_b = &self.table[(phase + 1) * taps..(phase + 2) * taps];
+            for ch in 0..num_channels {
+                // write_pos から始まる taps 個が、古い順に並んだ入力履歴
+                let start = ch * 2 * taps + self.write_pos;
+                let window = &self.history[start..start + taps];
+                let mut a = 0.0;
+                let mut b = 0.0;
+                for ((x, ca), cb) in window.iter().zip(coefficients_a).zip(coefficients_b) {
+                    a += x * ca;
+                    b += x * cb;
+                }
+                output[produced * num_channels + ch] = a + (b - a) * alpha;
+            }
+            produced += 1;
+            self.frac += self.step;
+        }
+    }
+}
+
+/// オーディオデータ全体のサンプリングレートを変換する
+///
+/// ファイルから読み込んだサンプルなど、ストリームとレートの異なるデータを変換するのに使います。
+/// 出力は入力と時間がそろうよう、リサンプラーの遅延を補正します。
+///
+/// # 引数
+/// * `samples` - 入力サンプル（インターリーブ）
+/// * `num_channels` - チャンネル数
+/// * `input_rate` - 入力のサンプリングレート
+/// * `output_rate` - 出力のサンプリングレート
+///
+/// # 実装時の注意
+/// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+pub fn resample(
+    samples: &[f32],
+    num_channels: usize,
+    input_rate: f32,
+    output_rate: f32,
+) -> Vec<f32> {
+    if num_channels == 0 || input_rate == output_rate {
+        return samples.to_vec();
+    }
+    let mut resampler = SincResampler::new();
+    resampler.prepare(num_channels, input_rate, output_rate);
+    // 最初の出力が入力の先頭と同じ時刻になるよう、遅延分の入力を先に読み込ませる
+    resampler.frac = 1.0 + resampler.half as f64;
+
+    let input_frames = samples.len() / num_channels;
+    let output_frames = (input_frames as f64 / resampler.step).ceil() as usize;
+    let mut output = vec![0.0; output_frames * num_channels];
+    let (_, produced) = resampler.process(samples, &mut output);
+
+    // 末尾は無音を読み込ませて出力を押し出す
+    let flush = vec![0.0; (resampler.half + 1) * num_channels];
+    resampler.process(&flush, &mut output[produced * num_channels..]);
+    output
+}
+
+/// 正規化された sinc 関数
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let px = core::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// -1～1 の範囲で定義したブラックマン窓
+fn blackman(x: f64) -> f64 {
+    if x.abs() > 1.0 {
+        return 0.0;
+    }
+    let t = (x + 1.0) * 0.5;
+    0.42 - 0.5 * (core::f64::consts::TAU * t).cos()
+        + 0.08 * (2.0 * core::f64::consts::TAU * t).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_sine_48k_to_44k() {
+        let frequency = 1000.0;
+        let input: Vec<f32> = (0..4800)
+            .map(|i| (core::f32::consts::TAU * frequency * i as f32 / 48000.0).sin())
+            .collect();
+        let output = resample(&input, 1, 48000.0, 44100.0);
+
+        assert_eq!(output.len(), 4410);
+        // フィルターの立ち上がりと末尾を除いて、44.1kHz で生成したサイン波と一致する
+        for (i, &sample) in output.iter().enumerate().skip(100).take(4200) {
+            let expected = (core::f32::consts::TAU * frequency * i as f32 / 44100.0).sin();
+            assert!(
+                (sample - expected).abs() < 1e-2,
+                "{}: {} != {}",
+                i,
+                sample,
+                expected
+            );
+        }
+    }
+}
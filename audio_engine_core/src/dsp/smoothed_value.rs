@@ -0,0 +1,112 @@
+/// パラメーターの急な変化によるノイズを防ぐため、目標値まで線形に変化する値
+pub struct SmoothedValue {
+    /// 現在値
+    current: f32,
+    /// 目標値
+    target: f32,
+    /// 1サンプルあたりの変化量
+    step: f32,
+    /// 目標値に到達するまでの残りサンプル数
+    remaining: usize,
+    /// 目標値に到達するまでのサンプル数
+    ramp_samples: usize,
+}
+
+impl SmoothedValue {
+    /// 初期値を指定して新しいSmoothedValueを作成
+    pub fn new(initial: f32) -> Self {
+        Self {
+            current: initial,
+            target: initial,
+            step: 0.0,
+            remaining: 0,
+            ramp_samples: 0,
+        }
+    }
+
+    /// 目標値に到達するまでの時間を設定する
+    pub fn prepare(&mut self, sample_rate: f32, ramp_ms: f32) {
+        self.ramp_samples = ((ramp_ms / 1000.0) * sample_rate).max(0.0) as usize;
+        self.set_current_and_target(self.target);
+    }
+
+    /// 目標値を設定する。現在値から目標値まで、設定された時間をかけて変化します。
+    pub fn set_target(&mut self, target: f32) {
+        if target == self.target {
+            return;
+        }
+        self.target = target;
+        if self.ramp_samples == 0 {
+            self.current = target;
+            self.remaining = 0;
+        } else {
+            self.remaining = self.ramp_samples;
+            self.step = (self.target - self.current) / self.ramp_samples as f32;
+        }
+    }
+
+    /// 指定したサンプル数をかけて目標値まで変化させる
+    ///
+    /// `prepare` で設定した時間ではなく、この呼び出しに限り `num_samples` を使います。
+    pub fn ramp_to(&mut self, target: f32, num_samples: usize) {
+        self.target = target;
+        if num_samples == 0 {
+            self.current = target;
+            self.remaining = 0;
+        } else {
+            self.remaining = num_samples;
+            self.step = (self.target - self.current) / num_samples as f32;
+        }
+    }
+
+    /// 変化させずに、現在値と目標値を同時に設定する
+    pub fn set_current_and_target(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.remaining = 0;
+    }
+
+    /// 1サンプル進めて、現在値を返す
+    pub fn next_value(&mut self) -> f32 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            if self.remaining == 0 {
+                self.current = self.target;
+            } else {
+                self.current += self.step;
+            }
+        }
+        self.current
+    }
+
+    /// 現在値を取得する
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    /// 目標値を取得する
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// 変化中かどうか
+    pub fn is_smoothing(&self) -> bool {
+        self.remaining > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smoothed_value_ramp() {
+        let mut value = SmoothedValue::new(0.0);
+        value.prepare(1000.0, 4.0);
+        value.set_target(1.0);
+
+        let ramp: Vec<f32> = (0..5).map(|_| value.next_value()).collect();
+        assert_eq!(ramp, vec![0.25, 0.5, 0.75, 1.0, 1.0]);
+        assert!(!value.is_smoothing());
+    }
+}
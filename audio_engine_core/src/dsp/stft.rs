@@ -0,0 +1,211 @@
+use crate::audio_buffer::AudioBuffer;
+use crate::prelude::*;
+
+use super::Fft;
+
+/// チャンネルごとの STFT の状態
+struct StftChannel {
+    /// 入力のリングバッファ（長さは FFT の長さ）
+    input: Vec<f32>,
+    /// 重ね合わせた出力のリングバッファ（長さは FFT の長さ）
+    output: Vec<f32>,
+}
+
+/// 窓関数・ホップ・オーバーラップアドを行う、ストリーミングの短時間フーリエ変換（STFT）
+///
+/// スペクトルを加工するノードはこれを持ち、`process` に渡すクロージャでスペクトルだけを加工します。
+/// 入力をホップごとに Hann 窓で切り出して FFT し、加工したスペクトルを逆 FFT して、
+/// 同じ窓をかけて重ね合わせます。スペクトルを加工しない場合、出力は入力を `latency_samples` だけ遅らせたものになります。
+///
+/// # 実装時の注意
+/// ノードの `latency_samples` では、このレイテンシーを返してください。
+pub struct Stft {
+    /// FFT
+    fft: Fft,
+    /// 分析と合成の窓関数（周期的な Hann 窓）
+    window: Vec<f32>,
+    /// ホップの長さ（サンプル数）
+    hop_size: usize,
+    /// 窓を 2 回かけて重ね合わせたときの振幅の補正
+    scale: f32,
+    /// チャンネルごとの状態
+    channels: Vec<StftChannel>,
+    /// リングバッファの次に書き込む位置
+    position: usize,
+    /// 前のフレームを処理してからのサンプル数
+    hop_counter: usize,
+    /// FFT の作業用の実部
+    re: Vec<f32>,
+    /// FFT の作業用の虚部
+    im: Vec<f32>,
+}
+
+impl Stft {
+    /// 新しいStftを作成
+    ///
+    /// 使う前に `prepare` で FFT の長さとホップを設定してください。
+    pub fn new() -> Self {
+        Self {
+            fft: Fft::new(1),
+            window: Vec::new(),
+            hop_size: 1,
+            scale: 1.0,
+            channels: Vec::new(),
+            position: 0,
+            hop_counter: 0,
+            re: Vec::new(),
+            im: Vec::new(),
+        }
+    }
+
+    /// FFT の長さとホップを設定してバッファを確保する
+    ///
+    /// # 引数
+    /// * `fft_size` - FFT の長さ（2 のべき乗）
+    /// * `hop_size` - ホップの長さ（FFT の長さの 1/4 以下だと、窓による振幅の揺れが無くなる）
+    /// * `num_channels` - 処理するチャンネル数
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn prepare(&mut self, fft_size: usize, hop_size: usize, num_channels: usize) {
+        self.fft = Fft::new(fft_size);
+        self.window = (0..fft_size)
+            .map(|i| 0.5 - 0.5 * (core::f32::consts::TAU * i as f32 / fft_size as f32).cos())
+            .collect();
+        self.hop_size = hop_size.clamp(1, fft_size);
+        // 窓の 2 乗をホップごとに重ね合わせた値の平均で割り、振幅を元に戻す
+        let overlap = self.window.iter().map(|w| w * w).sum::<f32>() / self.hop_size as f32;
+        self.scale = 1.0 / overlap;
+        self.channels = (0..num_channels)
+            .map(|_| StftChannel {
+                input: vec![0.0; fft_size],
+                output: vec![0.0; fft_size],
+            })
+            .collect();
+        self.re = vec![0.0; fft_size];
+        self.im = vec![0.0; fft_size];
+        self.position = 0;
+        self.hop_counter = 0;
+    }
+
+    /// FFT の長さ
+    pub fn fft_size(&self) -> usize {
+        self.window.len()
+    }
+
+    /// ホップの長さ
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// クロージャに渡すスペクトルのビンの数（FFT の長さ / 2 + 1）
+    pub fn num_bins(&self) -> usize {
+        self.fft_size() / 2 + 1
+    }
+
+    /// 入力から出力までの遅延（サンプル数）
+    pub fn latency_samples(&self) -> usize {
+        self.fft_size()
+    }
+
+    /// バッファを 0 でクリアする
+    pub fn reset(&mut self) {
+        for channel in self.channels.iter_mut() {
+            channel.input.fill(0.0);
+            channel.output.fill(0.0);
+        }
+        self.position = 0;
+        self.hop_counter = 0;
+    }
+
+    /// バッファを処理する
+    ///
+    /// ホップごとに、チャンネルごとのスペクトルを `process_spectrum` に渡します。
+    /// スペクトルは直流からナイキスト周波数までの `num_bins` 個のビンで、実部と虚部を書き換えると出力に反映されます。
+    ///
+    /// # 引数
+    /// * `buffer` - 処理するバッファ（`prepare` で指定したチャンネル数を超えるチャンネルはそのまま）
+    /// * `process_spectrum` - チャンネル番号、実部、虚部を受け取ってスペクトルを加工するクロージャ
+    pub fn process<F>(&mut self, buffer: &mut AudioBuffer, mut process_spectrum: F)
+    where
+        F: FnMut(usize, &mut [f32], &mut [f32]),
+    {
+        let fft_size = self.fft_size();
+        if fft_size == 0 {
+            return;
+        }
+        let num_channels = self.channels.len();
+        for frame in buffer.frames_mut() {
+            for (channel, sample) in self.channels.iter_mut().zip(frame.iter_mut()) {
+                channel.input[self.position] = *sample;
+                *sample = channel.output[self.position];
+                channel.output[self.position] = 0.0;
+            }
+            self.position = (self.position + 1) % fft_size;
+            self.hop_counter += 1;
+            if self.hop_counter < self.hop_size {
+                continue;
+            }
+            self.hop_counter = 0;
+            for ch in 0..num_channels {
+                self.process_frame(ch, &mut process_spectrum);
+            }
+        }
+    }
+
+    /// 直近の FFT の長さの入力を変換して加工し、出力に重ね合わせる
+    fn process_frame<F>(&mut self, ch: usize, process_spectrum: &mut F)
+    where
+        F: FnMut(usize, &mut [f32], &mut [f32]),
+    {
+        let fft_size = self.fft_size();
+        let channel = &mut self.channels[ch];
+        // position が最も古いサンプル
+        for (i, (re, w)) in self.re.iter_mut().zip(&self.window).enumerate() {
+            *re = channel.input[(self.position + i) % fft_size] * w;
+        }
+        self.im.fill(0.0);
+        self.fft.process(&mut self.re, &mut self.im, false);
+
+        let half = fft_size / 2;
+        process_spectrum(ch, &mut self.re[..=half], &mut self.im[..=half]);
+
+        // 実信号に戻すため、負の周波数のビンを共役で埋める
+        for k in 1..half {
+            self.re[fft_size - k] = self.re[k];
+            self.im[fft_size - k] = -self.im[k];
+        }
+        self.im[0] = 0.0;
+        self.im[half] = 0.0;
+        self.fft.process(&mut self.re, &mut self.im, true);
+
+        for (i, (re, w)) in self.re.iter().zip(&self.window).enumerate() {
+            channel.output[(self.position + i) % fft_size] += re * w * self.scale;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stft_identity_is_delay() {
+        let mut stft = Stft::new();
+        stft.prepare(16, 4, 1);
+        assert_eq!(stft.num_bins(), 9);
+
+        // スペクトルを加工しなければ、FFT の長さだけ遅れた入力が出力される
+        let input: Vec<f32> = (0..64).map(|i| ((i * 7) % 11) as f32 - 5.0).collect();
+        let mut output = input.clone();
+        for chunk in output.chunks_mut(5) {
+            let len = chunk.len();
+            stft.process(&mut AudioBuffer::new(1, len, chunk), |_, _, _| {});
+        }
+        let latency = stft.latency_samples();
+        assert!(output[..latency].iter().all(|x| x.abs() < 1e-4));
+        for (y, x) in output[latency..].iter().zip(&input) {
+            assert!((y - x).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! オーディオスレッドからアプリケーションにイベントを通知する仕組みです。
+//!
+//! クリップや xrun の検出、レベルメーターの値、MIDI のノートオンの受信、ノード独自のイベントなどを
+//! 固定長の EngineEvent としてロックフリーの EventQueue に入れ、アプリケーションのスレッドで取り出します。
+//!
+//! ノードは on_added で受け取る NodeContext の `events` を保持し、`process` の中から書き込みます。
+//!
+//! ```ignore
+//! fn on_added(&mut self, context: &NodeContext) {
+//!     self.events = context.events.clone();
+//!     self.node_id = context.node_id;
+//! }
+//!
+//! fn process(&mut self, buffer: &mut AudioBuffer) {
+//!     if let Some(events) = self.events.as_ref() {
+//!         events.push(EngineEvent::Node { node_id: self.node_id, code: 0, value: 1.0 });
+//!     }
+//! }
+//! ```
+
+use crate::audio_graph::NodeId;
+use crate::bounded_queue::BoundedQueue;
+
+/// 取り出すまでにためておけるイベント数の目安
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// オーディオスレッドからアプリケーションに通知するイベント
+///
+/// ヒープを使わないため、オーディオスレッドで作成できます。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EngineEvent {
+    /// 出力がクリップした（前回の通知からのクリップの回数）
+    ClipDetected { clips: u64 },
+    /// オーバーフロー・アンダーフロー（xrun）が発生した
+    XRun,
+    /// 出力のピークレベル（前回の通知からの、チャンネルごとの絶対値の最大）
+    PeakLevels { left: f32, right: f32 },
+    /// 再生中にオーディオコールバックが呼び出されなくなり、ストリームが止まった
+    ///
+    /// キューには入らず、サービスの EventNotifier が検出して通知します。
+    StreamStopped,
+    /// 再生中のはずのストリームで、オーディオコールバックが `stalled_ms` ミリ秒以上呼び出されていない
+    ///
+    /// サービスのウォッチドッグが検出してキューに入れます。
+    CallbackStalled { stalled_ms: u64 },
+    /// MIDI 入力のノートオンを受け取った
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: f32,
+    },
+    /// ノード独自のイベント（`code` と `value` の意味はノードごとに決める）
+    Node {
+        node_id: NodeId,
+        code: u32,
+        value: f32,
+    },
+}
+
+/// EngineEvent を受け渡す、ロックフリーのキュー
+///
+/// 複数のスレッドから同時に書き込み・取り出しができます。
+/// いっぱいの場合、書き込んだイベントは破棄され、破棄した件数が記録されます。
+pub struct EventQueue {
+    queue: BoundedQueue<EngineEvent>,
+}
+
+impl EventQueue {
+    /// 新しいEventQueueを作成
+    ///
+    /// # 引数
+    /// * `capacity` - 取り出すまでにためておけるイベント数
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: BoundedQueue::with_capacity(capacity),
+        }
+    }
+
+    /// イベントを書き込む
+    ///
+    /// # 戻り値
+    /// * 書き込めた場合は true。いっぱいの場合は false（イベントは破棄される）
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn push(&self, event: EngineEvent) -> bool {
+        self.queue.push(event)
+    }
+
+    /// 最も古いイベントを取り出す
+    pub fn pop(&self) -> Option<EngineEvent> {
+        self.queue.pop()
+    }
+
+    /// たまったイベントを全て取り出す
+    ///
+    /// # 引数
+    /// * `sink` - 取り出したイベントを受け取る関数
+    ///
+    /// # 戻り値
+    /// * 前回の呼び出しから、キューがいっぱいで破棄したイベント数
+    pub fn drain(&self, mut sink: impl FnMut(EngineEvent)) -> usize {
+        while let Some(event) = self.pop() {
+            sink(event);
+        }
+        self.queue.take_dropped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_queue_drops_when_full() {
+        let queue = EventQueue::new(2);
+        assert!(queue.push(EngineEvent::XRun));
+        assert!(queue.push(EngineEvent::ClipDetected { clips: 3 }));
+        assert!(!queue.push(EngineEvent::XRun));
+
+        let mut events = Vec::new();
+        assert_eq!(queue.drain(|event| events.push(event)), 1);
+        assert_eq!(
+            events,
+            [EngineEvent::XRun, EngineEvent::ClipDetected { clips: 3 }]
+        );
+
+        // 取り出した後は、次の周回として再び書き込める
+        assert!(queue.push(EngineEvent::PeakLevels {
+            left: 0.5,
+            right: 0.25
+        }));
+        assert_eq!(
+            queue.pop(),
+            Some(EngineEvent::PeakLevels {
+                left: 0.5,
+                right: 0.25
+            })
+        );
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -0,0 +1,168 @@
+//! ノードの周波数特性（振幅特性）を測定し、期待する値と比較します。
+//!
+//! フィルターやイコライザー、リサンプラーなどを、聴いて確かめる代わりに数値でテストするために使います。
+//! 測定は impulse_response モジュールのスイープ測定で求めたインパルス応答を FFT して行います。
+
+use crate::prelude::*;
+use crate::{audio_graph::AudioGraphNode, dsp::fft, impulse_response::SweepMeasurement};
+
+/// 測定するインパルス応答のサンプル数
+const IMPULSE_RESPONSE_LENGTH: usize = 8192;
+
+/// 振幅特性を求める FFT の長さ（インパルス応答を 0 で埋めて周波数の分解能を上げる）
+const FFT_SIZE: usize = 65536;
+
+/// 測定に使うスイープの長さ（秒）
+const SWEEP_DURATION: f32 = 0.5;
+
+/// 測定に使うスイープの開始周波数（Hz）
+const SWEEP_START_FREQUENCY: f32 = 10.0;
+
+/// 測定に使う 1 回の process のフレーム数
+const BLOCK_SIZE: usize = 256;
+
+/// 測定した振幅特性
+///
+/// スイープの帯域（10Hz～ナイキスト周波数）の外や、その境界付近の値は正確ではありません。
+pub struct FrequencyResponse {
+    /// FFT のビンごとの振幅（リニア、0Hz～ナイキスト周波数）
+    magnitudes: Vec<f32>,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl FrequencyResponse {
+    /// インパルス応答から振幅特性を求める
+    ///
+    /// # 引数
+    /// * `impulse_response` - インパルス応答（FFT_SIZE サンプルを超える部分は無視する）
+    /// * `sample_rate` - サンプリングレート
+    pub fn from_impulse_response(impulse_response: &[f32], sample_rate: f32) -> Self {
+        let mut re = vec![0.0; FFT_SIZE];
+        let mut im = vec![0.0; FFT_SIZE];
+        for (dst, &src) in re.iter_mut().zip(impulse_response) {
+            *dst = src as f64;
+        }
+        fft(&mut re, &mut im, false);
+        let magnitudes = re
+            .iter()
+            .zip(&im)
+            .take(FFT_SIZE / 2 + 1)
+            .map(|(re, im)| re.hypot(*im) as f32)
+            .collect();
+        Self {
+            magnitudes,
+            sample_rate,
+        }
+    }
+
+    /// ノードの振幅特性を測定する
+    ///
+    /// 入力ノード → `node` → 出力ノードのグラフにスイープを入力し、最初のチャンネルの出力から求めます。
+    /// ノードは測定の前に prepare されます。パラメーターのランプなど、時間とともに変化する処理は
+    /// 測定結果に含まれるため、測定の前に値を確定させておいてください。
+    ///
+    /// # 引数
+    /// * `node` - 測定するノード
+    /// * `sample_rate` - 測定するサンプリングレート
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn measure_node(node: Box<dyn AudioGraphNode>, sample_rate: f32) -> Self {
+        let mut measurement = SweepMeasurement::new(sample_rate);
+        measurement.set_frequencies(SWEEP_START_FREQUENCY, sample_rate / 2.0);
+        measurement.set_duration(SWEEP_DURATION);
+        measurement.set_tail(IMPULSE_RESPONSE_LENGTH as f32 / sample_rate);
+        let impulse_response = measurement.measure_node(node, BLOCK_SIZE, IMPULSE_RESPONSE_LENGTH);
+        Self::from_impulse_response(&impulse_response, sample_rate)
+    }
+
+    /// 指定した周波数の振幅（リニア、隣り合うビンから線形補間する）
+    pub fn magnitude(&self, frequency: f32) -> f32 {
+        let position = (frequency / self.sample_rate * FFT_SIZE as f32)
+            .clamp(0.0, (self.magnitudes.len() - 1) as f32);
+        let index = position as usize;
+        let next = (index + 1).min(self.magnitudes.len() - 1);
+        let frac = position - index as f32;
+        self.magnitudes[index] + (self.magnitudes[next] - self.magnitudes[index]) * frac
+    }
+
+    /// 指定した周波数の振幅（dB）
+    pub fn magnitude_db(&self, frequency: f32) -> f32 {
+        20.0 * self.magnitude(frequency).max(1e-10).log10()
+    }
+
+    /// 振幅が期待する値と許容誤差の範囲で一致するかを確認する
+    ///
+    /// # 引数
+    /// * `expected` - 周波数（Hz）と期待する振幅（dB）の組の一覧
+    /// * `tolerance_db` - 振幅の差の許容値（dB）
+    ///
+    /// # 戻り値
+    /// * 一致しない場合は、一致しなかった全ての周波数を説明するエラーメッセージ
+    pub fn check(&self, expected: &[(f32, f32)], tolerance_db: f32) -> Result<(), String> {
+        let mismatches: Vec<String> = expected
+            .iter()
+            .filter_map(|&(frequency, expected_db)| {
+                let actual_db = self.magnitude_db(frequency);
+                let diff = (actual_db - expected_db).abs();
+                (diff.is_nan() || diff > tolerance_db).then(|| {
+                    format!(
+                        "{}Hz: {:.2}dB != {:.2}dB",
+                        frequency, actual_db, expected_db
+                    )
+                })
+            })
+            .collect();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "振幅特性が一致しません（許容誤差 {}dB）: {}",
+                tolerance_db,
+                mismatches.join(", ")
+            ))
+        }
+    }
+}
+
+/// ノードの振幅特性を測定し、期待する値と比較する
+///
+/// # 引数
+/// * `node` - 測定するノード
+/// * `sample_rate` - 測定するサンプリングレート
+/// * `expected` - 周波数（Hz）と期待する振幅（dB）の組の一覧
+/// * `tolerance_db` - 振幅の差の許容値（dB）
+///
+/// # 戻り値
+/// * 一致しない場合は、一致しなかった全ての周波数を説明するエラーメッセージ
+pub fn check_node_response(
+    node: Box<dyn AudioGraphNode>,
+    sample_rate: f32,
+    expected: &[(f32, f32)],
+    tolerance_db: f32,
+) -> Result<(), String> {
+    FrequencyResponse::measure_node(node, sample_rate).check(expected, tolerance_db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_reports_mismatches() {
+        // 1 サンプルの遅延と -6dB のゲインの振幅特性は平坦
+        let response = FrequencyResponse::from_impulse_response(&[0.0, 0.5], 48000.0);
+        assert!(
+            response
+                .check(&[(100.0, -6.02), (10000.0, -6.02)], 0.01)
+                .is_ok()
+        );
+
+        let error = response
+            .check(&[(100.0, 0.0), (1000.0, -6.02), (5000.0, -12.0)], 0.5)
+            .unwrap_err();
+        assert!(error.contains("100Hz") && error.contains("5000Hz"));
+        assert!(!error.contains("1000Hz"));
+    }
+}
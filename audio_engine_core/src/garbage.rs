@@ -0,0 +1,180 @@
+//! 削除したノードやバッファを、リアルタイムでないスレッドで解放するための仕組みです。
+//!
+//! オーディオスレッドでノードを削除すると、そのままではノードやバッファのメモリがオーディオスレッドで解放されます。
+//! AudioGraph::set_garbage_sender で送信側を設定すると、削除したノードやバッファ、ノードへの割り当ては GarbageCollector に送られ、
+//! 所有者のスレッドで collect を呼び出したときに解放されます。
+//!
+//! ```ignore
+//! let (sender, collector) = garbage::channel(garbage::DEFAULT_CAPACITY);
+//! audio_graph.set_garbage_sender(Some(sender));
+//!
+//! // オーディオスレッドで削除しても、ここではメモリを解放しない
+//! audio_graph.discard_node(node_id);
+//!
+//! // メインスレッドで定期的に解放する
+//! collector.collect();
+//! ```
+//!
+//! 送信側と GarbageCollector はチャンネルを使うため、`std` フィーチャーが必要です。
+//! 無効な場合、削除したノードやバッファはその場で解放されます。
+
+#[cfg(feature = "std")]
+use std::sync::mpsc::{self, Receiver, SyncSender};
+
+use crate::audio_buffer::OwnedAudioBuffer;
+use crate::audio_graph::{
+    AudioGraphNode, AutomationRoute, FrozenSubgraph, InputTap, MacroMapping, ModulationRoute,
+    MorphRoute,
+};
+use crate::prelude::*;
+#[cfg(feature = "std")]
+use crate::rt_warn;
+
+/// collect を呼び出すまでにためておける、解放待ちの数の目安
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// 解放待ちのもの（解放するために保持するだけで、中身は読まない）
+#[allow(dead_code)]
+pub(crate) enum Garbage {
+    /// 削除したノード
+    Node(Box<dyn AudioGraphNode>),
+    /// 削除したノードの出力バッファ
+    Buffer(OwnedAudioBuffer),
+    /// フリーズしたサブグラフの元のノード
+    Frozen(FrozenSubgraph),
+    /// 削除したノードへの入力の一覧
+    InputTaps(Vec<InputTap>),
+    /// 削除したノードのモジュレーションの割り当て
+    Modulation(ModulationRoute),
+    /// 削除したノードの A/B モーフの割り当て
+    Morph(MorphRoute),
+    /// 削除したノードのマクロの割り当て
+    Macro(MacroMapping),
+    /// 削除したノードのオートメーション
+    Automation(AutomationRoute),
+}
+
+/// 解放待ちのものを送る送信側と、受け取って解放する GarbageCollector を作成する
+///
+/// # 引数
+/// * `capacity` - collect を呼び出すまでにためておける数。いっぱいの場合は送り元のスレッドで解放します。
+#[cfg(feature = "std")]
+pub fn channel(capacity: usize) -> (GarbageSender, GarbageCollector) {
+    let (sender, receiver) = mpsc::sync_channel(capacity.max(1));
+    (GarbageSender { sender }, GarbageCollector { receiver })
+}
+
+/// 解放待ちのものを GarbageCollector に送る送信側
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct GarbageSender {
+    sender: SyncSender<Garbage>,
+}
+
+#[cfg(feature = "std")]
+impl GarbageSender {
+    /// 解放待ちのものを送る
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    /// いっぱいの場合や GarbageCollector が破棄されている場合は、やむを得ずこのスレッドで解放します。
+    pub(crate) fn dispose(&self, garbage: Garbage) {
+        if self.sender.try_send(garbage).is_err() {
+            rt_warn!("解放待ちがいっぱいのため、オーディオスレッドで解放します");
+        }
+    }
+}
+
+/// 送られたノードやバッファを解放する受信側
+#[cfg(feature = "std")]
+pub struct GarbageCollector {
+    receiver: Receiver<Garbage>,
+}
+
+#[cfg(feature = "std")]
+impl GarbageCollector {
+    /// 送られたものを全て解放する
+    ///
+    /// # 戻り値
+    /// * 解放した数
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリの解放を行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn collect(&self) -> usize {
+        self.receiver.try_iter().count()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use assert_no_alloc::assert_no_alloc;
+
+    use super::*;
+    use crate::audio_buffer::AudioBuffer;
+    use crate::audio_graph::AudioGraph;
+    use crate::automation::AutomationLane;
+    use crate::nodes::GainProcessor;
+    use crate::parameter::MacroCurve;
+
+    /// drop された回数を数えるノード
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl AudioGraphNode for DropCounter {
+        fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+        fn process(&mut self, _buffer: &mut AudioBuffer) {}
+        fn reset(&mut self) {}
+    }
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_discarded_node_is_dropped_by_collector() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let (sender, collector) = channel(DEFAULT_CAPACITY);
+        let mut graph = AudioGraph::new();
+        graph.set_garbage_sender(Some(sender.clone()));
+        graph.prepare(48000.0, 64);
+        let node_id = graph.add_node(Box::new(DropCounter(dropped.clone())));
+
+        // 削除しただけでは解放されず、collect で解放される
+        assert!(graph.discard_node(node_id));
+        assert!(!graph.discard_node(node_id));
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+        assert!(collector.collect() >= 1);
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+
+        // 送るだけならメモリアロケーションは起きない
+        let garbage = Garbage::Buffer(OwnedAudioBuffer::new(2, 64));
+        assert_no_alloc(|| sender.dispose(garbage));
+        assert_eq!(collector.collect(), 1);
+    }
+
+    #[test]
+    fn test_discarded_node_mappings_are_sent_to_collector() {
+        let (sender, collector) = channel(DEFAULT_CAPACITY);
+        let mut graph = AudioGraph::new();
+        graph.set_garbage_sender(Some(sender));
+        graph.prepare(48000.0, 64);
+        let plain_id = graph.add_node(Box::new(GainProcessor::new()));
+        let mapped_id = graph.add_node(Box::new(GainProcessor::new()));
+        graph
+            .add_macro_mapping(0, mapped_id, "gain", 0.0, 1.0, MacroCurve::Linear)
+            .unwrap();
+        graph
+            .set_automation(mapped_id, "gain", AutomationLane::new())
+            .unwrap();
+
+        // マクロとオートメーションの割り当ての分だけ、解放待ちが多く送られる
+        assert!(graph.discard_node(plain_id));
+        let plain_count = collector.collect();
+        assert!(graph.discard_node(mapped_id));
+        assert_eq!(collector.collect(), plain_count + 2);
+    }
+}
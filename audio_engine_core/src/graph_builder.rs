@@ -0,0 +1,272 @@
+//! ノードの連鎖や並列の分岐を、メソッドをつなげて記述して音声グラフを組み立てます。
+//!
+//! ```
+//! use audio_engine_core::graph_builder::{gain, sine, GraphBuilder};
+//!
+//! // 2 つのサイン波をそれぞれ減衰させて出力する
+//! let built = GraphBuilder::new()
+//!     .parallel([vec![sine(220.0), gain(0.5)], vec![sine(523.25), gain(0.25)]])
+//!     .to_output()
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(built.graph.edges().len(), 4);
+//! ```
+//!
+//! 接続に失敗した場合もその場ではエラーを返さず、最初のエラーを build で返します。
+
+use core::marker::PhantomData;
+
+use crate::prelude::*;
+use crate::{
+    audio_graph::{AudioGraph, AudioGraphNode, NodeId},
+    nodes::{GainProcessor, InputNode, OutputNode, SineGenerator},
+};
+
+/// 音声グラフに追加したノードのハンドル
+///
+/// ノードの ID に、追加したノードの型を付けたものです。
+/// 型はハンドルの取り違えを防ぐためのもので、ID は AudioGraph の各メソッドにそのまま渡せます。
+pub struct NodeHandle<T> {
+    /// ノードの ID
+    id: NodeId,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> NodeHandle<T> {
+    fn new(id: NodeId) -> Self {
+        Self {
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    /// ノードの ID
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+}
+
+// derive は T にも Clone などを要求するため、手動で実装する
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NodeHandle<T> {}
+
+impl<T> PartialEq for NodeHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> core::fmt::Debug for NodeHandle<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "NodeHandle({})", self.id)
+    }
+}
+
+/// GraphBuilder で組み立てた音声グラフ
+pub struct BuiltGraph {
+    /// 組み立てた音声グラフ
+    pub graph: AudioGraph,
+    /// 入力ノード
+    pub input: NodeHandle<InputNode>,
+    /// 出力ノード
+    pub output: NodeHandle<OutputNode>,
+}
+
+/// 音声グラフを組み立てるビルダー
+///
+/// 入力ノードと出力ノードを持つ空のグラフから始め、「現在の末尾」のノードに続けてノードを接続していきます。
+/// 末尾は最初は空で、chain などで追加したノードや from_input で選んだノードになります。
+pub struct GraphBuilder {
+    /// 組み立て中の音声グラフ
+    graph: AudioGraph,
+    /// 入力ノード
+    input: NodeHandle<InputNode>,
+    /// 出力ノード
+    output: NodeHandle<OutputNode>,
+    /// 次に追加するノードへ接続する、現在の末尾のノードの ID
+    tails: Vec<NodeId>,
+    /// 最初に発生したエラー
+    error: Option<String>,
+}
+
+impl GraphBuilder {
+    /// 入力ノードと出力ノードだけを持つ、新しいGraphBuilderを作成
+    pub fn new() -> Self {
+        let mut graph = AudioGraph::new();
+        let input = NodeHandle::new(graph.add_node(Box::new(InputNode::new())));
+        let output = NodeHandle::new(graph.add_node(Box::new(OutputNode::new())));
+        Self {
+            graph,
+            input,
+            output,
+            tails: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// 入力ノード
+    pub fn input(&self) -> NodeHandle<InputNode> {
+        self.input
+    }
+
+    /// 出力ノード
+    pub fn output(&self) -> NodeHandle<OutputNode> {
+        self.output
+    }
+
+    /// ノードを接続せずに追加し、型付きのハンドルを返す
+    ///
+    /// 後から then で連鎖に組み込んだり、パラメーターを操作したりするために使います。
+    pub fn add<T: AudioGraphNode + 'static>(&mut self, node: T) -> NodeHandle<T> {
+        NodeHandle::new(self.graph.add_node(Box::new(node)))
+    }
+
+    /// 現在の末尾を入力ノードにする
+    pub fn from_input(mut self) -> Self {
+        self.tails = vec![self.input.id];
+        self
+    }
+
+    /// 現在の末尾に続けてノードを直列に追加し、最後のノードを新しい末尾にする
+    ///
+    /// # 引数
+    /// * `nodes` - 先頭から順に接続するノード
+    pub fn chain(mut self, nodes: impl IntoIterator<Item = Box<dyn AudioGraphNode>>) -> Self {
+        for node in nodes {
+            let id = self.graph.add_node(node);
+            self.connect_tails(id);
+            self.tails = vec![id];
+        }
+        self
+    }
+
+    /// 現在の末尾に続けて、追加済みのノードを接続し、新しい末尾にする
+    ///
+    /// # 引数
+    /// * `node` - add で追加したノードのハンドル
+    pub fn then<T>(mut self, node: NodeHandle<T>) -> Self {
+        self.connect_tails(node.id);
+        self.tails = vec![node.id];
+        self
+    }
+
+    /// 現在の末尾から分岐する並列の連鎖を追加し、各連鎖の最後のノードを新しい末尾にする
+    ///
+    /// 続けて chain などでノードを追加すると、各連鎖の出力がそのノードで合流します。
+    ///
+    /// # 引数
+    /// * `branches` - 分岐ごとの、先頭から順に接続するノード
+    pub fn parallel<B>(mut self, branches: impl IntoIterator<Item = B>) -> Self
+    where
+        B: IntoIterator<Item = Box<dyn AudioGraphNode>>,
+    {
+        let start = core::mem::take(&mut self.tails);
+        let mut ends = Vec::new();
+        for branch in branches {
+            self.tails = start.clone();
+            self = self.chain(branch);
+            ends.append(&mut self.tails);
+        }
+        self.tails = ends;
+        self
+    }
+
+    /// 現在の末尾を出力ノードに接続し、末尾を空にする
+    pub fn to_output(mut self) -> Self {
+        self.connect_tails(self.output.id);
+        self.tails.clear();
+        self
+    }
+
+    /// 音声グラフを完成させる
+    ///
+    /// # 戻り値
+    /// * 組み立てた音声グラフ。接続に失敗していた場合は `Err` で最初のエラーメッセージを返す
+    pub fn build(self) -> Result<BuiltGraph, String> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        Ok(BuiltGraph {
+            graph: self.graph,
+            input: self.input,
+            output: self.output,
+        })
+    }
+
+    /// 現在の末尾のノードを、指定したノードに接続する
+    fn connect_tails(&mut self, to_id: NodeId) {
+        for &from_id in &self.tails {
+            if let Err(e) = self.graph.add_edge(from_id, to_id) {
+                self.error.get_or_insert(e);
+            }
+        }
+    }
+}
+
+/// ノードを GraphBuilder の chain や parallel に渡せる形にする
+pub fn node<T: AudioGraphNode + 'static>(node: T) -> Box<dyn AudioGraphNode> {
+    Box::new(node)
+}
+
+/// 指定した周波数のサイン波を生成するノード
+pub fn sine(frequency: f32) -> Box<dyn AudioGraphNode> {
+    let mut sine = SineGenerator::new();
+    sine.set_frequency(frequency);
+    Box::new(sine)
+}
+
+/// 指定したゲイン（リニア）を掛けるノード
+pub fn gain(gain: f32) -> Box<dyn AudioGraphNode> {
+    let mut processor = GainProcessor::new();
+    processor.set_gain(gain);
+    Box::new(processor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_and_parallel_wiring() {
+        let mut builder = GraphBuilder::new();
+        let mix = builder.add(GainProcessor::new());
+        let built = builder
+            .from_input()
+            .parallel([vec![gain(0.5)], vec![sine(440.0), gain(0.25)]])
+            .then(mix)
+            .to_output()
+            .build()
+            .unwrap();
+
+        // 入力 → 2 つの分岐 → mix → 出力
+        let mut edges = built.graph.edges();
+        edges.sort();
+        let (input, output, mix) = (built.input.id(), built.output.id(), mix.id());
+        let (gain1, sine, gain2) = (
+            NodeId::from_raw(3),
+            NodeId::from_raw(4),
+            NodeId::from_raw(5),
+        );
+        let mut expected = vec![
+            (input, gain1),
+            (input, sine),
+            (sine, gain2),
+            (gain1, mix),
+            (gain2, mix),
+            (mix, output),
+        ];
+        expected.sort();
+        assert_eq!(edges, expected);
+
+        // 接続の失敗（循環）は build で報告される
+        let mut builder = GraphBuilder::new();
+        let node = builder.add(GainProcessor::new());
+        let result = builder.then(node).chain([gain(1.0)]).then(node).build();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! スイープ信号の応答からインパルス応答を求めます。
+//!
+//! TestSignal の指数サインスイープをノードやグラフに入力し、その出力をスイープで逆畳み込みして
+//! インパルス応答を得ます。フィルターなどの周波数特性をテストやサンプルで測定するために使います。
+
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraph, AudioGraphNode, NodeId},
+    dsp::fft,
+    nodes::{InputNode, OutputNode, TestSignal},
+    offline::OfflineRenderer,
+};
+
+/// 逆畳み込みの正則化の係数（励振信号のパワーの最大値に対する比）
+///
+/// スイープの帯域外などでパワーが小さい周波数を割ったときに、誤差が増幅されるのを防ぎます。
+const REGULARIZATION: f64 = 1e-6;
+
+/// 測定に使うチャンネル数（AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// 指数サインスイープによるインパルス応答の測定
+///
+/// スイープの後に tail 秒の無音を続けて入力し、残響やフィルターの減衰も含めて応答を記録します。
+/// 測定はオフラインで行うため、同じ設定からは常に同じ結果が得られます。
+pub struct SweepMeasurement {
+    /// サンプリングレート
+    sample_rate: f32,
+    /// スイープの開始周波数（Hz）
+    start_frequency: f32,
+    /// スイープの終了周波数（Hz）
+    end_frequency: f32,
+    /// スイープの長さ（秒）
+    duration: f32,
+    /// スイープの後に続ける無音の長さ（秒）
+    tail: f32,
+    /// スイープの振幅
+    amplitude: f32,
+}
+
+impl SweepMeasurement {
+    /// 新しいSweepMeasurementを作成（20Hz～20kHz、1 秒のスイープと 0.5 秒の無音、振幅 0.5）
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            start_frequency: 20.0,
+            end_frequency: 20000.0,
+            duration: 1.0,
+            tail: 0.5,
+            amplitude: 0.5,
+        }
+    }
+
+    /// スイープの開始周波数と終了周波数を設定（Hz）
+    pub fn set_frequencies(&mut self, start_frequency: f32, end_frequency: f32) {
+        self.start_frequency = start_frequency;
+        self.end_frequency = end_frequency;
+    }
+
+    /// スイープの長さを設定（秒）
+    pub fn set_duration(&mut self, duration: f32) {
+        self.duration = duration;
+    }
+
+    /// スイープの後に続ける無音の長さを設定（秒）
+    pub fn set_tail(&mut self, tail: f32) {
+        self.tail = tail.max(0.0);
+    }
+
+    /// スイープの振幅を設定
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        self.amplitude = amplitude;
+    }
+
+    /// 入力する励振信号（スイープと無音、モノラル）
+    pub fn excitation(&self) -> Vec<f32> {
+        let mut sweep = TestSignal::new();
+        sweep.set_frequencies(self.start_frequency, self.end_frequency);
+        sweep.set_duration(self.duration);
+        sweep.set_amplitude(self.amplitude);
+        sweep.prepare(self.sample_rate, 0);
+
+        let num_frames = ((self.duration + self.tail) * self.sample_rate).round() as usize;
+        let mut samples = vec![0.0; num_frames];
+        sweep.process(&mut AudioBuffer::new(1, num_frames, &mut samples));
+        samples
+    }
+
+    /// 励振信号に対する応答からインパルス応答を求める
+    ///
+    /// # 引数
+    /// * `response` - 励振信号を入力したときの出力（モノラル）
+    /// * `length` - 求めるインパルス応答のサンプル数
+    pub fn impulse_response(&self, response: &[f32], length: usize) -> Vec<f32> {
+        deconvolve(response, &self.excitation(), length)
+    }
+
+    /// グラフのインパルス応答を測定する
+    ///
+    /// 励振信号を全チャンネルに入力し、出力の最初のチャンネルから求めます。
+    ///
+    /// # 引数
+    /// * `graph` - 測定するグラフ
+    /// * `input_node_id` - 入力ノードの ID
+    /// * `output_node_id` - 出力ノードの ID
+    /// * `block_size` - 1 回の process で処理するフレーム数
+    /// * `length` - 求めるインパルス応答のサンプル数
+    pub fn measure_graph(
+        &self,
+        graph: &mut AudioGraph,
+        input_node_id: NodeId,
+        output_node_id: NodeId,
+        block_size: usize,
+        length: usize,
+    ) -> Vec<f32> {
+        let excitation = self.excitation();
+        let input: Vec<f32> = excitation
+            .iter()
+            .flat_map(|&sample| [sample; NUM_CHANNELS])
+            .collect();
+        let renderer = OfflineRenderer::new(self.sample_rate, block_size);
+        let output = renderer.render_with_input(graph, input_node_id, output_node_id, &input);
+        let response: Vec<f32> = output
+            .samples
+            .iter()
+            .step_by(NUM_CHANNELS)
+            .copied()
+            .collect();
+        deconvolve(&response, &excitation, length)
+    }
+
+    /// ノードのインパルス応答を測定する
+    ///
+    /// 入力ノード → `node` → 出力ノードのグラフを作り、measure_graph で測定します。
+    pub fn measure_node(
+        &self,
+        node: Box<dyn AudioGraphNode>,
+        block_size: usize,
+        length: usize,
+    ) -> Vec<f32> {
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let node_id = graph.add_node(node);
+        graph.add_edge(input_id, node_id).unwrap();
+        graph.add_edge(node_id, output_id).unwrap();
+        self.measure_graph(&mut graph, input_id, output_id, block_size, length)
+    }
+}
+
+/// 応答を励振信号で逆畳み込みし、インパルス応答を求める
+///
+/// 周波数領域で応答を励振信号で割ります（正則化付き）。
+/// FFT の長さを両者の長さの和以上にとるため、巡回による折り返しは起きません。
+///
+/// # 引数
+/// * `response` - 励振信号を入力したときの出力
+/// * `excitation` - 入力した励振信号
+/// * `length` - 求めるインパルス応答のサンプル数
+///
+/// # 実装時の注意
+/// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+pub fn deconvolve(response: &[f32], excitation: &[f32], length: usize) -> Vec<f32> {
+    let size = (response.len() + excitation.len())
+        .max(length)
+        .next_power_of_two();
+    let spectrum = |signal: &[f32]| {
+        let mut re = vec![0.0; size];
+        let mut im = vec![0.0; size];
+        for (dst, &src) in re.iter_mut().zip(signal) {
+            *dst = src as f64;
+        }
+        fft(&mut re, &mut im, false);
+        (re, im)
+    };
+    let (mut y_re, mut y_im) = spectrum(response);
+    let (x_re, x_im) = spectrum(excitation);
+
+    // H = Y X* / (|X|^2 + ε)
+    let max_power = x_re
+        .iter()
+        .zip(&x_im)
+        .fold(0.0f64, |max, (re, im)| max.max(re * re + im * im));
+    let epsilon = (max_power * REGULARIZATION).max(f64::MIN_POSITIVE);
+    for k in 0..size {
+        let power = x_re[k] * x_re[k] + x_im[k] * x_im[k] + epsilon;
+        let re = (y_re[k] * x_re[k] + y_im[k] * x_im[k]) / power;
+        let im = (y_im[k] * x_re[k] - y_re[k] * x_im[k]) / power;
+        y_re[k] = re;
+        y_im[k] = im;
+    }
+    fft(&mut y_re, &mut y_im, true);
+    y_re.iter().take(length).map(|&x| x as f32).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::GainProcessor;
+
+    #[test]
+    fn test_measure_gain_and_delay() {
+        let mut measurement = SweepMeasurement::new(48000.0);
+        measurement.set_duration(0.25);
+        measurement.set_tail(0.05);
+        // ナイキスト周波数までスイープしないと、帯域制限によるリンギングが残る
+        measurement.set_frequencies(10.0, 24000.0);
+
+        // ゲインだけのノードのインパルス応答は、先頭にゲインの値を持つインパルスになる
+        let mut gain = GainProcessor::new();
+        gain.set_gain(0.5);
+        let ir = measurement.measure_node(Box::new(gain), 128, 256);
+        assert_eq!(ir.len(), 256);
+        assert!((ir[0] - 0.5).abs() < 0.01);
+        assert!(ir[1..].iter().all(|s| s.abs() < 0.01));
+
+        // 遅延と減衰は、インパルスの位置と高さとして現れる
+        let excitation = measurement.excitation();
+        let mut response = vec![0.0; 10];
+        response.extend(excitation.iter().map(|s| -0.25 * s));
+        let ir = measurement.impulse_response(&response, 64);
+        let (peak_index, peak) = ir
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .unwrap();
+        assert_eq!(peak_index, 10);
+        assert!((peak + 0.25).abs() < 0.01);
+    }
+}
@@ -1,8 +1,43 @@
+//! DAW などのプロオーディオアプリケーションのための音声グラフと、ノードや DSP の実装です。
+//!
+//! `std` フィーチャー（デフォルトで有効）を無効にすると、no_std + alloc でビルドできます。
+//! スレッドやファイル IO を使うモジュール（background、garbage、audio_file など）は使えなくなりますが、
+//! 音声グラフとノードは組み込み機器のオーディオドライバーから処理できます。
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 // public modules
 pub mod audio_buffer;
+pub mod audio_buffer_utils;
+#[cfg(feature = "std")]
+pub mod audio_file;
 pub mod audio_graph;
+pub mod automation;
+#[cfg(feature = "std")]
+pub mod background;
+pub mod buffer_pool;
+pub mod diagnostics;
+pub mod dsp;
+pub mod event;
+pub mod frequency_response;
+pub mod garbage;
+pub mod graph_builder;
+pub mod impulse_response;
+pub mod midi;
+pub mod midi_file;
 pub mod nodes;
+pub mod offline;
+pub mod parameter;
+pub mod rt_log;
+pub mod sample;
+pub mod transport;
+pub mod tuning;
+pub mod wav;
 
 // private modules
-mod audio_buffer_utils;
+mod bounded_queue;
 mod directed_graph;
+mod prelude;
+mod slot_map;
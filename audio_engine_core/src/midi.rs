@@ -0,0 +1,152 @@
+//! ノートイベントなど、MIDI 関連の型を定義します。
+//!
+//! MPE（MIDI Polyphonic Expression）やプラグインのノートエクスプレッションのため、
+//! ノートごとのピッチベンド・プレッシャー・音色を NoteEvent::Expression で表します。
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+/// MPE のメンバーチャンネルのピッチベンドの範囲（半音）
+pub const MPE_PITCH_BEND_RANGE: f32 = 48.0;
+
+/// MPE で音色（ティンバー）に使うコントロールチェンジの番号
+pub const MPE_TIMBRE_CC: u8 = 74;
+
+/// ノートごとの表現の種類と値
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoteExpression {
+    /// ピッチベンド（半音単位、0 で変化なし）
+    PitchBend(f32),
+    /// プレッシャー（0～1）
+    Pressure(f32),
+    /// 音色（0～1、0.5 が中央）
+    Timbre(f32),
+}
+
+/// ノートごとの表現の現在値
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExpressionState {
+    /// ピッチベンド（半音単位）
+    pub pitch_bend: f32,
+    /// プレッシャー（0～1）
+    pub pressure: f32,
+    /// 音色（0～1）
+    pub timbre: f32,
+}
+
+impl ExpressionState {
+    /// 変化のない状態
+    pub const NEUTRAL: Self = Self {
+        pitch_bend: 0.0,
+        pressure: 0.0,
+        timbre: 0.5,
+    };
+
+    /// 表現の値を反映する
+    pub fn apply(&mut self, expression: NoteExpression) {
+        match expression {
+            NoteExpression::PitchBend(semitones) => self.pitch_bend = semitones,
+            NoteExpression::Pressure(pressure) => self.pressure = pressure,
+            NoteExpression::Timbre(timbre) => self.timbre = timbre,
+        }
+    }
+}
+
+/// ノードに送られるノートイベント
+///
+/// `timing` は処理ブロック先頭からのサンプルオフセットです。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoteEvent {
+    /// ノートオン
+    NoteOn {
+        /// ブロック先頭からのサンプルオフセット
+        timing: u32,
+        /// MIDI チャンネル（0～15）
+        channel: u8,
+        /// ノート番号（0～127）
+        note: u8,
+        /// ベロシティ（0～1）
+        velocity: f32,
+    },
+    /// ノートオフ
+    NoteOff {
+        /// ブロック先頭からのサンプルオフセット
+        timing: u32,
+        /// MIDI チャンネル（0～15）
+        channel: u8,
+        /// ノート番号（0～127）
+        note: u8,
+        /// リリースベロシティ（0～1）
+        velocity: f32,
+    },
+    /// ノートごと、またはチャンネルごとの表現（MPE、ノートエクスプレッション）
+    ///
+    /// MPE ではノートごとにチャンネルが割り当てられるため、チャンネル全体への表現は
+    /// そのチャンネルで発音中のノートに適用されます。
+    Expression {
+        /// ブロック先頭からのサンプルオフセット
+        timing: u32,
+        /// MIDI チャンネル（0～15）
+        channel: u8,
+        /// 対象のノート番号（`None` の場合はチャンネル全体）
+        note: Option<u8>,
+        /// 表現の種類と値
+        expression: NoteExpression,
+    },
+}
+
+impl NoteEvent {
+    /// ブロック先頭からのサンプルオフセットを取得する
+    pub fn timing(&self) -> u32 {
+        match self {
+            NoteEvent::NoteOn { timing, .. } => *timing,
+            NoteEvent::NoteOff { timing, .. } => *timing,
+            NoteEvent::Expression { timing, .. } => *timing,
+        }
+    }
+
+    /// ブロック先頭からのサンプルオフセットを置き換えたイベントを作成する
+    pub fn with_timing(mut self, new_timing: u32) -> Self {
+        match &mut self {
+            NoteEvent::NoteOn { timing, .. } => *timing = new_timing,
+            NoteEvent::NoteOff { timing, .. } => *timing = new_timing,
+            NoteEvent::Expression { timing, .. } => *timing = new_timing,
+        }
+        self
+    }
+}
+
+/// 14 ビットのピッチベンドの値を半音単位に変換する
+///
+/// # 引数
+/// * `value` - ピッチベンドの値（0～16383、8192 が中央）
+/// * `range` - 最大値での変化量（半音）
+pub fn pitch_bend_to_semitones(value: u16, range: f32) -> f32 {
+    (value.min(0x3fff) as f32 - 8192.0) / 8192.0 * range
+}
+
+/// ノート番号を周波数（Hz）に変換する（12平均律、A4 = 440Hz）
+///
+/// 小数のノート番号も受け付けるため、ピッチベンドなどの計算にも使えます。
+pub fn note_to_frequency(note: f32) -> f32 {
+    440.0 * 2.0_f32.powf((note - 69.0) / 12.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_to_frequency() {
+        assert!((note_to_frequency(69.0) - 440.0).abs() < 1e-3);
+        assert!((note_to_frequency(81.0) - 880.0).abs() < 1e-3);
+        assert!((note_to_frequency(60.0) - 261.6256).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_pitch_bend_to_semitones() {
+        assert_eq!(pitch_bend_to_semitones(8192, 2.0), 0.0);
+        assert_eq!(pitch_bend_to_semitones(0, MPE_PITCH_BEND_RANGE), -48.0);
+        assert!((pitch_bend_to_semitones(0x3fff, 2.0) - 2.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,329 @@
+//! スタンダード MIDI ファイル（SMF）を読み込みます。
+//!
+//! フォーマット 0 と 1 に対応し、全トラックのノートオン・ノートオフを時刻順にまとめます。
+//! 各イベントの位置は、テンポの変更（メタイベント 0x51）を反映した秒と、四分音符を 1 拍とする拍数の両方で持ちます。
+//! SMPTE 形式の時間単位には対応していません。
+
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::midi::NoteEvent;
+use crate::prelude::*;
+
+/// テンポの指定がない場合の四分音符の長さ（マイクロ秒、120 BPM）
+const DEFAULT_TEMPO: u32 = 500_000;
+
+/// 時刻付きのノートイベント
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MidiFileEvent {
+    /// 曲の先頭からの時刻（秒）
+    pub time: f64,
+    /// 曲の先頭からの位置（四分音符を 1 拍とする拍数）
+    pub beat: f64,
+    /// ノートイベント（タイミングは 0）
+    pub event: NoteEvent,
+}
+
+/// 読み込んだ MIDI ファイル
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiFile {
+    /// 時刻順のノートイベント
+    pub events: Vec<MidiFileEvent>,
+    /// 曲の長さ（秒）。最後のトラックの終わりまでの時間です。
+    pub duration: f64,
+    /// 曲の長さ（四分音符を 1 拍とする拍数）
+    pub duration_beats: f64,
+}
+
+/// トラックから読み出したイベント
+enum TrackEvent {
+    /// テンポの変更（四分音符の長さ、マイクロ秒）
+    Tempo(u32),
+    /// ノートイベント
+    Note(NoteEvent),
+    /// トラックの終わり
+    End,
+}
+
+/// バイト列の読み出し位置
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("MIDI ファイルが途中で終わっています")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// 可変長数値を読み出す
+    fn varint(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("可変長数値が長すぎます".to_string())
+    }
+}
+
+/// MIDI ファイルを読み込む
+///
+/// # 実装時の注意
+/// この関数はファイル IO とメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+#[cfg(feature = "std")]
+pub fn read_midi_file(path: impl AsRef<Path>) -> Result<MidiFile, String> {
+    let bytes = std::fs::read(path.as_ref()).map_err(|e| {
+        format!(
+            "MIDI ファイルを読み込めませんでした: {:?} ({})",
+            path.as_ref(),
+            e
+        )
+    })?;
+    parse_midi_file(&bytes)
+}
+
+/// メモリ上の MIDI ファイルを解析する
+///
+/// # 実装時の注意
+/// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+pub fn parse_midi_file(bytes: &[u8]) -> Result<MidiFile, String> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(4)? != b"MThd" {
+        return Err("MIDI ファイルではありません".to_string());
+    }
+    let header_len = reader.u32()? as usize;
+    let mut header = Reader::new(reader.take(header_len)?);
+    let format = header.u16()?;
+    let num_tracks = header.u16()?;
+    let division = header.u16()?;
+    if format > 1 {
+        return Err(format!("対応していないフォーマットです: {}", format));
+    }
+    if division & 0x8000 != 0 {
+        return Err("SMPTE 形式の時間単位には対応していません".to_string());
+    }
+    let ticks_per_quarter = division.max(1) as f64;
+
+    // 全トラックのイベントを（ティック, トラック番号, 順番）で並べる
+    let mut track_events: Vec<(u64, usize, TrackEvent)> = Vec::new();
+    for track_index in 0..num_tracks as usize {
+        // 未知のチャンクは読み飛ばす
+        let track = loop {
+            let id = reader.take(4)?;
+            let len = reader.u32()? as usize;
+            let data = reader.take(len)?;
+            if id == b"MTrk" {
+                break data;
+            }
+        };
+        parse_track(track, track_index, &mut track_events)?;
+    }
+    track_events.sort_by_key(|&(tick, track_index, _)| (tick, track_index));
+
+    // テンポの変更を反映してティックを秒に変換する
+    let mut events = Vec::new();
+    let mut tempo = DEFAULT_TEMPO;
+    let mut last_tick = 0;
+    let mut time = 0.0;
+    let mut duration = 0.0;
+    let mut duration_beats = 0.0;
+    for (tick, _, event) in track_events {
+        time += (tick - last_tick) as f64 * tempo as f64 / 1_000_000.0 / ticks_per_quarter;
+        last_tick = tick;
+        let beat = tick as f64 / ticks_per_quarter;
+        match event {
+            TrackEvent::Tempo(new_tempo) => tempo = new_tempo,
+            TrackEvent::Note(event) => events.push(MidiFileEvent { time, beat, event }),
+            TrackEvent::End => {}
+        }
+        duration = time;
+        duration_beats = beat;
+    }
+    Ok(MidiFile {
+        events,
+        duration,
+        duration_beats,
+    })
+}
+
+/// 1 トラック分のイベントを読み出す
+fn parse_track(
+    data: &[u8],
+    track_index: usize,
+    events: &mut Vec<(u64, usize, TrackEvent)>,
+) -> Result<(), String> {
+    let mut reader = Reader::new(data);
+    let mut tick = 0u64;
+    let mut running_status = None;
+    while !reader.is_empty() {
+        tick += reader.varint()? as u64;
+        let mut status = reader.u8()?;
+        // ランニングステータス: データバイトから始まる場合は前のステータスを使う
+        let first_data = if status < 0x80 {
+            let data = status;
+            status = running_status.ok_or("ステータスバイトがありません")?;
+            Some(data)
+        } else {
+            None
+        };
+        let event = match status {
+            0xff => {
+                let kind = reader.u8()?;
+                let len = reader.varint()? as usize;
+                let data = reader.take(len)?;
+                match (kind, data) {
+                    (0x51, [a, b, c]) => {
+                        Some(TrackEvent::Tempo(u32::from_be_bytes([0, *a, *b, *c])))
+                    }
+                    (0x2f, _) => Some(TrackEvent::End),
+                    _ => None,
+                }
+            }
+            0xf0 | 0xf7 => {
+                let len = reader.varint()? as usize;
+                reader.take(len)?;
+                None
+            }
+            0x80..=0xef => {
+                running_status = Some(status);
+                let data1 = match first_data {
+                    Some(data) => data,
+                    None => reader.u8()?,
+                };
+                // プログラムチェンジとチャンネルプレッシャーはデータバイトが 1 つ
+                let data2 = match status & 0xf0 {
+                    0xc0 | 0xd0 => 0,
+                    _ => reader.u8()?,
+                };
+                note_event(status, data1, data2).map(TrackEvent::Note)
+            }
+            _ => return Err(format!("不正なステータスバイトです: {:#x}", status)),
+        };
+        if let Some(event) = event {
+            let is_end = matches!(event, TrackEvent::End);
+            events.push((tick, track_index, event));
+            if is_end {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// チャンネルメッセージをノートイベントに変換する（ベロシティ 0 のノートオンはノートオフ）
+fn note_event(status: u8, note: u8, velocity: u8) -> Option<NoteEvent> {
+    let channel = status & 0x0f;
+    let note = note & 0x7f;
+    let velocity = (velocity & 0x7f) as f32 / 127.0;
+    match status & 0xf0 {
+        0x90 if velocity > 0.0 => Some(NoteEvent::NoteOn {
+            timing: 0,
+            channel,
+            note,
+            velocity,
+        }),
+        0x80 | 0x90 => Some(NoteEvent::NoteOff {
+            timing: 0,
+            channel,
+            note,
+            velocity,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// テスト用の MIDI ファイルを作成する
+    pub(crate) fn midi_file_bytes(ticks_per_quarter: u16, tracks: &[&[u8]]) -> Vec<u8> {
+        let mut bytes = b"MThd".to_vec();
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&(tracks.len().min(2) as u16 - 1).to_be_bytes());
+        bytes.extend_from_slice(&(tracks.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&ticks_per_quarter.to_be_bytes());
+        for track in tracks {
+            bytes.extend_from_slice(b"MTrk");
+            bytes.extend_from_slice(&(track.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(track);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parse_tempo_and_running_status() {
+        // トラック 0: 1 拍目で 60 BPM に変更
+        let tempo_track: &[u8] = &[
+            0x60, 0xff, 0x51, 0x03, 0x0f, 0x42,
+            0x40, // 96 ティック後に 1,000,000us/四分音符
+            0x00, 0xff, 0x2f, 0x00,
+        ];
+        // トラック 1: ランニングステータスとベロシティ 0 のノートオフ
+        let note_track: &[u8] = &[
+            0x00, 0x90, 60, 100, // 0 ティック: ノートオン
+            0x60, 60,
+            0, // 96 ティック: ノートオフ（ランニングステータス）
+            0x30, 0xc0, 5, // 144 ティック: プログラムチェンジ（無視）
+            0x30, 0x91, 64, 127, // 192 ティック: ノートオン
+            0x81, 0x40, 0xff, 0x2f, 0x00, // 384 ティック: トラックの終わり
+        ];
+        let file = parse_midi_file(&midi_file_bytes(96, &[tempo_track, note_track])).unwrap();
+
+        let times: Vec<f64> = file.events.iter().map(|e| e.time).collect();
+        // 最初の 1 拍は 120 BPM（0.5 秒）、以降は 60 BPM（1 秒/拍）
+        assert_eq!(times, vec![0.0, 0.5, 1.5]);
+        let beats: Vec<f64> = file.events.iter().map(|e| e.beat).collect();
+        assert_eq!(beats, vec![0.0, 1.0, 2.0]);
+        assert!(matches!(
+            file.events[1].event,
+            NoteEvent::NoteOff { note: 60, .. }
+        ));
+        assert!(matches!(
+            file.events[2].event,
+            NoteEvent::NoteOn {
+                channel: 1,
+                note: 64,
+                ..
+            }
+        ));
+        assert_eq!(file.duration, 3.5);
+        assert_eq!(file.duration_beats, 4.0);
+
+        assert!(parse_midi_file(b"RIFF").is_err());
+        assert!(parse_midi_file(&midi_file_bytes(96, &[&[0x00, 0x90, 60]])).is_err());
+    }
+}
@@ -1,19 +1,95 @@
+mod chord_generator;
+mod chorus;
+mod clip_detector;
+mod comb_filter;
+mod crossfader;
+mod double_precision_chain;
+mod envelope_follower;
+mod eq3;
+mod feedback_region;
+#[cfg(feature = "std")]
 mod feedback_sine_subgraph;
+mod flanger;
+mod fm_synth;
 mod gain_processor;
+mod granulator;
 mod impulse_generator;
 mod input_node;
+mod lfo_generator;
+mod loudness_meter;
+mod midi_file_player;
 mod output_node;
+mod phaser;
+mod pitch_detector;
+mod resampler;
+mod ring_modulator;
+mod sample_player;
 mod saw_generator;
+mod schroeder_allpass;
 mod sine_generator;
+mod spectral_freeze;
+mod state_variable_filter;
+#[cfg(feature = "std")]
 mod tap;
+#[cfg(feature = "std")]
 mod tap_test;
+mod test_signal;
+mod vocoder;
+mod wet_dry;
 
+pub use chord_generator::ChordGenerator;
+pub use chord_generator::ChordVoicing;
+pub use chord_generator::MAX_CHORD_NOTES;
+pub use chorus::Chorus;
+pub use clip_detector::ClipDetector;
+pub use clip_detector::ClipReadout;
+pub use comb_filter::CombFilter;
+pub use comb_filter::CombMode;
+pub use comb_filter::MAX_COMB_DELAY_MS;
+pub use crossfader::CROSSFADER_B_PORT;
+pub use crossfader::Crossfader;
+pub use double_precision_chain::DoublePrecisionChain;
+pub use envelope_follower::EnvelopeFollower;
+pub use eq3::Eq3;
+pub use feedback_region::FeedbackRegion;
+#[cfg(feature = "std")]
 pub use feedback_sine_subgraph::FeedbackSineSubgraph;
+pub use flanger::Flanger;
+pub use fm_synth::FmAlgorithm;
+pub use fm_synth::FmSynth;
 pub use gain_processor::GainProcessor;
+pub use granulator::GrainWindow;
+pub use granulator::Granulator;
 pub use impulse_generator::ImpulseGenerator;
 pub use input_node::InputNode;
+pub use lfo_generator::LfoGenerator;
+pub use loudness_meter::LoudnessMeter;
+pub use loudness_meter::LoudnessReadout;
+pub use midi_file_player::MidiFilePlayer;
 pub use output_node::OutputNode;
+pub use phaser::Phaser;
+pub use pitch_detector::PitchDetector;
+pub use pitch_detector::PitchReadout;
+pub use resampler::Resampler;
+pub use ring_modulator::CarrierSource;
+pub use ring_modulator::RING_MODULATOR_CARRIER_PORT;
+pub use ring_modulator::RingModulator;
+pub use sample_player::SamplePlayer;
 pub use saw_generator::SawGenerator;
+pub use schroeder_allpass::SchroederAllpass;
 pub use sine_generator::SineGenerator;
+pub use spectral_freeze::SpectralFreeze;
+pub use state_variable_filter::SVF_BANDPASS_PORT;
+pub use state_variable_filter::SVF_HIGHPASS_PORT;
+pub use state_variable_filter::SVF_NOTCH_PORT;
+pub use state_variable_filter::StateVariableFilter;
+#[cfg(feature = "std")]
 pub use tap::TapIn;
+#[cfg(feature = "std")]
 pub use tap::TapOut;
+pub use test_signal::TestSignal;
+pub use test_signal::TestSignalKind;
+pub use vocoder::MAX_VOCODER_BANDS;
+pub use vocoder::VOCODER_MODULATOR_PORT;
+pub use vocoder::Vocoder;
+pub use wet_dry::WetDry;
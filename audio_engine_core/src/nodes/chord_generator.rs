@@ -0,0 +1,328 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer, audio_graph::AudioGraphNode, midi::NoteEvent,
+    parameter::ParameterInfo,
+};
+
+/// 和音の構成音の最大数
+pub const MAX_CHORD_NOTES: usize = 8;
+
+/// ChordGenerator が公開するパラメーター
+const PARAMETERS: [ParameterInfo; 2] = [
+    ParameterInfo {
+        id: "voicing",
+        name: "Voicing",
+        min: 0.0,
+        max: 2.0,
+        default: 0.0,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "velocity_scale",
+        name: "Velocity Scale",
+        min: 0.0,
+        max: 1.0,
+        default: 1.0,
+        unit: "",
+    },
+];
+
+/// 和音のボイシング
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChordVoicing {
+    /// 音程をそのまま使う（密集配置）
+    Close,
+    /// 上から 2 番目の構成音を 1 オクターブ下げる
+    Drop2,
+    /// 偶数番目の構成音を 1 オクターブ上げる（開離配置）
+    Spread,
+}
+
+impl ChordVoicing {
+    /// パラメーターの値からボイシングに変換する
+    fn from_value(value: f32) -> Self {
+        match value.round() as i32 {
+            1 => ChordVoicing::Drop2,
+            2 => ChordVoicing::Spread,
+            _ => ChordVoicing::Close,
+        }
+    }
+
+    /// パラメーターの値
+    fn value(&self) -> f32 {
+        match self {
+            ChordVoicing::Close => 0.0,
+            ChordVoicing::Drop2 => 1.0,
+            ChordVoicing::Spread => 2.0,
+        }
+    }
+}
+
+/// 発音中の和音の構成音
+#[derive(Clone, Copy)]
+struct Chord {
+    notes: [u8; MAX_CHORD_NOTES],
+    len: usize,
+}
+
+impl Chord {
+    const EMPTY: Self = Self {
+        notes: [0; MAX_CHORD_NOTES],
+        len: 0,
+    };
+
+    fn notes(&self) -> &[u8] {
+        &self.notes[..self.len]
+    }
+}
+
+/// 受け取ったノートを和音に変換する MIDI エフェクトノード
+///
+/// AudioGraph::add_midi_route で MIDI の送り先（シンセなど）に接続し、ノートオンとノートオフを
+/// 設定した音程の和音に変換して送ります。ノートを指定した表現は和音の全ての構成音に送り、
+/// チャンネル全体の表現はそのまま送ります。オーディオの入力は変更せずに出力します。
+///
+/// 発音中に音程やボイシングを変更しても、ノートオフは発音時の和音に対して送ります。
+pub struct ChordGenerator {
+    /// ルートからの音程（半音）
+    intervals: [i8; MAX_CHORD_NOTES],
+    /// 構成音の数
+    num_intervals: usize,
+    /// ボイシング
+    voicing: ChordVoicing,
+    /// ルート以外の構成音のベロシティに掛ける係数（0～1）
+    velocity_scale: f32,
+    /// ルートのノート番号ごとの、発音中の和音
+    held: [Chord; 128],
+}
+
+impl ChordGenerator {
+    /// 新しいChordGeneratorを作成（長三和音）
+    pub fn new() -> Self {
+        let mut chord_generator = Self {
+            intervals: [0; MAX_CHORD_NOTES],
+            num_intervals: 0,
+            voicing: ChordVoicing::Close,
+            velocity_scale: 1.0,
+            held: [Chord::EMPTY; 128],
+        };
+        chord_generator.set_intervals(&[0, 4, 7]);
+        chord_generator
+    }
+
+    /// ルートからの音程（半音）を設定
+    ///
+    /// ルートを鳴らす場合は 0 を含めてください。MAX_CHORD_NOTES を超えた音程は無視します。
+    pub fn set_intervals(&mut self, intervals: &[i8]) {
+        self.num_intervals = intervals.len().min(MAX_CHORD_NOTES);
+        self.intervals[..self.num_intervals].copy_from_slice(&intervals[..self.num_intervals]);
+    }
+
+    /// ボイシングを設定
+    pub fn set_voicing(&mut self, voicing: ChordVoicing) {
+        self.voicing = voicing;
+    }
+
+    /// ルート以外の構成音のベロシティに掛ける係数を設定（0～1）
+    pub fn set_velocity_scale(&mut self, velocity_scale: f32) {
+        self.velocity_scale = velocity_scale.clamp(0.0, 1.0);
+    }
+
+    /// ルートのノート番号から、ボイシングを適用した和音を作成する
+    fn chord(&self, root: u8) -> Chord {
+        let mut offsets = [0i32; MAX_CHORD_NOTES];
+        let offsets = &mut offsets[..self.num_intervals];
+        for (offset, interval) in offsets.iter_mut().zip(self.intervals.iter()) {
+            *offset = *interval as i32;
+        }
+        match self.voicing {
+            ChordVoicing::Close => {}
+            ChordVoicing::Drop2 => {
+                // 音の高い順で 2 番目の構成音を下げる
+                if offsets.len() >= 2 {
+                    let mut sorted = [0i32; MAX_CHORD_NOTES];
+                    sorted[..offsets.len()].copy_from_slice(offsets);
+                    sorted[..offsets.len()].sort_unstable();
+                    let second = sorted[offsets.len() - 2];
+                    if let Some(offset) = offsets.iter_mut().find(|offset| **offset == second) {
+                        *offset -= 12;
+                    }
+                }
+            }
+            ChordVoicing::Spread => {
+                for offset in offsets.iter_mut().skip(1).step_by(2) {
+                    *offset += 12;
+                }
+            }
+        }
+
+        let mut chord = Chord::EMPTY;
+        for offset in offsets.iter() {
+            let note = root as i32 + offset;
+            // 範囲外と重複する構成音は鳴らさない
+            if (0..=127).contains(&note) && !chord.notes().contains(&(note as u8)) {
+                chord.notes[chord.len] = note as u8;
+                chord.len += 1;
+            }
+        }
+        chord
+    }
+}
+
+impl AudioGraphNode for ChordGenerator {
+    fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+
+    fn process(&mut self, _buffer: &mut AudioBuffer) {}
+
+    fn reset(&mut self) {
+        self.held = [Chord::EMPTY; 128];
+    }
+
+    fn process_note_event(&mut self, event: &NoteEvent, output: &mut dyn FnMut(NoteEvent)) {
+        match *event {
+            NoteEvent::NoteOn {
+                channel,
+                note,
+                velocity,
+                ..
+            } => {
+                let chord = self.chord(note & 0x7f);
+                self.held[note as usize & 0x7f] = chord;
+                for &chord_note in chord.notes() {
+                    output(NoteEvent::NoteOn {
+                        timing: event.timing(),
+                        channel,
+                        note: chord_note,
+                        velocity: if chord_note == note {
+                            velocity
+                        } else {
+                            velocity * self.velocity_scale
+                        },
+                    });
+                }
+            }
+            NoteEvent::NoteOff {
+                channel,
+                note,
+                velocity,
+                ..
+            } => {
+                let chord = core::mem::replace(&mut self.held[note as usize & 0x7f], Chord::EMPTY);
+                for &chord_note in chord.notes() {
+                    output(NoteEvent::NoteOff {
+                        timing: event.timing(),
+                        channel,
+                        note: chord_note,
+                        velocity,
+                    });
+                }
+            }
+            NoteEvent::Expression {
+                timing,
+                channel,
+                note: Some(note),
+                expression,
+            } => {
+                for &chord_note in self.held[note as usize & 0x7f].notes() {
+                    output(NoteEvent::Expression {
+                        timing,
+                        channel,
+                        note: Some(chord_note),
+                        expression,
+                    });
+                }
+            }
+            NoteEvent::Expression { note: None, .. } => output(*event),
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        match id {
+            "voicing" => self.set_voicing(ChordVoicing::from_value(value)),
+            "velocity_scale" => self.set_velocity_scale(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "voicing" => Some(self.voicing.value()),
+            "velocity_scale" => Some(self.velocity_scale),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note_on(note: u8) -> NoteEvent {
+        NoteEvent::NoteOn {
+            timing: 3,
+            channel: 0,
+            note,
+            velocity: 1.0,
+        }
+    }
+
+    fn notes(events: &[NoteEvent]) -> Vec<(u8, f32)> {
+        events
+            .iter()
+            .filter_map(|event| match *event {
+                NoteEvent::NoteOn { note, velocity, .. } => Some((note, velocity)),
+                NoteEvent::NoteOff { note, .. } => Some((note, 0.0)),
+                NoteEvent::Expression { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chord_generator_voicings() {
+        let mut chord_generator = ChordGenerator::new();
+        chord_generator.set_intervals(&[0, 4, 7, 11]);
+        chord_generator.set_velocity_scale(0.5);
+        let mut events = Vec::new();
+        chord_generator.process_note_event(&note_on(60), &mut |event| events.push(event));
+        assert_eq!(
+            notes(&events),
+            vec![(60, 1.0), (64, 0.5), (67, 0.5), (71, 0.5)]
+        );
+        assert!(events.iter().all(|event| event.timing() == 3));
+
+        // ボイシングを変えても、ノートオフは発音時の和音に送る
+        chord_generator.set_voicing(ChordVoicing::Drop2);
+        events.clear();
+        chord_generator.process_note_event(
+            &NoteEvent::NoteOff {
+                timing: 0,
+                channel: 0,
+                note: 60,
+                velocity: 0.0,
+            },
+            &mut |event| events.push(event),
+        );
+        assert_eq!(
+            notes(&events),
+            vec![(60, 0.0), (64, 0.0), (67, 0.0), (71, 0.0)]
+        );
+
+        // Drop2 は上から 2 番目（67）、Spread は偶数番目を 1 オクターブ動かす
+        events.clear();
+        chord_generator.process_note_event(&note_on(60), &mut |event| events.push(event));
+        assert_eq!(
+            notes(&events),
+            vec![(60, 1.0), (64, 0.5), (55, 0.5), (71, 0.5)]
+        );
+        chord_generator.set_voicing(ChordVoicing::Spread);
+        events.clear();
+        chord_generator.process_note_event(&note_on(120), &mut |event| events.push(event));
+        assert_eq!(notes(&events), vec![(120, 1.0), (127, 0.5)]);
+    }
+}
@@ -0,0 +1,163 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    dsp::{DelayLine, Lfo, SmoothedValue},
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// ボイスの最大数
+const MAX_VOICES: usize = 4;
+
+/// 最大遅延時間（ms）。基準遅延と変調幅の合計はこの値に制限されます。
+const MAX_DELAY_MS: f32 = 50.0;
+
+/// マルチボイスのコーラス
+///
+/// 位相をずらした LFO で遅延時間を変調した複数のボイスを原音に混ぜます。
+/// 右チャンネルは LFO の位相を 90 度ずらして広がりを出します。
+pub struct Chorus {
+    /// チャンネルごとのディレイライン
+    delay_lines: [DelayLine; NUM_CHANNELS],
+    /// チャンネル・ボイスごとの LFO
+    lfos: [[Lfo; MAX_VOICES]; NUM_CHANNELS],
+    /// ボイス数
+    num_voices: usize,
+    /// 基準遅延時間（ms）
+    delay_ms: f32,
+    /// 変調幅（ms）
+    depth_ms: f32,
+    /// ウェット信号の割合（0～1）
+    mix: SmoothedValue,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl Chorus {
+    /// 新しいChorusを作成
+    pub fn new() -> Self {
+        let mut chorus = Self {
+            delay_lines: [DelayLine::new(), DelayLine::new()],
+            lfos: core::array::from_fn(|_| core::array::from_fn(|_| Lfo::new())),
+            num_voices: 2,
+            delay_ms: 15.0,
+            depth_ms: 3.0,
+            mix: SmoothedValue::new(0.5),
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        };
+        chorus.set_rate(0.8);
+        chorus.update_lfo_phases();
+        chorus
+    }
+
+    /// ボイス数を設定（1～4）
+    pub fn set_num_voices(&mut self, num_voices: usize) {
+        self.num_voices = num_voices.clamp(1, MAX_VOICES);
+        self.update_lfo_phases();
+    }
+
+    /// LFO の周波数を設定（Hz）
+    pub fn set_rate(&mut self, rate: f32) {
+        for lfo in self.lfos.iter_mut().flatten() {
+            lfo.set_rate(rate);
+        }
+    }
+
+    /// 基準遅延時間を設定（ms）
+    pub fn set_delay_ms(&mut self, delay_ms: f32) {
+        self.delay_ms = delay_ms;
+    }
+
+    /// 変調幅を設定（ms）
+    pub fn set_depth_ms(&mut self, depth_ms: f32) {
+        self.depth_ms = depth_ms;
+    }
+
+    /// ウェット信号の割合を設定（0～1）
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+
+    /// ボイスごとに LFO の位相を均等にずらす
+    fn update_lfo_phases(&mut self) {
+        for (ch, lfos) in self.lfos.iter_mut().enumerate() {
+            for (voice, lfo) in lfos.iter_mut().enumerate() {
+                lfo.set_phase(voice as f32 / self.num_voices as f32 + ch as f32 * 0.25);
+            }
+        }
+    }
+}
+
+impl AudioGraphNode for Chorus {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        let max_delay_samples = ((MAX_DELAY_MS / 1000.0) * sample_rate).ceil() as usize;
+        for delay_line in self.delay_lines.iter_mut() {
+            delay_line.prepare(max_delay_samples);
+        }
+        for lfo in self.lfos.iter_mut().flatten() {
+            lfo.prepare(sample_rate);
+        }
+        self.mix.prepare(sample_rate, 20.0);
+    }
+
+    fn tail_samples(&self) -> usize {
+        // 最も遅れて読み出すボイスの遅延時間
+        let max_delay_ms = (self.delay_ms + self.depth_ms).min(MAX_DELAY_MS);
+        ((max_delay_ms / 1000.0) * self.sample_rate).ceil() as usize
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let num_channels = buffer.num_channels().min(NUM_CHANNELS);
+        let ms_to_samples = self.sample_rate / 1000.0;
+        for i in 0..buffer.num_frames() {
+            let mix = self.mix.next_value();
+            let frame = buffer.get_mut_frame(i);
+            for (ch, sample) in frame.iter_mut().enumerate().take(num_channels) {
+                let dry = *sample;
+                self.delay_lines[ch].write(dry);
+
+                let mut wet = 0.0;
+                for voice in 0..self.num_voices {
+                    let modulation = self.lfos[ch][voice].next_value();
+                    let delay_ms = (self.delay_ms + self.depth_ms * modulation).max(0.0);
+                    wet += self.delay_lines[ch].read(delay_ms * ms_to_samples);
+                }
+                wet /= self.num_voices as f32;
+
+                *sample = dry * (1.0 - mix) + wet * mix;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for delay_line in self.delay_lines.iter_mut() {
+            delay_line.reset();
+        }
+        for lfo in self.lfos.iter_mut().flatten() {
+            lfo.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chorus_with_zero_mix_is_dry() {
+        let mut chorus = Chorus::new();
+        chorus.set_mix(0.0);
+        chorus.prepare(1000.0, 4);
+
+        let mut vector: Vec<f32> = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
+        let expected = vector.clone();
+        let mut buffer = AudioBuffer::new(2, 4, vector.as_mut_slice());
+        chorus.process(&mut buffer);
+
+        assert_eq!(vector, expected);
+    }
+}
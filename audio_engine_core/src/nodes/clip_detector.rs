@@ -0,0 +1,190 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 1] = [ParameterInfo {
+    id: "threshold",
+    name: "Threshold",
+    min: 1.0,
+    max: 16.0,
+    default: 3.0,
+    unit: "samples",
+}];
+
+/// 検出したクリップをメインスレッドから読み出すためのハンドル
+///
+/// ClipDetector がオーディオスレッドで数えた値を、ロックせずに読み出せます。
+/// `ClipDetector::readout` で取得し、ノードをグラフに追加する前に保持してください。
+pub struct ClipReadout {
+    /// ±1.0 を超えたサンプルの数
+    clipped_samples: AtomicU64,
+    /// しきい値を超えて連続したクリップの回数
+    clips: AtomicU64,
+    /// まだ `take_new_clips` で取り出していないクリップの回数
+    new_clips: AtomicU64,
+}
+
+impl ClipReadout {
+    fn new() -> Self {
+        Self {
+            clipped_samples: AtomicU64::new(0),
+            clips: AtomicU64::new(0),
+            new_clips: AtomicU64::new(0),
+        }
+    }
+
+    /// ±1.0 を超えたサンプルの数（チャンネルごとに数えた合計）
+    pub fn clipped_samples(&self) -> u64 {
+        self.clipped_samples.load(Ordering::Relaxed)
+    }
+
+    /// しきい値を超えて連続したクリップの回数
+    pub fn clips(&self) -> u64 {
+        self.clips.load(Ordering::Relaxed)
+    }
+
+    /// 前回の呼び出しから今回までのクリップの回数を取り出す
+    ///
+    /// クリップのインジケーターを点灯するかどうかを、定期的にこの値で判定します。
+    pub fn take_new_clips(&self) -> u64 {
+        self.new_clips.swap(0, Ordering::Relaxed)
+    }
+
+    /// 数えた値をすべて 0 に戻す
+    pub fn clear(&self) {
+        self.clipped_samples.store(0, Ordering::Relaxed);
+        self.clips.store(0, Ordering::Relaxed);
+        self.new_clips.store(0, Ordering::Relaxed);
+    }
+}
+
+/// ±1.0 を超えたサンプルを数えるクリップディテクター
+///
+/// チャンネルごとに ±1.0 を超えたサンプルを数え、`threshold` サンプル以上連続して超えたときに 1 回のクリップとして
+/// 通知します。数えた値は `readout` のハンドルからメインスレッドで読み出せます。入力はそのまま出力します。
+pub struct ClipDetector {
+    /// 数えた値を公開するハンドル
+    readout: Arc<ClipReadout>,
+    /// クリップとして通知する、連続して超えたサンプル数
+    threshold: usize,
+    /// チャンネルごとの、連続して ±1.0 を超えているサンプル数
+    run_lengths: [usize; NUM_CHANNELS],
+}
+
+impl ClipDetector {
+    /// 新しいClipDetectorを作成
+    pub fn new() -> Self {
+        Self {
+            readout: Arc::new(ClipReadout::new()),
+            threshold: 3,
+            run_lengths: [0; NUM_CHANNELS],
+        }
+    }
+
+    /// 数えた値を読み出すハンドルを取得する
+    pub fn readout(&self) -> Arc<ClipReadout> {
+        self.readout.clone()
+    }
+
+    /// クリップとして通知する、連続して ±1.0 を超えたサンプル数を設定
+    pub fn set_threshold(&mut self, threshold: usize) {
+        self.threshold = threshold.clamp(1, PARAMETERS[0].max as usize);
+    }
+}
+
+impl AudioGraphNode for ClipDetector {
+    fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let mut clipped_samples = 0;
+        let mut clips = 0;
+        for frame in buffer.frames() {
+            for (run_length, sample) in self.run_lengths.iter_mut().zip(frame) {
+                if sample.abs() <= 1.0 {
+                    *run_length = 0;
+                    continue;
+                }
+                clipped_samples += 1;
+                *run_length += 1;
+                // 連続している間は 1 回だけ通知する
+                if *run_length == self.threshold {
+                    clips += 1;
+                }
+            }
+        }
+        if clipped_samples > 0 {
+            let readout = &self.readout;
+            readout
+                .clipped_samples
+                .fetch_add(clipped_samples, Ordering::Relaxed);
+            readout.clips.fetch_add(clips, Ordering::Relaxed);
+            readout.new_clips.fetch_add(clips, Ordering::Relaxed);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.run_lengths = [0; NUM_CHANNELS];
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        self.set_threshold(info.clamp(value).round() as usize);
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "threshold" => Some(self.threshold as f32),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip_detector_counts_runs() {
+        let mut detector = ClipDetector::new();
+        detector.set_threshold(2);
+        let readout = detector.readout();
+
+        // 左チャンネルは 1 サンプルだけ、右チャンネルは 3 サンプル連続して超える
+        let mut vector: Vec<f32> = vec![1.5, 1.2, 0.5, -1.1, 0.0, -1.3, 0.0, 0.5];
+        let expected = vector.clone();
+        detector.process(&mut AudioBuffer::new(2, 4, vector.as_mut_slice()));
+        assert_eq!(vector, expected);
+        assert_eq!(readout.clipped_samples(), 4);
+        assert_eq!(readout.clips(), 1);
+
+        // 右チャンネルの連続はブロックをまたいで数える
+        for _ in 0..2 {
+            let mut vector: Vec<f32> = vec![0.0, 2.0];
+            detector.process(&mut AudioBuffer::new(2, 1, vector.as_mut_slice()));
+        }
+        assert_eq!(readout.clips(), 2);
+        assert_eq!(readout.take_new_clips(), 2);
+        assert_eq!(readout.take_new_clips(), 0);
+
+        readout.clear();
+        assert_eq!(readout.clipped_samples(), 0);
+    }
+}
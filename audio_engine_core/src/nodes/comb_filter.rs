@@ -0,0 +1,242 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer, audio_graph::AudioGraphNode, dsp::DelayLine,
+    parameter::ParameterInfo,
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// 最大遅延時間（ms）
+pub const MAX_COMB_DELAY_MS: f32 = 1000.0;
+
+/// CombFilter が公開するパラメーター
+const PARAMETERS: [ParameterInfo; 4] = [
+    ParameterInfo {
+        id: "mode",
+        name: "Mode",
+        min: 0.0,
+        max: 1.0,
+        default: 1.0,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "delay_ms",
+        name: "Delay",
+        min: 0.1,
+        max: MAX_COMB_DELAY_MS,
+        default: 30.0,
+        unit: "ms",
+    },
+    ParameterInfo {
+        id: "coefficient",
+        name: "Coefficient",
+        min: -0.99,
+        max: 0.99,
+        default: 0.7,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "damping",
+        name: "Damping",
+        min: 0.0,
+        max: 1.0,
+        default: 0.0,
+        unit: "",
+    },
+];
+
+/// コムフィルターの構成
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CombMode {
+    /// 遅延した入力を加算する（y[n] = x[n] + g・x[n-D]）
+    Feedforward,
+    /// 遅延した出力を加算する（y[n] = x[n] + g・y[n-D]）
+    Feedback,
+}
+
+impl CombMode {
+    /// パラメーターの値から構成に変換する
+    fn from_value(value: f32) -> Self {
+        if value >= 0.5 {
+            CombMode::Feedback
+        } else {
+            CombMode::Feedforward
+        }
+    }
+
+    /// パラメーターの値
+    fn value(&self) -> f32 {
+        match self {
+            CombMode::Feedforward => 0.0,
+            CombMode::Feedback => 1.0,
+        }
+    }
+}
+
+/// 遅延時間と係数を設定できるコムフィルター
+///
+/// リバーブの構成要素として、SchroederAllpass と組み合わせてグラフ上で独自のリバーブを組めます。
+/// フィードバックの構成では、フィードバック経路に 1 次のローパス（damping）を入れて、
+/// 繰り返すたびに高域が減衰するようにできます（Freeverb のコムフィルターと同じ）。
+/// 遅延時間は小数サンプルに対応するため、パラメーターエッジで変調できます。
+pub struct CombFilter {
+    /// チャンネルごとのディレイライン
+    delay_lines: [DelayLine; NUM_CHANNELS],
+    /// チャンネルごとのフィードバック経路のローパスの状態
+    damping_state: [f32; NUM_CHANNELS],
+    /// 構成
+    mode: CombMode,
+    /// 遅延時間（ms）
+    delay_ms: f32,
+    /// 係数（-0.99～0.99）
+    coefficient: f32,
+    /// フィードバック経路のローパスの強さ（0～1、0 で減衰なし）
+    damping: f32,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl CombFilter {
+    /// 構成を指定して新しいCombFilterを作成
+    pub fn new(mode: CombMode) -> Self {
+        Self {
+            delay_lines: [DelayLine::new(), DelayLine::new()],
+            damping_state: [0.0; NUM_CHANNELS],
+            mode,
+            delay_ms: 30.0,
+            coefficient: 0.7,
+            damping: 0.0,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// 構成を設定
+    pub fn set_mode(&mut self, mode: CombMode) {
+        self.mode = mode;
+    }
+
+    /// 遅延時間を設定（ms）。0.1～MAX_COMB_DELAY_MS に制限されます。
+    pub fn set_delay_ms(&mut self, delay_ms: f32) {
+        self.delay_ms = delay_ms.clamp(0.1, MAX_COMB_DELAY_MS);
+    }
+
+    /// 係数を設定。発振を防ぐため -0.99～0.99 に制限されます。
+    pub fn set_coefficient(&mut self, coefficient: f32) {
+        self.coefficient = coefficient.clamp(-0.99, 0.99);
+    }
+
+    /// フィードバック経路のローパスの強さを設定（0～1）
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = damping.clamp(0.0, 1.0);
+    }
+}
+
+impl AudioGraphNode for CombFilter {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        let max_delay_samples = ((MAX_COMB_DELAY_MS / 1000.0) * sample_rate).ceil() as usize;
+        for delay_line in self.delay_lines.iter_mut() {
+            delay_line.prepare(max_delay_samples);
+        }
+        self.damping_state = [0.0; NUM_CHANNELS];
+    }
+
+    fn tail_samples(&self) -> usize {
+        let delay_samples = (self.delay_ms / 1000.0) * self.sample_rate;
+        let coefficient = self.coefficient.abs();
+        if self.mode == CombMode::Feedforward || coefficient == 0.0 {
+            return delay_samples.ceil() as usize;
+        }
+        // フィードバックで繰り返す信号が -60dB まで減衰する回数だけ、遅延時間を繰り返す
+        let repeats = (0.001f32.ln() / coefficient.ln()).ceil();
+        (delay_samples * (1.0 + repeats)).ceil() as usize
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let num_channels = buffer.num_channels().min(NUM_CHANNELS);
+        // 1 サンプル未満の遅延はフィードバックできないため、1 サンプル以上にする
+        let delay_samples = ((self.delay_ms / 1000.0) * self.sample_rate).max(1.0);
+        let coefficient = self.coefficient;
+        let damping = self.damping;
+        for frame in buffer.frames_mut() {
+            for (ch, sample) in frame.iter_mut().enumerate().take(num_channels) {
+                let delay_line = &mut self.delay_lines[ch];
+                let input = *sample;
+                match self.mode {
+                    CombMode::Feedforward => {
+                        delay_line.write(input);
+                        *sample = input + coefficient * delay_line.read(delay_samples);
+                    }
+                    CombMode::Feedback => {
+                        // 書き込む前に読み出すため、遅延は 1 サンプル短く指定する
+                        let delayed = delay_line.read(delay_samples - 1.0);
+                        let state = &mut self.damping_state[ch];
+                        *state = delayed * (1.0 - damping) + *state * damping;
+                        let output = input + coefficient * *state;
+                        delay_line.write(output);
+                        *sample = output;
+                    }
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for delay_line in self.delay_lines.iter_mut() {
+            delay_line.reset();
+        }
+        self.damping_state = [0.0; NUM_CHANNELS];
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        match id {
+            "mode" => self.set_mode(CombMode::from_value(value)),
+            "delay_ms" => self.set_delay_ms(value),
+            "coefficient" => self.set_coefficient(value),
+            "damping" => self.set_damping(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "mode" => Some(self.mode.value()),
+            "delay_ms" => Some(self.delay_ms),
+            "coefficient" => Some(self.coefficient),
+            "damping" => Some(self.damping),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1ch のインパルスを処理した出力
+    fn impulse_response(comb: &mut CombFilter) -> Vec<f32> {
+        comb.prepare(1000.0, 5);
+        let mut vector: Vec<f32> = vec![1.0, 0.0, 0.0, 0.0, 0.0];
+        comb.process(&mut AudioBuffer::new(1, 5, vector.as_mut_slice()));
+        vector
+    }
+
+    #[test]
+    fn test_comb_filter_modes() {
+        // 遅延 2 サンプル、係数 0.5
+        let mut comb = CombFilter::new(CombMode::Feedforward);
+        comb.set_delay_ms(2.0);
+        comb.set_coefficient(0.5);
+        assert_eq!(impulse_response(&mut comb), vec![1.0, 0.0, 0.5, 0.0, 0.0]);
+
+        comb.set_mode(CombMode::Feedback);
+        assert_eq!(impulse_response(&mut comb), vec![1.0, 0.0, 0.5, 0.0, 0.25]);
+    }
+}
@@ -0,0 +1,164 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, InputPorts},
+    dsp::SmoothedValue,
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// B 入力のポート番号（A 入力はメイン入力のポート 0）
+pub const CROSSFADER_B_PORT: usize = 1;
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 2] = [
+    ParameterInfo {
+        id: "position",
+        name: "Position",
+        min: 0.0,
+        max: 1.0,
+        default: 0.0,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "fade_time",
+        name: "Fade Time",
+        min: 0.0,
+        max: 10000.0,
+        default: 10.0,
+        unit: "ms",
+    },
+];
+
+/// 2つの入力（A/B）を等パワーカーブでクロスフェードするノード
+///
+/// A 入力はメイン入力（ポート 0）、B 入力はポート 1 です。
+/// position が 0 で A のみ、1 で B のみを出力します。
+/// position を変更すると、fade_time の時間をかけて自動的にフェードするため、
+/// グラフの分岐をクリックノイズなしに切り替えられます。
+pub struct Crossfader {
+    /// 現在の位置（0～1）
+    position: SmoothedValue,
+    /// フェード時間（ms）
+    fade_time_ms: f32,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl Crossfader {
+    /// 新しいCrossfaderを作成
+    pub fn new() -> Self {
+        Self {
+            position: SmoothedValue::new(0.0),
+            fade_time_ms: 10.0,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// フェード時間を設定（ms）
+    pub fn set_fade_time_ms(&mut self, fade_time_ms: f32) {
+        self.fade_time_ms = fade_time_ms.max(0.0);
+    }
+
+    /// フェード時間をかけて、指定した位置（0～1）までフェードする
+    pub fn fade_to(&mut self, position: f32) {
+        let num_samples = (self.fade_time_ms / 1000.0 * self.sample_rate) as usize;
+        self.position.ramp_to(position.clamp(0.0, 1.0), num_samples);
+    }
+
+    /// フェードせずに、位置（0～1）を即座に設定する
+    pub fn set_position(&mut self, position: f32) {
+        self.position
+            .set_current_and_target(position.clamp(0.0, 1.0));
+    }
+
+    /// フェード中かどうか
+    pub fn is_fading(&self) -> bool {
+        self.position.is_smoothing()
+    }
+}
+
+impl AudioGraphNode for Crossfader {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        self.process_with_inputs(buffer, &InputPorts::empty());
+    }
+
+    fn reset(&mut self) {
+        self.position.set_current_and_target(self.position.target());
+    }
+
+    fn num_input_ports(&self) -> usize {
+        2
+    }
+
+    fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, inputs: &InputPorts) {
+        let num_channels = buffer.num_channels();
+        let input_b = inputs.get(CROSSFADER_B_PORT);
+        for i in 0..buffer.num_frames() {
+            // 等パワーカーブ: gain_a^2 + gain_b^2 = 1
+            let angle = self.position.next_value() * core::f32::consts::FRAC_PI_2;
+            let gain_a = angle.cos();
+            let gain_b = angle.sin();
+            let frame = buffer.get_mut_frame(i);
+            for (ch, sample) in frame.iter_mut().enumerate() {
+                let b = input_b.map_or(0.0, |b| b[i * num_channels + ch]);
+                *sample = *sample * gain_a + b * gain_b;
+            }
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        match info.id {
+            "position" => self.fade_to(info.clamp(value)),
+            "fade_time" => self.set_fade_time_ms(info.clamp(value)),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "position" => Some(self.position.target()),
+            "fade_time" => Some(self.fade_time_ms),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossfader_equal_power_fade() {
+        let mut crossfader = Crossfader::new();
+        crossfader.prepare(1000.0, 4);
+        crossfader.set_fade_time_ms(2.0);
+
+        let input_b = vec![vec![1.0; 4]];
+        let connected = vec![true];
+        let inputs = InputPorts::new(&input_b, &connected, 1, 4);
+
+        // A から B へ 2 サンプルかけてフェード
+        crossfader.fade_to(1.0);
+        let mut vector: Vec<f32> = vec![1.0; 4];
+        let mut buffer = AudioBuffer::new(1, 4, vector.as_mut_slice());
+        crossfader.process_with_inputs(&mut buffer, &inputs);
+
+        // 中間点では両方が -3dB（cos(π/4) + sin(π/4)）
+        assert!((vector[0] - core::f32::consts::SQRT_2).abs() < 1e-5);
+        assert!((vector[1] - 1.0).abs() < 1e-5);
+        assert!(!crossfader.is_fading());
+    }
+}
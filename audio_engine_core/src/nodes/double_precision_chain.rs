@@ -0,0 +1,162 @@
+use crate::prelude::*;
+use crate::{
+    audio_buffer::{AudioBuffer, OwnedAudioBuffer},
+    audio_graph::{AudioGraphNode, NodeContext},
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// 複数のノードを直列につなぎ、倍精度（f64）で処理するノード
+///
+/// 入力を一度 f64 に変換し、各ノードの `process_f64` を順に呼び出してから f32 に戻します。
+/// ノード間の受け渡しで f32 への丸めが起きないため、長いフィードバックネットワークや
+/// 低いカットオフ周波数のフィルターなど、数値的に敏感なチェーンに使います。
+/// `process_f64` をオーバーライドしていないノードは、そのノードの中だけ f32 で処理されます。
+pub struct DoublePrecisionChain {
+    /// 直列に処理するノード
+    nodes: Vec<Box<dyn AudioGraphNode>>,
+    /// 倍精度の作業バッファ（インターリーブ）
+    buffer: OwnedAudioBuffer<f64>,
+    /// サンプリングレート
+    sample_rate: f32,
+    /// 最大バッファサイズ
+    max_num_samples: usize,
+}
+
+impl DoublePrecisionChain {
+    /// 新しいDoublePrecisionChainを作成
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            buffer: OwnedAudioBuffer::new(NUM_CHANNELS, 0),
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+            max_num_samples: 0,
+        }
+    }
+
+    /// チェーンの末尾にノードを追加する
+    ///
+    /// # 戻り値
+    /// * 追加したノードのチェーン内でのインデックス
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn push(&mut self, mut node: Box<dyn AudioGraphNode>) -> usize {
+        node.prepare(self.sample_rate, self.max_num_samples);
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// チェーン内のノードへの可変参照を取得する
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Box<dyn AudioGraphNode>> {
+        self.nodes.get_mut(index)
+    }
+
+    /// チェーン内のノード数
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// チェーンが空かどうか
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl AudioGraphNode for DoublePrecisionChain {
+    fn prepare(&mut self, sample_rate: f32, max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        self.max_num_samples = max_num_samples;
+        self.buffer = OwnedAudioBuffer::new(NUM_CHANNELS, max_num_samples);
+        for node in self.nodes.iter_mut() {
+            node.prepare(sample_rate, max_num_samples);
+        }
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let num_frames = buffer.num_frames();
+        if buffer.num_channels() != NUM_CHANNELS || num_frames > self.buffer.max_frames() {
+            // 作業バッファに収まらない場合は f32 のまま処理する
+            debug_assert!(
+                false,
+                "作業バッファが不足しています。num_frames: {}",
+                num_frames
+            );
+            for node in self.nodes.iter_mut() {
+                node.process(buffer);
+            }
+            return;
+        }
+
+        self.buffer.set_num_frames(num_frames);
+        for (dst, src) in self.buffer.as_mut_slice().iter_mut().zip(buffer.as_slice()) {
+            *dst = *src as f64;
+        }
+        let mut tmp_buffer = self.buffer.as_audio_buffer();
+        for node in self.nodes.iter_mut() {
+            node.process_f64(&mut tmp_buffer);
+        }
+        for (dst, src) in buffer.as_mut_slice().iter_mut().zip(self.buffer.as_slice()) {
+            *dst = *src as f32;
+        }
+    }
+
+    fn process_f64(&mut self, buffer: &mut AudioBuffer<f64>) {
+        for node in self.nodes.iter_mut() {
+            node.process_f64(buffer);
+        }
+    }
+
+    fn reset(&mut self) {
+        for node in self.nodes.iter_mut() {
+            node.reset();
+        }
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.nodes.iter().map(|node| node.latency_samples()).sum()
+    }
+
+    fn tail_samples(&self) -> usize {
+        self.nodes.iter().map(|node| node.tail_samples()).sum()
+    }
+
+    fn on_added(&mut self, context: &NodeContext) {
+        for node in self.nodes.iter_mut() {
+            node.on_added(context);
+        }
+    }
+
+    fn on_removed(&mut self, context: &NodeContext) {
+        for node in self.nodes.iter_mut() {
+            node.on_removed(context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::GainProcessor;
+
+    #[test]
+    fn test_double_precision_chain_keeps_precision() {
+        // f32 では 1e-8 倍して 1e8 倍すると丸め誤差が残るが、f64 ではほぼ元に戻る
+        let mut chain = DoublePrecisionChain::new();
+        let mut attenuate = GainProcessor::new();
+        attenuate.set_gain(1e-8);
+        let mut amplify = GainProcessor::new();
+        amplify.set_gain(1e8);
+        chain.push(Box::new(attenuate));
+        chain.push(Box::new(amplify));
+        chain.prepare(44100.0, 2);
+
+        let mut vector: Vec<f32> = vec![0.1, 0.2, 0.3, 0.4];
+        let expected = vector.clone();
+        let mut buffer = AudioBuffer::new(2, 2, vector.as_mut_slice());
+        chain.process(&mut buffer);
+
+        assert_eq!(vector, expected);
+    }
+}
@@ -0,0 +1,257 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, ProcessRate},
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 2] = [
+    ParameterInfo {
+        id: "attack",
+        name: "Attack",
+        min: 0.0,
+        max: 1000.0,
+        default: 10.0,
+        unit: "ms",
+    },
+    ParameterInfo {
+        id: "release",
+        name: "Release",
+        min: 0.0,
+        max: 5000.0,
+        default: 100.0,
+        unit: "ms",
+    },
+];
+
+/// 入力の振幅に追従するエンベロープフォロワー
+///
+/// 全チャンネルの絶対値の最大値に、アタック・リリースを持つ1次フィルターで追従します。
+/// エンベロープは全チャンネルに出力されるほか、モジュレーションソースとして
+/// `AudioGraph::add_modulation` で他のノードのパラメーターに接続できます。
+/// `set_process_rate` でコントロールレートにすると、区間ごとのピークに追従して CPU の負荷を減らせます。
+pub struct EnvelopeFollower {
+    /// アタック時間（ms）
+    attack_ms: f32,
+    /// リリース時間（ms）
+    release_ms: f32,
+    /// アタックの係数
+    attack_coefficient: f32,
+    /// リリースの係数
+    release_coefficient: f32,
+    /// エンベロープの現在値
+    envelope: f32,
+    /// サンプリングレート
+    sample_rate: f32,
+    /// 処理する頻度
+    process_rate: ProcessRate,
+}
+
+impl EnvelopeFollower {
+    /// 新しいEnvelopeFollowerを作成
+    pub fn new() -> Self {
+        let mut follower = Self {
+            attack_ms: 10.0,
+            release_ms: 100.0,
+            attack_coefficient: 0.0,
+            release_coefficient: 0.0,
+            envelope: 0.0,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+            process_rate: ProcessRate::Audio,
+        };
+        follower.update_coefficients();
+        follower
+    }
+
+    /// アタック時間を設定（ms）
+    pub fn set_attack_ms(&mut self, attack_ms: f32) {
+        self.attack_ms = attack_ms.max(0.0);
+        self.update_coefficients();
+    }
+
+    /// リリース時間を設定（ms）
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.max(0.0);
+        self.update_coefficients();
+    }
+
+    /// 処理する頻度を設定
+    pub fn set_process_rate(&mut self, process_rate: ProcessRate) {
+        self.process_rate = process_rate;
+    }
+
+    /// エンベロープの現在値を取得する
+    pub fn envelope(&self) -> f32 {
+        self.envelope
+    }
+
+    fn update_coefficients(&mut self) {
+        self.attack_coefficient = time_to_coefficient(self.attack_ms, self.sample_rate);
+        self.release_coefficient = time_to_coefficient(self.release_ms, self.sample_rate);
+    }
+}
+
+/// 時定数（ms）を1次フィルターの係数に変換する
+pub(super) fn time_to_coefficient(ms: f32, sample_rate: f32) -> f32 {
+    let samples = ms / 1000.0 * sample_rate;
+    if samples <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / samples).exp()
+    }
+}
+
+impl AudioGraphNode for EnvelopeFollower {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        for i in 0..buffer.num_frames() {
+            let frame = buffer.get_mut_frame(i);
+            let level = frame.iter().fold(0.0_f32, |acc, s| acc.max(s.abs()));
+            let coefficient = if level > self.envelope {
+                self.attack_coefficient
+            } else {
+                self.release_coefficient
+            };
+            self.envelope = level + (self.envelope - level) * coefficient;
+            frame.fill(self.envelope);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+
+    fn process_rate(&self) -> ProcessRate {
+        self.process_rate
+    }
+
+    fn process_control(&mut self, input: &AudioBuffer) -> f32 {
+        // 区間のピークに、区間の長さ分まとめて追従する
+        let level = input
+            .as_slice()
+            .iter()
+            .fold(0.0_f32, |acc, s| acc.max(s.abs()));
+        let coefficient = if level > self.envelope {
+            self.attack_coefficient
+        } else {
+            self.release_coefficient
+        };
+        self.envelope =
+            level + (self.envelope - level) * coefficient.powi(input.num_frames() as i32);
+        self.envelope
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        match info.id {
+            "attack" => self.set_attack_ms(info.clamp(value)),
+            "release" => self.set_release_ms(info.clamp(value)),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "attack" => Some(self.attack_ms),
+            "release" => Some(self.release_ms),
+            _ => None,
+        }
+    }
+
+    fn modulation_output(&self) -> Option<f32> {
+        Some(self.envelope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio_graph::AudioGraph;
+    use crate::nodes::{Eq3, InputNode, OutputNode};
+
+    use super::*;
+
+    // 一定値を出力するテスト用のノード
+    struct ConstantNode {
+        value: f32,
+    }
+
+    impl AudioGraphNode for ConstantNode {
+        fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+
+        fn process(&mut self, buffer: &mut AudioBuffer) {
+            buffer.as_mut_slice().fill(self.value);
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn test_envelope_follower_tracks_level() {
+        let mut follower = EnvelopeFollower::new();
+        follower.set_attack_ms(0.0);
+        follower.set_release_ms(0.0);
+        follower.prepare(1000.0, 4);
+
+        let mut vector: Vec<f32> = vec![0.5, -0.8, -0.25, 0.1, 0.0, 0.0, 1.0, 0.0];
+        let mut buffer = AudioBuffer::new(2, 4, vector.as_mut_slice());
+        follower.process(&mut buffer);
+
+        assert_eq!(vector, vec![0.8, 0.8, 0.25, 0.25, 0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(follower.modulation_output(), Some(1.0));
+    }
+
+    #[test]
+    fn test_envelope_follower_modulates_parameter() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let mut follower = EnvelopeFollower::new();
+        follower.set_attack_ms(0.0);
+        let follower_id = graph.add_node(Box::new(follower));
+        let eq_id = graph.add_node(Box::new(Eq3::new()));
+        let constant_id = graph.add_node(Box::new(ConstantNode { value: 0.5 }));
+
+        assert!(graph.add_edge(constant_id, follower_id).is_ok());
+        assert!(graph.add_edge(follower_id, eq_id).is_ok());
+        assert!(graph.add_edge(eq_id, output_node_id).is_ok());
+        assert!(
+            graph
+                .add_modulation(follower_id, eq_id, "mid_gain", 10.0)
+                .is_ok()
+        );
+        assert!(
+            graph
+                .add_modulation(eq_id, follower_id, "attack", 1.0)
+                .is_err()
+        );
+        graph.prepare(1000.0, 4);
+
+        let mut buffer: Vec<f32> = vec![0.0; 8];
+        let mut audio_buffer = AudioBuffer::new(2, 4, &mut buffer);
+        graph.process(&mut audio_buffer, input_node_id, output_node_id);
+
+        // エンベロープ 0.5 × 変調量 10.0 がゲインに加算される
+        assert_eq!(graph.get_parameter(eq_id, "mid_gain"), Some(5.0));
+
+        // 変調中に設定した値は上書きされず、変調前の値になる
+        graph.set_parameter(eq_id, "mid_gain", 2.0).unwrap();
+        graph.process(&mut audio_buffer, input_node_id, output_node_id);
+        assert_eq!(graph.get_parameter(eq_id, "mid_gain"), Some(7.0));
+
+        assert!(graph.remove_modulation(follower_id, eq_id, "mid_gain"));
+        assert_eq!(graph.get_parameter(eq_id, "mid_gain"), Some(2.0));
+    }
+}
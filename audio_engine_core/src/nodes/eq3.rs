@@ -0,0 +1,298 @@
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, InputPorts},
+    dsp::{Biquad, BiquadCoefficients},
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// パラメーターポートで周波数を変化させる場合に、係数を再計算する間隔（サンプル数）
+const COEFFICIENT_INTERVAL: usize = 16;
+
+/// バンドごとの周波数のパラメーター ID（パラメーターポートの番号は順に 1, 2, 3）
+const FREQUENCY_PORTS: [&str; 3] = ["low_freq", "mid_freq", "high_freq"];
+
+/// バンドの種類
+#[derive(Clone, Copy)]
+enum BandKind {
+    LowShelf,
+    Peaking,
+    HighShelf,
+}
+
+/// イコライザーの1バンド
+struct Band {
+    kind: BandKind,
+    enabled: bool,
+    gain_db: f32,
+    frequency: f32,
+    q: f32,
+    /// チャンネルごとのフィルター
+    filters: [Biquad; NUM_CHANNELS],
+}
+
+impl Band {
+    fn new(kind: BandKind, frequency: f32) -> Self {
+        Self {
+            kind,
+            enabled: true,
+            gain_db: 0.0,
+            frequency,
+            q: 0.707,
+            filters: [Biquad::new(), Biquad::new()],
+        }
+    }
+
+    fn update_coefficients(&mut self, sample_rate: f32) {
+        self.update_coefficients_with_frequency(sample_rate, self.frequency);
+    }
+
+    /// パラメーターの周波数の代わりに、指定した周波数で係数を計算する
+    fn update_coefficients_with_frequency(&mut self, sample_rate: f32, frequency: f32) {
+        let coefficients = match self.kind {
+            BandKind::LowShelf => {
+                BiquadCoefficients::low_shelf(sample_rate, frequency, self.q, self.gain_db)
+            }
+            BandKind::Peaking => {
+                BiquadCoefficients::peaking(sample_rate, frequency, self.q, self.gain_db)
+            }
+            BandKind::HighShelf => {
+                BiquadCoefficients::high_shelf(sample_rate, frequency, self.q, self.gain_db)
+            }
+        };
+        for filter in self.filters.iter_mut() {
+            filter.set_coefficients(coefficients);
+        }
+    }
+}
+
+/// 公開するパラメーターの一覧（バンドごとに enabled, gain, freq, q の順）
+const PARAMETERS: [ParameterInfo; 12] = [
+    param("low_enabled", "Low Enabled", 0.0, 1.0, 1.0, ""),
+    param("low_gain", "Low Gain", -24.0, 24.0, 0.0, "dB"),
+    param("low_freq", "Low Frequency", 20.0, 1000.0, 200.0, "Hz"),
+    param("low_q", "Low Q", 0.1, 10.0, 0.707, ""),
+    param("mid_enabled", "Mid Enabled", 0.0, 1.0, 1.0, ""),
+    param("mid_gain", "Mid Gain", -24.0, 24.0, 0.0, "dB"),
+    param("mid_freq", "Mid Frequency", 100.0, 10000.0, 1000.0, "Hz"),
+    param("mid_q", "Mid Q", 0.1, 10.0, 0.707, ""),
+    param("high_enabled", "High Enabled", 0.0, 1.0, 1.0, ""),
+    param("high_gain", "High Gain", -24.0, 24.0, 0.0, "dB"),
+    param("high_freq", "High Frequency", 1000.0, 20000.0, 5000.0, "Hz"),
+    param("high_q", "High Q", 0.1, 10.0, 0.707, ""),
+];
+
+const fn param(
+    id: &'static str,
+    name: &'static str,
+    min: f32,
+    max: f32,
+    default: f32,
+    unit: &'static str,
+) -> ParameterInfo {
+    ParameterInfo {
+        id,
+        name,
+        min,
+        max,
+        default,
+        unit,
+    }
+}
+
+/// 3バンドイコライザー（ローシェルフ、ピーキング、ハイシェルフ）
+///
+/// 各バンドは双2次フィルターで実装され、有効/無効とゲイン・周波数・Q をパラメーターとして公開します。
+/// パラメーターの ID は `{low,mid,high}_{enabled,gain,freq,q}` です。
+/// 周波数はパラメーターポートで変化させられ、COEFFICIENT_INTERVAL サンプルごとに係数を再計算します。
+pub struct Eq3 {
+    /// バンド（低域、中域、高域の順）
+    bands: [Band; 3],
+    /// 係数の再計算が必要かどうか
+    dirty: bool,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl Eq3 {
+    /// 新しいEq3を作成
+    pub fn new() -> Self {
+        Self {
+            bands: [
+                Band::new(BandKind::LowShelf, 200.0),
+                Band::new(BandKind::Peaking, 1000.0),
+                Band::new(BandKind::HighShelf, 5000.0),
+            ],
+            dirty: true,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// パラメーター ID をバンドのインデックスとフィールド名に分解する
+    fn split_id(id: &str) -> Option<(usize, &str)> {
+        let (band, field) = id.split_once('_')?;
+        let band_idx = match band {
+            "low" => 0,
+            "mid" => 1,
+            "high" => 2,
+            _ => return None,
+        };
+        Some((band_idx, field))
+    }
+}
+
+impl AudioGraphNode for Eq3 {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        self.dirty = true;
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        self.process_with_inputs(buffer, &InputPorts::empty());
+    }
+
+    fn num_input_ports(&self) -> usize {
+        FREQUENCY_PORTS.len() + 1
+    }
+
+    fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, inputs: &InputPorts) {
+        let modulated = (1..=FREQUENCY_PORTS.len()).any(|port| inputs.is_connected(port));
+        if self.dirty && !modulated {
+            for band in self.bands.iter_mut() {
+                band.update_coefficients(self.sample_rate);
+            }
+            self.dirty = false;
+        }
+
+        let num_channels = buffer.num_channels().min(NUM_CHANNELS);
+        for i in 0..buffer.num_frames() {
+            // パラメーターポートの信号を周波数に加算して、係数を再計算する
+            if modulated && i % COEFFICIENT_INTERVAL == 0 {
+                for (band_idx, band) in self.bands.iter_mut().enumerate() {
+                    let port = band_idx + 1;
+                    let signal = inputs.get(port).filter(|_| inputs.is_connected(port));
+                    let offset = signal.map_or(0.0, |signal| signal[i * inputs.num_channels()]);
+                    let frequency = PARAMETERS[band_idx * 4 + 2].clamp(band.frequency + offset);
+                    band.update_coefficients_with_frequency(self.sample_rate, frequency);
+                }
+            }
+            let frame = buffer.get_mut_frame(i);
+            for (ch, sample) in frame.iter_mut().enumerate().take(num_channels) {
+                let mut x = *sample;
+                for band in self.bands.iter_mut() {
+                    if band.enabled {
+                        x = band.filters[ch].process(x);
+                    }
+                }
+                *sample = x;
+            }
+        }
+        // 接続を解除した後は、パラメーターの周波数で係数を計算し直す
+        if modulated {
+            self.dirty = true;
+        }
+    }
+
+    fn parameter_port(&self, parameter_id: &str) -> Option<usize> {
+        FREQUENCY_PORTS
+            .iter()
+            .position(|id| *id == parameter_id)
+            .map(|idx| idx + 1)
+    }
+
+    fn reset(&mut self) {
+        for band in self.bands.iter_mut() {
+            for filter in band.filters.iter_mut() {
+                filter.reset();
+            }
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        let value = info.clamp(value);
+        let Some((band_idx, field)) = Self::split_id(id) else {
+            return false;
+        };
+        let band = &mut self.bands[band_idx];
+        match field {
+            "enabled" => band.enabled = value >= 0.5,
+            "gain" => band.gain_db = value,
+            "freq" => band.frequency = value,
+            "q" => band.q = value,
+            _ => return false,
+        }
+        self.dirty = true;
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        let (band_idx, field) = Self::split_id(id)?;
+        let band = &self.bands[band_idx];
+        match field {
+            "enabled" => Some(if band.enabled { 1.0 } else { 0.0 }),
+            "gain" => Some(band.gain_db),
+            "freq" => Some(band.frequency),
+            "q" => Some(band.q),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eq3_flat_is_transparent() {
+        let mut eq = Eq3::new();
+        eq.prepare(48000.0, 4);
+
+        let mut vector: Vec<f32> = vec![0.1, -0.2, 0.3, -0.4, 0.5, -0.6, 0.7, -0.8];
+        let expected = vector.clone();
+        let mut buffer = AudioBuffer::new(2, 4, vector.as_mut_slice());
+        eq.process(&mut buffer);
+
+        for (a, b) in vector.iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_eq3_parameters() {
+        let mut eq = Eq3::new();
+        assert_eq!(eq.parameters().len(), 12);
+
+        assert!(eq.set_parameter("mid_gain", 6.0));
+        assert_eq!(eq.get_parameter("mid_gain"), Some(6.0));
+
+        // 範囲外の値は制限される
+        assert!(eq.set_parameter("high_freq", 100000.0));
+        assert_eq!(eq.get_parameter("high_freq"), Some(20000.0));
+
+        assert!(!eq.set_parameter("unknown", 1.0));
+        assert_eq!(eq.get_parameter("unknown"), None);
+    }
+
+    #[test]
+    fn test_eq3_band_response() {
+        let mut eq = Eq3::new();
+        eq.set_parameter("low_gain", -6.0);
+        eq.set_parameter("mid_gain", 12.0);
+        eq.set_parameter("mid_q", 4.0);
+        eq.set_parameter("high_gain", 6.0);
+
+        // シェルフは帯域の外側でゲインの値に近づき、ピーキングは中心周波数でゲインの値になる
+        let expected = [(20.0, -6.0), (1000.0, 12.0), (20000.0, 6.0)];
+        crate::frequency_response::check_node_response(Box::new(eq), 48000.0, &expected, 0.5)
+            .unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraph, AudioGraphNode, NodeId},
+    transport::Transport,
+    tuning::Tuning,
+};
+
+use super::{InputNode, OutputNode};
+
+/// 内部のグラフをバッファサイズ 1 で処理するフィードバックリージョン
+///
+/// 内部のグラフのフィードバックのエッジ（AudioGraph::add_feedback_edge）は 1 ブロック遅れて入力されるため、
+/// リージョンの中では 1 サンプルの遅延でフィードバックします。FeedbackSineSubgraph のようなノードを手書きせずに、
+/// 短い遅延のフィードバックを含むノードの組を、外のグラフのブロック処理と組み合わせて使えます。
+///
+/// 通常は AudioGraph::create_feedback_region で既存のノードをまとめて作成します。直接作成する場合は、graph_mut で
+/// 内部のグラフにノードを追加し、input_node_id から output_node_id までを接続してください。
+///
+/// 内部のノードのパラメーターは、`"{元のノードIDの整数の表現}/{パラメーターID}"` の ID で設定できます。
+pub struct FeedbackRegion {
+    /// 内部のグラフ
+    graph: AudioGraph,
+    /// 内部のグラフの入力ノード
+    input_id: NodeId,
+    /// 内部のグラフの出力ノード
+    output_id: NodeId,
+    /// 元のグラフでのノードIDと、内部のグラフでのノードIDの対応
+    node_ids: Vec<(NodeId, NodeId)>,
+}
+
+impl FeedbackRegion {
+    /// 入力ノードと出力ノードだけを持つ新しいFeedbackRegionを作成
+    ///
+    /// # 引数
+    /// * `num_channels` - 内部のグラフが処理するチャンネル数（外のグラフと同じにする）
+    pub fn new(num_channels: usize) -> Self {
+        let mut graph = AudioGraph::new();
+        let _ = graph.set_num_channels(num_channels);
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        Self {
+            graph,
+            input_id,
+            output_id,
+            node_ids: Vec::new(),
+        }
+    }
+
+    /// 内部のグラフの入力ノード
+    pub fn input_node_id(&self) -> NodeId {
+        self.input_id
+    }
+
+    /// 内部のグラフの出力ノード
+    pub fn output_node_id(&self) -> NodeId {
+        self.output_id
+    }
+
+    /// 内部のグラフ
+    pub fn graph(&self) -> &AudioGraph {
+        &self.graph
+    }
+
+    /// 内部のグラフ（変更用）
+    pub fn graph_mut(&mut self) -> &mut AudioGraph {
+        &mut self.graph
+    }
+
+    /// 元のグラフでのノードIDを、内部のグラフでのノードIDに対応付ける
+    ///
+    /// パラメーターの ID で元のノードIDを使えるようにします。
+    pub fn map_node_id(&mut self, original_id: NodeId, inner_id: NodeId) {
+        self.node_ids.retain(|&(id, _)| id != original_id);
+        self.node_ids.push((original_id, inner_id));
+    }
+
+    /// 元のグラフでのノードIDに対応する、内部のグラフでのノードID
+    pub fn inner_node_id(&self, original_id: NodeId) -> Option<NodeId> {
+        self.node_ids
+            .iter()
+            .find(|&&(id, _)| id == original_id)
+            .map(|&(_, inner_id)| inner_id)
+    }
+
+    /// パラメーターの ID を、内部のグラフでのノードIDとパラメーターIDに分ける
+    fn split_parameter_id<'a>(&self, id: &'a str) -> Option<(NodeId, &'a str)> {
+        let (node_id, parameter_id) = id.split_once('/')?;
+        let original_id = NodeId::from_raw(node_id.parse().ok()?);
+        Some((self.inner_node_id(original_id)?, parameter_id))
+    }
+}
+
+impl AudioGraphNode for FeedbackRegion {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        // 1サンプル遅延でフィードバックするため、内部のグラフはバッファサイズ 1 で処理する
+        self.graph.prepare(sample_rate, 1);
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        // 最大バッファサイズを超えるバッファは、1 サンプルずつに分けて処理される
+        self.graph.process(buffer, self.input_id, self.output_id);
+    }
+
+    fn reset(&mut self) {
+        self.graph.reset();
+    }
+
+    fn update_transport(&mut self, transport: &Transport) {
+        self.graph.set_transport(*transport);
+    }
+
+    fn set_tuning(&mut self, tuning: &Tuning) {
+        self.graph.set_tuning(*tuning);
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        match self.split_parameter_id(id) {
+            Some((node_id, parameter_id)) => self
+                .graph
+                .set_parameter(node_id, parameter_id, value)
+                .is_ok(),
+            None => false,
+        }
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        let (node_id, parameter_id) = self.split_parameter_id(id)?;
+        self.graph.get_parameter(node_id, parameter_id)
+    }
+}
@@ -4,6 +4,7 @@ use super::{GainProcessor, SineGenerator, TapIn, TapOut};
 
 /// Sine 波のオシレーターの出力を自身の frequency にフィードバックするサブグラフ
 /// 1サンプル遅延でのフィードバックを行うため、サブグラフ内部は、バッファーサイズ=1 で処理される。
+/// 任意のノードの組で同じ処理を行う場合は、AudioGraph::create_feedback_region を使う。
 pub struct FeedbackSineSubgraph {
     sine_generator: SineGenerator,
     tap_in: TapIn,
@@ -0,0 +1,162 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    dsp::{DelayLine, Lfo, SmoothedValue},
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// 最大遅延時間（ms）。基準遅延と変調幅の合計はこの値に制限されます。
+const MAX_DELAY_MS: f32 = 20.0;
+
+/// フィードバック付きのフランジャー
+///
+/// 短い遅延時間を LFO で変調し、遅延信号の一部を入力にフィードバックします。
+pub struct Flanger {
+    /// チャンネルごとのディレイライン
+    delay_lines: [DelayLine; NUM_CHANNELS],
+    /// チャンネルごとの LFO
+    lfos: [Lfo; NUM_CHANNELS],
+    /// チャンネルごとの直前の遅延信号（フィードバック用）
+    last_delayed: [f32; NUM_CHANNELS],
+    /// 基準遅延時間（ms）
+    delay_ms: f32,
+    /// 変調幅（ms）
+    depth_ms: f32,
+    /// フィードバック量（-0.95～0.95）
+    feedback: f32,
+    /// ウェット信号の割合（0～1）
+    mix: SmoothedValue,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl Flanger {
+    /// 新しいFlangerを作成
+    pub fn new() -> Self {
+        let mut flanger = Self {
+            delay_lines: [DelayLine::new(), DelayLine::new()],
+            lfos: [Lfo::new(), Lfo::new()],
+            last_delayed: [0.0; NUM_CHANNELS],
+            delay_ms: 1.0,
+            depth_ms: 2.0,
+            feedback: 0.5,
+            mix: SmoothedValue::new(0.5),
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        };
+        flanger.set_rate(0.25);
+        flanger.lfos[1].set_phase(0.25);
+        flanger
+    }
+
+    /// LFO の周波数を設定（Hz）
+    pub fn set_rate(&mut self, rate: f32) {
+        for lfo in self.lfos.iter_mut() {
+            lfo.set_rate(rate);
+        }
+    }
+
+    /// 基準遅延時間を設定（ms）
+    pub fn set_delay_ms(&mut self, delay_ms: f32) {
+        self.delay_ms = delay_ms;
+    }
+
+    /// 変調幅を設定（ms）
+    pub fn set_depth_ms(&mut self, depth_ms: f32) {
+        self.depth_ms = depth_ms;
+    }
+
+    /// フィードバック量を設定。発振を防ぐため -0.95～0.95 に制限されます。
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(-0.95, 0.95);
+    }
+
+    /// ウェット信号の割合を設定（0～1）
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+}
+
+impl AudioGraphNode for Flanger {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        let max_delay_samples = ((MAX_DELAY_MS / 1000.0) * sample_rate).ceil() as usize;
+        for delay_line in self.delay_lines.iter_mut() {
+            delay_line.prepare(max_delay_samples);
+        }
+        for lfo in self.lfos.iter_mut() {
+            lfo.prepare(sample_rate);
+        }
+        self.mix.prepare(sample_rate, 20.0);
+        self.last_delayed = [0.0; NUM_CHANNELS];
+    }
+
+    fn tail_samples(&self) -> usize {
+        // フィードバックで繰り返す信号が -60dB まで減衰する回数だけ、最大の遅延時間を繰り返す
+        let max_delay_ms = (self.delay_ms + self.depth_ms).min(MAX_DELAY_MS);
+        let delay_samples = (max_delay_ms / 1000.0) * self.sample_rate;
+        let feedback = self.feedback.abs();
+        let repeats = if feedback > 0.0 {
+            (0.001f32.ln() / feedback.ln()).ceil()
+        } else {
+            0.0
+        };
+        (delay_samples * (1.0 + repeats)).ceil() as usize
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let num_channels = buffer.num_channels().min(NUM_CHANNELS);
+        let ms_to_samples = self.sample_rate / 1000.0;
+        for i in 0..buffer.num_frames() {
+            let mix = self.mix.next_value();
+            let frame = buffer.get_mut_frame(i);
+            for (ch, sample) in frame.iter_mut().enumerate().take(num_channels) {
+                let dry = *sample;
+                self.delay_lines[ch].write(dry + self.last_delayed[ch] * self.feedback);
+
+                // LFO を 0～1 に変換して、基準遅延から基準遅延＋変調幅の範囲で変調する
+                let modulation = (self.lfos[ch].next_value() + 1.0) * 0.5;
+                let delay_ms = self.delay_ms + self.depth_ms * modulation;
+                let wet = self.delay_lines[ch].read(delay_ms * ms_to_samples);
+                self.last_delayed[ch] = wet;
+
+                *sample = dry * (1.0 - mix) + wet * mix;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for delay_line in self.delay_lines.iter_mut() {
+            delay_line.reset();
+        }
+        for lfo in self.lfos.iter_mut() {
+            lfo.reset();
+        }
+        self.last_delayed = [0.0; NUM_CHANNELS];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flanger_delays_impulse() {
+        let mut flanger = Flanger::new();
+        flanger.set_delay_ms(2.0);
+        flanger.set_depth_ms(0.0);
+        flanger.set_feedback(0.0);
+        flanger.set_mix(1.0);
+        flanger.prepare(1000.0, 4);
+
+        // 1ch のインパルスが 2 サンプル遅れて出力される
+        let mut vector: Vec<f32> = vec![1.0, 0.0, 0.0, 0.0];
+        let mut buffer = AudioBuffer::new(1, 4, vector.as_mut_slice());
+        flanger.process(&mut buffer);
+
+        assert_eq!(vector, vec![0.0, 0.0, 1.0, 0.0]);
+    }
+}
@@ -0,0 +1,511 @@
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    dsp::{Glide, GlideMode},
+    midi::{ExpressionState, NoteEvent},
+    tuning::Tuning,
+};
+
+/// オペレーターの最大数
+const MAX_OPERATORS: usize = 4;
+
+/// 1ブロックあたりに保持できるノートイベントの最大数
+const MAX_PENDING_EVENTS: usize = 64;
+
+/// オペレーターの接続（アルゴリズム）
+///
+/// オペレーターの番号は 0 始まりで、モジュレーターは常に自身より大きい番号のオペレーターになります。
+/// 使わないオペレーターは level が無視されます。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FmAlgorithm {
+    /// 2オペレーターの直列（1 → 0）
+    Serial2,
+    /// 3オペレーターの直列（2 → 1 → 0）
+    Serial3,
+    /// 4オペレーターの直列（3 → 2 → 1 → 0）
+    Serial4,
+    /// 2系統の2オペレーター直列（1 → 0, 3 → 2）。キャリアは 0 と 2。
+    TwoStacks,
+    /// 3つのモジュレーターが1つのキャリアを変調（1, 2, 3 → 0）
+    ThreeToOne,
+    /// 4オペレーターすべてがキャリア（加算合成）
+    Additive,
+}
+
+impl FmAlgorithm {
+    /// 各オペレーターを変調するオペレーターのビットマスクと、キャリアのビットマスクを返す
+    fn routing(&self) -> ([u8; MAX_OPERATORS], u8) {
+        match self {
+            FmAlgorithm::Serial2 => ([0b0010, 0, 0, 0], 0b0001),
+            FmAlgorithm::Serial3 => ([0b0010, 0b0100, 0, 0], 0b0001),
+            FmAlgorithm::Serial4 => ([0b0010, 0b0100, 0b1000, 0], 0b0001),
+            FmAlgorithm::TwoStacks => ([0b0010, 0, 0b1000, 0], 0b0101),
+            FmAlgorithm::ThreeToOne => ([0b1110, 0, 0, 0], 0b0001),
+            FmAlgorithm::Additive => ([0, 0, 0, 0], 0b1111),
+        }
+    }
+
+    /// このアルゴリズムで使うオペレーターの数
+    fn num_operators(&self) -> usize {
+        match self {
+            FmAlgorithm::Serial2 => 2,
+            FmAlgorithm::Serial3 => 3,
+            _ => 4,
+        }
+    }
+}
+
+/// FM シンセのオペレーター（周波数比・レベル・セルフフィードバックを持つサイン波）
+#[derive(Clone, Copy)]
+struct FmOperator {
+    /// ノート周波数に対する周波数比
+    ratio: f32,
+    /// 出力レベル。モジュレーターとして使う場合は変調指数（ラジアン）として働く。
+    level: f32,
+    /// セルフフィードバック量（0～1 程度）
+    feedback: f32,
+    /// 現在の位相（0～1の範囲で保持）
+    phase: f32,
+    /// 直前2サンプルの出力（フィードバック用）
+    prev_outputs: [f32; 2],
+}
+
+impl FmOperator {
+    fn new() -> Self {
+        Self {
+            ratio: 1.0,
+            level: 1.0,
+            feedback: 0.0,
+            phase: 0.0,
+            prev_outputs: [0.0; 2],
+        }
+    }
+
+    /// 1サンプル分の出力を計算し、位相を進める
+    fn next(&mut self, base_phase_delta: f32, modulation: f32) -> f32 {
+        // 直前2サンプルの平均をフィードバックに使うことで発振を抑える
+        let feedback = (self.prev_outputs[0] + self.prev_outputs[1]) * 0.5 * self.feedback;
+        let out =
+            (self.phase * core::f32::consts::TAU + modulation + feedback * core::f32::consts::PI)
+                .sin()
+                * self.level;
+        self.prev_outputs[1] = self.prev_outputs[0];
+        self.prev_outputs[0] = out;
+
+        self.phase += base_phase_delta * self.ratio;
+        self.phase -= self.phase.floor();
+        out
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+        self.prev_outputs = [0.0; 2];
+    }
+}
+
+/// 2～4 オペレーターの FM シンセサイザーノード
+///
+/// ノートイベントで駆動されるモノフォニックのシンセサイザーです。
+/// 後着優先で発音し、発音中のノートのノートオフでリリースに入ります。
+/// ポルタメントの時間を設定すると、直前のノートの音程から滑らかに変化します。
+/// ノートごとの表現（MPE）は発音中のノートに適用し、ピッチベンドで音程を、
+/// プレッシャーと音色でモジュレーターの変調の深さを変えます。
+/// 入力バッファの内容は出力で上書きされます。
+pub struct FmSynth {
+    /// オペレーター
+    operators: [FmOperator; MAX_OPERATORS],
+    /// オペレーターの接続
+    algorithm: FmAlgorithm,
+    /// アタック時間（ms）
+    attack_ms: f32,
+    /// リリース時間（ms）
+    release_ms: f32,
+    /// 発音中のノート番号
+    current_note: Option<u8>,
+    /// 発音中のノートのチャンネル
+    current_channel: u8,
+    /// 発音中のノートの表現
+    expression: ExpressionState,
+    /// チャンネルごとの表現（次にそのチャンネルで発音するノートに引き継ぐ）
+    channel_expressions: [ExpressionState; 16],
+    /// ノート番号から周波数への変換に使う調律
+    tuning: Tuning,
+    /// ポルタメント（ピッチベンドを含まない音程）
+    glide: Glide,
+    /// 直前のノートを押さえたまま次のノートを弾いた場合だけポルタメントするかどうか
+    glide_legato: bool,
+    /// 発音中のノート周波数（Hz）
+    frequency: f32,
+    /// 発音中のノートのベロシティ
+    velocity: f32,
+    /// エンベロープの現在値（0～1）
+    envelope: f32,
+    /// ゲートが開いているかどうか
+    gate: bool,
+    /// 次の process で処理するノートイベント
+    pending_events: Vec<NoteEvent>,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl FmSynth {
+    /// 新しいFmSynthを作成
+    pub fn new() -> Self {
+        Self {
+            operators: [FmOperator::new(); MAX_OPERATORS],
+            algorithm: FmAlgorithm::Serial2,
+            attack_ms: 5.0,
+            release_ms: 200.0,
+            current_note: None,
+            current_channel: 0,
+            expression: ExpressionState::NEUTRAL,
+            channel_expressions: [ExpressionState::NEUTRAL; 16],
+            tuning: Tuning::equal_temperament(),
+            glide: Glide::new(69.0),
+            glide_legato: false,
+            frequency: 440.0,
+            velocity: 0.0,
+            envelope: 0.0,
+            gate: false,
+            pending_events: Vec::with_capacity(MAX_PENDING_EVENTS),
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// アルゴリズムを設定
+    pub fn set_algorithm(&mut self, algorithm: FmAlgorithm) {
+        self.algorithm = algorithm;
+    }
+
+    /// オペレーターの周波数比を設定
+    pub fn set_operator_ratio(&mut self, operator: usize, ratio: f32) {
+        if let Some(op) = self.operators.get_mut(operator) {
+            op.ratio = ratio;
+        }
+    }
+
+    /// オペレーターのレベルを設定
+    pub fn set_operator_level(&mut self, operator: usize, level: f32) {
+        if let Some(op) = self.operators.get_mut(operator) {
+            op.level = level;
+        }
+    }
+
+    /// オペレーターのセルフフィードバック量を設定
+    pub fn set_operator_feedback(&mut self, operator: usize, feedback: f32) {
+        if let Some(op) = self.operators.get_mut(operator) {
+            op.feedback = feedback;
+        }
+    }
+
+    /// アタック時間を設定（ms）
+    pub fn set_attack_ms(&mut self, attack_ms: f32) {
+        self.attack_ms = attack_ms;
+    }
+
+    /// リリース時間を設定（ms）
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_ms = release_ms;
+    }
+
+    /// ポルタメントの時間を設定（ms）。0 の場合はポルタメントしない
+    pub fn set_glide_ms(&mut self, glide_ms: f32) {
+        self.glide.set_time_ms(glide_ms);
+    }
+
+    /// ポルタメントの時間の扱いを設定
+    pub fn set_glide_mode(&mut self, mode: GlideMode) {
+        self.glide.set_mode(mode);
+    }
+
+    /// 直前のノートを押さえたまま次のノートを弾いた場合（レガート）だけポルタメントするかを設定
+    ///
+    /// false の場合は、リリース中や発音を終えた後のノートからもポルタメントします。
+    pub fn set_glide_legato(&mut self, glide_legato: bool) {
+        self.glide_legato = glide_legato;
+    }
+
+    fn apply_event(&mut self, event: &NoteEvent) {
+        match *event {
+            NoteEvent::NoteOn {
+                channel,
+                note,
+                velocity,
+                ..
+            } => {
+                // 無音から発音する場合は位相をそろえて、毎回同じアタックになるようにする
+                if !self.gate && self.envelope <= 0.0 {
+                    for op in self.operators.iter_mut() {
+                        op.reset();
+                    }
+                }
+                let glides = if self.glide_legato {
+                    self.gate
+                } else {
+                    self.current_note.is_some()
+                };
+                if glides {
+                    self.glide.glide_to(note as f32);
+                } else {
+                    self.glide.jump_to(note as f32);
+                }
+                self.current_note = Some(note);
+                self.current_channel = channel;
+                // 発音前にチャンネルへ送られた表現を引き継ぐ
+                self.expression = self.channel_expressions[channel as usize & 0x0f];
+                self.update_frequency();
+                self.velocity = velocity;
+                self.gate = true;
+            }
+            NoteEvent::NoteOff { note, .. } => {
+                if self.current_note == Some(note) {
+                    self.gate = false;
+                }
+            }
+            NoteEvent::Expression {
+                channel,
+                note,
+                expression,
+                ..
+            } => {
+                if note.is_none() {
+                    self.channel_expressions[channel as usize & 0x0f].apply(expression);
+                }
+                // 発音中のノートのチャンネルで、対象のノートが一致する場合に適用する
+                let owns_note = match note {
+                    Some(note) => self.current_note == Some(note),
+                    None => self.current_note.is_some(),
+                };
+                if owns_note && channel == self.current_channel {
+                    self.expression.apply(expression);
+                    self.update_frequency();
+                }
+            }
+        }
+    }
+
+    /// ポルタメント中の音程とピッチベンドから周波数を更新する
+    fn update_frequency(&mut self) {
+        if self.current_note.is_some() {
+            self.frequency = self
+                .tuning
+                .frequency(self.glide.current() + self.expression.pitch_bend);
+        }
+    }
+
+    /// エンベロープを1サンプル進める
+    fn next_envelope(&mut self) -> f32 {
+        if self.gate {
+            let step = ms_to_step(self.attack_ms, self.sample_rate);
+            self.envelope = (self.envelope + step).min(1.0);
+        } else {
+            let step = ms_to_step(self.release_ms, self.sample_rate);
+            self.envelope = (self.envelope - step).max(0.0);
+        }
+        self.envelope
+    }
+
+    /// 1サンプル分の出力を計算する
+    fn next_sample(&mut self) -> f32 {
+        if self.glide.is_gliding() {
+            self.glide.next_value();
+            self.update_frequency();
+        }
+        let envelope = self.next_envelope();
+        if envelope <= 0.0 {
+            return 0.0;
+        }
+
+        let (modulators, carriers) = self.algorithm.routing();
+        let num_operators = self.algorithm.num_operators();
+        let base_phase_delta = self.frequency / self.sample_rate;
+        // プレッシャーと音色で変調を深くする（音色が中央でプレッシャーが 0 の場合は変化なし）
+        let depth = (1.0 + self.expression.pressure) * self.expression.timbre * 2.0;
+
+        // モジュレーターは自身より大きい番号なので、大きい番号から順に計算する
+        let mut outputs = [0.0; MAX_OPERATORS];
+        for i in (0..num_operators).rev() {
+            let mut modulation = 0.0;
+            for (j, output) in outputs.iter().enumerate().take(num_operators) {
+                if modulators[i] & (1 << j) != 0 {
+                    modulation += output;
+                }
+            }
+            outputs[i] = self.operators[i].next(base_phase_delta, modulation * depth);
+        }
+
+        let mut sum = 0.0;
+        for (i, output) in outputs.iter().enumerate().take(num_operators) {
+            if carriers & (1 << i) != 0 {
+                sum += output;
+            }
+        }
+        sum * envelope * self.velocity
+    }
+}
+
+/// ms 単位の時間を、0 から 1 まで変化するための1サンプルあたりの増分に変換する
+fn ms_to_step(ms: f32, sample_rate: f32) -> f32 {
+    let samples = ms / 1000.0 * sample_rate;
+    if samples < 1.0 { 1.0 } else { 1.0 / samples }
+}
+
+impl AudioGraphNode for FmSynth {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        self.glide.prepare(sample_rate);
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let mut event_idx = 0;
+        for (i, frame) in buffer.frames_mut().enumerate() {
+            // このサンプルより前のタイミングのイベントを適用
+            while event_idx < self.pending_events.len()
+                && self.pending_events[event_idx].timing() as usize <= i
+            {
+                let event = self.pending_events[event_idx];
+                self.apply_event(&event);
+                event_idx += 1;
+            }
+
+            let val = self.next_sample();
+            frame.fill(val);
+        }
+
+        // ブロック外のタイミングのイベントはブロック末尾で適用する
+        while event_idx < self.pending_events.len() {
+            let event = self.pending_events[event_idx];
+            self.apply_event(&event);
+            event_idx += 1;
+        }
+        self.pending_events.clear();
+    }
+
+    fn reset(&mut self) {
+        for op in self.operators.iter_mut() {
+            op.reset();
+        }
+        self.current_note = None;
+        self.expression = ExpressionState::NEUTRAL;
+        self.channel_expressions = [ExpressionState::NEUTRAL; 16];
+        self.envelope = 0.0;
+        self.gate = false;
+        self.pending_events.clear();
+    }
+
+    fn set_tuning(&mut self, tuning: &Tuning) {
+        self.tuning = *tuning;
+        self.update_frequency();
+    }
+
+    fn handle_note_event(&mut self, event: &NoteEvent) {
+        // 容量を超えたイベントは、アロケーションを避けるため破棄する
+        if self.pending_events.len() < MAX_PENDING_EVENTS {
+            self.pending_events.push(*event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::midi::NoteExpression;
+
+    use super::*;
+
+    #[test]
+    fn test_fm_synth_single_carrier_is_sine() {
+        let mut synth = FmSynth::new();
+        synth.set_algorithm(FmAlgorithm::Additive);
+        synth.set_operator_level(1, 0.0);
+        synth.set_operator_level(2, 0.0);
+        synth.set_operator_level(3, 0.0);
+        synth.set_attack_ms(0.0);
+
+        // ノート 69 (440Hz) をサンプルレート 1760Hz で鳴らすと、1周期 4 サンプルのサイン波になる
+        synth.prepare(1760.0, 4);
+        synth.handle_note_event(&NoteEvent::NoteOn {
+            timing: 0,
+            channel: 0,
+            note: 69,
+            velocity: 1.0,
+        });
+        let mut vector: Vec<f32> = vec![0.0; 4];
+        let mut buffer = AudioBuffer::new(1, 4, vector.as_mut_slice());
+        synth.process(&mut buffer);
+
+        assert!(vector[0].abs() < 1e-5);
+        assert!((vector[1] - 1.0).abs() < 1e-5);
+        assert!(vector[2].abs() < 1e-5);
+        assert!((vector[3] + 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_fm_synth_expression_follows_owning_note() {
+        let mut synth = FmSynth::new();
+        synth.set_algorithm(FmAlgorithm::Additive);
+        synth.set_operator_level(1, 0.0);
+        synth.set_operator_level(2, 0.0);
+        synth.set_operator_level(3, 0.0);
+        synth.set_attack_ms(0.0);
+        synth.prepare(1760.0, 4);
+        synth.handle_note_event(&NoteEvent::NoteOn {
+            timing: 0,
+            channel: 2,
+            note: 69,
+            velocity: 1.0,
+        });
+
+        // 発音中のノートと異なるチャンネルのピッチベンドは適用されない
+        synth.handle_note_event(&NoteEvent::Expression {
+            timing: 0,
+            channel: 3,
+            note: None,
+            expression: NoteExpression::PitchBend(12.0),
+        });
+        let mut vector: Vec<f32> = vec![0.0; 4];
+        synth.process(&mut AudioBuffer::new(1, 4, vector.as_mut_slice()));
+        assert!((vector[1] - 1.0).abs() < 1e-5);
+
+        // 発音中のノートのチャンネルに 1 オクターブのピッチベンドを送ると 880Hz（1周期 2 サンプル）になる
+        synth.handle_note_event(&NoteEvent::Expression {
+            timing: 0,
+            channel: 2,
+            note: None,
+            expression: NoteExpression::PitchBend(12.0),
+        });
+        synth.process(&mut AudioBuffer::new(1, 4, vector.as_mut_slice()));
+        assert!(vector.iter().all(|s| s.abs() < 1e-5), "{:?}", vector);
+    }
+
+    #[test]
+    fn test_fm_synth_note_off_releases_to_silence() {
+        let mut synth = FmSynth::new();
+        synth.set_release_ms(1.0);
+        synth.prepare(1000.0, 8);
+
+        // ノートオンの前は無音
+        let mut vector: Vec<f32> = vec![1.0; 8];
+        synth.process(&mut AudioBuffer::new(1, 8, vector.as_mut_slice()));
+        assert!(vector.iter().all(|s| *s == 0.0));
+
+        synth.handle_note_event(&NoteEvent::NoteOn {
+            timing: 0,
+            channel: 0,
+            note: 60,
+            velocity: 1.0,
+        });
+        synth.process(&mut AudioBuffer::new(1, 8, vector.as_mut_slice()));
+        assert!(vector.iter().any(|s| s.abs() > 0.0));
+
+        // リリース 1ms（1サンプル）後は無音になる
+        synth.handle_note_event(&NoteEvent::NoteOff {
+            timing: 0,
+            channel: 0,
+            note: 60,
+            velocity: 0.0,
+        });
+        synth.process(&mut AudioBuffer::new(1, 8, vector.as_mut_slice()));
+        assert!(vector.iter().all(|s| *s == 0.0));
+    }
+}
@@ -1,6 +1,25 @@
-use crate::{audio_buffer::AudioBuffer, audio_graph::AudioGraphNode};
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, InputPorts},
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 1] = [ParameterInfo {
+    id: "gain",
+    name: "Gain",
+    min: 0.0,
+    max: 16.0,
+    default: 1.0,
+    unit: "",
+}];
+
+/// ゲインのパラメーターポート
+const GAIN_PORT: usize = 1;
 
 /// ゲインを処理するプロセッサー
+///
+/// ゲインはパラメーターポートで変化させられるため、オートメーションやエンベロープでサンプルごとに変化させられます。
 pub struct GainProcessor {
     /// ゲイン値
     gain: f32,
@@ -30,9 +49,60 @@ impl AudioGraphNode for GainProcessor {
         }
     }
 
+    fn num_input_ports(&self) -> usize {
+        2
+    }
+
+    fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, inputs: &InputPorts) {
+        // パラメーターポートの信号は、ゲインに加算する
+        let Some(gain_signal) = inputs.parameter_signal(GAIN_PORT) else {
+            self.process(buffer);
+            return;
+        };
+        for (frame, signal) in buffer.frames_mut().zip(gain_signal) {
+            let gain = self.gain + signal;
+            for sample in frame {
+                *sample *= gain;
+            }
+        }
+    }
+
+    fn parameter_port(&self, parameter_id: &str) -> Option<usize> {
+        match parameter_id {
+            "gain" => Some(GAIN_PORT),
+            _ => None,
+        }
+    }
+
     fn reset(&mut self) {
         // ゲインプロセッサーにはリセットする状態がない
     }
+
+    fn process_f64(&mut self, buffer: &mut AudioBuffer<f64>) {
+        let gain = self.gain as f64;
+        for sample in buffer.as_mut_slice() {
+            *sample *= gain;
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        self.gain = info.clamp(value);
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "gain" => Some(self.gain),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,368 @@
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::prelude::*;
+use crate::{audio_buffer::AudioBuffer, audio_graph::AudioGraphNode};
+#[cfg(feature = "std")]
+use crate::{audio_file, wav};
+
+/// 同時に発音できるグレインの最大数
+const MAX_GRAINS: usize = 64;
+
+/// グレインの窓関数の形状
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GrainWindow {
+    /// ハン窓
+    Hann,
+    /// 三角窓
+    Triangle,
+    /// 矩形窓（窓をかけない）
+    Rectangle,
+}
+
+impl GrainWindow {
+    /// グレイン内の位置（0～1）における窓の値を計算する
+    fn value(&self, x: f32) -> f32 {
+        match self {
+            GrainWindow::Hann => 0.5 - 0.5 * (x * core::f32::consts::TAU).cos(),
+            GrainWindow::Triangle => 1.0 - (2.0 * x - 1.0).abs(),
+            GrainWindow::Rectangle => 1.0,
+        }
+    }
+}
+
+/// 発音中のグレイン
+#[derive(Clone, Copy)]
+struct Grain {
+    /// 発音中かどうか
+    active: bool,
+    /// サンプルバッファ上の読み出し位置（サンプル単位、小数を含む）
+    read_pos: f32,
+    /// 1 出力サンプルあたりの読み出し位置の増分
+    increment: f32,
+    /// グレインの長さ（出力サンプル数）
+    length: usize,
+    /// グレイン開始からの経過サンプル数
+    elapsed: usize,
+}
+
+impl Grain {
+    fn new() -> Self {
+        Self {
+            active: false,
+            read_pos: 0.0,
+            increment: 1.0,
+            length: 0,
+            elapsed: 0,
+        }
+    }
+}
+
+/// グラニュラー再生を行うノード
+///
+/// 内部のサンプルバッファ（WAV ファイルから読み込む、または入力をライブで録音する）からグレインを切り出して再生します。
+/// グレインは事前に確保したスロットで管理するため、process 中にメモリアロケーションは発生しません。
+/// 出力はモノラルで、全チャンネルに同じ値を書き込みます。
+pub struct Granulator {
+    /// サンプルバッファ（モノラル）
+    sample_buffer: Vec<f32>,
+    /// サンプルバッファのサンプリングレート
+    sample_buffer_rate: f32,
+    /// ライブ録音時のサンプルバッファの長さ（ms）
+    capture_length_ms: f32,
+    /// 入力をサンプルバッファに録音するかどうか
+    capturing: bool,
+    /// ライブ録音の書き込み位置
+    write_pos: usize,
+    /// グレインのスロット
+    grains: [Grain; MAX_GRAINS],
+    /// グレインの長さ（ms）
+    grain_size_ms: f32,
+    /// 1秒あたりのグレイン生成数
+    density: f32,
+    /// ピッチ（半音単位）
+    pitch_semitones: f32,
+    /// 読み出し位置（サンプルバッファ全体に対する 0～1 の割合）
+    position: f32,
+    /// 読み出し位置のランダムなばらつき（ms）
+    spray_ms: f32,
+    /// 窓関数の形状
+    window: GrainWindow,
+    /// 次のグレインを生成するまでのサンプル数
+    samples_until_next_grain: f32,
+    /// 乱数の状態（xorshift）
+    rng_state: u32,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl Granulator {
+    /// 新しいGranulatorを作成
+    pub fn new() -> Self {
+        Self {
+            sample_buffer: Vec::new(),
+            sample_buffer_rate: 44100.0,
+            capture_length_ms: 2000.0,
+            capturing: false,
+            write_pos: 0,
+            grains: [Grain::new(); MAX_GRAINS],
+            grain_size_ms: 100.0,
+            density: 20.0,
+            pitch_semitones: 0.0,
+            position: 0.0,
+            spray_ms: 0.0,
+            window: GrainWindow::Hann,
+            samples_until_next_grain: 0.0,
+            rng_state: 0x1234_5678,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// サンプルバッファを設定する
+    ///
+    /// # 引数
+    /// * `samples` - インターリーブされたサンプル列
+    /// * `channels` - チャンネル数（モノラルにダウンミックスされます）
+    /// * `sample_rate` - サンプル列のサンプリングレート
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn set_sample(&mut self, samples: &[f32], channels: usize, sample_rate: f32) {
+        let channels = channels.max(1);
+        self.sample_buffer = samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+        self.sample_buffer_rate = sample_rate;
+        self.capturing = false;
+        for grain in self.grains.iter_mut() {
+            grain.active = false;
+        }
+    }
+
+    /// WAV ファイルを読み込んでサンプルバッファに設定する
+    ///
+    /// # 実装時の注意
+    /// この関数はファイル IO とメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    #[cfg(feature = "std")]
+    pub fn load_wav(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
+        let wav = wav::read_wav(path)?;
+        self.set_sample(&wav.samples, wav.channels, wav.sample_rate as f32);
+        Ok(())
+    }
+
+    /// 音声ファイルを読み込んでサンプルバッファに設定する
+    ///
+    /// WAV 以外のフォーマットは `symphonia` フィーチャーが有効な場合に読み込めます。
+    /// 読み込みに時間がかかる場合は AudioFileLoader で読み込み、set_sample で設定してください。
+    ///
+    /// # 実装時の注意
+    /// この関数はファイル IO とメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    #[cfg(feature = "std")]
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
+        let audio = audio_file::read_audio_file(path)?;
+        self.set_sample(&audio.samples, audio.channels, audio.sample_rate as f32);
+        Ok(())
+    }
+
+    /// 入力をサンプルバッファにライブ録音するかどうかを設定
+    ///
+    /// 有効にした場合、サンプルバッファはリングバッファとして使われ、次の prepare で確保されます。
+    pub fn set_capturing(&mut self, capturing: bool) {
+        self.capturing = capturing;
+    }
+
+    /// ライブ録音時のサンプルバッファの長さを設定（ms）
+    pub fn set_capture_length_ms(&mut self, ms: f32) {
+        self.capture_length_ms = ms;
+    }
+
+    /// グレインの長さを設定（ms）
+    pub fn set_grain_size_ms(&mut self, ms: f32) {
+        self.grain_size_ms = ms;
+    }
+
+    /// 1秒あたりのグレイン生成数を設定
+    pub fn set_density(&mut self, density: f32) {
+        self.density = density;
+    }
+
+    /// ピッチを設定（半音単位）
+    pub fn set_pitch_semitones(&mut self, semitones: f32) {
+        self.pitch_semitones = semitones;
+    }
+
+    /// 読み出し位置を設定（サンプルバッファ全体に対する 0～1 の割合）
+    pub fn set_position(&mut self, position: f32) {
+        self.position = position.clamp(0.0, 1.0);
+    }
+
+    /// 読み出し位置のランダムなばらつきを設定（ms）
+    pub fn set_spray_ms(&mut self, ms: f32) {
+        self.spray_ms = ms;
+    }
+
+    /// 窓関数の形状を設定
+    pub fn set_window(&mut self, window: GrainWindow) {
+        self.window = window;
+    }
+
+    /// -1～1 の一様乱数を生成する
+    fn next_random(&mut self) -> f32 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+
+    /// 空いているスロットにグレインを生成する。空きがなければ何もしない。
+    fn spawn_grain(&mut self) {
+        let buffer_len = self.sample_buffer.len();
+        if buffer_len == 0 {
+            return;
+        }
+        let Some(slot) = self.grains.iter().position(|g| !g.active) else {
+            return;
+        };
+
+        let spray = self.next_random() * self.spray_ms / 1000.0 * self.sample_buffer_rate;
+        let start = (self.position * buffer_len as f32 + spray).rem_euclid(buffer_len as f32);
+        let increment =
+            2.0_f32.powf(self.pitch_semitones / 12.0) * self.sample_buffer_rate / self.sample_rate;
+        let length = ((self.grain_size_ms / 1000.0) * self.sample_rate).max(1.0) as usize;
+
+        self.grains[slot] = Grain {
+            active: true,
+            read_pos: start,
+            increment,
+            length,
+            elapsed: 0,
+        };
+    }
+
+    /// 発音中の全グレインを1サンプル進め、その合計を返す
+    fn next_sample(&mut self) -> f32 {
+        let buffer_len = self.sample_buffer.len();
+        let mut sum = 0.0;
+        for grain in self.grains.iter_mut() {
+            if !grain.active {
+                continue;
+            }
+            // 線形補間で読み出す
+            let idx = grain.read_pos as usize % buffer_len;
+            let frac = grain.read_pos - grain.read_pos.floor();
+            let a = self.sample_buffer[idx];
+            let b = self.sample_buffer[(idx + 1) % buffer_len];
+            let x = grain.elapsed as f32 / grain.length as f32;
+            sum += (a + (b - a) * frac) * self.window.value(x);
+
+            grain.read_pos = (grain.read_pos + grain.increment).rem_euclid(buffer_len as f32);
+            grain.elapsed += 1;
+            if grain.elapsed >= grain.length {
+                grain.active = false;
+            }
+        }
+        sum
+    }
+}
+
+impl AudioGraphNode for Granulator {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        if self.capturing {
+            let len = ((self.capture_length_ms / 1000.0) * sample_rate).ceil() as usize;
+            self.sample_buffer = vec![0.0; len.max(1)];
+            self.sample_buffer_rate = sample_rate;
+            self.write_pos = 0;
+        }
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let num_channels = buffer.num_channels();
+        for frame in buffer.frames_mut() {
+            // ライブ録音中は入力のモノラルミックスをリングバッファに書き込む
+            if self.capturing && !self.sample_buffer.is_empty() {
+                let mono = frame.iter().sum::<f32>() / num_channels as f32;
+                self.sample_buffer[self.write_pos] = mono;
+                self.write_pos = (self.write_pos + 1) % self.sample_buffer.len();
+            }
+
+            self.samples_until_next_grain -= 1.0;
+            if self.density <= 0.0 {
+                // 密度が 0 の間に次のグレインまでの時間が負にたまると、密度を上げたときにグレインをまとめて生成してしまう
+                self.samples_until_next_grain = self.samples_until_next_grain.max(0.0);
+            } else if self.samples_until_next_grain <= 0.0 {
+                self.spawn_grain();
+                self.samples_until_next_grain += self.sample_rate / self.density;
+            }
+
+            let val = if self.sample_buffer.is_empty() {
+                0.0
+            } else {
+                self.next_sample()
+            };
+            frame.fill(val);
+        }
+    }
+
+    fn reset(&mut self) {
+        for grain in self.grains.iter_mut() {
+            grain.active = false;
+        }
+        self.samples_until_next_grain = 0.0;
+        if self.capturing {
+            self.sample_buffer.fill(0.0);
+            self.write_pos = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_granulator_plays_rectangular_grain() {
+        let mut granulator = Granulator::new();
+        // 値がインデックスと等しいサンプル列
+        let samples: Vec<f32> = (0..16).map(|i| i as f32).collect();
+        granulator.set_sample(&samples, 1, 1000.0);
+        granulator.set_window(GrainWindow::Rectangle);
+        granulator.set_grain_size_ms(4.0);
+        granulator.set_density(1.0);
+        granulator.set_position(0.25);
+        granulator.prepare(1000.0, 8);
+
+        let mut vector: Vec<f32> = vec![0.0; 8];
+        let mut buffer = AudioBuffer::new(1, 8, vector.as_mut_slice());
+        granulator.process(&mut buffer);
+
+        // 位置 0.25（インデックス4）から 4 サンプルのグレインが1つだけ再生される
+        assert_eq!(vector, vec![4.0, 5.0, 6.0, 7.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_granulator_resumes_after_zero_density() {
+        let mut granulator = Granulator::new();
+        let samples: Vec<f32> = (0..16).map(|i| i as f32).collect();
+        granulator.set_sample(&samples, 1, 1000.0);
+        granulator.set_window(GrainWindow::Rectangle);
+        granulator.set_grain_size_ms(4.0);
+        granulator.set_density(0.0);
+        granulator.set_position(0.25);
+        granulator.prepare(1000.0, 2000);
+
+        // 密度が 0 の間はグレインを生成しない
+        let mut vector: Vec<f32> = vec![0.0; 2000];
+        granulator.process(&mut AudioBuffer::new(1, 2000, vector.as_mut_slice()));
+        assert!(vector.iter().all(|&s| s == 0.0));
+
+        // 密度を上げると、止めていた間の分をまとめて生成せずに 1 つだけ生成する
+        granulator.set_density(1.0);
+        let mut vector: Vec<f32> = vec![0.0; 8];
+        granulator.process(&mut AudioBuffer::new(1, 8, vector.as_mut_slice()));
+        assert_eq!(vector, vec![4.0, 5.0, 6.0, 7.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+}
@@ -1,24 +1,166 @@
-use crate::{audio_buffer::AudioBuffer, audio_graph::AudioGraphNode};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    dsp::SmoothedValue,
+    parameter::{ParameterInfo, find_parameter},
+};
 
-/// 入力ノード - グラフの入力点を示すマーカーノード
-pub struct InputNode {}
+/// ゲインとミュートの変化にかける時間（ms）
+const GAIN_RAMP_MS: f32 = 10.0;
+
+/// デバイスのチャンネルを指定しないことを表す値
+const DEFAULT_CHANNEL: f32 = -1.0;
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 4] = [
+    ParameterInfo {
+        id: "gain",
+        name: "Input Gain",
+        min: -60.0,
+        max: 24.0,
+        default: 0.0,
+        unit: "dB",
+    },
+    ParameterInfo {
+        id: "mute",
+        name: "Mute",
+        min: 0.0,
+        max: 1.0,
+        default: 0.0,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "channel_left",
+        name: "Left Device Channel",
+        min: DEFAULT_CHANNEL,
+        max: 255.0,
+        default: DEFAULT_CHANNEL,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "channel_right",
+        name: "Right Device Channel",
+        min: DEFAULT_CHANNEL,
+        max: 255.0,
+        default: DEFAULT_CHANNEL,
+        unit: "",
+    },
+];
+
+/// 入力ノード - グラフの入力点を示すノード
+///
+/// 外部から渡された入力に、入力ゲイン（トリム）とミュートを適用してグラフに送ります。
+///
+/// `channel_left` / `channel_right` パラメーターは、グラフの左右のチャンネルに取り込む
+/// デバイスの入力チャンネルの番号です（-1 の場合はホストの設定に従う）。
+/// このノード自身は使わず、デバイスとの間でチャンネルを対応付けるホストが参照します。
+pub struct InputNode {
+    /// 入力ゲイン（リニア、ミュート中は 0）
+    gain: SmoothedValue,
+    /// 入力ゲイン（dB）
+    gain_db: f32,
+    /// ミュート中かどうか
+    muted: bool,
+    /// 左右のチャンネルに取り込むデバイスのチャンネル（None の場合はホストの設定に従う）
+    device_channels: [Option<usize>; 2],
+}
 
 impl InputNode {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            gain: SmoothedValue::new(1.0),
+            gain_db: 0.0,
+            muted: false,
+            device_channels: [None; 2],
+        }
+    }
+
+    /// 入力ゲインを設定（dB）
+    pub fn set_gain_db(&mut self, gain_db: f32) {
+        self.gain_db = gain_db;
+        self.update_gain();
+    }
+
+    /// ミュートするかどうかを設定
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.update_gain();
+    }
+
+    /// 左右のチャンネルに取り込むデバイスのチャンネルを設定
+    ///
+    /// # 引数
+    /// * `channels` - 左右それぞれのデバイスのチャンネル番号（None の場合はホストの設定に従う）
+    pub fn set_device_channels(&mut self, channels: [Option<usize>; 2]) {
+        self.device_channels = channels;
+    }
+
+    /// 左右のチャンネルに取り込むデバイスのチャンネル
+    pub fn device_channels(&self) -> [Option<usize>; 2] {
+        self.device_channels
+    }
+
+    fn update_gain(&mut self) {
+        let gain = if self.muted {
+            0.0
+        } else {
+            10.0f32.powf(self.gain_db / 20.0)
+        };
+        self.gain.set_target(gain);
     }
 }
 
 impl AudioGraphNode for InputNode {
-    fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {
-        // 何もしない
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.gain.prepare(sample_rate, GAIN_RAMP_MS);
     }
 
-    fn process(&mut self, _buffer: &mut AudioBuffer) {
-        // 何もしない
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        if !self.gain.is_smoothing() && self.gain.current() == 1.0 {
+            return;
+        }
+        let num_channels = buffer.num_channels();
+        for frame in buffer.as_mut_slice().chunks_exact_mut(num_channels) {
+            let gain = self.gain.next_value();
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
     }
 
     fn reset(&mut self) {
         // 何もしない
     }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        let value = info.clamp(value);
+        let channel = (value >= 0.0).then_some(value as usize);
+        match id {
+            "gain" => self.set_gain_db(value),
+            "mute" => self.set_muted(value >= 0.5),
+            "channel_left" => self.device_channels[0] = channel,
+            _ => self.device_channels[1] = channel,
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        let channel = |channel: Option<usize>| channel.map_or(DEFAULT_CHANNEL, |ch| ch as f32);
+        match id {
+            "gain" => Some(self.gain_db),
+            "mute" => Some(if self.muted { 1.0 } else { 0.0 }),
+            "channel_left" => Some(channel(self.device_channels[0])),
+            "channel_right" => Some(channel(self.device_channels[1])),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,202 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, ProcessRate},
+    dsp::{Lfo, LfoShape},
+    parameter::{ParameterInfo, find_parameter},
+    transport::Transport,
+};
+
+/// コントロールレートで処理する間隔のデフォルト（サンプル数）
+const DEFAULT_CONTROL_INTERVAL: usize = 32;
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 1] = [ParameterInfo {
+    id: "rate",
+    name: "Rate",
+    min: 0.01,
+    max: 20.0,
+    default: 1.0,
+    unit: "Hz",
+}];
+
+/// LFO の波形を出力するプロセッサー
+///
+/// デフォルトではコントロールレートで処理し、出力はグラフがオーディオレートに補間します。
+/// モジュレーションソースとして `AudioGraph::add_modulation` で他のノードのパラメーターに接続できます。
+/// `set_tempo_sync` で周期を拍数で指定すると、再生中はトランスポートの位置に位相を合わせます。
+pub struct LfoGenerator {
+    /// LFO
+    lfo: Lfo,
+    /// 周波数。Hz 単位。
+    rate: f32,
+    /// 最後に出力した値
+    value: f32,
+    /// テンポに同期する場合の 1 周期の拍数
+    sync_beats: Option<f64>,
+    /// 処理する頻度
+    process_rate: ProcessRate,
+}
+
+impl LfoGenerator {
+    /// 新しいLfoGeneratorを作成
+    pub fn new() -> Self {
+        Self {
+            lfo: Lfo::new(),
+            rate: 1.0,
+            value: 0.0,
+            sync_beats: None,
+            process_rate: ProcessRate::Every(DEFAULT_CONTROL_INTERVAL),
+        }
+    }
+
+    /// 周波数を設定（Hz）
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate;
+        self.lfo.set_rate(rate);
+    }
+
+    /// 波形を設定
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.lfo.set_shape(shape);
+    }
+
+    /// テンポに同期させる
+    ///
+    /// # 引数
+    /// * `beats` - 1 周期の拍数（四分音符を 1 拍とする）。`None` の場合は rate の周波数で動く。
+    pub fn set_tempo_sync(&mut self, beats: Option<f64>) {
+        self.sync_beats = beats.filter(|beats| *beats > 0.0);
+        if self.sync_beats.is_none() {
+            self.lfo.set_rate(self.rate);
+        }
+    }
+
+    /// 処理する頻度を設定
+    pub fn set_process_rate(&mut self, process_rate: ProcessRate) {
+        self.process_rate = process_rate;
+    }
+}
+
+impl AudioGraphNode for LfoGenerator {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.lfo.prepare(sample_rate);
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        for frame in buffer.frames_mut() {
+            self.value = self.lfo.next_value();
+            frame.fill(self.value);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.lfo.reset();
+        self.value = 0.0;
+    }
+
+    fn update_transport(&mut self, transport: &Transport) {
+        // 停止中は、最後に同期した周波数のまま動かす
+        let Some(beats) = self.sync_beats else {
+            return;
+        };
+        if transport.playing && transport.tempo > 0.0 {
+            self.lfo.set_rate((transport.tempo / 60.0 / beats) as f32);
+            self.lfo
+                .set_phase((transport.position_beats / beats).rem_euclid(1.0) as f32);
+        }
+    }
+
+    fn process_rate(&self) -> ProcessRate {
+        self.process_rate
+    }
+
+    fn process_control(&mut self, input: &AudioBuffer) -> f32 {
+        // 区間の終わりまで位相を進めた位置の値を出力する
+        self.lfo.skip(input.num_frames());
+        self.value = self.lfo.value();
+        self.value
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        self.set_rate(info.clamp(value));
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "rate" => Some(self.rate),
+            _ => None,
+        }
+    }
+
+    fn modulation_output(&self) -> Option<f32> {
+        Some(self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio_graph::AudioGraph;
+    use crate::nodes::{InputNode, OutputNode};
+
+    use super::*;
+
+    #[test]
+    fn test_control_rate_output_is_interpolated() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let mut generator = LfoGenerator::new();
+        generator.set_process_rate(ProcessRate::Every(2));
+        let generator_id = graph.add_node(Box::new(generator));
+        assert!(graph.add_edge(generator_id, output_node_id).is_ok());
+        graph.prepare(8.0, 6);
+
+        // 2 サンプルごとに 1/4 周期進み、その間は直線で補間される
+        let mut buffer: Vec<f32> = vec![0.0; 6];
+        let mut audio_buffer = AudioBuffer::new(1, 6, &mut buffer);
+        graph.process(&mut audio_buffer, input_node_id, output_node_id);
+        let expected = [0.5, 1.0, 0.5, 0.0, -0.5, -1.0];
+        for (actual, expected) in buffer.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-6, "{:?}", buffer);
+        }
+        let generator = graph.get_node(generator_id).unwrap();
+        assert!((generator.modulation_output().unwrap() + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_tempo_sync_follows_transport() {
+        let mut graph = AudioGraph::new();
+        let input_node_id = graph.add_node(Box::new(InputNode::new()));
+        let output_node_id = graph.add_node(Box::new(OutputNode::new()));
+        let mut generator = LfoGenerator::new();
+        generator.set_process_rate(ProcessRate::Audio);
+        generator.set_tempo_sync(Some(4.0));
+        let generator_id = graph.add_node(Box::new(generator));
+        assert!(graph.add_edge(generator_id, output_node_id).is_ok());
+        graph.prepare(48000.0, 4);
+
+        // 4 拍で 1 周期のため、1 拍目の位置では 1/4 周期（サイン波の頂点）になる
+        let mut transport = Transport::new();
+        transport.position_beats = 1.0;
+        transport.playing = true;
+        graph.set_transport(transport);
+        let mut buffer: Vec<f32> = vec![0.0; 4];
+        let mut audio_buffer = AudioBuffer::new(1, 4, &mut buffer);
+        graph.process(&mut audio_buffer, input_node_id, output_node_id);
+        assert!((buffer[0] - 1.0).abs() < 1e-6, "{:?}", buffer);
+
+        // 再生中はブロックの長さだけ位置が進む
+        let expected = 1.0 + 4.0 / transport.samples_per_beat(48000.0);
+        assert_eq!(graph.transport().position_beats, expected);
+    }
+}
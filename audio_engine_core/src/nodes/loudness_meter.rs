@@ -0,0 +1,392 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    dsp::{Biquad, BiquadCoefficients},
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// モーメンタリーラウドネスの 400ms のブロックに含まれる、100ms の区間の数
+const MOMENTARY_SUB_BLOCKS: usize = 4;
+
+/// ショートタームラウドネスの 3 秒に含まれる、100ms の区間の数
+const SHORT_TERM_SUB_BLOCKS: usize = 30;
+
+/// 絶対ゲート（LUFS）。インテグレーテッドラウドネスのヒストグラムの下限でもある
+const ABSOLUTE_GATE: f64 = -70.0;
+
+/// 相対ゲート（LU）
+const RELATIVE_GATE: f64 = -10.0;
+
+/// インテグレーテッドラウドネスのヒストグラムのビンの幅（LU）
+const HISTOGRAM_STEP: f64 = 0.1;
+
+/// インテグレーテッドラウドネスのヒストグラムのビンの数（-70～+10 LUFS）
+const HISTOGRAM_BINS: usize = 800;
+
+/// トゥルーピークを求めるオーバーサンプリングの倍率
+const OVERSAMPLING: usize = 4;
+
+/// トゥルーピークの補間フィルターの、位相ごとのタップ数
+const TAPS_PER_PHASE: usize = 12;
+
+/// 平均二乗値をラウドネス（LUFS）に変換する
+fn energy_to_lufs(energy: f64) -> f32 {
+    if energy > 0.0 {
+        (-0.691 + 10.0 * energy.log10()) as f32
+    } else {
+        f32::NEG_INFINITY
+    }
+}
+
+/// K 特性の 1 段目（頭部の影響を模したハイシェルフ）の係数
+///
+/// ITU-R BS.1770 の 48kHz の係数と同じ特性を、任意のサンプリングレートで計算します。
+fn k_weighting_shelf(sample_rate: f32) -> BiquadCoefficients {
+    let (f0, gain_db, q) = (1_681.974_5_f64, 3.999_843_9_f64, 0.707_175_2_f64);
+    let k = (core::f64::consts::PI * f0 / sample_rate as f64).tan();
+    let vh = 10.0_f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_8);
+    let a0 = 1.0 + k / q + k * k;
+    BiquadCoefficients {
+        b0: ((vh + vb * k / q + k * k) / a0) as f32,
+        b1: (2.0 * (k * k - vh) / a0) as f32,
+        b2: ((vh - vb * k / q + k * k) / a0) as f32,
+        a1: (2.0 * (k * k - 1.0) / a0) as f32,
+        a2: ((1.0 - k / q + k * k) / a0) as f32,
+    }
+}
+
+/// K 特性の 2 段目（RLB 特性のハイパス）の係数
+fn k_weighting_high_pass(sample_rate: f32) -> BiquadCoefficients {
+    let (f0, q) = (38.135_47_f64, 0.500_327_f64);
+    let k = (core::f64::consts::PI * f0 / sample_rate as f64).tan();
+    let a0 = 1.0 + k / q + k * k;
+    BiquadCoefficients {
+        b0: 1.0,
+        b1: -2.0,
+        b2: 1.0,
+        a1: (2.0 * (k * k - 1.0) / a0) as f32,
+        a2: ((1.0 - k / q + k * k) / a0) as f32,
+    }
+}
+
+/// 測定したラウドネスをメインスレッドから読み出すためのハンドル
+///
+/// LoudnessMeter が 100ms ごとに書き込んだ値を、ロックせずに読み出せます。
+/// `LoudnessMeter::readout` で取得し、ノードをグラフに追加する前に保持してください。
+/// まだ測定できていない値は負の無限大になります。
+pub struct LoudnessReadout {
+    /// モーメンタリーラウドネス（f32 のビット表現）
+    momentary: AtomicU32,
+    /// ショートタームラウドネス（f32 のビット表現）
+    short_term: AtomicU32,
+    /// インテグレーテッドラウドネス（f32 のビット表現）
+    integrated: AtomicU32,
+    /// トゥルーピーク（f32 のビット表現）
+    true_peak: AtomicU32,
+}
+
+impl LoudnessReadout {
+    fn new() -> Self {
+        let silence = f32::NEG_INFINITY.to_bits();
+        Self {
+            momentary: AtomicU32::new(silence),
+            short_term: AtomicU32::new(silence),
+            integrated: AtomicU32::new(silence),
+            true_peak: AtomicU32::new(silence),
+        }
+    }
+
+    /// 直近 400ms のモーメンタリーラウドネス（LUFS）
+    pub fn momentary_lufs(&self) -> f32 {
+        f32::from_bits(self.momentary.load(Ordering::Relaxed))
+    }
+
+    /// 直近 3 秒のショートタームラウドネス（LUFS）
+    pub fn short_term_lufs(&self) -> f32 {
+        f32::from_bits(self.short_term.load(Ordering::Relaxed))
+    }
+
+    /// 測定を始めてからの、ゲートをかけたインテグレーテッドラウドネス（LUFS）
+    pub fn integrated_lufs(&self) -> f32 {
+        f32::from_bits(self.integrated.load(Ordering::Relaxed))
+    }
+
+    /// 測定を始めてからのトゥルーピーク（dBTP）
+    pub fn true_peak_db(&self) -> f32 {
+        f32::from_bits(self.true_peak.load(Ordering::Relaxed))
+    }
+
+    fn store(&self, momentary: f32, short_term: f32, integrated: f32, true_peak: f32) {
+        self.momentary.store(momentary.to_bits(), Ordering::Relaxed);
+        self.short_term
+            .store(short_term.to_bits(), Ordering::Relaxed);
+        self.integrated
+            .store(integrated.to_bits(), Ordering::Relaxed);
+        self.true_peak.store(true_peak.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// ITU-R BS.1770 / EBU R128 に基づいてラウドネスを測定するラウドネスメーター
+///
+/// K 特性のフィルターをかけた入力から、モーメンタリー（400ms）・ショートターム（3 秒）・
+/// インテグレーテッド（絶対ゲート -70 LUFS と相対ゲート -10 LU をかけた全体）のラウドネスと、
+/// 4 倍オーバーサンプリングで求めたトゥルーピークを 100ms ごとに測定します。
+/// 測定した値は `readout` のハンドルからメインスレッドで読み出せます。入力はそのまま出力します。
+///
+/// インテグレーテッドラウドネスは、メモリアロケーションを避けるため 400ms のブロックを 0.1 LU 幅の
+/// ヒストグラムに集計して求めます。ゲートの判定はビンの単位で行います。
+/// `reset` で測定をやり直します。
+pub struct LoudnessMeter {
+    /// 測定値を公開するハンドル
+    readout: Arc<LoudnessReadout>,
+    /// チャンネルごとの K 特性のフィルター
+    filters: [[Biquad; 2]; NUM_CHANNELS],
+    /// 100ms の区間のサンプル数
+    sub_block_len: usize,
+    /// 現在の区間に入力したサンプル数
+    sub_block_position: usize,
+    /// 現在の区間の二乗和
+    sub_block_sum: f64,
+    /// 直近の区間の平均二乗値（リングバッファ）
+    sub_blocks: [f64; SHORT_TERM_SUB_BLOCKS],
+    /// これまでに測定した区間の数
+    num_sub_blocks: usize,
+    /// 400ms のブロックの数のヒストグラム
+    histogram_counts: [u32; HISTOGRAM_BINS],
+    /// ヒストグラムのビンごとの、ブロックの平均二乗値の和
+    histogram_energies: [f64; HISTOGRAM_BINS],
+    /// トゥルーピークの補間フィルターの係数（位相ごと）
+    interpolation: [[f32; TAPS_PER_PHASE]; OVERSAMPLING],
+    /// チャンネルごとの、補間フィルターに入力した直近のサンプル（新しい順）
+    peak_history: [[f32; TAPS_PER_PHASE]; NUM_CHANNELS],
+    /// 測定を始めてからの最大のトゥルーピーク（振幅）
+    true_peak: f32,
+}
+
+impl LoudnessMeter {
+    /// 新しいLoudnessMeterを作成
+    pub fn new() -> Self {
+        let mut meter = Self {
+            readout: Arc::new(LoudnessReadout::new()),
+            filters: core::array::from_fn(|_| [Biquad::new(), Biquad::new()]),
+            sub_block_len: 4410,
+            sub_block_position: 0,
+            sub_block_sum: 0.0,
+            sub_blocks: [0.0; SHORT_TERM_SUB_BLOCKS],
+            num_sub_blocks: 0,
+            histogram_counts: [0; HISTOGRAM_BINS],
+            histogram_energies: [0.0; HISTOGRAM_BINS],
+            interpolation: Self::interpolation_filter(),
+            peak_history: [[0.0; TAPS_PER_PHASE]; NUM_CHANNELS],
+            true_peak: 0.0,
+        };
+        meter.prepare_filters(44100.0); // デフォルトのサンプルレート
+        meter
+    }
+
+    /// 測定値を読み出すハンドルを取得する
+    pub fn readout(&self) -> Arc<LoudnessReadout> {
+        self.readout.clone()
+    }
+
+    fn prepare_filters(&mut self, sample_rate: f32) {
+        let shelf = k_weighting_shelf(sample_rate);
+        let high_pass = k_weighting_high_pass(sample_rate);
+        for [first, second] in self.filters.iter_mut() {
+            first.set_coefficients(shelf);
+            second.set_coefficients(high_pass);
+        }
+        self.sub_block_len = ((sample_rate * 0.1).round() as usize).max(1);
+    }
+
+    /// 4 倍に補間する窓関数付き sinc のフィルターを、位相ごとの係数に分ける
+    fn interpolation_filter() -> [[f32; TAPS_PER_PHASE]; OVERSAMPLING] {
+        let len = OVERSAMPLING * TAPS_PER_PHASE;
+        let center = (len - 1) as f64 / 2.0;
+        let tap = |n: usize| {
+            let x = (n as f64 - center) / OVERSAMPLING as f64;
+            let sinc = if x == 0.0 {
+                1.0
+            } else {
+                (core::f64::consts::PI * x).sin() / (core::f64::consts::PI * x)
+            };
+            let window = 0.5 - 0.5 * (core::f64::consts::TAU * (n as f64 + 0.5) / len as f64).cos();
+            (sinc * window) as f32
+        };
+        core::array::from_fn(|phase| core::array::from_fn(|k| tap(phase + OVERSAMPLING * k)))
+    }
+
+    /// 1 サンプルを補間フィルターに入力して、補間したサンプルの最大の絶対値を返す
+    fn oversampled_peak(&mut self, ch: usize, sample: f32) -> f32 {
+        let history = &mut self.peak_history[ch];
+        history.copy_within(..TAPS_PER_PHASE - 1, 1);
+        history[0] = sample;
+        self.interpolation
+            .iter()
+            .map(|phase| {
+                phase
+                    .iter()
+                    .zip(history.iter())
+                    .map(|(h, x)| h * x)
+                    .sum::<f32>()
+                    .abs()
+            })
+            .fold(sample.abs(), f32::max)
+    }
+
+    /// 100ms の区間が終わったときに、ラウドネスを計算してハンドルに書き込む
+    fn finish_sub_block(&mut self) {
+        let index = self.num_sub_blocks % SHORT_TERM_SUB_BLOCKS;
+        self.sub_blocks[index] = self.sub_block_sum / self.sub_block_len as f64;
+        self.num_sub_blocks += 1;
+        self.sub_block_sum = 0.0;
+        self.sub_block_position = 0;
+
+        // 直近の区間の平均。測定を始めた直後は、測定した区間だけで平均する
+        let recent_energy = |count: usize| {
+            let count = count.min(self.num_sub_blocks);
+            let sum: f64 = (0..count)
+                .map(|i| self.sub_blocks[(self.num_sub_blocks - 1 - i) % SHORT_TERM_SUB_BLOCKS])
+                .sum();
+            sum / count as f64
+        };
+        let momentary = recent_energy(MOMENTARY_SUB_BLOCKS);
+        let short_term = recent_energy(SHORT_TERM_SUB_BLOCKS);
+
+        // 400ms のブロックがそろったら、75% ずつ重ねてヒストグラムに集計する
+        if self.num_sub_blocks >= MOMENTARY_SUB_BLOCKS {
+            let loudness = energy_to_lufs(momentary) as f64;
+            if loudness >= ABSOLUTE_GATE {
+                let bin = ((loudness - ABSOLUTE_GATE) / HISTOGRAM_STEP) as usize;
+                let bin = bin.min(HISTOGRAM_BINS - 1);
+                self.histogram_counts[bin] += 1;
+                self.histogram_energies[bin] += momentary;
+            }
+        }
+
+        self.readout.store(
+            energy_to_lufs(momentary),
+            energy_to_lufs(short_term),
+            self.integrated_lufs(),
+            20.0 * self.true_peak.log10(),
+        );
+    }
+
+    /// ヒストグラムから、相対ゲートをかけたインテグレーテッドラウドネスを求める
+    fn integrated_lufs(&self) -> f32 {
+        let gated_energy = |first_bin: usize| {
+            let (count, energy) = self.histogram_counts[first_bin..]
+                .iter()
+                .zip(&self.histogram_energies[first_bin..])
+                .fold((0u64, 0.0), |(count, energy), (&c, &e)| {
+                    (count + c as u64, energy + e)
+                });
+            (count > 0).then(|| energy / count as f64)
+        };
+        let Some(absolute) = gated_energy(0) else {
+            return f32::NEG_INFINITY;
+        };
+        let threshold = energy_to_lufs(absolute) as f64 + RELATIVE_GATE;
+        let first_bin = (((threshold - ABSOLUTE_GATE) / HISTOGRAM_STEP)
+            .ceil()
+            .max(0.0) as usize)
+            .min(HISTOGRAM_BINS - 1);
+        gated_energy(first_bin).map_or(f32::NEG_INFINITY, energy_to_lufs)
+    }
+}
+
+impl AudioGraphNode for LoudnessMeter {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.prepare_filters(sample_rate);
+        self.reset();
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        for i in 0..buffer.num_frames() {
+            let frame = buffer.get_frame(i);
+            for (ch, &sample) in frame.iter().enumerate().take(NUM_CHANNELS) {
+                let [first, second] = &mut self.filters[ch];
+                let weighted = second.process(first.process(sample)) as f64;
+                self.sub_block_sum += weighted * weighted;
+                let peak = self.oversampled_peak(ch, sample);
+                self.true_peak = self.true_peak.max(peak);
+            }
+            self.sub_block_position += 1;
+            if self.sub_block_position >= self.sub_block_len {
+                self.finish_sub_block();
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for filter in self.filters.iter_mut().flatten() {
+            filter.reset();
+        }
+        self.sub_block_position = 0;
+        self.sub_block_sum = 0.0;
+        self.sub_blocks = [0.0; SHORT_TERM_SUB_BLOCKS];
+        self.num_sub_blocks = 0;
+        self.histogram_counts = [0; HISTOGRAM_BINS];
+        self.histogram_energies = [0.0; HISTOGRAM_BINS];
+        self.peak_history = [[0.0; TAPS_PER_PHASE]; NUM_CHANNELS];
+        self.true_peak = 0.0;
+        let silence = f32::NEG_INFINITY;
+        self.readout.store(silence, silence, silence, silence);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loudness_meter_sine() {
+        // -20 dBFS の 1kHz のサイン波をステレオで入力すると、約 -20 LUFS になる
+        const SAMPLE_RATE: f32 = 48000.0;
+        const FRAMES: usize = 48000 * 5;
+        let mut meter = LoudnessMeter::new();
+        let readout = meter.readout();
+        meter.prepare(SAMPLE_RATE, FRAMES);
+
+        let amplitude = 10.0_f32.powf(-20.0 / 20.0);
+        let mut vector: Vec<f32> = (0..FRAMES * 2)
+            .map(|i| {
+                let t = (i / 2) as f32 / SAMPLE_RATE;
+                amplitude * (core::f32::consts::TAU * 1000.0 * t).sin()
+            })
+            .collect();
+        let expected = vector.clone();
+        for chunk in vector.chunks_mut(512 * 2) {
+            let frames = chunk.len() / 2;
+            meter.process(&mut AudioBuffer::new(2, frames, chunk));
+        }
+        assert_eq!(vector, expected);
+
+        for lufs in [
+            readout.momentary_lufs(),
+            readout.short_term_lufs(),
+            readout.integrated_lufs(),
+        ] {
+            assert!((lufs + 20.0).abs() < 0.1, "{}", lufs);
+        }
+        assert!((readout.true_peak_db() + 20.0).abs() < 0.1);
+
+        // 無音のブロックは絶対ゲートで除かれ、無音との境界のブロックの分だけわずかに下がる
+        let mut silence = vec![0.0; 48000 * 2];
+        meter.process(&mut AudioBuffer::new(2, 48000, &mut silence));
+        assert!(readout.momentary_lufs() < -70.0);
+        let integrated = readout.integrated_lufs();
+        assert!(integrated < -20.05 && integrated > -20.3, "{}", integrated);
+
+        meter.reset();
+        assert_eq!(readout.integrated_lufs(), f32::NEG_INFINITY);
+    }
+}
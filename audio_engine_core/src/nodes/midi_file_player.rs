@@ -0,0 +1,378 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer, audio_graph::AudioGraphNode, midi::NoteEvent, midi_file::MidiFile,
+    parameter::ParameterInfo, transport::Transport,
+};
+
+/// MidiFilePlayer が公開するパラメーター
+const PARAMETERS: [ParameterInfo; 1] = [ParameterInfo {
+    id: "loop",
+    name: "Loop",
+    min: 0.0,
+    max: 1.0,
+    default: 0.0,
+    unit: "",
+}];
+
+/// 再生位置が前のブロックの終わりと一致しているとみなす誤差（拍）
+const POSITION_TOLERANCE: f64 = 1e-6;
+
+/// MIDI ファイルをトランスポートに合わせて再生し、ノートイベントを MIDI の送り先に送るノード
+///
+/// MIDI ファイルの拍の位置をトランスポートの再生位置（`position_beats`）に合わせ、各ブロックの範囲に入るイベントを
+/// ブロック内のタイミング付きで generate_note_events から出力します。イベントは AudioGraph::add_midi_route で
+/// 接続したシンセなどのノードに送られ、テンポの変更もトランスポートに従って反映されます。
+/// トランスポートが停止したときと、再生位置が飛んだとき（シーク）には、鳴っているノートを止めます。
+///
+/// オーディオは処理せず、入力をそのまま出力します。
+pub struct MidiFilePlayer {
+    /// 再生する MIDI ファイル
+    file: MidiFile,
+    /// サンプリングレート
+    sample_rate: f32,
+    /// ブロックの先頭のトランスポート
+    transport: Transport,
+    /// 前のブロックの終わりの再生位置（拍）。停止中やリセット後は None
+    expected_position: Option<f64>,
+    /// 次に送るイベントの番号
+    next_event: usize,
+    /// 曲の終わりで先頭に戻るかどうか
+    looping: bool,
+    /// 鳴っているノート（チャンネルごとのノート番号のビット集合）
+    active_notes: [u128; 16],
+}
+
+impl MidiFilePlayer {
+    /// MIDI ファイルから新しいMidiFilePlayerを作成
+    pub fn new(file: MidiFile) -> Self {
+        Self {
+            file,
+            sample_rate: 44100.0,
+            transport: Transport::new(),
+            expected_position: None,
+            next_event: 0,
+            looping: false,
+            active_notes: [0; 16],
+        }
+    }
+
+    /// 曲の終わりで先頭に戻るかどうかを設定
+    ///
+    /// ループ中は、トランスポートの再生位置を曲の長さで割った余りの位置を再生します。
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// ループ再生するかどうか（曲の長さが 0 の場合はループしない）
+    fn is_looping(&self) -> bool {
+        self.looping && self.file.duration_beats > 0.0
+    }
+
+    /// トランスポートの再生位置に対応する、曲の中の位置（拍）
+    fn file_position(&self, position_beats: f64) -> f64 {
+        if self.is_looping() {
+            position_beats.rem_euclid(self.file.duration_beats)
+        } else {
+            position_beats
+        }
+    }
+
+    /// 次に送るイベントを、曲の中の位置に合わせる
+    fn locate(&mut self, file_position: f64) {
+        self.next_event = self
+            .file
+            .events
+            .partition_point(|event| event.beat < file_position);
+    }
+
+    /// 鳴っているノートを全て止める
+    fn all_notes_off(&mut self, timing: u32, output: &mut dyn FnMut(NoteEvent)) {
+        for (channel, notes) in self.active_notes.iter_mut().enumerate() {
+            while *notes != 0 {
+                let note = notes.trailing_zeros() as u8;
+                *notes &= !(1 << note);
+                output(NoteEvent::NoteOff {
+                    timing,
+                    channel: channel as u8,
+                    note,
+                    velocity: 0.0,
+                });
+            }
+        }
+    }
+
+    /// 曲の中の `[start, end)` の範囲に入るイベントを出力する
+    ///
+    /// # 引数
+    /// * `start` / `end` - 曲の中の範囲（拍）
+    /// * `offset` - `start` のブロックの先頭からのオフセット（拍）
+    /// * `samples_per_beat` - 1 拍のサンプル数
+    fn emit_range(
+        &mut self,
+        start: f64,
+        end: f64,
+        offset: f64,
+        samples_per_beat: f64,
+        output: &mut dyn FnMut(NoteEvent),
+    ) {
+        while let Some(&file_event) = self.file.events.get(self.next_event) {
+            if file_event.beat >= end {
+                break;
+            }
+            self.next_event += 1;
+            let timing = ((file_event.beat - start + offset) * samples_per_beat)
+                .round()
+                .max(0.0) as u32;
+            let event = file_event.event;
+            match event {
+                NoteEvent::NoteOn { channel, note, .. } => {
+                    self.active_notes[channel as usize & 0x0f] |= 1 << (note & 0x7f);
+                }
+                NoteEvent::NoteOff { channel, note, .. } => {
+                    self.active_notes[channel as usize & 0x0f] &= !(1 << (note & 0x7f));
+                }
+                NoteEvent::Expression { .. } => {}
+            }
+            output(event.with_timing(timing));
+        }
+    }
+}
+
+impl AudioGraphNode for MidiFilePlayer {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+    }
+
+    fn process(&mut self, _buffer: &mut AudioBuffer) {}
+
+    fn reset(&mut self) {
+        self.expected_position = None;
+        self.next_event = 0;
+        self.active_notes = [0; 16];
+    }
+
+    fn update_transport(&mut self, transport: &Transport) {
+        self.transport = *transport;
+    }
+
+    fn generate_note_events(&mut self, num_frames: usize, output: &mut dyn FnMut(NoteEvent)) {
+        // 停止中は鳴っているノートを止め、再開したときに再生位置から探し直す
+        if !self.transport.playing || self.transport.tempo <= 0.0 {
+            self.all_notes_off(0, output);
+            self.expected_position = None;
+            return;
+        }
+
+        let samples_per_beat = self.transport.samples_per_beat(self.sample_rate);
+        let start = self.transport.position_beats;
+        let end = start + num_frames as f64 / samples_per_beat;
+
+        // 前のブロックの終わりから位置が飛んだ場合はシークとみなす
+        let is_continuous = self
+            .expected_position
+            .is_some_and(|expected| (expected - start).abs() <= POSITION_TOLERANCE);
+        if !is_continuous {
+            self.all_notes_off(0, output);
+            self.locate(self.file_position(start));
+        }
+        self.expected_position = Some(end);
+
+        if !self.is_looping() {
+            self.emit_range(start, end, 0.0, samples_per_beat, output);
+            return;
+        }
+
+        // 曲の終わりをまたぐ場合は、鳴っているノートを止めて先頭から続ける
+        let duration = self.file.duration_beats;
+        let mut position = start;
+        while position < end {
+            let file_start = self.file_position(position);
+            let file_end = (file_start + end - position).min(duration);
+            self.emit_range(
+                file_start,
+                file_end,
+                position - start,
+                samples_per_beat,
+                output,
+            );
+            position += file_end - file_start;
+            if file_end >= duration {
+                let timing = ((position - start) * samples_per_beat)
+                    .round()
+                    .min(num_frames.saturating_sub(1) as f64) as u32;
+                self.all_notes_off(timing, output);
+                self.next_event = 0;
+            }
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        match id {
+            "loop" => self.set_looping(value >= 0.5),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "loop" => Some(if self.looping { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_graph::AudioGraph;
+    use crate::midi_file::{MidiFileEvent, parse_midi_file, tests::midi_file_bytes};
+    use crate::nodes::{FmSynth, InputNode, OutputNode};
+    use crate::offline::OfflineRenderer;
+    use std::sync::{Arc, Mutex};
+
+    /// 受け取ったノートイベントを記録するノード
+    struct EventRecorder {
+        events: Arc<Mutex<Vec<NoteEvent>>>,
+    }
+
+    impl AudioGraphNode for EventRecorder {
+        fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+        fn process(&mut self, _buffer: &mut AudioBuffer) {}
+        fn reset(&mut self) {}
+        fn handle_note_event(&mut self, event: &NoteEvent) {
+            self.events.lock().unwrap().push(*event);
+        }
+    }
+
+    fn note_on(beat: f64, note: u8) -> MidiFileEvent {
+        MidiFileEvent {
+            time: beat * 0.5,
+            beat,
+            event: NoteEvent::NoteOn {
+                timing: 0,
+                channel: 0,
+                note,
+                velocity: 1.0,
+            },
+        }
+    }
+
+    /// 受け取ったイベントを (タイミング, ノート番号, ノートオンかどうか) で取り出す
+    fn take_events(events: &Mutex<Vec<NoteEvent>>) -> Vec<(u32, u8, bool)> {
+        events
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|event| match event {
+                NoteEvent::NoteOn { timing, note, .. } => (timing, note, true),
+                NoteEvent::NoteOff { timing, note, .. } => (timing, note, false),
+                NoteEvent::Expression { timing, .. } => (timing, 0, false),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_events_follow_transport() {
+        let file = MidiFile {
+            events: vec![note_on(0.0, 60), note_on(1.5, 64), note_on(3.0, 67)],
+            duration: 2.0,
+            duration_beats: 4.0,
+        };
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let player_id = graph.add_node(Box::new(MidiFilePlayer::new(file)));
+        let recorder_id = graph.add_node(Box::new(EventRecorder {
+            events: events.clone(),
+        }));
+        graph.add_midi_route(player_id, recorder_id).unwrap();
+        graph.prepare(1000.0, 1000);
+        let mut samples = vec![0.0; 2000];
+        let mut process = |graph: &mut AudioGraph| {
+            graph.process(
+                &mut AudioBuffer::new(2, 1000, &mut samples),
+                input_id,
+                output_id,
+            )
+        };
+
+        // 停止中は何も送らない
+        process(&mut graph);
+        assert!(take_events(&events).is_empty());
+
+        // 60 BPM では 1 秒（1000 フレーム）で 1 拍進む
+        let mut transport = graph.transport();
+        transport.tempo = 60.0;
+        transport.playing = true;
+        graph.set_transport(transport);
+        process(&mut graph);
+        assert_eq!(take_events(&events), vec![(0, 60, true)]);
+
+        // テンポを 120 BPM に上げると、1.5 拍目は 2 ブロック目の 250 フレーム目になる
+        let mut transport = graph.transport();
+        transport.tempo = 120.0;
+        graph.set_transport(transport);
+        process(&mut graph);
+        assert_eq!(take_events(&events), vec![(250, 64, true)]);
+
+        // 停止すると鳴っているノートを止める
+        let mut transport = graph.transport();
+        transport.playing = false;
+        graph.set_transport(transport);
+        process(&mut graph);
+        assert_eq!(take_events(&events), vec![(0, 60, false), (0, 64, false)]);
+
+        // 2.5 拍目にシークして再生すると、3 拍目のイベントが 250 フレーム目に送られる
+        let mut transport = graph.transport();
+        transport.position_beats = 2.5;
+        transport.playing = true;
+        graph.set_transport(transport);
+        process(&mut graph);
+        assert_eq!(take_events(&events), vec![(250, 67, true)]);
+
+        // 先頭へ戻すと、鳴っているノートを止めてから先頭から送り直す
+        let mut transport = graph.transport();
+        transport.position_beats = 0.0;
+        graph.set_transport(transport);
+        process(&mut graph);
+        assert_eq!(
+            take_events(&events),
+            vec![(0, 67, false), (0, 60, true), (750, 64, true)]
+        );
+    }
+
+    #[test]
+    fn test_render_midi_file_with_synth() {
+        // 1 拍のノートを 1 つ含む MIDI ファイル（96 ティック/四分音符）
+        let track: &[u8] = &[
+            0x00, 0x90, 69, 127, 0x60, 0x80, 69, 0, 0x00, 0xff, 0x2f, 0x00,
+        ];
+        let file = parse_midi_file(&midi_file_bytes(96, &[track])).unwrap();
+
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let player_id = graph.add_node(Box::new(MidiFilePlayer::new(file)));
+        let synth_id = graph.add_node(Box::new(FmSynth::new()));
+        graph.add_midi_route(player_id, synth_id).unwrap();
+        graph.add_edge(synth_id, output_id).unwrap();
+        let mut transport = graph.transport();
+        transport.playing = true;
+        graph.set_transport(transport);
+
+        let rendered =
+            OfflineRenderer::new(48000.0, 256).render(&mut graph, input_id, output_id, 24000);
+        let peak = rendered
+            .samples
+            .iter()
+            .fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak > 0.1);
+    }
+}
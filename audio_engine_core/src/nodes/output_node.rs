@@ -1,24 +1,296 @@
-use crate::{audio_buffer::AudioBuffer, audio_graph::AudioGraphNode};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    dsp::{Limiter, SmoothedValue},
+    parameter::{ParameterInfo, find_parameter},
+};
 
-/// 出力ノード - グラフの出力点を示すマーカーノード
-pub struct OutputNode {}
+/// 対応するチャンネル数
+const NUM_CHANNELS: usize = 2;
+
+/// リミッターのルックアヘッドの時間（ms）
+const LIMITER_LOOKAHEAD_MS: f32 = 5.0;
+
+/// マスターゲインの変化にかける時間（ms）
+const GAIN_RAMP_MS: f32 = 10.0;
+
+/// ピークホールドの期間が過ぎた後の減衰の速さ（dB/秒）
+const PEAK_DECAY_DB_PER_SECOND: f32 = 20.0;
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 5] = [
+    ParameterInfo {
+        id: "gain",
+        name: "Master Gain",
+        min: -60.0,
+        max: 12.0,
+        default: 0.0,
+        unit: "dB",
+    },
+    ParameterInfo {
+        id: "soft_clip",
+        name: "Soft Clip",
+        min: 0.0,
+        max: 1.0,
+        default: 0.0,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "limiter",
+        name: "Limiter",
+        min: 0.0,
+        max: 1.0,
+        default: 0.0,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "ceiling",
+        name: "Ceiling",
+        min: -24.0,
+        max: 0.0,
+        default: -1.0,
+        unit: "dB",
+    },
+    ParameterInfo {
+        id: "peak_hold",
+        name: "Peak Hold",
+        min: 0.0,
+        max: 10000.0,
+        default: 1000.0,
+        unit: "ms",
+    },
+];
+
+/// 出力ノード - グラフの出力点を示すノード
+///
+/// 出力の前段として、マスターゲイン・ソフトクリップ・安全のためのリミッターをこの順に適用し、
+/// 最後にチャンネルごとのピークをホールドします。いずれもパラメーターで設定でき、
+/// デフォルトではゲイン 0dB でソフトクリップとリミッターは無効のため、信号は変化しません。
+///
+/// リミッターを有効にすると、グラフの出力を天井（ceiling）以下に抑えます。
+/// フィードバックなどで信号が発散しても、スピーカーや耳を傷めないようにするためのものです。
+/// リミッターの有効・無効に関わらずリミッターは prepare で準備されるため、再生中に切り替えられます。
+/// 有効な間はルックアヘッドの分だけ出力が遅れます。
+///
+/// ホールドしたピーク（リニア）は `peak` か、読み取り専用のパラメーター
+/// `peak_left` / `peak_right` で取得できます。
+pub struct OutputNode {
+    /// マスターゲイン（リニア）
+    gain: SmoothedValue,
+    /// マスターゲイン（dB）
+    gain_db: f32,
+    /// ソフトクリップが有効かどうか
+    soft_clip: bool,
+    /// 安全のためのリミッター
+    limiter: Limiter,
+    /// リミッターが有効かどうか
+    limiter_enabled: bool,
+    /// ピークをホールドする時間（ms）
+    peak_hold_ms: f32,
+    /// ピークをホールドするサンプル数
+    peak_hold_samples: usize,
+    /// ホールド後の 1 サンプルあたりの減衰の係数
+    peak_decay: f32,
+    /// チャンネルごとのホールドしたピーク
+    peaks: [f32; NUM_CHANNELS],
+    /// チャンネルごとのホールドの残りサンプル数
+    peak_hold_remaining: [usize; NUM_CHANNELS],
+    /// サンプリングレート
+    sample_rate: f32,
+}
 
 impl OutputNode {
     pub fn new() -> Self {
-        Self {}
+        let mut limiter = Limiter::new();
+        limiter.set_ceiling_db(PARAMETERS[3].default);
+        Self {
+            gain: SmoothedValue::new(1.0),
+            gain_db: 0.0,
+            soft_clip: false,
+            limiter,
+            limiter_enabled: false,
+            peak_hold_ms: PARAMETERS[4].default,
+            peak_hold_samples: 0,
+            peak_decay: 1.0,
+            peaks: [0.0; NUM_CHANNELS],
+            peak_hold_remaining: [0; NUM_CHANNELS],
+            sample_rate: 44100.0,
+        }
+    }
+
+    /// マスターゲインを設定（dB）
+    pub fn set_gain_db(&mut self, gain_db: f32) {
+        self.gain_db = gain_db;
+        self.gain.set_target(10.0f32.powf(gain_db / 20.0));
+    }
+
+    /// ソフトクリップを有効にするかどうかを設定
+    pub fn set_soft_clip(&mut self, enabled: bool) {
+        self.soft_clip = enabled;
+    }
+
+    /// リミッターを有効にするかどうかを設定
+    pub fn set_limiter_enabled(&mut self, enabled: bool) {
+        if enabled && !self.limiter_enabled {
+            self.limiter.reset();
+        }
+        self.limiter_enabled = enabled;
+    }
+
+    /// リミッターの天井を設定（dB）
+    pub fn set_ceiling_db(&mut self, ceiling_db: f32) {
+        self.limiter.set_ceiling_db(ceiling_db);
+    }
+
+    /// ピークをホールドする時間を設定（ms）
+    pub fn set_peak_hold_ms(&mut self, ms: f32) {
+        self.peak_hold_ms = ms.max(0.0);
+        self.peak_hold_samples = (self.peak_hold_ms / 1000.0 * self.sample_rate) as usize;
+    }
+
+    /// ホールドしたピーク（リニア）
+    ///
+    /// # 引数
+    /// * `channel` - チャンネル番号（範囲外の場合は 0 を返す）
+    pub fn peak(&self, channel: usize) -> f32 {
+        self.peaks.get(channel).copied().unwrap_or(0.0)
+    }
+
+    /// ホールドしたピークをリセットする
+    pub fn reset_peaks(&mut self) {
+        self.peaks = [0.0; NUM_CHANNELS];
+        self.peak_hold_remaining = [0; NUM_CHANNELS];
     }
 }
 
 impl AudioGraphNode for OutputNode {
-    fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {
-        // 何もしない
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        self.gain.prepare(sample_rate, GAIN_RAMP_MS);
+        self.limiter
+            .prepare(sample_rate, NUM_CHANNELS, LIMITER_LOOKAHEAD_MS);
+        self.set_peak_hold_ms(self.peak_hold_ms);
+        self.peak_decay = 10.0f32.powf(-PEAK_DECAY_DB_PER_SECOND / 20.0 / sample_rate);
+        self.reset_peaks();
     }
 
-    fn process(&mut self, _buffer: &mut AudioBuffer) {
-        // 何もしない
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        // マスターゲインとソフトクリップ
+        let num_channels = buffer.num_channels();
+        if self.soft_clip || self.gain.is_smoothing() || self.gain.current() != 1.0 {
+            for frame in buffer.as_mut_slice().chunks_exact_mut(num_channels) {
+                let gain = self.gain.next_value();
+                for sample in frame.iter_mut() {
+                    *sample *= gain;
+                    if self.soft_clip {
+                        *sample = sample.tanh();
+                    }
+                }
+            }
+        }
+
+        if self.limiter_enabled {
+            self.limiter.process(buffer);
+        }
+
+        // チャンネルごとのピークホールド
+        for frame in buffer.as_slice().chunks_exact(num_channels) {
+            for (ch, sample) in frame.iter().enumerate().take(NUM_CHANNELS) {
+                let level = sample.abs();
+                if level >= self.peaks[ch] {
+                    self.peaks[ch] = level;
+                    self.peak_hold_remaining[ch] = self.peak_hold_samples;
+                } else if self.peak_hold_remaining[ch] > 0 {
+                    self.peak_hold_remaining[ch] -= 1;
+                } else {
+                    self.peaks[ch] = (self.peaks[ch] * self.peak_decay).max(level);
+                }
+            }
+        }
     }
 
     fn reset(&mut self) {
-        // 何もしない
+        self.limiter.reset();
+        self.reset_peaks();
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        let value = info.clamp(value);
+        match id {
+            "gain" => self.set_gain_db(value),
+            "soft_clip" => self.set_soft_clip(value >= 0.5),
+            "limiter" => self.set_limiter_enabled(value >= 0.5),
+            "ceiling" => self.set_ceiling_db(value),
+            _ => self.set_peak_hold_ms(value),
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "gain" => Some(self.gain_db),
+            "soft_clip" => Some(if self.soft_clip { 1.0 } else { 0.0 }),
+            "limiter" => Some(if self.limiter_enabled { 1.0 } else { 0.0 }),
+            "ceiling" => Some(self.limiter.ceiling_db()),
+            "peak_hold" => Some(self.peak_hold_ms),
+            "peak_left" => Some(self.peaks[0]),
+            "peak_right" => Some(self.peaks[1]),
+            _ => None,
+        }
+    }
+
+    fn latency_samples(&self) -> usize {
+        if self.limiter_enabled {
+            self.limiter.latency_samples()
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gain_soft_clip_and_peak_hold() {
+        let mut node = OutputNode::new();
+        node.prepare(1000.0, 64);
+
+        // デフォルトでは信号は変化せず、ピークだけを記録する
+        let mut samples = vec![0.5, -0.25, 2.0, 0.0];
+        node.process(&mut AudioBuffer::new(2, 2, &mut samples));
+        assert_eq!(samples, vec![0.5, -0.25, 2.0, 0.0]);
+        assert_eq!(node.get_parameter("peak_left"), Some(2.0));
+        assert_eq!(node.peak(1), 0.25);
+
+        // -6dB のゲイン（ランプの後）とソフトクリップ
+        assert!(node.set_parameter("gain", -6.0));
+        assert!(node.set_parameter("soft_clip", 1.0));
+        let mut samples = vec![4.0; 40];
+        node.process(&mut AudioBuffer::new(2, 20, &mut samples));
+        let expected = (4.0 * 10.0f32.powf(-6.0 / 20.0)).tanh();
+        assert!((samples[39] - expected).abs() < 1e-6);
+        assert!(samples.iter().all(|s| *s < 1.0));
+
+        // ホールドの期間（1000ms）が過ぎるとピークが減衰する
+        node.set_soft_clip(false);
+        node.set_gain_db(0.0);
+        node.process(&mut AudioBuffer::new(2, 20, &mut [0.0; 40]));
+        node.reset_peaks();
+        let mut samples = vec![0.0; 2 * 1200];
+        samples[0] = 0.5;
+        node.process(&mut AudioBuffer::new(2, 1200, &mut samples));
+        assert!((node.peak(0) - 0.5 * node.peak_decay.powi(199)).abs() < 1e-5);
+        assert!(node.peak(0) < 0.5);
     }
 }
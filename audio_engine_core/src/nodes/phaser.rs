@@ -0,0 +1,151 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    dsp::{FirstOrderAllpass, Lfo, SmoothedValue},
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// オールパスの最大段数
+const MAX_STAGES: usize = 12;
+
+/// N 段オールパスによるフェイザー
+///
+/// LFO でオールパスフィルターの周波数を変調し、原音と混ぜることでノッチを移動させます。
+pub struct Phaser {
+    /// チャンネル・段ごとのオールパスフィルター
+    stages: [[FirstOrderAllpass; MAX_STAGES]; NUM_CHANNELS],
+    /// チャンネルごとの LFO
+    lfos: [Lfo; NUM_CHANNELS],
+    /// チャンネルごとの直前の出力（フィードバック用）
+    last_output: [f32; NUM_CHANNELS],
+    /// 使用する段数
+    num_stages: usize,
+    /// 変調範囲の下限周波数（Hz）
+    min_frequency: f32,
+    /// 変調範囲の上限周波数（Hz）
+    max_frequency: f32,
+    /// フィードバック量（-0.95～0.95）
+    feedback: f32,
+    /// ウェット信号の割合（0～1）
+    mix: SmoothedValue,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl Phaser {
+    /// 新しいPhaserを作成
+    pub fn new() -> Self {
+        let mut phaser = Self {
+            stages: core::array::from_fn(|_| core::array::from_fn(|_| FirstOrderAllpass::new())),
+            lfos: [Lfo::new(), Lfo::new()],
+            last_output: [0.0; NUM_CHANNELS],
+            num_stages: 4,
+            min_frequency: 200.0,
+            max_frequency: 2000.0,
+            feedback: 0.3,
+            mix: SmoothedValue::new(0.5),
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        };
+        phaser.set_rate(0.5);
+        phaser.lfos[1].set_phase(0.25);
+        phaser
+    }
+
+    /// オールパスの段数を設定（2～12 の偶数に丸められます）
+    pub fn set_num_stages(&mut self, num_stages: usize) {
+        self.num_stages = (num_stages.clamp(2, MAX_STAGES) / 2) * 2;
+    }
+
+    /// LFO の周波数を設定（Hz）
+    pub fn set_rate(&mut self, rate: f32) {
+        for lfo in self.lfos.iter_mut() {
+            lfo.set_rate(rate);
+        }
+    }
+
+    /// 変調範囲を設定（Hz）
+    pub fn set_frequency_range(&mut self, min_frequency: f32, max_frequency: f32) {
+        self.min_frequency = min_frequency;
+        self.max_frequency = max_frequency;
+    }
+
+    /// フィードバック量を設定。発振を防ぐため -0.95～0.95 に制限されます。
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(-0.95, 0.95);
+    }
+
+    /// ウェット信号の割合を設定（0～1）
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+}
+
+impl AudioGraphNode for Phaser {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        for lfo in self.lfos.iter_mut() {
+            lfo.prepare(sample_rate);
+        }
+        self.mix.prepare(sample_rate, 20.0);
+        self.reset();
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let num_channels = buffer.num_channels().min(NUM_CHANNELS);
+        // 周波数は対数スケールで変調する
+        let ratio = self.max_frequency / self.min_frequency;
+        for i in 0..buffer.num_frames() {
+            let mix = self.mix.next_value();
+            let frame = buffer.get_mut_frame(i);
+            for (ch, sample) in frame.iter_mut().enumerate().take(num_channels) {
+                let modulation = (self.lfos[ch].next_value() + 1.0) * 0.5;
+                let frequency = self.min_frequency * ratio.powf(modulation);
+
+                let dry = *sample;
+                let mut wet = dry + self.last_output[ch] * self.feedback;
+                for stage in self.stages[ch].iter_mut().take(self.num_stages) {
+                    stage.set_frequency(frequency, self.sample_rate);
+                    wet = stage.process(wet);
+                }
+                self.last_output[ch] = wet;
+
+                *sample = dry * (1.0 - mix) + wet * mix;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for stage in self.stages.iter_mut().flatten() {
+            stage.reset();
+        }
+        for lfo in self.lfos.iter_mut() {
+            lfo.reset();
+        }
+        self.last_output = [0.0; NUM_CHANNELS];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phaser_output_is_finite_and_bounded() {
+        let mut phaser = Phaser::new();
+        phaser.set_mix(1.0);
+        phaser.set_feedback(0.9);
+        phaser.prepare(44100.0, 64);
+
+        let mut vector: Vec<f32> = (0..128)
+            .map(|i| if i % 2 == 0 { 1.0 } else { -1.0 })
+            .collect();
+        let mut buffer = AudioBuffer::new(2, 64, vector.as_mut_slice());
+        phaser.process(&mut buffer);
+
+        assert!(vector.iter().all(|s| s.is_finite() && s.abs() < 20.0));
+    }
+}
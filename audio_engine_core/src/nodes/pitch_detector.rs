@@ -0,0 +1,301 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, ProcessRate},
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// YIN の差分関数を積分する長さ（サンプル数）。検出できる最大の周期でもある
+const WINDOW_SIZE: usize = 1024;
+
+/// 検出できる最大の周波数（Hz）
+const MAX_FREQUENCY: f32 = 2000.0;
+
+/// これより小さい RMS の入力は無音として扱う
+const SILENCE_RMS: f32 = 1e-3;
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 1] = [ParameterInfo {
+    id: "threshold",
+    name: "Threshold",
+    min: 0.01,
+    max: 0.5,
+    default: 0.15,
+    unit: "",
+}];
+
+/// 検出したピッチをメインスレッドから読み出すためのハンドル
+///
+/// PitchDetector がオーディオスレッドで書き込んだ値を、ロックせずに読み出せます。
+/// `PitchDetector::readout` で取得し、ノードをグラフに追加する前に保持してください。
+pub struct PitchReadout {
+    /// 検出した基本周波数（f32 のビット表現）
+    frequency: AtomicU32,
+    /// 検出の確からしさ（f32 のビット表現）
+    confidence: AtomicU32,
+}
+
+impl PitchReadout {
+    fn new() -> Self {
+        Self {
+            frequency: AtomicU32::new(0.0f32.to_bits()),
+            confidence: AtomicU32::new(0.0f32.to_bits()),
+        }
+    }
+
+    /// 検出した基本周波数（Hz）。ピッチを検出できなかった場合は 0
+    pub fn frequency(&self) -> f32 {
+        f32::from_bits(self.frequency.load(Ordering::Relaxed))
+    }
+
+    /// 検出の確からしさ（0～1）
+    pub fn confidence(&self) -> f32 {
+        f32::from_bits(self.confidence.load(Ordering::Relaxed))
+    }
+
+    fn store(&self, frequency: f32, confidence: f32) {
+        self.frequency.store(frequency.to_bits(), Ordering::Relaxed);
+        self.confidence
+            .store(confidence.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// YIN アルゴリズムで入力の基本周波数を検出するピッチディテクター
+///
+/// 全チャンネルを平均したモノラル信号を、WINDOW_SIZE サンプルごとに直近の 2 × WINDOW_SIZE サンプルで解析します。
+/// 検出した基本周波数と確からしさは `readout` のハンドルからメインスレッドで読み出せるため、チューナーの表示に使えます。
+///
+/// オーディオレートでは入力をそのまま出力します。`set_process_rate` でコントロールレートにすると、
+/// 直近に検出した基本周波数（Hz）を出力するため、パラメーターポートに接続してピッチに追従するパッチを組めます。
+/// モジュレーションソースとしても直近に検出した基本周波数を返します。どちらもピッチを検出できない間は、
+/// 最後に検出した値を保持します。
+///
+/// # 実装時の注意
+/// 差分関数を直接計算するため、解析のたびに WINDOW_SIZE の 2 乗程度の計算を行います。
+pub struct PitchDetector {
+    /// メインスレッドに公開するハンドル
+    readout: Arc<PitchReadout>,
+    /// 入力の履歴のリングバッファ（長さ 2 × WINDOW_SIZE）
+    history: Vec<f32>,
+    /// 履歴の次に書き込む位置
+    position: usize,
+    /// 前回の解析からのサンプル数
+    hop_counter: usize,
+    /// 解析用に並べ替えた履歴
+    frame: Vec<f32>,
+    /// 累積平均で正規化した差分関数
+    difference: Vec<f32>,
+    /// 有声と判定する、正規化した差分関数のしきい値
+    threshold: f32,
+    /// 最後に検出した基本周波数（Hz）
+    frequency: f32,
+    /// サンプリングレート
+    sample_rate: f32,
+    /// 処理する頻度
+    process_rate: ProcessRate,
+}
+
+impl PitchDetector {
+    /// 新しいPitchDetectorを作成
+    pub fn new() -> Self {
+        Self {
+            readout: Arc::new(PitchReadout::new()),
+            history: vec![0.0; WINDOW_SIZE * 2],
+            position: 0,
+            hop_counter: 0,
+            frame: vec![0.0; WINDOW_SIZE * 2],
+            difference: vec![0.0; WINDOW_SIZE],
+            threshold: 0.15,
+            frequency: 0.0,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+            process_rate: ProcessRate::Audio,
+        }
+    }
+
+    /// 検出したピッチを読み出すハンドルを取得する
+    pub fn readout(&self) -> Arc<PitchReadout> {
+        self.readout.clone()
+    }
+
+    /// 有声と判定するしきい値を設定（小さいほど厳しい）
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = PARAMETERS[0].clamp(threshold);
+    }
+
+    /// 処理する頻度を設定
+    pub fn set_process_rate(&mut self, process_rate: ProcessRate) {
+        self.process_rate = process_rate;
+    }
+
+    /// 最後に検出した基本周波数（Hz）
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    /// モノラルにした 1 サンプルを履歴に追加し、WINDOW_SIZE サンプルごとに解析する
+    fn push(&mut self, sample: f32) {
+        self.history[self.position] = sample;
+        self.position = (self.position + 1) % self.history.len();
+        self.hop_counter += 1;
+        if self.hop_counter >= WINDOW_SIZE {
+            self.hop_counter = 0;
+            self.analyze();
+        }
+    }
+
+    /// 直近の履歴から基本周波数を検出して、ハンドルに書き込む
+    fn analyze(&mut self) {
+        let len = self.history.len();
+        for (i, x) in self.frame.iter_mut().enumerate() {
+            *x = self.history[(self.position + i) % len];
+        }
+
+        let rms = (self.frame.iter().map(|x| x * x).sum::<f32>() / len as f32).sqrt();
+        if rms < SILENCE_RMS {
+            self.readout.store(0.0, 0.0);
+            return;
+        }
+
+        // 差分関数を累積平均で正規化する（YIN のステップ 2、3）
+        let min_period = ((self.sample_rate / MAX_FREQUENCY) as usize).clamp(2, WINDOW_SIZE - 2);
+        self.difference[0] = 1.0;
+        let mut sum = 0.0;
+        for tau in 1..WINDOW_SIZE {
+            let d: f32 = self.frame[..WINDOW_SIZE]
+                .iter()
+                .zip(&self.frame[tau..tau + WINDOW_SIZE])
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            sum += d;
+            self.difference[tau] = if sum > 0.0 { d * tau as f32 / sum } else { 1.0 };
+        }
+
+        // しきい値を下回った最初の谷を周期とする（YIN のステップ 4）
+        let difference = &self.difference;
+        let mut period = None;
+        let mut tau = min_period;
+        while tau < WINDOW_SIZE - 1 {
+            if difference[tau] < self.threshold {
+                while tau + 1 < WINDOW_SIZE - 1 && difference[tau + 1] < difference[tau] {
+                    tau += 1;
+                }
+                period = Some(tau);
+                break;
+            }
+            tau += 1;
+        }
+        let Some(tau) = period else {
+            let minimum = difference[min_period..]
+                .iter()
+                .fold(1.0_f32, |a, &b| a.min(b));
+            self.readout.store(0.0, (1.0 - minimum).max(0.0));
+            return;
+        };
+
+        // 放物線で補間して周期を小数サンプルで求める（YIN のステップ 5）
+        let (a, b, c) = (difference[tau - 1], difference[tau], difference[tau + 1]);
+        let denominator = a - 2.0 * b + c;
+        let offset = if denominator.abs() > f32::EPSILON {
+            (0.5 * (a - c) / denominator).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+        self.frequency = self.sample_rate / (tau as f32 + offset);
+        self.readout
+            .store(self.frequency, (1.0 - b).clamp(0.0, 1.0));
+    }
+}
+
+impl AudioGraphNode for PitchDetector {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let scale = 1.0 / buffer.num_channels().max(1) as f32;
+        for i in 0..buffer.num_frames() {
+            let sample = buffer.get_frame(i).iter().sum::<f32>() * scale;
+            self.push(sample);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.position = 0;
+        self.hop_counter = 0;
+        self.frequency = 0.0;
+        self.readout.store(0.0, 0.0);
+    }
+
+    fn process_rate(&self) -> ProcessRate {
+        self.process_rate
+    }
+
+    fn process_control(&mut self, input: &AudioBuffer) -> f32 {
+        let scale = 1.0 / input.num_channels().max(1) as f32;
+        for frame in input.frames() {
+            self.push(frame.iter().sum::<f32>() * scale);
+        }
+        self.frequency
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        if find_parameter(&PARAMETERS, id).is_none() {
+            return false;
+        }
+        self.set_threshold(value);
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "threshold" => Some(self.threshold),
+            _ => None,
+        }
+    }
+
+    fn modulation_output(&self) -> Option<f32> {
+        Some(self.frequency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pitch_detector_detects_sine() {
+        let mut detector = PitchDetector::new();
+        let readout = detector.readout();
+        detector.prepare(44100.0, 4096);
+
+        let mut sine: Vec<f32> = (0..4096)
+            .map(|i| 0.5 * (core::f32::consts::TAU * 220.0 * i as f32 / 44100.0).sin())
+            .collect();
+        let expected = sine.clone();
+        detector.process(&mut AudioBuffer::new(1, 4096, &mut sine));
+
+        // 入力はそのまま出力され、検出した周波数はハンドルから読み出せる
+        assert_eq!(sine, expected);
+        assert!(
+            (readout.frequency() - 220.0).abs() < 0.5,
+            "{}",
+            readout.frequency()
+        );
+        assert!(readout.confidence() > 0.9);
+        assert_eq!(detector.modulation_output(), Some(readout.frequency()));
+
+        // 無音ではピッチを検出しないが、モジュレーションの出力は最後の値を保持する
+        let mut silence = vec![0.0; 4096];
+        detector.process(&mut AudioBuffer::new(1, 4096, &mut silence));
+        assert_eq!(readout.frequency(), 0.0);
+        assert!((detector.frequency() - 220.0).abs() < 2.2);
+    }
+}
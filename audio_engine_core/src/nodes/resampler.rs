@@ -0,0 +1,108 @@
+use crate::{
+    audio_buffer::AudioBuffer, audio_graph::AudioGraphNode, dsp::RateConverter, midi::NoteEvent,
+    parameter::ParameterInfo, transport::Transport, tuning::Tuning,
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// ラップしたノードを、グラフとは別のサンプリングレートで動かすノード
+///
+/// 入力をポリフェーズの窓付き sinc 補間で内部レートに変換してからラップしたノードで処理し、
+/// 結果をグラフのレートに戻します。特定のサンプリングレートを前提にしたノードを、
+/// 任意のレートのグラフで使う場合に利用します。変換による遅延は `latency_samples` で報告します。
+pub struct Resampler<T: AudioGraphNode> {
+    /// ラップしたノード
+    inner: T,
+    /// 内部のサンプリングレート
+    internal_sample_rate: f32,
+    /// レート変換器
+    converter: RateConverter,
+    /// グラフのサンプリングレート
+    sample_rate: f32,
+}
+
+impl<T: AudioGraphNode> Resampler<T> {
+    /// ノードをラップした新しいResamplerを作成
+    ///
+    /// # 引数
+    /// * `inner` - 内部レートで動かすノード
+    /// * `internal_sample_rate` - 内部のサンプリングレート（Hz）
+    pub fn new(inner: T, internal_sample_rate: f32) -> Self {
+        Self {
+            inner,
+            internal_sample_rate,
+            converter: RateConverter::new(),
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// ラップしたノードへの参照を取得
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// ラップしたノードへの可変参照を取得
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: AudioGraphNode> AudioGraphNode for Resampler<T> {
+    fn prepare(&mut self, sample_rate: f32, max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        self.converter.prepare(
+            NUM_CHANNELS,
+            sample_rate,
+            self.internal_sample_rate,
+            max_num_samples,
+        );
+        self.inner
+            .prepare(self.internal_sample_rate, self.converter.max_inner_frames());
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let inner = &mut self.inner;
+        self.converter.process(buffer, |b| inner.process(b));
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.converter.reset();
+    }
+
+    fn handle_note_event(&mut self, event: &NoteEvent) {
+        // タイミングは内部レートのサンプル数に換算する
+        let ratio = self.internal_sample_rate / self.sample_rate;
+        let timing = (event.timing() as f32 * ratio) as u32;
+        self.inner.handle_note_event(&event.with_timing(timing));
+    }
+
+    fn update_transport(&mut self, transport: &Transport) {
+        self.inner.update_transport(transport);
+    }
+
+    fn set_tuning(&mut self, tuning: &Tuning) {
+        self.inner.set_tuning(tuning);
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        self.inner.parameters()
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        self.inner.set_parameter(id, value)
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        self.inner.get_parameter(id)
+    }
+
+    fn modulation_output(&self) -> Option<f32> {
+        self.inner.modulation_output()
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.converter.latency_frames()
+    }
+}
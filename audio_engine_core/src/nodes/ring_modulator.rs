@@ -0,0 +1,137 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, InputPorts},
+    dsp::SmoothedValue,
+};
+
+/// キャリア入力のポート番号
+pub const RING_MODULATOR_CARRIER_PORT: usize = 1;
+
+/// リングモジュレーターのキャリアの種類
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CarrierSource {
+    /// 内部のサイン波オシレーター
+    Internal,
+    /// キャリア入力ポート（ポート 1）に接続された信号
+    External,
+}
+
+/// 入力信号にキャリアを掛け合わせるリングモジュレーター
+///
+/// キャリアは内部のサイン波オシレーターか、ポート 1 に接続された信号のどちらかを使います。
+pub struct RingModulator {
+    /// キャリアの種類
+    carrier_source: CarrierSource,
+    /// 内部キャリアの周波数（Hz）
+    frequency: f32,
+    /// 内部キャリアの位相（0～1の範囲で保持）
+    phase: f32,
+    /// ウェット信号の割合（0～1）
+    mix: SmoothedValue,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl RingModulator {
+    /// 新しいRingModulatorを作成
+    pub fn new() -> Self {
+        Self {
+            carrier_source: CarrierSource::Internal,
+            frequency: 440.0,
+            phase: 0.0,
+            mix: SmoothedValue::new(1.0),
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// キャリアの種類を設定
+    pub fn set_carrier_source(&mut self, carrier_source: CarrierSource) {
+        self.carrier_source = carrier_source;
+    }
+
+    /// 内部キャリアの周波数を設定（Hz）
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+
+    /// ウェット信号の割合を設定（0～1）
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+
+    /// 内部キャリアを1サンプル進める
+    fn next_internal_carrier(&mut self) -> f32 {
+        let carrier = (self.phase * core::f32::consts::TAU).sin();
+        self.phase += self.frequency / self.sample_rate;
+        self.phase -= self.phase.floor();
+        carrier
+    }
+}
+
+impl AudioGraphNode for RingModulator {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        self.mix.prepare(sample_rate, 20.0);
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        self.process_with_inputs(buffer, &InputPorts::empty());
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+
+    fn num_input_ports(&self) -> usize {
+        2
+    }
+
+    fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, inputs: &InputPorts) {
+        let num_channels = buffer.num_channels();
+        let external = match self.carrier_source {
+            CarrierSource::External => inputs.get(RING_MODULATOR_CARRIER_PORT),
+            CarrierSource::Internal => None,
+        };
+        for i in 0..buffer.num_frames() {
+            let mix = self.mix.next_value();
+            let internal_carrier = match self.carrier_source {
+                CarrierSource::Internal => self.next_internal_carrier(),
+                CarrierSource::External => 0.0,
+            };
+            let frame = buffer.get_mut_frame(i);
+            for (ch, sample) in frame.iter_mut().enumerate() {
+                // 外部キャリアが未接続の場合は 0 として扱う
+                let carrier = match external {
+                    Some(carrier) => carrier[i * num_channels + ch],
+                    None => internal_carrier,
+                };
+                let dry = *sample;
+                *sample = dry * (1.0 - mix) + dry * carrier * mix;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_modulator_with_external_carrier() {
+        let mut ring_modulator = RingModulator::new();
+        ring_modulator.set_carrier_source(CarrierSource::External);
+        ring_modulator.prepare(1000.0, 2);
+
+        let carrier = vec![vec![0.5, -1.0, 2.0, 0.0]];
+        let connected = vec![true];
+        let inputs = InputPorts::new(&carrier, &connected, 2, 2);
+
+        let mut vector: Vec<f32> = vec![1.0, 1.0, 0.5, 0.5];
+        let mut buffer = AudioBuffer::new(2, 2, vector.as_mut_slice());
+        ring_modulator.process_with_inputs(&mut buffer, &inputs);
+
+        assert_eq!(vector, vec![0.5, -1.0, 1.0, 0.0]);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::prelude::*;
+use crate::{audio_buffer::AudioBuffer, audio_graph::AudioGraphNode};
+
+/// メモリ上のサンプル列を先頭から再生するノード
+///
+/// AudioGraph::freeze でレンダリングしたサブグラフの代わりに使います。入力は無視し、サンプル列で上書きします。
+/// サンプル列の末尾に達した後は、ループしない場合は無音を出力します。
+pub struct SamplePlayer {
+    /// 再生するサンプル列（インターリーブ）
+    samples: Vec<f32>,
+    /// サンプル列のチャンネル数
+    channels: usize,
+    /// 次に再生するフレームの位置
+    position: usize,
+    /// 末尾に達したら先頭に戻るかどうか
+    looping: bool,
+}
+
+impl SamplePlayer {
+    /// 新しいSamplePlayerを作成
+    ///
+    /// # 引数
+    /// * `samples` - 再生するサンプル列（インターリーブ）
+    /// * `channels` - サンプル列のチャンネル数
+    pub fn new(samples: Vec<f32>, channels: usize) -> Self {
+        Self {
+            samples,
+            channels: channels.max(1),
+            position: 0,
+            looping: false,
+        }
+    }
+
+    /// 末尾に達したら先頭に戻るかどうかを設定
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// サンプル列のフレーム数
+    pub fn num_frames(&self) -> usize {
+        self.samples.len() / self.channels
+    }
+}
+
+impl AudioGraphNode for SamplePlayer {
+    fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {
+        // 何もしない
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let num_frames = self.num_frames();
+        for i in 0..buffer.num_frames() {
+            if self.looping && self.position >= num_frames && num_frames > 0 {
+                self.position = 0;
+            }
+            let frame = buffer.get_mut_frame(i);
+            if self.position < num_frames {
+                // チャンネル数が異なる場合は、サンプル列のチャンネルを繰り返して割り当てる
+                let source = &self.samples[self.position * self.channels..][..self.channels];
+                for (ch, sample) in frame.iter_mut().enumerate() {
+                    *sample = source[ch % self.channels];
+                }
+                self.position += 1;
+            } else {
+                frame.fill(0.0);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.position = 0;
+    }
+}
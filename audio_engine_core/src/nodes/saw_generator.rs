@@ -1,6 +1,27 @@
-use crate::{audio_buffer::AudioBuffer, audio_graph::AudioGraphNode};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, InputPorts},
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 1] = [ParameterInfo {
+    id: "frequency",
+    name: "Frequency",
+    min: 0.0,
+    max: 20000.0,
+    default: 440.0,
+    unit: "Hz",
+}];
+
+/// 周波数のパラメーターポート
+const FREQUENCY_PORT: usize = 1;
 
 /// ノコギリ波を生成するプロセッサー
+///
+/// 周波数はパラメーターポートで変化させられます。
 pub struct SawGenerator {
     /// 周波数
     frequency: f32,
@@ -26,18 +47,19 @@ impl SawGenerator {
     }
 
     /// ノコギリ波を生成する
-    fn calculate_saw(&mut self) -> f32 {
+    ///
+    /// # 引数
+    /// * `frequency` - このサンプルの周波数（Hz）
+    fn calculate_saw(&mut self, frequency: f32) -> f32 {
         // ノコギリ波を計算（0～1の位相を2倍して1を引くことで-1～1の範囲にマッピング）
         let saw = self.phase * 2.0 - 1.0;
 
         // 位相の増分を計算
-        let phase_delta = self.frequency / self.sample_rate;
+        let phase_delta = frequency / self.sample_rate;
 
-        // 位相を更新（0～1の範囲に保つ）
+        // 位相を更新（0～1の範囲に保つ。周波数が負になる場合も含む）
         self.phase += phase_delta;
-        if self.phase >= 1.0 {
-            self.phase -= 1.0;
-        }
+        self.phase -= self.phase.floor();
 
         saw
     }
@@ -49,20 +71,54 @@ impl AudioGraphNode for SawGenerator {
     }
 
     fn process(&mut self, buffer: &mut AudioBuffer) {
-        let num_channels = buffer.num_channels();
-        let num_samples = buffer.num_frames();
-        for i in 0..num_samples {
-            let val = self.calculate_saw();
+        self.process_with_inputs(buffer, &InputPorts::empty());
+    }
+
+    fn num_input_ports(&self) -> usize {
+        2
+    }
+
+    fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, inputs: &InputPorts) {
+        // パラメーターポートの信号は、周波数に加算する
+        let mut frequency_signal = inputs.parameter_signal(FREQUENCY_PORT);
+        for frame in buffer.frames_mut() {
+            let frequency = self.frequency
+                + frequency_signal
+                    .as_mut()
+                    .and_then(Iterator::next)
+                    .unwrap_or(0.0);
+            let val = self.calculate_saw(frequency);
             // ノコギリ波を各チャンネルに出力
-            for ch in 0..num_channels {
-                buffer.get_mut_frame(i)[ch] = val;
-            }
+            frame.fill(val);
         }
     }
 
+    fn parameter_port(&self, parameter_id: &str) -> Option<usize> {
+        (parameter_id == "frequency").then_some(FREQUENCY_PORT)
+    }
+
     fn reset(&mut self) {
         self.phase = 0.0;
     }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        self.frequency = info.clamp(value);
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "frequency" => Some(self.frequency),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,151 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer, audio_graph::AudioGraphNode, dsp::DelayLine,
+    parameter::ParameterInfo,
+};
+
+use super::MAX_COMB_DELAY_MS;
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// SchroederAllpass が公開するパラメーター
+const PARAMETERS: [ParameterInfo; 2] = [
+    ParameterInfo {
+        id: "delay_ms",
+        name: "Delay",
+        min: 0.1,
+        max: MAX_COMB_DELAY_MS,
+        default: 5.0,
+        unit: "ms",
+    },
+    ParameterInfo {
+        id: "coefficient",
+        name: "Coefficient",
+        min: -0.99,
+        max: 0.99,
+        default: 0.5,
+        unit: "",
+    },
+];
+
+/// 遅延時間と係数を設定できる Schroeder のオールパスフィルター
+///
+/// v[n] = x[n] + g・v[n-D]、y[n] = v[n-D] - g・v[n] で、振幅特性を変えずにインパルスを拡散します。
+/// リバーブの構成要素として、CombFilter と組み合わせてグラフ上で独自のリバーブを組めます。
+pub struct SchroederAllpass {
+    /// チャンネルごとのディレイライン（v を保存する）
+    delay_lines: [DelayLine; NUM_CHANNELS],
+    /// 遅延時間（ms）
+    delay_ms: f32,
+    /// 係数（-0.99～0.99）
+    coefficient: f32,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl SchroederAllpass {
+    /// 新しいSchroederAllpassを作成
+    pub fn new() -> Self {
+        Self {
+            delay_lines: [DelayLine::new(), DelayLine::new()],
+            delay_ms: 5.0,
+            coefficient: 0.5,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// 遅延時間を設定（ms）。0.1～MAX_COMB_DELAY_MS に制限されます。
+    pub fn set_delay_ms(&mut self, delay_ms: f32) {
+        self.delay_ms = delay_ms.clamp(0.1, MAX_COMB_DELAY_MS);
+    }
+
+    /// 係数を設定。発振を防ぐため -0.99～0.99 に制限されます。
+    pub fn set_coefficient(&mut self, coefficient: f32) {
+        self.coefficient = coefficient.clamp(-0.99, 0.99);
+    }
+}
+
+impl AudioGraphNode for SchroederAllpass {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        let max_delay_samples = ((MAX_COMB_DELAY_MS / 1000.0) * sample_rate).ceil() as usize;
+        for delay_line in self.delay_lines.iter_mut() {
+            delay_line.prepare(max_delay_samples);
+        }
+    }
+
+    fn tail_samples(&self) -> usize {
+        // 内部のフィードバックで繰り返す信号が -60dB まで減衰する回数だけ、遅延時間を繰り返す
+        let delay_samples = (self.delay_ms / 1000.0) * self.sample_rate;
+        let coefficient = self.coefficient.abs();
+        let repeats = if coefficient > 0.0 {
+            (0.001f32.ln() / coefficient.ln()).ceil()
+        } else {
+            0.0
+        };
+        (delay_samples * (1.0 + repeats)).ceil() as usize
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let num_channels = buffer.num_channels().min(NUM_CHANNELS);
+        // 書き込む前に読み出すため、遅延は 1 サンプル短く指定する
+        let delay_samples = ((self.delay_ms / 1000.0) * self.sample_rate).max(1.0) - 1.0;
+        let coefficient = self.coefficient;
+        for frame in buffer.frames_mut() {
+            for (ch, sample) in frame.iter_mut().enumerate().take(num_channels) {
+                let delay_line = &mut self.delay_lines[ch];
+                let delayed = delay_line.read(delay_samples);
+                let v = *sample + coefficient * delayed;
+                delay_line.write(v);
+                *sample = delayed - coefficient * v;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for delay_line in self.delay_lines.iter_mut() {
+            delay_line.reset();
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        match id {
+            "delay_ms" => self.set_delay_ms(value),
+            "coefficient" => self.set_coefficient(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "delay_ms" => Some(self.delay_ms),
+            "coefficient" => Some(self.coefficient),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schroeder_allpass_impulse_response() {
+        let mut allpass = SchroederAllpass::new();
+        allpass.set_delay_ms(2.0);
+        allpass.set_coefficient(0.5);
+        allpass.prepare(1000.0, 5);
+
+        // 遅延 2 サンプル、係数 0.5 のインパルス応答
+        let mut vector: Vec<f32> = vec![1.0, 0.0, 0.0, 0.0, 0.0];
+        allpass.process(&mut AudioBuffer::new(1, 5, vector.as_mut_slice()));
+        assert_eq!(vector, vec![-0.5, 0.0, 0.75, 0.0, 0.375]);
+    }
+}
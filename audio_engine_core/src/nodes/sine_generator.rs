@@ -1,9 +1,46 @@
-use crate::{audio_buffer::AudioBuffer, audio_graph::AudioGraphNode};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, InputPorts},
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 2] = [
+    ParameterInfo {
+        id: "frequency",
+        name: "Frequency",
+        min: 0.0,
+        max: 20000.0,
+        default: 440.0,
+        unit: "Hz",
+    },
+    ParameterInfo {
+        id: "phase",
+        name: "Phase",
+        min: 0.0,
+        max: 1.0,
+        default: 0.0,
+        unit: "",
+    },
+];
+
+/// 周波数のパラメーターポート（FM）
+const FREQUENCY_PORT: usize = 1;
+
+/// 位相のパラメーターポート（PM）
+const PHASE_PORT: usize = 2;
 
 /// サイン波を生成するプロセッサー
+///
+/// 周波数と位相のオフセットはパラメーターポートで変化させられるため、
+/// 他のオシレーターの出力を接続して FM や PM の音を作れます。
 pub struct SineGenerator {
     /// 周波数。Hz 単位。
     frequency: f32,
+    /// 位相のオフセット（周期を 1 とする）
+    phase_offset: f32,
     /// 現在の位相（0～1の範囲で保持）
     phase: f32,
     /// サンプリングレート
@@ -15,6 +52,7 @@ impl SineGenerator {
     pub fn new() -> Self {
         Self {
             frequency: 440.0,
+            phase_offset: 0.0,
             phase: 0.0,
             sample_rate: 44100.0, // デフォルトのサンプルレート
         }
@@ -25,19 +63,26 @@ impl SineGenerator {
         self.frequency = frequency;
     }
 
+    /// 位相のオフセットを設定（周期を 1 とする）
+    pub fn set_phase_offset(&mut self, phase_offset: f32) {
+        self.phase_offset = phase_offset;
+    }
+
     /// サイン波を生成する
-    fn calculate_sine(&mut self) -> f32 {
+    ///
+    /// # 引数
+    /// * `frequency` - このサンプルの周波数（Hz）
+    /// * `phase_offset` - このサンプルの位相のオフセット
+    fn calculate_sine(&mut self, frequency: f32, phase_offset: f32) -> f32 {
         // 位相から正弦波を計算（0～1の位相に2πを掛けて正弦関数に入力）
-        let sine = (self.phase * std::f32::consts::TAU).sin();
+        let sine = ((self.phase + phase_offset) * core::f32::consts::TAU).sin();
 
         // 位相の増分を計算
-        let phase_delta = self.frequency / self.sample_rate;
+        let phase_delta = frequency / self.sample_rate;
 
-        // 位相を更新（0～1の範囲に保つ）
+        // 位相を更新（0～1の範囲に保つ。FM で周波数が負になる場合も含む）
         self.phase += phase_delta;
-        if self.phase >= 1.0 {
-            self.phase -= 1.0;
-        }
+        self.phase -= self.phase.floor();
 
         sine
     }
@@ -49,20 +94,68 @@ impl AudioGraphNode for SineGenerator {
     }
 
     fn process(&mut self, buffer: &mut AudioBuffer) {
-        let num_channels = buffer.num_channels();
-        let num_samples = buffer.num_frames();
-        for i in 0..num_samples {
-            let val = self.calculate_sine();
+        self.process_with_inputs(buffer, &InputPorts::empty());
+    }
+
+    fn num_input_ports(&self) -> usize {
+        3
+    }
+
+    fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, inputs: &InputPorts) {
+        // パラメーターポートの信号は、パラメーターの値に加算する
+        let mut frequency_signal = inputs.parameter_signal(FREQUENCY_PORT);
+        let mut phase_signal = inputs.parameter_signal(PHASE_PORT);
+        for frame in buffer.frames_mut() {
+            let frequency = self.frequency
+                + frequency_signal
+                    .as_mut()
+                    .and_then(Iterator::next)
+                    .unwrap_or(0.0);
+            let phase_offset = self.phase_offset
+                + phase_signal
+                    .as_mut()
+                    .and_then(Iterator::next)
+                    .unwrap_or(0.0);
+            let val = self.calculate_sine(frequency, phase_offset);
             // サイン波を生成
-            for ch in 0..num_channels {
-                buffer.get_mut_frame(i)[ch] = val;
-            }
+            frame.fill(val);
+        }
+    }
+
+    fn parameter_port(&self, parameter_id: &str) -> Option<usize> {
+        match parameter_id {
+            "frequency" => Some(FREQUENCY_PORT),
+            "phase" => Some(PHASE_PORT),
+            _ => None,
         }
     }
 
     fn reset(&mut self) {
         self.phase = 0.0;
     }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        match id {
+            "frequency" => self.frequency = info.clamp(value),
+            _ => self.phase_offset = info.clamp(value),
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "frequency" => Some(self.frequency),
+            "phase" => Some(self.phase_offset),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
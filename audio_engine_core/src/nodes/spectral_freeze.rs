@@ -0,0 +1,205 @@
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    dsp::Stft,
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// FFT の長さ
+const FFT_SIZE: usize = 2048;
+
+/// ホップの長さ（FFT の長さの 1/4）
+const HOP_SIZE: usize = FFT_SIZE / 4;
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 1] = [ParameterInfo {
+    id: "freeze",
+    name: "Freeze",
+    min: 0.0,
+    max: 1.0,
+    default: 0.0,
+    unit: "",
+}];
+
+/// チャンネルごとのスペクトルの状態
+struct FreezeChannel {
+    /// 直前のフレームの位相
+    previous_phase: Vec<f32>,
+    /// 固定したスペクトルの振幅
+    magnitude: Vec<f32>,
+    /// 固定したスペクトルの現在の位相
+    phase: Vec<f32>,
+    /// 固定したときの、ビンごとの 1 ホップあたりの位相の変化
+    phase_advance: Vec<f32>,
+}
+
+/// スペクトルを固定して、その瞬間の音を鳴らし続けるスペクトラルフリーズ
+///
+/// `freeze` を 1 にした瞬間のスペクトルの振幅と、ビンごとの位相の進み方を保持し、
+/// 以降は入力を無視して、その位相の進み方で位相を進めながら同じスペクトルを出力し続けます。
+/// `freeze` が 0 の間は、STFT のレイテンシーだけ遅れた入力をそのまま出力します。
+pub struct SpectralFreeze {
+    /// STFT
+    stft: Stft,
+    /// チャンネルごとの状態
+    channels: Vec<FreezeChannel>,
+    /// スペクトルを固定するかどうか
+    freeze: bool,
+    /// チャンネルごとの、スペクトルを固定済みかどうか
+    frozen: [bool; NUM_CHANNELS],
+}
+
+impl SpectralFreeze {
+    /// 新しいSpectralFreezeを作成
+    pub fn new() -> Self {
+        Self {
+            stft: Stft::new(),
+            channels: Vec::new(),
+            freeze: false,
+            frozen: [false; NUM_CHANNELS],
+        }
+    }
+
+    /// スペクトルを固定するかどうかを設定
+    pub fn set_freeze(&mut self, freeze: bool) {
+        self.freeze = freeze;
+    }
+}
+
+impl AudioGraphNode for SpectralFreeze {
+    fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {
+        self.stft.prepare(FFT_SIZE, HOP_SIZE, NUM_CHANNELS);
+        let num_bins = self.stft.num_bins();
+        self.channels = (0..NUM_CHANNELS)
+            .map(|_| FreezeChannel {
+                previous_phase: vec![0.0; num_bins],
+                magnitude: vec![0.0; num_bins],
+                phase: vec![0.0; num_bins],
+                phase_advance: vec![0.0; num_bins],
+            })
+            .collect();
+        self.frozen = [false; NUM_CHANNELS];
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        let Self {
+            stft,
+            channels,
+            freeze,
+            frozen,
+        } = self;
+        let freeze = *freeze;
+        stft.process(buffer, |ch, re, im| {
+            let state = &mut channels[ch];
+            if !freeze {
+                frozen[ch] = false;
+                for (k, previous) in state.previous_phase.iter_mut().enumerate() {
+                    *previous = im[k].atan2(re[k]);
+                }
+                return;
+            }
+
+            if !frozen[ch] {
+                // 固定した瞬間のスペクトルと、直前のフレームからの位相の進み方を保持する
+                frozen[ch] = true;
+                for k in 0..re.len() {
+                    let phase = im[k].atan2(re[k]);
+                    state.magnitude[k] = re[k].hypot(im[k]);
+                    state.phase_advance[k] = phase - state.previous_phase[k];
+                    state.phase[k] = phase;
+                }
+            } else {
+                for (phase, advance) in state.phase.iter_mut().zip(&state.phase_advance) {
+                    *phase = (*phase + advance) % core::f32::consts::TAU;
+                }
+            }
+            for k in 0..re.len() {
+                let (sin, cos) = state.phase[k].sin_cos();
+                re[k] = state.magnitude[k] * cos;
+                im[k] = state.magnitude[k] * sin;
+            }
+        });
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.stft.latency_samples()
+    }
+
+    fn reset(&mut self) {
+        self.stft.reset();
+        for state in self.channels.iter_mut() {
+            state.previous_phase.fill(0.0);
+            state.magnitude.fill(0.0);
+        }
+        self.frozen = [false; NUM_CHANNELS];
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        if find_parameter(&PARAMETERS, id).is_none() {
+            return false;
+        }
+        self.set_freeze(value >= 0.5);
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "freeze" => Some(if self.freeze { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1ch のバッファの RMS
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_spectral_freeze_sustains_spectrum() {
+        const FRAMES: usize = FFT_SIZE * 4;
+        let mut freeze = SpectralFreeze::new();
+        freeze.prepare(44100.0, FRAMES);
+        assert_eq!(freeze.latency_samples(), FFT_SIZE);
+
+        // サイン波を入力しながら固定する
+        let mut sine: Vec<f32> = (0..FRAMES * 2)
+            .map(|i| (core::f32::consts::TAU * 440.0 * i as f32 / 44100.0).sin())
+            .collect();
+        let (first, second) = sine.split_at_mut(FRAMES);
+        freeze.process(&mut AudioBuffer::new(1, FRAMES, first));
+        let before = rms(&first[FRAMES / 2..]);
+        assert!((before - core::f32::consts::FRAC_1_SQRT_2).abs() < 0.05);
+        freeze.set_freeze(true);
+        freeze.process(&mut AudioBuffer::new(1, FRAMES, second));
+
+        // 固定した後は、無音を入力しても同じくらいの音量で鳴り続ける
+        let mut silence = vec![0.0; FRAMES];
+        freeze.process(&mut AudioBuffer::new(1, FRAMES, &mut silence));
+        let frozen = rms(&silence);
+        assert!(
+            (frozen - before).abs() < before * 0.2,
+            "{} {}",
+            before,
+            frozen
+        );
+
+        // 固定を解除すると、入力の無音に戻る
+        freeze.set_freeze(false);
+        let mut silence = vec![0.0; FRAMES];
+        freeze.process(&mut AudioBuffer::new(1, FRAMES, &mut silence));
+        assert!(rms(&silence[FRAMES / 2..]) < 1e-3);
+    }
+}
@@ -0,0 +1,256 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, InputPorts, OutputPorts},
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// バンドパスの出力ポート（メイン出力はローパス）
+pub const SVF_BANDPASS_PORT: usize = 1;
+
+/// ハイパスの出力ポート
+pub const SVF_HIGHPASS_PORT: usize = 2;
+
+/// ノッチの出力ポート
+pub const SVF_NOTCH_PORT: usize = 3;
+
+/// カットオフ周波数のパラメーターポート
+const CUTOFF_PORT: usize = 1;
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 2] = [
+    ParameterInfo {
+        id: "cutoff",
+        name: "Cutoff",
+        min: 20.0,
+        max: 20000.0,
+        default: 1000.0,
+        unit: "Hz",
+    },
+    ParameterInfo {
+        id: "q",
+        name: "Q",
+        min: 0.1,
+        max: 20.0,
+        default: 0.707,
+        unit: "",
+    },
+];
+
+/// TPT（トポロジー保存変換）のステートバリアブルフィルターの係数
+#[derive(Clone, Copy)]
+struct SvfCoefficients {
+    /// 1 / Q
+    k: f32,
+    a1: f32,
+    a2: f32,
+    a3: f32,
+}
+
+impl SvfCoefficients {
+    /// カットオフ周波数と Q から係数を計算する
+    ///
+    /// カットオフ周波数はナイキスト周波数の手前に制限します。
+    fn new(sample_rate: f32, cutoff: f32, q: f32) -> Self {
+        let cutoff = cutoff.clamp(1.0, sample_rate * 0.49);
+        let g = (core::f32::consts::PI * cutoff / sample_rate).tan();
+        let k = 1.0 / q;
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        Self { k, a1, a2, a3 }
+    }
+}
+
+/// ローパス・バンドパス・ハイパス・ノッチを同時に出力するステートバリアブルフィルター
+///
+/// TPT 形式の SVF で、カットオフ周波数をサンプルごとに変化させても安定しています。
+/// メイン出力はローパスで、バンドパス・ハイパス・ノッチはそれぞれ SVF_BANDPASS_PORT・SVF_HIGHPASS_PORT・
+/// SVF_NOTCH_PORT の出力ポートから AudioGraph::add_edge_from_port で接続します。
+/// カットオフ周波数はパラメーターポートで変化させられ、接続がある間はサンプルごとに係数を再計算します。
+pub struct StateVariableFilter {
+    /// カットオフ周波数（Hz）
+    cutoff: f32,
+    /// Q
+    q: f32,
+    /// 現在の係数
+    coefficients: SvfCoefficients,
+    /// チャンネルごとの 2 つの積分器の状態
+    state: [[f32; 2]; NUM_CHANNELS],
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl StateVariableFilter {
+    /// 新しいStateVariableFilterを作成
+    pub fn new() -> Self {
+        let sample_rate = 44100.0; // デフォルトのサンプルレート
+        Self {
+            cutoff: 1000.0,
+            q: 0.707,
+            coefficients: SvfCoefficients::new(sample_rate, 1000.0, 0.707),
+            state: [[0.0; 2]; NUM_CHANNELS],
+            sample_rate,
+        }
+    }
+
+    /// カットオフ周波数を設定（Hz）
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.cutoff = PARAMETERS[0].clamp(cutoff);
+        self.update_coefficients();
+    }
+
+    /// Q を設定
+    pub fn set_q(&mut self, q: f32) {
+        self.q = PARAMETERS[1].clamp(q);
+        self.update_coefficients();
+    }
+
+    /// パラメーターの値で係数を計算し直す
+    fn update_coefficients(&mut self) {
+        self.coefficients = SvfCoefficients::new(self.sample_rate, self.cutoff, self.q);
+    }
+}
+
+impl AudioGraphNode for StateVariableFilter {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        self.process_with_outputs(buffer, &InputPorts::empty(), &mut OutputPorts::empty());
+    }
+
+    fn num_input_ports(&self) -> usize {
+        2
+    }
+
+    fn num_output_ports(&self) -> usize {
+        4
+    }
+
+    fn process_with_outputs(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        inputs: &InputPorts,
+        outputs: &mut OutputPorts,
+    ) {
+        let mut cutoff_signal = inputs.parameter_signal(CUTOFF_PORT);
+        let mut ports = outputs.iter_mut();
+        let bandpass = ports.next().unwrap_or_default();
+        let highpass = ports.next().unwrap_or_default();
+        let notch = ports.next().unwrap_or_default();
+
+        let num_channels = buffer.num_channels();
+        for (i, frame) in buffer.frames_mut().enumerate() {
+            // パラメーターポートの信号をカットオフ周波数に加算して、係数を再計算する
+            if let Some(signal) = cutoff_signal.as_mut().and_then(Iterator::next) {
+                let cutoff = PARAMETERS[0].clamp(self.cutoff + signal);
+                self.coefficients = SvfCoefficients::new(self.sample_rate, cutoff, self.q);
+            }
+            let SvfCoefficients { k, a1, a2, a3 } = self.coefficients;
+            for (ch, sample) in frame.iter_mut().enumerate().take(NUM_CHANNELS) {
+                let [ic1eq, ic2eq] = &mut self.state[ch];
+                let v0 = *sample;
+                let v3 = v0 - *ic2eq;
+                let v1 = a1 * *ic1eq + a2 * v3;
+                let v2 = *ic2eq + a2 * *ic1eq + a3 * v3;
+                *ic1eq = 2.0 * v1 - *ic1eq;
+                *ic2eq = 2.0 * v2 - *ic2eq;
+
+                let idx = i * num_channels + ch;
+                let high = v0 - k * v1 - v2;
+                if let Some(out) = bandpass.get_mut(idx) {
+                    *out = v1;
+                }
+                if let Some(out) = highpass.get_mut(idx) {
+                    *out = high;
+                }
+                if let Some(out) = notch.get_mut(idx) {
+                    *out = v2 + high;
+                }
+                *sample = v2;
+            }
+        }
+        // 接続を解除した後は、パラメーターのカットオフ周波数に戻す
+        if cutoff_signal.is_some() {
+            self.update_coefficients();
+        }
+    }
+
+    fn parameter_port(&self, parameter_id: &str) -> Option<usize> {
+        match parameter_id {
+            "cutoff" => Some(CUTOFF_PORT),
+            _ => None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state = [[0.0; 2]; NUM_CHANNELS];
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        if find_parameter(&PARAMETERS, id).is_none() {
+            return false;
+        }
+        match id {
+            "cutoff" => self.set_cutoff(value),
+            _ => self.set_q(value),
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "cutoff" => Some(self.cutoff),
+            "q" => Some(self.q),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio_buffer::OwnedAudioBuffer;
+
+    use super::*;
+
+    #[test]
+    fn test_state_variable_filter_outputs() {
+        let mut svf = StateVariableFilter::new();
+        svf.set_cutoff(100.0);
+        svf.prepare(1000.0, 200);
+
+        // 直流を入力すると、ローパスとノッチは 1、バンドパスとハイパスは 0 に収束する
+        let mut vector: Vec<f32> = vec![1.0; 200];
+        let mut ports: Vec<OwnedAudioBuffer> =
+            (0..3).map(|_| OwnedAudioBuffer::new(1, 200)).collect();
+        svf.process_with_outputs(
+            &mut AudioBuffer::new(1, 200, vector.as_mut_slice()),
+            &InputPorts::empty(),
+            &mut OutputPorts::new(&mut ports),
+        );
+        let last = |samples: &[f32]| *samples.last().unwrap();
+        assert!((last(&vector) - 1.0).abs() < 1e-3);
+        assert!(last(ports[0].as_slice()).abs() < 1e-3);
+        assert!(last(ports[1].as_slice()).abs() < 1e-3);
+        assert!((last(ports[2].as_slice()) - 1.0).abs() < 1e-3);
+
+        // 各出力の関係: 入力 = ローパス + k・バンドパス + ハイパス、ノッチ = ローパス + ハイパス
+        let k = 1.0 / svf.q;
+        let [band, high, notch] = [0, 1, 2].map(|port| ports[port].as_slice());
+        for (i, low) in vector.iter().enumerate() {
+            assert!((low + k * band[i] + high[i] - 1.0).abs() < 1e-5);
+            assert!((notch[i] - (low + high[i])).abs() < 1e-6);
+        }
+    }
+}
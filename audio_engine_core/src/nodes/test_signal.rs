@@ -0,0 +1,344 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::AudioGraphNode,
+    parameter::{ParameterInfo, find_parameter},
+};
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 7] = [
+    ParameterInfo {
+        id: "kind",
+        name: "Signal Kind",
+        min: 0.0,
+        max: 4.0,
+        default: 0.0,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "start_frequency",
+        name: "Start Frequency",
+        min: 1.0,
+        max: 24000.0,
+        default: 20.0,
+        unit: "Hz",
+    },
+    ParameterInfo {
+        id: "end_frequency",
+        name: "End Frequency",
+        min: 1.0,
+        max: 24000.0,
+        default: 20000.0,
+        unit: "Hz",
+    },
+    ParameterInfo {
+        id: "duration",
+        name: "Duration",
+        min: 0.001,
+        max: 600.0,
+        default: 1.0,
+        unit: "s",
+    },
+    ParameterInfo {
+        id: "amplitude",
+        name: "Amplitude",
+        min: -1.0,
+        max: 1.0,
+        default: 0.5,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "delay",
+        name: "Delay",
+        min: 0.0,
+        max: 600.0,
+        default: 0.0,
+        unit: "s",
+    },
+    ParameterInfo {
+        id: "period",
+        name: "Repeat Period",
+        min: 0.0,
+        max: 600.0,
+        default: 0.0,
+        unit: "s",
+    },
+];
+
+/// テスト信号の種類
+///
+/// `kind` パラメーターの値は、この順番の番号（0～4）です。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TestSignalKind {
+    /// 指数（対数周波数）サインスイープ。インパルス応答の測定に使う。
+    ExponentialSweep,
+    /// 線形サインスイープ
+    LinearSweep,
+    /// ステップ（delay の後に amplitude の値になる）
+    Step,
+    /// 直流（常に amplitude の値）
+    Dc,
+    /// ハン窓をかけた短い線形スイープ
+    Chirp,
+}
+
+impl TestSignalKind {
+    /// `kind` パラメーターの値から種類を求める
+    fn from_index(index: usize) -> Self {
+        match index {
+            0 => TestSignalKind::ExponentialSweep,
+            1 => TestSignalKind::LinearSweep,
+            2 => TestSignalKind::Step,
+            3 => TestSignalKind::Dc,
+            _ => TestSignalKind::Chirp,
+        }
+    }
+
+    /// `kind` パラメーターの値
+    fn index(&self) -> usize {
+        match self {
+            TestSignalKind::ExponentialSweep => 0,
+            TestSignalKind::LinearSweep => 1,
+            TestSignalKind::Step => 2,
+            TestSignalKind::Dc => 3,
+            TestSignalKind::Chirp => 4,
+        }
+    }
+}
+
+/// フィルターの測定やテストのフィクスチャに使う信号を生成するプロセッサー
+///
+/// 各サンプルは、開始からのサンプル数と設定だけから閉じた式で計算します（位相を積算しない）。
+/// そのため、ブロックサイズやプラットフォームによらず、同じ設定からは常に同じ信号が得られます。
+///
+/// 信号は delay 秒の無音の後に始まります。スイープとチャープは duration 秒で終わり、
+/// period が 0 より大きい場合は period 秒ごとに繰り返します（0 の場合は 1 回だけで、その後は無音）。
+/// 全チャンネルに同じ信号を出力します。
+pub struct TestSignal {
+    /// 信号の種類
+    kind: TestSignalKind,
+    /// 開始周波数（Hz）
+    start_frequency: f32,
+    /// 終了周波数（Hz）
+    end_frequency: f32,
+    /// スイープ・チャープの長さ（秒）
+    duration: f32,
+    /// 振幅（ステップと直流の場合はその値）
+    amplitude: f32,
+    /// 信号が始まるまでの時間（秒）
+    delay: f32,
+    /// 繰り返しの周期（秒、0 の場合は繰り返さない）
+    period: f32,
+    /// 開始からのサンプル数
+    position: u64,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl TestSignal {
+    /// 新しいTestSignalを作成（20Hz～20kHz、1 秒の指数サインスイープ）
+    pub fn new() -> Self {
+        Self {
+            kind: TestSignalKind::ExponentialSweep,
+            start_frequency: PARAMETERS[1].default,
+            end_frequency: PARAMETERS[2].default,
+            duration: PARAMETERS[3].default,
+            amplitude: PARAMETERS[4].default,
+            delay: PARAMETERS[5].default,
+            period: PARAMETERS[6].default,
+            position: 0,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        }
+    }
+
+    /// 信号の種類を設定
+    pub fn set_kind(&mut self, kind: TestSignalKind) {
+        self.kind = kind;
+    }
+
+    /// スイープ・チャープの開始周波数と終了周波数を設定（Hz）
+    pub fn set_frequencies(&mut self, start_frequency: f32, end_frequency: f32) {
+        self.start_frequency = PARAMETERS[1].clamp(start_frequency);
+        self.end_frequency = PARAMETERS[2].clamp(end_frequency);
+    }
+
+    /// スイープ・チャープの長さを設定（秒）
+    pub fn set_duration(&mut self, duration: f32) {
+        self.duration = PARAMETERS[3].clamp(duration);
+    }
+
+    /// 振幅を設定（ステップと直流の場合はその値）
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        self.amplitude = amplitude;
+    }
+
+    /// 信号が始まるまでの時間を設定（秒）
+    pub fn set_delay(&mut self, delay: f32) {
+        self.delay = delay.max(0.0);
+    }
+
+    /// 繰り返しの周期を設定（秒、0 の場合は繰り返さない）
+    pub fn set_period(&mut self, period: f32) {
+        self.period = period.max(0.0);
+    }
+
+    /// 信号を最初からやり直す
+    pub fn restart(&mut self) {
+        self.position = 0;
+    }
+
+    /// 指定したサンプル位置の値を計算する
+    ///
+    /// # 引数
+    /// * `position` - 開始からのサンプル数
+    pub fn sample_at(&self, position: u64) -> f32 {
+        let sample_rate = self.sample_rate as f64;
+        let delay = (self.delay as f64 * sample_rate).round() as u64;
+        if position < delay {
+            return 0.0;
+        }
+        let mut n = position - delay;
+        match self.kind {
+            TestSignalKind::Step | TestSignalKind::Dc => return self.amplitude,
+            _ => {}
+        }
+
+        // 繰り返しの周期内の位置にする
+        let period = (self.period as f64 * sample_rate).round() as u64;
+        if period > 0 {
+            n %= period;
+        }
+        let length = (self.duration as f64 * sample_rate).round().max(1.0) as u64;
+        if n >= length {
+            return 0.0;
+        }
+
+        let t = n as f64 / sample_rate;
+        let duration = length as f64 / sample_rate;
+        let f1 = self.start_frequency as f64;
+        let f2 = self.end_frequency as f64;
+        let phase = match self.kind {
+            TestSignalKind::ExponentialSweep if f1 != f2 => {
+                let rate = (f2 / f1).ln();
+                core::f64::consts::TAU * f1 * duration / rate * ((t / duration * rate).exp() - 1.0)
+            }
+            _ => core::f64::consts::TAU * (f1 * t + (f2 - f1) * t * t / (2.0 * duration)),
+        };
+        let window = if self.kind == TestSignalKind::Chirp {
+            0.5 - 0.5 * (core::f64::consts::TAU * n as f64 / length as f64).cos()
+        } else {
+            1.0
+        };
+        (self.amplitude as f64 * window * phase.sin()) as f32
+    }
+}
+
+impl AudioGraphNode for TestSignal {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        for frame in buffer.frames_mut() {
+            let value = self.sample_at(self.position);
+            frame.fill(value);
+            self.position += 1;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.restart();
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        let value = info.clamp(value);
+        match id {
+            "kind" => self.set_kind(TestSignalKind::from_index(value.round() as usize)),
+            "start_frequency" => self.start_frequency = value,
+            "end_frequency" => self.end_frequency = value,
+            "duration" => self.set_duration(value),
+            "amplitude" => self.set_amplitude(value),
+            "delay" => self.set_delay(value),
+            _ => self.set_period(value),
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "kind" => Some(self.kind.index() as f32),
+            "start_frequency" => Some(self.start_frequency),
+            "end_frequency" => Some(self.end_frequency),
+            "duration" => Some(self.duration),
+            "amplitude" => Some(self.amplitude),
+            "delay" => Some(self.delay),
+            "period" => Some(self.period),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ブロックに分けて num_frames フレームを生成する
+    fn render(signal: &mut TestSignal, num_frames: usize, block_size: usize) -> Vec<f32> {
+        let mut samples = vec![0.0; num_frames];
+        for block in samples.chunks_mut(block_size) {
+            let len = block.len();
+            signal.process(&mut AudioBuffer::new(1, len, block));
+        }
+        samples
+    }
+
+    #[test]
+    fn test_signals_are_reproducible() {
+        let mut signal = TestSignal::new();
+        signal.prepare(48000.0, 512);
+        signal.set_frequencies(100.0, 1000.0);
+        signal.set_duration(0.05);
+        signal.set_period(0.1);
+
+        // ブロックサイズによらず同じ信号になり、周期ごとに繰り返す
+        let a = render(&mut signal, 9600, 512);
+        signal.reset();
+        let b = render(&mut signal, 9600, 37);
+        assert_eq!(a, b);
+        assert_eq!(a[..4800], a[4800..]);
+        assert_eq!(a[0], 0.0);
+        assert!(a[2400..4800].iter().all(|s| *s == 0.0));
+
+        // 指数スイープの瞬時周波数は開始周波数から指数的に上がる（最初の周期は 100Hz 付近）
+        let crossings = a[..480]
+            .windows(2)
+            .filter(|w| w[0] <= 0.0 && w[1] > 0.0)
+            .count();
+        assert!((1..=2).contains(&crossings));
+
+        // ステップは delay の後に振幅の値になる
+        assert!(signal.set_parameter("kind", 2.0));
+        assert!(signal.set_parameter("delay", 0.001));
+        signal.set_amplitude(-0.25);
+        signal.reset();
+        let step = render(&mut signal, 96, 64);
+        assert!(step[..48].iter().all(|s| *s == 0.0));
+        assert!(step[48..].iter().all(|s| *s == -0.25));
+
+        // チャープは窓をかけているため両端が 0 になる
+        signal.set_kind(TestSignalKind::Chirp);
+        signal.set_delay(0.0);
+        signal.set_amplitude(1.0);
+        assert_eq!(signal.sample_at(0), 0.0);
+        assert!(signal.sample_at(2399).abs() < 1e-5);
+        assert_eq!(signal.get_parameter("kind"), Some(4.0));
+    }
+}
@@ -0,0 +1,307 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    audio_buffer::AudioBuffer,
+    audio_graph::{AudioGraphNode, InputPorts},
+    dsp::{Biquad, BiquadCoefficients},
+    parameter::{ParameterInfo, find_parameter},
+};
+
+use super::envelope_follower::time_to_coefficient;
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// モジュレーター入力のポート番号（メイン入力はキャリア）
+pub const VOCODER_MODULATOR_PORT: usize = 1;
+
+/// バンド数の最大値
+pub const MAX_VOCODER_BANDS: usize = 32;
+
+/// 最も低いバンドの中心周波数（Hz）
+const LOWEST_FREQUENCY: f32 = 100.0;
+
+/// 最も高いバンドの中心周波数（Hz）
+const HIGHEST_FREQUENCY: f32 = 8000.0;
+
+/// 公開するパラメーターの一覧
+const PARAMETERS: [ParameterInfo; 3] = [
+    ParameterInfo {
+        id: "bands",
+        name: "Bands",
+        min: 4.0,
+        max: MAX_VOCODER_BANDS as f32,
+        default: 16.0,
+        unit: "",
+    },
+    ParameterInfo {
+        id: "attack",
+        name: "Attack",
+        min: 0.0,
+        max: 100.0,
+        default: 5.0,
+        unit: "ms",
+    },
+    ParameterInfo {
+        id: "release",
+        name: "Release",
+        min: 0.0,
+        max: 1000.0,
+        default: 50.0,
+        unit: "ms",
+    },
+];
+
+/// 2 つのバンドパスを直列にした 4 次のバンドパスフィルター
+///
+/// 2 次のバンドパスだけでは隣のバンドとの分離が足りないため、同じ係数で 2 回通します。
+struct BandFilter {
+    stages: [Biquad; 2],
+}
+
+impl BandFilter {
+    fn new() -> Self {
+        Self {
+            stages: [Biquad::new(), Biquad::new()],
+        }
+    }
+
+    fn set_coefficients(&mut self, coefficients: BiquadCoefficients) {
+        for stage in self.stages.iter_mut() {
+            stage.set_coefficients(coefficients);
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let [first, second] = &mut self.stages;
+        second.process(first.process(x))
+    }
+
+    fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+}
+
+/// 分析と合成のフィルターバンクによるボコーダー
+///
+/// モジュレーター（VOCODER_MODULATOR_PORT に接続した声など）を4 次のバンドパスフィルターのバンクで帯域に分け、
+/// 帯域ごとのエンベロープでキャリア（メイン入力のシンセなど）の同じ帯域の音量を制御します。
+/// バンドの中心周波数は 100Hz～8kHz に対数で等間隔に並び、Q はバンドの間隔から決めます。
+/// モジュレーターをホストのサイドチェーン入力から受け取る場合は、AudioGraph::set_sidechain_node で
+/// サイドチェーン入力を受け取る InputNode をモジュレーター入力に接続してください。
+/// モジュレーターが接続されていない場合は無音を出力します。
+pub struct Vocoder {
+    /// バンド数
+    num_bands: usize,
+    /// アタック時間（ms）
+    attack_ms: f32,
+    /// リリース時間（ms）
+    release_ms: f32,
+    /// アタックの係数
+    attack_coefficient: f32,
+    /// リリースの係数
+    release_coefficient: f32,
+    /// チャンネル・バンドごとの、モジュレーターを分析するフィルター
+    analysis: [[BandFilter; MAX_VOCODER_BANDS]; NUM_CHANNELS],
+    /// チャンネル・バンドごとの、キャリアを合成するフィルター
+    synthesis: [[BandFilter; MAX_VOCODER_BANDS]; NUM_CHANNELS],
+    /// チャンネル・バンドごとの、モジュレーターのエンベロープ
+    envelopes: [[f32; MAX_VOCODER_BANDS]; NUM_CHANNELS],
+    /// フィルターの係数の再計算が必要かどうか
+    dirty: bool,
+    /// サンプリングレート
+    sample_rate: f32,
+}
+
+impl Vocoder {
+    /// 新しいVocoderを作成
+    pub fn new() -> Self {
+        let mut vocoder = Self {
+            num_bands: 16,
+            attack_ms: 5.0,
+            release_ms: 50.0,
+            attack_coefficient: 0.0,
+            release_coefficient: 0.0,
+            analysis: core::array::from_fn(|_| core::array::from_fn(|_| BandFilter::new())),
+            synthesis: core::array::from_fn(|_| core::array::from_fn(|_| BandFilter::new())),
+            envelopes: [[0.0; MAX_VOCODER_BANDS]; NUM_CHANNELS],
+            dirty: true,
+            sample_rate: 44100.0, // デフォルトのサンプルレート
+        };
+        vocoder.update_envelope_coefficients();
+        vocoder
+    }
+
+    /// バンド数を設定（4～MAX_VOCODER_BANDS）
+    pub fn set_num_bands(&mut self, num_bands: usize) {
+        self.num_bands = num_bands.clamp(4, MAX_VOCODER_BANDS);
+        self.dirty = true;
+    }
+
+    /// エンベロープのアタック時間を設定（ms）
+    pub fn set_attack_ms(&mut self, attack_ms: f32) {
+        self.attack_ms = attack_ms.max(0.0);
+        self.update_envelope_coefficients();
+    }
+
+    /// エンベロープのリリース時間を設定（ms）
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.max(0.0);
+        self.update_envelope_coefficients();
+    }
+
+    fn update_envelope_coefficients(&mut self) {
+        self.attack_coefficient = time_to_coefficient(self.attack_ms, self.sample_rate);
+        self.release_coefficient = time_to_coefficient(self.release_ms, self.sample_rate);
+    }
+
+    /// バンドの中心周波数と Q から、フィルターバンクの係数を計算し直す
+    fn update_filter_coefficients(&mut self) {
+        // 隣り合うバンドの中心周波数の比から、バンドの幅が重なり合う Q を求める
+        let ratio = (HIGHEST_FREQUENCY / LOWEST_FREQUENCY).powf(1.0 / (self.num_bands - 1) as f32);
+        let q = ratio.sqrt() / (ratio - 1.0);
+        for band in 0..self.num_bands {
+            let frequency =
+                (LOWEST_FREQUENCY * ratio.powi(band as i32)).min(self.sample_rate * 0.45);
+            let coefficients = BiquadCoefficients::band_pass(self.sample_rate, frequency, q);
+            for ch in 0..NUM_CHANNELS {
+                self.analysis[ch][band].set_coefficients(coefficients);
+                self.synthesis[ch][band].set_coefficients(coefficients);
+            }
+        }
+        self.dirty = false;
+    }
+}
+
+impl AudioGraphNode for Vocoder {
+    fn prepare(&mut self, sample_rate: f32, _max_num_samples: usize) {
+        self.sample_rate = sample_rate;
+        self.update_envelope_coefficients();
+        self.dirty = true;
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        self.process_with_inputs(buffer, &InputPorts::empty());
+    }
+
+    fn num_input_ports(&self) -> usize {
+        2
+    }
+
+    fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, inputs: &InputPorts) {
+        if self.dirty {
+            self.update_filter_coefficients();
+        }
+        let Some(modulator) = inputs
+            .get(VOCODER_MODULATOR_PORT)
+            .filter(|_| inputs.is_connected(VOCODER_MODULATOR_PORT))
+        else {
+            buffer.as_mut_slice().fill(0.0);
+            return;
+        };
+
+        let num_channels = buffer.num_channels();
+        for (i, frame) in buffer.frames_mut().enumerate() {
+            for (ch, sample) in frame.iter_mut().enumerate().take(NUM_CHANNELS) {
+                let modulator = modulator[i * num_channels + ch];
+                let carrier = *sample;
+                let mut output = 0.0;
+                for band in 0..self.num_bands {
+                    // モジュレーターの帯域のエンベロープに追従する
+                    let level = self.analysis[ch][band].process(modulator).abs();
+                    let envelope = &mut self.envelopes[ch][band];
+                    let coefficient = if level > *envelope {
+                        self.attack_coefficient
+                    } else {
+                        self.release_coefficient
+                    };
+                    *envelope = level + (*envelope - level) * coefficient;
+
+                    // キャリアの同じ帯域の音量を、エンベロープで制御する
+                    output += self.synthesis[ch][band].process(carrier) * *envelope;
+                }
+                *sample = output;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for filters in self.analysis.iter_mut().chain(self.synthesis.iter_mut()) {
+            for filter in filters.iter_mut() {
+                filter.reset();
+            }
+        }
+        self.envelopes = [[0.0; MAX_VOCODER_BANDS]; NUM_CHANNELS];
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &PARAMETERS
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        let Some(info) = find_parameter(&PARAMETERS, id) else {
+            return false;
+        };
+        match info.id {
+            "bands" => self.set_num_bands(info.clamp(value).round() as usize),
+            "attack" => self.set_attack_ms(info.clamp(value)),
+            "release" => self.set_release_ms(info.clamp(value)),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        match id {
+            "bands" => Some(self.num_bands as f32),
+            "attack" => Some(self.attack_ms),
+            "release" => Some(self.release_ms),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// キャリアとモジュレーターにサイン波を入力したときの、後半の出力の RMS
+    fn vocoded_rms(carrier_frequency: f32, modulator_frequency: f32) -> f32 {
+        const SAMPLE_RATE: f32 = 44100.0;
+        const FRAMES: usize = 4410;
+        let sine = |frequency: f32| -> Vec<f32> {
+            (0..FRAMES)
+                .map(|i| (core::f32::consts::TAU * frequency * i as f32 / SAMPLE_RATE).sin())
+                .collect()
+        };
+        let mut vocoder = Vocoder::new();
+        vocoder.set_num_bands(8);
+        vocoder.prepare(SAMPLE_RATE, FRAMES);
+        let mut carrier = sine(carrier_frequency);
+        let modulator = [sine(modulator_frequency)];
+        vocoder.process_with_inputs(
+            &mut AudioBuffer::new(1, FRAMES, &mut carrier),
+            &InputPorts::new(&modulator, &[true], 1, FRAMES),
+        );
+        let tail = &carrier[FRAMES / 2..];
+        (tail.iter().map(|x| x * x).sum::<f32>() / tail.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_vocoder_follows_modulator_bands() {
+        // 同じ帯域のモジュレーターがあるとキャリアが通り、離れた帯域では大きく減衰する
+        let matched = vocoded_rms(1000.0, 1000.0);
+        let mismatched = vocoded_rms(1000.0, 6000.0);
+        assert!(matched > 0.1, "{}", matched);
+        assert!(mismatched < matched * 0.1, "{} {}", matched, mismatched);
+
+        // モジュレーターが接続されていない場合は無音
+        let mut vocoder = Vocoder::new();
+        vocoder.prepare(44100.0, 4);
+        let mut buffer = vec![1.0; 4];
+        vocoder.process(&mut AudioBuffer::new(1, 4, &mut buffer));
+        assert_eq!(buffer, vec![0.0; 4]);
+    }
+}
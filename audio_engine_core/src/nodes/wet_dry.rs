@@ -0,0 +1,238 @@
+use crate::prelude::*;
+use crate::{
+    audio_buffer::{AudioBuffer, OwnedAudioBuffer},
+    audio_graph::{AudioGraphNode, InputPorts, NodeContext, OutputPorts},
+    dsp::{DelayLine, SmoothedValue},
+    midi::NoteEvent,
+    parameter::ParameterInfo,
+    transport::Transport,
+    tuning::Tuning,
+};
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// WetDry 自身が公開するパラメーター
+const WET_PARAMETER: ParameterInfo = ParameterInfo {
+    id: "wet",
+    name: "Wet",
+    min: 0.0,
+    max: 1.0,
+    default: 1.0,
+    unit: "",
+};
+
+/// 任意のエフェクトノードをドライ/ウェットのミックスでラップするノード
+///
+/// 入力をドライ信号として保持したまま、ラップしたノードでウェット信号を作り、
+/// `wet` パラメーターの割合で混ぜます。ドライ信号はラップしたノードの `latency_samples` だけ
+/// 遅延させ、ウェット信号と時間をそろえます。
+///
+/// ラップしたノードのパラメーターはそのまま公開されるため、`AudioGraph::set_parameter` で操作できます。
+pub struct WetDry<T: AudioGraphNode> {
+    /// ラップしたノード
+    inner: T,
+    /// ドライ信号の一時バッファ（インターリーブ）
+    dry_buffer: OwnedAudioBuffer,
+    /// レイテンシー補正用のチャンネルごとのディレイライン
+    dry_delays: [DelayLine; NUM_CHANNELS],
+    /// ドライ信号の遅延（サンプル数）
+    latency: usize,
+    /// ウェット信号の割合（0～1）
+    wet: SmoothedValue,
+    /// 自身とラップしたノードのパラメーター一覧
+    parameters: Vec<ParameterInfo>,
+}
+
+impl<T: AudioGraphNode> WetDry<T> {
+    /// ノードをラップした新しいWetDryを作成
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn new(inner: T) -> Self {
+        let mut parameters = vec![WET_PARAMETER];
+        parameters.extend_from_slice(inner.parameters());
+        Self {
+            inner,
+            dry_buffer: OwnedAudioBuffer::new(NUM_CHANNELS, 0),
+            dry_delays: [DelayLine::new(), DelayLine::new()],
+            latency: 0,
+            wet: SmoothedValue::new(WET_PARAMETER.default),
+            parameters,
+        }
+    }
+
+    /// ウェット信号の割合を設定（0～1）
+    pub fn set_wet(&mut self, wet: f32) {
+        self.wet.set_target(wet.clamp(0.0, 1.0));
+    }
+
+    /// ラップしたノードへの参照を取得
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// ラップしたノードへの可変参照を取得
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: AudioGraphNode> AudioGraphNode for WetDry<T> {
+    fn prepare(&mut self, sample_rate: f32, max_num_samples: usize) {
+        self.inner.prepare(sample_rate, max_num_samples);
+        self.dry_buffer = OwnedAudioBuffer::new(NUM_CHANNELS, max_num_samples);
+        self.latency = self.inner.latency_samples();
+        for delay in self.dry_delays.iter_mut() {
+            delay.prepare(self.latency);
+        }
+        self.wet.prepare(sample_rate, 20.0);
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) {
+        self.process_with_inputs(buffer, &InputPorts::empty());
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        for delay in self.dry_delays.iter_mut() {
+            delay.reset();
+        }
+    }
+
+    fn handle_note_event(&mut self, event: &NoteEvent) {
+        self.inner.handle_note_event(event);
+    }
+
+    fn update_transport(&mut self, transport: &Transport) {
+        self.inner.update_transport(transport);
+    }
+
+    fn set_tuning(&mut self, tuning: &Tuning) {
+        self.inner.set_tuning(tuning);
+    }
+
+    fn num_input_ports(&self) -> usize {
+        self.inner.num_input_ports()
+    }
+
+    fn num_output_ports(&self) -> usize {
+        self.inner.num_output_ports()
+    }
+
+    fn process_with_inputs(&mut self, buffer: &mut AudioBuffer, inputs: &InputPorts) {
+        self.process_with_outputs(buffer, inputs, &mut OutputPorts::empty());
+    }
+
+    fn process_with_outputs(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        inputs: &InputPorts,
+        outputs: &mut OutputPorts,
+    ) {
+        let num_channels = buffer.num_channels().min(NUM_CHANNELS);
+        let num_frames = buffer.num_frames().min(self.dry_buffer.max_frames());
+        self.dry_buffer.set_num_frames(num_frames);
+        let mut dry_buffer = self.dry_buffer.as_audio_buffer();
+
+        // ドライ信号をレイテンシー分遅延させて保持する
+        for (frame, dry_frame) in buffer.frames().zip(dry_buffer.frames_mut()) {
+            for (ch, (&sample, dry)) in frame.iter().zip(dry_frame.iter_mut()).enumerate() {
+                self.dry_delays[ch].write(sample);
+                *dry = self.dry_delays[ch].read(self.latency as f32);
+            }
+        }
+
+        self.inner.process_with_outputs(buffer, inputs, outputs);
+
+        for (frame, dry_frame) in buffer.frames_mut().zip(dry_buffer.frames()) {
+            let wet = self.wet.next_value();
+            for (sample, &dry) in frame.iter_mut().zip(dry_frame).take(num_channels) {
+                *sample = dry * (1.0 - wet) + *sample * wet;
+            }
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterInfo] {
+        &self.parameters
+    }
+
+    fn set_parameter(&mut self, id: &str, value: f32) -> bool {
+        if id == WET_PARAMETER.id {
+            self.set_wet(WET_PARAMETER.clamp(value));
+            return true;
+        }
+        self.inner.set_parameter(id, value)
+    }
+
+    fn get_parameter(&self, id: &str) -> Option<f32> {
+        if id == WET_PARAMETER.id {
+            return Some(self.wet.target());
+        }
+        self.inner.get_parameter(id)
+    }
+
+    fn modulation_output(&self) -> Option<f32> {
+        self.inner.modulation_output()
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.inner.latency_samples()
+    }
+
+    fn tail_samples(&self) -> usize {
+        self.inner.tail_samples()
+    }
+
+    fn on_added(&mut self, context: &NodeContext) {
+        self.inner.on_added(context);
+    }
+
+    fn on_removed(&mut self, context: &NodeContext) {
+        self.inner.on_removed(context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2サンプル遅延させて符号を反転するノード
+    struct DelayedInvertNode {
+        history: [f32; 2],
+    }
+
+    impl AudioGraphNode for DelayedInvertNode {
+        fn prepare(&mut self, _sample_rate: f32, _max_num_samples: usize) {}
+
+        fn process(&mut self, buffer: &mut AudioBuffer) {
+            for sample in buffer.as_mut_slice().iter_mut() {
+                let out = -self.history[1];
+                self.history = [*sample, self.history[0]];
+                *sample = out;
+            }
+        }
+
+        fn reset(&mut self) {}
+
+        fn latency_samples(&self) -> usize {
+            2
+        }
+    }
+
+    #[test]
+    fn test_wet_dry_aligns_dry_path_with_latency() {
+        let mut wet_dry = WetDry::new(DelayedInvertNode { history: [0.0; 2] });
+        wet_dry.prepare(1000.0, 4);
+        wet_dry.wet.set_current_and_target(0.5);
+
+        // モノラルのインパルス
+        let mut vector: Vec<f32> = vec![1.0, 0.0, 0.0, 0.0];
+        let mut buffer = AudioBuffer::new(1, 4, vector.as_mut_slice());
+        wet_dry.process(&mut buffer);
+
+        // ドライとウェットが同じタイミングにそろい、打ち消し合う
+        assert_eq!(vector, vec![0.0; 4]);
+        assert!(wet_dry.parameters().iter().any(|p| p.id == "wet"));
+    }
+}
@@ -0,0 +1,259 @@
+//! オーディオデバイスを使わずに、グラフをバッファへレンダリングします。
+//!
+//! テストやファイルへの書き出しなど、リアルタイムで再生する必要のない用途に使います。
+
+use crate::prelude::*;
+use crate::{
+    audio_buffer::OwnedAudioBuffer,
+    audio_buffer_utils,
+    audio_graph::{AudioGraph, NodeId},
+    wav::WavData,
+};
+
+/// グラフをブロック単位で処理して、結果をまとめて返すレンダラー
+///
+/// オーディオデバイスのコールバックと同じように、固定のブロックサイズでグラフを繰り返し処理します。
+/// デバイスに依存しないため、同じグラフからは常に同じ結果が得られます。
+pub struct OfflineRenderer {
+    /// サンプリングレート
+    sample_rate: f32,
+    /// 1 回の process で処理するフレーム数
+    block_size: usize,
+    /// チャンネル数
+    num_channels: usize,
+}
+
+impl OfflineRenderer {
+    /// 新しいOfflineRendererを作成
+    ///
+    /// # 引数
+    /// * `sample_rate` - レンダリングするサンプリングレート
+    /// * `block_size` - 1 回の process で処理するフレーム数
+    pub fn new(sample_rate: f32, block_size: usize) -> Self {
+        Self {
+            sample_rate,
+            block_size,
+            num_channels: 2, // 現在、AudioGraph は 2ch のみのサポート
+        }
+    }
+
+    /// グラフをレンダリングする
+    ///
+    /// グラフはこのレンダラーのサンプリングレートとブロックサイズで prepare されます。
+    /// 最後のブロックは `num_frames` に合わせて短くなります。
+    ///
+    /// # 引数
+    /// * `graph` - レンダリングするグラフ
+    /// * `input_node_id` - 入力ノードの ID
+    /// * `output_node_id` - 出力ノードの ID
+    /// * `num_frames` - レンダリングするフレーム数
+    ///
+    /// # 戻り値
+    /// * レンダリング結果（インターリーブ）
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn render(
+        &self,
+        graph: &mut AudioGraph,
+        input_node_id: NodeId,
+        output_node_id: NodeId,
+        num_frames: usize,
+    ) -> WavData {
+        self.render_blocks(graph, input_node_id, output_node_id, num_frames, None)
+    }
+
+    /// 入力を与えてグラフをレンダリングする
+    ///
+    /// 入力はブロックごとに入力ノードへ渡されます。入力のフレーム数だけレンダリングします。
+    ///
+    /// # 引数
+    /// * `graph` - レンダリングするグラフ
+    /// * `input_node_id` - 入力ノードの ID
+    /// * `output_node_id` - 出力ノードの ID
+    /// * `input` - グラフへの入力（インターリーブ、チャンネル数はこのレンダラーと同じ）
+    ///
+    /// # 戻り値
+    /// * レンダリング結果（インターリーブ）
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn render_with_input(
+        &self,
+        graph: &mut AudioGraph,
+        input_node_id: NodeId,
+        output_node_id: NodeId,
+        input: &[f32],
+    ) -> WavData {
+        let num_frames = input.len() / self.num_channels;
+        self.render_blocks(
+            graph,
+            input_node_id,
+            output_node_id,
+            num_frames,
+            Some(input),
+        )
+    }
+
+    /// 入力を与えてグラフをレンダリングし、入力の後にグラフのテールの分だけ無音を与えて続ける
+    ///
+    /// ディレイなどの残響を途中で切らずに書き出す場合に使います。テールの長さは AudioGraph::tail_samples です。
+    ///
+    /// # 引数
+    /// * `graph` - レンダリングするグラフ
+    /// * `input_node_id` - 入力ノードの ID
+    /// * `output_node_id` - 出力ノードの ID
+    /// * `input` - グラフへの入力（インターリーブ、チャンネル数はこのレンダラーと同じ）
+    ///
+    /// # 戻り値
+    /// * レンダリング結果（インターリーブ、入力のフレーム数とテールの合計の長さ）
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn render_with_tail(
+        &self,
+        graph: &mut AudioGraph,
+        input_node_id: NodeId,
+        output_node_id: NodeId,
+        input: &[f32],
+    ) -> WavData {
+        // テールの長さはサンプリングレートに依存するため、先に準備する
+        graph.prepare(self.sample_rate, self.block_size);
+        let tail = graph.tail_samples(output_node_id);
+        let mut padded = input.to_vec();
+        padded.resize(input.len() + tail * self.num_channels, 0.0);
+        self.render_with_input(graph, input_node_id, output_node_id, &padded)
+    }
+
+    fn render_blocks(
+        &self,
+        graph: &mut AudioGraph,
+        input_node_id: NodeId,
+        output_node_id: NodeId,
+        num_frames: usize,
+        input: Option<&[f32]>,
+    ) -> WavData {
+        graph.prepare(self.sample_rate, self.block_size);
+
+        let mut block = OwnedAudioBuffer::new(self.num_channels, self.block_size);
+        let mut samples = Vec::with_capacity(num_frames * self.num_channels);
+        let mut rendered = 0;
+        while rendered < num_frames {
+            let frames = self.block_size.min(num_frames - rendered);
+            block.set_num_frames(frames);
+            let mut buffer = block.as_audio_buffer();
+            match input {
+                Some(input) => buffer.as_mut_slice().copy_from_slice(
+                    &input[rendered * self.num_channels..(rendered + frames) * self.num_channels],
+                ),
+                None => audio_buffer_utils::clear_buffer(&mut buffer),
+            }
+            graph.process(&mut buffer, input_node_id, output_node_id);
+            samples.extend_from_slice(buffer.as_slice());
+            rendered += frames;
+        }
+
+        WavData {
+            samples,
+            channels: self.num_channels,
+            sample_rate: self.sample_rate as u32,
+        }
+    }
+}
+
+/// 2 つのオーディオデータが許容誤差の範囲で一致するかを比較する
+///
+/// # 引数
+/// * `actual` - 比較するデータ
+/// * `expected` - 基準となるデータ
+/// * `tolerance` - サンプルごとの差の絶対値の許容値
+///
+/// # 戻り値
+/// * 一致しない場合は、最初に見つかった違いを説明するエラーメッセージ
+pub fn compare(actual: &WavData, expected: &WavData, tolerance: f32) -> Result<(), String> {
+    if actual.channels != expected.channels {
+        return Err(format!(
+            "チャンネル数が異なります: {} != {}",
+            actual.channels, expected.channels
+        ));
+    }
+    if actual.sample_rate != expected.sample_rate {
+        return Err(format!(
+            "サンプリングレートが異なります: {} != {}",
+            actual.sample_rate, expected.sample_rate
+        ));
+    }
+    if actual.num_frames() != expected.num_frames() {
+        return Err(format!(
+            "フレーム数が異なります: {} != {}",
+            actual.num_frames(),
+            expected.num_frames()
+        ));
+    }
+    for (i, (a, e)) in actual.samples.iter().zip(&expected.samples).enumerate() {
+        // NaN も不一致として扱う
+        let diff = (a - e).abs();
+        if diff.is_nan() || diff > tolerance {
+            return Err(format!(
+                "フレーム {} のチャンネル {} が一致しません: {} != {}（許容誤差 {}）",
+                i / actual.channels,
+                i % actual.channels,
+                a,
+                e,
+                tolerance
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{Flanger, ImpulseGenerator, InputNode, OutputNode};
+
+    #[test]
+    fn test_render_with_partial_last_block() {
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let impulse_id = graph.add_node(Box::new(ImpulseGenerator::new()));
+        graph.add_edge(impulse_id, output_id).unwrap();
+
+        let renderer = OfflineRenderer::new(48000.0, 64);
+        let rendered = renderer.render(&mut graph, input_id, output_id, 100);
+
+        assert_eq!(rendered.num_frames(), 100);
+        assert_eq!(rendered.sample_rate, 48000);
+        assert_eq!(&rendered.samples[0..2], &[1.0, 1.0]);
+        assert!(rendered.samples[2..].iter().all(|&s| s == 0.0));
+        assert!(compare(&rendered, &rendered, 0.0).is_ok());
+    }
+
+    #[test]
+    fn test_render_with_tail() {
+        let mut graph = AudioGraph::new();
+        let input_id = graph.add_node(Box::new(InputNode::new()));
+        let output_id = graph.add_node(Box::new(OutputNode::new()));
+        let first_id = graph.add_node(Box::new(Flanger::new()));
+        let second_id = graph.add_node(Box::new(Flanger::new()));
+        graph.add_edge(input_id, first_id).unwrap();
+        graph.add_edge(first_id, second_id).unwrap();
+        graph.add_edge(second_id, output_id).unwrap();
+        graph.prepare(48000.0, 64);
+
+        // 直列のエフェクトのテールは合計される
+        let flanger_tail = graph.get_node(first_id).unwrap().tail_samples();
+        assert!(flanger_tail > 0);
+        assert_eq!(graph.tail_samples(output_id), flanger_tail * 2);
+
+        // 入力の後にテールの分だけレンダリングを続ける
+        let mut input = vec![0.0; 2 * 10];
+        input[0] = 1.0;
+        input[1] = 1.0;
+        let renderer = OfflineRenderer::new(48000.0, 64);
+        let rendered = renderer.render_with_tail(&mut graph, input_id, output_id, &input);
+        assert_eq!(rendered.num_frames(), 10 + flanger_tail * 2);
+        assert!(rendered.samples[2 * 10..].iter().any(|&s| s != 0.0));
+    }
+}
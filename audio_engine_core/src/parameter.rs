@@ -0,0 +1,64 @@
+//! ノードのパラメーターを文字列の ID で操作するための型を定義します。
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+/// パラメーターの情報
+///
+/// ノードは `AudioGraphNode::parameters` でこの情報の一覧を公開し、
+/// `id` を指定して `set_parameter` / `get_parameter` で値を操作できるようにします。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParameterInfo {
+    /// パラメーターの ID（ノード内で一意）
+    pub id: &'static str,
+    /// 表示用の名前
+    pub name: &'static str,
+    /// 最小値
+    pub min: f32,
+    /// 最大値
+    pub max: f32,
+    /// デフォルト値
+    pub default: f32,
+    /// 単位（例: "Hz", "dB"）。単位がない場合は空文字列。
+    pub unit: &'static str,
+}
+
+impl ParameterInfo {
+    /// 値をパラメーターの範囲に制限する
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.clamp(self.min, self.max)
+    }
+}
+
+/// パラメーターの一覧から ID で情報を検索する
+pub fn find_parameter<'a>(parameters: &'a [ParameterInfo], id: &str) -> Option<&'a ParameterInfo> {
+    parameters.iter().find(|info| info.id == id)
+}
+
+/// マクロの値（0〜1）をパラメーターの値に変換するカーブ
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MacroCurve {
+    /// 直線的に変換する
+    Linear,
+    /// 比が一定になるように変換する（周波数など）。最小値と最大値が同じ符号でない場合は直線になる。
+    Exponential,
+    /// マクロの値を指定した指数で累乗してから直線的に変換する
+    Power(f32),
+}
+
+impl MacroCurve {
+    /// マクロの値をパラメーターの値に変換する
+    ///
+    /// # 引数
+    /// * `value` - マクロの値（0〜1 に制限される）
+    /// * `min` - マクロの値が 0 のときのパラメーターの値
+    /// * `max` - マクロの値が 1 のときのパラメーターの値
+    pub fn map(&self, value: f32, min: f32, max: f32) -> f32 {
+        let value = value.clamp(0.0, 1.0);
+        match *self {
+            MacroCurve::Exponential if min * max > 0.0 => min * (max / min).powf(value),
+            MacroCurve::Power(exponent) => min + (max - min) * value.powf(exponent),
+            _ => min + (max - min) * value,
+        }
+    }
+}
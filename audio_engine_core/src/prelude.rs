@@ -0,0 +1,38 @@
+//! std の prelude の代わりに、各モジュールで読み込む型やトレイトです。
+//!
+//! `std` フィーチャーを無効にしたビルドでは std の prelude がないため、各モジュールで
+//! `use crate::prelude::*;` として alloc の型を読み込みます。f32 の sin などの数学関数は、
+//! libm で実装した num_traits::Float のメソッドとして呼び出されます。
+
+pub use alloc::boxed::Box;
+pub use alloc::format;
+pub use alloc::string::{String, ToString};
+pub use alloc::vec;
+pub use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+pub use num_traits::Float as _;
+
+/// std がないビルドで、f32 と f64 の `rem_euclid` を提供するトレイト
+///
+/// std の同名のメソッドと同じく、結果は常に 0 以上 `rhs.abs()` 未満になります。
+#[cfg(not(feature = "std"))]
+pub trait RemEuclid {
+    fn rem_euclid(self, rhs: Self) -> Self;
+}
+
+#[cfg(not(feature = "std"))]
+impl RemEuclid for f32 {
+    fn rem_euclid(self, rhs: f32) -> f32 {
+        let r = self % rhs;
+        if r < 0.0 { r + rhs.abs() } else { r }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl RemEuclid for f64 {
+    fn rem_euclid(self, rhs: f64) -> f64 {
+        let r = self % rhs;
+        if r < 0.0 { r + rhs.abs() } else { r }
+    }
+}
@@ -0,0 +1,333 @@
+//! オーディオスレッドから安全に使えるログ機能です。
+//!
+//! `process` の中からは、ロックやメモリアロケーションを伴う `println!` などを使えません。
+//! このモジュールでは、固定長のログレコードをロックフリーのリングバッファに書き込み、
+//! 別スレッド（RtLogger）から取り出して出力します。
+//! `std` フィーチャーを無効にした場合は RtLogger を使えないため、アプリケーションのメインループから drain を呼び出します。
+//!
+//! ノードからは `rt_error!` / `rt_warn!` / `rt_info!` / `rt_debug!` マクロで書き込みます。
+//!
+//! ```ignore
+//! use audio_engine_core::rt_warn;
+//!
+//! fn process(&mut self, buffer: &mut AudioBuffer) {
+//!     if buffer.num_frames() > self.max_frames {
+//!         rt_warn!("バッファが大きすぎます: {}", buffer.num_frames());
+//!     }
+//! }
+//! ```
+
+use core::fmt::{self, Write};
+
+use crate::bounded_queue::{BoundedQueue, Slot};
+
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::thread::{self, JoinHandle};
+
+/// 1 件のログメッセージの最大バイト数（超えた分は切り捨てる）
+pub const MAX_MESSAGE_LEN: usize = 128;
+
+/// グローバルなログキューに保持できるレコード数
+pub const LOG_CAPACITY: usize = 256;
+
+/// ログの重要度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+/// 固定長のログレコード
+///
+/// ヒープを使わないため、オーディオスレッドで作成できます。
+#[derive(Clone, Copy)]
+pub struct LogRecord {
+    /// 重要度
+    pub level: LogLevel,
+    /// 書き込んだモジュールのパス
+    pub target: &'static str,
+    /// メッセージ（UTF-8）
+    message: [u8; MAX_MESSAGE_LEN],
+    /// メッセージのバイト数
+    len: usize,
+}
+
+impl LogRecord {
+    /// 新しいLogRecordを作成
+    ///
+    /// メッセージが MAX_MESSAGE_LEN を超える場合は、文字の境界で切り捨てます。
+    pub fn new(level: LogLevel, target: &'static str, args: fmt::Arguments) -> Self {
+        let mut record = Self {
+            level,
+            target,
+            message: [0; MAX_MESSAGE_LEN],
+            len: 0,
+        };
+        // 切り捨ては write_str の中で行うため、エラーは起こらない
+        let _ = record.write_fmt(args);
+        record
+    }
+
+    /// メッセージ
+    pub fn message(&self) -> &str {
+        // write_str で文字の境界ごとに書き込んでいるため、常に有効な UTF-8
+        core::str::from_utf8(&self.message[..self.len]).unwrap_or("")
+    }
+}
+
+impl Write for LogRecord {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let remaining = MAX_MESSAGE_LEN - self.len;
+        let mut end = s.len().min(remaining);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.message[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogRecord")
+            .field("level", &self.level)
+            .field("target", &self.target)
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+/// 固定長のログレコードを受け渡す、ロックフリーのキュー
+///
+/// 複数のスレッドから同時に書き込み・取り出しができます。
+/// いっぱいの場合、書き込んだレコードは破棄され、破棄した件数が記録されます。
+pub struct RtLogQueue<const N: usize> {
+    queue: BoundedQueue<LogRecord, [Slot<LogRecord>; N]>,
+}
+
+impl<const N: usize> RtLogQueue<N> {
+    /// 新しいRtLogQueueを作成
+    pub const fn new() -> Self {
+        Self {
+            queue: BoundedQueue::from_slots([const { Slot::new() }; N]),
+        }
+    }
+
+    /// レコードを書き込む
+    ///
+    /// # 戻り値
+    /// * 書き込めた場合は true。いっぱいの場合は false（レコードは破棄される）
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn push(&self, record: LogRecord) -> bool {
+        self.queue.push(record)
+    }
+
+    /// 最も古いレコードを取り出す
+    pub fn pop(&self) -> Option<LogRecord> {
+        self.queue.pop()
+    }
+
+    /// いっぱいで破棄したレコード数を取得し、0 に戻す
+    pub fn take_dropped(&self) -> usize {
+        self.queue.take_dropped()
+    }
+}
+
+impl<const N: usize> Default for RtLogQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// グローバルなログキュー（rt_* マクロの書き込み先）
+static LOG_QUEUE: RtLogQueue<LOG_CAPACITY> = RtLogQueue::new();
+
+/// グローバルなログキューにレコードを書き込む
+///
+/// 通常は rt_* マクロから呼び出します。リアルタイムスレッドから呼び出せます。
+pub fn log(level: LogLevel, target: &'static str, args: fmt::Arguments) {
+    LOG_QUEUE.push(LogRecord::new(level, target, args));
+}
+
+/// グローバルなログキューから、たまったレコードを全て取り出す
+///
+/// # 引数
+/// * `sink` - 取り出したレコードを受け取る関数
+///
+/// # 戻り値
+/// * 前回の呼び出しから、キューがいっぱいで破棄したレコード数
+pub fn drain(mut sink: impl FnMut(&LogRecord)) -> usize {
+    while let Some(record) = LOG_QUEUE.pop() {
+        sink(&record);
+    }
+    LOG_QUEUE.take_dropped()
+}
+
+/// グローバルなログキューを定期的に取り出して出力するスレッド
+///
+/// drop するとスレッドを停止します。停止する前に、残っているレコードを出力します。
+#[cfg(feature = "std")]
+pub struct RtLogger {
+    /// スレッドが動作中かどうか
+    running: Arc<AtomicBool>,
+    /// ログを出力するスレッド
+    thread: Option<JoinHandle<()>>,
+}
+
+#[cfg(feature = "std")]
+impl RtLogger {
+    /// レコードを標準エラー出力に書き出すスレッドを開始
+    pub fn start() -> Self {
+        Self::with_sink(Duration::from_millis(50), |record| {
+            eprintln!(
+                "[{:?} {}] {}",
+                record.level,
+                record.target,
+                record.message()
+            );
+        })
+    }
+
+    /// レコードを任意の関数に渡すスレッドを開始
+    ///
+    /// # 引数
+    /// * `interval` - キューを確認する間隔
+    /// * `sink` - 取り出したレコードを受け取る関数
+    pub fn with_sink(
+        interval: Duration,
+        mut sink: impl FnMut(&LogRecord) + Send + 'static,
+    ) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = running.clone();
+            thread::Builder::new()
+                .name("rt-logger".to_string())
+                .spawn(move || {
+                    let flush = |sink: &mut dyn FnMut(&LogRecord)| {
+                        let dropped = drain(&mut *sink);
+                        if dropped > 0 {
+                            let args = format_args!("{} 件のログを破棄しました", dropped);
+                            sink(&LogRecord::new(LogLevel::Warn, module_path!(), args));
+                        }
+                    };
+                    while running.load(Ordering::Acquire) {
+                        flush(&mut sink);
+                        thread::sleep(interval);
+                    }
+                    flush(&mut sink);
+                })
+                .expect("ログのスレッドを開始できませんでした")
+        };
+        Self {
+            running,
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for RtLogger {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// エラーをリアルタイムスレッドから記録する（引数は format! と同じ）
+#[macro_export]
+macro_rules! rt_error {
+    ($($arg:tt)*) => {
+        $crate::rt_log::log($crate::rt_log::LogLevel::Error, module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// 警告をリアルタイムスレッドから記録する（引数は format! と同じ）
+#[macro_export]
+macro_rules! rt_warn {
+    ($($arg:tt)*) => {
+        $crate::rt_log::log($crate::rt_log::LogLevel::Warn, module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// 情報をリアルタイムスレッドから記録する（引数は format! と同じ）
+#[macro_export]
+macro_rules! rt_info {
+    ($($arg:tt)*) => {
+        $crate::rt_log::log($crate::rt_log::LogLevel::Info, module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// デバッグ情報をリアルタイムスレッドから記録する（引数は format! と同じ）
+#[macro_export]
+macro_rules! rt_debug {
+    ($($arg:tt)*) => {
+        $crate::rt_log::log($crate::rt_log::LogLevel::Debug, module_path!(), format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_no_alloc::assert_no_alloc;
+
+    use super::*;
+
+    #[test]
+    fn test_queue_push_pop_and_overflow() {
+        let queue: RtLogQueue<4> = RtLogQueue::new();
+        assert_no_alloc(|| {
+            for i in 0..6 {
+                queue.push(LogRecord::new(
+                    LogLevel::Warn,
+                    "test",
+                    format_args!("message {}", i),
+                ));
+            }
+        });
+        // 4 件を超えた分は破棄される
+        assert_eq!(queue.take_dropped(), 2);
+        for i in 0..4 {
+            assert_eq!(queue.pop().unwrap().message(), format!("message {}", i));
+        }
+        assert!(queue.pop().is_none());
+
+        // 周回後も書き込める
+        queue.push(LogRecord::new(
+            LogLevel::Info,
+            "test",
+            format_args!("again"),
+        ));
+        assert_eq!(queue.pop().unwrap().message(), "again");
+
+        // 長いメッセージは文字の境界で切り捨てる
+        let record = LogRecord::new(LogLevel::Info, "test", format_args!("{}", "あ".repeat(100)));
+        assert_eq!(record.message(), "あ".repeat(MAX_MESSAGE_LEN / 3));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_macros_write_to_logger() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let logger = RtLogger::with_sink(Duration::from_millis(1), move |record| {
+            let _ = sender.send((record.level, record.message().to_string()));
+        });
+        assert_no_alloc(|| {
+            crate::rt_warn!("xrun: {} frames", 64);
+        });
+        drop(logger);
+        let records: Vec<_> = receiver.try_iter().collect();
+        assert!(records.contains(&(LogLevel::Warn, "xrun: 64 frames".to_string())));
+    }
+}
@@ -0,0 +1,76 @@
+//! オーディオバッファのサンプル型を定義します。
+
+use core::ops::{Add, AddAssign, Mul, Sub};
+
+/// オーディオバッファに格納できるサンプル型（f32 または f64）
+///
+/// 通常の処理は f32 で行い、長いフィードバックネットワークや低いカットオフ周波数のフィルターなど、
+/// 数値的に敏感な処理では f64 を使います。
+pub trait Sample:
+    Copy
+    + Default
+    + PartialOrd
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + AddAssign
+    + core::fmt::Debug
+{
+    /// 無音の値
+    const ZERO: Self;
+
+    /// f32 から変換する
+    fn from_f32(value: f32) -> Self;
+
+    /// f32 に変換する
+    fn to_f32(self) -> f32;
+
+    /// f64 から変換する
+    fn from_f64(value: f64) -> Self;
+
+    /// f64 に変換する
+    fn to_f64(self) -> f64;
+}
+
+impl Sample for f32 {
+    const ZERO: Self = 0.0;
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Sample for f64 {
+    const ZERO: Self = 0.0;
+
+    fn from_f32(value: f32) -> Self {
+        value as f64
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
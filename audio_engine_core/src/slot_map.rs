@@ -0,0 +1,260 @@
+use crate::audio_graph::NodeId;
+use crate::prelude::*;
+
+/// SlotMap のキー
+///
+/// キーはスロットの番号を持ち、同じスロットを再利用した別のキーとは（世代などで）区別できる必要があります。
+pub(crate) trait SlotKey: Copy + Eq {
+    /// キーのスロットの番号
+    fn slot(&self) -> usize;
+}
+
+impl SlotKey for NodeId {
+    fn slot(&self) -> usize {
+        self.index()
+    }
+}
+
+impl SlotKey for usize {
+    fn slot(&self) -> usize {
+        *self
+    }
+}
+
+/// 値のないスロットを表す、entries の中の位置
+const VACANT: usize = usize::MAX;
+
+/// SlotMap - スロットの番号で引くマップ
+///
+/// キーのスロットの番号を添字にして値の位置を引くため、ハッシュを計算せずに値を引けます。
+/// 値は削除のたびに詰めて並べるため、値の数に比例する時間で全ての値をたどれます。
+/// 同じスロットを再利用した別のキーでは、値が見つかりません。
+///
+/// AudioGraph と DirectedGraph で、ノードごとの情報を保持するために使います。
+pub(crate) struct SlotMap<K, V> {
+    /// スロットごとの、entries の中の位置（値のないスロットは VACANT）
+    slots: Vec<usize>,
+    /// キーと値の組（削除した位置は末尾の組で埋める）
+    entries: Vec<(K, V)>,
+}
+
+impl<K: SlotKey, V> SlotMap<K, V> {
+    /// 空のSlotMapを作成
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// スロットの番号が `num_slots` 未満のキーの値を、メモリアロケーションを行わずに入れられるSlotMapを作成
+    pub fn with_capacity(num_slots: usize) -> Self {
+        let mut map = Self::new();
+        map.reserve(num_slots);
+        map
+    }
+
+    /// スロットの番号が `num_slots` 未満のキーの値を、メモリアロケーションを行わずに入れられるよう確保する
+    pub fn reserve(&mut self, num_slots: usize) {
+        self.slots
+            .reserve(num_slots.saturating_sub(self.slots.len()));
+        self.entries
+            .reserve(num_slots.saturating_sub(self.entries.len()));
+    }
+
+    /// 値の数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 値がないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 全ての値を削除する
+    ///
+    /// # 実装時の注意
+    /// 確保したメモリは保持し、値の数に比例する時間で終わります。
+    pub fn clear(&mut self) {
+        for (key, _) in self.entries.iter() {
+            self.slots[key.slot()] = VACANT;
+        }
+        self.entries.clear();
+    }
+
+    /// キーの値の、entries の中の位置
+    fn position(&self, key: &K) -> Option<usize> {
+        let position = *self.slots.get(key.slot())?;
+        (position != VACANT && self.entries[position].0 == *key).then_some(position)
+    }
+
+    /// キーの値を取得する
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let position = self.position(key)?;
+        Some(&self.entries[position].1)
+    }
+
+    /// キーの値を変更可能な参照で取得する
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let position = self.position(key)?;
+        Some(&mut self.entries[position].1)
+    }
+
+    /// キーの値があるかどうか
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// キーの値を設定する
+    ///
+    /// 同じスロットに別のキーの値がある場合は、その値を置き換えます。
+    ///
+    /// # 戻り値
+    /// * 同じキーの値があった場合は、その値
+    ///
+    /// # 実装時の注意
+    /// reserve で確保した範囲を超えない限り、メモリアロケーションを行いません。
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let slot = key.slot();
+        if slot >= self.slots.len() {
+            self.slots.resize(slot + 1, VACANT);
+        }
+        match self.slots[slot] {
+            VACANT => {
+                self.slots[slot] = self.entries.len();
+                self.entries.push((key, value));
+                None
+            }
+            position => {
+                let (previous_key, previous) =
+                    core::mem::replace(&mut self.entries[position], (key, value));
+                (previous_key == key).then_some(previous)
+            }
+        }
+    }
+
+    /// キーの値がなければ `default` で作成し、変更可能な参照を取得する
+    pub fn get_or_insert_with(&mut self, key: K, default: impl FnOnce() -> V) -> &mut V {
+        let position = match self.position(&key) {
+            Some(position) => position,
+            None => {
+                self.insert(key, default());
+                self.slots[key.slot()]
+            }
+        };
+        &mut self.entries[position].1
+    }
+
+    /// キーの値を削除する
+    ///
+    /// # 戻り値
+    /// * 削除した値
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let position = self.position(key)?;
+        self.slots[key.slot()] = VACANT;
+        let (_, value) = self.entries.swap_remove(position);
+        // 末尾から移した組の位置を更新する
+        if let Some((moved, _)) = self.entries.get(position) {
+            self.slots[moved.slot()] = position;
+        }
+        Some(value)
+    }
+
+    /// キーと値の組を返すイテレーター
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// キーと値の組を、値を変更可能な参照で返すイテレーター
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+
+    /// キーを返すイテレーター
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    /// 値を返すイテレーター
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    /// 値を変更可能な参照で返すイテレーター
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+}
+
+impl<K: SlotKey, V> Default for SlotMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: SlotKey, V> core::ops::Index<&K> for SlotMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("キーの値がありません")
+    }
+}
+
+impl<K: SlotKey, V> FromIterator<(K, V)> for SlotMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_map_distinguishes_generations() {
+        let old_id = NodeId::from_raw(3);
+        let new_id = NodeId::from_raw((1 << (usize::BITS / 2)) | 3);
+        let mut map = SlotMap::new();
+        assert_eq!(map.insert(old_id, "old"), None);
+        assert_eq!(map.get(&old_id), Some(&"old"));
+        assert_eq!(map.get(&NodeId::from_raw(0)), None);
+
+        // 同じスロットを再利用した別の世代のキーでは見つからず、設定すると置き換わる
+        assert_eq!(map.get(&new_id), None);
+        assert_eq!(map.remove(&new_id), None);
+        assert_eq!(map.insert(new_id, "new"), None);
+        assert_eq!(map.get(&old_id), None);
+        assert_eq!(map.len(), 1);
+
+        assert_eq!(map.remove(&new_id), Some("new"));
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_slot_map_keeps_entries_dense() {
+        let mut map = SlotMap::with_capacity(8);
+        for key in 0..8usize {
+            map.insert(key, key * 10);
+        }
+
+        // 削除した位置は末尾の値で埋め、残りの値は引き続き引ける
+        assert_eq!(map.remove(&2), Some(20));
+        assert_eq!(map.remove(&5), Some(50));
+        assert_eq!(map.values().count(), 6);
+        for key in [0, 1, 3, 4, 6, 7] {
+            assert_eq!(map.get(&key), Some(&(key * 10)));
+        }
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&7), None);
+        *map.get_or_insert_with(7, || 0) += 1;
+        assert_eq!(map.get(&7), Some(&1));
+    }
+}
@@ -0,0 +1,87 @@
+//! テンポや拍子、再生位置など、再生の進行状況（トランスポート）です。
+//!
+//! AudioGraph はブロックごとに AudioGraphNode::update_transport でノードにトランスポートを渡し、
+//! 再生中であればブロックの長さだけ位置を進めます。プラグインではホストから受け取った値を
+//! AudioGraph::set_transport でブロックごとに設定し、サービスではグラフが進める位置をそのまま使うため、
+//! テンポに同期するノードはどちらでも同じように動作します。
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+/// テンポや拍子、再生位置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transport {
+    /// テンポ（BPM）
+    pub tempo: f64,
+    /// 拍子の分子
+    pub time_sig_numerator: u32,
+    /// 拍子の分母
+    pub time_sig_denominator: u32,
+    /// ブロックの先頭の再生位置（四分音符を 1 拍とする拍数）
+    pub position_beats: f64,
+    /// 再生中かどうか
+    pub playing: bool,
+}
+
+impl Transport {
+    /// 新しいTransportを作成（120 BPM、4/4 拍子、停止中）
+    pub fn new() -> Self {
+        Self {
+            tempo: 120.0,
+            time_sig_numerator: 4,
+            time_sig_denominator: 4,
+            position_beats: 0.0,
+            playing: false,
+        }
+    }
+
+    /// 1 小節の長さ（四分音符を 1 拍とする拍数）
+    pub fn beats_per_bar(&self) -> f64 {
+        self.time_sig_numerator as f64 * 4.0 / self.time_sig_denominator.max(1) as f64
+    }
+
+    /// ブロックの先頭の小節の位置
+    ///
+    /// # 戻り値
+    /// * 小節番号（0 始まり）と、小節の先頭からの拍数の組
+    pub fn bar_position(&self) -> (i64, f64) {
+        let beats_per_bar = self.beats_per_bar();
+        let bar = (self.position_beats / beats_per_bar).floor();
+        (bar as i64, self.position_beats - bar * beats_per_bar)
+    }
+
+    /// 1 拍（四分音符）の長さ（サンプル数）
+    pub fn samples_per_beat(&self, sample_rate: f32) -> f64 {
+        sample_rate as f64 * 60.0 / self.tempo
+    }
+
+    /// 再生中であれば、指定したサンプル数だけ再生位置を進める
+    pub fn advance(&mut self, num_frames: usize, sample_rate: f32) {
+        if self.playing && self.tempo > 0.0 {
+            self.position_beats += num_frames as f64 / self.samples_per_beat(sample_rate);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_advances_while_playing() {
+        let mut transport = Transport::new();
+        transport.time_sig_numerator = 6;
+        transport.time_sig_denominator = 8;
+
+        // 停止中は進まない
+        transport.advance(48000, 48000.0);
+        assert_eq!(transport.position_beats, 0.0);
+
+        // 120 BPM では 1 秒で 2 拍進み、6/8 拍子の 1 小節は 3 拍
+        transport.playing = true;
+        transport.advance(48000 * 2, 48000.0);
+        assert_eq!(transport.position_beats, 4.0);
+        assert_eq!(transport.beats_per_bar(), 3.0);
+        assert_eq!(transport.bar_position(), (1, 1.0));
+    }
+}
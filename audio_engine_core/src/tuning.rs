@@ -0,0 +1,307 @@
+//! ノート番号から周波数への変換に使う調律（チューニングテーブル）です。
+//!
+//! デフォルトは 12 平均律（A4 = 440Hz）で、Scala の音階ファイル（.scl）と
+//! キーボードマッピングファイル（.kbm）から読み込んだ調律にも置き換えられます。
+//! AudioGraph::set_tuning で設定すると、AudioGraphNode::set_tuning でグラフの全てのノードに渡されます。
+//!
+//! 参考:
+//! https://www.huygens-fokker.org/scala/scl_format.html
+//! https://www.huygens-fokker.org/scala/help.htm#mappings
+
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::prelude::*;
+
+/// 調律を持つ MIDI のキーの数
+const NUM_KEYS: usize = 128;
+
+/// キーボードマッピングを指定しない場合に、音階の 1 段目を割り当てるキー（C4）
+const DEFAULT_MIDDLE_NOTE: i32 = 60;
+
+/// キーボードマッピングを指定しない場合の基準のキー（A4）と周波数
+const DEFAULT_REFERENCE_NOTE: i32 = 69;
+const DEFAULT_REFERENCE_FREQUENCY: f64 = 440.0;
+
+/// MIDI のキーごとの周波数の表
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tuning {
+    /// キーごとの周波数（Hz）
+    frequencies: [f32; NUM_KEYS],
+}
+
+/// Scala のキーボードマッピング
+struct KeyboardMapping {
+    /// 調律を変えるキーの範囲
+    first_key: i32,
+    last_key: i32,
+    /// 音階の 1 段目を割り当てるキー
+    middle_note: i32,
+    /// 周波数を指定するキーと、その周波数
+    reference_note: i32,
+    reference_frequency: f64,
+    /// マッピングを 1 周したときに進む音階の段数
+    octave_degree: i64,
+    /// キーごとの音階の段（`None` は割り当てなし）。空の場合はキーに音階の段を順に割り当てる
+    map: Vec<Option<i64>>,
+}
+
+impl Tuning {
+    /// 12 平均律（A4 = 440Hz）の調律を作成
+    pub fn equal_temperament() -> Self {
+        let mut frequencies = [0.0; NUM_KEYS];
+        for (key, frequency) in frequencies.iter_mut().enumerate() {
+            *frequency = equal_temperament_frequency(key as i32) as f32;
+        }
+        Self { frequencies }
+    }
+
+    /// Scala の音階とキーボードマッピングから調律を作成
+    ///
+    /// # 引数
+    /// * `scl` - 音階ファイル（.scl）の内容
+    /// * `kbm` - キーボードマッピングファイル（.kbm）の内容。`None` の場合は C4 に音階の 1 段目を割り当て、A4 を 440Hz とする
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok` で調律を返し、形式が不正な場合は `Err` でエラーメッセージを返す
+    ///
+    /// # 実装時の注意
+    /// マッピングで割り当てのないキーは、基準のキーからの 12 平均律の周波数になります。
+    pub fn from_scala(scl: &str, kbm: Option<&str>) -> Result<Self, String> {
+        let cents = parse_scl(scl)?;
+        let mapping = match kbm {
+            Some(kbm) => parse_kbm(kbm)?,
+            None => KeyboardMapping {
+                first_key: 0,
+                last_key: NUM_KEYS as i32 - 1,
+                middle_note: DEFAULT_MIDDLE_NOTE,
+                reference_note: DEFAULT_REFERENCE_NOTE,
+                reference_frequency: DEFAULT_REFERENCE_FREQUENCY,
+                octave_degree: cents.len() as i64,
+                map: Vec::new(),
+            },
+        };
+
+        let reference_cents = mapping
+            .key_cents(mapping.reference_note, &cents)
+            .ok_or_else(|| "基準のキーに音階の段が割り当てられていません".to_string())?;
+        let mut frequencies = [0.0; NUM_KEYS];
+        for (key, frequency) in frequencies.iter_mut().enumerate() {
+            let key = key as i32;
+            *frequency = match mapping.key_cents(key, &cents) {
+                Some(key_cents) if (mapping.first_key..=mapping.last_key).contains(&key) => {
+                    mapping.reference_frequency
+                        * 2.0f64.powf((key_cents - reference_cents) / 1200.0)
+                }
+                _ => {
+                    mapping.reference_frequency
+                        * 2.0f64.powf((key - mapping.reference_note) as f64 / 12.0)
+                }
+            } as f32;
+        }
+        Ok(Self { frequencies })
+    }
+
+    /// ノート番号を周波数（Hz）に変換する
+    ///
+    /// 小数のノート番号は隣り合うキーの間を対数で補間するため、ピッチベンドなどの計算にも使えます。
+    /// 0～127 の範囲外は、端のキーから 12 平均律で延長します。
+    ///
+    /// # 実装時の注意
+    /// メモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn frequency(&self, note: f32) -> f32 {
+        let last = (NUM_KEYS - 1) as f32;
+        if note < 0.0 {
+            return self.frequencies[0] * 2.0f32.powf(note / 12.0);
+        }
+        if note > last {
+            return self.frequencies[NUM_KEYS - 1] * 2.0f32.powf((note - last) / 12.0);
+        }
+        let key = (note.floor() as usize).min(NUM_KEYS - 2);
+        let fraction = note - key as f32;
+        let low = self.frequencies[key];
+        let high = self.frequencies[key + 1];
+        low * (high / low).powf(fraction)
+    }
+}
+
+impl KeyboardMapping {
+    /// キーの、音階の 1 段目からのセント
+    ///
+    /// # 戻り値
+    /// * 音階の段が割り当てられていないキーの場合は `None`
+    fn key_cents(&self, key: i32, cents: &[f64]) -> Option<f64> {
+        let offset = (key - self.middle_note) as i64;
+        let degree = if self.map.is_empty() {
+            offset
+        } else {
+            let size = self.map.len() as i64;
+            let degree = self.map[offset.rem_euclid(size) as usize]?;
+            degree + offset.div_euclid(size) * self.octave_degree
+        };
+        Some(degree_cents(degree, cents))
+    }
+}
+
+/// 音階の段の、1 段目からのセント（音階の最後の段を周期として繰り返す）
+fn degree_cents(degree: i64, cents: &[f64]) -> f64 {
+    let size = cents.len() as i64;
+    let period = cents[cents.len() - 1];
+    let step = degree.rem_euclid(size) as usize;
+    let within = if step == 0 { 0.0 } else { cents[step - 1] };
+    degree.div_euclid(size) as f64 * period + within
+}
+
+/// 12 平均律のキーの周波数
+fn equal_temperament_frequency(key: i32) -> f64 {
+    DEFAULT_REFERENCE_FREQUENCY * 2.0f64.powf((key - DEFAULT_REFERENCE_NOTE) as f64 / 12.0)
+}
+
+/// コメント（`!` で始まる行）を除いた行
+fn content_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().filter(|line| !line.starts_with('!'))
+}
+
+/// 音階ファイルを読み込み、2 段目以降の各段の 1 段目からのセントを返す（最後の段が周期）
+fn parse_scl(scl: &str) -> Result<Vec<f64>, String> {
+    // 1 行目は説明（空の場合もある）、2 行目は段の数
+    let mut lines = content_lines(scl).skip(1);
+    let count: usize = lines
+        .next()
+        .and_then(|line| line.trim().parse().ok())
+        .ok_or_else(|| "音階の段の数が読み込めません".to_string())?;
+    if count == 0 {
+        return Err("音階の段がありません".to_string());
+    }
+    let cents: Vec<f64> = lines
+        .take(count)
+        .map(|line| {
+            let value = line.split_whitespace().next().unwrap_or_default();
+            parse_pitch(value).ok_or_else(|| format!("音階の段が読み込めません: {}", line))
+        })
+        .collect::<Result<_, _>>()?;
+    if cents.len() != count {
+        return Err(format!(
+            "音階の段の数が一致しません: {}（{} 段と指定されています）",
+            cents.len(),
+            count
+        ));
+    }
+    if cents[count - 1] <= 0.0 {
+        return Err("音階の周期は 0 セントより大きい必要があります".to_string());
+    }
+    Ok(cents)
+}
+
+/// 音階の段をセントに変換する（`.` を含む場合はセント、それ以外は比率）
+fn parse_pitch(value: &str) -> Option<f64> {
+    if value.contains('.') {
+        return value.parse().ok();
+    }
+    let (numerator, denominator) = value.split_once('/').unwrap_or((value, "1"));
+    let numerator: f64 = numerator.parse().ok()?;
+    let denominator: f64 = denominator.parse().ok()?;
+    (numerator > 0.0 && denominator > 0.0).then(|| 1200.0 * (numerator / denominator).log2())
+}
+
+/// キーボードマッピングファイルを読み込む
+fn parse_kbm(kbm: &str) -> Result<KeyboardMapping, String> {
+    let mut lines = content_lines(kbm).map(|line| line.split_whitespace().next().unwrap_or(""));
+    let mut header = |name: &str| {
+        lines
+            .next()
+            .ok_or_else(|| format!("キーボードマッピングの{}がありません", name))
+    };
+    let parse_int = |name: &str, value: &str| {
+        value
+            .parse::<i64>()
+            .map_err(|_| format!("キーボードマッピングの{}が読み込めません: {}", name, value))
+    };
+    let size = parse_int("大きさ", header("大きさ")?)?;
+    let first_key = parse_int("最初のキー", header("最初のキー")?)?;
+    let last_key = parse_int("最後のキー", header("最後のキー")?)?;
+    let middle_note = parse_int("中央のキー", header("中央のキー")?)?;
+    let reference_note = parse_int("基準のキー", header("基準のキー")?)?;
+    let reference_value = header("基準の周波数")?;
+    let reference_frequency: f64 = reference_value
+        .parse()
+        .ok()
+        .filter(|frequency: &f64| *frequency > 0.0)
+        .ok_or_else(|| {
+            format!(
+                "キーボードマッピングの基準の周波数が読み込めません: {}",
+                reference_value
+            )
+        })?;
+    let octave_degree = parse_int("周期の段", header("周期の段")?)?;
+    if size < 0 {
+        return Err(format!("キーボードマッピングの大きさが不正です: {}", size));
+    }
+    let map = (0..size)
+        .map(|_| match header("割り当て")? {
+            "x" | "X" => Ok(None),
+            value => parse_int("割り当て", value).map(Some),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(KeyboardMapping {
+        first_key: first_key as i32,
+        last_key: last_key as i32,
+        middle_note: middle_note as i32,
+        reference_note: reference_note as i32,
+        reference_frequency,
+        octave_degree,
+        map,
+    })
+}
+
+/// Scala の音階ファイルとキーボードマッピングファイルを読み込んで調律を作成する
+///
+/// # 引数
+/// * `scl_path` - 音階ファイル（.scl）のパス
+/// * `kbm_path` - キーボードマッピングファイル（.kbm）のパス。`None` の場合は Tuning::from_scala のデフォルトを使う
+///
+/// # 戻り値
+/// * 成功した場合は `Ok` で調律を返し、読み込めない場合は `Err` でエラーメッセージを返す
+#[cfg(feature = "std")]
+pub fn read_scala_files(
+    scl_path: impl AsRef<Path>,
+    kbm_path: Option<&Path>,
+) -> Result<Tuning, String> {
+    let read = |path: &Path| {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("ファイルを読み込めません: {}: {}", path.display(), e))
+    };
+    let scl = read(scl_path.as_ref())?;
+    let kbm = kbm_path.map(read).transpose()?;
+    Tuning::from_scala(&scl, kbm.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scala_tuning() {
+        // 12 平均律の音階は、デフォルトの調律と同じになる
+        let equal = "! 12edo.scl\n12-TET\n 12\n!\n100.0\n200.0\n300.0\n400.0\n500.0\n600.0\n700.0\n800.0\n900.0\n1000.0\n1100.0\n2/1\n";
+        let tuning = Tuning::from_scala(equal, None).unwrap();
+        let default = Tuning::equal_temperament();
+        for note in [0.0, 60.0, 69.0, 69.5, 127.0] {
+            assert!((tuning.frequency(note) / default.frequency(note) - 1.0).abs() < 1e-5);
+        }
+        assert!((default.frequency(81.0) - 880.0).abs() < 1e-3);
+
+        // 5 音の純正律の音階を、マッピングで白鍵だけに割り当てる（黒鍵は割り当てなし）
+        let scale = "pentatonic\n5\n9/8\n5/4\n3/2\n5/3\n2/1\n";
+        let kbm =
+            "! white keys\n12\n0\n127\n60\n60\n261.0\n5\n0\nx\n1\nx\n2\nx\nx\n3\nx\n4\nx\nx\n";
+        let tuning = Tuning::from_scala(scale, Some(kbm)).unwrap();
+        assert!((tuning.frequency(60.0) - 261.0).abs() < 1e-3);
+        assert!((tuning.frequency(67.0) - 261.0 * 1.5).abs() < 1e-3);
+        assert!((tuning.frequency(72.0) - 522.0).abs() < 1e-3);
+        assert!((tuning.frequency(48.0) - 130.5).abs() < 1e-3);
+
+        // 段の数が足りない音階はエラー
+        assert!(Tuning::from_scala("broken\n3\n100.0\n", None).is_err());
+    }
+}
@@ -0,0 +1,287 @@
+//! WAV ファイルの読み込みと書き出しを行います。
+//!
+//! 読み込みの対応フォーマットはリニア PCM（8/16/24/32bit）と IEEE float（32bit）です。
+//! 書き出しは IEEE float（32bit）で行います。
+
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::dsp;
+use crate::prelude::*;
+
+/// 読み込んだ WAV データ
+pub struct WavData {
+    /// サンプル（インターリーブ、-1～1 に正規化済み）
+    pub samples: Vec<f32>,
+    /// チャンネル数
+    pub channels: usize,
+    /// サンプリングレート（Hz）
+    pub sample_rate: u32,
+}
+
+impl WavData {
+    /// フレーム数を取得する
+    pub fn num_frames(&self) -> usize {
+        self.samples.len().checked_div(self.channels).unwrap_or(0)
+    }
+
+    /// 全チャンネルを平均したモノラルのサンプル列を作成する
+    pub fn to_mono(&self) -> Vec<f32> {
+        if self.channels == 0 {
+            return Vec::new();
+        }
+        self.samples
+            .chunks_exact(self.channels)
+            .map(|frame| frame.iter().sum::<f32>() / self.channels as f32)
+            .collect()
+    }
+
+    /// サンプリングレートを変換した WavData を作成する
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn resampled(&self, sample_rate: u32) -> WavData {
+        WavData {
+            samples: dsp::resample(
+                &self.samples,
+                self.channels,
+                self.sample_rate as f32,
+                sample_rate as f32,
+            ),
+            channels: self.channels,
+            sample_rate,
+        }
+    }
+}
+
+/// WAV ファイルを読み込む
+///
+/// # 実装時の注意
+/// この関数はファイル IO とメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+#[cfg(feature = "std")]
+pub fn read_wav(path: impl AsRef<Path>) -> Result<WavData, String> {
+    let bytes = std::fs::read(path.as_ref()).map_err(|e| {
+        format!(
+            "WAV ファイルを読み込めませんでした: {:?} ({})",
+            path.as_ref(),
+            e
+        )
+    })?;
+    parse_wav(&bytes)
+}
+
+/// メモリ上の WAV データを解析する
+///
+/// # 実装時の注意
+/// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+pub fn parse_wav(bytes: &[u8]) -> Result<WavData, String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("RIFF/WAVE ヘッダーが見つかりません".to_string());
+    }
+
+    let mut format: Option<(u16, usize, u32, u16)> = None;
+    let mut data: Option<&[u8]> = None;
+
+    // チャンクを順に読む
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = u32::from_le_bytes([
+            bytes[pos + 4],
+            bytes[pos + 5],
+            bytes[pos + 6],
+            bytes[pos + 7],
+        ]) as usize;
+        let body_start = pos + 8;
+        let body_end = (body_start + size).min(bytes.len());
+        let body = &bytes[body_start..body_end];
+
+        if id == b"fmt " {
+            if body.len() < 16 {
+                return Err("fmt チャンクが短すぎます".to_string());
+            }
+            let mut format_tag = u16::from_le_bytes([body[0], body[1]]);
+            let channels = u16::from_le_bytes([body[2], body[3]]) as usize;
+            let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+            let bits_per_sample = u16::from_le_bytes([body[14], body[15]]);
+            // WAVE_FORMAT_EXTENSIBLE の場合はサブフォーマットの先頭2バイトが実際のフォーマット
+            if format_tag == 0xFFFE && body.len() >= 26 {
+                format_tag = u16::from_le_bytes([body[24], body[25]]);
+            }
+            format = Some((format_tag, channels, sample_rate, bits_per_sample));
+        } else if id == b"data" {
+            data = Some(body);
+        }
+
+        // チャンクは2バイト境界にそろえられている
+        pos = body_start + size + (size & 1);
+    }
+
+    let (format_tag, channels, sample_rate, bits_per_sample) =
+        format.ok_or("fmt チャンクが見つかりません")?;
+    let data = data.ok_or("data チャンクが見つかりません")?;
+    if channels == 0 {
+        return Err("チャンネル数が 0 です".to_string());
+    }
+
+    let samples: Vec<f32> = match (format_tag, bits_per_sample) {
+        (1, 8) => data.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect(),
+        (1, 16) => data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        (1, 24) => data
+            .chunks_exact(3)
+            .map(|b| {
+                let v = i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8;
+                v as f32 / 8_388_608.0
+            })
+            .collect(),
+        (1, 32) => data
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (3, 32) => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        _ => {
+            return Err(format!(
+                "対応していないフォーマットです: format_tag={}, bits_per_sample={}",
+                format_tag, bits_per_sample
+            ));
+        }
+    };
+
+    Ok(WavData {
+        samples,
+        channels,
+        sample_rate,
+    })
+}
+
+/// WAV ファイルを書き出す（IEEE float 32bit）
+///
+/// # 実装時の注意
+/// この関数はファイル IO とメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+#[cfg(feature = "std")]
+pub fn write_wav(path: impl AsRef<Path>, wav: &WavData) -> Result<(), String> {
+    std::fs::write(path.as_ref(), encode_wav(wav)).map_err(|e| {
+        format!(
+            "WAV ファイルを書き出せませんでした: {:?} ({})",
+            path.as_ref(),
+            e
+        )
+    })
+}
+
+/// WavData を WAV 形式（IEEE float 32bit）のバイト列に変換する
+///
+/// # 実装時の注意
+/// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+pub fn encode_wav(wav: &WavData) -> Vec<u8> {
+    let header = encode_wav_header(wav.channels, wav.sample_rate, wav.samples.len());
+    let mut bytes = Vec::with_capacity(header.len() + wav.samples.len() * 4);
+    bytes.extend_from_slice(&header);
+    for sample in wav.samples.iter() {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+/// WAV 形式（IEEE float 32bit）のヘッダーを作成する
+///
+/// 書き出しながら録音する場合は、仮のサンプル数でヘッダーを書いておき、完了時に書き直します。
+///
+/// # 引数
+/// * `channels` - チャンネル数
+/// * `sample_rate` - サンプリングレート（Hz）
+/// * `num_samples` - 全チャンネルのサンプル数の合計
+pub fn encode_wav_header(channels: usize, sample_rate: u32, num_samples: usize) -> [u8; 44] {
+    let channels = channels as u16;
+    let block_align = channels * 4;
+    let data_size = (num_samples * 4) as u32;
+    let mut header = [0u8; 44];
+    let fields: [&[u8]; 13] = [
+        b"RIFF",
+        &(36 + data_size).to_le_bytes(),
+        b"WAVE",
+        b"fmt ",
+        &16u32.to_le_bytes(),
+        &3u16.to_le_bytes(),
+        &channels.to_le_bytes(),
+        &sample_rate.to_le_bytes(),
+        &(sample_rate * block_align as u32).to_le_bytes(),
+        &block_align.to_le_bytes(),
+        &32u16.to_le_bytes(),
+        b"data",
+        &data_size.to_le_bytes(),
+    ];
+    let mut pos = 0;
+    for field in fields {
+        header[pos..pos + field.len()].copy_from_slice(field);
+        pos += field.len();
+    }
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 16bit PCM の WAV データを作成する
+    fn make_wav_16bit(channels: u16, sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+        let data_size = (samples.len() * 2) as u32;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&channels.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for s in samples {
+            bytes.extend_from_slice(&s.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parse_wav_16bit() {
+        let bytes = make_wav_16bit(2, 48000, &[0, 16384, -32768, 32767]);
+        let wav = parse_wav(&bytes).unwrap();
+
+        assert_eq!(wav.channels, 2);
+        assert_eq!(wav.sample_rate, 48000);
+        assert_eq!(wav.num_frames(), 2);
+        assert_eq!(wav.samples[0], 0.0);
+        assert_eq!(wav.samples[1], 0.5);
+        assert_eq!(wav.samples[2], -1.0);
+        assert!((wav.to_mono()[0] - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_wav_rejects_invalid_header() {
+        assert!(parse_wav(b"not a wav file").is_err());
+    }
+
+    #[test]
+    fn test_encode_wav_round_trip() {
+        let wav = WavData {
+            samples: vec![0.0, 0.25, -0.5, 1.0],
+            channels: 2,
+            sample_rate: 44100,
+        };
+        let decoded = parse_wav(&encode_wav(&wav)).unwrap();
+
+        assert_eq!(decoded.channels, 2);
+        assert_eq!(decoded.sample_rate, 44100);
+        assert_eq!(decoded.samples, wav.samples);
+    }
+}
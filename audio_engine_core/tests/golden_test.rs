@@ -0,0 +1,131 @@
+//! 基準となるグラフをオフラインでレンダリングし、保存済みのゴールデンファイルと比較するテスト
+//!
+//! ゴールデンファイルは `tests/golden/<名前>.wav` にあります。
+//! 意図して出力を変えた場合は、`UPDATE_GOLDEN=1 cargo test` で書き直してください。
+
+use std::path::PathBuf;
+
+use audio_engine_core::audio_graph::AudioGraph;
+use audio_engine_core::nodes::{
+    FeedbackSineSubgraph, GainProcessor, ImpulseGenerator, InputNode, OutputNode, SineGenerator,
+    TapIn, TapOut, TestSignal,
+};
+use audio_engine_core::offline::{self, OfflineRenderer};
+use audio_engine_core::wav::{self, WavData};
+
+/// レンダリングするサンプリングレート
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// 1 回の process で処理するフレーム数
+const BLOCK_SIZE: usize = 256;
+
+/// レンダリングするフレーム数（最後のブロックが短くなるよう、ブロックサイズの倍数からずらしている）
+const NUM_FRAMES: usize = 4000;
+
+/// サンプルごとの許容誤差（プラットフォームによる浮動小数点演算の差を吸収する）
+const TOLERANCE: f32 = 1e-4;
+
+/// レンダリング結果をゴールデンファイルと比較する
+///
+/// 環境変数 `UPDATE_GOLDEN` が設定されている場合は、比較せずにゴールデンファイルを書き直します。
+fn assert_golden(name: &str, rendered: &WavData) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect::<PathBuf>()
+        .with_extension("wav");
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        wav::write_wav(&path, rendered).unwrap();
+        return;
+    }
+
+    let expected = wav::read_wav(&path).unwrap_or_else(|e| {
+        panic!(
+            "{}\nUPDATE_GOLDEN=1 を指定してゴールデンファイルを作成してください。",
+            e
+        )
+    });
+    if let Err(e) = offline::compare(rendered, &expected, TOLERANCE) {
+        panic!("{} がゴールデンファイルと一致しません: {}", name, e);
+    }
+}
+
+#[test]
+fn test_two_sine_waves() {
+    let mut graph = AudioGraph::new();
+    let mut sine_generator1 = SineGenerator::new();
+    let mut sine_generator2 = SineGenerator::new();
+    sine_generator1.set_frequency(220.0);
+    sine_generator2.set_frequency(523.25);
+
+    let node_id_in = graph.add_node(Box::new(InputNode::new()));
+    let node_id_out = graph.add_node(Box::new(OutputNode::new()));
+    let node_id_s1 = graph.add_node(Box::new(sine_generator1));
+    let node_id_s2 = graph.add_node(Box::new(sine_generator2));
+    graph.add_edge(node_id_s1, node_id_out).unwrap();
+    graph.add_edge(node_id_s2, node_id_out).unwrap();
+
+    let renderer = OfflineRenderer::new(SAMPLE_RATE, BLOCK_SIZE);
+    let rendered = renderer.render(&mut graph, node_id_in, node_id_out, NUM_FRAMES);
+    assert_golden("two_sine_waves", &rendered);
+}
+
+#[test]
+fn test_feedback_delay() {
+    let mut graph = AudioGraph::new();
+    let mut tap_in = TapIn::new();
+    let mut tap_out = TapOut::new(tap_in.shared_buffer());
+    let mut gain = GainProcessor::new();
+    tap_in.set_max_delay_time_ms(50.0);
+    tap_out.set_delay_time_ms(10.0);
+    gain.set_gain(0.5);
+
+    let node_id_in = graph.add_node(Box::new(InputNode::new()));
+    let node_id_out = graph.add_node(Box::new(OutputNode::new()));
+    let node_id_impulse_generator = graph.add_node(Box::new(ImpulseGenerator::new()));
+    let node_id_tap_in = graph.add_node(Box::new(tap_in));
+    let node_id_tap_out = graph.add_node(Box::new(tap_out));
+    let node_id_gain = graph.add_node(Box::new(gain));
+    graph
+        .add_edge(node_id_impulse_generator, node_id_tap_in)
+        .unwrap();
+    graph.add_edge(node_id_tap_out, node_id_out).unwrap();
+    graph.add_edge(node_id_tap_out, node_id_gain).unwrap();
+    graph.add_edge(node_id_gain, node_id_tap_in).unwrap();
+
+    let renderer = OfflineRenderer::new(SAMPLE_RATE, BLOCK_SIZE);
+    let rendered = renderer.render(&mut graph, node_id_in, node_id_out, NUM_FRAMES);
+    assert_golden("feedback_delay", &rendered);
+}
+
+#[test]
+fn test_feedback_sine() {
+    let mut graph = AudioGraph::new();
+    let node_id_in = graph.add_node(Box::new(InputNode::new()));
+    let node_id_out = graph.add_node(Box::new(OutputNode::new()));
+    let node_id_feedback_sine = graph.add_node(Box::new(FeedbackSineSubgraph::new()));
+    graph.add_edge(node_id_feedback_sine, node_id_out).unwrap();
+
+    let renderer = OfflineRenderer::new(SAMPLE_RATE, BLOCK_SIZE);
+    let rendered = renderer.render(&mut graph, node_id_in, node_id_out, NUM_FRAMES);
+    assert_golden("feedback_sine", &rendered);
+}
+
+#[test]
+fn test_exponential_sweep() {
+    let mut graph = AudioGraph::new();
+    let mut sweep = TestSignal::new();
+    sweep.set_frequencies(100.0, 10000.0);
+    sweep.set_duration(0.05);
+    sweep.set_delay(0.005);
+
+    let node_id_in = graph.add_node(Box::new(InputNode::new()));
+    let node_id_out = graph.add_node(Box::new(OutputNode::new()));
+    let node_id_sweep = graph.add_node(Box::new(sweep));
+    graph.add_edge(node_id_sweep, node_id_out).unwrap();
+
+    let renderer = OfflineRenderer::new(SAMPLE_RATE, BLOCK_SIZE);
+    let rendered = renderer.render(&mut graph, node_id_in, node_id_out, NUM_FRAMES);
+    assert_golden("exponential_sweep", &rendered);
+}
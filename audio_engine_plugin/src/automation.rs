@@ -0,0 +1,175 @@
+//! ホストからオートメーションできる汎用のパラメーター（オートメーションスロット）です。
+//!
+//! プラグインはスロットを NUM_AUTOMATION_SLOTS 個公開し、エディターでグラフ内の任意のノードのパラメーターに割り当てます。
+//! スロットの値（0〜1）は、割り当てたパラメーターの最小値から最大値までの範囲に変換して設定します。
+
+use nih_plug::prelude::*;
+
+use audio_engine_core::audio_graph::{AudioGraph, NodeId};
+use audio_engine_core::parameter::ParameterInfo;
+
+/// オートメーションスロットの数
+pub const NUM_AUTOMATION_SLOTS: usize = 32;
+
+/// 保存するスロットの割り当て（スロットの番号、ノードの名前、パラメーター ID）
+///
+/// ノード ID は initialize でグラフを組み立て直すたびに変わるため、エディターに表示するノードの名前で保存し、
+/// initialize のたびに Automation::restore でノード ID に解決します。
+pub type AutomationBinding = (usize, String, String);
+
+/// オートメーションスロット 1 つ分のパラメーター
+#[derive(Params)]
+pub struct AutomationSlotParams {
+    /// スロットの値（0〜1）
+    #[id = "automation"]
+    pub value: FloatParam,
+}
+
+impl AutomationSlotParams {
+    /// スロットのパラメーターを作成
+    ///
+    /// # 引数
+    /// * `index` - スロットの番号（0 始まり）
+    pub fn new(index: usize) -> Self {
+        Self {
+            value: FloatParam::new(
+                format!("オートメーション {}", index + 1),
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage())
+            .with_unit("%"),
+        }
+    }
+}
+
+/// スロットを割り当てたノードのパラメーター
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutomationTarget {
+    /// ノードのID
+    pub node_id: NodeId,
+    /// ノードの名前（保存する割り当てに使う）
+    pub node_name: &'static str,
+    /// パラメーター
+    pub parameter: ParameterInfo,
+}
+
+impl AutomationTarget {
+    /// 保存した割り当てから、グラフ内のパラメーターを探す
+    ///
+    /// # 引数
+    /// * `graph` - 割り当て先のパラメーターを探すグラフ
+    /// * `nodes` - グラフ内のノードと、その名前
+    /// * `node_name` - 保存したノードの名前
+    /// * `parameter_id` - 保存したパラメーター ID
+    ///
+    /// # 戻り値
+    /// * ノードとパラメーターが存在する場合は `Some` で返す
+    pub fn find(
+        graph: &AudioGraph,
+        nodes: &[(NodeId, &'static str)],
+        node_name: &str,
+        parameter_id: &str,
+    ) -> Option<Self> {
+        let &(node_id, node_name) = nodes.iter().find(|&&(_, name)| name == node_name)?;
+        let parameter = *graph
+            .get_node(node_id)?
+            .parameters()
+            .iter()
+            .find(|info| info.id == parameter_id)?;
+        Some(Self {
+            node_id,
+            node_name,
+            parameter,
+        })
+    }
+}
+
+/// オーディオスレッドで使う、スロットの割り当てと前回の値
+pub struct Automation {
+    /// スロットごとの割り当て
+    targets: [Option<AutomationTarget>; NUM_AUTOMATION_SLOTS],
+    /// スロットごとの、前回のブロックで反映した値（未反映の場合は NaN）
+    last_values: [f32; NUM_AUTOMATION_SLOTS],
+}
+
+impl Automation {
+    /// 割り当てのないAutomationを作成
+    pub fn new() -> Self {
+        Self {
+            targets: [None; NUM_AUTOMATION_SLOTS],
+            last_values: [f32::NAN; NUM_AUTOMATION_SLOTS],
+        }
+    }
+
+    /// 保存した割り当てを読み込む
+    ///
+    /// # 引数
+    /// * `graph` - 割り当て先のパラメーターを探すグラフ
+    /// * `nodes` - グラフ内のノードと、その名前
+    /// * `bindings` - 保存した割り当て。グラフに存在しないノードやパラメーターへの割り当ては無視する
+    pub fn restore(
+        &mut self,
+        graph: &AudioGraph,
+        nodes: &[(NodeId, &'static str)],
+        bindings: &[AutomationBinding],
+    ) {
+        *self = Self::new();
+        for (slot, node_name, parameter_id) in bindings.iter() {
+            let target = AutomationTarget::find(graph, nodes, node_name, parameter_id);
+            if let Some(entry) = self.targets.get_mut(*slot) {
+                *entry = target;
+            }
+        }
+    }
+
+    /// スロットの割り当てを変更する
+    ///
+    /// # 引数
+    /// * `slot` - スロットの番号
+    /// * `target` - 割り当てるパラメーター。`None` の場合は割り当てを解除する
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn bind(&mut self, slot: usize, target: Option<AutomationTarget>) {
+        if let Some(entry) = self.targets.get_mut(slot) {
+            *entry = target;
+            // 次のブロックで現在のスロットの値を反映する
+            self.last_values[slot] = f32::NAN;
+        }
+    }
+
+    /// 値が変わったスロットを、割り当てたパラメーターに反映する
+    ///
+    /// エディターなどで設定した値を上書きしないよう、スロットの値が変わったときだけ設定します。
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub fn apply(&mut self, slots: &[AutomationSlotParams], graph: &mut AudioGraph) {
+        for ((target, last_value), slot) in self
+            .targets
+            .iter()
+            .zip(self.last_values.iter_mut())
+            .zip(slots.iter())
+        {
+            let Some(target) = target else {
+                continue;
+            };
+            let value = slot.value.value();
+            if value == *last_value {
+                continue;
+            }
+            *last_value = value;
+            // 削除されたノードへの割り当ては、エラーメッセージを作らないようにここで読み飛ばす
+            if graph.get_node(target.node_id).is_some() {
+                let info = &target.parameter;
+                let _ = graph.set_parameter(
+                    target.node_id,
+                    info.id,
+                    info.min + (info.max - info.min) * value,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! プラグインのエディターです。
+//!
+//! 現在のノードグラフとノードごとのレベルを表示し、ホストのパラメーターとノードのパラメーターをスライダーで操作できます。
+//! ノードのパラメーターは、ホストからオートメーションできるスロットに割り当てられます。
+//! egui のデフォルトのフォントには日本語のグリフがないため、画面に表示する名前は英語にしています。
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::SyncSender;
+use std::sync::{Arc, Mutex};
+
+use nih_plug::prelude::*;
+use nih_plug_egui::egui::{self, Align2, Color32, FontId, Rect, Sense, Stroke, Vec2};
+use nih_plug_egui::{create_egui_editor, widgets, EguiState};
+
+use audio_engine_core::audio_graph::{AudioGraph, NodeId};
+use audio_engine_core::parameter::ParameterInfo;
+
+use crate::automation::{AutomationBinding, AutomationTarget, NUM_AUTOMATION_SLOTS};
+use crate::rust_audio_engine::RustAudioEngineParams;
+
+/// エディターの初期サイズ（幅、高さ）
+const EDITOR_SIZE: (u32, u32) = (640, 520);
+
+/// グラフに描くノードの箱の大きさ
+const NODE_SIZE: Vec2 = Vec2::new(110.0, 36.0);
+
+/// グラフの列（接続の深さ）と行の間隔
+const NODE_SPACING: Vec2 = Vec2::new(150.0, 52.0);
+
+/// レベルメーターの表示を、描画ごとに下げる割合
+const METER_DECAY: f32 = 0.85;
+
+/// エディターからオーディオスレッドへ送る操作
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorMessage {
+    /// ノードのパラメーターを設定する
+    SetParameter {
+        node_id: NodeId,
+        parameter_id: &'static str,
+        value: f32,
+    },
+    /// オートメーションスロットの割り当てを変更する（`None` の場合は解除する）
+    BindAutomation {
+        slot: usize,
+        target: Option<AutomationTarget>,
+    },
+}
+
+/// エディターの状態の初期値を作成
+pub fn default_state() -> Arc<EguiState> {
+    EguiState::from_size(EDITOR_SIZE.0, EDITOR_SIZE.1)
+}
+
+/// エディターに表示するノード
+struct NodeView {
+    /// ノードのID
+    id: NodeId,
+    /// 表示名
+    name: &'static str,
+    /// パラメーターと、エディターで設定した値
+    parameters: Vec<(ParameterInfo, f32)>,
+    /// 表示する列（入力側からの接続の深さ）
+    column: usize,
+    /// 列の中での行
+    row: usize,
+}
+
+/// エディターに表示するグラフ
+///
+/// プラグインの initialize でグラフを組み立てた後に作り直します。
+pub struct GraphView {
+    /// ノード
+    nodes: Vec<NodeView>,
+    /// 接続（nodes のインデックスの組）
+    edges: Vec<(usize, usize)>,
+    /// ノードごとの出力のピーク（f32 のビット列。nodes と同じ順番）
+    meters: Arc<[AtomicU32]>,
+}
+
+impl GraphView {
+    /// 空のグラフを作成
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            meters: Arc::new([]),
+        }
+    }
+
+    /// オーディオグラフから表示するグラフを作成
+    ///
+    /// # 引数
+    /// * `graph` - 表示するオーディオグラフ
+    /// * `names` - 表示するノードと、その表示名
+    ///
+    /// # 実装時の注意
+    /// この関数はメモリアロケーションを行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn from_graph(graph: &AudioGraph, names: &[(NodeId, &'static str)]) -> Self {
+        let mut nodes: Vec<NodeView> = names
+            .iter()
+            .map(|&(id, name)| NodeView {
+                id,
+                name,
+                parameters: graph
+                    .get_node(id)
+                    .map(|node| node.parameters())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|info| {
+                        (
+                            *info,
+                            graph.get_parameter(id, info.id).unwrap_or(info.default),
+                        )
+                    })
+                    .collect(),
+                column: 0,
+                row: 0,
+            })
+            .collect();
+        let index_of = |id: NodeId| nodes.iter().position(|node| node.id == id);
+        let edges: Vec<(usize, usize)> = graph
+            .edges()
+            .into_iter()
+            .filter_map(|(from_id, to_id)| Some((index_of(from_id)?, index_of(to_id)?)))
+            .collect();
+
+        // 接続元より 1 つ右の列に並べる（グラフに循環はないため、ノード数の回数で収束する）
+        for _ in 0..nodes.len() {
+            for &(from, to) in edges.iter() {
+                nodes[to].column = nodes[to].column.max(nodes[from].column + 1);
+            }
+        }
+        let mut rows = vec![0; nodes.len()];
+        for node in nodes.iter_mut() {
+            node.row = rows[node.column];
+            rows[node.column] += 1;
+        }
+
+        let meters = nodes.iter().map(|_| AtomicU32::new(0)).collect();
+        Self {
+            nodes,
+            edges,
+            meters,
+        }
+    }
+
+    /// 表示するノードのID（meters と同じ順番）
+    pub fn node_ids(&self) -> Vec<NodeId> {
+        self.nodes.iter().map(|node| node.id).collect()
+    }
+
+    /// ノードごとのレベルメーター（record_level で書き込む）
+    pub fn meters(&self) -> Arc<[AtomicU32]> {
+        self.meters.clone()
+    }
+}
+
+/// レベルメーターに出力のピークを書き込む
+///
+/// エディターが読み出すまでの最大値を保持します。
+///
+/// # 実装時の注意
+/// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+/// 0 以上の f32 はビット列の大小と値の大小が一致するため、整数の fetch_max で最大値を取れます。
+pub fn record_level(meter: &AtomicU32, peak: f32) {
+    meter.fetch_max(peak.max(0.0).to_bits(), Ordering::Relaxed);
+}
+
+/// エディターを作成
+///
+/// # 引数
+/// * `params` - プラグインのパラメーター
+/// * `graph_view` - 表示するグラフ
+/// * `messages` - オーディオスレッドへの操作の送り先
+pub fn create(
+    params: Arc<RustAudioEngineParams>,
+    graph_view: Arc<Mutex<GraphView>>,
+    messages: SyncSender<EditorMessage>,
+) -> Option<Box<dyn Editor>> {
+    create_egui_editor(
+        params.editor_state.clone(),
+        (),
+        |_, _| {},
+        move |egui_ctx, setter, _state| {
+            egui::CentralPanel::default().show(egui_ctx, |ui| {
+                ui.heading("Host parameters");
+                ui.label("Gain");
+                ui.add(widgets::ParamSlider::for_param(&params.gain, setter));
+                ui.label("Frequency");
+                ui.add(widgets::ParamSlider::for_param(&params.frequency, setter));
+                for (index, param) in params.macros().into_iter().enumerate() {
+                    ui.label(format!("Macro {}", index + 1));
+                    ui.add(widgets::ParamSlider::for_param(param, setter));
+                }
+
+                // オーディオスレッドはこのロックを取らないため、ここで待っても処理は止まらない
+                let Ok(mut view) = graph_view.lock() else {
+                    return;
+                };
+                ui.separator();
+                ui.heading("Graph");
+                draw_graph(ui, &view);
+
+                ui.separator();
+                ui.heading("Node parameters");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (index, node) in view.nodes.iter_mut().enumerate() {
+                        if node.parameters.is_empty() {
+                            continue;
+                        }
+                        ui.push_id(index, |ui| {
+                            ui.collapsing(node.name, |ui| {
+                                for (info, value) in node.parameters.iter_mut() {
+                                    ui.horizontal(|ui| {
+                                        let slider = egui::Slider::new(value, info.min..=info.max)
+                                            .text(info.name)
+                                            .suffix(format!(" {}", info.unit));
+                                        // キューがいっぱいの場合は、次に動かしたときの値で反映される
+                                        if ui.add(slider).changed() {
+                                            let _ =
+                                                messages.try_send(EditorMessage::SetParameter {
+                                                    node_id: node.id,
+                                                    parameter_id: info.id,
+                                                    value: *value,
+                                                });
+                                        }
+                                        let target = AutomationTarget {
+                                            node_id: node.id,
+                                            node_name: node.name,
+                                            parameter: *info,
+                                        };
+                                        automation_selector(ui, &params, &messages, target);
+                                    });
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+
+            // レベルメーターを動かし続けるため、毎フレーム描き直す
+            egui_ctx.request_repaint();
+        },
+    )
+}
+
+/// パラメーターを割り当てるオートメーションスロットを選ぶ
+fn automation_selector(
+    ui: &mut egui::Ui,
+    params: &RustAudioEngineParams,
+    messages: &SyncSender<EditorMessage>,
+    target: AutomationTarget,
+) {
+    let Ok(mut bindings) = params.automation_bindings.lock() else {
+        return;
+    };
+    let is_target = |(_, node_name, parameter_id): &AutomationBinding| {
+        node_name == target.node_name && parameter_id == target.parameter.id
+    };
+    let current = bindings
+        .iter()
+        .find(|binding| is_target(binding))
+        .map(|&(slot, _, _)| slot);
+
+    let slot_name = |slot: Option<usize>| match slot {
+        Some(slot) => format!("Auto {}", slot + 1),
+        None => "No automation".to_string(),
+    };
+    let mut selected = current;
+    egui::ComboBox::from_id_salt((target.node_name, target.parameter.id))
+        .selected_text(slot_name(selected))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut selected, None, slot_name(None));
+            for slot in 0..NUM_AUTOMATION_SLOTS {
+                ui.selectable_value(&mut selected, Some(slot), slot_name(Some(slot)));
+            }
+        });
+    if selected == current {
+        return;
+    }
+
+    // このパラメーターの以前の割り当てと、選んだスロットの以前の割り当てを解除してから割り当てる
+    bindings.retain(|binding| !is_target(binding) && Some(binding.0) != selected);
+    if let Some(slot) = current {
+        let _ = messages.try_send(EditorMessage::BindAutomation { slot, target: None });
+    }
+    if let Some(slot) = selected {
+        bindings.push((
+            slot,
+            target.node_name.to_string(),
+            target.parameter.id.to_string(),
+        ));
+        let _ = messages.try_send(EditorMessage::BindAutomation {
+            slot,
+            target: Some(target),
+        });
+    }
+}
+
+/// ノードグラフとレベルメーターを描く
+fn draw_graph(ui: &mut egui::Ui, view: &GraphView) {
+    let num_columns = view
+        .nodes
+        .iter()
+        .map(|node| node.column + 1)
+        .max()
+        .unwrap_or(0);
+    let num_rows = view
+        .nodes
+        .iter()
+        .map(|node| node.row + 1)
+        .max()
+        .unwrap_or(0);
+    let size = Vec2::new(
+        num_columns as f32 * NODE_SPACING.x,
+        num_rows as f32 * NODE_SPACING.y,
+    );
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let origin = response.rect.min;
+    let node_rect = |node: &NodeView| {
+        let offset = Vec2::new(
+            node.column as f32 * NODE_SPACING.x,
+            node.row as f32 * NODE_SPACING.y,
+        );
+        Rect::from_min_size(origin + offset, NODE_SIZE)
+    };
+
+    for &(from, to) in view.edges.iter() {
+        let from_rect = node_rect(&view.nodes[from]);
+        let to_rect = node_rect(&view.nodes[to]);
+        painter.line_segment(
+            [from_rect.right_center(), to_rect.left_center()],
+            Stroke::new(1.5, Color32::GRAY),
+        );
+    }
+
+    for (node, meter) in view.nodes.iter().zip(view.meters.iter()) {
+        let rect = node_rect(node);
+        painter.rect_filled(rect, 4.0, Color32::from_gray(48));
+        painter.text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            node.name,
+            FontId::proportional(14.0),
+            Color32::WHITE,
+        );
+
+        // ノードの下端に出力のレベルを描き、表示した値を少しずつ下げる
+        let level = f32::from_bits(meter.load(Ordering::Relaxed));
+        meter.store((level * METER_DECAY).to_bits(), Ordering::Relaxed);
+        let color = if level > 1.0 {
+            Color32::RED
+        } else {
+            Color32::GREEN
+        };
+        let bar = Rect::from_min_size(
+            rect.left_bottom() - Vec2::new(0.0, 4.0),
+            Vec2::new(rect.width() * level.min(1.0), 4.0),
+        );
+        painter.rect_filled(bar, 0.0, color);
+    }
+}
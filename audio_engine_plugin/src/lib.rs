@@ -1,5 +1,10 @@
+mod automation;
+mod editor;
 mod rust_audio_engine;
+mod sysex;
 
 use nih_plug::prelude::*;
 
+pub use rust_audio_engine::RustAudioEngine;
+
 nih_export_clap!(rust_audio_engine::RustAudioEngine);
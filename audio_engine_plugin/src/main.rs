@@ -0,0 +1,11 @@
+//! プラグインを、DAW の外で動かすスタンドアローンのアプリとして起動します。
+//!
+//! オーディオデバイスには JACK または CPAL で接続し、エディターをウィンドウとして表示します。
+//! `--backend` や `--sample-rate` などのオプションは `--help` で確認できます。
+
+use audio_engine_plugin::RustAudioEngine;
+use nih_plug::prelude::*;
+
+fn main() {
+    nih_export_standalone::<RustAudioEngine>();
+}
@@ -1,22 +1,73 @@
 use nih_plug::prelude::*;
-use std::sync::Arc;
+use nih_plug_egui::EguiState;
+use std::sync::atomic::AtomicU32;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+
+use audio_engine_core::audio_buffer::{OwnedAudioBuffer, PlanarAudioBuffer};
+use audio_engine_core::audio_buffer_utils::{deinterleave, interleave};
+use audio_engine_core::audio_graph::{AudioGraph, NodeId};
+use audio_engine_core::midi::{
+    self, pitch_bend_to_semitones, NoteExpression, MPE_PITCH_BEND_RANGE, MPE_TIMBRE_CC,
+};
+use audio_engine_core::nodes::{
+    EnvelopeFollower, FmSynth, GainProcessor, InputNode, OutputNode, SawGenerator, SineGenerator,
+};
+use audio_engine_core::parameter::MacroCurve;
+
+use crate::automation::{
+    Automation, AutomationBinding, AutomationSlotParams, NUM_AUTOMATION_SLOTS,
+};
+use crate::editor::{self, EditorMessage, GraphView};
+use crate::sysex::PatchSysEx;
+
+/// エディターから届く操作を、処理するまでためておける数
+const EDITOR_MESSAGE_CAPACITY: usize = 256;
+
+/// サイドチェーン入力と追加の出力バスのポート名（追加の出力バスはグラフの出力バスの番号順）
+const PORT_NAMES: PortNames = PortNames {
+    layout: None,
+    main_input: None,
+    main_output: None,
+    aux_inputs: &["Sidechain"],
+    aux_outputs: &["Sine", "Saw"],
+};
 
-use audio_engine_core::audio_buffer::AudioBuffer;
-use audio_engine_core::audio_graph::AudioGraph;
-use audio_engine_core::nodes::{GainProcessor, InputNode, OutputNode, SawGenerator, SineGenerator};
 // メインのプラグイン実装
 pub struct RustAudioEngine {
     params: Arc<RustAudioEngineParams>,
     audio_graph: AudioGraph,
-    tmp_buffer: Vec<f32>,
+    tmp_buffer: OwnedAudioBuffer,
+    /// サイドチェーン入力をインターリーブに変換するための一時バッファ
+    sidechain_buffer: OwnedAudioBuffer,
     num_channels: usize,
     num_samples: usize,
-    input_node_id: usize,
-    output_node_id: usize,
+    input_node_id: NodeId,
+    output_node_id: NodeId,
+    /// ホストから受け取ったノートを送るシンセサイザーのノード
+    synth_node_id: NodeId,
+    /// 入力が止まった後も処理を続けるサンプル数（グラフのテール）
+    tail_samples: u32,
+    /// エディターに表示するグラフ
+    graph_view: Arc<Mutex<GraphView>>,
+    /// レベルメーターを表示するノード（meters と同じ順番）
+    meter_node_ids: Vec<NodeId>,
+    /// ノードごとのレベルメーター（エディターと共有する）
+    meters: Arc<[AtomicU32]>,
+    /// エディターからの操作の送り先（エディターに渡す）
+    message_sender: SyncSender<EditorMessage>,
+    /// エディターからの操作
+    messages: Receiver<EditorMessage>,
+    /// オートメーションスロットの割り当て
+    automation: Automation,
 }
 
 #[derive(Params)]
 pub struct RustAudioEngineParams {
+    /// エディターのウィンドウの状態
+    #[persist = "editor-state"]
+    pub editor_state: Arc<EguiState>,
+
     /// ゲインパラメーター
     #[id = "gain"]
     pub gain: FloatParam,
@@ -24,18 +75,61 @@ pub struct RustAudioEngineParams {
     /// 周波数パラメーター
     #[id = "frequency"]
     pub frequency: FloatParam,
+
+    /// マクロ 1〜4（グラフ内の複数のパラメーターを 1 つのつまみで操作する）
+    #[id = "macro1"]
+    pub macro1: FloatParam,
+    #[id = "macro2"]
+    pub macro2: FloatParam,
+    #[id = "macro3"]
+    pub macro3: FloatParam,
+    #[id = "macro4"]
+    pub macro4: FloatParam,
+
+    /// グラフ内の任意のパラメーターに割り当てられるオートメーションスロット
+    #[nested(array, group = "オートメーション")]
+    pub automation: [AutomationSlotParams; NUM_AUTOMATION_SLOTS],
+
+    /// オートメーションスロットの割り当て（エディターで変更し、プロジェクトに保存する）
+    #[persist = "automation-bindings"]
+    pub automation_bindings: Mutex<Vec<AutomationBinding>>,
+}
+
+impl RustAudioEngineParams {
+    /// マクロのパラメーター（番号順）
+    pub(crate) fn macros(&self) -> [&FloatParam; 4] {
+        [&self.macro1, &self.macro2, &self.macro3, &self.macro4]
+    }
+}
+
+/// マクロのパラメーターを作成
+fn macro_param(name: &str) -> FloatParam {
+    FloatParam::new(name, 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+        .with_value_to_string(formatters::v2s_f32_percentage(0))
+        .with_string_to_value(formatters::s2v_f32_percentage())
+        .with_unit("%")
 }
 
 impl Default for RustAudioEngine {
     fn default() -> Self {
+        let (message_sender, messages) = mpsc::sync_channel(EDITOR_MESSAGE_CAPACITY);
         Self {
             params: Arc::new(RustAudioEngineParams::default()),
             audio_graph: AudioGraph::new(),
-            tmp_buffer: Vec::new(),
+            tmp_buffer: OwnedAudioBuffer::new(0, 0),
+            sidechain_buffer: OwnedAudioBuffer::new(0, 0),
             num_channels: 0,
             num_samples: 0,
-            input_node_id: 0,
-            output_node_id: 0,
+            input_node_id: NodeId::from_raw(0),
+            output_node_id: NodeId::from_raw(0),
+            synth_node_id: NodeId::from_raw(0),
+            tail_samples: 0,
+            graph_view: Arc::new(Mutex::new(GraphView::new())),
+            meter_node_ids: Vec::new(),
+            meters: Arc::new([]),
+            message_sender,
+            messages,
+            automation: Automation::new(),
         }
     }
 }
@@ -43,6 +137,8 @@ impl Default for RustAudioEngine {
 impl Default for RustAudioEngineParams {
     fn default() -> Self {
         Self {
+            editor_state: editor::default_state(),
+
             // ゲインパラメーター
             gain: FloatParam::new(
                 "ゲイン",
@@ -69,6 +165,16 @@ impl Default for RustAudioEngineParams {
             )
             .with_value_to_string(formatters::v2s_f32_hz_then_khz(2))
             .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+
+            // マクロ
+            macro1: macro_param("マクロ 1"),
+            macro2: macro_param("マクロ 2"),
+            macro3: macro_param("マクロ 3"),
+            macro4: macro_param("マクロ 4"),
+
+            // オートメーションスロット
+            automation: std::array::from_fn(AutomationSlotParams::new),
+            automation_bindings: Mutex::new(Vec::new()),
         }
     }
 }
@@ -81,56 +187,93 @@ impl Plugin for RustAudioEngine {
 
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
-        main_input_channels: None, // 入力チャンネルなし（ジェネレーターベースのプラグイン）
-        main_output_channels: NonZeroU32::new(2), // ステレオ出力
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        AudioIOLayout {
+            main_input_channels: None, // 入力チャンネルなし（ジェネレーターベースのプラグイン）
+            main_output_channels: NonZeroU32::new(2), // ステレオ出力
 
-        aux_input_ports: &[],
-        aux_output_ports: &[],
+            aux_input_ports: &[new_nonzero_u32(2)], // ステレオのサイドチェーン入力
+            aux_output_ports: &[new_nonzero_u32(2), new_nonzero_u32(2)], // オシレーターごとの出力
 
-        names: PortNames::const_default(),
-    }];
+            names: PORT_NAMES,
+        },
+        AudioIOLayout {
+            main_input_channels: None,
+            main_output_channels: NonZeroU32::new(1), // モノラル出力
 
-    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+            aux_input_ports: &[new_nonzero_u32(1)], // モノラルのサイドチェーン入力
+            aux_output_ports: &[new_nonzero_u32(1), new_nonzero_u32(1)],
+
+            names: PORT_NAMES,
+        },
+    ];
+
+    // ノートと MPE の表現（ピッチベンド、プレッシャー、CC）、パッチを変更する SysEx メッセージを受け取る
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
 
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
-    type SysExMessage = ();
+    type SysExMessage = PatchSysEx;
     type BackgroundTask = ();
 
     fn params(&self) -> Arc<dyn Params> {
         self.params.clone()
     }
 
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        editor::create(
+            self.params.clone(),
+            self.graph_view.clone(),
+            self.message_sender.clone(),
+        )
+    }
+
     fn initialize(
         &mut self,
         audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
         _context: &mut impl InitContext<Self>,
     ) -> bool {
-        // ノードをリセット
-        self.reset();
-
         let sample_rate = buffer_config.sample_rate;
         self.num_samples = buffer_config.max_buffer_size as usize;
         // AudioIOLayout から出力チャンネル数を取得します。
-        // プラグインはステレオ出力に設定しているため、必ず Some であることが期待されます。
+        // プラグインのレイアウトはすべて出力を持つため、必ず Some であることが期待されます。
         self.num_channels = audio_io_layout
             .main_output_channels
             .expect("出力チャンネルが設定されていません")
             .get() as usize;
 
+        // ホストがレイアウトやサンプリングレートを切り替えると initialize が呼び直されるため、
+        // 前のノードや接続、割り当てを残さないよう、新しいグラフを組み立ててチャンネル数を合わせます。
+        self.audio_graph = AudioGraph::new();
+        if let Err(e) = self.audio_graph.set_num_channels(self.num_channels) {
+            nih_error!("{}", e);
+            return false;
+        }
+
         // 一時バッファのサイズを更新します。
-        self.tmp_buffer
-            .resize(self.num_channels * self.num_samples, 0.0);
+        self.tmp_buffer = OwnedAudioBuffer::new(self.num_channels, self.num_samples);
+        let sidechain_channels = audio_io_layout
+            .aux_input_ports
+            .first()
+            .map_or(0, |channels| channels.get() as usize);
+        self.sidechain_buffer = OwnedAudioBuffer::new(sidechain_channels, self.num_samples);
 
         // ノードを作成
         let mut sine_generator = SineGenerator::new();
         let mut gain_processor = GainProcessor::new();
         let mut saw_generator = SawGenerator::new();
+        let synth = FmSynth::new();
         let input_node = InputNode::new();
         let output_node = OutputNode::new();
+        // サイドチェーン入力のレベルに合わせて出力を下げる（サイドチェーンコンプレッション）
+        let sidechain_node = InputNode::new();
+        let sidechain_follower = EnvelopeFollower::new();
+        let ducker = GainProcessor::new();
+        // オシレーターごとの出力を、追加の出力バスに送る
+        let sine_output_node = OutputNode::new();
+        let saw_output_node = OutputNode::new();
 
         // パラメーターの設定
         {
@@ -152,6 +295,12 @@ impl Plugin for RustAudioEngine {
         let sine_generator_id = self.audio_graph.add_node(Box::new(sine_generator));
         let gain_processor_id = self.audio_graph.add_node(Box::new(gain_processor));
         let saw_generator_id = self.audio_graph.add_node(Box::new(saw_generator));
+        self.synth_node_id = self.audio_graph.add_node(Box::new(synth));
+        let sidechain_node_id = self.audio_graph.add_node(Box::new(sidechain_node));
+        let sidechain_follower_id = self.audio_graph.add_node(Box::new(sidechain_follower));
+        let ducker_id = self.audio_graph.add_node(Box::new(ducker));
+        let sine_output_id = self.audio_graph.add_node(Box::new(sine_output_node));
+        let saw_output_id = self.audio_graph.add_node(Box::new(saw_output_node));
 
         // グラフにエッジを追加
         let _ = self
@@ -162,10 +311,90 @@ impl Plugin for RustAudioEngine {
             .add_edge(saw_generator_id, gain_processor_id);
         let _ = self
             .audio_graph
-            .add_edge(gain_processor_id, self.output_node_id);
+            .add_edge(self.synth_node_id, gain_processor_id);
+        let _ = self.audio_graph.add_edge(gain_processor_id, ducker_id);
+        let _ = self.audio_graph.add_edge(ducker_id, self.output_node_id);
+        let _ = self
+            .audio_graph
+            .add_edge(sidechain_node_id, sidechain_follower_id);
+        let _ = self.audio_graph.add_edge(sine_generator_id, sine_output_id);
+        let _ = self.audio_graph.add_edge(saw_generator_id, saw_output_id);
+
+        // 追加の出力ポートの順に、出力バスに出力ノードを割り当てる
+        for (bus, node_id) in [sine_output_id, saw_output_id].into_iter().enumerate() {
+            if let Err(e) = self.audio_graph.set_output_bus(bus, Some(node_id)) {
+                nih_error!("{}", e);
+            }
+        }
+
+        // ホストのサイドチェーン入力を専用の入力ノードで受け取り、そのエンベロープで出力のゲインを下げる
+        if let Err(e) = self.audio_graph.set_sidechain_node(Some(sidechain_node_id)) {
+            nih_error!("{}", e);
+        }
+        if let Err(e) =
+            self.audio_graph
+                .add_modulation(sidechain_follower_id, ducker_id, "gain", -1.0)
+        {
+            nih_error!("{}", e);
+        }
+
+        // マクロ 1 で音色全体を操作する（サイン波を明るく、ノコギリ波を高く、全体を小さく）
+        let macro_mappings = [
+            (
+                sine_generator_id,
+                "frequency",
+                523.25,
+                1046.5,
+                MacroCurve::Exponential,
+            ),
+            (
+                saw_generator_id,
+                "frequency",
+                220.0,
+                440.0,
+                MacroCurve::Exponential,
+            ),
+            (gain_processor_id, "gain", 0.5, 0.25, MacroCurve::Linear),
+        ];
+        for (node_id, parameter_id, min, max, curve) in macro_mappings {
+            if let Err(e) =
+                self.audio_graph
+                    .add_macro_mapping(0, node_id, parameter_id, min, max, curve)
+            {
+                nih_error!("{}", e);
+            }
+        }
 
         self.audio_graph
             .prepare(sample_rate, buffer_config.max_buffer_size as usize);
+        self.tail_samples = self
+            .audio_graph
+            .tail_samples(self.output_node_id)
+            .try_into()
+            .unwrap_or(u32::MAX);
+
+        // エディターに表示するグラフを作り直す（名前はオートメーションの割り当ての保存にも使う）
+        let node_names = [
+            (sine_generator_id, "Sine"),
+            (saw_generator_id, "Saw"),
+            (self.synth_node_id, "FM Synth"),
+            (gain_processor_id, "Gain"),
+            (sidechain_node_id, "Sidechain"),
+            (ducker_id, "Ducker"),
+            (self.output_node_id, "Output"),
+        ];
+        let graph_view = GraphView::from_graph(&self.audio_graph, &node_names);
+        self.meter_node_ids = graph_view.node_ids();
+        self.meters = graph_view.meters();
+        if let Ok(mut view) = self.graph_view.lock() {
+            *view = graph_view;
+        }
+
+        // 保存したオートメーションスロットの割り当てを、ノードの名前から新しいノード ID に解決して読み込む
+        if let Ok(bindings) = self.params.automation_bindings.lock() {
+            self.automation
+                .restore(&self.audio_graph, &node_names, &bindings);
+        }
 
         true
     }
@@ -175,34 +404,261 @@ impl Plugin for RustAudioEngine {
         self.audio_graph.reset();
     }
 
+    // `alloc_guard` フィーチャーが有効なデバッグビルドでは、nih_plug がこの関数内のメモリアロケーションを検出して停止させます。
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        let mut audio_buffer =
-            AudioBuffer::new(self.num_channels, self.num_samples, &mut self.tmp_buffer);
+        self.tmp_buffer
+            .set_num_frames(buffer.samples().min(self.num_samples));
+        let mut audio_buffer = self.tmp_buffer.as_audio_buffer();
+        let mut planar_buffer = PlanarAudioBuffer::new(buffer.as_slice());
+
+        // ホストのテンポや拍子、再生位置をグラフのトランスポートに反映する
+        // （ホストが提供しない値は、前のブロックからグラフが進めた値を引き継ぐ）
+        let host_transport = context.transport();
+        let mut transport = self.audio_graph.transport();
+        transport.playing = host_transport.playing;
+        if let Some(tempo) = host_transport.tempo {
+            transport.tempo = tempo;
+        }
+        if let (Some(numerator), Some(denominator)) = (
+            host_transport.time_sig_numerator,
+            host_transport.time_sig_denominator,
+        ) {
+            transport.time_sig_numerator = numerator.max(1) as u32;
+            transport.time_sig_denominator = denominator.max(1) as u32;
+        }
+        if let Some(position_beats) = host_transport.pos_beats() {
+            transport.position_beats = position_beats;
+        }
+        self.audio_graph.set_transport(transport);
+
+        // マクロの値をブロックごとに反映
+        for (index, param) in self.params.macros().into_iter().enumerate() {
+            self.audio_graph.set_macro(index, param.value());
+        }
 
-        // 引数のバッファをオーディオバッファへコピー
-        for (frame_idx, frame) in buffer.iter_samples().enumerate() {
-            for (ch, sample) in frame.into_iter().enumerate() {
-                audio_buffer.get_mut_frame(frame_idx)[ch] = *sample;
+        // エディターからの操作を反映
+        while let Ok(message) = self.messages.try_recv() {
+            match message {
+                EditorMessage::SetParameter {
+                    node_id,
+                    parameter_id,
+                    value,
+                } => {
+                    // 作り直す前のグラフのノードへの変更は、エラーメッセージを作らないようにここで読み飛ばす
+                    if self.audio_graph.get_node(node_id).is_some() {
+                        let _ = self.audio_graph.set_parameter(node_id, parameter_id, value);
+                    }
+                }
+                EditorMessage::BindAutomation { slot, target } => {
+                    self.automation.bind(slot, target);
+                }
             }
         }
 
+        // 値が変わったオートメーションスロットを、割り当てたパラメーターに反映
+        self.automation
+            .apply(&self.params.automation, &mut self.audio_graph);
+
+        // ノートと表現をシンセサイザーに送り、外部のツールから届いた SysEx メッセージでパッチを変更する
+        // （SysEx はブロックの先頭でまとめて反映）
+        while let Some(event) = context.next_event() {
+            if let NoteEvent::MidiSysEx { message, .. } = event {
+                self.apply_sysex(message);
+            } else if let Some(event) = to_core_note_event(&event) {
+                self.audio_graph.send_note_event(self.synth_node_id, event);
+            }
+        }
+
+        // 引数のバッファ（プレーナー）をオーディオバッファ（インターリーブ）へ変換
+        interleave(&planar_buffer, &mut audio_buffer);
+
+        // ホストのサイドチェーン入力をグラフに渡す
+        if let Some(sidechain) = aux.inputs.first_mut() {
+            self.sidechain_buffer
+                .set_num_frames(sidechain.samples().min(self.num_samples));
+            let mut sidechain_buffer = self.sidechain_buffer.as_audio_buffer();
+            interleave(
+                &PlanarAudioBuffer::new(sidechain.as_slice()),
+                &mut sidechain_buffer,
+            );
+            self.audio_graph.set_sidechain_input(&sidechain_buffer);
+        }
+
         // プロセッサーチェーンを処理（サイン波生成 → ゲイン処理）
         self.audio_graph
             .process(&mut audio_buffer, self.input_node_id, self.output_node_id);
 
         // 引数のバッファへ書き戻し
-        for (frame_idx, frame) in buffer.iter_samples().enumerate() {
-            for (ch, sample) in frame.into_iter().enumerate() {
-                *sample = audio_buffer.get_frame(frame_idx)[ch];
+        deinterleave(&audio_buffer, &mut planar_buffer);
+
+        // 出力バスの出力を、追加の出力ポートへ書き込む（インターリーブ → プレーナー）
+        let graph_channels = self.audio_graph.num_channels();
+        for (bus, output) in aux.outputs.iter_mut().enumerate() {
+            let samples = self.audio_graph.output_bus(bus).unwrap_or_default();
+            for (ch, channel) in output.as_slice().iter_mut().enumerate() {
+                channel.fill(0.0);
+                for (sample, frame) in channel.iter_mut().zip(samples.chunks_exact(graph_channels))
+                {
+                    *sample = frame.get(ch).copied().unwrap_or(0.0);
+                }
+            }
+        }
+
+        // ノードの出力のピークをレベルメーターに書き込む
+        for (meter, &node_id) in self.meters.iter().zip(self.meter_node_ids.iter()) {
+            if let Some(peak) = self.audio_graph.output_peak(node_id) {
+                editor::record_level(meter, peak);
             }
         }
 
-        ProcessStatus::Normal
+        // エフェクトの残響がある場合は、入力が止まった後もその長さだけ処理を続けてもらう
+        if self.tail_samples > 0 {
+            ProcessStatus::Tail(self.tail_samples)
+        } else {
+            ProcessStatus::Normal
+        }
+    }
+}
+
+impl RustAudioEngine {
+    /// SysEx メッセージでパッチを変更する
+    ///
+    /// # 実装時の注意
+    /// オーディオスレッドから呼び出すため、メモリアロケーションを伴う操作は行いません。
+    /// 存在しないノードやパラメーターへのメッセージは、エラーメッセージを作らないように読み飛ばします。
+    fn apply_sysex(&mut self, message: PatchSysEx) {
+        match message {
+            PatchSysEx::SetParameter {
+                node,
+                parameter,
+                value,
+            } => {
+                let Some(&node_id) = self.meter_node_ids.get(node as usize) else {
+                    return;
+                };
+                let Some(info) = self
+                    .audio_graph
+                    .get_node(node_id)
+                    .and_then(|node| node.parameters().get(parameter as usize).copied())
+                else {
+                    return;
+                };
+                let _ = self.audio_graph.set_parameter(
+                    node_id,
+                    info.id,
+                    info.min + (info.max - info.min) * value,
+                );
+            }
+            PatchSysEx::Reset => self.audio_graph.reset(),
+        }
+    }
+}
+
+/// ホストのノートイベントを、グラフのノートイベントに変換する
+///
+/// CLAP のノートエクスプレッションはノートごと、MIDI のピッチベンド（範囲は MPE_PITCH_BEND_RANGE）と
+/// チャンネルプレッシャー、MPE_TIMBRE_CC のコントロールチェンジはチャンネル全体の表現になります。
+///
+/// # 戻り値
+/// * ノートと表現以外のイベントの場合は `None`
+fn to_core_note_event(event: &NoteEvent<PatchSysEx>) -> Option<midi::NoteEvent> {
+    let expression = |timing, channel, note, expression| {
+        Some(midi::NoteEvent::Expression {
+            timing,
+            channel,
+            note,
+            expression,
+        })
+    };
+    match *event {
+        NoteEvent::NoteOn {
+            timing,
+            channel,
+            note,
+            velocity,
+            ..
+        } => Some(midi::NoteEvent::NoteOn {
+            timing,
+            channel,
+            note,
+            velocity,
+        }),
+        NoteEvent::NoteOff {
+            timing,
+            channel,
+            note,
+            velocity,
+            ..
+        } => Some(midi::NoteEvent::NoteOff {
+            timing,
+            channel,
+            note,
+            velocity,
+        }),
+        NoteEvent::PolyTuning {
+            timing,
+            channel,
+            note,
+            tuning,
+            ..
+        } => expression(
+            timing,
+            channel,
+            Some(note),
+            NoteExpression::PitchBend(tuning),
+        ),
+        NoteEvent::PolyPressure {
+            timing,
+            channel,
+            note,
+            pressure,
+            ..
+        } => expression(
+            timing,
+            channel,
+            Some(note),
+            NoteExpression::Pressure(pressure),
+        ),
+        NoteEvent::PolyBrightness {
+            timing,
+            channel,
+            note,
+            brightness,
+            ..
+        } => expression(
+            timing,
+            channel,
+            Some(note),
+            NoteExpression::Timbre(brightness),
+        ),
+        NoteEvent::MidiPitchBend {
+            timing,
+            channel,
+            value,
+        } => {
+            let value = (value * 16384.0).clamp(0.0, 16383.0) as u16;
+            let semitones = pitch_bend_to_semitones(value, MPE_PITCH_BEND_RANGE);
+            expression(timing, channel, None, NoteExpression::PitchBend(semitones))
+        }
+        NoteEvent::MidiChannelPressure {
+            timing,
+            channel,
+            pressure,
+        } => expression(timing, channel, None, NoteExpression::Pressure(pressure)),
+        NoteEvent::MidiCC {
+            timing,
+            channel,
+            cc,
+            value,
+        } if cc == MPE_TIMBRE_CC => {
+            expression(timing, channel, None, NoteExpression::Timbre(value))
+        }
+        _ => None,
     }
 }
 
@@ -0,0 +1,106 @@
+//! 外部のツールから、ホストの MIDI の経路を通して動作中のプラグインのパッチを変更する SysEx メッセージです。
+//!
+//! オーディオスレッドで受け取ってそのまま反映するため、メモリアロケーションを伴わない操作だけを扱い、
+//! 固定長のバイナリ形式でやり取りします。値は 14 ビット（0〜16383）で 0〜1 を表します。
+//!
+//! | コマンド | 形式 |
+//! |----------|------|
+//! | ノードのパラメーターを設定 | `F0 7D 01 <ノード番号> <パラメーター番号> <値の上位 7 ビット> <値の下位 7 ビット> F7` |
+//! | グラフをリセット | `F0 7D 02 F7` |
+//!
+//! ノード番号はエディターに表示するノードの順番、パラメーター番号はノードが公開するパラメーターの順番です。
+//! パラメーターの値は、最小値から最大値までの範囲に変換して設定します。
+
+use nih_plug::prelude::*;
+
+/// SysEx の開始と終了のバイト
+const SYSEX_START: u8 = 0xF0;
+const SYSEX_END: u8 = 0xF7;
+
+/// 製造者 ID（非営利・教育用）
+const MANUFACTURER_ID: u8 = 0x7D;
+
+/// コマンドの番号
+const COMMAND_SET_PARAMETER: u8 = 0x01;
+const COMMAND_RESET: u8 = 0x02;
+
+/// 14 ビットの値の最大値
+const MAX_VALUE: u16 = 0x3FFF;
+
+/// メッセージの最大の長さ（バイト）
+const MAX_MESSAGE_LEN: usize = 8;
+
+/// パッチを変更する SysEx メッセージ
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatchSysEx {
+    /// ノードのパラメーターを設定する
+    SetParameter {
+        /// エディターに表示するノードの順番
+        node: u8,
+        /// ノードが公開するパラメーターの順番
+        parameter: u8,
+        /// 値（0〜1。最小値から最大値までの範囲に変換する）
+        value: f32,
+    },
+    /// グラフのすべてのノードをリセットする
+    Reset,
+}
+
+impl SysExMessage for PatchSysEx {
+    type Buffer = [u8; MAX_MESSAGE_LEN];
+
+    fn from_buffer(buffer: &[u8]) -> Option<Self> {
+        // 開始と終了のバイトの間は、7 ビットのデータバイトだけが並ぶ
+        let [SYSEX_START, MANUFACTURER_ID, data @ .., SYSEX_END] = buffer else {
+            return None;
+        };
+        if data.iter().any(|byte| *byte > 0x7F) {
+            return None;
+        }
+        match *data {
+            [COMMAND_SET_PARAMETER, node, parameter, msb, lsb] => {
+                let value = ((msb as u16) << 7) | lsb as u16;
+                Some(Self::SetParameter {
+                    node,
+                    parameter,
+                    value: value as f32 / MAX_VALUE as f32,
+                })
+            }
+            [COMMAND_RESET] => Some(Self::Reset),
+            _ => None,
+        }
+    }
+
+    fn to_buffer(self) -> (Self::Buffer, usize) {
+        match self {
+            Self::SetParameter {
+                node,
+                parameter,
+                value,
+            } => {
+                let value = (value.clamp(0.0, 1.0) * MAX_VALUE as f32).round() as u16;
+                let buffer = [
+                    SYSEX_START,
+                    MANUFACTURER_ID,
+                    COMMAND_SET_PARAMETER,
+                    node & 0x7F,
+                    parameter & 0x7F,
+                    (value >> 7) as u8,
+                    (value & 0x7F) as u8,
+                    SYSEX_END,
+                ];
+                (buffer, 8)
+            }
+            Self::Reset => {
+                let mut buffer = [0; MAX_MESSAGE_LEN];
+                buffer[..4].copy_from_slice(&[
+                    SYSEX_START,
+                    MANUFACTURER_ID,
+                    COMMAND_RESET,
+                    SYSEX_END,
+                ]);
+                (buffer, 4)
+            }
+        }
+    }
+}
@@ -0,0 +1,456 @@
+//! audio_engine_core と audio_engine_service を Python から利用するためのバインディングです。
+//!
+//! パッチの試作や DSP の実験のために、ノートブックから音声グラフを組み立て、オフラインでレンダリングした結果を
+//! numpy の配列として受け取れます。maturin でビルドし、`import audio_engine` で読み込みます。
+//!
+//! ```python
+//! import audio_engine
+//!
+//! graph = audio_engine.Graph()
+//! input_id = graph.add_node("input")
+//! sine = graph.add_node("sine")
+//! output_id = graph.add_node("output")
+//! graph.connect(sine, output_id)
+//!
+//! # 周波数を変えながらレンダリングする（形状は (値の数, フレーム数, チャンネル数)）
+//! sweep = graph.sweep(input_id, output_id, sine, "frequency", [220.0, 440.0, 880.0], 48000)
+//!
+//! # 同じグラフをオーディオデバイスで再生する
+//! engine = audio_engine.Engine()
+//! engine.load(graph)
+//! engine.start()
+//! ```
+
+use std::collections::HashMap;
+
+use audio_engine_core::audio_graph::{AudioGraph, NodeId};
+use audio_engine_core::offline::OfflineRenderer;
+use audio_engine_core::wav::WavData;
+use audio_engine_service::command::{CommandResponse, GraphCommand};
+use audio_engine_service::graph_file::{GraphDescription, LoadedGraph};
+use audio_engine_service::service::AudioEngineService;
+use numpy::{
+    PyArray1, PyArray2, PyArray3, PyArrayMethods, PyReadonlyArray2, PyUntypedArrayMethods,
+};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// レンダリングするチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+const NUM_CHANNELS: usize = 2;
+
+/// エラーメッセージを Python の例外に変換する
+fn to_py_err(message: String) -> PyErr {
+    PyRuntimeError::new_err(message)
+}
+
+/// Python に返すノード ID の整数の表現
+fn raw_id(node_id: NodeId) -> u64 {
+    node_id.as_raw() as u64
+}
+
+/// Python から渡されたノード ID の整数の表現を NodeId に変換する
+fn node_id(raw: u64) -> NodeId {
+    NodeId::from_raw(raw as usize)
+}
+
+/// ノードの名前と ID の対応を、Python に返す辞書の内容に変換する
+fn node_ids(loaded: &LoadedGraph) -> HashMap<String, u64> {
+    loaded
+        .node_ids
+        .iter()
+        .map(|(name, &node_id)| (name.clone(), raw_id(node_id)))
+        .collect()
+}
+
+/// インターリーブのレンダリング結果を、(フレーム数, チャンネル数) の配列に変換する
+fn to_array<'py>(py: Python<'py>, wav: WavData) -> PyResult<Bound<'py, PyArray2<f32>>> {
+    let num_frames = wav.samples.len() / wav.channels;
+    PyArray1::from_vec(py, wav.samples).reshape([num_frames, wav.channels])
+}
+
+/// コマンドの結果から値を取り出す
+fn expect_value(response: CommandResponse) -> PyResult<f32> {
+    match response {
+        CommandResponse::Value(value) => Ok(value),
+        _ => Err(to_py_err("予期しない応答です".to_string())),
+    }
+}
+
+/// オフラインで処理する音声グラフ
+///
+/// ノードは node_factory の種類の名前で追加し、ノード ID の整数で指定します。
+/// 音声グラフはオーディオデバイスに接続されていないため、レンダリングは常に同じ結果になります。
+#[pyclass(unsendable, module = "audio_engine")]
+pub struct Graph {
+    audio_graph: AudioGraph,
+}
+
+impl Graph {
+    /// コマンドを音声グラフに適用する
+    fn apply(&mut self, command: GraphCommand) -> PyResult<CommandResponse> {
+        command.apply(&mut self.audio_graph).map_err(to_py_err)
+    }
+
+    /// 必要ならノードをリセットしてから、レンダラーを作成する
+    fn renderer(&mut self, sample_rate: f32, block_size: usize, reset: bool) -> OfflineRenderer {
+        if reset {
+            self.audio_graph.reset();
+        }
+        OfflineRenderer::new(sample_rate, block_size.max(1))
+    }
+}
+
+#[pymethods]
+impl Graph {
+    /// 空の音声グラフを作成
+    #[new]
+    fn new() -> Self {
+        Self {
+            audio_graph: AudioGraph::new(),
+        }
+    }
+
+    /// JSON の記述からノードとエッジを追加する
+    ///
+    /// # 戻り値
+    /// * 記述のノードの名前と ID の対応
+    fn load_json(&mut self, text: &str) -> PyResult<HashMap<String, u64>> {
+        let description = GraphDescription::from_json(text).map_err(to_py_err)?;
+        let loaded = description
+            .build(&mut self.audio_graph)
+            .map_err(to_py_err)?;
+        Ok(node_ids(&loaded))
+    }
+
+    /// 音声グラフを JSON の記述に変換する
+    ///
+    /// ノードの名前は `種類_ID` になります。変換できる範囲は GraphDescription::capture を参照してください。
+    fn to_json(&self) -> PyResult<String> {
+        GraphDescription::capture(&self.audio_graph)
+            .map(|description| description.to_json())
+            .map_err(to_py_err)
+    }
+
+    /// ノードを追加する
+    ///
+    /// # 引数
+    /// * `kind` - ノードの種類（"sine"、"gain" など node_factory::NODE_KINDS のいずれか）
+    ///
+    /// # 戻り値
+    /// * 追加したノードの ID
+    fn add_node(&mut self, kind: &str) -> PyResult<u64> {
+        match self.apply(GraphCommand::AddNode {
+            kind: kind.to_string(),
+        })? {
+            CommandResponse::NodeAdded(node_id) => Ok(raw_id(node_id)),
+            _ => Err(to_py_err("予期しない応答です".to_string())),
+        }
+    }
+
+    /// ノードを削除する
+    fn remove_node(&mut self, node_id: u64) -> PyResult<()> {
+        if self
+            .audio_graph
+            .remove_node(self::node_id(node_id))
+            .is_some()
+        {
+            Ok(())
+        } else {
+            Err(to_py_err(format!("ノードID {}が存在しません", node_id)))
+        }
+    }
+
+    /// エッジを追加する
+    fn connect(&mut self, from: u64, to: u64) -> PyResult<()> {
+        self.apply(GraphCommand::Connect {
+            from: node_id(from),
+            to: node_id(to),
+        })
+        .map(|_| ())
+    }
+
+    /// エッジを削除する
+    fn disconnect(&mut self, from: u64, to: u64) -> PyResult<()> {
+        self.apply(GraphCommand::Disconnect {
+            from: node_id(from),
+            to: node_id(to),
+        })
+        .map(|_| ())
+    }
+
+    /// ノードのパラメーターを設定する（値はパラメーターの範囲に制限される）
+    fn set_parameter(&mut self, node_id: u64, parameter_id: &str, value: f32) -> PyResult<()> {
+        self.apply(GraphCommand::SetParameter {
+            node_id: self::node_id(node_id),
+            parameter_id: parameter_id.to_string(),
+            value,
+        })
+        .map(|_| ())
+    }
+
+    /// ノードのパラメーターの現在の値を取得する
+    fn get_parameter(&mut self, node_id: u64, parameter_id: &str) -> PyResult<f32> {
+        let response = self.apply(GraphCommand::GetParameter {
+            node_id: self::node_id(node_id),
+            parameter_id: parameter_id.to_string(),
+        })?;
+        expect_value(response)
+    }
+
+    /// ノードが公開するパラメーターの情報の一覧を取得する
+    ///
+    /// # 戻り値
+    /// * `id`、`name`、`unit`、`min`、`max`、`default` をキーとする辞書のリスト
+    fn parameters<'py>(
+        &mut self,
+        py: Python<'py>,
+        node_id: u64,
+    ) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let response = self.apply(GraphCommand::GetParameterInfos {
+            node_id: self::node_id(node_id),
+        })?;
+        let CommandResponse::ParameterInfos(infos) = response else {
+            return Err(to_py_err("予期しない応答です".to_string()));
+        };
+        infos
+            .iter()
+            .map(|info| {
+                let dict = PyDict::new(py);
+                dict.set_item("id", info.id)?;
+                dict.set_item("name", info.name)?;
+                dict.set_item("unit", info.unit)?;
+                dict.set_item("min", info.min)?;
+                dict.set_item("max", info.max)?;
+                dict.set_item("default", info.default)?;
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// すべてのノードをリセットする
+    fn reset(&mut self) {
+        self.audio_graph.reset();
+    }
+
+    /// 無音を入力して音声グラフをレンダリングする
+    ///
+    /// # 引数
+    /// * `reset` - レンダリングの前にノードをリセットするかどうか。false の場合は前回の続きから処理する
+    ///
+    /// # 戻り値
+    /// * 形状が (フレーム数, チャンネル数) の float32 の配列
+    #[pyo3(signature = (input_id, output_id, num_frames, sample_rate = 48000.0, block_size = 256, reset = true))]
+    #[allow(clippy::too_many_arguments)]
+    fn render<'py>(
+        &mut self,
+        py: Python<'py>,
+        input_id: u64,
+        output_id: u64,
+        num_frames: usize,
+        sample_rate: f32,
+        block_size: usize,
+        reset: bool,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let renderer = self.renderer(sample_rate, block_size, reset);
+        let wav = renderer.render(
+            &mut self.audio_graph,
+            node_id(input_id),
+            node_id(output_id),
+            num_frames,
+        );
+        to_array(py, wav)
+    }
+
+    /// 配列を入力して音声グラフをレンダリングする
+    ///
+    /// # 引数
+    /// * `input` - 形状が (フレーム数, チャンネル数) の float32 の配列
+    /// * `tail` - 入力の後に、音声グラフのテールの分だけ無音を与えて続けるかどうか
+    ///
+    /// # 戻り値
+    /// * 形状が (フレーム数, チャンネル数) の float32 の配列
+    #[pyo3(signature = (input_id, output_id, input, sample_rate = 48000.0, block_size = 256, reset = true, tail = false))]
+    #[allow(clippy::too_many_arguments)]
+    fn render_input<'py>(
+        &mut self,
+        py: Python<'py>,
+        input_id: u64,
+        output_id: u64,
+        input: PyReadonlyArray2<'py, f32>,
+        sample_rate: f32,
+        block_size: usize,
+        reset: bool,
+        tail: bool,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        if input.shape()[1] != NUM_CHANNELS {
+            return Err(PyValueError::new_err(format!(
+                "入力のチャンネル数は {} である必要があります",
+                NUM_CHANNELS
+            )));
+        }
+        // 行優先で並べると、インターリーブの順序になる
+        let samples: Vec<f32> = input.as_array().iter().copied().collect();
+        let renderer = self.renderer(sample_rate, block_size, reset);
+        let (input_id, output_id) = (node_id(input_id), node_id(output_id));
+        let wav = if tail {
+            renderer.render_with_tail(&mut self.audio_graph, input_id, output_id, &samples)
+        } else {
+            renderer.render_with_input(&mut self.audio_graph, input_id, output_id, &samples)
+        };
+        to_array(py, wav)
+    }
+
+    /// パラメーターの値を変えながら、それぞれの値で音声グラフをレンダリングする
+    ///
+    /// 値ごとにノードをリセットしてからレンダリングするため、結果は値の順序に依存しません。
+    /// 終了後、パラメーターは最後の値のままになります。
+    ///
+    /// # 戻り値
+    /// * 形状が (値の数, フレーム数, チャンネル数) の float32 の配列
+    #[pyo3(signature = (input_id, output_id, node_id, parameter_id, values, num_frames, sample_rate = 48000.0, block_size = 256))]
+    #[allow(clippy::too_many_arguments)]
+    fn sweep<'py>(
+        &mut self,
+        py: Python<'py>,
+        input_id: u64,
+        output_id: u64,
+        node_id: u64,
+        parameter_id: &str,
+        values: Vec<f32>,
+        num_frames: usize,
+        sample_rate: f32,
+        block_size: usize,
+    ) -> PyResult<Bound<'py, PyArray3<f32>>> {
+        let mut samples = Vec::with_capacity(values.len() * num_frames * NUM_CHANNELS);
+        for &value in &values {
+            self.set_parameter(node_id, parameter_id, value)?;
+            let renderer = self.renderer(sample_rate, block_size, true);
+            let wav = renderer.render(
+                &mut self.audio_graph,
+                self::node_id(input_id),
+                self::node_id(output_id),
+                num_frames,
+            );
+            samples.extend_from_slice(&wav.samples);
+        }
+        PyArray1::from_vec(py, samples).reshape([values.len(), num_frames, NUM_CHANNELS])
+    }
+}
+
+/// オーディオデバイスで音声グラフを再生するエンジン
+///
+/// AudioEngineService を包み、Graph で組み立てた音声グラフを読み込んで再生します。
+/// 再生中のパラメーターの変更は、コマンドとして音声グラフに適用します。
+#[pyclass(unsendable, module = "audio_engine")]
+pub struct Engine {
+    service: AudioEngineService,
+    /// 読み込んだ音声グラフのノードの対応
+    loaded: Option<LoadedGraph>,
+}
+
+impl Engine {
+    /// 記述の音声グラフに置き換える
+    fn load_description(
+        &mut self,
+        description: &GraphDescription,
+    ) -> PyResult<HashMap<String, u64>> {
+        let loaded = self.service.load_graph(description).map_err(to_py_err)?;
+        let node_ids = node_ids(&loaded);
+        self.loaded = Some(loaded);
+        Ok(node_ids)
+    }
+
+    /// コマンドを音声グラフに適用し、結果を返す
+    fn request(&mut self, command: GraphCommand) -> PyResult<CommandResponse> {
+        let reply = self
+            .service
+            .command_sender()
+            .request(command)
+            .map_err(to_py_err)?;
+        self.service.process_commands();
+        reply
+            .try_recv()
+            .map_err(|_| to_py_err("コマンドが処理されませんでした".to_string()))?
+            .map_err(to_py_err)
+    }
+}
+
+#[pymethods]
+impl Engine {
+    /// 既定のオーディオデバイスを使うエンジンを作成
+    #[new]
+    fn new() -> Self {
+        Self {
+            service: AudioEngineService::new(),
+            loaded: None,
+        }
+    }
+
+    /// Graph の音声グラフを読み込む
+    ///
+    /// Graph の内容を記述に変換して読み込むため、ノード ID は変わります。再生中の場合は新しい音声グラフで再開します。
+    ///
+    /// # 戻り値
+    /// * ノードの名前（`種類_元のID`）と、エンジン内での ID の対応
+    fn load(&mut self, graph: &Graph) -> PyResult<HashMap<String, u64>> {
+        let description = GraphDescription::capture(&graph.audio_graph).map_err(to_py_err)?;
+        self.load_description(&description)
+    }
+
+    /// JSON の記述の音声グラフを読み込む
+    ///
+    /// # 戻り値
+    /// * 記述のノードの名前と ID の対応
+    fn load_json(&mut self, text: &str) -> PyResult<HashMap<String, u64>> {
+        let description = GraphDescription::from_json(text).map_err(to_py_err)?;
+        self.load_description(&description)
+    }
+
+    /// 読み込んだ音声グラフの再生を開始する
+    fn start(&mut self) -> PyResult<()> {
+        let Some(loaded) = self.loaded.as_ref() else {
+            return Err(to_py_err("音声グラフが読み込まれていません".to_string()));
+        };
+        self.service
+            .start_playback(loaded.input_id, loaded.output_id)
+            .map_err(to_py_err)
+    }
+
+    /// 再生を停止する
+    fn stop(&mut self) -> PyResult<()> {
+        self.service.stop_playback().map_err(to_py_err)
+    }
+
+    /// 再生中かどうか
+    fn is_playing(&self) -> bool {
+        self.service.is_playing()
+    }
+
+    /// ノードのパラメーターを設定する（再生中でも設定できる）
+    fn set_parameter(&mut self, node_id: u64, parameter_id: &str, value: f32) -> PyResult<()> {
+        self.request(GraphCommand::SetParameter {
+            node_id: self::node_id(node_id),
+            parameter_id: parameter_id.to_string(),
+            value,
+        })
+        .map(|_| ())
+    }
+
+    /// ノードのパラメーターの現在の値を取得する
+    fn get_parameter(&mut self, node_id: u64, parameter_id: &str) -> PyResult<f32> {
+        let response = self.request(GraphCommand::GetParameter {
+            node_id: self::node_id(node_id),
+            parameter_id: parameter_id.to_string(),
+        })?;
+        expect_value(response)
+    }
+}
+
+/// Python のモジュール `audio_engine`
+#[pymodule]
+fn audio_engine(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Graph>()?;
+    module.add_class::<Engine>()?;
+    Ok(())
+}
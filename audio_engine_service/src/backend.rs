@@ -0,0 +1,210 @@
+//! オーディオデバイスとのやり取りを抽象化するバックエンドです。
+//!
+//! AudioEngineService はバックエンドからストリームの設定を受け取り、オーディオコールバックを渡します。
+//! 実機では PortAudioBackend を、オーディオデバイスのない CI などでは NullBackend を使います。
+
+mod channel_selection;
+mod null;
+mod port_audio;
+
+pub use null::{NullBackend, NullBackendProbe};
+pub use port_audio::{DeviceDescription, HostApiDescription, PortAudioBackend};
+
+/// ストリームの設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamConfig {
+    /// サンプルレート（Hz）
+    pub sample_rate: f64,
+    /// 1 回のコールバックで処理するフレーム数
+    pub frames_per_buffer: usize,
+    /// 入力チャンネル数
+    pub num_input_channels: usize,
+    /// 出力チャンネル数
+    pub num_output_channels: usize,
+    /// デバイスと音声グラフの間のチャンネルの対応。None の場合は ChannelMap::default_for で決めます。
+    pub channel_map: Option<ChannelMap>,
+}
+
+/// デバイスのチャンネルと音声グラフのチャンネルの対応（チャンネルマップ）
+///
+/// インデックスが音声グラフのチャンネル、値がデバイスのチャンネルです。
+/// 例えばデバイスの入力 3/4 をグラフの入力 0/1 に、グラフの出力 0/1 をデバイスの出力 7/8 に割り当てる場合は
+/// `inputs: vec![Some(2), Some(3)], outputs: vec![Some(6), Some(7)]` とします（チャンネルは 0 始まり）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelMap {
+    /// グラフの入力チャンネルごとの、デバイスの入力チャンネル。None の場合は無音
+    pub inputs: Vec<Option<usize>>,
+    /// グラフの出力チャンネルごとの、デバイスの出力チャンネル。None の場合はどこにも出力しない
+    pub outputs: Vec<Option<usize>>,
+}
+
+impl ChannelMap {
+    /// デバイスのチャンネル数から、既定のチャンネルマップを作成
+    ///
+    /// デバイスのチャンネル 0, 1, ... をグラフのチャンネル 0, 1, ... にそのまま割り当てます。
+    /// 入力がモノラルの場合は、グラフの全ての入力チャンネルに同じ信号を割り当てます。
+    ///
+    /// # 引数
+    /// * `num_graph_channels` - 音声グラフのチャンネル数
+    /// * `num_input_channels` - デバイスの入力チャンネル数
+    /// * `num_output_channels` - デバイスの出力チャンネル数
+    pub fn default_for(
+        num_graph_channels: usize,
+        num_input_channels: usize,
+        num_output_channels: usize,
+    ) -> Self {
+        let inputs = (0..num_graph_channels)
+            .map(|ch| match num_input_channels {
+                0 => None,
+                1 => Some(0),
+                _ => (ch < num_input_channels).then_some(ch),
+            })
+            .collect();
+        let outputs = (0..num_graph_channels)
+            .map(|ch| (ch < num_output_channels).then_some(ch))
+            .collect();
+        Self { inputs, outputs }
+    }
+
+    /// チャンネルマップがストリームの設定と矛盾しないか確認する
+    ///
+    /// # 引数
+    /// * `num_graph_channels` - 音声グラフのチャンネル数
+    /// * `config` - デバイスのストリームの設定
+    pub fn validate(&self, num_graph_channels: usize, config: &StreamConfig) -> Result<(), String> {
+        for (name, map, num_channels) in [
+            ("入力", &self.inputs, config.num_input_channels),
+            ("出力", &self.outputs, config.num_output_channels),
+        ] {
+            if map.len() > num_graph_channels {
+                return Err(format!(
+                    "{}のチャンネルマップが音声グラフのチャンネル数 {} を超えています",
+                    name, num_graph_channels
+                ));
+            }
+            if let Some(channel) = map.iter().flatten().find(|&&ch| ch >= num_channels) {
+                return Err(format!(
+                    "{}チャンネル {} はデバイスのチャンネル数 {} を超えています",
+                    name, channel, num_channels
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ストリームのイベント
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// ストリームが終了した
+    Finished,
+    /// デバイスの切断などのエラーでストリームが止まった
+    Error(String),
+    /// 止まったストリームを開き直して再開した
+    Restarted,
+    /// ストリームを開き直せなかった
+    RestartFailed(String),
+    /// ストリームは動作中のまま、オーディオコールバックが呼び出されなくなった（ウォッチドッグが検出）
+    Stalled,
+}
+
+/// コールバック時点のストリームの状態
+///
+/// 前回のコールバックから今回までに、オーバーフロー・アンダーフローが起きたかどうかを表します。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StreamStatus {
+    /// 入力データが足りず、無音で埋められた
+    pub input_underflow: bool,
+    /// 入力データを処理しきれず、破棄された
+    pub input_overflow: bool,
+    /// 出力が間に合わず、デバイスに無音が挿入された
+    pub output_underflow: bool,
+    /// 出力データが破棄された
+    pub output_overflow: bool,
+}
+
+impl StreamStatus {
+    /// いずれかのオーバーフロー・アンダーフロー（xrun）が起きたかどうか
+    pub fn has_xrun(&self) -> bool {
+        self.input_underflow || self.input_overflow || self.output_underflow || self.output_overflow
+    }
+}
+
+/// オーディオコールバック
+///
+/// 引数は (入力バッファ, 出力バッファ, フレーム数, ストリームの状態) で、バッファはいずれもインターリーブです。
+/// リアルタイムスレッドから呼び出されるため、メモリアロケーションなどの遅延を生む処理を行ってはいけません。
+pub type AudioCallback = Box<dyn FnMut(&[f32], &mut [f32], usize, StreamStatus) + Send>;
+
+/// オーディオデバイスのバックエンド
+///
+/// open でデバイスを開いてストリームの設定を決め、start で渡されたコールバックを定期的に呼び出します。
+pub trait AudioBackend {
+    /// デバイスを開き、ストリームの設定を返す
+    ///
+    /// # 実装時の注意
+    /// start より前に呼び出されます。ストリームはまだ開始しないでください。
+    fn open(&mut self) -> Result<StreamConfig, String>;
+
+    /// ストリームを開始する
+    ///
+    /// # 引数
+    /// * `callback` - ストリームから定期的に呼び出すオーディオコールバック
+    fn start(&mut self, callback: AudioCallback) -> Result<(), String>;
+
+    /// ストリームを停止する
+    ///
+    /// 停止後、start で渡されたコールバックは破棄されます。
+    fn stop(&mut self) -> Result<(), String>;
+
+    /// ストリームが動作中かどうか
+    fn is_active(&self) -> bool;
+
+    /// 出力デバイスを指定する
+    ///
+    /// 次に open したときから指定したデバイスを使います。
+    ///
+    /// # 引数
+    /// * `device_id` - バックエンドごとのデバイスの ID
+    fn set_output_device(&mut self, device_id: usize) -> Result<(), String>;
+
+    /// start 後にストリームが止まっていれば、その理由を返す
+    ///
+    /// # 実装時の注意
+    /// 所有者のスレッドから定期的に呼び出されます。同じ停止を二度通知しないよう、
+    /// 止まったストリームは閉じてコールバックを破棄してください。
+    fn poll_event(&mut self) -> Option<StreamEvent>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_map() {
+        let config = StreamConfig {
+            sample_rate: 48000.0,
+            frames_per_buffer: 256,
+            num_input_channels: 1,
+            num_output_channels: 8,
+            channel_map: None,
+        };
+        // モノラルの入力はグラフの全ての入力チャンネルに割り当てる
+        let map = ChannelMap::default_for(2, 1, 8);
+        assert_eq!(map.inputs, vec![Some(0), Some(0)]);
+        assert_eq!(map.outputs, vec![Some(0), Some(1)]);
+        assert!(map.validate(2, &config).is_ok());
+
+        // デバイスにないチャンネルや、グラフのチャンネル数を超える割り当てはエラー
+        let map = ChannelMap {
+            inputs: vec![Some(1)],
+            outputs: vec![Some(6), Some(7)],
+        };
+        assert!(map.validate(2, &config).is_err());
+        let map = ChannelMap {
+            inputs: vec![],
+            outputs: vec![Some(0), Some(1), Some(2)],
+        };
+        assert!(map.validate(2, &config).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use super::{AudioCallback, StreamStatus};
+
+/// デバイスのチャンネルのうち、オーディオコールバックに渡すチャンネルの選択（チャンネルセレクター）
+///
+/// デバイスの全チャンネルでストリームを開き、選んだチャンネルだけを詰めて受け渡します。
+/// ASIO のチャンネルセレクターと同じ働きを、ホスト API によらずに実現します。
+pub(crate) struct ChannelSelection {
+    /// デバイスのチャンネル数
+    device_channels: usize,
+    /// 選んだデバイスのチャンネル（選んだ順にコールバックのチャンネル 0, 1, ... になる）
+    selected: Vec<usize>,
+    /// 選んだチャンネルだけを詰めたバッファ（インターリーブ）
+    buffer: Vec<f32>,
+}
+
+impl ChannelSelection {
+    /// 新しいChannelSelectionを作成
+    ///
+    /// # 引数
+    /// * `device_channels` - デバイスのチャンネル数
+    /// * `selected` - 選ぶデバイスのチャンネル
+    /// * `max_frames` - 1 回のコールバックの最大フレーム数
+    pub(crate) fn new(
+        device_channels: usize,
+        selected: Vec<usize>,
+        max_frames: usize,
+    ) -> Result<Self, String> {
+        if let Some(&channel) = selected.iter().find(|&&ch| ch >= device_channels) {
+            return Err(format!(
+                "チャンネル {} はデバイスのチャンネル数 {} を超えています",
+                channel, device_channels
+            ));
+        }
+        Ok(Self {
+            device_channels,
+            buffer: vec![0.0; max_frames * selected.len()],
+            selected,
+        })
+    }
+
+    /// 選んだチャンネル数
+    pub(crate) fn num_channels(&self) -> usize {
+        self.selected.len()
+    }
+
+    /// デバイスの入力から、選んだチャンネルを集める
+    pub(crate) fn gather(&mut self, device: &[f32], frames: usize) -> &[f32] {
+        let num_channels = self.selected.len();
+        let frames = frames.min(self.buffer.len() / num_channels.max(1));
+        let buffer = &mut self.buffer[..frames * num_channels];
+        for (dst, src) in buffer
+            .chunks_exact_mut(num_channels)
+            .zip(device.chunks_exact(self.device_channels))
+        {
+            for (sample, &ch) in dst.iter_mut().zip(self.selected.iter()) {
+                *sample = src[ch];
+            }
+        }
+        buffer
+    }
+
+    /// 書き込み用のバッファを取得する（scatter でデバイスの出力に書き戻す）
+    pub(crate) fn buffer_mut(&mut self, frames: usize) -> &mut [f32] {
+        let num_channels = self.selected.len();
+        let frames = frames.min(self.buffer.len() / num_channels.max(1));
+        &mut self.buffer[..frames * num_channels]
+    }
+
+    /// buffer_mut に書き込んだ内容を、デバイスの出力の選んだチャンネルに書き戻す
+    ///
+    /// 選んでいないチャンネルは無音にします。
+    pub(crate) fn scatter(&self, device: &mut [f32], frames: usize) {
+        let num_channels = self.selected.len();
+        let frames = frames.min(self.buffer.len() / num_channels.max(1));
+        device.fill(0.0);
+        for (src, dst) in self.buffer[..frames * num_channels]
+            .chunks_exact(num_channels)
+            .zip(device.chunks_exact_mut(self.device_channels))
+        {
+            for (&sample, &ch) in src.iter().zip(self.selected.iter()) {
+                dst[ch] = sample;
+            }
+        }
+    }
+}
+
+/// コールバックの前後で、選んだチャンネルだけを受け渡すようにラップする
+///
+/// # 引数
+/// * `callback` - 選んだチャンネルだけを受け取るコールバック
+/// * `input` - 入力のチャンネルの選択。None の場合はデバイスの全チャンネル
+/// * `output` - 出力のチャンネルの選択。None の場合はデバイスの全チャンネル
+pub(crate) fn select_channels(
+    mut callback: AudioCallback,
+    mut input: Option<ChannelSelection>,
+    mut output: Option<ChannelSelection>,
+) -> AudioCallback {
+    if input.is_none() && output.is_none() {
+        return callback;
+    }
+    Box::new(
+        move |in_buffer: &[f32], out_buffer: &mut [f32], frames: usize, status: StreamStatus| {
+            let in_buffer = match input.as_mut() {
+                Some(selection) => selection.gather(in_buffer, frames),
+                None => in_buffer,
+            };
+            match output.as_mut() {
+                Some(selection) => {
+                    callback(in_buffer, selection.buffer_mut(frames), frames, status);
+                    selection.scatter(out_buffer, frames);
+                }
+                None => callback(in_buffer, out_buffer, frames, status),
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_channels() {
+        // 4ch のデバイスの入力 3, 1 を受け取り、2ch のデバイスの出力 1, 0 にそのまま書き出す
+        let input = ChannelSelection::new(4, vec![3, 1], 2).unwrap();
+        let output = ChannelSelection::new(2, vec![1, 0], 2).unwrap();
+        assert_eq!(input.num_channels(), 2);
+        let mut callback = select_channels(
+            Box::new(|in_buffer: &[f32], out_buffer: &mut [f32], _, _| {
+                out_buffer.copy_from_slice(in_buffer);
+            }),
+            Some(input),
+            Some(output),
+        );
+
+        let device_input = [0.0, 1.0, 2.0, 3.0, 10.0, 11.0, 12.0, 13.0];
+        let mut device_output = [9.0; 4];
+        callback(
+            &device_input,
+            &mut device_output,
+            2,
+            StreamStatus::default(),
+        );
+        assert_eq!(device_output, [1.0, 3.0, 11.0, 13.0]);
+
+        assert!(ChannelSelection::new(2, vec![2], 2).is_err());
+    }
+}
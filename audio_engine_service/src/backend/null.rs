@@ -0,0 +1,239 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::{AudioBackend, AudioCallback, StreamConfig, StreamEvent, StreamStatus};
+
+/// オーディオデバイスを使わないバックエンド
+///
+/// 専用のスレッドから、設定したサンプルレートに相当する間隔で無音の入力とともにコールバックを呼び出します。
+/// 出力は破棄されますが、NullBackendProbe でコールバックの回数や出力のピークを確認できます。
+/// NullBackendProbe からデバイスの切断やハングをシミュレートすることもできます。
+/// オーディオデバイスのない CI などで、サービスのライフサイクルやコールバックの配線をテストするのに使います。
+pub struct NullBackend {
+    /// シミュレートするデバイスごとのストリームの設定
+    devices: Vec<StreamConfig>,
+    /// 使用するデバイスのインデックス
+    output_device: usize,
+    /// ストリームが動作中かどうか（コールバックを呼び出すスレッドの停止に使う）
+    running: Arc<AtomicBool>,
+    /// コールバックを呼び出すスレッド
+    thread: Option<JoinHandle<()>>,
+    /// 動作状況の記録
+    probe: NullBackendProbe,
+}
+
+impl NullBackend {
+    /// 新しいNullBackendを作成
+    ///
+    /// # 引数
+    /// * `config` - シミュレートするストリームの設定。デバイス 0 として扱います。
+    pub fn new(config: StreamConfig) -> Self {
+        Self {
+            devices: vec![config],
+            output_device: 0,
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+            probe: NullBackendProbe::new(),
+        }
+    }
+
+    /// シミュレートするデバイスを追加する
+    ///
+    /// # 戻り値
+    /// * 追加したデバイスの ID（set_output_device に渡す）
+    pub fn add_device(&mut self, config: StreamConfig) -> usize {
+        self.devices.push(config);
+        self.devices.len() - 1
+    }
+
+    /// 動作状況を確認するためのプローブを取得する
+    ///
+    /// バックエンドを AudioEngineService に渡す前に取得しておきます。
+    pub fn probe(&self) -> NullBackendProbe {
+        self.probe.clone()
+    }
+}
+
+impl AudioBackend for NullBackend {
+    fn open(&mut self) -> Result<StreamConfig, String> {
+        // 開き直した場合は、新しいデバイスにつながったものとして扱う
+        self.probe.disconnected.store(false, Ordering::Release);
+        self.probe.hung.store(false, Ordering::Release);
+        Ok(self.devices[self.output_device].clone())
+    }
+
+    fn start(&mut self, mut callback: AudioCallback) -> Result<(), String> {
+        if self.thread.is_some() {
+            return Err("ストリームはすでに開始しています".to_string());
+        }
+
+        let config = self.devices[self.output_device].clone();
+        let running = self.running.clone();
+        let probe = self.probe.clone();
+        let disconnected = self.probe.disconnected.clone();
+        let hung = self.probe.hung.clone();
+        running.store(true, Ordering::Release);
+
+        let thread = thread::Builder::new()
+            .name("null-audio-backend".to_string())
+            .spawn(move || {
+                let frames = config.frames_per_buffer;
+                let input = vec![0.0; frames * config.num_input_channels];
+                let mut output = vec![0.0; frames * config.num_output_channels];
+                let period = Duration::from_secs_f64(frames as f64 / config.sample_rate);
+                let mut deadline = Instant::now();
+                let mut status = StreamStatus::default();
+
+                while running.load(Ordering::Acquire) && !disconnected.load(Ordering::Acquire) {
+                    // ハングしている間は、ストリームを動作中のままコールバックを呼び出さない
+                    if hung.load(Ordering::Acquire) {
+                        thread::sleep(period);
+                        deadline = Instant::now();
+                        continue;
+                    }
+                    callback(&input, &mut output, frames, status);
+                    probe.record(&output, config.num_output_channels);
+
+                    // 実機と同じ間隔になるよう待機する（遅れた場合は取り戻さずに基準を更新し、
+                    // 実機でデバイスに無音が挿入されるのと同様に次のコールバックでアンダーフローを通知する）
+                    deadline += period;
+                    let now = Instant::now();
+                    if deadline > now {
+                        thread::sleep(deadline - now);
+                        status.output_underflow = false;
+                    } else {
+                        deadline = now;
+                        status.output_underflow = true;
+                    }
+                }
+            })
+            .map_err(|e| format!("スレッドを開始できませんでした: {}", e))?;
+
+        self.thread = Some(thread);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread
+                .join()
+                .map_err(|_| "コールバックのスレッドがパニックしました".to_string())?;
+        }
+        Ok(())
+    }
+
+    fn is_active(&self) -> bool {
+        self.running.load(Ordering::Acquire) && !self.probe.disconnected.load(Ordering::Acquire)
+    }
+
+    fn set_output_device(&mut self, device_id: usize) -> Result<(), String> {
+        if device_id >= self.devices.len() {
+            return Err(format!("デバイスが見つかりません: {}", device_id));
+        }
+        self.output_device = device_id;
+        Ok(())
+    }
+
+    fn poll_event(&mut self) -> Option<StreamEvent> {
+        if self.thread.is_none() || !self.probe.disconnected.load(Ordering::Acquire) {
+            return None;
+        }
+        let _ = self.stop();
+        Some(StreamEvent::Error("デバイスが切断されました".to_string()))
+    }
+}
+
+impl Drop for NullBackend {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// NullBackend の動作状況の記録
+///
+/// クローンしたプローブは同じ記録を共有します。
+#[derive(Clone)]
+pub struct NullBackendProbe {
+    /// コールバックを呼び出した回数
+    callback_count: Arc<AtomicUsize>,
+    /// 出力の絶対値の最大値（f32 のビット表現）
+    peak: Arc<AtomicU32>,
+    /// 出力チャンネルごとの絶対値の最大値
+    channel_peaks: Arc<Mutex<Vec<f32>>>,
+    /// デバイスが切断されたかどうか
+    disconnected: Arc<AtomicBool>,
+    /// デバイスがハングしたかどうか
+    hung: Arc<AtomicBool>,
+}
+
+impl NullBackendProbe {
+    fn new() -> Self {
+        Self {
+            callback_count: Arc::new(AtomicUsize::new(0)),
+            peak: Arc::new(AtomicU32::new(0)),
+            channel_peaks: Arc::new(Mutex::new(Vec::new())),
+            disconnected: Arc::new(AtomicBool::new(false)),
+            hung: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// コールバック 1 回分の出力を記録する
+    fn record(&self, output: &[f32], num_channels: usize) {
+        let peak = output.iter().fold(0.0f32, |acc, s| acc.max(s.abs()));
+        // 0 以上の f32 はビット表現の大小と値の大小が一致するため、整数のまま最大値を取れる
+        self.peak.fetch_max(peak.to_bits(), Ordering::Relaxed);
+        // デバイスを模したスレッドからのみ呼び出されるため、ロックを使ってよい
+        let mut channel_peaks = self.channel_peaks.lock().unwrap();
+        if channel_peaks.len() < num_channels {
+            channel_peaks.resize(num_channels, 0.0);
+        }
+        for frame in output.chunks_exact(num_channels.max(1)) {
+            for (peak, sample) in channel_peaks.iter_mut().zip(frame) {
+                *peak = peak.max(sample.abs());
+            }
+        }
+        self.callback_count.fetch_add(1, Ordering::Release);
+    }
+
+    /// コールバックを呼び出した回数
+    pub fn callback_count(&self) -> usize {
+        self.callback_count.load(Ordering::Acquire)
+    }
+
+    /// これまでの出力の絶対値の最大値
+    pub fn peak(&self) -> f32 {
+        f32::from_bits(self.peak.load(Ordering::Relaxed))
+    }
+
+    /// これまでの、指定した出力チャンネルの絶対値の最大値
+    ///
+    /// # 引数
+    /// * `channel` - デバイスの出力チャンネル
+    pub fn channel_peak(&self, channel: usize) -> f32 {
+        self.channel_peaks
+            .lock()
+            .unwrap()
+            .get(channel)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// デバイスの切断をシミュレートする
+    ///
+    /// コールバックの呼び出しが止まり、poll_event がエラーを返すようになります。
+    /// バックエンドを開き直すと、新しいデバイスにつながったものとして再開できます。
+    pub fn disconnect(&self) {
+        self.disconnected.store(true, Ordering::Release);
+    }
+
+    /// デバイスのハングをシミュレートする
+    ///
+    /// ストリームは動作中のまま、コールバックの呼び出しだけが止まります。poll_event はエラーを返しません。
+    /// バックエンドを開き直すと、新しいデバイスにつながったものとして再開できます。
+    pub fn hang(&self) {
+        self.hung.store(true, Ordering::Release);
+    }
+}
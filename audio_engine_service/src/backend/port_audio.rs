@@ -0,0 +1,474 @@
+use portaudio as pa;
+
+use super::channel_selection::{select_channels, ChannelSelection};
+use super::{AudioBackend, AudioCallback, ChannelMap, StreamConfig, StreamEvent, StreamStatus};
+
+// 定数定義：サンプルレート、フレーム数、チャネル数の設定
+const SAMPLE_RATE: f64 = 44_100.0;
+const FRAMES: u32 = 256;
+const INTERLEAVED: bool = true;
+
+/// ホスト API の情報
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostApiDescription {
+    /// ホスト API のインデックス（PortAudioBackend::set_host_api に渡す）
+    pub index: usize,
+    /// 名前
+    pub name: String,
+    /// 種類（"ASIO", "WASAPI", "JACK", "CoreAudio" など）
+    pub kind: String,
+    /// デバイス数
+    pub device_count: usize,
+    /// デフォルトの入力デバイスの ID
+    pub default_input_device: Option<usize>,
+    /// デフォルトの出力デバイスの ID
+    pub default_output_device: Option<usize>,
+}
+
+/// デバイスの情報
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceDescription {
+    /// デバイスの ID（AudioEngineService::switch_output_device に渡す）
+    pub index: usize,
+    /// 名前
+    pub name: String,
+    /// 所属するホスト API のインデックス
+    pub host_api: usize,
+    /// 最大入力チャンネル数
+    pub max_input_channels: usize,
+    /// 最大出力チャンネル数
+    pub max_output_channels: usize,
+    /// デフォルトのサンプルレート（Hz）
+    pub default_sample_rate: f64,
+}
+
+/// open で決めたストリームの設定
+enum Settings {
+    /// 入出力（デュプレックス）
+    Duplex(pa::DuplexStreamSettings<f32, f32>),
+    /// 出力のみ
+    Output(pa::OutputStreamSettings<f32>),
+}
+
+/// 開始したストリーム
+enum Stream {
+    /// 入出力（デュプレックス）
+    Duplex(pa::Stream<pa::NonBlocking, pa::Duplex<f32, f32>>),
+    /// 出力のみ
+    Output(pa::Stream<pa::NonBlocking, pa::Output<f32>>),
+}
+
+impl Stream {
+    fn is_active(&self) -> Result<bool, pa::Error> {
+        match self {
+            Stream::Duplex(stream) => stream.is_active(),
+            Stream::Output(stream) => stream.is_active(),
+        }
+    }
+
+    fn stop(&mut self) -> Result<(), pa::Error> {
+        match self {
+            Stream::Duplex(stream) => stream.stop(),
+            Stream::Output(stream) => stream.stop(),
+        }
+    }
+
+    fn close(&mut self) -> Result<(), pa::Error> {
+        match self {
+            Stream::Duplex(stream) => stream.close(),
+            Stream::Output(stream) => stream.close(),
+        }
+    }
+}
+
+/// PortAudio のデバイスでストリームを動かすバックエンド
+///
+/// デフォルトではデフォルトのホスト API の、デフォルトの入力デバイスと出力デバイスで
+/// デュプレックスストリームを開きます。set_host_api で ASIO や WASAPI などのホスト API を選ぶと、
+/// そのホスト API のデフォルトデバイスを使います。
+/// 複数のバックエンド（AudioEngineService）を同時に動かすことができ、モニター用の出力などで
+/// 入力が不要な場合は output_only で作成すると、入力デバイスを取り合わずに済みます。
+pub struct PortAudioBackend {
+    /// PortAudio のインスタンス。open で初期化されます。
+    pa_instance: Option<pa::PortAudio>,
+    /// open で決めたストリームの設定
+    settings: Option<Settings>,
+    /// PortAudio ストリーム。音声入出力の処理を担当します。
+    stream: Option<Stream>,
+    /// 出力デバイス。None の場合はデフォルトの出力デバイスを使います。
+    output_device: Option<pa::DeviceIndex>,
+    /// 入力デバイスを開くかどうか
+    input_enabled: bool,
+    /// ホスト API。None の場合はデフォルトのホスト API を使います。
+    host_api: Option<pa::HostApiIndex>,
+    /// 入力のチャンネルセレクター。None の場合はデバイスの全チャンネルを使います。
+    input_channels: Option<Vec<usize>>,
+    /// 出力のチャンネルセレクター。None の場合はデバイスの全チャンネルを使います。
+    output_channels: Option<Vec<usize>>,
+    /// デバイスと音声グラフの間のチャンネルマップ。None の場合は既定の対応を使います。
+    channel_map: Option<ChannelMap>,
+    /// open で決めた入力のチャンネルの選択
+    input_selection: Option<ChannelSelection>,
+    /// open で決めた出力のチャンネルの選択
+    output_selection: Option<ChannelSelection>,
+}
+
+impl PortAudioBackend {
+    /// 入出力のストリームを開く、新しいPortAudioBackendを作成
+    pub fn new() -> Self {
+        Self {
+            pa_instance: None,
+            settings: None,
+            stream: None,
+            output_device: None,
+            input_enabled: true,
+            host_api: None,
+            input_channels: None,
+            output_channels: None,
+            channel_map: None,
+            input_selection: None,
+            output_selection: None,
+        }
+    }
+
+    /// 出力のみのストリームを開く、新しいPortAudioBackendを作成
+    ///
+    /// オーディオコールバックには空の入力バッファが渡されます。
+    pub fn output_only() -> Self {
+        Self {
+            input_enabled: false,
+            ..Self::new()
+        }
+    }
+
+    /// 利用できるホスト API の一覧を取得する
+    ///
+    /// # 実装時の注意
+    /// この関数は PortAudio の初期化を行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn host_apis() -> Result<Vec<HostApiDescription>, String> {
+        let pa_instance = pa::PortAudio::new().map_err(|e| e.to_string())?;
+        Ok(pa_instance
+            .host_apis()
+            .map(|(index, info)| HostApiDescription {
+                index: index as usize,
+                name: info.name.to_string(),
+                kind: format!("{:?}", info.host_type),
+                device_count: info.device_count as usize,
+                default_input_device: info.default_input_device.map(|d| d.0 as usize),
+                default_output_device: info.default_output_device.map(|d| d.0 as usize),
+            })
+            .collect())
+    }
+
+    /// デバイスの一覧を取得する
+    ///
+    /// # 引数
+    /// * `host_api` - ホスト API のインデックス。None の場合は全てのホスト API のデバイスを返します。
+    ///
+    /// # 実装時の注意
+    /// この関数は PortAudio の初期化を行うため、リアルタイムスレッドから呼び出すべきではありません。
+    pub fn devices(host_api: Option<usize>) -> Result<Vec<DeviceDescription>, String> {
+        let pa_instance = pa::PortAudio::new().map_err(|e| e.to_string())?;
+        let mut devices = Vec::new();
+        for device in pa_instance.devices().map_err(|e| e.to_string())? {
+            let (index, info) = device.map_err(|e| e.to_string())?;
+            if host_api.is_some_and(|host_api| info.host_api as usize != host_api) {
+                continue;
+            }
+            devices.push(DeviceDescription {
+                index: index.0 as usize,
+                name: info.name.to_string(),
+                host_api: info.host_api as usize,
+                max_input_channels: info.max_input_channels as usize,
+                max_output_channels: info.max_output_channels as usize,
+                default_sample_rate: info.default_sample_rate,
+            });
+        }
+        Ok(devices)
+    }
+
+    /// ホスト API を選ぶ
+    ///
+    /// 次に open したときから、指定したホスト API のデフォルトデバイスを使います。
+    /// 低レイテンシーが必要な Windows 環境では ASIO や WASAPI を選んでください。
+    ///
+    /// # 引数
+    /// * `host_api` - ホスト API のインデックス（host_apis で取得できます）
+    pub fn set_host_api(&mut self, host_api: usize) {
+        self.host_api = Some(host_api as pa::HostApiIndex);
+    }
+
+    /// デバイスのチャンネルのうち、使うチャンネルを選ぶ（チャンネルセレクター）
+    ///
+    /// 選んだチャンネルが、選んだ順にオーディオコールバックのチャンネル 0, 1, ... になります。
+    /// ASIO のチャンネルセレクターと同じ働きで、ホスト API によらず使えます。
+    /// 次に open したときから有効になります。
+    ///
+    /// # 引数
+    /// * `input` - 入力デバイスのチャンネル。None の場合は全チャンネル
+    /// * `output` - 出力デバイスのチャンネル。None の場合は全チャンネル
+    pub fn set_channel_selectors(&mut self, input: Option<Vec<usize>>, output: Option<Vec<usize>>) {
+        self.input_channels = input;
+        self.output_channels = output;
+    }
+
+    /// デバイスと音声グラフの間のチャンネルマップを設定する
+    ///
+    /// チャンネルセレクターを設定している場合、デバイスのチャンネルは選んだチャンネルの中での番号になります。
+    /// 次に open したときから有効になります。
+    ///
+    /// # 引数
+    /// * `channel_map` - チャンネルマップ。None の場合は ChannelMap::default_for で決めます。
+    pub fn set_channel_map(&mut self, channel_map: Option<ChannelMap>) {
+        self.channel_map = channel_map;
+    }
+}
+
+impl AudioBackend for PortAudioBackend {
+    fn open(&mut self) -> Result<StreamConfig, String> {
+        // 開き直す場合にデバイスの一覧が更新されるよう、古いインスタンスを破棄してから初期化し直す
+        self.stream = None;
+        self.pa_instance = None;
+
+        // PortAudio の初期化
+        let pa_instance = pa::PortAudio::new().map_err(|e| e.to_string())?;
+        println!("PortAudio:");
+        println!("バージョン: {}", pa_instance.version());
+        println!("バージョンテキスト: {:?}", pa_instance.version_text());
+        println!(
+            "ホスト数: {}",
+            pa_instance.host_api_count().map_err(|e| e.to_string())?
+        );
+        let default_host = pa_instance.default_host_api().map_err(|e| e.to_string())?;
+        println!(
+            "デフォルトホスト: {:#?}",
+            pa_instance.host_api_info(default_host)
+        );
+
+        // ホスト API の設定（指定がない場合はデフォルトのホスト API）
+        let host_api_info = match self.host_api {
+            Some(host_api) => {
+                let info = pa_instance
+                    .host_api_info(host_api)
+                    .ok_or_else(|| format!("ホスト API が見つかりません: {}", host_api))?;
+                println!("ホスト: {:#?}", &info);
+                Some(info)
+            }
+            None => None,
+        };
+
+        // 出力デバイスの設定（指定がない場合はホスト API のデフォルトの出力デバイス）
+        let def_output = match (self.output_device, &host_api_info) {
+            (Some(device), _) => device,
+            (None, Some(info)) => info
+                .default_output_device
+                .ok_or("ホスト API にデフォルトの出力デバイスがありません")?,
+            (None, None) => pa_instance
+                .default_output_device()
+                .map_err(|e| e.to_string())?,
+        };
+        let output_info = pa_instance
+            .device_info(def_output)
+            .map_err(|e| e.to_string())?;
+        println!("出力デバイス情報: {:#?}", &output_info);
+        let num_output_channels = output_info.max_output_channels;
+        let output_latency = output_info.default_low_output_latency;
+        let output_params =
+            pa::StreamParameters::new(def_output, num_output_channels, INTERLEAVED, output_latency);
+        self.output_selection = match self.output_channels.clone() {
+            Some(channels) => Some(ChannelSelection::new(
+                num_output_channels as usize,
+                channels,
+                FRAMES as usize,
+            )?),
+            None => None,
+        };
+        let num_output_channels = self
+            .output_selection
+            .as_ref()
+            .map_or(num_output_channels as usize, |s| s.num_channels());
+
+        if !self.input_enabled {
+            // 出力フォーマットがサポートされているか確認
+            pa_instance
+                .is_output_format_supported(output_params, SAMPLE_RATE)
+                .map_err(|e| e.to_string())?;
+            self.settings = Some(Settings::Output(pa::OutputStreamSettings::new(
+                output_params,
+                SAMPLE_RATE,
+                FRAMES,
+            )));
+            self.pa_instance = Some(pa_instance);
+            self.input_selection = None;
+            return Ok(StreamConfig {
+                sample_rate: SAMPLE_RATE,
+                frames_per_buffer: FRAMES as usize,
+                num_input_channels: 0,
+                num_output_channels,
+                channel_map: self.channel_map.clone(),
+            });
+        }
+
+        // 入力デバイスの設定（ホスト API が指定されている場合はそのデフォルトの入力デバイス）
+        let def_input = match &host_api_info {
+            Some(info) => info
+                .default_input_device
+                .ok_or("ホスト API にデフォルトの入力デバイスがありません")?,
+            None => pa_instance
+                .default_input_device()
+                .map_err(|e| e.to_string())?,
+        };
+        let input_info = pa_instance
+            .device_info(def_input)
+            .map_err(|e| e.to_string())?;
+        println!("デフォルト入力デバイス情報: {:#?}", &input_info);
+        let num_input_channels = input_info.max_input_channels;
+        let input_latency = input_info.default_low_input_latency;
+        let input_params = pa::StreamParameters::<f32>::new(
+            def_input,
+            num_input_channels,
+            INTERLEAVED,
+            input_latency,
+        );
+        self.input_selection = match self.input_channels.clone() {
+            Some(channels) => Some(ChannelSelection::new(
+                num_input_channels as usize,
+                channels,
+                FRAMES as usize,
+            )?),
+            None => None,
+        };
+        let num_input_channels = self
+            .input_selection
+            .as_ref()
+            .map_or(num_input_channels as usize, |s| s.num_channels());
+
+        // デュプレックスフォーマットがサポートされているか確認
+        let result =
+            pa_instance.is_duplex_format_supported(input_params, output_params, SAMPLE_RATE);
+        println!("デュプレックスフォーマットサポート確認: {:?}", result);
+        result.map_err(|e| e.to_string())?;
+
+        // ストリームの設定
+        self.settings = Some(Settings::Duplex(pa::DuplexStreamSettings::new(
+            input_params,
+            output_params,
+            SAMPLE_RATE,
+            FRAMES,
+        )));
+        self.pa_instance = Some(pa_instance);
+
+        Ok(StreamConfig {
+            sample_rate: SAMPLE_RATE,
+            frames_per_buffer: FRAMES as usize,
+            num_input_channels,
+            num_output_channels,
+            channel_map: self.channel_map.clone(),
+        })
+    }
+
+    fn start(&mut self, callback: AudioCallback) -> Result<(), String> {
+        let (Some(pa_instance), Some(settings)) = (self.pa_instance.as_mut(), self.settings.take())
+        else {
+            return Err("デバイスが開かれていません。先に open を呼び出してください。".to_string());
+        };
+
+        // チャンネルセレクターで選んだチャンネルだけをコールバックに受け渡す
+        let mut callback = select_channels(
+            callback,
+            self.input_selection.take(),
+            self.output_selection.take(),
+        );
+
+        // 非ブロッキングストリームの生成
+        let mut stream = match settings {
+            Settings::Duplex(settings) => {
+                let callback = move |pa::DuplexStreamCallbackArgs {
+                                         in_buffer,
+                                         out_buffer,
+                                         frames,
+                                         flags,
+                                         ..
+                                     }| {
+                    callback(in_buffer, out_buffer, frames, stream_status(flags));
+                    pa::Continue
+                };
+                Stream::Duplex(
+                    pa_instance
+                        .open_non_blocking_stream(settings, callback)
+                        .map_err(|e| e.to_string())?,
+                )
+            }
+            Settings::Output(settings) => {
+                let callback = move |pa::OutputStreamCallbackArgs {
+                                         buffer,
+                                         frames,
+                                         flags,
+                                         ..
+                                     }| {
+                    callback(&[], buffer, frames, stream_status(flags));
+                    pa::Continue
+                };
+                Stream::Output(
+                    pa_instance
+                        .open_non_blocking_stream(settings, callback)
+                        .map_err(|e| e.to_string())?,
+                )
+            }
+        };
+
+        // ストリームの開始
+        match &mut stream {
+            Stream::Duplex(stream) => stream.start(),
+            Stream::Output(stream) => stream.start(),
+        }
+        .map_err(|e| e.to_string())?;
+        println!("Stream started");
+
+        // ストリームをフィールドに保持
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        if let Some(mut stream) = self.stream.take() {
+            stream.stop().map_err(|e| e.to_string())?;
+            stream.close().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn is_active(&self) -> bool {
+        self.stream
+            .as_ref()
+            .is_some_and(|stream| stream.is_active().unwrap_or(false))
+    }
+
+    fn set_output_device(&mut self, device_id: usize) -> Result<(), String> {
+        self.output_device = Some(pa::DeviceIndex(device_id as u32));
+        Ok(())
+    }
+
+    fn poll_event(&mut self) -> Option<StreamEvent> {
+        let event = match self.stream.as_ref()?.is_active() {
+            Ok(true) => return None,
+            Ok(false) => StreamEvent::Finished,
+            Err(e) => StreamEvent::Error(e.to_string()),
+        };
+        // 止まったストリームは閉じて、コールバックを破棄する
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.close();
+        }
+        Some(event)
+    }
+}
+
+/// PortAudio のコールバックフラグを StreamStatus に変換する
+fn stream_status(flags: pa::StreamCallbackFlags) -> StreamStatus {
+    StreamStatus {
+        input_underflow: flags.contains(pa::StreamCallbackFlags::INPUT_UNDERFLOW),
+        input_overflow: flags.contains(pa::StreamCallbackFlags::INPUT_OVERFLOW),
+        output_underflow: flags.contains(pa::StreamCallbackFlags::OUTPUT_UNDERFLOW),
+        output_overflow: flags.contains(pa::StreamCallbackFlags::OUTPUT_OVERFLOW),
+    }
+}
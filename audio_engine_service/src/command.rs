@@ -0,0 +1,397 @@
+//! 再生中の音声グラフを別スレッドから操作するためのコマンドキューです。
+//!
+//! OSC サーバーなどの外部からの制御は GraphCommand を CommandSender で送り、
+//! AudioEngineService の所有者が process_commands で音声グラフに適用します。
+//! 結果が必要な場合は CommandSender::request で送り、返された Receiver で受け取ります。
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use audio_engine_core::audio_graph::{AudioGraph, GainStage, NodeId};
+use audio_engine_core::parameter::{MacroCurve, ParameterInfo};
+use audio_engine_core::tuning::Tuning;
+
+use crate::node_factory;
+
+/// GetMeter で返すチャンネル数（現在、音声グラフは 2ch のみのサポート）
+pub const METER_CHANNELS: usize = 2;
+
+/// 音声グラフへの操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphCommand {
+    /// ノードを追加する（種類は node_factory::NODE_KINDS のいずれか）
+    AddNode { kind: String },
+    /// ノードを削除する（ノードのメモリは process_commands の呼び出し元のスレッドで解放する）
+    RemoveNode { node_id: NodeId },
+    /// ノードのパラメーターを設定する
+    SetParameter {
+        node_id: NodeId,
+        parameter_id: String,
+        value: f32,
+    },
+    /// エッジを追加する
+    Connect { from: NodeId, to: NodeId },
+    /// エッジを削除する
+    Disconnect { from: NodeId, to: NodeId },
+    /// 接続元ノードの出力ポートから接続先ノードの入力ポートにエッジを追加する
+    ConnectPort {
+        from: NodeId,
+        from_port: usize,
+        to: NodeId,
+        to_port: usize,
+    },
+    /// 接続元ノードの出力でパラメーターをオーディオレートで変化させるエッジを追加する
+    ConnectParameter {
+        from: NodeId,
+        to: NodeId,
+        parameter_id: String,
+        scale: f32,
+        offset: f32,
+    },
+    /// 接続元ノードの出力を 1 ブロック遅れて入力するフィードバックのエッジを追加する
+    ConnectFeedback { from: NodeId, to: NodeId, gain: f32 },
+    /// フィードバックのエッジを削除する
+    DisconnectFeedback { from: NodeId, to: NodeId },
+    /// ノードの組を 1 サンプルずつ処理するフィードバックリージョンにまとめる（結果は FeedbackRegion の ID）
+    CreateFeedbackRegion { node_ids: Vec<NodeId> },
+    /// MIDI の接続を追加する
+    ConnectMidi { from: NodeId, to: NodeId },
+    /// MIDI の接続を削除する
+    DisconnectMidi { from: NodeId, to: NodeId },
+    /// ノードとエッジの一覧を取得する
+    GetTopology,
+    /// 各ノードの出力のピークを取得する
+    GetMeters,
+    /// ノードの出力のピークをチャンネルごとに取得する
+    GetMeter { node_id: NodeId },
+    /// 全ノードのパラメーターの現在の値を取得する
+    GetParameters,
+    /// ノードのパラメーターの現在の値を取得する
+    GetParameter {
+        node_id: NodeId,
+        parameter_id: String,
+    },
+    /// ノードが公開するパラメーターの情報の一覧を取得する
+    GetParameterInfos { node_id: NodeId },
+    /// A/B モーフの 2 つのスナップショット（ノード ID, パラメーター ID, 値）を設定する
+    SetMorphSnapshots {
+        a: Vec<(NodeId, String, f32)>,
+        b: Vec<(NodeId, String, f32)>,
+    },
+    /// A/B モーフの位置（0 で A、1 で B）を設定する
+    SetMorphPosition { position: f32 },
+    /// マクロにノードのパラメーターを割り当てる
+    MapMacro {
+        index: usize,
+        node_id: NodeId,
+        parameter_id: String,
+        min: f32,
+        max: f32,
+        curve: MacroCurve,
+    },
+    /// マクロの値（0〜1）を設定する
+    SetMacro { index: usize, value: f32 },
+    /// ゲインステージングの分析を有効または無効にする
+    SetGainStaging { enabled: bool },
+    /// ゲインステージングのレポートを取得する
+    GetGainStaging,
+    /// サブグラフをレンダリングして SamplePlayer に置き換える（結果は SamplePlayer の ID）
+    Freeze {
+        node_ids: Vec<NodeId>,
+        num_frames: usize,
+    },
+    /// フリーズしたサブグラフを元のノードに戻す
+    Unfreeze { node_id: NodeId },
+    /// トランスポートのテンポ（BPM）を設定する
+    SetTempo { tempo: f64 },
+    /// トランスポートを再生または停止する（停止しても再生位置は保つ）
+    SetPlaying { playing: bool },
+    /// ノート番号から周波数への変換に使う調律を設定する
+    SetTuning { tuning: Box<Tuning> },
+}
+
+/// GraphCommand の結果
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandResponse {
+    /// 操作が完了した
+    Done,
+    /// ノードを追加した（追加したノードの ID）
+    NodeAdded(NodeId),
+    /// ノードとエッジの一覧
+    Topology {
+        /// ノードの ID（昇順）
+        nodes: Vec<NodeId>,
+        /// エッジ（接続元 ID, 接続先 ID）
+        edges: Vec<(NodeId, NodeId)>,
+    },
+    /// ノードごとの、直前のブロックの出力のピーク（ノード ID, ピーク）
+    Meters(Vec<(NodeId, f32)>),
+    /// 直前のブロックの出力の、チャンネルごとのピーク
+    ChannelPeaks(Vec<f32>),
+    /// 全ノードのパラメーターの値（ノード ID, パラメーター ID, 値）
+    Parameters(Vec<(NodeId, String, f32)>),
+    /// パラメーターの値
+    Value(f32),
+    /// ノードが公開するパラメーターの情報
+    ParameterInfos(Vec<ParameterInfo>),
+    /// ノードごとのゲインステージングの記録
+    GainStaging(Vec<GainStage>),
+}
+
+impl GraphCommand {
+    /// 音声グラフにコマンドを適用する
+    ///
+    /// # 戻り値
+    /// * 成功した場合は `Ok` で結果を返し、失敗した場合は `Err` でエラーメッセージを返す
+    pub fn apply(&self, audio_graph: &mut AudioGraph) -> Result<CommandResponse, String> {
+        match self {
+            GraphCommand::AddNode { kind } => {
+                node_factory::add_node(audio_graph, kind).map(CommandResponse::NodeAdded)
+            }
+            GraphCommand::RemoveNode { node_id } => {
+                if audio_graph.discard_node(*node_id) {
+                    Ok(CommandResponse::Done)
+                } else {
+                    Err(format!("ノードID {}が存在しません", node_id))
+                }
+            }
+            GraphCommand::SetParameter {
+                node_id,
+                parameter_id,
+                value,
+            } => audio_graph
+                .set_parameter(*node_id, parameter_id, *value)
+                .map(|_| CommandResponse::Done),
+            GraphCommand::Connect { from, to } => audio_graph
+                .add_edge(*from, *to)
+                .map(|_| CommandResponse::Done),
+            GraphCommand::Disconnect { from, to } => {
+                if audio_graph.remove_edge(*from, *to) {
+                    Ok(CommandResponse::Done)
+                } else {
+                    Err(format!("エッジ {} -> {} が存在しません", from, to))
+                }
+            }
+            GraphCommand::ConnectPort {
+                from,
+                from_port,
+                to,
+                to_port,
+            } => audio_graph
+                .add_edge_from_port(*from, *from_port, *to, *to_port)
+                .map(|_| CommandResponse::Done),
+            GraphCommand::ConnectParameter {
+                from,
+                to,
+                parameter_id,
+                scale,
+                offset,
+            } => audio_graph
+                .add_parameter_edge(*from, *to, parameter_id, *scale, *offset)
+                .map(|_| CommandResponse::Done),
+            GraphCommand::ConnectFeedback { from, to, gain } => audio_graph
+                .add_feedback_edge(*from, *to, 0, *gain)
+                .map(|_| CommandResponse::Done),
+            GraphCommand::DisconnectFeedback { from, to } => {
+                if audio_graph.remove_feedback_edge(*from, *to) {
+                    Ok(CommandResponse::Done)
+                } else {
+                    Err(format!(
+                        "フィードバックのエッジ {} -> {} が存在しません",
+                        from, to
+                    ))
+                }
+            }
+            GraphCommand::CreateFeedbackRegion { node_ids } => audio_graph
+                .create_feedback_region(node_ids)
+                .map(CommandResponse::NodeAdded),
+            GraphCommand::ConnectMidi { from, to } => audio_graph
+                .add_midi_route(*from, *to)
+                .map(|_| CommandResponse::Done),
+            GraphCommand::DisconnectMidi { from, to } => {
+                if audio_graph.remove_midi_route(*from, *to) {
+                    Ok(CommandResponse::Done)
+                } else {
+                    Err(format!("MIDI の接続 {} -> {} が存在しません", from, to))
+                }
+            }
+            GraphCommand::GetTopology => Ok(CommandResponse::Topology {
+                nodes: audio_graph.node_ids(),
+                edges: audio_graph.edges(),
+            }),
+            GraphCommand::GetMeters => Ok(CommandResponse::Meters(
+                audio_graph
+                    .node_ids()
+                    .into_iter()
+                    .map(|node_id| (node_id, audio_graph.output_peak(node_id).unwrap_or(0.0)))
+                    .collect(),
+            )),
+            GraphCommand::GetMeter { node_id } => {
+                let mut peaks = vec![0.0; METER_CHANNELS];
+                if audio_graph.output_channel_peaks(*node_id, &mut peaks) {
+                    Ok(CommandResponse::ChannelPeaks(peaks))
+                } else {
+                    Err(format!("ノード {} のメーターがありません", node_id))
+                }
+            }
+            GraphCommand::GetParameters => Ok(CommandResponse::Parameters(
+                audio_graph
+                    .node_ids()
+                    .into_iter()
+                    .flat_map(|node_id| {
+                        let node = audio_graph.get_node(node_id).unwrap();
+                        node.parameters().iter().filter_map(move |info| {
+                            node.get_parameter(info.id)
+                                .map(|value| (node_id, info.id.to_string(), value))
+                        })
+                    })
+                    .collect(),
+            )),
+            GraphCommand::GetParameter {
+                node_id,
+                parameter_id,
+            } => audio_graph
+                .get_parameter(*node_id, parameter_id)
+                .map(CommandResponse::Value)
+                .ok_or_else(|| {
+                    format!(
+                        "ノード {} にパラメーター {} がありません",
+                        node_id, parameter_id
+                    )
+                }),
+            GraphCommand::GetParameterInfos { node_id } => audio_graph
+                .get_node(*node_id)
+                .map(|node| CommandResponse::ParameterInfos(node.parameters().to_vec()))
+                .ok_or_else(|| format!("ノード {} が存在しません", node_id)),
+            GraphCommand::SetMorphSnapshots { a, b } => audio_graph
+                .set_morph_snapshots(a, b)
+                .map(|_| CommandResponse::Done),
+            GraphCommand::SetMorphPosition { position } => {
+                audio_graph.set_morph_position(*position);
+                Ok(CommandResponse::Done)
+            }
+            GraphCommand::MapMacro {
+                index,
+                node_id,
+                parameter_id,
+                min,
+                max,
+                curve,
+            } => audio_graph
+                .add_macro_mapping(*index, *node_id, parameter_id, *min, *max, *curve)
+                .map(|_| CommandResponse::Done),
+            GraphCommand::SetMacro { index, value } => {
+                if audio_graph.set_macro(*index, *value) {
+                    Ok(CommandResponse::Done)
+                } else {
+                    Err(format!("マクロの番号 {} が不正です", index))
+                }
+            }
+            GraphCommand::SetGainStaging { enabled } => {
+                audio_graph.set_gain_staging_enabled(*enabled);
+                Ok(CommandResponse::Done)
+            }
+            GraphCommand::GetGainStaging => Ok(CommandResponse::GainStaging(
+                audio_graph.gain_staging_report(),
+            )),
+            GraphCommand::Freeze {
+                node_ids,
+                num_frames,
+            } => audio_graph
+                .freeze(node_ids, *num_frames)
+                .map(CommandResponse::NodeAdded),
+            GraphCommand::Unfreeze { node_id } => audio_graph
+                .unfreeze(*node_id)
+                .map(|_| CommandResponse::Done),
+            GraphCommand::SetTempo { tempo } => {
+                if !(*tempo > 0.0 && tempo.is_finite()) {
+                    return Err(format!("テンポが不正です: {}", tempo));
+                }
+                let mut transport = audio_graph.transport();
+                transport.tempo = *tempo;
+                audio_graph.set_transport(transport);
+                Ok(CommandResponse::Done)
+            }
+            GraphCommand::SetPlaying { playing } => {
+                let mut transport = audio_graph.transport();
+                transport.playing = *playing;
+                audio_graph.set_transport(transport);
+                Ok(CommandResponse::Done)
+            }
+            GraphCommand::SetTuning { tuning } => {
+                audio_graph.set_tuning(**tuning);
+                Ok(CommandResponse::Done)
+            }
+        }
+    }
+}
+
+/// コマンドの結果を受け取るための受信側
+pub type CommandReply = Receiver<Result<CommandResponse, String>>;
+
+/// キューに入ったコマンドと、結果の送り先
+pub(crate) struct PendingCommand {
+    pub(crate) command: GraphCommand,
+    pub(crate) reply: Option<Sender<Result<CommandResponse, String>>>,
+}
+
+/// コマンドキューの送信側。クローンして複数のスレッドから送信できます。
+#[derive(Clone)]
+pub struct CommandSender {
+    sender: Sender<PendingCommand>,
+}
+
+impl CommandSender {
+    /// コマンドを送る（結果は受け取らない）
+    ///
+    /// # 戻り値
+    /// * 送れた場合は `Ok(())`、サービスが破棄されている場合は `Err` でエラーメッセージを返す
+    pub fn send(&self, command: GraphCommand) -> Result<(), String> {
+        self.sender
+            .send(PendingCommand {
+                command,
+                reply: None,
+            })
+            .map_err(|_| "サービスが破棄されています".to_string())
+    }
+
+    /// コマンドを送り、結果を受け取るための受信側を返す
+    ///
+    /// 結果は、所有者のスレッドが process_commands を呼び出したときに届きます。
+    pub fn request(&self, command: GraphCommand) -> Result<CommandReply, String> {
+        let (reply, receiver) = mpsc::channel();
+        self.sender
+            .send(PendingCommand {
+                command,
+                reply: Some(reply),
+            })
+            .map_err(|_| "サービスが破棄されています".to_string())?;
+        Ok(receiver)
+    }
+}
+
+/// コマンドキュー
+pub(crate) struct CommandQueue {
+    /// 送信側（sender でクローンを渡す）
+    sender: Sender<PendingCommand>,
+    /// 受信側
+    receiver: Receiver<PendingCommand>,
+}
+
+impl CommandQueue {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+
+    /// 送信側を取得する
+    pub(crate) fn sender(&self) -> CommandSender {
+        CommandSender {
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// たまったコマンドを全て取り出す
+    pub(crate) fn drain(&self) -> impl Iterator<Item = PendingCommand> + '_ {
+        self.receiver.try_iter()
+    }
+}
@@ -0,0 +1,198 @@
+//! 音声グラフを 1 行ずつ記述するテキスト形式です。
+//!
+//! ```text
+//! # サイン波を減衰させて出力し、入力もそのまま出力する
+//! sine frequency=220 -> gain gain=0.5 -> out
+//! in -> out
+//!
+//! # 名前を付けたノードは、後の行から参照できる
+//! fader: crossfader position=0.5 -> out
+//! saw frequency=110 -> fader
+//! ```
+//!
+//! - 1 行（または `;` で区切った 1 文）が、`->` でつないだノードの連鎖です。
+//! - 各ノードは `種類 パラメーター=値 ...` で記述します。種類は node_factory::NODE_KINDS のいずれかです。
+//! - `名前: 種類 ...` で名前を付けられます。名前を付けない場合は、種類に連番を付けた名前（sine1 など）になります。
+//! - 定義済みの名前だけを書くと、そのノードを参照します。
+//! - 入力ノード `in` と出力ノード `out` は常に用意されています。
+//! - `#` 以降はコメントです。
+
+use std::collections::HashMap;
+
+use crate::graph_file::{EdgeDescription, GraphDescription, NodeDescription};
+use crate::node_factory::NODE_KINDS;
+
+/// 入力ノードの名前
+const INPUT_NAME: &str = "in";
+/// 出力ノードの名前
+const OUTPUT_NAME: &str = "out";
+
+impl GraphDescription {
+    /// テキスト形式の記述を解釈する
+    ///
+    /// # 戻り値
+    /// * 音声グラフの記述。構文が不正な場合は `Err` で行番号を含むエラーメッセージを返す
+    pub fn from_dsl(text: &str) -> Result<Self, String> {
+        let mut parser = Parser::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            for statement in line.split(';') {
+                parser
+                    .parse_statement(statement)
+                    .map_err(|e| format!("{} 行目: {}", line_index + 1, e))?;
+            }
+        }
+        Ok(Self {
+            nodes: parser.nodes,
+            edges: parser.edges,
+            input: INPUT_NAME.to_string(),
+            output: OUTPUT_NAME.to_string(),
+        })
+    }
+}
+
+/// 解釈中の状態
+struct Parser {
+    /// 定義したノード
+    nodes: Vec<NodeDescription>,
+    /// 接続
+    edges: Vec<EdgeDescription>,
+    /// 名前を付けずに定義したノードの、種類ごとの数
+    counts: HashMap<String, usize>,
+}
+
+impl Parser {
+    fn new() -> Self {
+        let node = |name: &str, kind: &str| NodeDescription {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            parameters: Vec::new(),
+        };
+        Self {
+            nodes: vec![node(INPUT_NAME, "input"), node(OUTPUT_NAME, "output")],
+            edges: Vec::new(),
+            counts: HashMap::new(),
+        }
+    }
+
+    /// `->` でつないだ 1 文を解釈する
+    fn parse_statement(&mut self, statement: &str) -> Result<(), String> {
+        if statement.trim().is_empty() {
+            return Ok(());
+        }
+        let mut previous: Option<String> = None;
+        for element in statement.split("->") {
+            let name = self.parse_node(element)?;
+            if let Some(from) = previous {
+                self.edges.push(EdgeDescription::new(from, name.clone()));
+            }
+            previous = Some(name);
+        }
+        Ok(())
+    }
+
+    /// 連鎖の 1 要素を解釈し、ノードの名前を返す
+    fn parse_node(&mut self, element: &str) -> Result<String, String> {
+        let (label, body) = match element.split_once(':') {
+            Some((label, body)) => (Some(label.trim()), body),
+            None => (None, element),
+        };
+        let mut words = body.split_whitespace();
+        let head = words.next().ok_or("ノードの記述がありません")?;
+
+        // 名前だけの場合は、定義済みのノードを参照する
+        if label.is_none() && self.nodes.iter().any(|node| node.name == head) {
+            if let Some(word) = words.next() {
+                return Err(format!(
+                    "定義済みのノード {} にはパラメーターを指定できません: {}",
+                    head, word
+                ));
+            }
+            return Ok(head.to_string());
+        }
+
+        if !NODE_KINDS.contains(&head) {
+            return Err(format!("ノードの種類か名前が不正です: {}", head));
+        }
+        let mut parameters = Vec::new();
+        for word in words {
+            let (id, value) = word.split_once('=').ok_or_else(|| {
+                format!("パラメーターは ID=値 の形式で指定してください: {}", word)
+            })?;
+            let value = value
+                .parse()
+                .map_err(|_| format!("パラメーター {} の値が不正です: {}", id, value))?;
+            parameters.push((id.to_string(), value));
+        }
+
+        let name = match label {
+            Some("") => return Err("ノードの名前が空です".to_string()),
+            Some(label) => label.to_string(),
+            None => {
+                let count = self.counts.entry(head.to_string()).or_default();
+                *count += 1;
+                format!("{}{}", head, count)
+            }
+        };
+        if self.nodes.iter().any(|node| node.name == name) {
+            return Err(format!("ノードの名前が重複しています: {}", name));
+        }
+        self.nodes.push(NodeDescription {
+            name: name.clone(),
+            kind: head.to_string(),
+            parameters,
+        });
+        Ok(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use audio_engine_core::audio_graph::AudioGraph;
+
+    #[test]
+    fn test_parse_and_build_graph() {
+        let description = GraphDescription::from_dsl(
+            "
+            # コメント
+            sine frequency=220 -> gain gain=0.5 -> out
+            fader: crossfader position=0.25 -> out; in -> fader
+            sine frequency=330 -> fader
+            ",
+        )
+        .unwrap();
+        let names: Vec<&str> = description.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["in", "out", "sine1", "gain1", "fader", "sine2"]);
+        assert_eq!(
+            description.nodes[2].parameters,
+            vec![("frequency".to_string(), 220.0)]
+        );
+
+        let mut audio_graph = AudioGraph::new();
+        let loaded = description.build(&mut audio_graph).unwrap();
+        let id = |name: &str| loaded.node_id(name).unwrap();
+        assert_eq!(audio_graph.get_parameter(id("gain1"), "gain"), Some(0.5));
+        assert_eq!(
+            audio_graph.get_parameter(id("sine2"), "frequency"),
+            Some(330.0)
+        );
+        let mut expected = vec![
+            (id("sine1"), id("gain1")),
+            (id("gain1"), id("out")),
+            (id("fader"), id("out")),
+            (id("in"), id("fader")),
+            (id("sine2"), id("fader")),
+        ];
+        expected.sort();
+        assert_eq!(audio_graph.edges(), expected);
+
+        // 不正な記述は行番号付きのエラーになる
+        let error = GraphDescription::from_dsl("sine -> out\nunknown -> out").unwrap_err();
+        assert!(error.starts_with("2 行目"));
+        assert!(GraphDescription::from_dsl("sine frequency -> out").is_err());
+        assert!(GraphDescription::from_dsl("out gain=1").is_err());
+        assert!(GraphDescription::from_dsl("in: sine").is_err());
+        assert!(GraphDescription::from_dsl("sine -> -> out").is_err());
+    }
+}
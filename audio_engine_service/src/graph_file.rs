@@ -0,0 +1,498 @@
+//! 音声グラフの記述ファイル（JSON または YAML）を読み込み、AudioGraph を構築します。
+//!
+//! ```json
+//! {
+//!     "nodes": [
+//!         { "name": "in", "kind": "input" },
+//!         { "name": "fader", "kind": "crossfader", "params": { "position": 0.5 } },
+//!         { "name": "osc", "kind": "sine" },
+//!         { "name": "out", "kind": "output" }
+//!     ],
+//!     "edges": [
+//!         ["in", "fader"],
+//!         { "from": "osc", "to": "fader", "to_port": 1 },
+//!         ["fader", "out"]
+//!     ],
+//!     "input": "in",
+//!     "output": "out"
+//! }
+//! ```
+//!
+//! エッジは `[接続元, 接続先]` で記述すると、メイン出力からメイン入力にゲイン 1 で接続します。
+//! オブジェクトで記述すると、`from_port`（接続元の出力ポート）、`to_port`（接続先の入力ポート）、
+//! `gain`、`offset` を指定できます（省略した場合はそれぞれ 0, 0, 1, 0）。
+//! 同じノード間のエッジを複数記述すると、並列のエッジになります。
+//!
+//! YAML の場合も同じ構造で記述します。テキスト形式（graph_dsl）でも記述できます。
+//! ノードの種類は node_factory::NODE_KINDS のいずれかです。ノードは名前で参照します。
+//!
+//! GraphDescription::capture で、構築した音声グラフを記述に戻して JSON に書き出すこともできます。
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use audio_engine_core::audio_graph::{AudioGraph, EdgeTap, NodeId};
+use serde_json::{json, Map, Value};
+
+use crate::node_factory;
+
+/// ノードの記述
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeDescription {
+    /// 名前（記述の中で一意）
+    pub name: String,
+    /// 種類
+    pub kind: String,
+    /// 初期値を設定するパラメーター（ID, 値）
+    pub parameters: Vec<(String, f32)>,
+}
+
+/// エッジの記述
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeDescription {
+    /// 接続元の名前
+    pub from: String,
+    /// 接続先の名前
+    pub to: String,
+    /// 接続元の出力ポート、接続先の入力ポート、ゲイン、オフセット
+    pub tap: EdgeTap,
+}
+
+impl EdgeDescription {
+    /// メイン出力からメイン入力にゲイン 1 で接続するエッジの記述を作成
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+            tap: EdgeTap::DEFAULT,
+        }
+    }
+
+    /// JSON の値に変換する（ポートとゲインが既定の場合は `[接続元, 接続先]`）
+    fn to_value(&self) -> Value {
+        if self.tap == EdgeTap::DEFAULT {
+            return json!([self.from, self.to]);
+        }
+        json!({
+            "from": self.from,
+            "to": self.to,
+            "from_port": self.tap.from_port,
+            "to_port": self.tap.port,
+            "gain": self.tap.gain,
+            "offset": self.tap.offset,
+        })
+    }
+
+    /// JSON の値を解釈する
+    fn from_value(value: &Value) -> Result<Self, String> {
+        let invalid = || format!("エッジが不正です: {}", value);
+        if let Some([Value::String(from), Value::String(to)]) = value.as_array().map(Vec::as_slice)
+        {
+            return Ok(Self::new(from.clone(), to.clone()));
+        }
+        let object = value.as_object().ok_or_else(invalid)?;
+        let name = |key: &str| {
+            object
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(invalid)
+        };
+        let port = |key: &str| match object.get(key) {
+            Some(port) => port.as_u64().map(|port| port as usize).ok_or_else(invalid),
+            None => Ok(0),
+        };
+        let number = |key: &str, default: f32| match object.get(key) {
+            Some(number) => number
+                .as_f64()
+                .map(|number| number as f32)
+                .ok_or_else(invalid),
+            None => Ok(default),
+        };
+        Ok(Self {
+            from: name("from")?,
+            to: name("to")?,
+            tap: EdgeTap {
+                from_port: port("from_port")?,
+                port: port("to_port")?,
+                gain: number("gain", 1.0)?,
+                offset: number("offset", 0.0)?,
+            },
+        })
+    }
+}
+
+/// 音声グラフの記述
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphDescription {
+    /// ノード
+    pub nodes: Vec<NodeDescription>,
+    /// エッジ（同じノード間のエッジは並列のエッジとして記述した順に追加する）
+    pub edges: Vec<EdgeDescription>,
+    /// 入力ノードの名前
+    pub input: String,
+    /// 出力ノードの名前
+    pub output: String,
+}
+
+/// 構築した音声グラフのノードの対応
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedGraph {
+    /// ノードの名前と ID の対応
+    pub node_ids: HashMap<String, NodeId>,
+    /// 入力ノードの ID
+    pub input_id: NodeId,
+    /// 出力ノードの ID
+    pub output_id: NodeId,
+}
+
+impl LoadedGraph {
+    /// 名前からノードの ID を取得する
+    pub fn node_id(&self, name: &str) -> Result<NodeId, String> {
+        self.node_ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("ノードが見つかりません: {}", name))
+    }
+}
+
+impl GraphDescription {
+    /// 記述ファイルを読み込む
+    ///
+    /// # 実装時の注意
+    /// 拡張子が .yaml または .yml の場合は YAML、.patch の場合はテキスト形式、
+    /// それ以外は JSON として解釈します。
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            format!(
+                "記述ファイルを読み込めませんでした: {:?} ({})",
+                path.as_ref(),
+                e
+            )
+        })?;
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => Self::from_yaml(&text),
+            Some("patch") => Self::from_dsl(&text),
+            _ => Self::from_json(&text),
+        }
+    }
+
+    /// JSON の記述を解釈する
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        Self::from_value(&serde_json::from_str(text).map_err(|e| e.to_string())?)
+    }
+
+    /// YAML の記述を解釈する
+    pub fn from_yaml(text: &str) -> Result<Self, String> {
+        Self::from_value(&serde_yaml::from_str(text).map_err(|e| e.to_string())?)
+    }
+
+    /// 音声グラフのノード・パラメーター・エッジを記述に変換する
+    ///
+    /// ノードの名前は `種類_ID` になります。node_factory で種類を記録したノードのみ変換できます。
+    /// エッジはポート、ゲイン、並列のエッジも含めて変換しますが、フィードバックのエッジは含まれません。
+    ///
+    /// # 戻り値
+    /// * 変換した記述。種類が記録されていないノードがある場合や、入力・出力ノードがない場合は `Err`
+    pub fn capture(audio_graph: &AudioGraph) -> Result<Self, String> {
+        let mut names = HashMap::new();
+        let mut nodes = Vec::new();
+        let (mut input, mut output) = (None, None);
+        for node_id in audio_graph.node_ids() {
+            let kind = audio_graph
+                .node_kind(node_id)
+                .ok_or_else(|| format!("ノード {} の種類が不明なため書き出せません", node_id))?;
+            let name = format!("{}_{}", kind, node_id.as_raw());
+            match kind {
+                "input" if input.is_none() => input = Some(name.clone()),
+                "output" if output.is_none() => output = Some(name.clone()),
+                _ => {}
+            }
+            let node = audio_graph.get_node(node_id).unwrap();
+            let parameters = node
+                .parameters()
+                .iter()
+                .filter_map(|info| {
+                    node.get_parameter(info.id)
+                        .map(|value| (info.id.to_string(), value))
+                })
+                .collect();
+            names.insert(node_id, name.clone());
+            nodes.push(NodeDescription {
+                name,
+                kind: kind.to_string(),
+                parameters,
+            });
+        }
+        let edges = audio_graph
+            .edges()
+            .into_iter()
+            .flat_map(|(from, to)| {
+                let (from_name, to_name) = (&names[&from], &names[&to]);
+                audio_graph
+                    .edge_taps(from, to)
+                    .into_iter()
+                    .map(|tap| EdgeDescription {
+                        from: from_name.clone(),
+                        to: to_name.clone(),
+                        tap,
+                    })
+            })
+            .collect();
+        Ok(Self {
+            nodes,
+            edges,
+            input: input.ok_or("入力ノードがありません")?,
+            output: output.ok_or("出力ノードがありません")?,
+        })
+    }
+
+    /// JSON の記述に変換する
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<Value> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut params = Map::new();
+                for (id, value) in node.parameters.iter() {
+                    params.insert(id.clone(), json!(value));
+                }
+                json!({ "name": node.name, "kind": node.kind, "params": params })
+            })
+            .collect();
+        let edges: Vec<Value> = self.edges.iter().map(EdgeDescription::to_value).collect();
+        serde_json::to_string_pretty(&json!({
+            "nodes": nodes,
+            "edges": edges,
+            "input": self.input,
+            "output": self.output,
+        }))
+        .unwrap()
+    }
+
+    fn from_value(root: &Value) -> Result<Self, String> {
+        let string_field = |value: &Value, name: &str| {
+            value
+                .get(name)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| format!("{} がありません", name))
+        };
+
+        let mut nodes = Vec::new();
+        for node in root
+            .get("nodes")
+            .and_then(Value::as_array)
+            .ok_or("nodes がありません")?
+        {
+            let mut parameters = Vec::new();
+            if let Some(params) = node.get("params") {
+                for (id, value) in params.as_object().ok_or("params が不正です")? {
+                    let value = value
+                        .as_f64()
+                        .ok_or_else(|| format!("パラメーター {} の値が不正です", id))?;
+                    parameters.push((id.clone(), value as f32));
+                }
+            }
+            nodes.push(NodeDescription {
+                name: string_field(node, "name")?,
+                kind: string_field(node, "kind")?,
+                parameters,
+            });
+        }
+
+        let mut edges = Vec::new();
+        if let Some(edge_values) = root.get("edges") {
+            for edge in edge_values.as_array().ok_or("edges が不正です")? {
+                edges.push(EdgeDescription::from_value(edge)?);
+            }
+        }
+
+        Ok(Self {
+            nodes,
+            edges,
+            input: string_field(root, "input")?,
+            output: string_field(root, "output")?,
+        })
+    }
+
+    /// 記述に従ってノードとエッジを音声グラフに追加する
+    ///
+    /// # 戻り値
+    /// * ノードの名前と ID の対応。失敗した場合は `Err` でエラーメッセージを返す
+    pub fn build(&self, audio_graph: &mut AudioGraph) -> Result<LoadedGraph, String> {
+        let mut node_ids = HashMap::new();
+        for node in self.nodes.iter() {
+            if node_ids.contains_key(&node.name) {
+                return Err(format!("ノードの名前が重複しています: {}", node.name));
+            }
+            let node_id = node_factory::add_node(audio_graph, &node.kind)?;
+            for (parameter_id, value) in node.parameters.iter() {
+                audio_graph.set_parameter(node_id, parameter_id, *value)?;
+            }
+            node_ids.insert(node.name.clone(), node_id);
+        }
+
+        let loaded = LoadedGraph {
+            input_id: NodeId::from_raw(0),
+            output_id: NodeId::from_raw(0),
+            node_ids,
+        };
+        // 同じノード間のエッジは、並列のエッジとしてまとめて追加する
+        let mut edges: Vec<((NodeId, NodeId), Vec<EdgeTap>)> = Vec::new();
+        for edge in self.edges.iter() {
+            let key = (loaded.node_id(&edge.from)?, loaded.node_id(&edge.to)?);
+            match edges.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, taps)) => taps.push(edge.tap),
+                None => edges.push((key, vec![edge.tap])),
+            }
+        }
+        for ((from, to), taps) in edges.iter() {
+            audio_graph.restore_edge(*from, *to, taps)?;
+        }
+        Ok(LoadedGraph {
+            input_id: loaded.node_id(&self.input)?,
+            output_id: loaded.node_id(&self.output)?,
+            ..loaded
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_graph_from_json() {
+        let description = GraphDescription::from_json(
+            r#"{
+                "nodes": [
+                    { "name": "in", "kind": "input" },
+                    { "name": "fader", "kind": "crossfader", "params": { "position": 0.25 } },
+                    { "name": "out", "kind": "output" }
+                ],
+                "edges": [["in", "fader"], ["fader", "out"]],
+                "input": "in",
+                "output": "out"
+            }"#,
+        )
+        .unwrap();
+
+        let mut audio_graph = AudioGraph::new();
+        let loaded = description.build(&mut audio_graph).unwrap();
+        let fader = loaded.node_id("fader").unwrap();
+        assert_eq!(audio_graph.get_parameter(fader, "position"), Some(0.25));
+        assert_eq!(
+            audio_graph.edges(),
+            vec![(loaded.input_id, fader), (fader, loaded.output_id)]
+        );
+
+        // 存在しないノードへの接続や、対応していない種類はエラー
+        let mut invalid = description.clone();
+        invalid.edges.push(EdgeDescription::new("fader", "missing"));
+        assert!(invalid.build(&mut AudioGraph::new()).is_err());
+        let mut invalid = description.clone();
+        invalid.nodes[1].kind = "unknown".to_string();
+        assert!(invalid.build(&mut AudioGraph::new()).is_err());
+        assert!(GraphDescription::from_json(r#"{ "nodes": [] }"#).is_err());
+
+        // YAML でも同じ記述を読み込める
+        let yaml = GraphDescription::from_yaml(
+            "
+nodes:
+  - { name: in, kind: input }
+  - { name: fader, kind: crossfader, params: { position: 0.25 } }
+  - { name: out, kind: output }
+edges: [[in, fader], [fader, out]]
+input: in
+output: out
+",
+        )
+        .unwrap();
+        assert_eq!(yaml, description);
+
+        // 構築した音声グラフを JSON に書き出し、読み込み直すと同じ構成になる
+        let captured = GraphDescription::capture(&audio_graph).unwrap();
+        let restored = GraphDescription::from_json(&captured.to_json()).unwrap();
+        let mut restored_graph = AudioGraph::new();
+        let reloaded = restored.build(&mut restored_graph).unwrap();
+        let fader = reloaded.node_id(&captured.nodes[1].name).unwrap();
+        assert_eq!(restored_graph.get_parameter(fader, "position"), Some(0.25));
+        assert_eq!(
+            restored_graph.edges(),
+            vec![(reloaded.input_id, fader), (fader, reloaded.output_id)]
+        );
+    }
+
+    #[test]
+    fn test_capture_keeps_port_edges() {
+        let description = GraphDescription::from_json(
+            r#"{
+                "nodes": [
+                    { "name": "in", "kind": "input" },
+                    { "name": "osc", "kind": "sine" },
+                    { "name": "fader", "kind": "crossfader" },
+                    { "name": "gain", "kind": "gain" },
+                    { "name": "out", "kind": "output" }
+                ],
+                "edges": [
+                    ["in", "fader"],
+                    { "from": "osc", "to": "fader", "to_port": 1, "gain": 0.5 },
+                    ["fader", "gain"],
+                    ["osc", "gain"],
+                    { "from": "osc", "to": "gain", "to_port": 1, "gain": 0.25, "offset": 0.5 },
+                    ["gain", "out"]
+                ],
+                "input": "in",
+                "output": "out"
+            }"#,
+        )
+        .unwrap();
+        let mut audio_graph = AudioGraph::new();
+        let loaded = description.build(&mut audio_graph).unwrap();
+        let (osc, fader, gain) = (
+            loaded.node_id("osc").unwrap(),
+            loaded.node_id("fader").unwrap(),
+            loaded.node_id("gain").unwrap(),
+        );
+        let port_tap = |port, gain, offset| EdgeTap {
+            port,
+            gain,
+            offset,
+            ..EdgeTap::DEFAULT
+        };
+        assert_eq!(
+            audio_graph.edge_taps(osc, fader),
+            vec![port_tap(1, 0.5, 0.0)]
+        );
+        assert_eq!(
+            audio_graph.edge_taps(osc, gain),
+            vec![EdgeTap::DEFAULT, port_tap(1, 0.25, 0.5)]
+        );
+
+        // 書き出して読み込み直しても、ポート・ゲイン・並列のエッジが変わらない
+        let captured = GraphDescription::capture(&audio_graph).unwrap();
+        let restored = GraphDescription::from_json(&captured.to_json()).unwrap();
+        assert_eq!(restored.edges, captured.edges);
+        let mut restored_graph = AudioGraph::new();
+        let reloaded = restored.build(&mut restored_graph).unwrap();
+        for (from, to) in audio_graph.edges() {
+            let name = |id: NodeId| {
+                let kind = audio_graph.node_kind(id).unwrap();
+                reloaded
+                    .node_id(&format!("{}_{}", kind, id.as_raw()))
+                    .unwrap()
+            };
+            assert_eq!(
+                restored_graph.edge_taps(name(from), name(to)),
+                audio_graph.edge_taps(from, to)
+            );
+        }
+        assert_eq!(restored_graph.edges().len(), audio_graph.edges().len());
+
+        // 存在しないポートへの接続はエラー
+        let mut invalid = description.clone();
+        invalid.edges[1].tap.port = 2;
+        assert!(invalid.build(&mut AudioGraph::new()).is_err());
+    }
+}
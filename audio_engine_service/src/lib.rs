@@ -1,4 +1,21 @@
+pub mod backend;
+pub mod command;
+pub mod graph_dsl;
+pub mod graph_file;
 mod init;
+pub mod midi;
+pub mod node_factory;
+pub mod notifier;
+#[cfg(feature = "osc")]
+pub mod osc;
+pub mod preset;
+pub mod recorder;
+#[cfg(feature = "scripting")]
+pub mod script;
 pub mod service;
+pub mod stats;
+pub mod watchdog;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 pub use init::init;
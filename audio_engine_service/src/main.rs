@@ -1,11 +1,334 @@
-//! 非ブロッキングストリームの構築と使用例のデモ。
+//! 音声グラフの記述ファイルを読み込んで再生するコマンドラインツールです。
 //!
-//! 入力デバイスの音声を出力デバイスの1ch目にルーティングし、2ch目は0で埋める設定です。
+//! ```text
+//! audio_engine_service [オプション] [グラフの記述ファイル]
+//!
+//! オプション:
+//!   --list-devices      ホスト API とデバイスの一覧を表示して終了する
+//!   --host-api <番号>   使うホスト API
+//!   --device <番号>     出力デバイス
+//!   --output-only       入力デバイスを開かない
+//!   --no-limiter        出力ノードの安全のためのリミッターを有効にしない
+//!   --midi <番号>       MIDI 入力ポート（midi フィーチャーが有効な場合）
+//!   --midi-target <ノード>  MIDI 入力のノートイベントを送るノード
+//! ```
+//!
+//! 記述ファイルの形式は graph_file モジュール（JSON / YAML）と
+//! graph_dsl モジュール（拡張子 .patch のテキスト形式）を参照してください。
+//! `scripting` フィーチャーが有効な場合は、拡張子 .rhai のスクリプト（script モジュール）も読み込めます。
+//! 指定しない場合は、入力をそのまま出力し、サイン波を重ねるデモを再生します。
+//! 再生中は標準入力からコマンドを受け付けます（help で一覧を表示）。
 //! フィードバックに注意してください。
 
 extern crate portaudio;
 
+use std::io::BufRead;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+#[cfg(feature = "scripting")]
+use std::time::Instant;
+
+use audio_engine_core::audio_graph::NodeId;
+use audio_engine_core::rt_log::RtLogger;
+use audio_engine_service::backend::{AudioBackend, PortAudioBackend};
+use audio_engine_service::command::GraphCommand;
+use audio_engine_service::graph_file::{GraphDescription, LoadedGraph};
+#[cfg(feature = "midi")]
+use audio_engine_service::midi::MidiService;
+use audio_engine_service::recorder::RecordSource;
+#[cfg(feature = "scripting")]
+use audio_engine_service::script::ScriptHost;
+use audio_engine_service::service::{AudioEngineService, EngineEvent};
+
+/// 記述ファイルを指定しない場合に再生するデモの音声グラフ
+const DEMO_GRAPH: &str = r#"{
+    "nodes": [
+        { "name": "in", "kind": "input" },
+        { "name": "sine", "kind": "sine" },
+        { "name": "out", "kind": "output" }
+    ],
+    "edges": [["in", "out"], ["sine", "out"]],
+    "input": "in",
+    "output": "out"
+}"#;
+
+/// 標準入力のコマンドの一覧
+const HELP: &str = "\
+コマンド:
+  set <ノード> <パラメーター> <値>  パラメーターを設定する
+  connect <ノード> <ノード>         エッジを追加する
+  disconnect <ノード> <ノード>      エッジを削除する
+  stats                             ストリームの統計情報を表示する
+  record <パス> [output|input|both] 録音を開始する（既定は出力）
+  record stop                       録音を終了する
+  stop                              再生を停止して終了する
+ノードは記述ファイルの名前か、ノード ID で指定します。";
+
+/// コマンドライン引数
+#[derive(Default)]
+struct Options {
+    /// デバイスの一覧を表示して終了する
+    list_devices: bool,
+    /// 使うホスト API
+    host_api: Option<usize>,
+    /// 出力デバイス
+    device: Option<usize>,
+    /// 入力デバイスを開かない
+    output_only: bool,
+    /// 安全のためのリミッターを有効にしない
+    no_limiter: bool,
+    /// グラフの記述ファイル
+    graph_file: Option<String>,
+    /// MIDI 入力ポート
+    #[cfg(feature = "midi")]
+    midi_port: Option<usize>,
+    /// MIDI 入力のノートイベントを送るノード
+    #[cfg(feature = "midi")]
+    midi_target: Option<String>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+        let number = |name: &str, value: Option<String>| {
+            value
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| format!("{} には番号を指定してください", name))
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--list-devices" => options.list_devices = true,
+                "--host-api" => options.host_api = Some(number(&arg, args.next())?),
+                "--device" => options.device = Some(number(&arg, args.next())?),
+                "--output-only" => options.output_only = true,
+                "--no-limiter" => options.no_limiter = true,
+                #[cfg(feature = "midi")]
+                "--midi" => options.midi_port = Some(number(&arg, args.next())?),
+                #[cfg(feature = "midi")]
+                "--midi-target" => {
+                    options.midi_target = Some(
+                        args.next()
+                            .ok_or("--midi-target にはノードを指定してください")?,
+                    )
+                }
+                _ if arg.starts_with("--") => return Err(format!("不明なオプションです: {}", arg)),
+                _ => options.graph_file = Some(arg),
+            }
+        }
+        Ok(options)
+    }
+}
+
 fn main() {
-    let _service = audio_engine_service::init();
-    loop {}
+    // オーディオスレッドからのログを標準エラー出力に書き出す
+    let _logger = RtLogger::start();
+    let result = Options::parse(std::env::args().skip(1)).and_then(run);
+    if let Err(e) = result {
+        eprintln!("エラー: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// ホスト API とデバイスの一覧を表示する
+fn list_devices() -> Result<(), String> {
+    for host_api in PortAudioBackend::host_apis()? {
+        println!(
+            "ホスト API {}: {} ({})",
+            host_api.index, host_api.name, host_api.kind
+        );
+        for device in PortAudioBackend::devices(Some(host_api.index))? {
+            println!(
+                "  デバイス {}: {} (入力 {}ch, 出力 {}ch, {} Hz)",
+                device.index,
+                device.name,
+                device.max_input_channels,
+                device.max_output_channels,
+                device.default_sample_rate
+            );
+        }
+    }
+    #[cfg(feature = "midi")]
+    for (index, name) in MidiService::ports()?.iter().enumerate() {
+        println!("MIDI 入力ポート {}: {}", index, name);
+    }
+    Ok(())
+}
+
+/// ノードを名前か ID で指定した文字列から、ノードの ID を取得する
+fn node_id(loaded: &LoadedGraph, name: &str) -> Result<NodeId, String> {
+    loaded
+        .node_id(name)
+        .or_else(|e| name.parse::<usize>().map(NodeId::from_raw).map_err(|_| e))
+}
+
+fn run(options: Options) -> Result<(), String> {
+    if options.list_devices {
+        return list_devices();
+    }
+
+    let mut backend = if options.output_only {
+        PortAudioBackend::output_only()
+    } else {
+        PortAudioBackend::new()
+    };
+    if let Some(host_api) = options.host_api {
+        backend.set_host_api(host_api);
+    }
+    if let Some(device) = options.device {
+        backend.set_output_device(device)?;
+    }
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    if options.no_limiter {
+        service.set_safety_limiter(None);
+    }
+
+    #[cfg(feature = "scripting")]
+    let mut script = None;
+    let loaded = match &options.graph_file {
+        #[cfg(feature = "scripting")]
+        Some(path) if path.ends_with(".rhai") => {
+            let source = std::fs::read_to_string(path)
+                .map_err(|e| format!("スクリプトを読み込めませんでした: {} ({})", path, e))?;
+            let mut host = ScriptHost::new(&source, service.command_sender())?;
+            host.run(service.get_mut_audio_graph())?;
+            let (input_id, output_id) = host
+                .endpoints()
+                .ok_or("スクリプトで set_endpoints を呼び出してください")?;
+            script = Some(host);
+            LoadedGraph {
+                node_ids: Default::default(),
+                input_id,
+                output_id,
+            }
+        }
+        Some(path) => GraphDescription::load(path)?.build(service.get_mut_audio_graph())?,
+        None => GraphDescription::from_json(DEMO_GRAPH)?.build(service.get_mut_audio_graph())?,
+    };
+    #[cfg(feature = "midi")]
+    let _midi = match options.midi_port {
+        Some(port) => {
+            let midi = MidiService::open(port, service.midi_queue())?;
+            println!("MIDI 入力を開きました: {}", midi.port_name());
+            Some(midi)
+        }
+        None => None,
+    };
+    #[cfg(feature = "midi")]
+    if let Some(target) = &options.midi_target {
+        service.set_midi_target(Some(node_id(&loaded, target)?));
+    }
+    service.start_playback(loaded.input_id, loaded.output_id)?;
+    println!("再生を開始しました。help でコマンドの一覧を表示します。");
+
+    // 標準入力は専用のスレッドで読み、メインスレッドではサービスのイベントとコマンドを処理する
+    let (line_sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if line_sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    #[cfg(feature = "scripting")]
+    let mut last_update = Instant::now();
+    loop {
+        match lines.recv_timeout(Duration::from_millis(10)) {
+            Ok(line) => match execute(&line, &mut service, &loaded) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(e) => eprintln!("エラー: {}", e),
+            },
+            // 標準入力が閉じられた場合は、コマンドを受け付けずに再生を続ける
+            Err(RecvTimeoutError::Disconnected) => thread::sleep(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(host) = script.as_mut() {
+            let now = Instant::now();
+            // エラーが続けて表示されないよう、失敗したスクリプトはそれ以上実行しない
+            if let Err(e) = host.update(now - last_update) {
+                eprintln!("スクリプトのエラー: {}", e);
+                script = None;
+            }
+            last_update = now;
+        }
+        if let Some(event) = service.poll_event() {
+            println!("ストリームのイベント: {:?}", event);
+        }
+        for event in service.poll_events() {
+            // ピークレベルは頻繁に届くため表示しない
+            if !matches!(event, EngineEvent::PeakLevels { .. }) {
+                println!("エンジンのイベント: {:?}", event);
+            }
+        }
+        for e in service.process_commands() {
+            eprintln!("エラー: {}", e);
+        }
+    }
+
+    if service.is_recording() {
+        let summary = service.stop_recording()?;
+        println!("録音を終了しました: {} フレーム", summary.num_frames);
+    }
+    service.stop_playback()
+}
+
+/// 標準入力のコマンドを実行する
+///
+/// # 戻り値
+/// * 終了する場合は true
+fn execute(
+    line: &str,
+    service: &mut AudioEngineService,
+    loaded: &LoadedGraph,
+) -> Result<bool, String> {
+    let node_id = |name: &str| node_id(loaded, name);
+    let sender = service.command_sender();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        [] => {}
+        ["help"] => println!("{}", HELP),
+        ["set", node, parameter_id, value] => sender.send(GraphCommand::SetParameter {
+            node_id: node_id(node)?,
+            parameter_id: parameter_id.to_string(),
+            value: value
+                .parse()
+                .map_err(|_| format!("値が不正です: {}", value))?,
+        })?,
+        ["connect", from, to] => sender.send(GraphCommand::Connect {
+            from: node_id(from)?,
+            to: node_id(to)?,
+        })?,
+        ["disconnect", from, to] => sender.send(GraphCommand::Disconnect {
+            from: node_id(from)?,
+            to: node_id(to)?,
+        })?,
+        ["stats"] => println!("{:?}", service.stats()),
+        ["record", "stop"] => {
+            let summary = service.stop_recording()?;
+            println!(
+                "録音を終了しました: {} フレーム（欠落 {} サンプル）",
+                summary.num_frames, summary.dropped_samples
+            );
+        }
+        ["record", path] => {
+            service.start_recording(path, RecordSource::Output)?;
+            println!("録音を開始しました: {}", path);
+        }
+        ["record", path, source] => {
+            let source = match *source {
+                "output" => RecordSource::Output,
+                "input" => RecordSource::Input,
+                "both" => RecordSource::InputAndOutput,
+                _ => return Err(format!("不明な録音の信号です: {}", source)),
+            };
+            service.start_recording(path, source)?;
+            println!("録音を開始しました: {}", path);
+        }
+        ["stop"] => return Ok(true),
+        _ => return Err(format!("不明なコマンドです: {}（help で一覧を表示）", line)),
+    }
+    Ok(false)
 }
@@ -0,0 +1,311 @@
+//! MIDI 機器からの入力を音声グラフに送る機能です。
+//!
+//! MIDI の受信スレッドは受け取ったメッセージを受信時刻とともに MidiQueue に入れ、
+//! オーディオコールバックがブロックの先頭で取り出して、対象ノードにノートイベントとして送ります。
+//! 受信時刻の間隔を保つため、イベントは 1 ブロック分遅らせてブロック内に配置します。
+//!
+//! `midi` フィーチャーが有効な場合は、midir で MIDI 入力ポートを開く MidiService を使えます。
+
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use audio_engine_core::midi::{
+    pitch_bend_to_semitones, NoteEvent, NoteExpression, MPE_PITCH_BEND_RANGE, MPE_TIMBRE_CC,
+};
+
+/// キューの容量（メッセージ数、2 の累乗）
+const QUEUE_CAPACITY: usize = 1024;
+
+/// MIDI メッセージをノートイベントに変換する
+///
+/// ベロシティ 0 のノートオンはノートオフとして扱います。タイミングは 0 です。
+/// MPE の表現として、ピッチベンド（範囲は MPE_PITCH_BEND_RANGE）とチャンネルプレッシャーはチャンネル全体、
+/// ポリフォニックキープレッシャーはノートごと、MPE_TIMBRE_CC のコントロールチェンジは音色として扱います。
+///
+/// # 戻り値
+/// * ノートと表現以外のメッセージの場合は None
+pub fn parse_note_event(message: &[u8]) -> Option<NoteEvent> {
+    let [status, data1, ..] = *message else {
+        return None;
+    };
+    let channel = status & 0x0f;
+    let data1 = data1 & 0x7f;
+    let data2 = message.get(2).map_or(0, |data2| data2 & 0x7f);
+    let expression = |note, expression| {
+        Some(NoteEvent::Expression {
+            timing: 0,
+            channel,
+            note,
+            expression,
+        })
+    };
+    match status & 0xf0 {
+        0x80 | 0x90 if message.len() < 3 => None,
+        0x90 if data2 > 0 => Some(NoteEvent::NoteOn {
+            timing: 0,
+            channel,
+            note: data1,
+            velocity: data2 as f32 / 127.0,
+        }),
+        0x80 | 0x90 => Some(NoteEvent::NoteOff {
+            timing: 0,
+            channel,
+            note: data1,
+            velocity: data2 as f32 / 127.0,
+        }),
+        0xa0 => expression(Some(data1), NoteExpression::Pressure(data2 as f32 / 127.0)),
+        0xb0 if data1 == MPE_TIMBRE_CC => {
+            expression(None, NoteExpression::Timbre(data2 as f32 / 127.0))
+        }
+        0xd0 => expression(None, NoteExpression::Pressure(data1 as f32 / 127.0)),
+        0xe0 => {
+            let value = ((data2 as u16) << 7) | data1 as u16;
+            expression(
+                None,
+                NoteExpression::PitchBend(pitch_bend_to_semitones(value, MPE_PITCH_BEND_RANGE)),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// キューの 1 要素
+struct MidiSlot {
+    /// 受信時刻（キューの作成からのマイクロ秒）
+    time_us: AtomicU64,
+    /// MIDI メッセージ（先頭の 3 バイト）
+    message: AtomicU32,
+}
+
+/// MIDI の受信スレッドからオーディオコールバックにメッセージを受け渡すキュー
+///
+/// 取り出し（オーディオコールバック）はロックを伴いません。
+/// 複数の MIDI ポートから入れられるよう、入れる側だけを Mutex で直列化します。
+pub struct MidiQueue {
+    slots: Box<[MidiSlot]>,
+    /// 入れたメッセージ数の累計
+    write_pos: AtomicUsize,
+    /// 取り出したメッセージ数の累計
+    read_pos: AtomicUsize,
+    /// 入れる側の排他制御
+    producer: Mutex<()>,
+    /// 受信時刻の基準
+    epoch: Instant,
+}
+
+impl MidiQueue {
+    pub fn new() -> Self {
+        Self {
+            slots: (0..QUEUE_CAPACITY)
+                .map(|_| MidiSlot {
+                    time_us: AtomicU64::new(0),
+                    message: AtomicU32::new(0),
+                })
+                .collect(),
+            write_pos: AtomicUsize::new(0),
+            read_pos: AtomicUsize::new(0),
+            producer: Mutex::new(()),
+            epoch: Instant::now(),
+        }
+    }
+
+    /// キューの作成からの経過時間（マイクロ秒）
+    pub fn now_us(&self) -> u64 {
+        self.epoch.elapsed().as_micros() as u64
+    }
+
+    /// 受信したメッセージを現在時刻とともに入れる
+    ///
+    /// # 戻り値
+    /// * キューがいっぱいでメッセージを捨てた場合は false
+    pub fn push(&self, message: &[u8]) -> bool {
+        self.push_at(self.now_us(), message)
+    }
+
+    /// 受信時刻を指定してメッセージを入れる
+    pub fn push_at(&self, time_us: u64, message: &[u8]) -> bool {
+        let _guard = self.producer.lock().unwrap_or_else(|e| e.into_inner());
+        let write_pos = self.write_pos.load(Ordering::Relaxed);
+        if write_pos - self.read_pos.load(Ordering::Acquire) >= QUEUE_CAPACITY {
+            return false;
+        }
+        let mut packed = [0u8; 4];
+        for (dst, src) in packed.iter_mut().zip(message.iter().take(3)) {
+            *dst = *src;
+        }
+        let slot = &self.slots[write_pos % QUEUE_CAPACITY];
+        slot.time_us.store(time_us, Ordering::Relaxed);
+        slot.message
+            .store(u32::from_le_bytes(packed), Ordering::Relaxed);
+        self.write_pos.store(write_pos + 1, Ordering::Release);
+        true
+    }
+
+    /// たまったノートイベントを全て取り出す
+    ///
+    /// # 引数
+    /// * `now_us` - ブロックの処理を始めた時刻（now_us で取得する）
+    /// * `block_us` - 1 ブロックの長さ（マイクロ秒）
+    /// * `sample_rate` - イベントのタイミングを表すサンプリングレート
+    /// * `f` - タイミングを設定したイベントを受け取る関数
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    /// 取り出しは 1 スレッドからのみ行ってください。
+    pub fn drain(
+        &self,
+        now_us: u64,
+        block_us: u64,
+        sample_rate: f64,
+        mut f: impl FnMut(NoteEvent),
+    ) {
+        let read_pos = self.read_pos.load(Ordering::Relaxed);
+        let write_pos = self.write_pos.load(Ordering::Acquire);
+        for pos in read_pos..write_pos {
+            let slot = &self.slots[pos % QUEUE_CAPACITY];
+            let time_us = slot.time_us.load(Ordering::Relaxed);
+            let message = slot.message.load(Ordering::Relaxed).to_le_bytes();
+            if let Some(event) = parse_note_event(&message) {
+                // 1 ブロック前の時刻を基準に配置する（それより古いイベントはブロックの先頭）
+                let offset_us = block_us.saturating_sub(now_us.saturating_sub(time_us));
+                let timing = (offset_us as f64 * sample_rate / 1_000_000.0) as u32;
+                f(event.with_timing(timing));
+            }
+        }
+        self.read_pos.store(write_pos, Ordering::Release);
+    }
+}
+
+impl Default for MidiQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "midi")]
+pub use input::MidiService;
+
+#[cfg(feature = "midi")]
+mod input {
+    use std::sync::Arc;
+
+    use midir::{MidiInput, MidiInputConnection};
+
+    use super::MidiQueue;
+
+    /// MIDI 入力ポートから受け取ったメッセージを MidiQueue に入れるサービス
+    ///
+    /// 破棄するとポートを閉じます。
+    pub struct MidiService {
+        /// 開いているポートの名前
+        port_name: String,
+        /// ポートとの接続（破棄すると閉じる）
+        _connection: MidiInputConnection<()>,
+    }
+
+    impl MidiService {
+        /// MIDI 入力ポートの名前の一覧を取得する
+        pub fn ports() -> Result<Vec<String>, String> {
+            let input = MidiInput::new("audio_engine_service").map_err(|e| e.to_string())?;
+            input
+                .ports()
+                .iter()
+                .map(|port| input.port_name(port).map_err(|e| e.to_string()))
+                .collect()
+        }
+
+        /// MIDI 入力ポートを開き、受け取ったメッセージをキューに入れる
+        ///
+        /// # 引数
+        /// * `port_index` - ports で取得した一覧の番号
+        /// * `queue` - メッセージを入れるキュー（AudioEngineService::midi_queue で取得する）
+        ///
+        /// # 実装時の注意
+        /// バックエンドによってタイムスタンプの基準が異なるため、受信時刻はキュー側の時計で記録します。
+        pub fn open(port_index: usize, queue: Arc<MidiQueue>) -> Result<Self, String> {
+            let input = MidiInput::new("audio_engine_service").map_err(|e| e.to_string())?;
+            let port = input
+                .ports()
+                .into_iter()
+                .nth(port_index)
+                .ok_or_else(|| format!("MIDI 入力ポートが見つかりません: {}", port_index))?;
+            let port_name = input.port_name(&port).map_err(|e| e.to_string())?;
+            let connection = input
+                .connect(
+                    &port,
+                    "audio_engine_service-in",
+                    move |_timestamp, message, _| {
+                        queue.push(message);
+                    },
+                    (),
+                )
+                .map_err(|e| e.to_string())?;
+            Ok(Self {
+                port_name,
+                _connection: connection,
+            })
+        }
+
+        /// 開いているポートの名前
+        pub fn port_name(&self) -> &str {
+            &self.port_name
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_places_events_within_block() {
+        assert_eq!(
+            parse_note_event(&[0x91, 60, 127]),
+            Some(NoteEvent::NoteOn {
+                timing: 0,
+                channel: 1,
+                note: 60,
+                velocity: 1.0
+            })
+        );
+        // ベロシティ 0 のノートオンはノートオフ
+        assert!(matches!(
+            parse_note_event(&[0x90, 60, 0]),
+            Some(NoteEvent::NoteOff { .. })
+        ));
+        assert_eq!(parse_note_event(&[0xb0, 7, 100]), None);
+        assert_eq!(parse_note_event(&[0x90]), None);
+        // MPE の表現はチャンネル全体（ポリフォニックキープレッシャーはノートごと）
+        assert_eq!(
+            parse_note_event(&[0xe2, 0, 0x60]),
+            Some(NoteEvent::Expression {
+                timing: 0,
+                channel: 2,
+                note: None,
+                expression: NoteExpression::PitchBend(24.0)
+            })
+        );
+        assert!(matches!(
+            parse_note_event(&[0xa0, 60, 127]),
+            Some(NoteEvent::Expression {
+                note: Some(60),
+                expression: NoteExpression::Pressure(1.0),
+                ..
+            })
+        ));
+
+        let queue = MidiQueue::new();
+        // 1 ブロック（10ms）前より古いイベントは先頭、それ以降は受信時刻の間隔を保って配置する
+        queue.push_at(0, &[0x90, 60, 100]);
+        queue.push_at(15_000, &[0xb0, 7, 100]);
+        queue.push_at(15_000, &[0x80, 60, 0]);
+        queue.push_at(19_000, &[0x90, 64, 100]);
+        let mut timings = Vec::new();
+        queue.drain(20_000, 10_000, 1000.0, |event| timings.push(event.timing()));
+        assert_eq!(timings, vec![0, 5, 9]);
+
+        // 取り出したイベントは再び取り出されない
+        queue.drain(30_000, 10_000, 1000.0, |_| panic!());
+    }
+}
@@ -0,0 +1,98 @@
+//! 種類の名前からノードを作成します。
+//!
+//! OSC や WebSocket などの外部からの制御や、グラフの記述ファイルから、名前でノードを追加するために使います。
+
+use audio_engine_core::audio_graph::{AudioGraph, AudioGraphNode, NodeId};
+use audio_engine_core::nodes::{
+    ChordGenerator, Chorus, ClipDetector, CombFilter, CombMode, Crossfader, EnvelopeFollower, Eq3,
+    Flanger, FmSynth, GainProcessor, Granulator, ImpulseGenerator, InputNode, LfoGenerator,
+    LoudnessMeter, OutputNode, Phaser, PitchDetector, RingModulator, SawGenerator,
+    SchroederAllpass, SineGenerator, SpectralFreeze, StateVariableFilter, TestSignal, Vocoder,
+};
+
+/// 名前で作成できるノードの種類
+pub const NODE_KINDS: &[&str] = &[
+    "input",
+    "output",
+    "sine",
+    "saw",
+    "impulse",
+    "gain",
+    "chorus",
+    "flanger",
+    "phaser",
+    "eq3",
+    "svf",
+    "comb_filter",
+    "allpass",
+    "spectral_freeze",
+    "crossfader",
+    "envelope_follower",
+    "pitch_detector",
+    "loudness_meter",
+    "clip_detector",
+    "lfo",
+    "ring_modulator",
+    "vocoder",
+    "granulator",
+    "fm_synth",
+    "chord_generator",
+    "test_signal",
+];
+
+/// 種類の名前からノードを作成する
+///
+/// # 引数
+/// * `kind` - ノードの種類（NODE_KINDS のいずれか）
+///
+/// # 戻り値
+/// * 作成したノード。対応していない種類の場合は `Err` でエラーメッセージを返す
+pub fn create_node(kind: &str) -> Result<Box<dyn AudioGraphNode>, String> {
+    Ok(match kind {
+        "input" => Box::new(InputNode::new()),
+        "output" => Box::new(OutputNode::new()),
+        "sine" => Box::new(SineGenerator::new()),
+        "saw" => Box::new(SawGenerator::new()),
+        "impulse" => Box::new(ImpulseGenerator::new()),
+        "gain" => Box::new(GainProcessor::new()),
+        "chorus" => Box::new(Chorus::new()),
+        "flanger" => Box::new(Flanger::new()),
+        "phaser" => Box::new(Phaser::new()),
+        "eq3" => Box::new(Eq3::new()),
+        "svf" => Box::new(StateVariableFilter::new()),
+        "comb_filter" => Box::new(CombFilter::new(CombMode::Feedback)),
+        "allpass" => Box::new(SchroederAllpass::new()),
+        "spectral_freeze" => Box::new(SpectralFreeze::new()),
+        "crossfader" => Box::new(Crossfader::new()),
+        "envelope_follower" => Box::new(EnvelopeFollower::new()),
+        "pitch_detector" => Box::new(PitchDetector::new()),
+        "loudness_meter" => Box::new(LoudnessMeter::new()),
+        "clip_detector" => Box::new(ClipDetector::new()),
+        "lfo" => Box::new(LfoGenerator::new()),
+        "ring_modulator" => Box::new(RingModulator::new()),
+        "vocoder" => Box::new(Vocoder::new()),
+        "granulator" => Box::new(Granulator::new()),
+        "fm_synth" => Box::new(FmSynth::new()),
+        "chord_generator" => Box::new(ChordGenerator::new()),
+        "test_signal" => Box::new(TestSignal::new()),
+        _ => return Err(format!("対応していないノードの種類です: {}", kind)),
+    })
+}
+
+/// 種類の名前からノードを作成して音声グラフに追加し、種類を記録する
+///
+/// 記録した種類は、音声グラフを記述ファイルに書き出すとき（GraphDescription::capture）に使います。
+///
+/// # 戻り値
+/// * 追加したノードの ID。対応していない種類の場合は `Err` でエラーメッセージを返す
+pub fn add_node(audio_graph: &mut AudioGraph, kind: &str) -> Result<NodeId, String> {
+    let node = create_node(kind)?;
+    // create_node が成功した種類は必ず NODE_KINDS に含まれる
+    let kind = NODE_KINDS
+        .iter()
+        .find(|k| **k == kind)
+        .ok_or_else(|| format!("対応していないノードの種類です: {}", kind))?;
+    let node_id = audio_graph.add_node(node);
+    audio_graph.set_node_kind(node_id, kind)?;
+    Ok(node_id)
+}
@@ -0,0 +1,89 @@
+//! 音声エンジンのイベントを、専用のスレッドからコールバックで通知する機能です。
+//!
+//! AudioEngineService::spawn_event_notifier で開始すると、オーディオスレッドや音声グラフのノードから
+//! 通知されたイベントを定期的に取り出し、渡した関数を呼び出します。
+//! 関数はオーディオスレッドではなく通知用のスレッドで呼び出されるため、ロックや UI の更新を行えます。
+//!
+//! ストリームが動作中のはずなのにオーディオコールバックが STALL_TIMEOUT_MS 以上呼び出されない場合は、
+//! デバイスの切断などでストリームが止まったとみなして EngineEvent::StreamStopped を通知します。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use audio_engine_core::event::{EngineEvent, EventQueue};
+
+use crate::stats::StatsRecorder;
+use crate::watchdog::StallDetector;
+
+/// イベントを確認する間隔（ミリ秒）
+const NOTIFY_INTERVAL_MS: u64 = 10;
+
+/// ストリームが止まったとみなす、オーディオコールバックが呼び出されない時間（ミリ秒）
+pub const STALL_TIMEOUT_MS: u64 = 500;
+
+/// イベントを通知するスレッド
+///
+/// drop するとスレッドを停止します。停止する前に、残っているイベントを通知します。
+pub struct EventNotifier {
+    /// スレッドが動作中かどうか
+    running: Arc<AtomicBool>,
+    /// イベントを通知するスレッド
+    thread: Option<JoinHandle<()>>,
+}
+
+impl EventNotifier {
+    /// イベントを通知するスレッドを開始
+    ///
+    /// # 引数
+    /// * `events` - イベントを取り出すキュー
+    /// * `stats` - オーディオコールバックの呼び出し回数を記録しているレコーダー
+    /// * `playing` - ストリームが動作中のはずかどうか
+    /// * `callback` - イベントを受け取る関数
+    pub(crate) fn start(
+        events: Arc<EventQueue>,
+        stats: Arc<StatsRecorder>,
+        playing: Arc<AtomicBool>,
+        mut callback: impl FnMut(EngineEvent) + Send + 'static,
+    ) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = running.clone();
+            thread::Builder::new()
+                .name("event-notifier".to_string())
+                .spawn(move || {
+                    let timeout = Duration::from_millis(STALL_TIMEOUT_MS);
+                    let mut detector = StallDetector::new(stats.snapshot().callback_count);
+                    while running.load(Ordering::Acquire) {
+                        events.drain(&mut callback);
+
+                        // 再生中にコールバックの呼び出し回数が増えなくなったら、一度だけ通知する
+                        let count = stats.snapshot().callback_count;
+                        if detector
+                            .check(count, playing.load(Ordering::Acquire), timeout)
+                            .is_some()
+                        {
+                            callback(EngineEvent::StreamStopped);
+                        }
+                        thread::sleep(Duration::from_millis(NOTIFY_INTERVAL_MS));
+                    }
+                    events.drain(&mut callback);
+                })
+                .expect("イベントを通知するスレッドを開始できませんでした")
+        };
+        Self {
+            running,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for EventNotifier {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
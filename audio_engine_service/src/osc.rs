@@ -0,0 +1,445 @@
+//! OSC（Open Sound Control）で音声グラフを操作するサーバーです。
+//!
+//! `osc` フィーチャーを有効にすると使えます。UDP で受け取った OSC メッセージを GraphCommand に変換し、
+//! AudioEngineService のコマンドキューに送ります。TouchOSC / Max / Pd などから、パッチをライブで操作できます。
+//!
+//! | アドレス | 引数 | 操作 |
+//! | --- | --- | --- |
+//! | `/node/{id}/param/{name}` | 値（数値 1 つ） | ノードのパラメーターを設定 |
+//! | `/graph/connect` | 接続元 ID, 接続先 ID | エッジを追加 |
+//! | `/graph/disconnect` | 接続元 ID, 接続先 ID | エッジを削除 |
+//! | `/macro/{番号}` | 値（0〜1 の数値 1 つ） | マクロの値を設定 |
+//!
+//! Pd のように数値を float で送るクライアントにも対応するため、ID は整数値の float も受け付けます。
+
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use audio_engine_core::audio_graph::NodeId;
+
+use crate::command::{CommandSender, GraphCommand};
+
+/// 受信できる OSC パケットの最大サイズ
+const MAX_PACKET_SIZE: usize = 65536;
+
+/// 停止の要求を確認する間隔（ミリ秒）
+const POLL_INTERVAL_MS: u64 = 50;
+
+/// OSC メッセージの引数
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(String),
+    Bool(bool),
+}
+
+impl OscArg {
+    /// 数値として取得する
+    fn as_f32(&self) -> Option<f32> {
+        match self {
+            OscArg::Int(v) => Some(*v as f32),
+            OscArg::Float(v) => Some(*v),
+            OscArg::Long(v) => Some(*v as f32),
+            OscArg::Double(v) => Some(*v as f32),
+            OscArg::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+            OscArg::String(_) => None,
+        }
+    }
+
+    /// ノード ID として取得する（0 以上の整数値のみ）
+    fn as_id(&self) -> Option<NodeId> {
+        let raw = match self {
+            OscArg::Int(v) => usize::try_from(*v).ok(),
+            OscArg::Long(v) => usize::try_from(*v).ok(),
+            OscArg::Float(v) if *v >= 0.0 && v.fract() == 0.0 => Some(*v as usize),
+            OscArg::Double(v) if *v >= 0.0 && v.fract() == 0.0 => Some(*v as usize),
+            _ => None,
+        };
+        raw.map(NodeId::from_raw)
+    }
+}
+
+/// OSC メッセージ
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    /// アドレス（例: "/graph/connect"）
+    pub address: String,
+    /// 引数
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    /// 音声グラフへの操作に変換する
+    ///
+    /// # 戻り値
+    /// * 対応するアドレスと引数の場合は GraphCommand、そうでない場合は `Err` でエラーメッセージを返す
+    pub fn to_command(&self) -> Result<GraphCommand, String> {
+        let invalid_args = || format!("{} の引数が不正です: {:?}", self.address, self.args);
+        let parts: Vec<&str> = self.address.split('/').skip(1).collect();
+        match parts.as_slice() {
+            ["node", node_id, "param", parameter_id] => {
+                let node_id = node_id
+                    .parse()
+                    .map(NodeId::from_raw)
+                    .map_err(|_| format!("ノード ID が不正です: {}", node_id))?;
+                let value = match self.args.as_slice() {
+                    [arg] => arg.as_f32().ok_or_else(invalid_args)?,
+                    _ => return Err(invalid_args()),
+                };
+                Ok(GraphCommand::SetParameter {
+                    node_id,
+                    parameter_id: parameter_id.to_string(),
+                    value,
+                })
+            }
+            ["graph", operation @ ("connect" | "disconnect")] => {
+                let (from, to) = match self.args.as_slice() {
+                    [from, to] => (
+                        from.as_id().ok_or_else(invalid_args)?,
+                        to.as_id().ok_or_else(invalid_args)?,
+                    ),
+                    _ => return Err(invalid_args()),
+                };
+                Ok(if *operation == "connect" {
+                    GraphCommand::Connect { from, to }
+                } else {
+                    GraphCommand::Disconnect { from, to }
+                })
+            }
+            ["macro", index] => {
+                let index = index
+                    .parse()
+                    .map_err(|_| format!("マクロの番号が不正です: {}", index))?;
+                let value = match self.args.as_slice() {
+                    [arg] => arg.as_f32().ok_or_else(invalid_args)?,
+                    _ => return Err(invalid_args()),
+                };
+                Ok(GraphCommand::SetMacro { index, value })
+            }
+            _ => Err(format!("対応していないアドレスです: {}", self.address)),
+        }
+    }
+}
+
+/// OSC パケット（メッセージまたはバンドル）をデコードする
+///
+/// バンドルの場合は、含まれるメッセージを順に返します（タイムタグは無視して即座に適用します）。
+pub fn decode_packet(packet: &[u8]) -> Result<Vec<OscMessage>, String> {
+    let mut messages = Vec::new();
+    decode_into(packet, &mut messages)?;
+    Ok(messages)
+}
+
+fn decode_into(packet: &[u8], messages: &mut Vec<OscMessage>) -> Result<(), String> {
+    let mut reader = Reader {
+        data: packet,
+        pos: 0,
+    };
+    if packet.starts_with(b"#bundle\0") {
+        reader.pos = 8;
+        // タイムタグ
+        reader.take(8)?;
+        while reader.pos < packet.len() {
+            let size = reader.read_i32()?;
+            let size = usize::try_from(size).map_err(|_| "バンドルの要素のサイズが不正です")?;
+            decode_into(reader.take(size)?, messages)?;
+        }
+        return Ok(());
+    }
+
+    let address = reader.read_string()?;
+    if !address.starts_with('/') {
+        return Err(format!("アドレスが不正です: {}", address));
+    }
+    // 型タグを省略した古いクライアントのメッセージは、引数なしとして扱う
+    let type_tags = if reader.pos < packet.len() {
+        reader.read_string()?
+    } else {
+        ",".to_string()
+    };
+    let Some(type_tags) = type_tags.strip_prefix(',') else {
+        return Err(format!("型タグが不正です: {}", type_tags));
+    };
+    let mut args = Vec::with_capacity(type_tags.len());
+    for tag in type_tags.chars() {
+        args.push(match tag {
+            'i' => OscArg::Int(reader.read_i32()?),
+            'f' => OscArg::Float(f32::from_bits(reader.read_i32()? as u32)),
+            'h' => OscArg::Long(i64::from_be_bytes(reader.take(8)?.try_into().unwrap())),
+            'd' => OscArg::Double(f64::from_be_bytes(reader.take(8)?.try_into().unwrap())),
+            's' => OscArg::String(reader.read_string()?),
+            'T' => OscArg::Bool(true),
+            'F' => OscArg::Bool(false),
+            _ => return Err(format!("対応していない型タグです: {}", tag)),
+        });
+    }
+    messages.push(OscMessage { address, args });
+    Ok(())
+}
+
+/// OSC パケットを先頭から読み進めるためのリーダー
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or("パケットが途中で終わっています")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// ヌル終端で 4 バイト境界に揃えられた文字列を読む
+    fn read_string(&mut self) -> Result<String, String> {
+        let rest = &self.data[self.pos.min(self.data.len())..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("文字列が終端されていません")?;
+        let string = std::str::from_utf8(&rest[..len])
+            .map_err(|_| "文字列が UTF-8 ではありません")?
+            .to_string();
+        // ヌル文字を含めて 4 バイト境界まで進める
+        self.take((len / 4 + 1) * 4)?;
+        Ok(string)
+    }
+}
+
+/// OSC サーバー
+///
+/// 専用のスレッドで UDP のパケットを受け取り、GraphCommand に変換して送ります。
+/// 変換できないメッセージは標準エラー出力に報告して無視します。
+/// drop するとスレッドを停止します。
+pub struct OscServer {
+    /// 待ち受けているアドレス
+    local_addr: SocketAddr,
+    /// スレッドが動作中かどうか
+    running: Arc<AtomicBool>,
+    /// パケットを受け取るスレッド
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OscServer {
+    /// 指定したアドレスで待ち受けを開始する
+    ///
+    /// # 引数
+    /// * `addr` - 待ち受けるアドレス（例: "0.0.0.0:9000"）
+    /// * `sender` - コマンドの送り先（AudioEngineService::command_sender で取得する）
+    pub fn bind(addr: impl ToSocketAddrs, sender: CommandSender) -> Result<Self, String> {
+        let socket = UdpSocket::bind(addr).map_err(|e| e.to_string())?;
+        socket
+            .set_read_timeout(Some(Duration::from_millis(POLL_INTERVAL_MS)))
+            .map_err(|e| e.to_string())?;
+        let local_addr = socket.local_addr().map_err(|e| e.to_string())?;
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread = {
+            let running = running.clone();
+            thread::Builder::new()
+                .name("osc-server".to_string())
+                .spawn(move || {
+                    let mut packet = vec![0; MAX_PACKET_SIZE];
+                    while running.load(Ordering::Acquire) {
+                        // タイムアウトした場合は停止の要求を確認して待ち受けを続ける
+                        let Ok((len, _)) = socket.recv_from(&mut packet) else {
+                            continue;
+                        };
+                        let commands: Result<Vec<_>, _> =
+                            decode_packet(&packet[..len]).and_then(|messages| {
+                                messages.iter().map(OscMessage::to_command).collect()
+                            });
+                        match commands {
+                            Ok(commands) => {
+                                for command in commands {
+                                    if sender.send(command).is_err() {
+                                        // サービスが破棄された
+                                        return;
+                                    }
+                                }
+                            }
+                            Err(e) => eprintln!("OSC メッセージを処理できませんでした: {}", e),
+                        }
+                    }
+                })
+                .map_err(|e| format!("スレッドを開始できませんでした: {}", e))?
+        };
+
+        Ok(Self {
+            local_addr,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// 待ち受けているアドレス（ポートに 0 を指定した場合に、割り当てられたポートを確認できる）
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for OscServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandQueue;
+
+    /// テスト用に OSC の文字列をエンコードする
+    fn encode_string(bytes: &mut Vec<u8>, s: &str) {
+        bytes.extend_from_slice(s.as_bytes());
+        bytes.resize((bytes.len() / 4 + 1) * 4, 0);
+    }
+
+    /// テスト用に OSC メッセージをエンコードする
+    fn encode_message(address: &str, args: &[OscArg]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        encode_string(&mut bytes, address);
+        let mut type_tags = ",".to_string();
+        let mut data = Vec::new();
+        for arg in args {
+            match arg {
+                OscArg::Int(v) => {
+                    type_tags.push('i');
+                    data.extend_from_slice(&v.to_be_bytes());
+                }
+                OscArg::Float(v) => {
+                    type_tags.push('f');
+                    data.extend_from_slice(&v.to_be_bytes());
+                }
+                OscArg::Long(v) => {
+                    type_tags.push('h');
+                    data.extend_from_slice(&v.to_be_bytes());
+                }
+                OscArg::Double(v) => {
+                    type_tags.push('d');
+                    data.extend_from_slice(&v.to_be_bytes());
+                }
+                OscArg::String(v) => {
+                    type_tags.push('s');
+                    encode_string(&mut data, v);
+                }
+                // 真偽値は型タグだけで表し、データを持たない
+                OscArg::Bool(v) => type_tags.push(if *v { 'T' } else { 'F' }),
+            }
+        }
+        encode_string(&mut bytes, &type_tags);
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    #[test]
+    fn test_decode_messages_and_bundles() {
+        let set_parameter = encode_message("/node/3/param/frequency", &[OscArg::Float(440.0)]);
+        let connect = encode_message("/graph/connect", &[OscArg::Int(1), OscArg::Float(2.0)]);
+
+        // バンドルの中のメッセージは順に取り出す
+        let mut bundle = Vec::new();
+        encode_string(&mut bundle, "#bundle");
+        bundle.extend_from_slice(&1u64.to_be_bytes());
+        for message in [&set_parameter, &connect] {
+            bundle.extend_from_slice(&(message.len() as i32).to_be_bytes());
+            bundle.extend_from_slice(message);
+        }
+        let commands: Vec<_> = decode_packet(&bundle)
+            .unwrap()
+            .iter()
+            .map(|message| message.to_command().unwrap())
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                GraphCommand::SetParameter {
+                    node_id: NodeId::from_raw(3),
+                    parameter_id: "frequency".to_string(),
+                    value: 440.0,
+                },
+                GraphCommand::Connect {
+                    from: NodeId::from_raw(1),
+                    to: NodeId::from_raw(2),
+                },
+            ]
+        );
+
+        // マクロの番号はアドレスで指定する
+        let set_macro = encode_message("/macro/2", &[OscArg::Float(0.75)]);
+        assert_eq!(
+            decode_packet(&set_macro).unwrap()[0].to_command(),
+            Ok(GraphCommand::SetMacro {
+                index: 2,
+                value: 0.75
+            })
+        );
+
+        // 64 ビットの数値や真偽値の引数も受け付ける
+        let args = vec![
+            OscArg::Long(6),
+            OscArg::Double(7.0),
+            OscArg::Bool(true),
+            OscArg::Bool(false),
+        ];
+        let message = &decode_packet(&encode_message("/graph/connect", &args)).unwrap()[0];
+        assert_eq!(message.args, args);
+        let connect_long = encode_message("/graph/connect", &args[..2]);
+        assert_eq!(
+            decode_packet(&connect_long).unwrap()[0].to_command(),
+            Ok(GraphCommand::Connect {
+                from: NodeId::from_raw(6),
+                to: NodeId::from_raw(7),
+            })
+        );
+
+        // 対応していないアドレスや引数はエラー
+        let unknown = encode_message("/graph/clear", &[]);
+        assert!(decode_packet(&unknown).unwrap()[0].to_command().is_err());
+        let invalid = encode_message("/graph/disconnect", &[OscArg::String("a".into())]);
+        assert!(decode_packet(&invalid).unwrap()[0].to_command().is_err());
+        assert!(decode_packet(&connect[..connect.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn test_server_sends_commands() {
+        let queue = CommandQueue::new();
+        let server = OscServer::bind("127.0.0.1:0", queue.sender()).unwrap();
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let message = encode_message("/graph/disconnect", &[OscArg::Int(4), OscArg::Int(5)]);
+        client.send_to(&message, server.local_addr()).unwrap();
+
+        // サーバーのスレッドがコマンドを送るまで待つ
+        for _ in 0..100 {
+            if let Some(pending) = queue.drain().next() {
+                assert_eq!(
+                    pending.command,
+                    GraphCommand::Disconnect {
+                        from: NodeId::from_raw(4),
+                        to: NodeId::from_raw(5),
+                    }
+                );
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("コマンドが届きませんでした");
+    }
+}
@@ -0,0 +1,436 @@
+//! ノードのパラメーターの値だけを名前付きのスナップショット（プリセット）として保存し、呼び出す機能です。
+//!
+//! グラフの記述ファイル（graph_file）がノードと接続の構成を扱うのに対し、プリセットは構成を変えずに
+//! パラメーターの値だけを切り替えます。ノードは記述ファイルでの名前で参照するため、同じ記述ファイルから
+//! 構築し直した音声グラフでも使えます。
+//!
+//! ```json
+//! {
+//!     "presets": [
+//!         { "name": "low", "values": { "osc": { "frequency": 220.0 }, "fader": { "position": 0.0 } } },
+//!         { "name": "high", "values": { "osc": { "frequency": 880.0 }, "fader": { "position": 1.0 } } }
+//!     ]
+//! }
+//! ```
+//!
+//! 呼び出しは PresetMorph で行い、各パラメーターを現在の値からプリセットの値まで指定した時間で変化させます。
+//! 2 つのプリセットの間を 1 つの値で行き来する場合は、Preset::resolve で変換した値を
+//! GraphCommand::SetMorphSnapshots で設定し、GraphCommand::SetMorphPosition で位置を操作します。
+//! 再生中の音声グラフのパラメーターの値は GraphCommand::GetParameters で取得します。
+
+use std::path::Path;
+use std::time::Duration;
+
+use audio_engine_core::audio_graph::NodeId;
+use serde_json::{json, Map, Value};
+
+use crate::command::{CommandSender, GraphCommand};
+use crate::graph_file::LoadedGraph;
+
+/// プリセットに含まれるパラメーターの値
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresetValue {
+    /// ノードの名前
+    pub node: String,
+    /// パラメーターの ID
+    pub parameter_id: String,
+    /// 値
+    pub value: f32,
+}
+
+/// 名前付きのパラメーターのスナップショット
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    /// プリセットの名前
+    pub name: String,
+    /// パラメーターの値
+    pub values: Vec<PresetValue>,
+}
+
+impl Preset {
+    /// パラメーターの現在の値からプリセットを作る
+    ///
+    /// # 引数
+    /// * `name` - プリセットの名前
+    /// * `parameters` - GraphCommand::GetParameters で取得したパラメーターの値
+    /// * `loaded` - ノードの名前と ID の対応（名前のないノードのパラメーターは含めない）
+    pub fn capture(name: &str, parameters: &[(NodeId, String, f32)], loaded: &LoadedGraph) -> Self {
+        let mut values: Vec<PresetValue> = parameters
+            .iter()
+            .filter_map(|(node_id, parameter_id, value)| {
+                let (node, _) = loaded.node_ids.iter().find(|(_, id)| *id == node_id)?;
+                Some(PresetValue {
+                    node: node.clone(),
+                    parameter_id: parameter_id.clone(),
+                    value: *value,
+                })
+            })
+            .collect();
+        values.sort_by(|a, b| (&a.node, &a.parameter_id).cmp(&(&b.node, &b.parameter_id)));
+        Self {
+            name: name.to_string(),
+            values,
+        }
+    }
+
+    /// ノードの名前を ID に置き換えた値の一覧（GraphCommand::SetMorphSnapshots に渡す形式）
+    ///
+    /// # 戻り値
+    /// * プリセットのノードが音声グラフにない場合は `Err` でエラーメッセージを返す
+    pub fn resolve(&self, loaded: &LoadedGraph) -> Result<Vec<(NodeId, String, f32)>, String> {
+        self.values
+            .iter()
+            .map(|value| {
+                Ok((
+                    loaded.node_id(&value.node)?,
+                    value.parameter_id.clone(),
+                    value.value,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// 複数のプリセットの保存先
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PresetBank {
+    /// 追加した順のプリセット
+    presets: Vec<Preset>,
+}
+
+impl PresetBank {
+    /// 空のプリセットの保存先を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// プリセットを追加する（同じ名前のプリセットがある場合は置き換える）
+    pub fn insert(&mut self, preset: Preset) {
+        match self.presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    /// 名前でプリセットを取得する
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// 名前でプリセットを削除する
+    ///
+    /// # 戻り値
+    /// * 削除したプリセット。存在しない場合は `None`
+    pub fn remove(&mut self, name: &str) -> Option<Preset> {
+        let index = self.presets.iter().position(|preset| preset.name == name)?;
+        Some(self.presets.remove(index))
+    }
+
+    /// プリセットの名前の一覧（追加した順）
+    pub fn names(&self) -> Vec<&str> {
+        self.presets
+            .iter()
+            .map(|preset| preset.name.as_str())
+            .collect()
+    }
+
+    /// ファイルからプリセットを読み込む
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            format!(
+                "プリセットファイルを読み込めませんでした: {:?} ({})",
+                path.as_ref(),
+                e
+            )
+        })?;
+        Self::from_json(&text)
+    }
+
+    /// プリセットをファイルに保存する
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        std::fs::write(path.as_ref(), self.to_json()).map_err(|e| {
+            format!(
+                "プリセットファイルを保存できませんでした: {:?} ({})",
+                path.as_ref(),
+                e
+            )
+        })
+    }
+
+    /// JSON の記述を解釈する
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let root: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let mut bank = Self::new();
+        for preset in root
+            .get("presets")
+            .and_then(Value::as_array)
+            .ok_or("presets がありません")?
+        {
+            let name = preset
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or("name がありません")?;
+            let mut values = Vec::new();
+            if let Some(nodes) = preset.get("values") {
+                for (node, parameters) in nodes.as_object().ok_or("values が不正です")? {
+                    for (parameter_id, value) in parameters
+                        .as_object()
+                        .ok_or_else(|| format!("ノード {} の値が不正です", node))?
+                    {
+                        let value = value.as_f64().ok_or_else(|| {
+                            format!("パラメーター {}.{} の値が不正です", node, parameter_id)
+                        })?;
+                        values.push(PresetValue {
+                            node: node.clone(),
+                            parameter_id: parameter_id.clone(),
+                            value: value as f32,
+                        });
+                    }
+                }
+            }
+            bank.insert(Preset {
+                name: name.to_string(),
+                values,
+            });
+        }
+        Ok(bank)
+    }
+
+    /// JSON の記述に変換する
+    pub fn to_json(&self) -> String {
+        let presets: Vec<Value> = self
+            .presets
+            .iter()
+            .map(|preset| {
+                let mut nodes = Map::new();
+                for value in preset.values.iter() {
+                    let parameters = nodes
+                        .entry(value.node.clone())
+                        .or_insert_with(|| Value::Object(Map::new()));
+                    parameters[value.parameter_id.as_str()] = json!(value.value);
+                }
+                json!({ "name": preset.name, "values": nodes })
+            })
+            .collect();
+        serde_json::to_string_pretty(&json!({ "presets": presets })).unwrap()
+    }
+}
+
+/// 変化させるパラメーター
+struct MorphTarget {
+    node_id: NodeId,
+    parameter_id: String,
+    /// 呼び出した時点の値
+    start: f32,
+    /// プリセットの値
+    end: f32,
+}
+
+/// プリセットの呼び出し（パラメーターを現在の値からプリセットの値まで直線的に変化させる）
+///
+/// update を呼び出すたびに、経過時間に応じた値を SetParameter コマンドで送ります。
+pub struct PresetMorph {
+    targets: Vec<MorphTarget>,
+    /// 変化にかける時間（秒）
+    duration: f64,
+    /// 呼び出しからの経過時間（秒）
+    elapsed: f64,
+}
+
+impl PresetMorph {
+    /// プリセットの呼び出しを始める
+    ///
+    /// # 引数
+    /// * `preset` - 呼び出すプリセット
+    /// * `current` - GraphCommand::GetParameters で取得した、パラメーターの現在の値
+    /// * `loaded` - ノードの名前と ID の対応
+    /// * `duration` - 変化にかける時間（0 の場合は最初の update でプリセットの値にする）
+    ///
+    /// # 戻り値
+    /// * プリセットのノードが音声グラフにない場合は `Err` でエラーメッセージを返す
+    pub fn new(
+        preset: &Preset,
+        current: &[(NodeId, String, f32)],
+        loaded: &LoadedGraph,
+        duration: Duration,
+    ) -> Result<Self, String> {
+        let mut targets = Vec::new();
+        for value in preset.values.iter() {
+            let node_id = loaded.node_id(&value.node)?;
+            // 現在の値が分からないパラメーターは、変化させずにプリセットの値にする
+            let start = current
+                .iter()
+                .find(|(id, parameter_id, _)| *id == node_id && *parameter_id == value.parameter_id)
+                .map_or(value.value, |(_, _, start)| *start);
+            targets.push(MorphTarget {
+                node_id,
+                parameter_id: value.parameter_id.clone(),
+                start,
+                end: value.value,
+            });
+        }
+        Ok(Self {
+            targets,
+            duration: duration.as_secs_f64(),
+            elapsed: 0.0,
+        })
+    }
+
+    /// 時間を進め、各パラメーターの値を送る
+    ///
+    /// # 引数
+    /// * `elapsed` - 前回の呼び出しからの経過時間
+    /// * `sender` - 音声グラフへの操作を送るコマンドキューの送信側
+    ///
+    /// # 戻り値
+    /// * プリセットの値に達した場合は `Ok(true)`。以降の呼び出しでは何も送らない
+    ///
+    /// # 実装時の注意
+    /// 所有者のスレッドから process_commands とともに定期的に呼び出してください。
+    pub fn update(&mut self, elapsed: Duration, sender: &CommandSender) -> Result<bool, String> {
+        if self.is_finished() {
+            return Ok(true);
+        }
+        self.elapsed += elapsed.as_secs_f64();
+        let t = if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0) as f32
+        } else {
+            1.0
+        };
+        for target in self.targets.iter() {
+            sender.send(GraphCommand::SetParameter {
+                node_id: target.node_id,
+                parameter_id: target.parameter_id.clone(),
+                value: target.start + (target.end - target.start) * t,
+            })?;
+        }
+        if t >= 1.0 {
+            self.targets.clear();
+        }
+        Ok(self.is_finished())
+    }
+
+    /// プリセットの値に達したかどうか
+    pub fn is_finished(&self) -> bool {
+        self.targets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{NullBackend, StreamConfig};
+    use crate::command::CommandResponse;
+    use crate::graph_file::GraphDescription;
+    use crate::service::AudioEngineService;
+    use audio_engine_core::audio_buffer::AudioBuffer;
+
+    #[test]
+    fn test_capture_store_and_morph() {
+        let mut service =
+            AudioEngineService::with_backend(Box::new(NullBackend::new(StreamConfig {
+                sample_rate: 48000.0,
+                frames_per_buffer: 256,
+                num_input_channels: 0,
+                num_output_channels: 2,
+                channel_map: None,
+            })));
+        let loaded = GraphDescription::from_json(
+            r#"{
+                "nodes": [
+                    { "name": "in", "kind": "input" },
+                    { "name": "osc", "kind": "sine", "params": { "frequency": 220.0 } },
+                    { "name": "out", "kind": "output" }
+                ],
+                "edges": [["osc", "out"]],
+                "input": "in",
+                "output": "out"
+            }"#,
+        )
+        .unwrap()
+        .build(service.get_mut_audio_graph())
+        .unwrap();
+        let osc = loaded.node_id("osc").unwrap();
+        let parameters = |service: &mut AudioEngineService| match GraphCommand::GetParameters
+            .apply(service.get_mut_audio_graph())
+            .unwrap()
+        {
+            CommandResponse::Parameters(parameters) => parameters,
+            response => panic!("予期しない結果です: {:?}", response),
+        };
+
+        // パラメーターの値だけを名前で保存し、JSON を経由しても同じ内容になる
+        let mut bank = PresetBank::new();
+        bank.insert(Preset::capture("low", &parameters(&mut service), &loaded));
+        service
+            .get_mut_audio_graph()
+            .set_parameter(osc, "frequency", 620.0)
+            .unwrap();
+        bank.insert(Preset::capture("high", &parameters(&mut service), &loaded));
+        assert_eq!(bank.names(), vec!["low", "high"]);
+        let low = bank.get("low").unwrap();
+        assert!(low.values.contains(&PresetValue {
+            node: "osc".to_string(),
+            parameter_id: "frequency".to_string(),
+            value: 220.0,
+        }));
+        assert_eq!(PresetBank::from_json(&bank.to_json()).unwrap(), bank);
+
+        // 呼び出すと、指定した時間をかけて値が変化する
+        let sender = service.command_sender();
+        let mut morph = PresetMorph::new(
+            low,
+            &parameters(&mut service),
+            &loaded,
+            Duration::from_millis(100),
+        )
+        .unwrap();
+        assert!(!morph.update(Duration::from_millis(50), &sender).unwrap());
+        assert!(service.process_commands().is_empty());
+        let frequency = service
+            .get_mut_audio_graph()
+            .get_parameter(osc, "frequency")
+            .unwrap();
+        assert!((frequency - 420.0).abs() < 1e-3, "{}", frequency);
+        assert!(morph.update(Duration::from_millis(50), &sender).unwrap());
+        assert!(service.process_commands().is_empty());
+        assert_eq!(
+            service
+                .get_mut_audio_graph()
+                .get_parameter(osc, "frequency"),
+            Some(220.0)
+        );
+
+        // A/B モーフでは、2 つのプリセットの間の値を位置で指定する
+        let high = bank.get("high").unwrap();
+        GraphCommand::SetMorphSnapshots {
+            a: low.resolve(&loaded).unwrap(),
+            b: high.resolve(&loaded).unwrap(),
+        }
+        .apply(service.get_mut_audio_graph())
+        .unwrap();
+        service.get_mut_audio_graph().prepare(48000.0, 256);
+        service.get_mut_audio_graph().set_morph_position(0.5);
+        let mut samples = vec![0.0; 2 * 256];
+        for _ in 0..8 {
+            service.get_mut_audio_graph().process(
+                &mut AudioBuffer::new(2, 256, &mut samples),
+                loaded.input_id,
+                loaded.output_id,
+            );
+        }
+        assert_eq!(
+            service
+                .get_mut_audio_graph()
+                .get_parameter(osc, "frequency"),
+            Some(420.0)
+        );
+
+        // 音声グラフにないノードのプリセットは呼び出せない
+        let mut unknown = low.clone();
+        unknown.values[0].node = "missing".to_string();
+        assert!(PresetMorph::new(&unknown, &[], &loaded, Duration::ZERO).is_err());
+        assert!(bank.remove("low").is_some());
+        assert_eq!(bank.names(), vec!["high"]);
+    }
+}
@@ -0,0 +1,329 @@
+//! オーディオコールバックの信号を WAV ファイルに書き出す録音機能です。
+//!
+//! オーディオコールバックはロックフリーのリングバッファ（RecordTap）にサンプルを書き込むだけで、
+//! ファイルへの書き出しは専用のスレッドで行います。
+
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use audio_engine_core::wav::encode_wav_header;
+
+/// リングバッファの容量（サンプル数、2 の累乗）。48kHz・2ch で約 2.7 秒分
+const RING_CAPACITY: usize = 1 << 18;
+
+/// 書き出しスレッドがリングバッファを確認する間隔（ミリ秒）
+const WRITE_INTERVAL_MS: u64 = 20;
+
+/// 録音の結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordingSummary {
+    /// 書き出したフレーム数
+    pub num_frames: usize,
+    /// 書き出しが間に合わずに捨てたサンプル数
+    pub dropped_samples: usize,
+}
+
+/// 録音する信号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordSource {
+    /// 音声グラフの出力（出力デバイスに送る直前の信号）
+    Output,
+    /// デバイスの入力（チャンネルマップに従って音声グラフの入力チャンネルに集めた信号）
+    Input,
+    /// 入力と出力（入力のチャンネルの後に出力のチャンネルを並べる）
+    InputAndOutput,
+}
+
+impl RecordSource {
+    /// 録音するファイルのチャンネル数
+    ///
+    /// # 引数
+    /// * `graph_channels` - 音声グラフのチャンネル数
+    pub fn num_channels(self, graph_channels: usize) -> usize {
+        match self {
+            RecordSource::Output | RecordSource::Input => graph_channels,
+            RecordSource::InputAndOutput => graph_channels * 2,
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => RecordSource::Input,
+            2 => RecordSource::InputAndOutput,
+            _ => RecordSource::Output,
+        }
+    }
+}
+
+/// オーディオコールバックから録音するサンプルを受け渡すリングバッファ
+///
+/// 書き込み（オーディオコールバック）と読み出し（書き出しスレッド）がそれぞれ 1 スレッドの場合に使えます。
+pub(crate) struct RecordTap {
+    /// サンプル（f32 のビット表現）
+    samples: Box<[AtomicU32]>,
+    /// 書き込んだサンプル数の累計
+    write_pos: AtomicUsize,
+    /// 読み出したサンプル数の累計
+    read_pos: AtomicUsize,
+    /// 録音中かどうか
+    armed: AtomicBool,
+    /// 録音する信号（RecordSource の番号）
+    source: AtomicU8,
+    /// いっぱいで捨てたサンプル数
+    dropped: AtomicUsize,
+}
+
+impl RecordTap {
+    pub(crate) fn new() -> Self {
+        Self {
+            samples: (0..RING_CAPACITY).map(|_| AtomicU32::new(0)).collect(),
+            write_pos: AtomicUsize::new(0),
+            read_pos: AtomicUsize::new(0),
+            armed: AtomicBool::new(false),
+            source: AtomicU8::new(RecordSource::Output as u8),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// 録音中であれば、サンプルを書き込む
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub(crate) fn write(&self, samples: &[f32]) {
+        if !self.armed.load(Ordering::Acquire) {
+            return;
+        }
+        let write_pos = self.write_pos.load(Ordering::Relaxed);
+        let read_pos = self.read_pos.load(Ordering::Acquire);
+        let available = RING_CAPACITY - (write_pos - read_pos);
+        let len = samples.len().min(available);
+        for (i, sample) in samples[..len].iter().enumerate() {
+            self.samples[(write_pos + i) % RING_CAPACITY]
+                .store(sample.to_bits(), Ordering::Relaxed);
+        }
+        self.write_pos.store(write_pos + len, Ordering::Release);
+        if len < samples.len() {
+            self.dropped
+                .fetch_add(samples.len() - len, Ordering::Relaxed);
+        }
+    }
+
+    /// 録音中であれば、1 ブロック分の入力と出力から録音する信号を書き込む
+    ///
+    /// # 引数
+    /// * `input` - 音声グラフの入力（インターリーブ）
+    /// * `output` - 音声グラフの出力（インターリーブ、input と同じ長さ）
+    /// * `channels` - input と output のチャンネル数
+    /// * `scratch` - 入力と出力を並べるための作業用バッファ（input の 2 倍以上の長さ）
+    ///
+    /// # 実装時の注意
+    /// ロックやメモリアロケーションを伴わないため、リアルタイムスレッドから呼び出せます。
+    pub(crate) fn write_block(
+        &self,
+        input: &[f32],
+        output: &[f32],
+        channels: usize,
+        scratch: &mut [f32],
+    ) {
+        if !self.armed.load(Ordering::Acquire) {
+            return;
+        }
+        match RecordSource::from_u8(self.source.load(Ordering::Relaxed)) {
+            RecordSource::Output => self.write(output),
+            RecordSource::Input => self.write(input),
+            RecordSource::InputAndOutput => {
+                let channels = channels.max(1);
+                let scratch = &mut scratch[..input.len() + output.len()];
+                for ((dst, input), output) in scratch
+                    .chunks_exact_mut(channels * 2)
+                    .zip(input.chunks_exact(channels))
+                    .zip(output.chunks_exact(channels))
+                {
+                    dst[..channels].copy_from_slice(input);
+                    dst[channels..].copy_from_slice(output);
+                }
+                self.write(scratch);
+            }
+        }
+    }
+
+    /// たまったサンプルを読み出す
+    ///
+    /// # 戻り値
+    /// * 読み出したサンプル数
+    fn read(&self, buffer: &mut [f32]) -> usize {
+        let read_pos = self.read_pos.load(Ordering::Relaxed);
+        let write_pos = self.write_pos.load(Ordering::Acquire);
+        let len = buffer.len().min(write_pos - read_pos);
+        for (i, sample) in buffer[..len].iter_mut().enumerate() {
+            *sample = f32::from_bits(
+                self.samples[(read_pos + i) % RING_CAPACITY].load(Ordering::Relaxed),
+            );
+        }
+        self.read_pos.store(read_pos + len, Ordering::Release);
+        len
+    }
+}
+
+/// 録音中のファイルと書き出しスレッド
+pub(crate) struct Recorder {
+    /// 録音を受け渡すリングバッファ
+    tap: Arc<RecordTap>,
+    /// ファイルのチャンネル数
+    channels: usize,
+    /// 書き出しスレッドが動作中かどうか
+    running: Arc<AtomicBool>,
+    /// 書き出しスレッド（終了時に書き出したサンプル数を返す）
+    thread: Option<JoinHandle<Result<usize, String>>>,
+}
+
+impl Recorder {
+    /// ファイルを作成し、録音を開始する
+    ///
+    /// # 引数
+    /// * `path` - 書き出す WAV ファイルのパス
+    /// * `tap` - オーディオコールバックと共有するリングバッファ
+    /// * `source` - 録音する信号
+    /// * `graph_channels` - 音声グラフのチャンネル数
+    /// * `sample_rate` - サンプリングレート（Hz）
+    pub(crate) fn start(
+        path: impl AsRef<Path>,
+        tap: Arc<RecordTap>,
+        source: RecordSource,
+        graph_channels: usize,
+        sample_rate: u32,
+    ) -> Result<Self, String> {
+        let channels = source.num_channels(graph_channels);
+        let path: PathBuf = path.as_ref().to_path_buf();
+        let file = File::create(&path)
+            .map_err(|e| format!("録音ファイルを作成できませんでした: {:?} ({})", path, e))?;
+        let mut writer = BufWriter::new(file);
+        // サンプル数は完了時に書き直す
+        writer
+            .write_all(&encode_wav_header(channels, sample_rate, 0))
+            .map_err(|e| e.to_string())?;
+
+        // 前回の録音の残りを捨ててから録音を始める
+        tap.read_pos
+            .store(tap.write_pos.load(Ordering::Acquire), Ordering::Release);
+        tap.dropped.store(0, Ordering::Relaxed);
+        tap.source.store(source as u8, Ordering::Relaxed);
+        tap.armed.store(true, Ordering::Release);
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let tap = tap.clone();
+            let running = running.clone();
+            thread::Builder::new()
+                .name("recorder".to_string())
+                .spawn(move || {
+                    let mut buffer = vec![0.0; 8192];
+                    let mut bytes = Vec::with_capacity(buffer.len() * 4);
+                    let mut num_samples = 0;
+                    loop {
+                        // 停止の要求を先に確認し、要求後に残ったサンプルも書き出してから終了する
+                        let stopping = !running.load(Ordering::Acquire);
+                        loop {
+                            let len = tap.read(&mut buffer);
+                            if len == 0 {
+                                break;
+                            }
+                            bytes.clear();
+                            for sample in buffer[..len].iter() {
+                                bytes.extend_from_slice(&sample.to_le_bytes());
+                            }
+                            writer.write_all(&bytes).map_err(|e| e.to_string())?;
+                            num_samples += len;
+                        }
+                        if stopping {
+                            break;
+                        }
+                        thread::sleep(Duration::from_millis(WRITE_INTERVAL_MS));
+                    }
+                    // ヘッダーを実際のサンプル数で書き直す
+                    writer.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+                    writer
+                        .write_all(&encode_wav_header(channels, sample_rate, num_samples))
+                        .map_err(|e| e.to_string())?;
+                    writer.flush().map_err(|e| e.to_string())?;
+                    Ok(num_samples)
+                })
+                .map_err(|e| format!("スレッドを開始できませんでした: {}", e))?
+        };
+
+        Ok(Self {
+            tap,
+            channels,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// 録音を終了し、ファイルを閉じる
+    pub(crate) fn finish(mut self) -> Result<RecordingSummary, String> {
+        self.tap.armed.store(false, Ordering::Release);
+        self.running.store(false, Ordering::Release);
+        let num_samples = self
+            .thread
+            .take()
+            .ok_or("録音は終了しています")?
+            .join()
+            .map_err(|_| "録音のスレッドがパニックしました".to_string())??;
+        Ok(RecordingSummary {
+            num_frames: num_samples / self.channels.max(1),
+            dropped_samples: self.tap.dropped.load(Ordering::Relaxed),
+        })
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.tap.armed.store(false, Ordering::Release);
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use audio_engine_core::wav::read_wav;
+
+    #[test]
+    fn test_record_to_wav() {
+        let path = std::env::temp_dir().join("audio_engine_service_recorder_test.wav");
+        let tap = Arc::new(RecordTap::new());
+
+        // 録音前の書き込みは無視される
+        tap.write(&[9.0; 4]);
+        let recorder = Recorder::start(&path, tap.clone(), RecordSource::Output, 2, 48000).unwrap();
+        for block in 0..10 {
+            let samples: Vec<f32> = (0..64).map(|i| (block * 64 + i) as f32 / 1000.0).collect();
+            tap.write(&samples);
+        }
+        let summary = recorder.finish().unwrap();
+        // 録音後の書き込みも無視される
+        tap.write(&[9.0; 4]);
+
+        assert_eq!(
+            summary,
+            RecordingSummary {
+                num_frames: 320,
+                dropped_samples: 0
+            }
+        );
+        let wav = read_wav(&path).unwrap();
+        assert_eq!(wav.channels, 2);
+        assert_eq!(wav.sample_rate, 48000);
+        assert_eq!(wav.num_frames(), 320);
+        assert_eq!(wav.samples[639], 0.639);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,358 @@
+//! Rhai スクリプトで音声グラフを構築・操作する機能です。
+//!
+//! スクリプトは所有者のスレッド（メインスレッド）で実行します。スクリプトの本体は再生前に
+//! run で実行し、音声グラフをその場で構築します。その後の update では on_tick 関数や予約した変更を実行し、
+//! 音声グラフへの操作はコマンドキューに送るため、process_commands を呼び出したときに反映されます。
+//!
+//! ```text
+//! let input = add_node("input");
+//! let output = add_node("output");
+//! let osc = add_node("sine");
+//! connect(osc, output);
+//! set_endpoints(input, output);
+//!
+//! // 2 秒後に周波数を変える
+//! at(2.0, osc, "frequency", 330.0);
+//!
+//! // 定義した場合は update のたびに呼び出される（引数は開始からの秒数）
+//! fn on_tick(time) {
+//!     set(2, "frequency", 440.0 + 100.0 * sin(time));
+//! }
+//! ```
+//!
+//! スクリプトから使える関数:
+//! - `add_node(種類)` - ノードを追加し、ID を返す（スクリプトの本体でのみ使える）
+//! - `remove_node(ID)` / `connect(接続元, 接続先)` / `disconnect(接続元, 接続先)`
+//! - `set(ID, パラメーター, 値)` - パラメーターを変更する
+//! - `at(秒, ID, パラメーター, 値)` - 開始からの時刻を指定してパラメーターの変更を予約する
+//! - `now()` - 開始からの秒数
+//! - `set_endpoints(入力ノード, 出力ノード)` - 再生に使うノードを指定する
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use audio_engine_core::audio_graph::{AudioGraph, NodeId};
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST, FLOAT, INT};
+
+use crate::command::{CommandResponse, CommandSender, GraphCommand};
+
+/// update のたびに呼び出すスクリプトの関数の名前
+const TICK_FUNCTION: &str = "on_tick";
+
+/// 予約したパラメーターの変更
+struct ScheduledChange {
+    /// 開始からの時刻（秒）
+    time: f64,
+    /// ノードの ID
+    node_id: NodeId,
+    /// パラメーターの ID
+    parameter_id: String,
+    /// 値
+    value: f32,
+}
+
+/// スクリプトと共有する状態
+struct ScriptState {
+    /// run の間だけ借りている音声グラフ
+    audio_graph: Option<AudioGraph>,
+    /// コマンドキューの送信側
+    sender: CommandSender,
+    /// 開始からの秒数
+    time: f64,
+    /// 予約したパラメーターの変更
+    scheduled: Vec<ScheduledChange>,
+    /// 再生に使う入力ノードと出力ノードの ID
+    endpoints: Option<(NodeId, NodeId)>,
+}
+
+impl ScriptState {
+    /// コマンドを実行する
+    ///
+    /// run の間は音声グラフにその場で適用し、それ以外の間はコマンドキューに送る。
+    fn execute(&mut self, command: GraphCommand) -> Result<CommandResponse, Box<EvalAltResult>> {
+        match self.audio_graph.as_mut() {
+            Some(audio_graph) => command.apply(audio_graph).map_err(Into::into),
+            None if matches!(command, GraphCommand::AddNode { .. }) => {
+                Err("ノードの追加はスクリプトの本体でのみ行えます".into())
+            }
+            None => {
+                self.sender.send(command)?;
+                Ok(CommandResponse::Done)
+            }
+        }
+    }
+}
+
+/// スクリプトの実行環境
+pub struct ScriptHost {
+    engine: Engine,
+    ast: AST,
+    /// スクリプトのグローバル変数
+    scope: Scope<'static>,
+    state: Rc<RefCell<ScriptState>>,
+}
+
+/// スクリプトの関数の引数を ID に変換する
+fn to_id(value: INT) -> Result<NodeId, Box<EvalAltResult>> {
+    usize::try_from(value)
+        .map(NodeId::from_raw)
+        .map_err(|_| format!("ID が不正です: {}", value).into())
+}
+
+impl ScriptHost {
+    /// スクリプトをコンパイルする
+    ///
+    /// # 引数
+    /// * `source` - スクリプトのソースコード
+    /// * `sender` - 再生中の音声グラフへの操作を送るコマンドキューの送信側
+    pub fn new(source: &str, sender: CommandSender) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        let state = Rc::new(RefCell::new(ScriptState {
+            audio_graph: None,
+            sender,
+            time: 0.0,
+            scheduled: Vec::new(),
+            endpoints: None,
+        }));
+
+        let s = state.clone();
+        engine.register_fn(
+            "add_node",
+            move |kind: &str| -> Result<INT, Box<EvalAltResult>> {
+                let kind = kind.to_string();
+                match s.borrow_mut().execute(GraphCommand::AddNode { kind })? {
+                    CommandResponse::NodeAdded(node_id) => Ok(node_id.as_raw() as INT),
+                    response => Err(format!("予期しない結果です: {:?}", response).into()),
+                }
+            },
+        );
+        let s = state.clone();
+        engine.register_fn(
+            "remove_node",
+            move |node_id: INT| -> Result<(), Box<EvalAltResult>> {
+                let node_id = to_id(node_id)?;
+                s.borrow_mut()
+                    .execute(GraphCommand::RemoveNode { node_id })
+                    .map(|_| ())
+            },
+        );
+        let s = state.clone();
+        engine.register_fn(
+            "connect",
+            move |from: INT, to: INT| -> Result<(), Box<EvalAltResult>> {
+                let (from, to) = (to_id(from)?, to_id(to)?);
+                s.borrow_mut()
+                    .execute(GraphCommand::Connect { from, to })
+                    .map(|_| ())
+            },
+        );
+        let s = state.clone();
+        engine.register_fn(
+            "disconnect",
+            move |from: INT, to: INT| -> Result<(), Box<EvalAltResult>> {
+                let (from, to) = (to_id(from)?, to_id(to)?);
+                s.borrow_mut()
+                    .execute(GraphCommand::Disconnect { from, to })
+                    .map(|_| ())
+            },
+        );
+        let s = state.clone();
+        engine.register_fn(
+            "set",
+            move |node_id: INT,
+                  parameter_id: &str,
+                  value: FLOAT|
+                  -> Result<(), Box<EvalAltResult>> {
+                let command = GraphCommand::SetParameter {
+                    node_id: to_id(node_id)?,
+                    parameter_id: parameter_id.to_string(),
+                    value: value as f32,
+                };
+                s.borrow_mut().execute(command).map(|_| ())
+            },
+        );
+        let s = state.clone();
+        engine.register_fn(
+            "at",
+            move |time: FLOAT,
+                  node_id: INT,
+                  parameter_id: &str,
+                  value: FLOAT|
+                  -> Result<(), Box<EvalAltResult>> {
+                let change = ScheduledChange {
+                    time,
+                    node_id: to_id(node_id)?,
+                    parameter_id: parameter_id.to_string(),
+                    value: value as f32,
+                };
+                s.borrow_mut().scheduled.push(change);
+                Ok(())
+            },
+        );
+        let s = state.clone();
+        engine.register_fn("now", move || s.borrow().time as FLOAT);
+        let s = state.clone();
+        engine.register_fn(
+            "set_endpoints",
+            move |input: INT, output: INT| -> Result<(), Box<EvalAltResult>> {
+                s.borrow_mut().endpoints = Some((to_id(input)?, to_id(output)?));
+                Ok(())
+            },
+        );
+
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        Ok(Self {
+            engine,
+            ast,
+            scope: Scope::new(),
+            state,
+        })
+    }
+
+    /// スクリプトの本体（関数の外の文）を実行し、音声グラフを構築する
+    ///
+    /// # 引数
+    /// * `audio_graph` - 構築する音声グラフ（再生前に AudioEngineService::get_mut_audio_graph で取得する）
+    pub fn run(&mut self, audio_graph: &mut AudioGraph) -> Result<(), String> {
+        // 実行中だけ音声グラフを借り、エラーの場合も必ず返す
+        self.state.borrow_mut().audio_graph =
+            Some(std::mem::replace(audio_graph, AudioGraph::new()));
+        let result = self.engine.run_ast_with_scope(&mut self.scope, &self.ast);
+        if let Some(built) = self.state.borrow_mut().audio_graph.take() {
+            *audio_graph = built;
+        }
+        result.map_err(|e| e.to_string())
+    }
+
+    /// 時間を進め、on_tick の呼び出しと予約したパラメーターの変更を行う
+    ///
+    /// # 引数
+    /// * `elapsed` - 前回の呼び出しからの経過時間
+    ///
+    /// # 実装時の注意
+    /// 所有者のスレッドから process_commands とともに定期的に呼び出してください。
+    pub fn update(&mut self, elapsed: Duration) -> Result<(), String> {
+        let time = {
+            let mut state = self.state.borrow_mut();
+            state.time += elapsed.as_secs_f64();
+            state.time
+        };
+
+        if self
+            .ast
+            .iter_functions()
+            .any(|function| function.name == TICK_FUNCTION)
+        {
+            // 本体は run で実行済みのため、関数だけを呼び出す
+            let options = CallFnOptions::new().eval_ast(false);
+            let _: Dynamic = self
+                .engine
+                .call_fn_with_options::<Dynamic>(
+                    options,
+                    &mut self.scope,
+                    &self.ast,
+                    TICK_FUNCTION,
+                    (time,),
+                )
+                .map_err(|e| e.to_string())?;
+        }
+
+        // 時刻に達した変更を、予約した順に送る
+        let mut state = self.state.borrow_mut();
+        let (due, pending): (Vec<_>, Vec<_>) = state
+            .scheduled
+            .drain(..)
+            .partition(|change| change.time <= time);
+        state.scheduled = pending;
+        for change in due {
+            state.sender.send(GraphCommand::SetParameter {
+                node_id: change.node_id,
+                parameter_id: change.parameter_id,
+                value: change.value,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// スクリプトが指定した、再生に使う入力ノードと出力ノードの ID
+    pub fn endpoints(&self) -> Option<(NodeId, NodeId)> {
+        self.state.borrow().endpoints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{NullBackend, StreamConfig};
+    use crate::service::AudioEngineService;
+
+    #[test]
+    fn test_build_and_schedule() {
+        let mut service =
+            AudioEngineService::with_backend(Box::new(NullBackend::new(StreamConfig {
+                sample_rate: 48000.0,
+                frames_per_buffer: 256,
+                num_input_channels: 0,
+                num_output_channels: 2,
+                channel_map: None,
+            })));
+        let mut host = ScriptHost::new(
+            r#"
+            let input = add_node("input");
+            let output = add_node("output");
+            let osc = add_node("sine");
+            connect(osc, output);
+            set(osc, "frequency", 220.0);
+            set_endpoints(input, output);
+            at(1.0, osc, "frequency", 330.0);
+
+            fn on_tick(time) {
+                set(2, "frequency", 200.0 + time * 100.0);
+            }
+            "#,
+            service.command_sender(),
+        )
+        .unwrap();
+        host.run(service.get_mut_audio_graph()).unwrap();
+        let (input, output) = host.endpoints().unwrap();
+        let audio_graph = service.get_mut_audio_graph();
+        let osc = NodeId::from_raw(2);
+        assert_eq!(audio_graph.node_ids(), vec![input, output, osc]);
+        assert_eq!(audio_graph.edges(), vec![(osc, output)]);
+        assert_eq!(audio_graph.get_parameter(osc, "frequency"), Some(220.0));
+
+        // on_tick の変更はコマンドキューを通して適用される
+        host.update(Duration::from_millis(500)).unwrap();
+        assert!(service.process_commands().is_empty());
+        assert_eq!(
+            service
+                .get_mut_audio_graph()
+                .get_parameter(osc, "frequency"),
+            Some(250.0)
+        );
+
+        // 予約した変更は、時刻に達した update で on_tick の後に送られる
+        host.update(Duration::from_millis(500)).unwrap();
+        assert!(service.process_commands().is_empty());
+        assert_eq!(
+            service
+                .get_mut_audio_graph()
+                .get_parameter(osc, "frequency"),
+            Some(330.0)
+        );
+
+        // 本体の実行後はノードを追加できず、スクリプトのエラーはメッセージとして返る
+        let mut host = ScriptHost::new(
+            r#"fn on_tick(time) { add_node("sine"); }"#,
+            service.command_sender(),
+        )
+        .unwrap();
+        host.run(service.get_mut_audio_graph()).unwrap();
+        assert!(host.update(Duration::from_millis(10)).is_err());
+        let mut host =
+            ScriptHost::new(r#"add_node("unknown");"#, service.command_sender()).unwrap();
+        assert!(host.run(service.get_mut_audio_graph()).is_err());
+        assert!(ScriptHost::new("let = ;", service.command_sender()).is_err());
+    }
+}
@@ -3,153 +3,992 @@
 //! 入力デバイスの音声を出力デバイスの1ch目にルーティングし、2ch目は0で埋める設定です。
 //! フィードバックに注意してください。
 
-extern crate portaudio;
-
+#[cfg(feature = "alloc_guard")]
 use assert_no_alloc::*;
 use audio_engine_core::audio_buffer::AudioBuffer;
-use audio_engine_core::audio_graph::AudioGraph;
-use portaudio as pa;
+use audio_engine_core::audio_graph::{AudioGraph, AudioGraphNode, GraphSnapshot, NodeId};
+use audio_engine_core::dsp::{RateConverter, SmoothedValue};
+use audio_engine_core::event::{self, EventQueue};
+use audio_engine_core::garbage::{self, GarbageCollector};
+use audio_engine_core::midi::NoteEvent;
+use audio_engine_core::nodes::ClipDetector;
+use audio_engine_core::rt_warn;
+use serde_json::json;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::backend::{
+    AudioBackend, ChannelMap, PortAudioBackend, StreamConfig, StreamEvent, StreamStatus,
+};
+use crate::command::{CommandQueue, CommandSender, PendingCommand};
+use crate::graph_file::{GraphDescription, LoadedGraph};
+use crate::midi::MidiQueue;
+use crate::notifier::EventNotifier;
+use crate::recorder::{RecordSource, RecordTap, Recorder, RecordingSummary};
+use crate::stats::{StatsRecorder, StreamStats};
+use crate::watchdog::{Watchdog, MIN_TIMEOUT_MS};
+
+pub use audio_engine_core::event::EngineEvent;
 
-#[cfg(debug_assertions)]
+#[cfg(all(debug_assertions, feature = "alloc_guard"))]
 #[global_allocator]
 static A: AllocDisabler = AllocDisabler;
 
-// 定数定義：サンプルレート、フレーム数、チャネル数の設定
-const SAMPLE_RATE: f64 = 44_100.0;
-const FRAMES: u32 = 256;
-const INTERLEAVED: bool = true;
+/// リアルタイムスレッドでの処理を実行します。
+///
+/// `alloc_guard` フィーチャーが有効なデバッグビルドでは、処理中にメモリアロケーションが発生すると
+/// その場で停止するため、ノードに隠れたアロケーションを開発中に検出できます。
+#[inline]
+fn run_realtime<T>(f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "alloc_guard")]
+    {
+        assert_no_alloc(f)
+    }
+    #[cfg(not(feature = "alloc_guard"))]
+    {
+        f()
+    }
+}
+
+/// 音声グラフのチャンネル数（現在、音声グラフは 2ch のみのサポート）
+const GRAPH_CHANNELS: usize = 2;
+
+/// MIDI 入力の送り先がないことを表す値
+const NO_MIDI_TARGET: usize = usize::MAX;
+
+/// ストリームの開始・停止時のフェードの長さ（ミリ秒）
+const FADE_MS: f32 = 10.0;
+
+/// フェードアウトの完了を待つ最大時間（ミリ秒）
+const FADE_TIMEOUT_MS: u64 = 100;
+
+/// 安全のためのリミッターの天井のデフォルト（dB）
+const DEFAULT_LIMITER_CEILING_DB: f32 = -1.0;
+
+/// 出力のピークレベルを EngineEvent::PeakLevels として通知する間隔（ミリ秒）
+const PEAK_INTERVAL_MS: f64 = 50.0;
+
+/// オーディオコールバックとの間でフェードアウトをやり取りするためのフラグ
+struct FadeControl {
+    /// フェードアウトの要求
+    requested: AtomicBool,
+    /// フェードアウトが完了した
+    completed: AtomicBool,
+}
+
+impl FadeControl {
+    fn new() -> Self {
+        Self {
+            requested: AtomicBool::new(false),
+            completed: AtomicBool::new(false),
+        }
+    }
+}
+
+/// 音声グラフのサンプルレートに依存する、オーディオコールバックの状態
+///
+/// 再生中に音声グラフを準備し直すときは、メインスレッドで新しい状態を作り、StateSwap で
+/// ブロックの間に差し替えます。
+struct GraphRateState {
+    /// 音声グラフを処理するサンプルレート
+    graph_sample_rate: f32,
+    /// デバイスと音声グラフの間のサンプルレート変換
+    rate_converter: RateConverter,
+    /// フェードイン・フェードアウトのゲイン
+    fade: SmoothedValue,
+    /// フェードアウトの制御
+    fade_control: Arc<FadeControl>,
+}
+
+impl GraphRateState {
+    /// 新しい状態を作成します（フェードインから始まる）。
+    ///
+    /// # 引数
+    /// * `device_sample_rate` - デバイスのサンプルレート
+    /// * `graph_sample_rate` - 音声グラフを処理するサンプルレート
+    /// * `frames_per_buffer` - 1 回のコールバックで処理するフレーム数
+    fn new(device_sample_rate: f32, graph_sample_rate: f32, frames_per_buffer: usize) -> Self {
+        let mut rate_converter = RateConverter::new();
+        rate_converter.prepare(
+            GRAPH_CHANNELS,
+            device_sample_rate,
+            graph_sample_rate,
+            frames_per_buffer,
+        );
+        let mut fade = SmoothedValue::new(0.0);
+        fade.prepare(device_sample_rate, FADE_MS);
+        fade.set_target(1.0);
+        Self {
+            graph_sample_rate,
+            rate_converter,
+            fade,
+            fade_control: Arc::new(FadeControl::new()),
+        }
+    }
+}
+
+/// メインスレッドで作った状態を、オーディオコールバックのブロックの間に差し替えるためのスロット
+///
+/// 差し替えはコールバック内で mem::swap により行い、古い状態はスロットに残します。
+/// 古い状態の解放はメインスレッドで行うため、コールバック内でメモリの解放は起きません。
+struct StateSwap<T> {
+    /// 差し替える新しい状態、または差し替え後の古い状態
+    slot: Mutex<Option<T>>,
+    /// 新しい状態が差し替えを待っている
+    pending: AtomicBool,
+}
+
+impl<T> StateSwap<T> {
+    fn new() -> Self {
+        Self {
+            slot: Mutex::new(None),
+            pending: AtomicBool::new(false),
+        }
+    }
+
+    /// 新しい状態を差し替え待ちにします（メインスレッドから呼び出す）。
+    fn offer(&self, state: T) {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        *slot = Some(state);
+        self.pending.store(true, Ordering::Release);
+    }
+
+    /// 差し替え待ちの状態があれば、現在の状態と入れ替えます（オーディオコールバックから呼び出す）。
+    ///
+    /// ロックを取得できない場合は待たずに諦め、次のブロックで再び試みます。
+    ///
+    /// # 戻り値
+    /// * 入れ替えた場合は true
+    fn try_swap(&self, current: &mut T) -> bool {
+        if !self.pending.load(Ordering::Acquire) {
+            return false;
+        }
+        let Ok(mut slot) = self.slot.try_lock() else {
+            return false;
+        };
+        let Some(next) = slot.as_mut() else {
+            return false;
+        };
+        std::mem::swap(current, next);
+        self.pending.store(false, Ordering::Release);
+        true
+    }
+
+    /// 差し替えが済んでいれば、古い状態を取り出します（メインスレッドから呼び出す）。
+    fn take_retired(&self) -> Option<T> {
+        if self.pending.load(Ordering::Acquire) {
+            return None;
+        }
+        self.slot.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+/// ストリームが止まった場合の再起動の方針
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    /// 再起動しない
+    Never,
+    /// 新しいデフォルトデバイスでストリームを開き直し、同じ音声グラフで再開する
+    Restart,
+}
 
-/// AudioEngineService 構造体は、音声グラフと PortAudio のストリーム管理をまとめたものです。
+/// AudioEngineService 構造体は、音声グラフとオーディオバックエンドのストリーム管理をまとめたものです。
 ///
 /// 利用者はこの構造体で音声エンジンの初期化やストリームの開始、音声処理の実行を行います。
+/// インスタンスごとに独立した音声グラフとストリームを持つため、メイン出力とモニター出力のように、
+/// 異なるデバイスで複数のインスタンスを同時に動かすことができます。
 pub struct AudioEngineService {
-    /// 音声グラフ。オーディオコールバック内でのみ利用されます。
-    audio_graph: Option<AudioGraph>,
-    /// PortAudio ストリーム。音声入出力の処理を担当します。
-    stream: Option<pa::Stream<pa::NonBlocking, pa::Duplex<f32, f32>>>,
+    /// 音声グラフ。再生中はオーディオコールバックと共有し、コールバック内でのみ利用されます。
+    audio_graph: Arc<Mutex<AudioGraph>>,
+    /// オーディオバックエンド。音声入出力の処理を担当します。
+    backend: Box<dyn AudioBackend>,
+    /// 音声グラフを処理するサンプルレート。None の場合はデバイスのサンプルレートで処理します。
+    graph_sample_rate: Option<f32>,
+    /// ストリームの統計情報。オーディオコールバックと共有します。
+    stats: Arc<StatsRecorder>,
+    /// 再生中の入力ノードと出力ノードの ID。停止中は None。
+    playback: Option<(NodeId, NodeId)>,
+    /// 再生中かどうか（playback が Some かどうか）。イベントを通知するスレッドと共有します。
+    playing: Arc<AtomicBool>,
+    /// ストリームが止まった場合の再起動の方針
+    restart_policy: RestartPolicy,
+    /// 次の poll_event でストリームを開き直すかどうか
+    restart_pending: bool,
+    /// オーディオコールバックの停止を監視するスレッド。無効の場合は None。
+    watchdog: Option<Watchdog>,
+    /// ウォッチドッグがオーディオコールバックの停止を検出したかどうか。poll_event で取り出します。
+    stalled: Arc<AtomicBool>,
+    /// 再生中のストリームのフェードアウトの制御
+    fade: Arc<FadeControl>,
+    /// 別スレッドから送られた音声グラフへの操作
+    commands: CommandQueue,
+    /// 音声グラフから削除したノードやバッファを、process_commands で解放する受信側
+    garbage: GarbageCollector,
+    /// 録音するサンプルをオーディオコールバックから受け取るリングバッファ
+    record_tap: Arc<RecordTap>,
+    /// 録音中のファイル。録音していない場合は None。
+    recorder: Option<Recorder>,
+    /// 再生中のストリームのサンプルレート。停止中は None。
+    stream_sample_rate: Option<f64>,
+    /// 再生中のストリームの 1 回のコールバックのフレーム数
+    stream_frames_per_buffer: usize,
+    /// 再生中のオーディオコールバックの状態を差し替えるためのスロット
+    state_swap: Arc<StateSwap<GraphRateState>>,
+    /// MIDI 入力のメッセージをオーディオコールバックに受け渡すキュー
+    midi_queue: Arc<MidiQueue>,
+    /// MIDI 入力のノートイベントを送るノードの ID（整数の表現）。送らない場合は NO_MIDI_TARGET。
+    midi_target: Arc<AtomicUsize>,
+    /// 出力ノードで有効にする安全のためのリミッターの天井（dB）。None の場合は出力ノードの設定に任せます。
+    safety_limiter: Option<f32>,
+    /// オーディオコールバックや音声グラフのノードから、アプリケーションにイベントを通知するキュー
+    events: Arc<EventQueue>,
 }
 
 impl AudioEngineService {
     /// AudioEngineService の新しいインスタンスを生成します。
     ///
-    /// 内部で新規の音声グラフを作成し、ストリームは None に初期化されます。
+    /// 内部で新規の音声グラフを作成し、PortAudio のデフォルトデバイスを使います。
     pub fn new() -> Self {
+        Self::with_backend(Box::new(PortAudioBackend::new()))
+    }
+
+    /// バックエンドを指定して AudioEngineService の新しいインスタンスを生成します。
+    ///
+    /// オーディオデバイスのない環境では NullBackend を渡します。
+    pub fn with_backend(backend: Box<dyn AudioBackend>) -> Self {
+        let (garbage_sender, garbage) = garbage::channel(garbage::DEFAULT_CAPACITY);
+        let events = Arc::new(EventQueue::new(event::DEFAULT_CAPACITY));
+        let mut audio_graph = AudioGraph::new();
+        audio_graph.set_garbage_sender(Some(garbage_sender));
+        audio_graph.set_event_queue(Some(events.clone()));
         AudioEngineService {
-            audio_graph: Some(AudioGraph::new()),
-            stream: None,
+            audio_graph: Arc::new(Mutex::new(audio_graph)),
+            backend,
+            graph_sample_rate: None,
+            stats: Arc::new(StatsRecorder::new()),
+            playback: None,
+            playing: Arc::new(AtomicBool::new(false)),
+            restart_policy: RestartPolicy::Never,
+            restart_pending: false,
+            watchdog: None,
+            stalled: Arc::new(AtomicBool::new(false)),
+            fade: Arc::new(FadeControl::new()),
+            commands: CommandQueue::new(),
+            garbage,
+            record_tap: Arc::new(RecordTap::new()),
+            recorder: None,
+            stream_sample_rate: None,
+            stream_frames_per_buffer: 0,
+            state_swap: Arc::new(StateSwap::new()),
+            midi_queue: Arc::new(MidiQueue::new()),
+            midi_target: Arc::new(AtomicUsize::new(NO_MIDI_TARGET)),
+            safety_limiter: Some(DEFAULT_LIMITER_CEILING_DB),
+            events,
         }
     }
 
+    /// 音声グラフへの可変参照を取得します。
+    ///
+    /// 再生中は音声グラフをオーディオコールバックと共有しているため、呼び出すとパニックします。
+    /// stop_playback の後は再びアクセスできます。
     pub fn get_mut_audio_graph(&mut self) -> &mut AudioGraph {
-        self.audio_graph.as_mut().unwrap()
+        Arc::get_mut(&mut self.audio_graph)
+            .expect("再生中は音声グラフにアクセスできません")
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 現在の音声グラフを記述に変換します。
+    ///
+    /// 再生中でも呼び出せます。その間は音声グラフをロックするため、オーディオコールバックは
+    /// 1 ブロック分無音を出力することがあります。変換できる範囲は GraphDescription::capture を参照してください。
+    pub fn export_graph(&self) -> Result<GraphDescription, String> {
+        let audio_graph = self.audio_graph.lock().unwrap_or_else(|e| e.into_inner());
+        GraphDescription::capture(&audio_graph)
+    }
+
+    /// オーディオスレッドが参照している音声グラフの状態のスナップショットを取得します。
+    ///
+    /// 再生中でも呼び出せます。export_graph と同様に音声グラフをロックするため、
+    /// オーディオコールバックは 1 ブロック分無音を出力することがあります。
+    pub fn debug_snapshot(&self) -> GraphSnapshot {
+        let audio_graph = self.audio_graph.lock().unwrap_or_else(|e| e.into_inner());
+        audio_graph.debug_snapshot()
+    }
+
+    /// debug_snapshot の内容を JSON に変換します。
+    ///
+    /// ノードID は整数の表現（NodeId::as_raw）で書き出します。ログに残したり、ツールで可視化したりするために使います。
+    pub fn debug_snapshot_json(&self) -> String {
+        let snapshot = self.debug_snapshot();
+        let nodes: Vec<_> = snapshot
+            .nodes
+            .iter()
+            .map(|node| {
+                let inputs: Vec<_> = node
+                    .inputs
+                    .iter()
+                    .map(|input| {
+                        json!({
+                            "from": input.from_id.as_raw(),
+                            "from_port": input.from_port,
+                            "port": input.port,
+                            "gain": input.gain,
+                            "offset": input.offset,
+                        })
+                    })
+                    .collect();
+                let params: serde_json::Map<_, _> = node
+                    .parameters
+                    .iter()
+                    .map(|(id, value)| (id.to_string(), json!(value)))
+                    .collect();
+                json!({
+                    "id": node.node_id.as_raw(),
+                    "kind": node.kind,
+                    "inputs": inputs,
+                    "output_channels": node.output_channels,
+                    "output_frames": node.output_frames,
+                    "output_capacity": node.output_capacity,
+                    "port_outputs": node.num_port_outputs,
+                    "params": params,
+                })
+            })
+            .collect();
+        let order: Vec<_> = snapshot
+            .processing_order
+            .iter()
+            .map(|node_id| node_id.as_raw())
+            .collect();
+        serde_json::to_string_pretty(&json!({
+            "sample_rate": snapshot.sample_rate,
+            "max_buffer_size": snapshot.max_buffer_size,
+            "block_size": snapshot.block_size,
+            "num_channels": snapshot.num_channels,
+            "processing_order": order,
+            "nodes": nodes,
+        }))
+        .unwrap()
+    }
+
+    /// 音声グラフを記述の内容に置き換えます。
+    ///
+    /// 再生中の場合は停止して置き換え、新しい入力ノードと出力ノードで再生を再開します。
+    /// 記述からグラフを構築できない場合は、元の音声グラフを変更せずにエラーを返します。
+    ///
+    /// # 戻り値
+    /// * ノードの名前と ID の対応
+    pub fn load_graph(&mut self, description: &GraphDescription) -> Result<LoadedGraph, String> {
+        // 置き換える前に、記述から構築できることを確認する
+        description.build(&mut AudioGraph::new())?;
+
+        let playing = self.playback.is_some();
+        if playing {
+            self.stop_playback()?;
+        }
+        let audio_graph = self.get_mut_audio_graph();
+        for node_id in audio_graph.node_ids() {
+            audio_graph.remove_node(node_id);
+        }
+        let loaded = description.build(audio_graph)?;
+        self.garbage.collect();
+        if playing {
+            self.start_playback(loaded.input_id, loaded.output_id)?;
+        }
+        Ok(loaded)
+    }
+
+    /// 音声グラフを処理するサンプルレートを設定します。
+    ///
+    /// デバイスのサンプルレートと異なる場合、オーディオコールバック内でサンプルレート変換を行います。
+    /// 48kHz を前提に作ったグラフを 44.1kHz のデバイスで動かす場合などに使います。
+    /// start_playback より前に呼び出す必要があります。再生中に変更する場合は reprepare_graph を使います。
+    pub fn set_graph_sample_rate(&mut self, sample_rate: Option<f32>) {
+        self.graph_sample_rate = sample_rate;
+    }
+
+    /// 再生を止めずに、音声グラフを新しいサンプルレートで準備し直します。
+    ///
+    /// 新しいサンプルレート変換などの状態はメインスレッドで作り、フェードアウトして音声グラフを
+    /// 準備し直した後、オーディオコールバックのブロックの間に差し替えてフェードインします。
+    /// 停止中の場合は set_graph_sample_rate と同じく、次の start_playback から使われます。
+    ///
+    /// # 引数
+    /// * `sample_rate` - 音声グラフを処理するサンプルレート。None の場合はデバイスのサンプルレート。
+    pub fn reprepare_graph(&mut self, sample_rate: Option<f32>) -> Result<(), String> {
+        if sample_rate.is_some_and(|rate| !(rate > 0.0 && rate.is_finite())) {
+            return Err(format!("サンプルレートが不正です: {:?}", sample_rate));
+        }
+        self.graph_sample_rate = sample_rate;
+        let Some(device_sample_rate) = self.stream_sample_rate.filter(|_| self.is_playing()) else {
+            return Ok(());
+        };
+
+        // メモリアロケーションを伴う準備は、すべてメインスレッドで行う
+        let graph_sample_rate = sample_rate.unwrap_or(device_sample_rate as f32);
+        let state = GraphRateState::new(
+            device_sample_rate as f32,
+            graph_sample_rate,
+            self.stream_frames_per_buffer,
+        );
+        let max_inner_frames = state.rate_converter.max_inner_frames();
+        let fade_control = state.fade_control.clone();
+
+        self.fade_out();
+        {
+            // ロック中はコールバックが無音を出力する。ロックを解放する前に差し替えを待ちにしておくことで、
+            // 新しいサンプルレートの音声グラフを古い状態で処理することはない
+            let mut graph = self.audio_graph.lock().unwrap_or_else(|e| e.into_inner());
+            graph.prepare(graph_sample_rate, max_inner_frames);
+            self.state_swap.offer(state);
+        }
+        self.fade = fade_control;
+
+        // 差し替えられた古い状態をメインスレッドで解放する
+        let deadline = Instant::now() + Duration::from_millis(FADE_TIMEOUT_MS);
+        while self.backend.is_active() && Instant::now() < deadline {
+            if self.state_swap.take_retired().is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+
+    /// 安全のためのリミッターの天井を設定します。
+    ///
+    /// Some の場合、再生を開始するときに出力ノードのリミッターを有効にし、天井を設定します。
+    /// フィードバックなどで信号が発散しても、出力が天井を超えないようにするためのものです。
+    /// None の場合は出力ノードの設定を変更しません。デフォルトは -1dB です。
+    /// start_playback より前に呼び出す必要があります。
+    pub fn set_safety_limiter(&mut self, ceiling_db: Option<f32>) {
+        self.safety_limiter = ceiling_db;
+    }
+
+    /// ストリームが止まった場合の再起動の方針を設定します。
+    ///
+    /// デフォルトは RestartPolicy::Never です。
+    pub fn set_restart_policy(&mut self, policy: RestartPolicy) {
+        self.restart_policy = policy;
+    }
+
+    /// オーディオコールバックの停止を監視するウォッチドッグを設定します。
+    ///
+    /// 有効にすると、再生中にオーディオコールバックが `timeout_ms` ミリ秒以上呼び出されない場合に
+    /// EngineEvent::CallbackStalled を通知し、poll_event から StreamEvent::Stalled を返します。
+    /// 再起動の方針が RestartPolicy::Restart の場合は、続けて poll_event を呼び出したときにストリームを開き直します。
+    /// デフォルトは無効です。
+    ///
+    /// # 引数
+    /// * `timeout_ms` - 止まったとみなす時間（ミリ秒）。`None` の場合はウォッチドッグを停止します。
+    ///   watchdog::MIN_TIMEOUT_MS 未満の値は MIN_TIMEOUT_MS に切り上げます。
+    ///   コールバックの周期（バッファサイズ / サンプルレート）より短いと、正常に動作していても止まったと判定されるため、
+    ///   周期より十分に長い値を指定してください。
+    pub fn set_watchdog(&mut self, timeout_ms: Option<u64>) {
+        // 新しいスレッドを開始する前に、前のスレッドを停止する
+        self.watchdog = None;
+        self.stalled.store(false, Ordering::Release);
+        self.watchdog = timeout_ms.map(|timeout_ms| {
+            Watchdog::start(
+                self.stats.clone(),
+                self.playing.clone(),
+                self.stalled.clone(),
+                self.events.clone(),
+                Duration::from_millis(timeout_ms.max(MIN_TIMEOUT_MS)),
+            )
+        });
     }
 
-    /// PortAudio の初期化と非ブロッキングストリームの開始を行います。
+    /// デバイスを開き、ストリームを開始します。
     ///
     /// 引数 node_id_in, node_id_out を利用して、音声グラフ上で音声処理を実行します。
-    /// このメソッド実行後、audio_graph はオーディオコールバックに move されるため、以降は利用できません。
+    /// 再生中、音声グラフはオーディオコールバックと共有されるため、get_mut_audio_graph は利用できません。
     pub fn start_playback(
         &mut self,
-        node_id_in: usize,
-        node_id_out: usize,
-    ) -> Result<(), pa::Error> {
-        // PortAudio の初期化
-        let pa_instance = pa::PortAudio::new()?;
-        println!("PortAudio:");
-        println!("バージョン: {}", pa_instance.version());
-        println!("バージョンテキスト: {:?}", pa_instance.version_text());
-        println!("ホスト数: {}", pa_instance.host_api_count()?);
-        let default_host = pa_instance.default_host_api()?;
-        println!(
-            "デフォルトホスト: {:#?}",
-            pa_instance.host_api_info(default_host)
+        node_id_in: NodeId,
+        node_id_out: NodeId,
+    ) -> Result<(), String> {
+        if self.playback.is_some() {
+            return Err("すでに再生中です".to_string());
+        }
+        // 統計情報は再生ごとに計測し直す
+        self.stats.reset();
+        self.start_stream(node_id_in, node_id_out)?;
+        self.playback = Some((node_id_in, node_id_out));
+        self.playing.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// ストリームを停止します。
+    ///
+    /// ノイズを防ぐため、短くフェードアウトしてから停止します。
+    /// 音声グラフは保持されるため、再び start_playback で再生できます。
+    pub fn stop_playback(&mut self) -> Result<(), String> {
+        self.fade_out();
+        self.playback = None;
+        self.playing.store(false, Ordering::Release);
+        self.restart_pending = false;
+        self.backend.stop()
+    }
+
+    /// 出力デバイスを切り替えます。
+    ///
+    /// 再生中の場合は、フェードアウトしてストリームを停止し、音声グラフを新しいデバイスの
+    /// サンプルレートで準備し直してから、フェードインして再開します。音声グラフはそのまま引き継がれます。
+    /// 停止中の場合は、次の start_playback から指定したデバイスを使います。
+    ///
+    /// # 引数
+    /// * `device_id` - バックエンドごとのデバイスの ID
+    pub fn switch_output_device(&mut self, device_id: usize) -> Result<(), String> {
+        let Some((node_id_in, node_id_out)) = self.playback else {
+            return self.backend.set_output_device(device_id);
+        };
+
+        self.fade_out();
+        self.backend.stop()?;
+        self.backend.set_output_device(device_id)?;
+        if let Err(e) = self.start_stream(node_id_in, node_id_out) {
+            self.playback = None;
+            self.playing.store(false, Ordering::Release);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// ストリームが動作中かどうか
+    pub fn is_playing(&self) -> bool {
+        self.backend.is_active()
+    }
+
+    /// ストリームのイベントを取り出します。
+    ///
+    /// デバイスの切断などでストリームが止まった場合に、その通知を返します。
+    /// ウォッチドッグが有効な場合は、オーディオコールバックが呼び出されなくなったときに StreamEvent::Stalled を返します。
+    /// 再起動の方針が RestartPolicy::Restart の場合は、続けて呼び出したときに新しいデフォルトデバイスで
+    /// ストリームを開き直し、結果を StreamEvent::Restarted または StreamEvent::RestartFailed として返します。
+    /// 開き直せなかった場合は、次の呼び出しで再び試みます。
+    ///
+    /// # 実装時の注意
+    /// デバイスの操作を行うため、所有者のスレッドから定期的に呼び出してください。
+    pub fn poll_event(&mut self) -> Option<StreamEvent> {
+        let (node_id_in, node_id_out) = self.playback?;
+        if self.restart_pending {
+            let _ = self.backend.stop();
+            return Some(match self.start_stream(node_id_in, node_id_out) {
+                Ok(()) => {
+                    self.restart_pending = false;
+                    StreamEvent::Restarted
+                }
+                Err(e) => StreamEvent::RestartFailed(e),
+            });
+        }
+
+        let event = match self.backend.poll_event() {
+            Some(event) => event,
+            None if self.stalled.swap(false, Ordering::AcqRel) => StreamEvent::Stalled,
+            None => return None,
+        };
+        match self.restart_policy {
+            RestartPolicy::Restart => self.restart_pending = true,
+            // ハングしたストリームは動作中のままのため、コールバックが再開する可能性を残して再生中として扱う
+            RestartPolicy::Never if event == StreamEvent::Stalled => {}
+            RestartPolicy::Never => {
+                self.playback = None;
+                self.playing.store(false, Ordering::Release);
+            }
+        }
+        Some(event)
+    }
+
+    /// オーディオコールバックや音声グラフのノードから通知されたイベントを、全て取り出します。
+    ///
+    /// 出力のクリップ（EngineEvent::ClipDetected）と xrun、一定間隔の出力のピークレベル、
+    /// MIDI 入力のノートオン、ノード独自のイベントを、発生した順に返します。
+    /// 取り出すまでにためておけるのは event::DEFAULT_CAPACITY 件までで、それを超えたイベントは破棄されます。
+    pub fn poll_events(&self) -> Vec<EngineEvent> {
+        let mut events = Vec::new();
+        let dropped = self.events.drain(|event| events.push(event));
+        if dropped > 0 {
+            eprintln!("イベントが多すぎるため、{} 件を破棄しました", dropped);
+        }
+        events
+    }
+
+    /// 通知されたイベントを、専用のスレッドから関数で受け取ります。
+    ///
+    /// poll_events で取り出す代わりに、イベントが届くたびに `callback` を通知用のスレッドで呼び出します。
+    /// オーディオコールバックが止まった場合は EngineEvent::StreamStopped も通知します。
+    /// 返された EventNotifier を破棄すると通知を止めます。
+    ///
+    /// # 実装時の注意
+    /// 動作中は poll_events と同じキューからイベントを取り出すため、poll_events と併用しないでください。
+    pub fn spawn_event_notifier(
+        &self,
+        callback: impl FnMut(EngineEvent) + Send + 'static,
+    ) -> EventNotifier {
+        EventNotifier::start(
+            self.events.clone(),
+            self.stats.clone(),
+            self.playing.clone(),
+            callback,
+        )
+    }
+
+    /// 通知されたイベントを 1 件だけ取り出します。
+    ///
+    /// 呼び出し側がイベントを格納する領域を用意する FFI などで、poll_events の代わりに使います。
+    pub fn next_event(&self) -> Option<EngineEvent> {
+        self.events.pop()
+    }
+
+    /// 録音を開始します。
+    ///
+    /// デバイスの入力（音声グラフの入力チャンネルに集めた信号）や、出力デバイスに送る直前の信号を
+    /// デバイスのサンプルレートで IEEE float 32bit の WAV ファイルに書き出します。
+    /// ファイルへの書き出しは専用のスレッドで行います。
+    /// 再生中のみ開始でき、stop_recording を呼び出すまで続きます。
+    ///
+    /// # 引数
+    /// * `path` - 書き出す WAV ファイルのパス
+    /// * `source` - 録音する信号（入力と出力の両方の場合、入力の後に出力のチャンネルを並べる）
+    pub fn start_recording(
+        &mut self,
+        path: impl AsRef<Path>,
+        source: RecordSource,
+    ) -> Result<(), String> {
+        if self.recorder.is_some() {
+            return Err("すでに録音中です".to_string());
+        }
+        let sample_rate = self
+            .stream_sample_rate
+            .filter(|_| self.is_playing())
+            .ok_or("再生中のみ録音できます")?;
+        self.recorder = Some(Recorder::start(
+            path,
+            self.record_tap.clone(),
+            source,
+            GRAPH_CHANNELS,
+            sample_rate as u32,
+        )?);
+        Ok(())
+    }
+
+    /// 録音を終了し、ファイルを閉じます。
+    ///
+    /// # 戻り値
+    /// * 書き出したフレーム数と、書き出しが間に合わずに捨てたサンプル数
+    pub fn stop_recording(&mut self) -> Result<RecordingSummary, String> {
+        self.recorder.take().ok_or("録音していません")?.finish()
+    }
+
+    /// 録音中かどうか
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// MIDI 入力のメッセージを入れるキューを取得します。
+    ///
+    /// MidiService にこのキューを渡すと、受け取ったノートイベントが set_midi_target で指定したノードに届きます。
+    pub fn midi_queue(&self) -> Arc<MidiQueue> {
+        self.midi_queue.clone()
+    }
+
+    /// MIDI 入力のノートイベントを送るノードを指定します。
+    ///
+    /// 再生中も変更できます。None を指定すると、受け取ったイベントは捨てられます。
+    pub fn set_midi_target(&self, node_id: Option<NodeId>) {
+        self.midi_target.store(
+            node_id.map_or(NO_MIDI_TARGET, |id| id.as_raw()),
+            Ordering::Relaxed,
         );
+    }
+
+    /// 音声グラフへの操作を送るための送信側を取得します。
+    ///
+    /// OSC サーバーなど、サービスを所有していないスレッドから音声グラフを操作するために使います。
+    /// 送ったコマンドは process_commands を呼び出したときに適用されます。
+    pub fn command_sender(&self) -> CommandSender {
+        self.commands.sender()
+    }
+
+    /// 送られたコマンドを音声グラフに適用します。
+    ///
+    /// 再生中も呼び出せます。その間はオーディオコールバックが音声グラフを取得できず、
+    /// 1 ブロック分が無音になる場合があります。
+    /// CommandSender::request で送られたコマンドの結果は、その送り主に返します。
+    /// 削除したノードやバッファのメモリも、ここで解放します。
+    ///
+    /// # 戻り値
+    /// * CommandSender::send で送られ、適用に失敗したコマンドのエラーメッセージ
+    ///
+    /// # 実装時の注意
+    /// 所有者のスレッドから poll_event とともに定期的に呼び出してください。
+    pub fn process_commands(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut audio_graph = None;
+        for PendingCommand { command, reply } in self.commands.drain() {
+            let audio_graph = audio_graph
+                .get_or_insert_with(|| self.audio_graph.lock().unwrap_or_else(|e| e.into_inner()));
+            let result = command.apply(audio_graph);
+            match reply {
+                // 送り主がすでに待つのをやめている場合は、結果を捨てる
+                Some(reply) => {
+                    let _ = reply.send(result);
+                }
+                None => {
+                    if let Err(e) = result {
+                        errors.push(e);
+                    }
+                }
+            }
+        }
+        drop(audio_graph);
+        self.garbage.collect();
+        errors
+    }
+
+    /// ストリームの統計情報（xrun の回数、コールバックの平均・最大負荷）を取得します。
+    ///
+    /// 値は start_playback からの累計です。モニタリング用に任意のスレッドから呼び出せます。
+    pub fn stats(&self) -> StreamStats {
+        self.stats.snapshot()
+    }
 
-        // 入力デバイスの設定
-        let def_input = pa_instance.default_input_device()?;
-        let input_info = pa_instance.device_info(def_input)?;
-        println!("デフォルト入力デバイス情報: {:#?}", &input_info);
-        let num_input_channels = input_info.max_input_channels;
-        let input_latency = input_info.default_low_input_latency;
-        let input_params = pa::StreamParameters::<f32>::new(
-            def_input,
+    /// 再生中のストリームをフェードアウトし、完了するまで待ちます。
+    fn fade_out(&self) {
+        self.fade.requested.store(true, Ordering::Release);
+        let deadline = Instant::now() + Duration::from_millis(FADE_TIMEOUT_MS);
+        while self.backend.is_active()
+            && !self.fade.completed.load(Ordering::Acquire)
+            && Instant::now() < deadline
+        {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// デバイスを開いて音声グラフを準備し、ストリームを開始します。
+    fn start_stream(&mut self, node_id_in: NodeId, node_id_out: NodeId) -> Result<(), String> {
+        let config = self.backend.open()?;
+        let StreamConfig {
+            sample_rate,
+            frames_per_buffer,
             num_input_channels,
-            INTERLEAVED,
-            input_latency,
-        );
+            num_output_channels,
+            ..
+        } = config;
+
+        // デバイスと音声グラフの間のチャンネルの対応を決める
+        let channel_map = config.channel_map.clone().unwrap_or_else(|| {
+            ChannelMap::default_for(GRAPH_CHANNELS, num_input_channels, num_output_channels)
+        });
+        if let Err(e) = channel_map.validate(GRAPH_CHANNELS, &config) {
+            self.backend.stop()?;
+            return Err(e);
+        }
+        let mut input_map = [None; GRAPH_CHANNELS];
+        input_map[..channel_map.inputs.len()].copy_from_slice(&channel_map.inputs);
+        let mut output_map = [None; GRAPH_CHANNELS];
+        output_map[..channel_map.outputs.len()].copy_from_slice(&channel_map.outputs);
+        // 音声グラフで処理するためのバッファ（インターリーブ）
+        let mut graph_buffer = vec![0.0; frames_per_buffer * GRAPH_CHANNELS];
+        // 録音のために処理前の入力を残すバッファと、入力と出力を並べるための作業用バッファ
+        let mut input_buffer = vec![0.0; frames_per_buffer * GRAPH_CHANNELS];
+        let mut record_buffer = vec![0.0; frames_per_buffer * GRAPH_CHANNELS * 2];
+
+        // サンプルレート変換とフェードの準備（グラフとデバイスのレートが同じ場合は変換しない）
+        let graph_sample_rate = self.graph_sample_rate.unwrap_or(sample_rate as f32);
+        let mut state =
+            GraphRateState::new(sample_rate as f32, graph_sample_rate, frames_per_buffer);
+
+        // オーディオグラフの準備（前のストリームのコールバックは破棄済みのため、ここでは共有されていない）
+        let graph = Arc::get_mut(&mut self.audio_graph)
+            .ok_or("音声グラフが前のストリームから解放されていません")?
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner());
+        graph.prepare(graph_sample_rate, state.rate_converter.max_inner_frames());
+        // 入力ノードでデバイスのチャンネルが指定されている場合は、チャンネルマップより優先する
+        for (graph_ch, id) in ["channel_left", "channel_right"].into_iter().enumerate() {
+            let Some(device_ch) = graph
+                .get_parameter(node_id_in, id)
+                .filter(|ch| *ch >= 0.0)
+                .map(|ch| ch as usize)
+            else {
+                continue;
+            };
+            if device_ch >= num_input_channels {
+                self.backend.stop()?;
+                return Err(format!(
+                    "入力ノードで指定されたデバイスの入力チャンネル {} がありません（入力は {}ch）",
+                    device_ch, num_input_channels
+                ));
+            }
+            input_map[graph_ch] = Some(device_ch);
+        }
+        if let Some(ceiling_db) = self.safety_limiter {
+            // 出力ノードがリミッターを持たないノードの場合は何もしない
+            let _ = graph.set_parameter(node_id_out, "ceiling", ceiling_db);
+            let _ = graph.set_parameter(node_id_out, "limiter", 1.0);
+        }
+
+        let audio_graph = self.audio_graph.clone();
+        let stats = self.stats.clone();
+        let record_tap = self.record_tap.clone();
+        let midi_queue = self.midi_queue.clone();
+        let midi_target = self.midi_target.clone();
+        let events = self.events.clone();
+        let mut clip_detector = ClipDetector::new();
+        let clip_readout = clip_detector.readout();
+        // 出力のピークレベルを、PEAK_INTERVAL_MS ごとにまとめて通知する
+        let peak_interval = (sample_rate * PEAK_INTERVAL_MS / 1000.0).max(1.0) as usize;
+        let mut peak_frames = 0;
+        let mut peak = [0.0f32; GRAPH_CHANNELS];
+        let block_us = (frames_per_buffer as f64 / sample_rate * 1_000_000.0) as u64;
+        self.stream_sample_rate = Some(sample_rate);
+        self.stream_frames_per_buffer = frames_per_buffer;
 
-        // 出力デバイスの設定
-        let def_output = pa_instance.default_output_device()?;
-        let output_info = pa_instance.device_info(def_output)?;
-        println!("デフォルト出力デバイス情報: {:#?}", &output_info);
-        let num_output_channels = output_info.max_output_channels;
-        let output_latency = output_info.default_low_output_latency;
-        let output_params =
-            pa::StreamParameters::new(def_output, num_output_channels, INTERLEAVED, output_latency);
-
-        // デュプレックスフォーマットがサポートされているか確認
-        let result =
-            pa_instance.is_duplex_format_supported(input_params, output_params, SAMPLE_RATE);
-        println!("デュプレックスフォーマットサポート確認: {:?}", result);
-        result?;
-
-        // ストリームの設定
-        let settings =
-            pa::DuplexStreamSettings::new(input_params, output_params, SAMPLE_RATE, FRAMES);
-
-        // self.audio_graph をコールバック用に取り出す (move するため、以降は利用できません)
-        let mut audio_graph = self
-            .audio_graph
-            .take()
-            .expect("音声グラフが初期化されていません");
-
-        // オーディオグラフの準備
-        audio_graph.prepare(SAMPLE_RATE as f32, FRAMES as usize);
-
-        // コールバックに移譲するため、audio_graph を move してクロージャで保持します
-        let callback = move |pa::DuplexStreamCallbackArgs {
-                                 in_buffer,
-                                 out_buffer,
-                                 frames,
-                                 ..
-                             }| {
-            assert_no_alloc(|| {
+        // ストリームの開始時はフェードインし、要求に応じてフェードアウトする
+        self.fade = state.fade_control.clone();
+        self.state_swap = Arc::new(StateSwap::new());
+        let state_swap = self.state_swap.clone();
+
+        // 音声グラフはコールバックと共有し、コールバック内では try_lock で待たずに取得します。
+        // 再生中にロックを取り合うのは reprepare_graph で準備し直す間のみで、その間は無音を出力します。
+        let callback = move |in_buffer: &[f32],
+                             out_buffer: &mut [f32],
+                             frames: usize,
+                             status: StreamStatus| {
+            let started = Instant::now();
+            run_realtime(|| {
                 // フレーム数の確認
-                assert!(frames == FRAMES as usize);
+                assert!(frames == frames_per_buffer);
                 // 出力バッファを0で初期化
                 out_buffer.fill(0.0);
-                // 入力信号を全ての出力チャネルにコピー
-                for frame in 0..frames {
-                    for ch in 0..num_output_channels as usize {
-                        out_buffer[frame * num_output_channels as usize + ch] = in_buffer[frame];
+                let Ok(mut audio_graph) = audio_graph.try_lock() else {
+                    return;
+                };
+                // 音声グラフが準備し直された場合は、ブロックの頭で状態を差し替える
+                state_swap.try_swap(&mut state);
+                let GraphRateState {
+                    graph_sample_rate,
+                    rate_converter,
+                    fade,
+                    fade_control,
+                } = &mut state;
+                // チャンネルマップに従って、デバイスの入力を音声グラフの入力チャンネルに集める
+                let graph_buffer = &mut graph_buffer[..frames * GRAPH_CHANNELS];
+                for (frame, dst) in graph_buffer.chunks_exact_mut(GRAPH_CHANNELS).enumerate() {
+                    for (sample, device_ch) in dst.iter_mut().zip(input_map) {
+                        *sample = device_ch
+                            .and_then(|ch| in_buffer.get(frame * num_input_channels + ch))
+                            .copied()
+                            .unwrap_or(0.0);
                     }
                 }
+                let input_buffer = &mut input_buffer[..graph_buffer.len()];
+                input_buffer.copy_from_slice(graph_buffer);
+                // MIDI 入力のノートイベントを、グラフのサンプルレートでのタイミングで対象ノードに送る
+                let target = midi_target.load(Ordering::Relaxed);
+                midi_queue.drain(
+                    midi_queue.now_us(),
+                    block_us,
+                    *graph_sample_rate as f64,
+                    |event| {
+                        if let NoteEvent::NoteOn {
+                            channel,
+                            note,
+                            velocity,
+                            ..
+                        } = event
+                        {
+                            events.push(EngineEvent::NoteOn {
+                                channel,
+                                note,
+                                velocity,
+                            });
+                        }
+                        if target != NO_MIDI_TARGET {
+                            audio_graph.send_note_event(NodeId::from_raw(target), event);
+                        }
+                    },
+                );
                 // AudioBuffer に変換し、音声グラフで処理
-                let mut audio_buffer =
-                    AudioBuffer::new(num_output_channels as usize, frames, out_buffer);
+                let mut audio_buffer = AudioBuffer::new(GRAPH_CHANNELS, frames, graph_buffer);
 
-                // move 済みの audio_graph で音声処理を実行
-                audio_graph.process(&mut audio_buffer, node_id_in, node_id_out);
+                // 音声グラフで音声処理を実行（必要に応じてグラフのサンプルレートに変換）
+                rate_converter.process(&mut audio_buffer, |buffer| {
+                    audio_graph.process(buffer, node_id_in, node_id_out);
+                });
+                clip_detector.process(&mut audio_buffer);
+                let clips = clip_readout.take_new_clips();
+                if clips > 0 {
+                    events.push(EngineEvent::ClipDetected { clips });
+                }
 
-                // オーディオグラフの処理後、出力バッファのサンプル値を -2.0 ～ +2.0 に制限（クリップ）する
-                for sample in out_buffer.iter_mut() {
+                // オーディオグラフの処理後、出力のサンプル値を -2.0 ～ +2.0 に制限（クリップ）する
+                for sample in graph_buffer.iter_mut() {
                     if *sample < -2.0 {
                         *sample = -2.0;
                     } else if *sample > 2.0 {
                         *sample = 2.0;
                     }
                 }
+
+                // フェードを適用する
+                if fade_control.requested.load(Ordering::Acquire) {
+                    fade.set_target(0.0);
+                }
+                for frame in graph_buffer.chunks_exact_mut(GRAPH_CHANNELS) {
+                    let gain = fade.next_value();
+                    for (sample, peak) in frame.iter_mut().zip(peak.iter_mut()) {
+                        *sample *= gain;
+                        *peak = peak.max(sample.abs());
+                    }
+                }
+                peak_frames += frames;
+                if peak_frames >= peak_interval {
+                    let [left, right] = peak;
+                    events.push(EngineEvent::PeakLevels { left, right });
+                    peak = [0.0; GRAPH_CHANNELS];
+                    peak_frames = 0;
+                }
+
+                // 録音中であれば、選択した信号を録音する
+                record_tap.write_block(
+                    input_buffer,
+                    graph_buffer,
+                    GRAPH_CHANNELS,
+                    &mut record_buffer,
+                );
+
+                // チャンネルマップに従って、音声グラフの出力をデバイスの出力チャンネルに書き出す
+                for (src, dst) in graph_buffer
+                    .chunks_exact(GRAPH_CHANNELS)
+                    .zip(out_buffer.chunks_exact_mut(num_output_channels.max(1)))
+                {
+                    for (&sample, device_ch) in src.iter().zip(output_map) {
+                        if let Some(ch) = device_ch {
+                            dst[ch] = sample;
+                        }
+                    }
+                }
+                if fade.target() == 0.0 && !fade.is_smoothing() {
+                    fade_control.completed.store(true, Ordering::Release);
+                }
             });
-            pa::Continue
+            // 処理時間と、このブロックの再生にかかる時間を比べて負荷を記録する
+            let budget = Duration::from_secs_f64(frames as f64 / sample_rate);
+            if status.has_xrun() {
+                rt_warn!("xrun が発生しました: {:?}", status);
+                events.push(EngineEvent::XRun);
+            }
+            stats.record(status, started.elapsed(), budget);
         };
 
-        // 非ブロッキングストリームの生成と開始
-        let mut stream = pa_instance.open_non_blocking_stream(settings, callback)?;
-        stream.start()?;
-        println!("Stream started");
-
-        // ストリームをフィールドに保持
-        self.stream = Some(stream);
-        Ok(())
+        // 前のストリームで検出した停止は、新しいストリームには当てはまらない
+        self.stalled.store(false, Ordering::Release);
+        self.backend.start(Box::new(callback))
     }
 }
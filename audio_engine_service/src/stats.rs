@@ -0,0 +1,122 @@
+//! ストリームの健全性（xrun の回数やコールバックの負荷）の計測を行います。
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::backend::StreamStatus;
+
+/// ストリームの統計情報のスナップショット
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StreamStats {
+    /// コールバックが呼び出された回数
+    pub callback_count: u64,
+    /// オーバーフロー・アンダーフロー（xrun）が報告された回数
+    pub xrun_count: u64,
+    /// 平均負荷（コールバックの処理時間 / 使える時間）。1.0 を超えると処理が間に合っていない。
+    pub average_load: f32,
+    /// 最大負荷
+    pub peak_load: f32,
+}
+
+/// オーディオコールバックから統計情報を記録するためのレコーダー
+///
+/// 記録はアトミック変数のみで行うため、リアルタイムスレッドから呼び出せます。
+pub(crate) struct StatsRecorder {
+    /// コールバックが呼び出された回数
+    callback_count: AtomicU64,
+    /// xrun が報告された回数
+    xrun_count: AtomicU64,
+    /// 処理時間の合計（ナノ秒）
+    busy_nanos: AtomicU64,
+    /// 使える時間の合計（ナノ秒）
+    budget_nanos: AtomicU64,
+    /// 最大負荷（f32 のビット表現）
+    peak_load: AtomicU32,
+}
+
+impl StatsRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            callback_count: AtomicU64::new(0),
+            xrun_count: AtomicU64::new(0),
+            busy_nanos: AtomicU64::new(0),
+            budget_nanos: AtomicU64::new(0),
+            peak_load: AtomicU32::new(0),
+        }
+    }
+
+    /// 記録をリセットする
+    pub(crate) fn reset(&self) {
+        self.callback_count.store(0, Ordering::Relaxed);
+        self.xrun_count.store(0, Ordering::Relaxed);
+        self.busy_nanos.store(0, Ordering::Relaxed);
+        self.budget_nanos.store(0, Ordering::Relaxed);
+        self.peak_load.store(0, Ordering::Relaxed);
+    }
+
+    /// コールバック 1 回分を記録する
+    ///
+    /// # 引数
+    /// * `status` - バックエンドから報告されたストリームの状態
+    /// * `busy` - コールバックの処理時間
+    /// * `budget` - コールバックで使える時間（フレーム数 / サンプルレート）
+    pub(crate) fn record(&self, status: StreamStatus, busy: Duration, budget: Duration) {
+        self.callback_count.fetch_add(1, Ordering::Relaxed);
+        if status.has_xrun() {
+            self.xrun_count.fetch_add(1, Ordering::Relaxed);
+        }
+        self.busy_nanos
+            .fetch_add(busy.as_nanos() as u64, Ordering::Relaxed);
+        self.budget_nanos
+            .fetch_add(budget.as_nanos() as u64, Ordering::Relaxed);
+
+        let load = busy.as_secs_f32() / budget.as_secs_f32().max(f32::MIN_POSITIVE);
+        // 0 以上の f32 はビット表現の大小と値の大小が一致するため、整数のまま最大値を取れる
+        self.peak_load.fetch_max(load.to_bits(), Ordering::Relaxed);
+    }
+
+    /// 現在の統計情報を取得する
+    pub(crate) fn snapshot(&self) -> StreamStats {
+        let busy = self.busy_nanos.load(Ordering::Relaxed);
+        let budget = self.budget_nanos.load(Ordering::Relaxed);
+        StreamStats {
+            callback_count: self.callback_count.load(Ordering::Relaxed),
+            xrun_count: self.xrun_count.load(Ordering::Relaxed),
+            average_load: if budget > 0 {
+                (busy as f64 / budget as f64) as f32
+            } else {
+                0.0
+            },
+            peak_load: f32::from_bits(self.peak_load.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_recorder() {
+        let recorder = StatsRecorder::new();
+        let budget = Duration::from_millis(10);
+        recorder.record(StreamStatus::default(), Duration::from_millis(2), budget);
+        recorder.record(
+            StreamStatus {
+                output_underflow: true,
+                ..StreamStatus::default()
+            },
+            Duration::from_millis(6),
+            budget,
+        );
+
+        let stats = recorder.snapshot();
+        assert_eq!(stats.callback_count, 2);
+        assert_eq!(stats.xrun_count, 1);
+        assert!((stats.average_load - 0.4).abs() < 1e-6);
+        assert!((stats.peak_load - 0.6).abs() < 1e-6);
+
+        recorder.reset();
+        assert_eq!(recorder.snapshot(), StreamStats::default());
+    }
+}
@@ -0,0 +1,176 @@
+//! オーディオコールバックが止まったことを検出するウォッチドッグです。
+//!
+//! デバイスやドライバーがハングすると、ストリームは動作中のままオーディオコールバックだけが呼び出されなくなり、
+//! エラーも通知されずに無音になります。ウォッチドッグは専用のスレッドで、コールバックごとに増える
+//! 呼び出し回数（ハートビート）を監視し、再生中に指定した時間以上増えなかった場合に
+//! EngineEvent::CallbackStalled を通知します。
+//!
+//! AudioEngineService::set_watchdog で有効にすると、検出した停止は poll_event から
+//! StreamEvent::Stalled として返され、再起動の方針が RestartPolicy::Restart の場合はストリームを開き直します。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use audio_engine_core::event::{EngineEvent, EventQueue};
+
+use crate::stats::StatsRecorder;
+
+/// ハートビートを確認する間隔の上限（ミリ秒）
+const CHECK_INTERVAL_MS: u64 = 10;
+
+/// 止まったとみなす時間の下限（ミリ秒）
+///
+/// AudioEngineService::set_watchdog は、これより短いタイムアウトをこの値に切り上げます。
+pub const MIN_TIMEOUT_MS: u64 = 10;
+
+/// コールバックの呼び出し回数の変化から、コールバックが止まったことを判定する
+pub(crate) struct StallDetector {
+    /// 最後に確認した呼び出し回数
+    last_count: u64,
+    /// 最後に呼び出し回数が増えた（または再生していなかった）時刻
+    last_progress: Instant,
+    /// 止まったことを通知済みかどうか
+    stalled: bool,
+}
+
+impl StallDetector {
+    /// 新しいStallDetectorを作成
+    ///
+    /// # 引数
+    /// * `count` - 現在のコールバックの呼び出し回数
+    pub(crate) fn new(count: u64) -> Self {
+        Self {
+            last_count: count,
+            last_progress: Instant::now(),
+            stalled: false,
+        }
+    }
+
+    /// 呼び出し回数を確認する
+    ///
+    /// # 引数
+    /// * `count` - 現在のコールバックの呼び出し回数
+    /// * `playing` - ストリームが動作中のはずかどうか（動作中でない間は止まったとみなさない）
+    /// * `timeout` - 止まったとみなす、呼び出し回数が増えない時間
+    ///
+    /// # 戻り値
+    /// * 止まったと判定した最初の確認でのみ、呼び出し回数が増えていない時間を返す
+    ///   （再びコールバックが呼び出されるまで、続けて通知しない）
+    pub(crate) fn check(
+        &mut self,
+        count: u64,
+        playing: bool,
+        timeout: Duration,
+    ) -> Option<Duration> {
+        if count != self.last_count || !playing {
+            self.last_count = count;
+            self.last_progress = Instant::now();
+            self.stalled = false;
+            return None;
+        }
+        let elapsed = self.last_progress.elapsed();
+        if self.stalled || elapsed < timeout {
+            return None;
+        }
+        self.stalled = true;
+        Some(elapsed)
+    }
+}
+
+/// オーディオコールバックの停止を監視するスレッド
+///
+/// drop するとスレッドを停止します。
+pub struct Watchdog {
+    /// スレッドが動作中かどうか
+    running: Arc<AtomicBool>,
+    /// 監視するスレッド
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// 監視するスレッドを開始
+    ///
+    /// # 引数
+    /// * `stats` - オーディオコールバックの呼び出し回数を記録しているレコーダー
+    /// * `playing` - ストリームが動作中のはずかどうか
+    /// * `stalled` - 停止を検出したときに `true` にするフラグ（サービスが poll_event で取り出す）
+    /// * `events` - EngineEvent::CallbackStalled を入れるキュー
+    /// * `timeout` - 止まったとみなす、コールバックが呼び出されない時間
+    pub(crate) fn start(
+        stats: Arc<StatsRecorder>,
+        playing: Arc<AtomicBool>,
+        stalled: Arc<AtomicBool>,
+        events: Arc<EventQueue>,
+        timeout: Duration,
+    ) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        // タイムアウトが短い場合も、その 1/4 程度の精度で検出できるようにする
+        // （タイムアウトが 0 の場合も、スレッドが休まずに確認し続けないように 1 ミリ秒は休む）
+        let interval = (timeout / 4)
+            .min(Duration::from_millis(CHECK_INTERVAL_MS))
+            .max(Duration::from_millis(1));
+        let thread = {
+            let running = running.clone();
+            thread::Builder::new()
+                .name("audio-watchdog".to_string())
+                .spawn(move || {
+                    let mut detector = StallDetector::new(stats.snapshot().callback_count);
+                    while running.load(Ordering::Acquire) {
+                        let count = stats.snapshot().callback_count;
+                        if let Some(elapsed) =
+                            detector.check(count, playing.load(Ordering::Acquire), timeout)
+                        {
+                            stalled.store(true, Ordering::Release);
+                            events.push(EngineEvent::CallbackStalled {
+                                stalled_ms: elapsed.as_millis() as u64,
+                            });
+                        }
+                        thread::sleep(interval);
+                    }
+                })
+                .expect("ウォッチドッグのスレッドを開始できませんでした")
+        };
+        Self {
+            running,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stall_detector() {
+        let timeout = Duration::from_millis(20);
+        let mut detector = StallDetector::new(0);
+        assert_eq!(detector.check(1, true, timeout), None);
+
+        // 呼び出し回数が増えないまま timeout を過ぎると、一度だけ通知する
+        thread::sleep(timeout);
+        assert!(detector.check(1, true, timeout).unwrap() >= timeout);
+        assert_eq!(detector.check(1, true, timeout), None);
+
+        // 再生していない間は止まったとみなさない
+        assert_eq!(detector.check(1, false, timeout), None);
+        thread::sleep(timeout);
+        assert_eq!(detector.check(1, false, timeout), None);
+
+        // コールバックが再開した後は、再び検出する
+        assert_eq!(detector.check(2, true, timeout), None);
+        thread::sleep(timeout);
+        assert!(detector.check(2, true, timeout).is_some());
+    }
+}
@@ -0,0 +1,561 @@
+//! WebSocket 上の JSON-RPC 2.0 で音声グラフを操作するサーバーです。
+//!
+//! `websocket` フィーチャーを有効にすると使えます。ブラウザのパッチエディターなどから、
+//! ノードの追加・削除や接続、パラメーターの設定、トポロジーやメーターの取得を行えます。
+//!
+//! ```text
+//! → {"jsonrpc": "2.0", "id": 1, "method": "add_node", "params": {"kind": "sine"}}
+//! ← {"jsonrpc": "2.0", "id": 1, "result": {"node_id": 2}}
+//! ```
+//!
+//! | メソッド | パラメーター | 結果 |
+//! | --- | --- | --- |
+//! | `list_node_kinds` | なし | ノードの種類の配列 |
+//! | `add_node` | `kind` | `{"node_id": ID}` |
+//! | `remove_node` | `node_id` | `null` |
+//! | `connect` / `disconnect` | `from`, `to` | `null` |
+//! | `connect_port` | `from`, `from_port`（省略時 0）, `to`, `to_port`（省略時 0） | `null` |
+//! | `connect_parameter` | `from`, `to`, `parameter_id`, `scale`（省略時 1）, `offset`（省略時 0） | `null` |
+//! | `connect_feedback` | `from`, `to`, `gain`（省略時 1） | `null` |
+//! | `disconnect_feedback` | `from`, `to` | `null` |
+//! | `create_feedback_region` | `node_ids` | `{"node_id": FeedbackRegion の ID}` |
+//! | `connect_midi` / `disconnect_midi` | `from`, `to` | `null` |
+//! | `set_parameter` | `node_id`, `parameter_id`, `value` | `null` |
+//! | `get_topology` | なし | `{"nodes": [ID, ...], "edges": [[接続元, 接続先], ...]}` |
+//! | `get_meters` | なし | `{"meters": [{"node_id": ID, "peak": ピーク}, ...]}` |
+//! | `get_meter` | `node_id` | `{"peaks": [左のピーク, 右のピーク]}` |
+//! | `set_morph_position` | `position` | `null` |
+//! | `set_gain_staging` | `enabled` | `null` |
+//! | `get_gain_staging` | なし | `{"stages": [{"node_id": ID, "input_peak": ピーク, "output_peak": ピーク, "clipped_blocks": 数, "blocks": 数}, ...]}` |
+//! | `freeze` | `node_ids`, `num_frames` | `{"node_id": SamplePlayer の ID}` |
+//! | `unfreeze` | `node_id` | `null` |
+//! | `set_tempo` | `tempo` | `null` |
+//! | `set_playing` | `playing` | `null` |
+//! | `load_tuning` | `scl_path`, `kbm_path`（省略可） | `null` |
+//! | `get_parameters` | なし | `{"parameters": [{"node_id": ID, "parameter_id": ID, "value": 値}, ...]}` |
+//! | `get_parameter` | `node_id`, `parameter_id` | `{"value": 値}` |
+//! | `get_parameter_infos` | `node_id` | `{"parameters": [{"id": ID, "name": 名前, "min": 最小値, "max": 最大値, "default": デフォルト値, "unit": 単位}, ...]}` |
+//!
+//! `connect_feedback` のエッジは 1 ブロック遅れて入力され、`create_feedback_region` でまとめたノードの間では
+//! 1 サンプルの遅延になります。
+//!
+//! `load_tuning` は Scala の音階ファイルとキーボードマッピングファイルを読み込んで調律を設定します。
+//! `scl_path` を省略すると 12 平均律に戻します。
+//!
+//! 音声グラフへの操作はコマンドキューを経由するため、結果はサービスの所有者が
+//! process_commands を呼び出したときに返ります。
+
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use audio_engine_core::audio_graph::NodeId;
+use audio_engine_core::tuning::{read_scala_files, Tuning};
+use serde_json::{json, Value};
+use tungstenite::{Error, Message};
+
+use crate::command::{CommandResponse, CommandSender, GraphCommand};
+use crate::node_factory::NODE_KINDS;
+
+/// 停止の要求を確認する間隔（ミリ秒）
+const POLL_INTERVAL_MS: u64 = 50;
+
+/// process_commands による結果を待つ最大時間（ミリ秒）
+const REPLY_TIMEOUT_MS: u64 = 1000;
+
+/// freeze でレンダリングできる最大のフレーム数（192kHz で 60 秒分）
+///
+/// レンダリングではフレーム数 × チャンネル数のサンプル列を確保するため、リモートから
+/// 巨大な値を指定してメモリを使い果たさせられないようにする。
+const MAX_FREEZE_FRAMES: u64 = 60 * 192_000;
+
+/// JSON-RPC のエラーコード: JSON として解釈できない
+const PARSE_ERROR: i64 = -32700;
+/// JSON-RPC のエラーコード: リクエストの形式が不正
+const INVALID_REQUEST: i64 = -32600;
+/// JSON-RPC のエラーコード: メソッドが存在しない
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC のエラーコード: パラメーターが不正
+const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC のエラーコード: 音声グラフの操作に失敗した
+const GRAPH_ERROR: i64 = -32000;
+
+/// JSON-RPC のリクエストを処理し、レスポンスを返す
+///
+/// # 引数
+/// * `text` - リクエスト（JSON）
+/// * `sender` - コマンドの送り先
+///
+/// # 戻り値
+/// * レスポンス（JSON）
+pub fn handle_request(text: &str, sender: &CommandSender) -> String {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => return error_response(Value::Null, PARSE_ERROR, &e.to_string()),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return error_response(id, INVALID_REQUEST, "method がありません");
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    // 音声グラフを使わないメソッドはその場で応答する
+    if method == "list_node_kinds" {
+        return result_response(id, json!(NODE_KINDS));
+    }
+
+    let command = match parse_command(method, &params) {
+        Ok(command) => command,
+        Err((code, message)) => return error_response(id, code, &message),
+    };
+    let result = sender.request(command).and_then(|reply| {
+        reply
+            .recv_timeout(Duration::from_millis(REPLY_TIMEOUT_MS))
+            .map_err(|_| {
+                "応答がありません（process_commands が呼び出されていません）".to_string()
+            })?
+    });
+    match result {
+        Ok(response) => result_response(id, response_to_json(response)),
+        Err(e) => error_response(id, GRAPH_ERROR, &e),
+    }
+}
+
+/// メソッドとパラメーターを GraphCommand に変換する
+fn parse_command(method: &str, params: &Value) -> Result<GraphCommand, (i64, String)> {
+    let id_param = |name: &str| {
+        params
+            .get(name)
+            .and_then(Value::as_u64)
+            .map(|id| NodeId::from_raw(id as usize))
+            .ok_or_else(|| (INVALID_PARAMS, format!("{} が不正です", name)))
+    };
+    let port_param = |name: &str| {
+        params
+            .get(name)
+            .and_then(Value::as_u64)
+            .map_or(0, |port| port as usize)
+    };
+    let ids_param = |name: &str| {
+        params
+            .get(name)
+            .and_then(Value::as_array)
+            .and_then(|ids| {
+                ids.iter()
+                    .map(|id| id.as_u64().map(|id| NodeId::from_raw(id as usize)))
+                    .collect()
+            })
+            .ok_or_else(|| (INVALID_PARAMS, format!("{} が不正です", name)))
+    };
+    let string_param = |name: &str| {
+        params
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| (INVALID_PARAMS, format!("{} が不正です", name)))
+    };
+    Ok(match method {
+        "add_node" => GraphCommand::AddNode {
+            kind: string_param("kind")?,
+        },
+        "remove_node" => GraphCommand::RemoveNode {
+            node_id: id_param("node_id")?,
+        },
+        "connect" => GraphCommand::Connect {
+            from: id_param("from")?,
+            to: id_param("to")?,
+        },
+        "disconnect" => GraphCommand::Disconnect {
+            from: id_param("from")?,
+            to: id_param("to")?,
+        },
+        "connect_port" => GraphCommand::ConnectPort {
+            from: id_param("from")?,
+            from_port: port_param("from_port"),
+            to: id_param("to")?,
+            to_port: port_param("to_port"),
+        },
+        "connect_parameter" => GraphCommand::ConnectParameter {
+            from: id_param("from")?,
+            to: id_param("to")?,
+            parameter_id: string_param("parameter_id")?,
+            scale: params.get("scale").and_then(Value::as_f64).unwrap_or(1.0) as f32,
+            offset: params.get("offset").and_then(Value::as_f64).unwrap_or(0.0) as f32,
+        },
+        "connect_feedback" => GraphCommand::ConnectFeedback {
+            from: id_param("from")?,
+            to: id_param("to")?,
+            gain: params.get("gain").and_then(Value::as_f64).unwrap_or(1.0) as f32,
+        },
+        "disconnect_feedback" => GraphCommand::DisconnectFeedback {
+            from: id_param("from")?,
+            to: id_param("to")?,
+        },
+        "create_feedback_region" => GraphCommand::CreateFeedbackRegion {
+            node_ids: ids_param("node_ids")?,
+        },
+        "connect_midi" => GraphCommand::ConnectMidi {
+            from: id_param("from")?,
+            to: id_param("to")?,
+        },
+        "disconnect_midi" => GraphCommand::DisconnectMidi {
+            from: id_param("from")?,
+            to: id_param("to")?,
+        },
+        "set_parameter" => GraphCommand::SetParameter {
+            node_id: id_param("node_id")?,
+            parameter_id: string_param("parameter_id")?,
+            value: params
+                .get("value")
+                .and_then(Value::as_f64)
+                .ok_or_else(|| (INVALID_PARAMS, "value が不正です".to_string()))?
+                as f32,
+        },
+        "get_topology" => GraphCommand::GetTopology,
+        "get_meters" => GraphCommand::GetMeters,
+        "get_meter" => GraphCommand::GetMeter {
+            node_id: id_param("node_id")?,
+        },
+        "get_parameters" => GraphCommand::GetParameters,
+        "get_parameter" => GraphCommand::GetParameter {
+            node_id: id_param("node_id")?,
+            parameter_id: string_param("parameter_id")?,
+        },
+        "get_parameter_infos" => GraphCommand::GetParameterInfos {
+            node_id: id_param("node_id")?,
+        },
+        "set_gain_staging" => GraphCommand::SetGainStaging {
+            enabled: params
+                .get("enabled")
+                .and_then(Value::as_bool)
+                .ok_or_else(|| (INVALID_PARAMS, "enabled が不正です".to_string()))?,
+        },
+        "get_gain_staging" => GraphCommand::GetGainStaging,
+        "set_tempo" => GraphCommand::SetTempo {
+            tempo: params
+                .get("tempo")
+                .and_then(Value::as_f64)
+                .ok_or_else(|| (INVALID_PARAMS, "tempo が不正です".to_string()))?,
+        },
+        "set_playing" => GraphCommand::SetPlaying {
+            playing: params
+                .get("playing")
+                .and_then(Value::as_bool)
+                .ok_or_else(|| (INVALID_PARAMS, "playing が不正です".to_string()))?,
+        },
+        "load_tuning" => {
+            let path = |name: &str| params.get(name).and_then(Value::as_str).map(Path::new);
+            let tuning = match path("scl_path") {
+                Some(scl_path) => {
+                    read_scala_files(scl_path, path("kbm_path")).map_err(|e| (INVALID_PARAMS, e))?
+                }
+                None => Tuning::equal_temperament(),
+            };
+            GraphCommand::SetTuning {
+                tuning: Box::new(tuning),
+            }
+        }
+        "freeze" => GraphCommand::Freeze {
+            node_ids: ids_param("node_ids")?,
+            num_frames: params
+                .get("num_frames")
+                .and_then(Value::as_u64)
+                .filter(|&num_frames| num_frames <= MAX_FREEZE_FRAMES)
+                .ok_or_else(|| {
+                    (
+                        INVALID_PARAMS,
+                        format!(
+                            "num_frames が不正です（{} フレームまで）",
+                            MAX_FREEZE_FRAMES
+                        ),
+                    )
+                })? as usize,
+        },
+        "unfreeze" => GraphCommand::Unfreeze {
+            node_id: id_param("node_id")?,
+        },
+        "set_morph_position" => GraphCommand::SetMorphPosition {
+            position: params
+                .get("position")
+                .and_then(Value::as_f64)
+                .ok_or_else(|| (INVALID_PARAMS, "position が不正です".to_string()))?
+                as f32,
+        },
+        _ => {
+            return Err((
+                METHOD_NOT_FOUND,
+                format!("メソッドが存在しません: {}", method),
+            ))
+        }
+    })
+}
+
+/// コマンドの結果を JSON に変換する
+fn response_to_json(response: CommandResponse) -> Value {
+    match response {
+        CommandResponse::Done => Value::Null,
+        CommandResponse::NodeAdded(node_id) => json!({ "node_id": node_id.as_raw() }),
+        CommandResponse::Topology { nodes, edges } => json!({
+            "nodes": nodes.iter().map(NodeId::as_raw).collect::<Vec<_>>(),
+            "edges": edges
+                .iter()
+                .map(|(from, to)| (from.as_raw(), to.as_raw()))
+                .collect::<Vec<_>>()
+        }),
+        CommandResponse::Meters(meters) => json!({
+            "meters": meters
+                .iter()
+                .map(|(node_id, peak)| json!({ "node_id": node_id.as_raw(), "peak": peak }))
+                .collect::<Vec<_>>()
+        }),
+        CommandResponse::ChannelPeaks(peaks) => json!({ "peaks": peaks }),
+        CommandResponse::GainStaging(stages) => json!({
+            "stages": stages
+                .iter()
+                .map(|stage| json!({
+                    "node_id": stage.node_id.as_raw(),
+                    "input_peak": stage.input_peak,
+                    "output_peak": stage.output_peak,
+                    "clipped_blocks": stage.clipped_blocks,
+                    "blocks": stage.blocks,
+                }))
+                .collect::<Vec<_>>()
+        }),
+        CommandResponse::Parameters(parameters) => json!({
+            "parameters": parameters
+                .iter()
+                .map(|(node_id, parameter_id, value)| json!({
+                    "node_id": node_id.as_raw(),
+                    "parameter_id": parameter_id,
+                    "value": value,
+                }))
+                .collect::<Vec<_>>()
+        }),
+        CommandResponse::Value(value) => json!({ "value": value }),
+        CommandResponse::ParameterInfos(infos) => json!({
+            "parameters": infos
+                .iter()
+                .map(|info| json!({
+                    "id": info.id,
+                    "name": info.name,
+                    "min": info.min,
+                    "max": info.max,
+                    "default": info.default,
+                    "unit": info.unit,
+                }))
+                .collect::<Vec<_>>()
+        }),
+    }
+}
+
+fn result_response(id: Value, result: Value) -> String {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string()
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message }
+    })
+    .to_string()
+}
+
+/// WebSocket の制御サーバー
+///
+/// 接続ごとにスレッドを立て、テキストメッセージを JSON-RPC のリクエストとして処理します。
+/// drop すると、待ち受けと全ての接続を停止します。
+pub struct WebSocketServer {
+    /// 待ち受けているアドレス
+    local_addr: SocketAddr,
+    /// サーバーが動作中かどうか
+    running: Arc<AtomicBool>,
+    /// 接続を待ち受けるスレッド
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WebSocketServer {
+    /// 指定したアドレスで待ち受けを開始する
+    ///
+    /// # 引数
+    /// * `addr` - 待ち受けるアドレス（例: "127.0.0.1:9001"）
+    /// * `sender` - コマンドの送り先（AudioEngineService::command_sender で取得する）
+    pub fn bind(addr: impl ToSocketAddrs, sender: CommandSender) -> Result<Self, String> {
+        let listener = TcpListener::bind(addr).map_err(|e| e.to_string())?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread = {
+            let running = running.clone();
+            thread::Builder::new()
+                .name("websocket-server".to_string())
+                .spawn(move || {
+                    let mut connections = Vec::new();
+                    while running.load(Ordering::Acquire) {
+                        match listener.accept() {
+                            Ok((stream, _)) => {
+                                let running = running.clone();
+                                let sender = sender.clone();
+                                connections.push(thread::spawn(move || {
+                                    if let Err(e) = serve_connection(stream, &sender, &running) {
+                                        eprintln!("WebSocket の接続を終了しました: {}", e);
+                                    }
+                                }));
+                            }
+                            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                                thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+                            }
+                            Err(e) => {
+                                eprintln!("WebSocket の接続を受け付けられませんでした: {}", e)
+                            }
+                        }
+                        connections.retain(|connection| !connection.is_finished());
+                    }
+                    for connection in connections {
+                        let _ = connection.join();
+                    }
+                })
+                .map_err(|e| format!("スレッドを開始できませんでした: {}", e))?
+        };
+
+        Ok(Self {
+            local_addr,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// 待ち受けているアドレス（ポートに 0 を指定した場合に、割り当てられたポートを確認できる）
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for WebSocketServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// 1 つの接続を、切断されるかサーバーが停止するまで処理する
+fn serve_connection(
+    stream: TcpStream,
+    sender: &CommandSender,
+    running: &AtomicBool,
+) -> Result<(), String> {
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    let mut websocket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    // 停止の要求を確認できるよう、読み込みにタイムアウトを設定する
+    websocket
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(POLL_INTERVAL_MS)))
+        .map_err(|e| e.to_string())?;
+
+    while running.load(Ordering::Acquire) {
+        match websocket.read() {
+            Ok(Message::Text(text)) => {
+                let response = handle_request(&text, sender);
+                websocket
+                    .send(Message::Text(response))
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok(Message::Close(_)) | Err(Error::ConnectionClosed) => return Ok(()),
+            // ping / pong などは tungstenite が処理する
+            Ok(_) => {}
+            Err(Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    let _ = websocket.close(None);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandQueue;
+    use audio_engine_core::audio_graph::AudioGraph;
+
+    #[test]
+    fn test_json_rpc_over_websocket() {
+        let queue = CommandQueue::new();
+        let server = WebSocketServer::bind("127.0.0.1:0", queue.sender()).unwrap();
+
+        // サービスの所有者の代わりに、届いたコマンドを音声グラフに適用するスレッド
+        let done = Arc::new(AtomicBool::new(false));
+        let owner = {
+            let done = done.clone();
+            thread::spawn(move || {
+                let mut audio_graph = AudioGraph::new();
+                while !done.load(Ordering::Acquire) {
+                    for pending in queue.drain() {
+                        let result = pending.command.apply(&mut audio_graph);
+                        let _ = pending.reply.unwrap().send(result);
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+
+        let (mut client, _) =
+            tungstenite::connect(format!("ws://{}", server.local_addr())).unwrap();
+        let mut call = |request: Value| -> Value {
+            client.send(Message::Text(request.to_string())).unwrap();
+            loop {
+                if let Message::Text(text) = client.read().unwrap() {
+                    return serde_json::from_str(&text).unwrap();
+                }
+            }
+        };
+
+        let response = call(
+            json!({"jsonrpc": "2.0", "id": 1, "method": "add_node", "params": {"kind": "sine"}}),
+        );
+        assert_eq!(response["result"]["node_id"], 0);
+        let response = call(
+            json!({"jsonrpc": "2.0", "id": 2, "method": "add_node", "params": {"kind": "output"}}),
+        );
+        assert_eq!(response["result"]["node_id"], 1);
+        let response = call(
+            json!({"jsonrpc": "2.0", "id": 3, "method": "connect", "params": {"from": 0, "to": 1}}),
+        );
+        assert_eq!(response["result"], Value::Null);
+        let response = call(json!({"jsonrpc": "2.0", "id": 4, "method": "get_topology"}));
+        assert_eq!(response["id"], 4);
+        assert_eq!(
+            response["result"],
+            json!({"nodes": [0, 1], "edges": [[0, 1]]})
+        );
+
+        // 音声グラフの操作の失敗や、不正なリクエストはエラーとして返す
+        let response = call(
+            json!({"jsonrpc": "2.0", "id": 5, "method": "connect", "params": {"from": 1, "to": 0}}),
+        );
+        assert_eq!(response["error"]["code"], GRAPH_ERROR);
+        let response = call(json!({"jsonrpc": "2.0", "id": 6, "method": "unknown"}));
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+        let response =
+            call(json!({"jsonrpc": "2.0", "id": 7, "method": "remove_node", "params": {}}));
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = call(json!({
+            "jsonrpc": "2.0",
+            "id": 8,
+            "method": "freeze",
+            "params": {"node_ids": [0], "num_frames": MAX_FREEZE_FRAMES + 1}
+        }));
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        client.close(None).unwrap();
+        done.store(true, Ordering::Release);
+        owner.join().unwrap();
+    }
+}
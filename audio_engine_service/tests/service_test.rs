@@ -0,0 +1,640 @@
+use audio_engine_core::audio_graph::NodeId;
+use audio_engine_core::event::EngineEvent;
+use audio_engine_core::nodes::{
+    Crossfader, FmSynth, GainProcessor, InputNode, OutputNode, SineGenerator,
+};
+use audio_engine_core::wav::read_wav;
+use audio_engine_service::backend::{ChannelMap, NullBackend, StreamConfig, StreamEvent};
+use audio_engine_service::command::{CommandResponse, GraphCommand};
+use audio_engine_service::graph_file::GraphDescription;
+use audio_engine_service::recorder::RecordSource;
+use audio_engine_service::service::{AudioEngineService, RestartPolicy};
+use std::{sync::mpsc, thread, time::Duration};
+
+#[test]
+fn test_service_with_null_backend() {
+    // オーディオデバイスを使わないバックエンドで AudioEngineService を生成
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let probe = backend.probe();
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    let (node_id_in, node_id_out): (NodeId, NodeId);
+    {
+        // AudioEngineService 内の音声グラフにアクセスしてノードを追加
+        let audio_graph = service.get_mut_audio_graph();
+        let mut sine_generator = SineGenerator::new();
+        sine_generator.set_frequency(440.0);
+
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        let node_id_sine = audio_graph.add_node(Box::new(sine_generator));
+        audio_graph.add_edge(node_id_sine, node_id_out).unwrap();
+    }
+
+    // ストリームを開始し、コールバックが呼び出されて音声グラフの出力が届くことを確認
+    service.start_playback(node_id_in, node_id_out).unwrap();
+    assert!(service.is_playing());
+    thread::sleep(Duration::from_millis(100));
+    assert!(probe.callback_count() > 0);
+    assert!(probe.peak() > 0.5);
+    let stats = service.stats();
+    assert!(stats.callback_count > 0);
+    assert!(stats.average_load > 0.0 && stats.average_load <= stats.peak_load);
+
+    // 停止後はコールバックが呼び出されない
+    service.stop_playback().unwrap();
+    assert!(!service.is_playing());
+    let count = probe.callback_count();
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(probe.callback_count(), count);
+}
+
+#[test]
+fn test_service_restarts_after_device_loss() {
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let probe = backend.probe();
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    service.set_restart_policy(RestartPolicy::Restart);
+    let (node_id_in, node_id_out, node_id_sine): (NodeId, NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        node_id_sine = audio_graph.add_node(Box::new(SineGenerator::new()));
+        audio_graph.add_edge(node_id_sine, node_id_out).unwrap();
+    }
+    service.start_playback(node_id_in, node_id_out).unwrap();
+    assert_eq!(service.poll_event(), None);
+
+    // デバイスが切断されると、エラーが通知されたのち同じ音声グラフで再開する
+    probe.disconnect();
+    assert!(!service.is_playing());
+    assert!(matches!(service.poll_event(), Some(StreamEvent::Error(_))));
+    assert_eq!(service.poll_event(), Some(StreamEvent::Restarted));
+    assert!(service.is_playing());
+    let count = probe.callback_count();
+    thread::sleep(Duration::from_millis(50));
+    assert!(probe.callback_count() > count);
+
+    // 停止後は音声グラフに再びアクセスできる
+    service.stop_playback().unwrap();
+    assert!(service
+        .get_mut_audio_graph()
+        .get_node(node_id_sine)
+        .is_some());
+}
+
+#[test]
+fn test_watchdog_restarts_stalled_stream() {
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let probe = backend.probe();
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    service.set_restart_policy(RestartPolicy::Restart);
+    service.set_watchdog(Some(100));
+    let (node_id_in, node_id_out): (NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+    }
+    service.start_playback(node_id_in, node_id_out).unwrap();
+    thread::sleep(Duration::from_millis(150));
+    assert_eq!(service.poll_event(), None);
+
+    // デバイスがハングすると、ストリームは動作中のままでもウォッチドッグが検出する
+    probe.hang();
+    let stalled = std::iter::repeat_with(|| {
+        thread::sleep(Duration::from_millis(20));
+        service.poll_event()
+    })
+    .take(100)
+    .find_map(|event| event);
+    assert_eq!(stalled, Some(StreamEvent::Stalled));
+    assert!(service.poll_events().iter().any(
+        |event| matches!(event, EngineEvent::CallbackStalled { stalled_ms } if *stalled_ms >= 100)
+    ));
+
+    // 開き直すと、コールバックの呼び出しが再開する
+    assert_eq!(service.poll_event(), Some(StreamEvent::Restarted));
+    let count = probe.callback_count();
+    thread::sleep(Duration::from_millis(50));
+    assert!(probe.callback_count() > count);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(service.poll_event(), None);
+    service.stop_playback().unwrap();
+}
+
+#[test]
+fn test_service_notifies_clipping() {
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    service.set_safety_limiter(None);
+    let (node_id_in, node_id_out): (NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        let node_id_sine = audio_graph.add_node(Box::new(SineGenerator::new()));
+        let mut gain_processor = GainProcessor::new();
+        gain_processor.set_gain(2.0);
+        let node_id_gain = audio_graph.add_node(Box::new(gain_processor));
+        audio_graph.add_edge(node_id_sine, node_id_gain).unwrap();
+        audio_graph.add_edge(node_id_gain, node_id_out).unwrap();
+    }
+
+    // 出力が ±1.0 を超えると、クリップがイベントとして通知される
+    service.start_playback(node_id_in, node_id_out).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    let events = service.poll_events();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, EngineEvent::ClipDetected { clips } if *clips > 0)));
+    // 出力のピークレベルも一定間隔で通知される
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, EngineEvent::PeakLevels { left, .. } if *left > 1.0)));
+    service.stop_playback().unwrap();
+}
+
+#[test]
+fn test_event_notifier_reports_stream_stop() {
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let probe = backend.probe();
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    let (node_id_in, node_id_out): (NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        let node_id_sine = audio_graph.add_node(Box::new(SineGenerator::new()));
+        audio_graph.add_edge(node_id_sine, node_id_out).unwrap();
+    }
+    service.start_playback(node_id_in, node_id_out).unwrap();
+
+    // イベントは通知用のスレッドから届く
+    let (sender, receiver) = mpsc::channel();
+    let notifier = service.spawn_event_notifier(move |event| {
+        let _ = sender.send((event, thread::current().name().map(str::to_string)));
+    });
+    let (_, name) = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(name.as_deref(), Some("event-notifier"));
+
+    // poll_event を呼び出さなくても、デバイスが切断されてストリームが止まると通知される
+    probe.disconnect();
+    let stopped = std::iter::from_fn(|| receiver.recv_timeout(Duration::from_secs(2)).ok())
+        .any(|(event, _)| event == EngineEvent::StreamStopped);
+    assert!(stopped);
+    drop(notifier);
+    assert!(matches!(service.poll_event(), Some(StreamEvent::Error(_))));
+}
+
+#[test]
+fn test_switch_output_device_while_playing() {
+    let mut backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let device_id = backend.add_device(StreamConfig {
+        sample_rate: 44100.0,
+        frames_per_buffer: 128,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let probe = backend.probe();
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    let (node_id_in, node_id_out, node_id_sine): (NodeId, NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        node_id_sine = audio_graph.add_node(Box::new(SineGenerator::new()));
+        audio_graph.add_edge(node_id_sine, node_id_out).unwrap();
+    }
+    service.start_playback(node_id_in, node_id_out).unwrap();
+    thread::sleep(Duration::from_millis(20));
+
+    // 存在しないデバイスへの切り替えは失敗する
+    assert!(service.switch_output_device(device_id + 1).is_err());
+
+    // 切り替え後も同じ音声グラフで再生が続く
+    service.switch_output_device(device_id).unwrap();
+    assert!(service.is_playing());
+    let count = probe.callback_count();
+    thread::sleep(Duration::from_millis(50));
+    assert!(probe.callback_count() > count);
+
+    service.stop_playback().unwrap();
+    assert!(service
+        .get_mut_audio_graph()
+        .get_node(node_id_sine)
+        .is_some());
+}
+
+#[test]
+fn test_multiple_services_run_concurrently() {
+    // 周波数の異なるサイン波を、それぞれ独立したサービスで同時に再生する
+    let mut services = Vec::new();
+    let mut probes = Vec::new();
+    for (frequency, gain) in [(440.0, 1.0), (880.0, 0.25)] {
+        let backend = NullBackend::new(StreamConfig {
+            sample_rate: 48000.0,
+            frames_per_buffer: 256,
+            num_input_channels: 0,
+            num_output_channels: 2,
+            channel_map: None,
+        });
+        probes.push(backend.probe());
+        let mut service = AudioEngineService::with_backend(Box::new(backend));
+        let (node_id_in, node_id_out): (NodeId, NodeId);
+        {
+            let audio_graph = service.get_mut_audio_graph();
+            let mut sine_generator = SineGenerator::new();
+            sine_generator.set_frequency(frequency);
+            let mut gain_processor = GainProcessor::new();
+            gain_processor.set_gain(gain);
+
+            node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+            node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+            let node_id_sine = audio_graph.add_node(Box::new(sine_generator));
+            let node_id_gain = audio_graph.add_node(Box::new(gain_processor));
+            audio_graph.add_edge(node_id_sine, node_id_gain).unwrap();
+            audio_graph.add_edge(node_id_gain, node_id_out).unwrap();
+        }
+        service.start_playback(node_id_in, node_id_out).unwrap();
+        services.push(service);
+    }
+
+    thread::sleep(Duration::from_millis(100));
+    for service in services.iter() {
+        assert!(service.is_playing());
+        assert!(service.stats().callback_count > 0);
+    }
+    // それぞれのグラフの出力が、それぞれのストリームに届いている
+    // （フルスケールのサイン波は、デフォルトで有効な安全のためのリミッターで -1dB に抑えられる）
+    assert!(probes[0].peak() > 0.85 && probes[0].peak() <= 0.892);
+    assert!(probes[1].peak() > 0.2 && probes[1].peak() < 0.3);
+
+    for service in services.iter_mut() {
+        service.stop_playback().unwrap();
+    }
+}
+
+#[test]
+fn test_channel_map_routes_graph_outputs_to_device_channels() {
+    // グラフの出力 0/1 を、4ch のデバイスの出力 3/1 に割り当てる
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 4,
+        channel_map: Some(ChannelMap {
+            inputs: vec![],
+            outputs: vec![Some(3), Some(1)],
+        }),
+    });
+    let probe = backend.probe();
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    let (node_id_in, node_id_out): (NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        let node_id_sine = audio_graph.add_node(Box::new(SineGenerator::new()));
+        audio_graph.add_edge(node_id_sine, node_id_out).unwrap();
+    }
+    service.start_playback(node_id_in, node_id_out).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    service.stop_playback().unwrap();
+
+    // 割り当てたチャンネルにだけ出力が届く
+    assert!(probe.channel_peak(3) > 0.5);
+    assert!(probe.channel_peak(1) > 0.5);
+    assert_eq!(probe.channel_peak(0), 0.0);
+    assert_eq!(probe.channel_peak(2), 0.0);
+
+    // 入力ノードで存在しないデバイスの入力チャンネルを指定すると、再生を開始できない
+    service
+        .get_mut_audio_graph()
+        .set_parameter(node_id_in, "channel_left", 2.0)
+        .unwrap();
+    assert!(service.start_playback(node_id_in, node_id_out).is_err());
+    assert!(!service.is_playing());
+}
+
+#[test]
+fn test_commands_are_applied_while_playing() {
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    let (node_id_in, node_id_out, node_id_sine, node_id_fader): (NodeId, NodeId, NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        node_id_sine = audio_graph.add_node(Box::new(SineGenerator::new()));
+        node_id_fader = audio_graph.add_node(Box::new(Crossfader::new()));
+    }
+    service.start_playback(node_id_in, node_id_out).unwrap();
+
+    // 別スレッドから送ったコマンドが、再生中の音声グラフに適用される
+    let sender = service.command_sender();
+    thread::spawn(move || {
+        sender
+            .send(GraphCommand::Connect {
+                from: node_id_sine,
+                to: node_id_out,
+            })
+            .unwrap();
+        sender
+            .send(GraphCommand::SetParameter {
+                node_id: node_id_fader,
+                parameter_id: "position".to_string(),
+                value: 0.25,
+            })
+            .unwrap();
+        sender
+            .send(GraphCommand::Disconnect {
+                from: node_id_in,
+                to: node_id_out,
+            })
+            .unwrap();
+    })
+    .join()
+    .unwrap();
+    let errors = service.process_commands();
+    // 存在しないエッジの削除だけが失敗する
+    assert_eq!(errors.len(), 1);
+
+    // パラメーターの値と情報は、名前やノード ID を指定して取得できる
+    let sender = service.command_sender();
+    let value = sender
+        .request(GraphCommand::GetParameter {
+            node_id: node_id_fader,
+            parameter_id: "position".to_string(),
+        })
+        .unwrap();
+    let infos = sender
+        .request(GraphCommand::GetParameterInfos {
+            node_id: node_id_fader,
+        })
+        .unwrap();
+    service.process_commands();
+    assert_eq!(value.recv().unwrap(), Ok(CommandResponse::Value(0.25)));
+    let Ok(CommandResponse::ParameterInfos(infos)) = infos.recv().unwrap() else {
+        panic!("パラメーターの情報を取得できません");
+    };
+    assert!(infos.iter().any(|info| info.id == "position"));
+
+    service.stop_playback().unwrap();
+    let audio_graph = service.get_mut_audio_graph();
+    assert_eq!(
+        audio_graph.get_parameter(node_id_fader, "position"),
+        Some(0.25)
+    );
+    assert!(audio_graph.remove_edge(node_id_sine, node_id_out));
+}
+
+#[test]
+fn test_record_output_while_playing() {
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    let (node_id_in, node_id_out): (NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        let node_id_sine = audio_graph.add_node(Box::new(SineGenerator::new()));
+        audio_graph.add_edge(node_id_sine, node_id_out).unwrap();
+    }
+    let path = std::env::temp_dir().join("audio_engine_service_record_test.wav");
+
+    // 再生していない間は録音できない
+    assert!(service
+        .start_recording(&path, RecordSource::Output)
+        .is_err());
+
+    service.start_playback(node_id_in, node_id_out).unwrap();
+    service
+        .start_recording(&path, RecordSource::InputAndOutput)
+        .unwrap();
+    assert!(service
+        .start_recording(&path, RecordSource::Output)
+        .is_err());
+    thread::sleep(Duration::from_millis(100));
+    let summary = service.stop_recording().unwrap();
+    service.stop_playback().unwrap();
+    assert!(!service.is_recording());
+
+    // 入力（無音）のチャンネルの後に出力のチャンネルが並ぶ
+    let wav = read_wav(&path).unwrap();
+    assert_eq!(wav.channels, 4);
+    assert_eq!(wav.sample_rate, 48000);
+    assert_eq!(wav.num_frames(), summary.num_frames);
+    assert!(summary.num_frames > 0);
+    assert!(wav
+        .samples
+        .chunks_exact(4)
+        .all(|frame| frame[..2] == [0.0; 2]));
+    assert!(wav
+        .samples
+        .chunks_exact(4)
+        .any(|frame| frame[2].abs() > 0.5));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_midi_queue_plays_target_node() {
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let probe = backend.probe();
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    let (node_id_in, node_id_out, node_id_synth): (NodeId, NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        node_id_synth = audio_graph.add_node(Box::new(FmSynth::new()));
+        audio_graph.add_edge(node_id_synth, node_id_out).unwrap();
+    }
+    service.start_playback(node_id_in, node_id_out).unwrap();
+    let midi_queue = service.midi_queue();
+
+    // 送り先を指定するまでは、受け取ったイベントは捨てられる
+    midi_queue.push(&[0x90, 69, 127]);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(probe.peak(), 0.0);
+
+    // ハードウェアの MIDI 入力と同じ経路で、ノートオンがシンセに届く
+    service.set_midi_target(Some(node_id_synth));
+    midi_queue.push(&[0x90, 69, 127]);
+    thread::sleep(Duration::from_millis(100));
+    service.stop_playback().unwrap();
+    assert!(probe.peak() > 0.1);
+}
+
+#[test]
+fn test_reprepare_graph_while_playing() {
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let probe = backend.probe();
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    let (node_id_in, node_id_out): (NodeId, NodeId);
+    {
+        let audio_graph = service.get_mut_audio_graph();
+        node_id_in = audio_graph.add_node(Box::new(InputNode::new()));
+        node_id_out = audio_graph.add_node(Box::new(OutputNode::new()));
+        let node_id_sine = audio_graph.add_node(Box::new(SineGenerator::new()));
+        audio_graph.add_edge(node_id_sine, node_id_out).unwrap();
+    }
+    service.start_playback(node_id_in, node_id_out).unwrap();
+    thread::sleep(Duration::from_millis(20));
+
+    // 不正なサンプルレートは受け付けない
+    assert!(service.reprepare_graph(Some(0.0)).is_err());
+
+    // 再生を止めずに音声グラフのサンプルレートを変更し、差し替え後も音が出続ける
+    service.reprepare_graph(Some(24000.0)).unwrap();
+    assert!(service.is_playing());
+    let count = probe.callback_count();
+    let path = std::env::temp_dir().join("audio_engine_service_reprepare_test.wav");
+    service
+        .start_recording(&path, RecordSource::Output)
+        .unwrap();
+    thread::sleep(Duration::from_millis(100));
+    service.stop_recording().unwrap();
+    assert!(probe.callback_count() > count);
+    service.reprepare_graph(None).unwrap();
+    service.stop_playback().unwrap();
+
+    let wav = read_wav(&path).unwrap();
+    assert!(wav.samples.iter().all(|s| s.is_finite()));
+    assert!(wav.samples.iter().any(|s| s.abs() > 0.5));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_export_and_load_graph_while_playing() {
+    let backend = NullBackend::new(StreamConfig {
+        sample_rate: 48000.0,
+        frames_per_buffer: 256,
+        num_input_channels: 0,
+        num_output_channels: 2,
+        channel_map: None,
+    });
+    let probe = backend.probe();
+    let mut service = AudioEngineService::with_backend(Box::new(backend));
+    let description = GraphDescription::from_json(
+        r#"{
+            "nodes": [
+                { "name": "in", "kind": "input" },
+                { "name": "osc", "kind": "sine", "params": { "frequency": 330.0 } },
+                { "name": "out", "kind": "output" }
+            ],
+            "edges": [["osc", "out"]],
+            "input": "in",
+            "output": "out"
+        }"#,
+    )
+    .unwrap();
+    let loaded = service.load_graph(&description).unwrap();
+    service
+        .start_playback(loaded.input_id, loaded.output_id)
+        .unwrap();
+
+    // 再生中に書き出した記述を読み込み直すと、同じ構成で再生が続く
+    let exported = service.export_graph().unwrap();
+    let osc_name = exported
+        .nodes
+        .iter()
+        .find(|node| node.kind == "sine")
+        .map(|node| node.name.clone())
+        .unwrap();
+    let reloaded = service
+        .load_graph(&GraphDescription::from_json(&exported.to_json()).unwrap())
+        .unwrap();
+    assert!(service.is_playing());
+
+    // 再生中のスナップショットには、オーディオスレッドが処理しているノードとパラメーターが含まれる
+    let snapshot = service.debug_snapshot();
+    let osc = reloaded.node_id(&osc_name).unwrap();
+    let osc_snapshot = snapshot
+        .nodes
+        .iter()
+        .find(|node| node.node_id == osc)
+        .unwrap();
+    assert_eq!(osc_snapshot.kind, Some("sine"));
+    assert_eq!(osc_snapshot.output_capacity, 256);
+    assert!(osc_snapshot.parameters.contains(&("frequency", 330.0)));
+    assert!(service
+        .debug_snapshot_json()
+        .contains("\"processing_order\""));
+
+    // 構築できない記述は、音声グラフを変更せずにエラーになる
+    let mut invalid = exported.clone();
+    invalid.nodes[0].kind = "unknown".to_string();
+    assert!(service.load_graph(&invalid).is_err());
+    thread::sleep(Duration::from_millis(50));
+    assert!(probe.peak() > 0.5);
+
+    service.stop_playback().unwrap();
+    assert_eq!(
+        service
+            .get_mut_audio_graph()
+            .get_parameter(osc, "frequency"),
+        Some(330.0)
+    );
+}
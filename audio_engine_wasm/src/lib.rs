@@ -0,0 +1,243 @@
+//! audio_engine_core の音声グラフを、Web Audio の AudioWorklet で動かすためのアダプターです。
+//!
+//! wasm32-unknown-unknown 向けにビルドし、`worklet/processor.js` の AudioWorkletProcessor から読み込みます。
+//! wasm-bindgen などの JS のグルーコードを使わず C の ABI の関数だけを公開するため、
+//! AudioWorkletGlobalScope の中でそのままインスタンス化できます。
+//!
+//! AudioWorklet は 1 回の process でチャンネルごとの 128 フレームの配列を渡すため、wasm のメモリ上に
+//! チャンネルごとのバッファを用意します。JS 側は入力をバッファにコピーしてから worklet_process を呼び出し、
+//! 出力のバッファから結果をコピーします。
+//!
+//! 音声グラフは build_graph で、ネイティブと同じ GraphBuilder のコードで組み立てます。
+
+use audio_engine_core::audio_buffer::OwnedAudioBuffer;
+use audio_engine_core::audio_graph::{AudioGraph, NodeId};
+use audio_engine_core::graph_builder::{BuiltGraph, GraphBuilder, gain, sine};
+
+/// AudioWorklet が 1 回の process で渡すフレーム数（レンダークォンタム）
+pub const RENDER_QUANTUM: usize = 128;
+
+/// 処理するチャンネル数（現在、AudioGraph は 2ch のみのサポート）
+pub const NUM_CHANNELS: usize = 2;
+
+/// AudioWorklet のチャンネルごとの配列と、音声グラフの間で受け渡しをするプロセッサー
+pub struct WorkletProcessor {
+    audio_graph: AudioGraph,
+    /// 入力ノードの ID
+    input_id: NodeId,
+    /// 出力ノードの ID
+    output_id: NodeId,
+    /// JS から書き込む、チャンネルごとの入力
+    inputs: [[f32; RENDER_QUANTUM]; NUM_CHANNELS],
+    /// JS が読み出す、チャンネルごとの出力
+    outputs: [[f32; RENDER_QUANTUM]; NUM_CHANNELS],
+    /// 音声グラフに渡すインターリーブのバッファ
+    block: OwnedAudioBuffer,
+}
+
+impl WorkletProcessor {
+    /// 新しいWorkletProcessorを作成
+    ///
+    /// # 引数
+    /// * `audio_graph` - 処理する音声グラフ
+    /// * `input_id` - 入力ノードの ID
+    /// * `output_id` - 出力ノードの ID
+    /// * `sample_rate` - AudioContext のサンプリングレート
+    pub fn new(
+        mut audio_graph: AudioGraph,
+        input_id: NodeId,
+        output_id: NodeId,
+        sample_rate: f32,
+    ) -> Self {
+        audio_graph.prepare(sample_rate, RENDER_QUANTUM);
+        Self {
+            audio_graph,
+            input_id,
+            output_id,
+            inputs: [[0.0; RENDER_QUANTUM]; NUM_CHANNELS],
+            outputs: [[0.0; RENDER_QUANTUM]; NUM_CHANNELS],
+            block: OwnedAudioBuffer::new(NUM_CHANNELS, RENDER_QUANTUM),
+        }
+    }
+
+    /// 音声グラフ
+    pub fn audio_graph_mut(&mut self) -> &mut AudioGraph {
+        &mut self.audio_graph
+    }
+
+    /// チャンネルの入力のバッファ
+    pub fn input_mut(&mut self, channel: usize) -> Option<&mut [f32; RENDER_QUANTUM]> {
+        self.inputs.get_mut(channel)
+    }
+
+    /// チャンネルの出力のバッファ
+    pub fn output(&self, channel: usize) -> Option<&[f32; RENDER_QUANTUM]> {
+        self.outputs.get(channel)
+    }
+
+    /// 入力のバッファを音声グラフで処理し、出力のバッファに書き込む
+    ///
+    /// # 引数
+    /// * `num_frames` - 処理するフレーム数（RENDER_QUANTUM 以下）
+    ///
+    /// # 実装時の注意
+    /// メモリアロケーションを伴わないため、AudioWorklet の process から呼び出せます。
+    pub fn process(&mut self, num_frames: usize) {
+        self.block.set_num_frames(num_frames.min(RENDER_QUANTUM));
+        let mut buffer = self.block.as_audio_buffer();
+        for (i, frame) in buffer.frames_mut().enumerate() {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample = self.inputs[channel][i];
+            }
+        }
+        self.audio_graph
+            .process(&mut buffer, self.input_id, self.output_id);
+        for (i, frame) in buffer.frames().enumerate() {
+            for (channel, sample) in frame.iter().enumerate() {
+                self.outputs[channel][i] = *sample;
+            }
+        }
+    }
+}
+
+/// ブラウザで動かす音声グラフを組み立てる
+///
+/// audio_engine_service の init と同じく、2 つのサイン波を出力します。
+/// ノード ID は入力ノードが 0、出力ノードが 1 で、以降は追加した順に割り当てられます。
+fn build_graph() -> Result<BuiltGraph, String> {
+    GraphBuilder::new()
+        .parallel([vec![sine(220.0), gain(0.5)], vec![sine(523.25), gain(0.5)]])
+        .to_output()
+        .build()
+}
+
+/// 音声グラフを組み立て、AudioContext のサンプリングレートで準備します。
+///
+/// AudioWorkletProcessor のコンストラクタで、wasm のモジュールをインスタンス化した後に 1 回呼び出してください。
+/// 呼び出すとバッファの位置が変わるため、worklet_input_buffer などは呼び出した後に取得し直してください。
+///
+/// # 引数
+/// * `sample_rate` - AudioWorkletGlobalScope の `sampleRate`
+///
+/// # 戻り値
+/// * 成功した場合は `true`。音声グラフを組み立てられない場合は `false`
+#[unsafe(no_mangle)]
+pub extern "C" fn worklet_init(sample_rate: f32) -> bool {
+    let Ok(built) = build_graph() else {
+        return false;
+    };
+    let processor = WorkletProcessor::new(
+        built.graph,
+        built.input.id(),
+        built.output.id(),
+        sample_rate,
+    );
+    unsafe {
+        PROCESSOR = Some(processor);
+    }
+    true
+}
+
+/// チャンネルの入力のバッファの位置を取得します。
+///
+/// JS 側は wasm のメモリのこの位置から RENDER_QUANTUM 個の f32 に入力を書き込みます。
+///
+/// # 戻り値
+/// * バッファの先頭へのポインタ。worklet_init の前に呼び出した場合や、チャンネルが不正な場合は NULL
+#[unsafe(no_mangle)]
+pub extern "C" fn worklet_input_buffer(channel: u32) -> *mut f32 {
+    let processor = &raw mut PROCESSOR;
+    unsafe { (*processor).as_mut() }
+        .and_then(|processor| processor.input_mut(channel as usize))
+        .map_or(std::ptr::null_mut(), |buffer| buffer.as_mut_ptr())
+}
+
+/// チャンネルの出力のバッファの位置を取得します。
+///
+/// JS 側は worklet_process の後に、wasm のメモリのこの位置から RENDER_QUANTUM 個の f32 を読み出します。
+///
+/// # 戻り値
+/// * バッファの先頭へのポインタ。worklet_init の前に呼び出した場合や、チャンネルが不正な場合は NULL
+#[unsafe(no_mangle)]
+pub extern "C" fn worklet_output_buffer(channel: u32) -> *const f32 {
+    let processor = &raw const PROCESSOR;
+    unsafe { (*processor).as_ref() }
+        .and_then(|processor| processor.output(channel as usize))
+        .map_or(std::ptr::null(), |buffer| buffer.as_ptr())
+}
+
+/// 入力のバッファを音声グラフで処理し、出力のバッファに書き込みます。
+///
+/// AudioWorkletProcessor の process から、レンダークォンタムごとに呼び出してください。
+///
+/// # 引数
+/// * `num_frames` - 処理するフレーム数（RENDER_QUANTUM 以下）
+///
+/// # 戻り値
+/// * 処理した場合は `true`。worklet_init の前に呼び出した場合は `false`
+#[unsafe(no_mangle)]
+pub extern "C" fn worklet_process(num_frames: u32) -> bool {
+    let processor = &raw mut PROCESSOR;
+    let Some(processor) = (unsafe { (*processor).as_mut() }) else {
+        return false;
+    };
+    processor.process(num_frames as usize);
+    true
+}
+
+/// ノードのパラメーターを、パラメーターの番号で設定します。
+///
+/// 文字列を wasm のメモリに渡さずに済むよう、パラメーターは AudioGraphNode::parameters の順番で指定します。
+///
+/// # 引数
+/// * `node_id` - ノード ID の整数の表現
+/// * `index` - パラメーターの番号
+/// * `value` - 設定する値（パラメーターの範囲に制限される）
+///
+/// # 戻り値
+/// * 成功した場合は `true`。worklet_init の前に呼び出した場合や、ノードやパラメーターが存在しない場合は `false`
+#[unsafe(no_mangle)]
+pub extern "C" fn worklet_set_parameter(node_id: u32, index: u32, value: f32) -> bool {
+    let processor = &raw mut PROCESSOR;
+    let Some(processor) = (unsafe { (*processor).as_mut() }) else {
+        return false;
+    };
+    let audio_graph = processor.audio_graph_mut();
+    let node_id = NodeId::from_raw(node_id as usize);
+    let Some(info) = audio_graph
+        .get_node(node_id)
+        .and_then(|node| node.parameters().get(index as usize))
+    else {
+        return false;
+    };
+    let parameter_id = info.id;
+    audio_graph
+        .set_parameter(node_id, parameter_id, value)
+        .is_ok()
+}
+
+static mut PROCESSOR: Option<WorkletProcessor> = None;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worklet_exports_process_graph() {
+        assert!(!worklet_process(RENDER_QUANTUM as u32));
+        assert!(worklet_init(48000.0));
+        assert!(worklet_input_buffer(NUM_CHANNELS as u32).is_null());
+
+        // 入力は使わないため、出力には 2 つのサイン波が書き込まれる
+        assert!(worklet_process(RENDER_QUANTUM as u32));
+        let output =
+            unsafe { std::slice::from_raw_parts(worklet_output_buffer(0), RENDER_QUANTUM) };
+        assert!(output.iter().any(|sample| sample.abs() > 0.1));
+        assert!(output.iter().all(|sample| sample.abs() <= 1.0));
+
+        // ノード ID 2 は 1 つ目のサイン波で、パラメーター 0 は周波数
+        assert!(worklet_set_parameter(2, 0, 440.0));
+        assert!(!worklet_set_parameter(2, 99, 440.0));
+        assert!(!worklet_set_parameter(99, 0, 440.0));
+    }
+}
@@ -0,0 +1,85 @@
+// audio_engine_wasm の音声グラフを AudioWorklet で動かすプロセッサーです。
+//
+// AudioWorkletGlobalScope では fetch を使えないため、メインスレッドでコンパイルした wasm のモジュールを
+// processorOptions.module で渡します。
+//
+//   const module = await WebAssembly.compileStreaming(fetch("audio_engine_wasm.wasm"));
+//   await context.audioWorklet.addModule("processor.js");
+//   const node = new AudioWorkletNode(context, "audio-engine", {
+//     outputChannelCount: [2],
+//     processorOptions: { module },
+//   });
+//   node.connect(context.destination);
+//
+// パラメーターは port からメッセージで設定します。
+//
+//   node.port.postMessage({ type: "setParameter", nodeId: 2, index: 0, value: 440 });
+
+// 音声グラフのチャンネル数（audio_engine_wasm の NUM_CHANNELS）
+const NUM_CHANNELS = 2;
+
+class AudioEngineProcessor extends AudioWorkletProcessor {
+  constructor(options) {
+    super();
+    this.exports = null;
+    // wasm のメモリ上のバッファを指す配列（メモリが拡張されると作り直す）
+    this.memory = null;
+    this.inputs = [];
+    this.outputs = [];
+    this.port.onmessage = (event) => this.onMessage(event.data);
+    WebAssembly.instantiate(options.processorOptions.module, {}).then((instance) => {
+      if (instance.exports.worklet_init(sampleRate)) {
+        this.exports = instance.exports;
+      }
+    });
+  }
+
+  onMessage(message) {
+    if (this.exports && message.type === "setParameter") {
+      this.exports.worklet_set_parameter(message.nodeId, message.index, message.value);
+    }
+  }
+
+  // wasm のメモリが変わっていれば、バッファを指す配列を作り直す
+  updateViews() {
+    const buffer = this.exports.memory.buffer;
+    if (this.memory === buffer) {
+      return;
+    }
+    this.memory = buffer;
+    const frames = (pointer) => new Float32Array(buffer, pointer, 128);
+    this.inputs = [];
+    this.outputs = [];
+    for (let channel = 0; channel < NUM_CHANNELS; channel++) {
+      this.inputs.push(frames(this.exports.worklet_input_buffer(channel)));
+      this.outputs.push(frames(this.exports.worklet_output_buffer(channel)));
+    }
+  }
+
+  process(inputs, outputs) {
+    const output = outputs[0];
+    if (!this.exports || output.length === 0) {
+      return true;
+    }
+    this.updateViews();
+
+    // 接続されていない入力は無音、モノラルの入力は両方のチャンネルに入れる
+    const input = inputs[0];
+    const numFrames = output[0].length;
+    for (let channel = 0; channel < NUM_CHANNELS; channel++) {
+      if (input.length > 0) {
+        this.inputs[channel].set(input[Math.min(channel, input.length - 1)]);
+      } else {
+        this.inputs[channel].fill(0);
+      }
+    }
+    this.exports.worklet_process(numFrames);
+    for (let channel = 0; channel < output.length; channel++) {
+      const source = this.outputs[Math.min(channel, NUM_CHANNELS - 1)];
+      output[channel].set(source.subarray(0, numFrames));
+    }
+    return true;
+  }
+}
+
+registerProcessor("audio-engine", AudioEngineProcessor);